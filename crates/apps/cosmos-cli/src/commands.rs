@@ -0,0 +1,257 @@
+//! Command implementations
+//!
+//! Each command opens the store itself, so commands that don't need the
+//! search index never take it.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use config::CosmosConfig;
+use mail::{
+    Account, AccountHealth, FileBlobStore, GmailAuth, GmailClient, GmailCredentials, MailStore,
+    SearchBackend, SearchConfig, SearchIndex, SnapshotManifest, SqliteMailStore, SyncOptions,
+    ThreadId, ThreadSummary,
+};
+use serde::Serialize;
+
+use crate::Command;
+
+/// An account as printed by `accounts`, without its token
+#[derive(Serialize)]
+struct AccountRow {
+    id: i64,
+    email: String,
+    is_primary: bool,
+    needs_reauth: bool,
+}
+
+/// Run a parsed command, printing results to stdout
+pub fn run(command: Command, json: bool) -> Result<()> {
+    // Works even when the settings are too broken to open the store
+    if let Command::ConfigDoctor = command {
+        return config_doctor(json);
+    }
+    // Snapshots work on the store's files rather than an open store
+    if let Command::ExportSnapshot { path } = &command {
+        return export_snapshot(path, json);
+    }
+    if let Command::ImportSnapshot { path } = &command {
+        return import_snapshot(path, json);
+    }
+
+    let store = open_store()?;
+    match command {
+        Command::Accounts => {
+            let rows: Vec<AccountRow> = store
+                .list_accounts()?
+                .into_iter()
+                .map(|account| AccountRow {
+                    needs_reauth: mail::account_health(&store, account.id)
+                        .is_ok_and(|health| health == AccountHealth::NeedsReauth),
+                    id: account.id,
+                    email: account.email,
+                    is_primary: account.is_primary,
+                })
+                .collect();
+            if json {
+                return print_json(&rows);
+            }
+            for row in rows {
+                println!(
+                    "{}\t{}{}{}",
+                    row.id,
+                    row.email,
+                    if row.is_primary { "\t(primary)" } else { "" },
+                    if row.needs_reauth { "\t(needs sign-in)" } else { "" }
+                );
+            }
+        }
+        Command::RemoveAccount { email } => {
+            let account = find_account(&store, &email)?;
+            let index = open_search_index().ok();
+            let search = index.as_ref().map(|index| index as &dyn SearchBackend);
+            mail::remove_account(&store, search, account.id)?;
+            eprintln!("Removed {}", account.email);
+        }
+        Command::Sync { account, full } => {
+            let accounts = match account {
+                Some(email) => vec![find_account(&store, &email)?],
+                None => store.list_accounts()?,
+            };
+            let credentials = GmailCredentials::load().context("Gmail credentials not found")?;
+            let cosmos_config = CosmosConfig::load()?;
+            let search_index = open_search_index().ok().map(Arc::new);
+            for account in accounts {
+                if mail::account_health(&store, account.id)? == AccountHealth::NeedsReauth {
+                    eprintln!("Skipping {}: sign in again in Orion", account.email);
+                    continue;
+                }
+                let auth = GmailAuth::with_token_data(
+                    credentials.client_id.clone(),
+                    credentials.client_secret.clone(),
+                    account.token_data.clone(),
+                );
+                let client = GmailClient::new(auth);
+                let settings = cosmos_config.for_account(&account.email)?;
+                let options = SyncOptions {
+                    full_resync: full,
+                    search_index: search_index.clone(),
+                    ..Default::default()
+                }
+                .with_settings(&settings.sync)
+                .with_network(&settings.network);
+                let stats = mail::sync_gmail(&client, &store, account.id, options)
+                    .with_context(|| format!("Sync failed for {}", account.email))?;
+                eprintln!(
+                    "Synced {}: {} created, {} updated, {} errors in {}ms",
+                    account.email,
+                    stats.messages_created,
+                    stats.messages_updated,
+                    stats.errors,
+                    stats.duration_ms
+                );
+            }
+        }
+        Command::Threads {
+            label,
+            account,
+            limit,
+        } => {
+            let account_id = account
+                .map(|email| find_account(&store, &email).map(|account| account.id))
+                .transpose()?;
+            let threads: Vec<ThreadSummary> = store
+                .list_threads_after(Some(&label), account_id, None, limit)?
+                .into_iter()
+                .map(ThreadSummary::from)
+                .collect();
+            if json {
+                return print_json(&threads);
+            }
+            for thread in threads {
+                print_row(
+                    thread.id.as_str(),
+                    thread.is_unread,
+                    &thread.last_message_at.format("%Y-%m-%d %H:%M").to_string(),
+                    thread.sender_name.as_deref().unwrap_or(&thread.sender_email),
+                    &thread.subject,
+                );
+            }
+        }
+        Command::Search {
+            query,
+            account,
+            limit,
+        } => {
+            let account_id = account
+                .map(|email| find_account(&store, &email).map(|account| account.id))
+                .transpose()?;
+            let index = open_search_index()?;
+            let results =
+                mail::search::search_threads_for_account(&index, &store, &query, limit, account_id)?;
+            if json {
+                return print_json(&results);
+            }
+            for result in results {
+                print_row(
+                    result.thread_id.as_str(),
+                    result.is_unread,
+                    &result.last_message_at.format("%Y-%m-%d %H:%M").to_string(),
+                    result.sender_name.as_deref().unwrap_or(&result.sender_email),
+                    &result.subject,
+                );
+            }
+        }
+        Command::Export { thread_id, path } => {
+            mail::export_thread_pdf(&store, &ThreadId::new(thread_id), &path)?;
+            eprintln!("Saved {}", path.display());
+        }
+        Command::ConfigDoctor | Command::ExportSnapshot { .. } | Command::ImportSnapshot { .. } => {
+            unreachable!("handled before opening the store")
+        }
+    }
+    Ok(())
+}
+
+/// Print the effective settings, failing if any are invalid
+fn config_doctor(json: bool) -> Result<()> {
+    let doctor = config::doctor()?;
+    if json {
+        print_json(&doctor)?;
+    } else {
+        print!("{}", doctor);
+    }
+    if !doctor.is_ok() {
+        anyhow::bail!("{} configuration problem(s) found", doctor.issues.len());
+    }
+    Ok(())
+}
+
+/// Save the mailbox to a snapshot archive
+fn export_snapshot(path: &Path, json: bool) -> Result<()> {
+    let storage = CosmosConfig::load()?.storage;
+    let manifest = mail::export_snapshot(&storage, path)?;
+    print_snapshot(&manifest, json)?;
+    eprintln!("Saved {}", path.display());
+    Ok(())
+}
+
+/// Restore a snapshot archive into this profile's empty data directory
+fn import_snapshot(path: &Path, json: bool) -> Result<()> {
+    let storage = CosmosConfig::load()?.storage;
+    config::migrate_data()?;
+    let manifest = mail::import_snapshot(&storage, path)?;
+    print_snapshot(&manifest, json)?;
+    eprintln!("Imported into {}", storage.dir()?.display());
+    Ok(())
+}
+
+fn print_snapshot(manifest: &SnapshotManifest, json: bool) -> Result<()> {
+    if json {
+        return print_json(manifest);
+    }
+    println!("Accounts:\t{}", manifest.accounts.join(", "));
+    println!(
+        "Created:\t{} by {}",
+        manifest.created_at.format("%Y-%m-%d %H:%M"),
+        manifest.created_by
+    );
+    println!("Schema:\t{}", manifest.schema_version);
+    println!("Search index:\t{}", if manifest.search_index { "yes" } else { "no" });
+    Ok(())
+}
+
+/// Find an account by email address
+fn find_account(store: &dyn MailStore, email: &str) -> Result<Account> {
+    store
+        .get_account_by_email(email)?
+        .with_context(|| format!("No account for {}", email))
+}
+
+/// Print a thread as one tab-separated line, unread threads starred
+fn print_row(id: &str, unread: bool, date: &str, sender: &str, subject: &str) {
+    let marker = if unread { "*" } else { " " };
+    println!("{}{}\t{}\t{}\t{}", marker, id, date, sender, subject);
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Open the mail database Orion uses
+fn open_store() -> Result<SqliteMailStore> {
+    for path in config::migrate_data()? {
+        eprintln!("Moved mail data to {}", path.display());
+    }
+    let storage = CosmosConfig::load()?.storage;
+    let blob_store = Box::new(FileBlobStore::new(storage.blob_path()?)?);
+    SqliteMailStore::new(storage.db_path()?, blob_store)
+}
+
+/// Open the search index Orion uses
+fn open_search_index() -> Result<SearchIndex> {
+    let index_path = CosmosConfig::load()?.storage.index_path()?;
+    Ok(SearchIndex::open(&index_path)?.with_config(SearchConfig::load()))
+}
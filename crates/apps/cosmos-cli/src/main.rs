@@ -0,0 +1,200 @@
+//! cosmos-cli - Command-line access to the Cosmos mail store
+//!
+//! Works on the same database, blobs and search index as Orion, so mailbox
+//! state can be scripted or inspected without the GPUI app. Logs go to
+//! stderr (set `RUST_LOG` for more); results go to stdout, as JSON with
+//! `--json`.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+
+mod commands;
+
+const USAGE: &str = "\
+Usage: cosmos-cli [--json] [--profile <name>] <command> [options]
+
+Commands:
+  accounts                        List accounts and their health
+  accounts remove <email>         Remove an account and its local mail
+  sync [--account <email>] [--full]
+                                  Sync one or every account with Gmail
+  threads [--label <id>] [--account <email>] [--limit <n>]
+                                  List the newest threads (default: INBOX)
+  search <query> [--account <email>] [--limit <n>]
+                                  Search with Gmail-style operators
+  export <thread-id> <file.pdf>   Save a thread as a PDF
+  snapshot export <file.zip>      Save the whole mailbox to move it to another machine
+  snapshot import <file.zip>      Restore a saved mailbox into an empty profile
+  config doctor                   Show the effective settings and any problems
+
+Options:
+  --json                          Print results as JSON
+  --profile <name>                Use a named profile's settings and data
+                                  (default: $COSMOS_PROFILE)
+";
+
+/// Threads or search results printed when `--limit` isn't given
+const DEFAULT_LIMIT: usize = 25;
+
+/// A parsed command line
+#[derive(Debug)]
+pub enum Command {
+    Accounts,
+    RemoveAccount {
+        email: String,
+    },
+    Sync {
+        account: Option<String>,
+        full: bool,
+    },
+    Threads {
+        label: String,
+        account: Option<String>,
+        limit: usize,
+    },
+    Search {
+        query: String,
+        account: Option<String>,
+        limit: usize,
+    },
+    Export {
+        thread_id: String,
+        path: PathBuf,
+    },
+    ExportSnapshot {
+        path: PathBuf,
+    },
+    ImportSnapshot {
+        path: PathBuf,
+    },
+    ConfigDoctor,
+}
+
+fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn"))
+        .format_timestamp_millis()
+        .init();
+
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") || args.is_empty() {
+        print!("{}", USAGE);
+        return;
+    }
+    let json = take_flag(&mut args, "--json");
+
+    let result = take_option(&mut args, "--profile")
+        .and_then(|profile| config::select_profile(profile.as_deref()))
+        .and_then(|_| parse_command(args))
+        .and_then(|command| commands::run(command, json));
+    if let Err(e) = result {
+        eprintln!("error: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Parse everything after the global flags into a command
+fn parse_command(mut args: Vec<String>) -> Result<Command> {
+    if args.is_empty() {
+        bail!("Missing command\n\n{}", USAGE);
+    }
+    let name = args.remove(0);
+    let command = match name.as_str() {
+        "accounts" => match args.first().map(String::as_str) {
+            None => Command::Accounts,
+            Some("remove") => Command::RemoveAccount {
+                email: args.get(1).cloned().context("accounts remove needs an email")?,
+            },
+            Some(other) => bail!("Unknown accounts subcommand '{}'", other),
+        },
+        "sync" => {
+            let account = take_option(&mut args, "--account")?;
+            let full = take_flag(&mut args, "--full");
+            Command::Sync { account, full }
+        }
+        "threads" => {
+            let label = take_option(&mut args, "--label")?.unwrap_or_else(|| "INBOX".to_string());
+            let account = take_option(&mut args, "--account")?;
+            let limit = take_limit(&mut args)?;
+            Command::Threads {
+                label,
+                account,
+                limit,
+            }
+        }
+        "search" => {
+            let account = take_option(&mut args, "--account")?;
+            let limit = take_limit(&mut args)?;
+            if args.is_empty() {
+                bail!("search needs a query");
+            }
+            Command::Search {
+                query: args.drain(..).collect::<Vec<_>>().join(" "),
+                account,
+                limit,
+            }
+        }
+        "export" => {
+            let [thread_id, path] = <[String; 2]>::try_from(std::mem::take(&mut args))
+                .map_err(|_| anyhow::anyhow!("export needs a thread ID and an output file"))?;
+            Command::Export {
+                thread_id,
+                path: PathBuf::from(path),
+            }
+        }
+        "snapshot" => {
+            let path = || {
+                args.get(1)
+                    .map(PathBuf::from)
+                    .context("snapshot needs an archive file")
+            };
+            match args.first().map(String::as_str) {
+                Some("export") => Command::ExportSnapshot { path: path()? },
+                Some("import") => Command::ImportSnapshot { path: path()? },
+                Some(other) => bail!("Unknown snapshot subcommand '{}'", other),
+                None => bail!("snapshot needs a subcommand: export or import"),
+            }
+        }
+        "config" => match args.first().map(String::as_str) {
+            Some("doctor") => Command::ConfigDoctor,
+            Some(other) => bail!("Unknown config subcommand '{}'", other),
+            None => bail!("config needs a subcommand: doctor"),
+        },
+        other => bail!("Unknown command '{}'\n\n{}", other, USAGE),
+    };
+
+    if let Some(extra) = args.iter().find(|arg| arg.starts_with("--")) {
+        bail!("Unknown option '{}' for {}", extra, name);
+    }
+    Ok(command)
+}
+
+/// Remove `flag` from `args`, returning whether it was present
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != before
+}
+
+/// Remove `name` and its value from `args`
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>> {
+    let Some(index) = args.iter().position(|arg| arg == name) else {
+        return Ok(None);
+    };
+    if index + 1 >= args.len() {
+        bail!("{} needs a value", name);
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Ok(Some(value))
+}
+
+/// Remove `--limit` and parse it, falling back to the default
+fn take_limit(args: &mut Vec<String>) -> Result<usize> {
+    match take_option(args, "--limit")? {
+        Some(limit) => limit
+            .parse()
+            .with_context(|| format!("Invalid --limit '{}'", limit)),
+        None => Ok(DEFAULT_LIMIT),
+    }
+}
@@ -0,0 +1,188 @@
+//! cosmosd - Headless background sync daemon
+//!
+//! Syncs every account on a schedule and raises new mail notifications
+//! while Orion is closed. Open apps connect to the daemon socket (see
+//! `mail::daemon`) for live updates and leave scheduled syncs to it.
+//!
+//! Run `cosmosd --once` to sync every account once and exit, e.g. from cron,
+//! and `cosmosd --profile <name>` to serve a named profile (see
+//! `config::select_profile`); each profile runs its own daemon.
+//! Settings come from the `[daemon]` table of `cosmos.toml` (see
+//! `config::CosmosConfig`), e.g. `poll_interval_secs = 120` and
+//! `api_server = true`; an older `cosmosd.json` is still read. With
+//! `api_server` on, the daemon also serves the local API (`mail::server`)
+//! for scripts and launcher extensions; `api_actions` lets them archive,
+//! star and so on.
+//! Builds with the `otlp` feature push sync metrics to the OpenTelemetry
+//! collector at `otlp_endpoint`, e.g. `"http://localhost:4318/v1/metrics"`.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use config::{CosmosConfig, DaemonSettings, StorageSettings};
+use log::{info, warn};
+use mail::{
+    ActionHandler, DaemonEvent, EventServer, FileBlobStore, GmailAuth, GmailClient,
+    GmailCredentials, MailStore, NotificationConfig, Notifier, SearchConfig, SearchIndex,
+    SqliteMailStore,
+};
+#[cfg(unix)]
+use mail::server::ApiService;
+
+#[cfg(unix)]
+mod sync;
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("cosmosd listens on a Unix socket and is only available on Unix");
+}
+
+#[cfg(unix)]
+fn main() -> anyhow::Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .format_timestamp_millis()
+        .init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let once = args.iter().any(|arg| arg == "--once");
+    if let Some(profile) = config::select_profile(config::profile_arg(&args)?)? {
+        info!("Using profile {}", profile);
+    }
+
+    config::init()?;
+    match config::migrate_data() {
+        Ok(moved) => {
+            for path in moved {
+                info!("Moved mail data to {}", path.display());
+            }
+        }
+        Err(e) => warn!("Failed to move mail data to the data directory: {:#}", e),
+    }
+    // Separate from Orion's log so the two processes never share a file
+    if let Some(path) = config::config_path("cosmosd.diagnostics.json") {
+        mail::init_diagnostics(&path);
+    }
+
+    let cosmos_config = CosmosConfig::load().unwrap_or_else(|e| {
+        warn!("Ignoring invalid Cosmos config: {:#}", e);
+        CosmosConfig::default()
+    });
+
+    let credentials = GmailCredentials::load().context("Gmail credentials not found")?;
+    let store: Arc<dyn MailStore> = Arc::new(open_store(&cosmos_config.storage)?);
+    let search_index = match open_search_index(&cosmos_config.storage) {
+        Ok(index) => Some(Arc::new(index)),
+        Err(e) => {
+            warn!("Syncing without a search index: {}", e);
+            None
+        }
+    };
+
+    let socket_path = mail::daemon_socket_path().context("Could not determine config directory")?;
+    let server = Arc::new(EventServer::bind(&socket_path).with_context(|| {
+        format!("Failed to listen on {}", socket_path.display())
+    })?);
+    info!("Listening on {}", socket_path.display());
+
+    // With the daemon in charge there is no window to open, so clicks are
+    // handed to whichever app is connected
+    let (notifier, mut clicks) = Notifier::new(NotificationConfig::load());
+    let click_server = server.clone();
+    std::thread::spawn(move || {
+        while let Some(thread_id) = clicks.blocking_recv() {
+            click_server.broadcast(&DaemonEvent::OpenThread { thread_id });
+        }
+    });
+
+    let daemon_config = cosmos_config.daemon.clone();
+    let _metrics = daemon_config.otlp_endpoint.as_deref().and_then(start_metrics);
+    if daemon_config.api_server {
+        start_api_server(&daemon_config, &credentials, &store, &search_index)?;
+    }
+
+    let interval = Duration::from_secs(daemon_config.poll_interval_secs.max(1));
+    let mut syncer =
+        sync::Syncer::new(credentials, store, search_index, notifier, cosmos_config);
+
+    loop {
+        let started = Instant::now();
+        syncer.sync_all(&server);
+        if once {
+            break;
+        }
+        std::thread::sleep(interval.saturating_sub(started.elapsed()));
+    }
+    Ok(())
+}
+
+/// Serve the local API next to the event socket
+///
+/// Action handlers are only created for accounts present at startup, so
+/// accounts added later stay read-only until the daemon restarts.
+#[cfg(unix)]
+fn start_api_server(
+    daemon_config: &DaemonSettings,
+    credentials: &GmailCredentials,
+    store: &Arc<dyn MailStore>,
+    search_index: &Option<Arc<SearchIndex>>,
+) -> anyhow::Result<()> {
+    let mut service = ApiService::new(store.clone(), search_index.clone());
+    if daemon_config.api_actions {
+        for account in store.list_accounts()? {
+            let auth = GmailAuth::with_token_data(
+                credentials.client_id.clone(),
+                credentials.client_secret.clone(),
+                account.token_data.clone(),
+            );
+            let mut handler = ActionHandler::new(Arc::new(GmailClient::new(auth)), store.clone());
+            if let Some(index) = search_index.clone() {
+                handler = handler.with_search_index(index);
+            }
+            service = service.with_actions(account.id, Arc::new(handler));
+        }
+    }
+
+    let path = mail::server::api_socket_path().context("Could not determine config directory")?;
+    mail::server::serve(Arc::new(service), &path)?;
+    info!("Serving the local API on {}", path.display());
+    Ok(())
+}
+
+/// Push sync metrics to the collector at `endpoint`
+///
+/// Returns a guard that flushes the metrics when dropped.
+#[cfg(feature = "otlp")]
+fn start_metrics(endpoint: &str) -> Option<mail::telemetry::OtlpExporter> {
+    match mail::telemetry::install_otlp_exporter(
+        endpoint,
+        "cosmosd",
+        mail::telemetry::DEFAULT_EXPORT_INTERVAL,
+    ) {
+        Ok(exporter) => {
+            info!("Pushing metrics to {}", endpoint);
+            Some(exporter)
+        }
+        Err(e) => {
+            warn!("Not exporting metrics: {:#}", e);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "otlp"))]
+fn start_metrics(_endpoint: &str) -> Option<()> {
+    warn!("Ignoring otlp_endpoint: cosmosd was built without the otlp feature");
+    None
+}
+
+/// Open the mail database Orion uses
+fn open_store(storage: &StorageSettings) -> anyhow::Result<SqliteMailStore> {
+    let blob_store = Box::new(FileBlobStore::new(storage.blob_path()?)?);
+    SqliteMailStore::new(storage.db_path()?, blob_store)
+}
+
+/// Open the search index Orion uses
+fn open_search_index(storage: &StorageSettings) -> anyhow::Result<SearchIndex> {
+    Ok(SearchIndex::open(storage.index_path()?)?.with_config(SearchConfig::load()))
+}
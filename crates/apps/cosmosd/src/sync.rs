@@ -0,0 +1,200 @@
+//! Scheduled account syncs
+//!
+//! Mirrors Orion's per-account sync: incremental syncs go through
+//! `sync_gmail`, while initial syncs run the fetch and process phases
+//! directly so rule matches on new mail can raise notifications.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use config::CosmosConfig;
+use log::{debug, error, info, warn};
+use mail::{
+    Account, AccountHealth, DaemonEvent, EventServer, GmailAuth, GmailClient, GmailCredentials,
+    MailStore, NetworkBudgetSpentError, Notifier, SearchBackend, SearchIndex, SyncAction,
+    SyncOptions, SyncState, SyncStats, fetch_phase, process_pending_batch, push_rule_changes,
+};
+
+/// Messages processed per batch during an initial sync
+const BATCH_SIZE: usize = 100;
+
+/// Syncs accounts and reports progress to connected apps
+pub struct Syncer {
+    credentials: GmailCredentials,
+    store: Arc<dyn MailStore>,
+    search_index: Option<Arc<SearchIndex>>,
+    notifier: Notifier,
+    config: CosmosConfig,
+    /// Clients are kept across rounds so refreshed access tokens are reused
+    clients: HashMap<i64, Arc<GmailClient>>,
+}
+
+impl Syncer {
+    pub fn new(
+        credentials: GmailCredentials,
+        store: Arc<dyn MailStore>,
+        search_index: Option<Arc<SearchIndex>>,
+        notifier: Notifier,
+        config: CosmosConfig,
+    ) -> Self {
+        Self {
+            credentials,
+            store,
+            search_index,
+            notifier,
+            config,
+            clients: HashMap::new(),
+        }
+    }
+
+    /// Sync every healthy, unpaused account once, then purge trash and spam
+    /// past Gmail's retention
+    ///
+    /// Accounts are read from the store each round, so ones added or removed
+    /// in Orion are picked up without restarting the daemon.
+    pub fn sync_all(&mut self, server: &EventServer) {
+        let accounts = match self.store.list_accounts() {
+            Ok(accounts) => accounts,
+            Err(e) => {
+                error!("Failed to load accounts: {}", e);
+                return;
+            }
+        };
+        self.clients
+            .retain(|id, _| accounts.iter().any(|account| account.id == *id));
+
+        for account in accounts {
+            let health = mail::account_health(self.store.as_ref(), account.id)
+                .unwrap_or(AccountHealth::Healthy);
+            if health == AccountHealth::NeedsReauth {
+                debug!("Skipping {}: needs to be reconnected in Orion", account.email);
+                continue;
+            }
+            if !account.sync_enabled {
+                debug!("Skipping {}: syncing is paused", account.email);
+                continue;
+            }
+
+            server.broadcast(&DaemonEvent::SyncStarted {
+                account_id: account.id,
+            });
+            match self.sync_account(&account) {
+                Ok(stats) => {
+                    info!(
+                        "Synced {}: {} created, {} updated",
+                        account.email, stats.messages_created, stats.messages_updated
+                    );
+                    server.broadcast(&DaemonEvent::SyncCompleted {
+                        account_id: account.id,
+                        messages_created: stats.messages_created,
+                        messages_updated: stats.messages_updated,
+                    });
+                }
+                Err(e) => {
+                    if e.downcast_ref::<NetworkBudgetSpentError>().is_some() {
+                        info!("Stopped syncing {}: {}", account.email, e);
+                    } else {
+                        error!("Sync failed for {}: {:#}", account.email, e);
+                    }
+                    server.broadcast(&DaemonEvent::SyncFailed {
+                        account_id: account.id,
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+
+        // Paused accounts included, as Gmail purges their trash regardless
+        let search = self
+            .search_index
+            .as_deref()
+            .map(|index| index as &dyn SearchBackend);
+        if let Err(e) = mail::purge_expired_trash(self.store.as_ref(), search, None) {
+            warn!("Failed to purge expired trash: {}", e);
+        }
+    }
+
+    /// Gmail client for an account, created on first use
+    fn client(&mut self, account: &Account) -> Arc<GmailClient> {
+        self.clients
+            .entry(account.id)
+            .or_insert_with(|| {
+                let auth = GmailAuth::with_token_data(
+                    self.credentials.client_id.clone(),
+                    self.credentials.client_secret.clone(),
+                    account.token_data.clone(),
+                );
+                Arc::new(GmailClient::new(auth))
+            })
+            .clone()
+    }
+
+    fn sync_account(&mut self, account: &Account) -> Result<SyncStats> {
+        let client = self.client(account);
+        let store = self.store.as_ref();
+        let settings = self.config.for_account(&account.email)?;
+        let options = SyncOptions {
+            search_index: self.search_index.clone(),
+            ..Default::default()
+        }
+        .with_settings(&settings.sync)
+        .with_network(&settings.network);
+
+        // A revoked token fails every request, so stop before touching any
+        // synced data
+        let profile = client.get_profile().inspect_err(|e| {
+            if let Err(record_err) = mail::record_auth_failure(store, account.id, e) {
+                warn!("Failed to record auth failure: {}", record_err);
+            }
+        })?;
+
+        let existing_state = store.get_sync_state(account.id)?;
+        if let SyncAction::IncrementalSync { .. } | SyncAction::StaleResync { .. } =
+            mail::determine_sync_action(existing_state.as_ref(), false)
+        {
+            return mail::sync_gmail(client.as_ref(), store, account.id, options);
+        }
+
+        if existing_state.is_none() {
+            info!("First sync for {} - clearing account data", account.email);
+            let search = self
+                .search_index
+                .as_deref()
+                .map(|index| index as &dyn SearchBackend);
+            mail::clear_account(store, search, account.id)?;
+            store.save_sync_state(SyncState::partial(account.id, &profile.history_id))?;
+        }
+
+        let mut stats = SyncStats::default();
+        // A fetch stopped by a full pending queue resumes once it's processed
+        loop {
+            let fetched = fetch_phase(client.as_ref(), store, account.id, &options, &mut stats)?;
+            loop {
+                let result =
+                    process_pending_batch(store, account.id, &options, &mut stats, BATCH_SIZE)?;
+                if !result.rule_matches.is_empty() {
+                    self.notifier
+                        .notify_matches(store, account.id, &result.rule_matches);
+                    if let Err(e) = push_rule_changes(&client, &result.rule_matches) {
+                        warn!("Failed to apply rule changes for {}: {}", account.email, e);
+                    }
+                }
+                if !result.has_more {
+                    break;
+                }
+            }
+            if !fetched.backlogged {
+                break;
+            }
+        }
+
+        // Keep failed IDs from the fetch phase for retry on the next round
+        let mut complete = SyncState::new(account.id, &profile.history_id);
+        if let Some(state) = store.get_sync_state(account.id)? {
+            complete.failed_message_ids = state.failed_message_ids;
+        }
+        store.save_sync_state(complete)?;
+        Ok(stats)
+    }
+}
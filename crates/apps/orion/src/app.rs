@@ -1,32 +1,65 @@
 //! Root application component for Orion mail app
 
 use chrono::{DateTime, Local, Utc};
+use config::{ConfigWatcher, CosmosConfig, StorageSettings};
 use gpui::prelude::*;
 use gpui::*;
 use gpui_component::button::{Button, ButtonVariants};
 use gpui_component::webview::WebView;
-use gpui_component::{ActiveTheme, Icon, IconName, Sizable, Size as ComponentSize};
+use gpui_component::{ActiveTheme, Icon, IconName, Root, Sizable, Size as ComponentSize};
 use log::{debug, error, info, warn};
 use mail::{
-    Account, ActionHandler, FileBlobStore, GmailAuth, GmailClient, Label, LabelId, MailStore,
-    SearchIndex, SqliteMailStore, SyncOptions, SyncState, SyncStats, ThreadId,
+    Account, AccountHealth, ActionHandler, DaemonEvent, FetchPhaseStats, FileBlobStore, GmailAuth,
+    GmailClient, IntegrityConfig, Label, LabelId, LabelNode, MailStore, MessageId, NotificationConfig,
+    Notifier, RuleMatch, SavedSearch, SavedSearchSummary,
+    SchedulerState, SearchBackend, SearchConfig, SearchIndex, SqliteMailStore, SyncOptions,
+    SyncOrchestrator, SyncSchedule,
+    SyncSkipReason, SyncState, SyncStats, ThreadId, TrackingConfig, get_thread_label_ids,
+    label_tree, list_saved_searches_with_counts, push_rule_changes, run_startup_check, suggest,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use crate::components::{AccountItem, AllAccountsItem, SearchBox, SearchBoxEvent, ShortcutsHelp};
+use crate::components::{
+    AccountItem, AllAccountsItem, AttachmentPreview, AttachmentPreviewModal, CommandPalette,
+    CommandPaletteEvent, LabelOption, LabelPicker, LabelPickerEvent, LabelState, ReplyBox,
+    ReplyBoxEvent, SearchBox, SearchBoxEvent, ShortcutsHelp,
+};
 use crate::input::{
-    Dismiss, GoToAllMail, GoToDrafts, GoToInbox, GoToSent, GoToStarred, GoToTrash, ShowShortcuts,
+    Command, CommandTarget, Compose, CycleDateFormat, CycleRowDensity, CycleSnippetLines, Dismiss,
+    EditLabels, ExportDiagnostics, ExportThreadAsPdf, GoToAllMail, GoToDrafts, GoToInbox, GoToSent,
+    GoToStarred, GoToTrash, PrintThread, ShowCommandPalette, ShowShortcuts, SwitchToAccount,
+    SyncNow, ToggleAvatars, ToggleConversationView, ToggleConversationViewForLabel,
+    ToggleMessageCounts, UseDarkTheme, UseLightTheme, UseSystemTheme, action_commands,
 };
 use wry::WebViewBuilder;
 
+use crate::appearance::{APPEARANCE_CONFIG_FILE, AppearanceConfig, ThemePreference};
 use crate::components::Sidebar;
+use crate::display::DisplayConfig;
+use crate::layout::{LayoutConfig, SplitMode};
+use crate::session::Session;
 use crate::templates;
-use crate::views::{SearchResultsView, ThreadListView, ThreadView};
+use crate::tray::{Tray, TrayCommand, TraySummary};
+use crate::views::{
+    BulkAction, ComposeView, SearchResultsView, ThreadListView, ThreadView, UNASSIGNABLE_LABELS,
+};
 
 // Global actions for keyboard shortcuts
 actions!(orion, [FocusSearch]);
 
+/// Maximum number of typeahead suggestions shown under the search box
+const SEARCH_SUGGESTION_LIMIT: usize = 8;
+
+/// Number of recent threads offered in the command palette
+const PALETTE_RECENT_THREADS: usize = 20;
+
+/// Initial size of a compose window
+const COMPOSE_WINDOW_SIZE: (f32, f32) = (640., 560.);
+
+/// How often queued store changes are folded into one tray refresh
+const TRAY_EVENT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
 /// Current view in the application
 #[derive(Clone)]
 pub enum View {
@@ -40,6 +73,21 @@ pub enum View {
     Search,
 }
 
+/// Thread shown in the split layout's preview pane
+struct Preview {
+    thread_id: ThreadId,
+    /// Pre-generated HTML, like `View::Thread`
+    html: String,
+    /// Header for the previewed thread
+    view: Entity<ThreadView>,
+}
+
+/// A config file edited while Orion is running
+enum ConfigChange {
+    SyncSchedule(SyncSchedule),
+    Appearance(AppearanceConfig),
+}
+
 /// What view should receive focus on next render
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum PendingFocus {
@@ -64,7 +112,6 @@ pub struct AccountState {
     /// Gmail API client for this account
     pub gmail_client: Arc<GmailClient>,
     /// Action handler for email operations (used for per-account actions)
-    #[allow(dead_code)]
     pub action_handler: Arc<ActionHandler>,
     /// Whether this account is currently syncing
     pub is_syncing: bool,
@@ -72,6 +119,8 @@ pub struct AccountState {
     pub last_sync_at: Option<DateTime<Utc>>,
     /// Last sync error message
     pub sync_error: Option<String>,
+    /// Whether the account's token still works
+    pub health: AccountHealth,
 }
 
 /// Root application state
@@ -84,6 +133,10 @@ pub struct OrionApp {
     accounts: HashMap<i64, AccountState>,
     /// Currently selected account for filtering (None = unified view, all accounts)
     selected_account: Option<i64>,
+    /// State restored on the next launch, including the selected account
+    session: Session,
+    /// Title last given to the window, so it is only set on change
+    window_title: String,
     /// Primary account ID (first registered, used for fallback)
     primary_account_id: Option<i64>,
 
@@ -115,6 +168,18 @@ pub struct OrionApp {
     webview_loaded_html: Option<String>,
     /// Search index for full-text search
     search_index: Option<Arc<SearchIndex>>,
+    /// Threads indexed / total while the search index rebuilds in the background
+    index_rebuild_progress: Option<(usize, usize)>,
+    /// Threads done / total while a whole label is archived or marked read
+    label_action_progress: Option<(usize, usize)>,
+    /// Outbox messages not yet sent, and how many of them failed
+    outbox_unsent: (usize, usize),
+    /// Pinned saved searches shown as sidebar smart folders
+    smart_folders: Vec<SavedSearchSummary>,
+    /// The current account's user labels, nested by name
+    label_tree: Vec<LabelNode>,
+    /// Nested labels whose children are hidden in the sidebar
+    collapsed_labels: HashSet<String>,
     /// Search box component
     search_box: Option<Entity<SearchBox>>,
     /// Search results view
@@ -125,18 +190,54 @@ pub struct OrionApp {
     pending_focus: Option<PendingFocus>,
     /// Whether to show keyboard shortcuts help overlay
     show_shortcuts_help: bool,
+    /// Command palette overlay, while open
+    command_palette: Option<Entity<CommandPalette>>,
+    /// Label picker overlay, if open
+    label_picker: Option<Entity<LabelPicker>>,
+    /// Threads the open label picker edits, with their accounts
+    label_picker_threads: Vec<(i64, ThreadId)>,
+    /// Focus to restore when the label picker closes
+    label_picker_return_focus: Option<FocusHandle>,
+    /// What had focus before the command palette opened
+    palette_return_focus: Option<FocusHandle>,
     /// Pending G-sequence (waiting for second key)
     pending_g_sequence: bool,
     /// The list context from which the current thread was opened
     thread_list_context: ListContext,
+    /// Split pane settings
+    layout: LayoutConfig,
+    /// Theme settings
+    appearance: AppearanceConfig,
+    /// Thread in the preview pane (split layout only)
+    preview: Option<Preview>,
+    /// Marks the previewed thread read once it has been shown long enough
+    preview_read_task: Option<Task<()>>,
+    /// Quick reply box under the thread being read, full-size or previewed
+    reply_box: Option<Entity<ReplyBox>>,
+    /// Desktop notifications for mail matching Notify rules
+    notifier: Arc<Notifier>,
+    /// Menu bar unread badge and recent threads
+    tray: Tray,
+    /// Refreshes the tray when the store changes
+    tray_events_task: Option<Task<()>>,
+    /// Attachment shown in the preview overlay
+    attachment_preview: Option<AttachmentPreview>,
 
     // === Sync Configuration ===
-    /// Minimum seconds between syncs (cooldown)
-    sync_cooldown_secs: u64,
-    /// Background polling interval in seconds
-    poll_interval_secs: u64,
+    /// Shared settings: storage paths and per-account sync overrides
+    cosmos_config: CosmosConfig,
+    /// Sync cooldown and background polling interval
+    sync_schedule: SyncSchedule,
+    /// Apply config file edits while running
+    config_watchers: Vec<ConfigWatcher>,
     /// Background polling task handle
     poll_task: Option<Task<()>>,
+    /// Whether the `cosmosd` daemon is running the scheduled syncs
+    daemon_connected: bool,
+    /// Scheduler introspection for the sync button countdown
+    sync_scheduler: SchedulerState,
+    /// Pauses and resumes syncing per account
+    sync_orchestrator: SyncOrchestrator,
     /// Track window active state for foreground detection
     was_window_active: bool,
 
@@ -164,13 +265,52 @@ impl OrionApp {
         let thread_list_view = cx.new(|cx| ThreadListView::new(store_clone, cx));
         debug!("[BOOT]   ThreadListView created: {:?}", new_start.elapsed());
 
+        // Clicking a notification brings the app forward on its thread
+        let (notifier, mut notification_clicks) = Notifier::new(NotificationConfig::load());
+        cx.spawn(async move |this, cx| {
+            while let Some(thread_id) = notification_clicks.recv().await {
+                let opened = cx.update(|cx| {
+                    cx.activate(true);
+                    this.update(cx, |app, cx| app.show_thread(thread_id, cx))
+                });
+                if !matches!(opened, Ok(Ok(()))) {
+                    break;
+                }
+            }
+        })
+        .detach();
+
+        let (tray, mut tray_commands) = Tray::new();
+        cx.spawn(async move |this, cx| {
+            while let Some(command) = tray_commands.recv().await {
+                let handled = cx.update(|cx| {
+                    if command != TrayCommand::SyncNow {
+                        cx.activate(true);
+                    }
+                    this.update(cx, |app, cx| app.run_tray_command(command, cx))
+                });
+                if !matches!(handled, Ok(Ok(()))) {
+                    break;
+                }
+            }
+        })
+        .detach();
+
+        let sync_orchestrator = SyncOrchestrator::new(store.clone());
+        let cosmos_config = CosmosConfig::load().unwrap_or_else(|e| {
+            warn!("Ignoring invalid Cosmos config: {:#}", e);
+            CosmosConfig::default()
+        });
+
         Self {
             current_view: View::Inbox,
             store,
 
             // Multi-account state
             accounts: HashMap::new(),
-            selected_account: None, // Unified view by default
+            selected_account: None, // Unified view until the saved one is restored
+            session: Session::load(),
+            window_title: String::new(),
             primary_account_id: None,
 
             // Primary account shortcuts (set by load_accounts/add_account)
@@ -189,18 +329,42 @@ impl OrionApp {
             webview: None,
             webview_loaded_html: None,
             search_index: None,
+            index_rebuild_progress: None,
+            label_action_progress: None,
+            outbox_unsent: (0, 0),
+            smart_folders: Vec::new(),
+            label_tree: Vec::new(),
+            collapsed_labels: HashSet::new(),
             search_box: None,
             search_results_view: None,
             pending_focus_results: false,
             pending_focus: Some(PendingFocus::ThreadList), // Focus thread list on launch
             show_shortcuts_help: false,
+            command_palette: None,
+            label_picker: None,
+            label_picker_threads: Vec::new(),
+            label_picker_return_focus: None,
+            palette_return_focus: None,
             pending_g_sequence: false,
             thread_list_context: ListContext::Inbox,
+            layout: LayoutConfig::load(),
+            appearance: AppearanceConfig::load(),
+            preview: None,
+            preview_read_task: None,
+            reply_box: None,
+            notifier: Arc::new(notifier),
+            tray,
+            tray_events_task: None,
+            attachment_preview: None,
 
             // Sync config
-            sync_cooldown_secs: 30,
-            poll_interval_secs: 60,
+            sync_schedule: SyncSchedule::from_settings(&cosmos_config.sync),
+            config_watchers: Vec::new(),
+            cosmos_config,
             poll_task: None,
+            daemon_connected: false,
+            sync_scheduler: SchedulerState::default(),
+            sync_orchestrator,
             was_window_active: true,
 
             // OAuth credentials (set later via set_credentials)
@@ -219,6 +383,7 @@ impl OrionApp {
     /// Call this after the UI is displayed for deferred loading
     pub fn load_persistent_storage(&mut self, cx: &mut Context<Self>) {
         let background = cx.background_executor().clone();
+        let storage = self.cosmos_config.storage.clone();
 
         cx.spawn(async move |this, cx| {
             // Load database and search index on background thread
@@ -227,7 +392,7 @@ impl OrionApp {
                     use std::time::Instant;
                     let start = Instant::now();
 
-                    let store: Arc<dyn MailStore> = match Self::create_persistent_store() {
+                    let store: Arc<dyn MailStore> = match Self::create_persistent_store(&storage) {
                         Ok(store) => Arc::new(store),
                         Err(e) => {
                             warn!(
@@ -253,7 +418,7 @@ impl OrionApp {
                     let last_sync_at = sync_info.last_sync_at;
                     let should_auto_sync = mail::should_auto_sync_on_startup(sync_state.as_ref());
 
-                    let search_index = match Self::create_search_index() {
+                    let search_index = match Self::create_search_index(&storage) {
                         Ok(index) => {
                             debug!(
                                 "[BOOT]   SearchIndex opened (background): {:?}",
@@ -276,8 +441,18 @@ impl OrionApp {
                 cx.update(|cx| {
                     this.update(cx, |app, cx| {
                         app.store = store.clone();
+                        app.sync_orchestrator = SyncOrchestrator::new(store.clone());
                         app.last_sync_at = last_sync_at;
+                        app.sync_scheduler.last_sync_at = last_sync_at;
+                        app.sync_scheduler.next_allowed_sync_at = mail::next_allowed_sync_at(
+                            last_sync_at,
+                            app.sync_schedule.cooldown_secs,
+                        );
                         app.search_index = search_index;
+                        app.check_search_index(cx);
+                        app.check_integrity(cx);
+                        app.purge_expired_trash(cx);
+                        app.connect_daemon(cx);
 
                         // Load accounts from database
                         if let (Some(client_id), Some(client_secret)) =
@@ -289,13 +464,15 @@ impl OrionApp {
                         // Update thread list view with the real store
                         if let Some(thread_list) = &app.thread_list_view {
                             thread_list.update(cx, |view, cx| {
-                                view.set_store(store);
+                                view.set_store(store, cx);
                                 view.load_threads(cx);
                             });
                         }
 
                         // Update inbox unread count
                         app.refresh_inbox_unread_count();
+                        app.refresh_smart_folders();
+                        app.subscribe_tray_to_store(cx);
 
                         info!("Persistent storage loaded");
 
@@ -333,16 +510,265 @@ impl OrionApp {
         .detach();
     }
 
-    /// Create search index in the config directory
-    fn create_search_index() -> anyhow::Result<SearchIndex> {
+    /// Verify the search index and rebuild it in the background if needed
+    ///
+    /// A freshly created or schema-outdated index is rebuilt straight away;
+    /// otherwise the index is checked against the store and rebuilt only if
+    /// files are corrupt or messages are missing. Searches keep working on
+    /// the partial index while the rebuild runs.
+    fn check_search_index(&mut self, cx: &mut Context<Self>) {
+        use std::sync::Mutex;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::time::Duration;
+
+        let Some(index) = self.search_index.clone() else {
+            return;
+        };
+        let store = self.store.clone();
+        let progress = Arc::new(Mutex::new(None));
+        let done = Arc::new(AtomicBool::new(false));
+        let background = cx.background_executor().clone();
+
+        let task_progress = progress.clone();
+        let task_done = done.clone();
+        let task = background.spawn(async move {
+            let result = (|| -> anyhow::Result<Option<usize>> {
+                if !index.needs_rebuild() {
+                    let report = index.verify(store.as_ref())?;
+                    if report.is_healthy() {
+                        return Ok(None);
+                    }
+                    warn!(
+                        "Search index out of date ({} indexed, {} stored, {} corrupt files)",
+                        report.indexed_messages,
+                        report.stored_messages,
+                        report.corrupted_files.len()
+                    );
+                }
+                let count = index.rebuild_from_store(store.as_ref(), |done, total| {
+                    *task_progress.lock().unwrap() = Some((done, total));
+                })?;
+                Ok(Some(count))
+            })();
+            task_done.store(true, Ordering::SeqCst);
+            result
+        });
+
+        cx.spawn(async move |this, cx| {
+            // Mirror rebuild progress into the sidebar until the task finishes
+            while !done.load(Ordering::SeqCst) {
+                cx.background_executor().timer(Duration::from_millis(250)).await;
+                let current = *progress.lock().unwrap();
+                let alive = cx
+                    .update(|cx| {
+                        this.update(cx, |app, cx| {
+                            if app.index_rebuild_progress != current {
+                                app.index_rebuild_progress = current;
+                                cx.notify();
+                            }
+                        })
+                        .is_ok()
+                    })
+                    .unwrap_or(false);
+                if !alive {
+                    return;
+                }
+            }
+
+            let result = task.await;
+            cx.update(|cx| {
+                this.update(cx, |app, cx| {
+                    app.index_rebuild_progress = None;
+                    match result {
+                        Ok(Some(count)) => {
+                            info!("Rebuilt search index ({} messages)", count);
+                            app.refresh_smart_folders();
+                        }
+                        Ok(None) => debug!("Search index verified"),
+                        Err(e) => error!("Failed to rebuild search index: {}", e),
+                    }
+                    cx.notify();
+                })
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Retry an account's unsent mail in the background
+    ///
+    /// Runs with every sync, so messages Gmail couldn't take are retried as
+    /// their backoff comes due. The sidebar's outbox count is refreshed
+    /// afterwards.
+    fn process_outbox(&mut self, account_id: i64, cx: &mut Context<Self>) {
+        let Some(handler) = self
+            .accounts
+            .get(&account_id)
+            .map(|state| state.action_handler.clone())
+        else {
+            return;
+        };
+        let store = self.store.clone();
+        let background = cx.background_executor().clone();
+        cx.spawn(async move |this, cx| {
+            let unsent = background
+                .spawn(async move {
+                    if let Err(e) = handler.process_outbox(account_id) {
+                        warn!("Failed to process outbox for account {}: {}", account_id, e);
+                    }
+                    mail::count_unsent(store.as_ref(), None)
+                })
+                .await;
+
+            cx.update(|cx| {
+                this.update(cx, |app, cx| match unsent {
+                    Ok(unsent) => {
+                        if app.outbox_unsent != unsent {
+                            app.outbox_unsent = unsent;
+                            cx.notify();
+                        }
+                    }
+                    Err(e) => warn!("Failed to count outbox messages: {}", e),
+                })
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Check the store and search index for orphaned data, if enabled
+    ///
+    /// Controlled by `mail.integrity.json`; results are logged.
+    fn check_integrity(&mut self, cx: &mut Context<Self>) {
+        let config = IntegrityConfig::load();
+        if !config.check_on_startup {
+            return;
+        }
+
+        let store = self.store.clone();
+        let search_index = self.search_index.clone();
+        cx.background_executor()
+            .spawn(async move {
+                let search = search_index.as_deref().map(|index| index as &dyn SearchBackend);
+                if let Err(e) = run_startup_check(store.as_ref(), search, &config) {
+                    error!("Integrity check failed: {}", e);
+                }
+            })
+            .detach();
+    }
+
+    /// Remove mail that has outlived Gmail's Trash and Spam retention
+    ///
+    /// Gmail purges it on its own schedule; this keeps the local store in
+    /// step while no sync reports the deletions.
+    fn purge_expired_trash(&mut self, cx: &mut Context<Self>) {
+        let store = self.store.clone();
+        let search_index = self.search_index.clone();
+        cx.background_executor()
+            .spawn(async move {
+                let search = search_index.as_deref().map(|index| index as &dyn SearchBackend);
+                if let Err(e) = mail::purge_expired_trash(store.as_ref(), search, None) {
+                    warn!("Failed to purge expired trash: {}", e);
+                }
+            })
+            .detach();
+    }
+
+    /// Follow the `cosmosd` sync daemon if one is running
+    ///
+    /// While connected, scheduled syncs are left to the daemon and its events
+    /// refresh the views; an explicit sync still runs in the app. If the
+    /// daemon exits, the app goes back to polling on its own.
+    fn connect_daemon(&mut self, cx: &mut Context<Self>) {
+        let Some(path) = mail::daemon_socket_path() else {
+            return;
+        };
+        let events = match mail::connect_daemon(&path) {
+            Ok(events) => events,
+            Err(e) => {
+                debug!("No sync daemon at {}: {}", path.display(), e);
+                return;
+            }
+        };
+        info!("Connected to sync daemon at {}", path.display());
+        self.daemon_connected = true;
+
+        // Reading the socket blocks, so forward events from a plain thread
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        std::thread::spawn(move || {
+            for event in events {
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+
+        cx.spawn(async move |this, cx| {
+            while let Some(event) = receiver.recv().await {
+                let handled =
+                    cx.update(|cx| this.update(cx, |app, cx| app.handle_daemon_event(event, cx)));
+                if !matches!(handled, Ok(Ok(()))) {
+                    return;
+                }
+            }
+            warn!("Sync daemon disconnected, resuming local polling");
+            cx.update(|cx| {
+                this.update(cx, |app, cx| {
+                    app.daemon_connected = false;
+                    if app.poll_task.is_none() && app.gmail_client.is_some() {
+                        app.start_polling(cx);
+                    }
+                })
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Reflect something the sync daemon did
+    fn handle_daemon_event(&mut self, event: DaemonEvent, cx: &mut Context<Self>) {
+        match event {
+            DaemonEvent::SyncStarted { account_id } => {
+                if let Some(state) = self.accounts.get_mut(&account_id) {
+                    state.is_syncing = true;
+                }
+            }
+            DaemonEvent::SyncCompleted { account_id, .. } => {
+                if let Some(state) = self.accounts.get_mut(&account_id) {
+                    state.is_syncing = false;
+                    state.last_sync_at = Some(Utc::now());
+                    state.sync_error = None;
+                }
+                // Rows written by another process don't raise store events
+                self.mark_synced();
+                self.refresh_inbox_unread_count();
+                if let Some(thread_list) = &self.thread_list_view {
+                    thread_list.update(cx, |view, cx| view.load_threads(cx));
+                }
+            }
+            DaemonEvent::SyncFailed { account_id, error } => {
+                if let Some(state) = self.accounts.get_mut(&account_id) {
+                    state.is_syncing = false;
+                    state.sync_error = Some(error);
+                }
+            }
+            DaemonEvent::OpenThread { thread_id } => {
+                cx.activate(true);
+                self.show_thread(thread_id, cx);
+            }
+        }
+        cx.notify();
+    }
+
+    /// Create search index in the data directory
+    fn create_search_index(storage: &StorageSettings) -> anyhow::Result<SearchIndex> {
         // Ensure config directory exists
         config::init()?;
 
         // Get path for search index
-        let index_path = config::config_path("mail.search.idx")
-            .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
+        let index_path = storage.index_path()?;
 
-        SearchIndex::open(&index_path)
+        Ok(SearchIndex::open(&index_path)?.with_config(SearchConfig::load()))
     }
 
     /// Get or create the shared WebView
@@ -362,13 +788,13 @@ impl OrionApp {
         let bg_g = (bg.g * 255.0) as u8;
         let bg_b = (bg.b * 255.0) as u8;
 
-        // Create initial HTML with dark background
+        // Create initial HTML with the theme background
         let initial_html = format!(
             "<html><head><style>html,body{{margin:0;padding:0;background:rgb({},{},{});}}</style></head><body></body></html>",
             bg_r, bg_g, bg_b
         );
 
-        // Create a new WebView with dark background
+        // Create a new WebView with the theme background
         let wry_webview = WebViewBuilder::new()
             .with_html(&initial_html)
             .with_background_color((bg_r, bg_g, bg_b, 255))
@@ -391,16 +817,14 @@ impl OrionApp {
         self.webview_loaded_html = None;
     }
 
-    /// Create persistent storage in the config directory
-    fn create_persistent_store() -> anyhow::Result<SqliteMailStore> {
+    /// Create persistent storage in the data directory
+    fn create_persistent_store(storage: &StorageSettings) -> anyhow::Result<SqliteMailStore> {
         // Ensure config directory exists
         config::init()?;
 
         // Get paths for database and blob storage
-        let db_path = config::config_path("mail.db")
-            .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
-        let blob_path = config::config_path("blobs")
-            .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
+        let db_path = storage.db_path()?;
+        let blob_path = storage.blob_path()?;
 
         // Create blob store for message bodies
         let blob_store = Box::new(FileBlobStore::new(&blob_path)?);
@@ -444,10 +868,16 @@ impl OrionApp {
     ) {
         match event {
             SearchBoxEvent::QueryChanged(query) => {
-                self.update_search(query.clone(), cx);
+                self.update_search(query.clone(), true, cx);
+                self.refresh_suggestions(query.clone(), cx);
             }
             SearchBoxEvent::Submitted(query) => {
-                self.update_search(query.clone(), cx);
+                if !query.trim().is_empty()
+                    && let Err(e) = self.store.record_recent_search(query.trim())
+                {
+                    warn!("Failed to record recent search: {}", e);
+                }
+                self.update_search(query.clone(), false, cx);
                 // Set flag to focus results on next render (when we have window access)
                 self.pending_focus_results = true;
             }
@@ -461,7 +891,9 @@ impl OrionApp {
     }
 
     /// Update search results with a new query
-    fn update_search(&mut self, query: String, cx: &mut Context<Self>) {
+    ///
+    /// `instant` runs the prefix/fuzzy search used while typing.
+    fn update_search(&mut self, query: String, instant: bool, cx: &mut Context<Self>) {
         if query.is_empty() {
             self.clear_search(cx);
             return;
@@ -473,9 +905,11 @@ impl OrionApp {
                 let store = self.store.clone();
                 let index = index.clone();
                 let app_handle = cx.entity().clone();
+                let account_id = self.selected_account;
                 self.search_results_view = Some(cx.new(|cx| {
                     let mut view = SearchResultsView::new(store, index, cx);
                     view.set_app(app_handle);
+                    view.set_account_filter(account_id, cx);
                     view
                 }));
             }
@@ -484,7 +918,11 @@ impl OrionApp {
         // Execute search
         if let Some(ref results_view) = self.search_results_view {
             results_view.update(cx, |view, cx| {
-                view.search(query.clone(), cx);
+                if instant {
+                    view.instant_search(query.clone(), cx);
+                } else {
+                    view.search(query.clone(), cx);
+                }
             });
         }
 
@@ -495,8 +933,47 @@ impl OrionApp {
         cx.notify();
     }
 
+    /// Load typeahead suggestions for the query and show them in the search box
+    fn refresh_suggestions(&mut self, query: String, cx: &mut Context<Self>) {
+        let (Some(index), Some(search_box)) = (self.search_index.clone(), self.search_box.clone())
+        else {
+            return;
+        };
+        if query.trim().is_empty() {
+            search_box.update(cx, |view, cx| view.clear_suggestions(cx));
+            return;
+        }
+
+        let store = self.store.clone();
+        let background = cx.background_executor().clone();
+        cx.spawn(async move |_this, cx| {
+            let result = background
+                .spawn(async move {
+                    suggest(&index, store.as_ref(), &query, SEARCH_SUGGESTION_LIMIT)
+                        .map(|suggestions| (query, suggestions))
+                })
+                .await;
+
+            let _ = cx.update(|cx| match result {
+                Ok((query, suggestions)) => {
+                    search_box.update(cx, |view, cx| {
+                        // Drop suggestions for text the user has since changed
+                        if view.query(cx) == query {
+                            view.set_suggestions(suggestions, cx);
+                        }
+                    });
+                }
+                Err(e) => warn!("Failed to load search suggestions: {}", e),
+            });
+        })
+        .detach();
+    }
+
     /// Clear search and return to inbox
     fn clear_search(&mut self, cx: &mut Context<Self>) {
+        if let Some(search_box) = self.search_box.clone() {
+            search_box.update(cx, |view, cx| view.clear_suggestions(cx));
+        }
         self.search_results_view = None;
         self.show_inbox(cx);
     }
@@ -517,6 +994,15 @@ impl OrionApp {
         }
     }
 
+    /// Get the thread being read, whether open or in the preview pane
+    fn shown_thread_id(&self) -> Option<&ThreadId> {
+        match &self.current_view {
+            View::Thread { thread_id, .. } => Some(thread_id),
+            View::Inbox if self.layout.is_split() => self.preview.as_ref().map(|p| &p.thread_id),
+            _ => None,
+        }
+    }
+
     /// Archive the current thread (navigates back to inbox after)
     pub fn archive_current_thread(&mut self, cx: &mut Context<Self>) {
         let Some(thread_id) = self.current_thread_id().cloned() else {
@@ -737,84 +1223,285 @@ impl OrionApp {
         .detach();
     }
 
-    /// Add a new Gmail account via OAuth flow
+    /// Apply a bulk action to many threads, possibly from several accounts
     ///
-    /// This will:
-    /// 1. Open browser for OAuth authentication (runs on background thread)
-    /// 2. Get the user's email from Gmail API
-    /// 3. Save account with token to database
-    /// 4. Create AccountState
-    pub fn add_account(&mut self, cx: &mut Context<Self>) {
-        let (Some(client_id), Some(client_secret)) =
-            (self.oauth_client_id.clone(), self.oauth_client_secret.clone())
-        else {
-            error!("No OAuth credentials configured");
+    /// Threads are grouped by account so each account's handler modifies its
+    /// own messages in as few Gmail requests as possible.
+    pub fn bulk_modify_threads(
+        &mut self,
+        threads: Vec<(i64, ThreadId)>,
+        action: BulkAction,
+        cx: &mut Context<Self>,
+    ) {
+        let mut by_handler: Vec<(Arc<ActionHandler>, Vec<ThreadId>)> = Vec::new();
+        let mut by_account: HashMap<i64, Vec<ThreadId>> = HashMap::new();
+        for (account_id, thread_id) in threads {
+            by_account.entry(account_id).or_default().push(thread_id);
+        }
+        for (account_id, thread_ids) in by_account {
+            let handler = self
+                .accounts
+                .get(&account_id)
+                .map(|state| state.action_handler.clone())
+                .or_else(|| self.action_handler.clone());
+            match handler {
+                Some(handler) => by_handler.push((handler, thread_ids)),
+                None => warn!("Cannot modify threads: no handler for account {}", account_id),
+            }
+        }
+        if by_handler.is_empty() {
             return;
-        };
-
-        let store = self.store.clone();
-        let current_account_count = self.accounts.len();
-        let background = cx.background_executor().clone();
+        }
 
-        info!("Starting OAuth flow for new account...");
+        info!("Applying {:?} to {} accounts", action, by_handler.len());
 
+        let background = cx.background_executor().clone();
         cx.spawn(async move |this, cx| {
-            // Run OAuth flow on background thread to avoid blocking UI
-            let oauth_result = background
+            let result = background
                 .spawn(async move {
-                    // Create auth with no existing token (will trigger OAuth flow)
-                    let auth = GmailAuth::with_token_data(
-                        client_id.clone(),
-                        client_secret.clone(),
-                        None,
-                    );
-                    let client = GmailClient::new(auth);
-
-                    // Get access token (triggers OAuth flow in browser)
-                    let profile = client.get_profile()?;
-                    let email = profile.email_address;
-                    let token_data = client.get_token_data();
-
-                    // Check if account already exists
-                    if let Ok(Some(_)) = store.get_account_by_email(&email) {
-                        return Err(anyhow::anyhow!("Account {} already exists", email));
+                    let (add, remove) = action.label_changes();
+                    let mut modified = 0;
+                    for (handler, thread_ids) in &by_handler {
+                        modified += handler.batch_modify(thread_ids, &add, &remove)?;
                     }
-
-                    // Create and save account
-                    let is_primary = current_account_count == 0;
-                    let new_account = Account {
-                        id: 0,
-                        email: email.clone(),
-                        display_name: None,
-                        avatar_color: Self::generate_avatar_color(current_account_count),
-                        is_primary,
-                        added_at: chrono::Utc::now(),
-                        token_data,
-                    };
-
-                    let account = store.register_account(new_account)?;
-                    info!("Saved account: {} (id={})", email, account.id);
-
-                    Ok((account, client_id, client_secret))
+                    anyhow::Ok(modified)
                 })
                 .await;
 
-            // Update app state on main thread
-            match oauth_result {
-                Ok((account, client_id, client_secret)) => {
-                    cx.update(|cx| {
-                        this.update(cx, |app, cx| {
-                            // Create AccountState
-                            let auth = GmailAuth::with_token_data(
-                                client_id,
-                                client_secret,
-                                account.token_data.clone(),
-                            );
+            cx.update(|cx| {
+                this.update(cx, |app, cx| {
+                    match result {
+                        Ok(modified) => {
+                            info!("Bulk action modified {} messages", modified);
+                            if let Some(thread_list) = &app.thread_list_view {
+                                thread_list.update(cx, |view, cx| view.load_threads(cx));
+                            }
+                            app.refresh_inbox_unread_count();
+                            app.try_sync(cx);
+                        }
+                        Err(e) => {
+                            error!("Failed to apply bulk action: {}", e);
+                        }
+                    }
+                    cx.notify();
+                })
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Archive or mark read every thread with a label, in the background
+    ///
+    /// Covers one account, or every account when `account_id` is None.
+    /// Threads are modified in batches and progress shows in the sidebar,
+    /// so clearing thousands of threads doesn't hold up the UI.
+    pub fn bulk_modify_label(
+        &mut self,
+        label_id: String,
+        account_id: Option<i64>,
+        action: BulkAction,
+        cx: &mut Context<Self>,
+    ) {
+        use std::sync::Mutex;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::time::Duration;
+
+        if !matches!(action, BulkAction::Archive | BulkAction::MarkRead) {
+            warn!("{:?} can't be applied to a whole label", action);
+            return;
+        }
+        if self.label_action_progress.is_some() {
+            warn!("A label-wide action is already running");
+            return;
+        }
+
+        let handlers: Vec<(i64, Arc<ActionHandler>)> = match account_id {
+            Some(id) => self
+                .accounts
+                .get(&id)
+                .map(|state| state.action_handler.clone())
+                .or_else(|| self.action_handler.clone())
+                .map(|handler| vec![(id, handler)])
+                .unwrap_or_default(),
+            None => self
+                .accounts
+                .iter()
+                .map(|(id, state)| (*id, state.action_handler.clone()))
+                .collect(),
+        };
+        if handlers.is_empty() {
+            warn!("Cannot modify {}: no action handler", label_id);
+            return;
+        }
+
+        info!("Applying {:?} to all of {} in {} accounts", action, label_id, handlers.len());
+        self.label_action_progress = Some((0, 0));
+        cx.notify();
+
+        let progress = Arc::new(Mutex::new((0, 0)));
+        let done = Arc::new(AtomicBool::new(false));
+        let task_progress = progress.clone();
+        let task_done = done.clone();
+        let task = cx.background_executor().spawn(async move {
+            let result = (|| -> anyhow::Result<usize> {
+                let mut modified = 0;
+                // Progress so far from accounts already finished
+                let mut finished = (0, 0);
+                for (account_id, handler) in &handlers {
+                    let on_progress = |done: usize, total: usize| {
+                        *task_progress.lock().unwrap() = (finished.0 + done, finished.1 + total);
+                    };
+                    modified += match action {
+                        BulkAction::MarkRead => {
+                            handler.mark_label_read(*account_id, &label_id, on_progress)?
+                        }
+                        _ => handler.archive_label(*account_id, &label_id, on_progress)?,
+                    };
+                    finished = *task_progress.lock().unwrap();
+                }
+                Ok(modified)
+            })();
+            task_done.store(true, Ordering::SeqCst);
+            result
+        });
+
+        cx.spawn(async move |this, cx| {
+            // Mirror progress into the sidebar until the task finishes
+            while !done.load(Ordering::SeqCst) {
+                cx.background_executor().timer(Duration::from_millis(250)).await;
+                let current = Some(*progress.lock().unwrap());
+                let alive = cx
+                    .update(|cx| {
+                        this.update(cx, |app, cx| {
+                            if app.label_action_progress != current {
+                                app.label_action_progress = current;
+                                cx.notify();
+                            }
+                        })
+                        .is_ok()
+                    })
+                    .unwrap_or(false);
+                if !alive {
+                    return;
+                }
+            }
+
+            let result = task.await;
+            cx.update(|cx| {
+                this.update(cx, |app, cx| {
+                    app.label_action_progress = None;
+                    match result {
+                        Ok(modified) => info!("Label-wide action modified {} messages", modified),
+                        Err(e) => error!("Failed to apply label-wide action: {}", e),
+                    }
+                    // Reload even on failure, since earlier batches went through
+                    if let Some(thread_list) = &app.thread_list_view {
+                        thread_list.update(cx, |view, cx| view.load_threads(cx));
+                    }
+                    app.refresh_inbox_unread_count();
+                    app.try_sync(cx);
+                    cx.notify();
+                })
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Whether a label-wide archive or mark-read is running
+    pub fn is_modifying_label(&self) -> bool {
+        self.label_action_progress.is_some()
+    }
+
+    /// Labels the sidebar knows about
+    pub fn labels(&self) -> &[Label] {
+        &self.labels
+    }
+
+    /// Add a new Gmail account via OAuth flow
+    ///
+    /// This will:
+    /// 1. Open browser for OAuth authentication (runs on background thread)
+    /// 2. Get the user's email from Gmail API
+    /// 3. Save account with token to database
+    /// 4. Create AccountState
+    pub fn add_account(&mut self, cx: &mut Context<Self>) {
+        let (Some(client_id), Some(client_secret)) =
+            (self.oauth_client_id.clone(), self.oauth_client_secret.clone())
+        else {
+            error!("No OAuth credentials configured");
+            return;
+        };
+
+        let store = self.store.clone();
+        let current_account_count = self.accounts.len();
+        let background = cx.background_executor().clone();
+
+        info!("Starting OAuth flow for new account...");
+
+        cx.spawn(async move |this, cx| {
+            // Run OAuth flow on background thread to avoid blocking UI
+            let oauth_result = background
+                .spawn(async move {
+                    // Create auth with no existing token (will trigger OAuth flow)
+                    let auth = GmailAuth::with_token_data(
+                        client_id.clone(),
+                        client_secret.clone(),
+                        None,
+                    );
+                    let client = GmailClient::new(auth);
+
+                    // Get access token (triggers OAuth flow in browser)
+                    let profile = client.get_profile()?;
+                    let email = profile.email_address;
+                    let token_data = client.get_token_data();
+
+                    // Check if account already exists
+                    if let Ok(Some(_)) = store.get_account_by_email(&email) {
+                        return Err(anyhow::anyhow!("Account {} already exists", email));
+                    }
+
+                    // Create and save account
+                    let is_primary = current_account_count == 0;
+                    let new_account = Account {
+                        id: 0,
+                        email: email.clone(),
+                        display_name: None,
+                        avatar_color: Self::generate_avatar_color(current_account_count),
+                        is_primary,
+                        added_at: chrono::Utc::now(),
+                        token_data,
+                        signature: None,
+                        sync_enabled: true,
+                    };
+
+                    let account = store.register_account(new_account)?;
+                    info!("Saved account: {} (id={})", email, account.id);
+
+                    Ok((account, client_id, client_secret))
+                })
+                .await;
+
+            // Update app state on main thread
+            match oauth_result {
+                Ok((account, client_id, client_secret)) => {
+                    cx.update(|cx| {
+                        this.update(cx, |app, cx| {
+                            // Create AccountState
+                            let auth = GmailAuth::with_token_data(
+                                client_id,
+                                client_secret,
+                                account.token_data.clone(),
+                            );
                             let gmail_client = Arc::new(GmailClient::new(auth));
-                            let action_handler = Arc::new(ActionHandler::new(
-                                gmail_client.clone(),
-                                app.store.clone(),
-                            ));
+                            let mut action_handler =
+                                ActionHandler::new(gmail_client.clone(), app.store.clone())
+                                    .with_tracking(TrackingConfig::load());
+                            if let Some(index) = app.search_index.clone() {
+                                action_handler = action_handler.with_search_index(index);
+                            }
+                            let action_handler = Arc::new(action_handler);
 
                             let account_state = AccountState {
                                 account: account.clone(),
@@ -823,6 +1510,7 @@ impl OrionApp {
                                 is_syncing: false,
                                 last_sync_at: None,
                                 sync_error: None,
+                                health: AccountHealth::Healthy,
                             };
 
                             if account.is_primary {
@@ -883,12 +1571,16 @@ impl OrionApp {
 
             // Create Gmail client and action handler
             let gmail_client = Arc::new(GmailClient::new(auth));
-            let action_handler = Arc::new(ActionHandler::new(
-                gmail_client.clone(),
-                self.store.clone(),
-            ));
+            let mut action_handler = ActionHandler::new(gmail_client.clone(), self.store.clone())
+                .with_tracking(TrackingConfig::load());
+            if let Some(index) = self.search_index.clone() {
+                action_handler = action_handler.with_search_index(index);
+            }
+            let action_handler = Arc::new(action_handler);
 
             // Create AccountState
+            let health = mail::account_health(self.store.as_ref(), account.id)
+                .unwrap_or(AccountHealth::Healthy);
             let account_state = AccountState {
                 account: account.clone(),
                 gmail_client: gmail_client.clone(),
@@ -896,6 +1588,7 @@ impl OrionApp {
                 is_syncing: false,
                 last_sync_at: None,
                 sync_error: None,
+                health,
             };
 
             // Set primary account fields
@@ -916,6 +1609,17 @@ impl OrionApp {
             );
         }
 
+        // Go back to the account the last session was showing
+        let saved_account = self.session.selected_account.as_ref().and_then(|email| {
+            self.accounts
+                .values()
+                .find(|state| &state.account.email == email)
+                .map(|state| state.account.id)
+        });
+        if saved_account.is_some() {
+            self.set_account_filter(saved_account, cx);
+        }
+
         cx.notify();
     }
 
@@ -960,6 +1664,14 @@ impl OrionApp {
     /// Pass `None` for unified view (all accounts), or `Some(id)` for single account.
     pub fn set_account_filter(&mut self, account_id: Option<i64>, cx: &mut Context<Self>) {
         self.selected_account = account_id;
+        self.refresh_smart_folders();
+        self.refresh_label_tree();
+
+        let selected_email = self.selected_account_email().map(str::to_string);
+        if self.session.selected_account != selected_email {
+            self.session.selected_account = selected_email;
+            self.session.save();
+        }
 
         // Update thread list view with the new account filter
         if let Some(thread_list) = &self.thread_list_view {
@@ -968,9 +1680,29 @@ impl OrionApp {
             });
         }
 
+        // Search results follow the same scope
+        if let Some(results_view) = &self.search_results_view {
+            results_view.update(cx, |view, cx| {
+                view.set_account_filter(account_id, cx);
+            });
+        }
+
         cx.notify();
     }
 
+    /// Email of the account the views are filtered to, if any
+    fn selected_account_email(&self) -> Option<&str> {
+        let state = self.accounts.get(&self.selected_account?)?;
+        Some(state.account.email.as_str())
+    }
+
+    /// Accounts in sidebar order, oldest first
+    fn ordered_accounts(&self) -> Vec<&AccountState> {
+        let mut accounts: Vec<_> = self.accounts.values().collect();
+        accounts.sort_by_key(|state| state.account.id);
+        accounts
+    }
+
     /// Check if we're in unified view (all accounts)
     #[allow(dead_code)]
     pub fn is_unified_view(&self) -> bool {
@@ -983,94 +1715,300 @@ impl OrionApp {
         self.accounts.values().collect()
     }
 
-    /// Sync all accounts (or just the selected account if filtered)
+    /// Ask for confirmation, then remove an account
+    fn confirm_remove_account(
+        &mut self,
+        account_id: i64,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(state) = self.accounts.get(&account_id) else {
+            return;
+        };
+        let message = format!("Remove {}?", state.account.email);
+        let answer = window.prompt(
+            PromptLevel::Warning,
+            &message,
+            Some("Its mail is deleted from this device and Orion's access is revoked."),
+            &["Remove Account", "Cancel"],
+            cx,
+        );
+
+        cx.spawn(async move |this, cx| {
+            if answer.await != Ok(0) {
+                return;
+            }
+            cx.update(|cx| {
+                this.update(cx, |app, cx| app.remove_account(account_id, cx)).ok();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Remove an account with all its local data and revoke its OAuth token
     ///
-    /// This is called by the sync button in the sidebar.
-    pub fn sync_all_accounts(&mut self, cx: &mut Context<Self>) {
-        // If a specific account is selected, just sync that one
-        if let Some(account_id) = self.selected_account {
-            self.sync_account(account_id, cx);
+    /// The account disappears from the sidebar straight away; the cleanup
+    /// runs in the background.
+    fn remove_account(&mut self, account_id: i64, cx: &mut Context<Self>) {
+        let Some(state) = self.accounts.remove(&account_id) else {
             return;
-        }
+        };
+        let email = state.account.email;
 
-        // Otherwise sync all accounts
-        let account_ids: Vec<i64> = self.accounts.keys().copied().collect();
-        for account_id in account_ids {
-            self.sync_account(account_id, cx);
+        if self.selected_account == Some(account_id) {
+            self.set_account_filter(None, cx);
         }
-    }
 
-    /// Navigate to thread list view
-    pub fn show_inbox(&mut self, cx: &mut Context<Self>) {
-        if self.thread_list_view.is_none() {
-            let store = self.store.clone();
-            self.thread_list_view = Some(cx.new(|cx| ThreadListView::new(store, cx)));
+        // Promote the oldest remaining account if the primary one was removed
+        if self.primary_account_id == Some(account_id) {
+            let next = self.accounts.values().min_by_key(|s| s.account.id);
+            self.primary_account_id = next.map(|s| s.account.id);
+            self.gmail_client = next.map(|s| s.gmail_client.clone());
+            self.action_handler = next.map(|s| s.action_handler.clone());
+            self.profile_email = next.map(|s| s.account.email.clone());
         }
-        // Hide the WebView when not viewing a thread
-        self.hide_webview(cx);
-        // Clean up thread view
-        self.thread_view = None;
-        self.current_view = View::Inbox;
-        self.thread_list_context = ListContext::Inbox;
-        // Focus thread list on next render
-        self.pending_focus = Some(PendingFocus::ThreadList);
         cx.notify();
+
+        let store = self.store.clone();
+        let search_index = self.search_index.clone();
+        let background = cx.background_executor().clone();
+        cx.spawn(async move |this, cx| {
+            let result = background
+                .spawn(async move {
+                    let search = search_index.as_deref().map(|index| index as &dyn SearchBackend);
+                    mail::remove_account(store.as_ref(), search, account_id)
+                })
+                .await;
+
+            if let Err(e) = result {
+                error!("Failed to remove account {}: {}", email, e);
+            }
+
+            cx.update(|cx| {
+                this.update(cx, |app, cx| {
+                    if let Some(thread_list) = &app.thread_list_view {
+                        thread_list.update(cx, |view, cx| view.load_threads(cx));
+                    }
+                    app.refresh_inbox_unread_count();
+                    app.refresh_smart_folders();
+                    cx.notify();
+                })
+            })
+            .ok();
+        })
+        .detach();
     }
 
-    /// Navigate to thread view
-    pub fn show_thread(&mut self, thread_id: ThreadId, cx: &mut Context<Self>) {
-        // Track which list context we're coming from
-        self.thread_list_context = match self.current_view {
-            View::Search => ListContext::Search,
-            _ => ListContext::Inbox,
+    /// Sign an account in again after its token was revoked
+    ///
+    /// Runs the OAuth flow in the browser, then swaps the account's Gmail
+    /// client for one using the new token and resumes syncing. Synced mail
+    /// is kept.
+    fn reconnect_account(&mut self, account_id: i64, cx: &mut Context<Self>) {
+        let (Some(client_id), Some(client_secret)) =
+            (self.oauth_client_id.clone(), self.oauth_client_secret.clone())
+        else {
+            error!("No OAuth credentials configured");
+            return;
         };
 
-        // Load thread data and generate HTML upfront (not during render)
         let store = self.store.clone();
-        let theme = cx.theme();
-        let thread_html = match mail::get_thread_detail(store.as_ref(), &thread_id) {
-            Ok(Some(detail)) => {
-                info!(
-                    "Thread {} has {} messages",
-                    thread_id.as_str(),
-                    detail.messages.len()
-                );
-                let html = templates::thread_html(&detail.messages, &theme);
-                info!("Generated HTML with {} bytes", html.len());
-                html
-            }
+        let background = cx.background_executor().clone();
+        let (auth_id, auth_secret) = (client_id.clone(), client_secret.clone());
+
+        cx.spawn(async move |this, cx| {
+            let result = background
+                .spawn(async move {
+                    mail::reconnect_account(store.as_ref(), account_id, auth_id, auth_secret)
+                })
+                .await;
+
+            let account = match result {
+                Ok(account) => account,
+                Err(e) => {
+                    error!("Failed to reconnect account {}: {}", account_id, e);
+                    return;
+                }
+            };
+
+            cx.update(|cx| {
+                this.update(cx, |app, cx| {
+                    let auth = GmailAuth::with_token_data(
+                        client_id,
+                        client_secret,
+                        account.token_data.clone(),
+                    );
+                    let gmail_client = Arc::new(GmailClient::new(auth));
+                    let mut action_handler =
+                        ActionHandler::new(gmail_client.clone(), app.store.clone())
+                            .with_tracking(TrackingConfig::load());
+                    if let Some(index) = app.search_index.clone() {
+                        action_handler = action_handler.with_search_index(index);
+                    }
+                    let action_handler = Arc::new(action_handler);
+
+                    if app.primary_account_id == Some(account_id) {
+                        app.gmail_client = Some(gmail_client.clone());
+                        app.action_handler = Some(action_handler.clone());
+                    }
+                    if let Some(state) = app.accounts.get_mut(&account_id) {
+                        state.account = account;
+                        state.gmail_client = gmail_client;
+                        state.action_handler = action_handler;
+                        state.health = AccountHealth::Healthy;
+                        state.sync_error = None;
+                    }
+                    cx.notify();
+
+                    app.sync_account(account_id, cx);
+                })
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Pause syncing an account, or resume it, from the sidebar
+    ///
+    /// For metered connections: a paused account keeps its mail but isn't
+    /// synced, by Orion or `cosmosd`, until resumed.
+    fn toggle_account_sync(&mut self, account_id: i64, cx: &mut Context<Self>) {
+        let Some(state) = self.accounts.get_mut(&account_id) else {
+            return;
+        };
+        let enabled = !state.account.sync_enabled;
+        let result = if enabled {
+            self.sync_orchestrator.resume(account_id)
+        } else {
+            self.sync_orchestrator.pause(account_id)
+        };
+        if let Err(e) = result {
+            error!("Failed to change syncing for {}: {}", state.account.email, e);
+            return;
+        }
+        state.account.sync_enabled = enabled;
+        cx.notify();
+
+        if enabled {
+            self.sync_account(account_id, cx);
+        }
+    }
+
+    /// Sync all accounts (or just the selected account if filtered)
+    ///
+    /// This is called by the sync button in the sidebar. An explicit click
+    /// overrides the cooldown, so the scheduler's skip reason is cleared.
+    pub fn sync_all_accounts(&mut self, cx: &mut Context<Self>) {
+        self.sync_scheduler.record_started();
+
+        // If a specific account is selected, just sync that one
+        if let Some(account_id) = self.selected_account {
+            self.sync_account(account_id, cx);
+            return;
+        }
+
+        // Otherwise sync all accounts
+        let account_ids: Vec<i64> = self.accounts.keys().copied().collect();
+        for account_id in account_ids {
+            self.sync_account(account_id, cx);
+        }
+    }
+
+    /// Navigate to thread list view
+    pub fn show_inbox(&mut self, cx: &mut Context<Self>) {
+        if self.thread_list_view.is_none() {
+            let store = self.store.clone();
+            self.thread_list_view = Some(cx.new(|cx| ThreadListView::new(store, cx)));
+        }
+        // Hide the WebView when not viewing a thread
+        self.hide_webview(cx);
+        // Clean up thread view
+        self.thread_view = None;
+        self.current_view = View::Inbox;
+        self.thread_list_context = ListContext::Inbox;
+        // Focus thread list on next render
+        self.pending_focus = Some(PendingFocus::ThreadList);
+        cx.notify();
+    }
+
+    /// Navigate to thread view
+    pub fn show_thread(&mut self, thread_id: ThreadId, cx: &mut Context<Self>) {
+        // Track which list context we're coming from
+        self.thread_list_context = match self.current_view {
+            View::Search => ListContext::Search,
+            _ => ListContext::Inbox,
+        };
+
+        // Load thread data and generate HTML upfront (not during render)
+        let highlight_terms = self.highlight_terms(cx);
+        let thread_html = self.thread_html(&thread_id, &highlight_terms, cx);
+        self.thread_view = Some(self.new_thread_view(thread_id.clone(), cx));
+        self.current_view = View::Thread {
+            html: thread_html,
+            thread_id: thread_id.clone(),
+        };
+        // Focus thread view on next render
+        self.pending_focus = Some(PendingFocus::ThreadView);
+        cx.notify();
+
+        self.mark_thread_read(thread_id, cx);
+    }
+
+    /// Search terms to highlight in the open thread
+    ///
+    /// Only threads opened from search results are highlighted.
+    fn highlight_terms(&self, cx: &App) -> Vec<String> {
+        match (&self.thread_list_context, &self.search_results_view) {
+            (ListContext::Search, Some(results_view)) => results_view.read(cx).query_terms(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Generate the HTML shown in the WebView for a thread
+    fn thread_html(&self, thread_id: &ThreadId, highlight_terms: &[String], cx: &App) -> String {
+        let theme = cx.theme();
+        match mail::get_thread_detail(self.store.as_ref(), thread_id) {
+            Ok(Some(detail)) => {
+                info!(
+                    "Thread {} has {} messages",
+                    thread_id.as_str(),
+                    detail.messages.len()
+                );
+                let html = templates::thread_html(&detail.messages, theme, highlight_terms);
+                info!("Generated HTML with {} bytes", html.len());
+                html
+            }
             Ok(None) => {
                 warn!("Thread {} not found", thread_id.as_str());
-                templates::error_html("Thread not found", &theme)
+                templates::error_html("Thread not found", theme)
             }
             Err(e) => {
                 error!("Failed to load thread {}: {}", thread_id.as_str(), e);
-                templates::error_html(&format!("Failed to load thread: {}", e), &theme)
+                templates::error_html(&format!("Failed to load thread: {}", e), theme)
             }
-        };
+        }
+    }
 
+    /// Create the header view for a thread
+    fn new_thread_view(&self, thread_id: ThreadId, cx: &mut Context<Self>) -> Entity<ThreadView> {
+        let store = self.store.clone();
         let app_handle = cx.entity().clone();
-        let thread_id_clone = thread_id.clone();
-        self.thread_view = Some(cx.new(|cx| {
-            let mut view = ThreadView::new(store, thread_id.clone(), cx);
+        cx.new(|cx| {
+            let mut view = ThreadView::new(store, thread_id, cx);
             view.set_app(app_handle);
             view.load_thread(cx);
             view
-        }));
-        self.current_view = View::Thread {
-            html: thread_html.clone(),
-            thread_id: thread_id_clone.clone(),
-        };
-        // Focus thread view on next render
-        self.pending_focus = Some(PendingFocus::ThreadView);
-        cx.notify();
+        })
+    }
 
-        // Mark thread as read in background
+    /// Mark a thread as read in the background
+    fn mark_thread_read(&mut self, thread_id: ThreadId, cx: &mut Context<Self>) {
         if let Some(action_handler) = self.action_handler.clone() {
             let background = cx.background_executor().clone();
             cx.spawn(async move |this, cx| {
                 let result = background
-                    .spawn(async move { action_handler.set_read(&thread_id_clone, true) })
+                    .spawn(async move { action_handler.set_read(&thread_id, true) })
                     .await;
 
                 if let Err(e) = result {
@@ -1092,7 +2030,62 @@ impl OrionApp {
         }
     }
 
-    /// Refresh the unread count for the Inbox label from storage
+    /// Whether threads are previewed beside the list instead of opened in place
+    pub fn is_split_layout(&self) -> bool {
+        self.layout.is_split()
+    }
+
+    /// Show the thread list's selection in the preview pane
+    ///
+    /// Called while rendering the split layout; does nothing unless the
+    /// selection changed since the last preview.
+    fn sync_preview(&mut self, thread_list: &Entity<ThreadListView>, cx: &mut Context<Self>) {
+        let selected = thread_list.read(cx).selected_thread_id().cloned();
+        if selected.as_ref() == self.preview.as_ref().map(|p| &p.thread_id) {
+            return;
+        }
+
+        let Some(thread_id) = selected else {
+            self.preview = None;
+            self.preview_read_task = None;
+            self.hide_webview(cx);
+            return;
+        };
+        self.preview = Some(Preview {
+            html: self.thread_html(&thread_id, &[], cx),
+            view: self.new_thread_view(thread_id.clone(), cx),
+            thread_id: thread_id.clone(),
+        });
+
+        // Only mark unread threads read, and only if the user stays on them;
+        // replacing the task cancels the timer for the previous thread
+        let is_unread = self
+            .store
+            .get_thread(&thread_id)
+            .ok()
+            .flatten()
+            .is_some_and(|thread| thread.is_unread);
+        self.preview_read_task = is_unread.then(|| {
+            let delay = self.layout.mark_read_delay();
+            cx.spawn(async move |this, cx| {
+                cx.background_executor().timer(delay).await;
+                cx.update(|cx| {
+                    this.update(cx, |app, cx| {
+                        let still_previewed = app
+                            .preview
+                            .as_ref()
+                            .is_some_and(|p| p.thread_id == thread_id);
+                        if still_previewed {
+                            app.mark_thread_read(thread_id, cx);
+                        }
+                    })
+                })
+                .ok();
+            })
+        });
+    }
+
+    /// Refresh the unread counts for the Inbox and the nested labels from storage
     fn refresh_inbox_unread_count(&mut self) {
         let unread_count = self
             .store
@@ -1106,6 +2099,78 @@ impl OrionApp {
                 break;
             }
         }
+        self.refresh_label_tree();
+    }
+
+    /// Reload the current account's nested labels and their unread counts
+    fn refresh_label_tree(&mut self) {
+        let Some(account_id) = self.current_account_id() else {
+            self.label_tree.clear();
+            return;
+        };
+        match label_tree(self.store.as_ref(), account_id) {
+            Ok(tree) => self.label_tree = tree,
+            Err(e) => warn!("Failed to load labels: {}", e),
+        }
+    }
+
+    /// Show or hide a nested label's children in the sidebar
+    fn toggle_label_collapsed(&mut self, label_id: String, cx: &mut Context<Self>) {
+        if !self.collapsed_labels.remove(&label_id) {
+            self.collapsed_labels.insert(label_id);
+        }
+        cx.notify();
+    }
+
+    /// Show the current unread count and newest threads in the tray
+    fn refresh_tray(&self) {
+        self.tray.update(&TraySummary::load(self.store.as_ref()));
+    }
+
+    /// Refresh the tray now and again whenever the store changes
+    fn subscribe_tray_to_store(&mut self, cx: &mut Context<Self>) {
+        use std::sync::mpsc::TryRecvError;
+
+        self.refresh_tray();
+        let events = self.store.subscribe();
+        self.tray_events_task = Some(cx.spawn(async move |this, cx| {
+            loop {
+                cx.background_executor().timer(TRAY_EVENT_INTERVAL).await;
+
+                // Any number of queued changes needs only one refresh
+                let mut changed = false;
+                let disconnected = loop {
+                    match events.try_recv() {
+                        Ok(_) => changed = true,
+                        Err(TryRecvError::Empty) => break false,
+                        Err(TryRecvError::Disconnected) => break true,
+                    }
+                };
+
+                if changed {
+                    let alive = cx
+                        .update(|cx| this.update(cx, |app, _| app.refresh_tray()).is_ok())
+                        .unwrap_or(false);
+                    if !alive {
+                        break;
+                    }
+                }
+
+                if disconnected {
+                    break;
+                }
+            }
+        }));
+    }
+
+    /// Carry out a command chosen from the tray menu
+    fn run_tray_command(&mut self, command: TrayCommand, cx: &mut Context<Self>) {
+        match command {
+            TrayCommand::ShowApp => {}
+            TrayCommand::OpenThread(thread_id) => self.show_thread(thread_id, cx),
+            TrayCommand::Compose => self.open_compose(cx),
+            TrayCommand::SyncNow => self.sync_all_accounts(cx),
+        }
     }
 
     /// Select a label/folder to view
@@ -1132,15 +2197,22 @@ impl OrionApp {
 
     /// Check if enough time has passed since last sync to allow a new sync.
     ///
-    /// Returns false if:
+    /// Returns the reason the sync would be skipped:
     /// - Already syncing
     /// - Gmail client not configured
-    /// - Last sync was less than `sync_cooldown_secs` ago
-    fn should_sync(&self) -> bool {
-        if self.is_syncing || self.gmail_client.is_none() {
-            return false;
+    /// - Last sync was less than the sync cooldown ago
+    fn should_sync(&self) -> Result<(), SyncSkipReason> {
+        if self.daemon_connected {
+            return Err(SyncSkipReason::DaemonActive);
         }
-        mail::cooldown_elapsed(self.last_sync_at, self.sync_cooldown_secs)
+        mail::check_sync_allowed(
+            self.last_sync_at,
+            self.sync_schedule.cooldown_secs,
+            self.is_syncing,
+            self.gmail_client.is_some(),
+            false,
+            Utc::now(),
+        )
     }
 
     /// Try to sync if cooldown has elapsed.
@@ -1148,17 +2220,89 @@ impl OrionApp {
     /// This is the preferred way to trigger syncs from activity handlers
     /// (label navigation, actions completing, window focus, etc).
     fn try_sync(&mut self, cx: &mut Context<Self>) {
-        if self.should_sync() {
-            debug!("try_sync: cooldown elapsed, starting sync");
-            self.sync(cx);
-        } else {
-            debug!("try_sync: skipping sync (cooldown not elapsed or already syncing)");
+        match self.should_sync() {
+            Ok(()) => {
+                debug!("try_sync: cooldown elapsed, starting sync");
+                self.sync_scheduler.record_started();
+                self.sync(cx);
+            }
+            Err(reason) => {
+                debug!("try_sync: skipping sync ({})", reason.description());
+                self.sync_scheduler.record_skip(reason, Utc::now());
+            }
+        }
+    }
+
+    /// Record a completed sync for cooldown and countdown tracking
+    fn mark_synced(&mut self) {
+        let now = Utc::now();
+        self.last_sync_at = Some(now);
+        self.sync_scheduler.record_sync(now, self.sync_schedule.cooldown_secs);
+        self.refresh_smart_folders();
+    }
+
+    /// Reload pinned saved searches and their result counts
+    fn refresh_smart_folders(&mut self) {
+        let Some(ref index) = self.search_index else {
+            return;
+        };
+        match list_saved_searches_with_counts(
+            self.store.as_ref(),
+            index,
+            self.selected_account,
+            true,
+        ) {
+            Ok(folders) => self.smart_folders = folders,
+            Err(e) => warn!("Failed to load saved searches: {}", e),
+        }
+    }
+
+    /// Save a search query as a pinned smart folder
+    ///
+    /// The query doubles as the folder name; saving the same query twice is a no-op.
+    pub fn save_search(&mut self, query: String, cx: &mut Context<Self>) {
+        let query = query.trim().to_string();
+        if query.is_empty() {
+            return;
+        }
+
+        let existing = self.store.list_saved_searches().unwrap_or_default();
+        if existing.iter().any(|s| s.query == query) {
+            return;
         }
+
+        match self
+            .store
+            .save_search(SavedSearch::new(query.clone(), query).with_pinned(true))
+        {
+            Ok(search) => info!("Saved search {:?}", search.name),
+            Err(e) => error!("Failed to save search: {}", e),
+        }
+        self.refresh_smart_folders();
+        cx.notify();
+    }
+
+    /// Remove a saved search
+    fn delete_saved_search(&mut self, id: i64, cx: &mut Context<Self>) {
+        if let Err(e) = self.store.delete_saved_search(id) {
+            error!("Failed to delete saved search: {}", e);
+        }
+        self.refresh_smart_folders();
+        cx.notify();
+    }
+
+    /// Run a saved search, showing its query in the search box
+    fn open_saved_search(&mut self, query: String, window: &mut Window, cx: &mut Context<Self>) {
+        let search_box = self.get_or_create_search_box(window, cx);
+        search_box.update(cx, |view, cx| {
+            view.set_query(&query, window, cx);
+        });
+        self.update_search(query, false, cx);
     }
 
     /// Start background polling for new mail.
     ///
-    /// Runs a loop that syncs every `poll_interval_secs` seconds.
+    /// Runs a loop that syncs every `sync.poll_interval_secs` seconds.
     /// Polling stops if Gmail client is removed or app is dropped.
     fn start_polling(&mut self, cx: &mut Context<Self>) {
         use std::time::Duration;
@@ -1166,16 +2310,39 @@ impl OrionApp {
         // Cancel existing poll task if any
         self.poll_task = None;
 
-        let interval = Duration::from_secs(self.poll_interval_secs);
+        let interval = self.sync_schedule.poll_interval();
         info!(
             "Starting background sync polling (interval: {}s)",
-            self.poll_interval_secs
+            interval.as_secs()
         );
 
         self.poll_task = Some(cx.spawn(async move |this, cx| {
             loop {
-                // Wait for the polling interval
-                cx.background_executor().timer(interval).await;
+                let next_poll_at = Utc::now()
+                    + chrono::Duration::from_std(interval).unwrap_or(chrono::Duration::zero());
+                let scheduled = cx
+                    .update(|cx| {
+                        this.update(cx, |app, _cx| {
+                            app.sync_scheduler.record_poll_scheduled(next_poll_at);
+                        })
+                        .is_ok()
+                    })
+                    .unwrap_or(false);
+                if !scheduled {
+                    break;
+                }
+
+                // Wait for the polling interval, ticking once a second so the
+                // sidebar countdown stays current
+                while Utc::now() < next_poll_at {
+                    cx.background_executor().timer(Duration::from_secs(1)).await;
+                    let alive = cx
+                        .update(|cx| this.update(cx, |_app, cx| cx.notify()).is_ok())
+                        .unwrap_or(false);
+                    if !alive {
+                        return;
+                    }
+                }
 
                 // Try to sync
                 let should_continue = cx
@@ -1183,7 +2350,11 @@ impl OrionApp {
                         this.update(cx, |app, cx| {
                             app.try_sync(cx);
                             // Continue polling only if gmail is configured
-                            app.gmail_client.is_some()
+                            let keep_polling = app.gmail_client.is_some();
+                            if !keep_polling {
+                                app.sync_scheduler.record_poll_stopped();
+                            }
+                            keep_polling
                         })
                         .unwrap_or(false)
                     })
@@ -1201,7 +2372,7 @@ impl OrionApp {
     ///
     /// This is the preferred way to sync individual accounts in multi-account mode.
     pub fn sync_account(&mut self, account_id: i64, cx: &mut Context<Self>) {
-        use mail::{fetch_phase, process_pending_batch};
+        use mail::process_pending_batch;
         use std::sync::atomic::{AtomicBool, Ordering};
         use std::time::Duration;
 
@@ -1216,24 +2387,59 @@ impl OrionApp {
             return;
         }
 
+        if account_state.health == AccountHealth::NeedsReauth {
+            debug!("[SYNC] Account {} needs to be reconnected", account_id);
+            return;
+        }
+
+        // Pausing the account cancels the sync through the ticket
+        let ticket = match self.sync_orchestrator.begin(account_id) {
+            Ok(Some(ticket)) => ticket,
+            Ok(None) => {
+                debug!("[SYNC] Account {} is paused", account_id);
+                return;
+            }
+            Err(e) => {
+                warn!("[SYNC] {:#}", e);
+                return;
+            }
+        };
+
         let client = account_state.gmail_client.clone();
         let account_email = account_state.account.email.clone();
+        let (sync_settings, network_settings) =
+            match self.cosmos_config.for_account(&account_email) {
+                Ok(config) => (config.sync, config.network),
+                Err(e) => {
+                    warn!("[SYNC] {:#}", e);
+                    (
+                        self.cosmos_config.sync.clone(),
+                        self.cosmos_config.network.clone(),
+                    )
+                }
+            };
+        // Aliases rarely change, so fetch them once per session
+        let refresh_aliases = account_state.last_sync_at.is_none();
 
         // Mark account as syncing
         account_state.is_syncing = true;
         cx.notify();
 
         info!("[SYNC] Starting sync for account {} (id={})", account_email, account_id);
+        self.process_outbox(account_id, cx);
 
         let store = self.store.clone();
         let search_index = self.search_index.clone();
+        let notifier = self.notifier.clone();
         let background = cx.background_executor().clone();
 
         cx.spawn(async move |this, cx| {
             let options = SyncOptions {
                 search_index: search_index.clone(),
+                cancel: ticket.cancel(),
                 ..Default::default()
-            };
+            }
+            .with_settings(&sync_settings);
 
             // Get history_id for sync state
             let client_for_profile = client.clone();
@@ -1251,19 +2457,73 @@ impl OrionApp {
                 }
                 Err(e) => {
                     warn!("[SYNC] Failed to get profile for {}: {}", account_email, e);
+
+                    // A revoked token fails every request, so stop before
+                    // touching any synced data
+                    let health = mail::record_auth_failure(store.as_ref(), account_id, &e)
+                        .unwrap_or(AccountHealth::Healthy);
+                    if health == AccountHealth::NeedsReauth {
+                        cx.update(|cx| {
+                            this.update(cx, |app, cx| {
+                                if let Some(state) = app.accounts.get_mut(&account_id) {
+                                    state.is_syncing = false;
+                                    state.health = health;
+                                    state.sync_error = Some(e.to_string());
+                                }
+                                cx.notify();
+                            })
+                        })
+                        .ok();
+                        return;
+                    }
                     None
                 }
             };
 
-            // Check for existing sync state
-            let existing_sync_state = store.get_sync_state(account_id).ok().flatten();
-            let sync_info = mail::get_sync_state_info(existing_sync_state.as_ref());
-            let has_existing_sync = sync_info.last_sync_at.is_some();
+            if refresh_aliases && history_id.is_some() {
+                let client_for_aliases = client.clone();
+                let store_for_aliases = store.clone();
+                background
+                    .spawn(async move {
+                        if let Err(e) = mail::refresh_send_as_aliases(
+                            &client_for_aliases,
+                            store_for_aliases.as_ref(),
+                            account_id,
+                        ) {
+                            warn!("[SYNC] Failed to fetch aliases for {}: {}", account_id, e);
+                        }
+                    })
+                    .detach();
+            }
 
-            // First sync for this account - clear any stale data
-            if !has_existing_sync {
+            // Labels are cheap to revalidate; the sidebar tree is rebuilt
+            // with the unread counts when the sync finishes
+            if history_id.is_some() {
+                let client_for_labels = client.clone();
+                let store_for_labels = store.clone();
+                background
+                    .spawn(async move {
+                        if let Err(e) = mail::refresh_labels(
+                            &client_for_labels,
+                            store_for_labels.as_ref(),
+                            account_id,
+                        ) {
+                            warn!("[SYNC] Failed to fetch labels for {}: {}", account_id, e);
+                        }
+                    })
+                    .detach();
+            }
+
+            // Check for existing sync state
+            let existing_sync_state = store.get_sync_state(account_id).ok().flatten();
+            let sync_info = mail::get_sync_state_info(existing_sync_state.as_ref());
+            let has_existing_sync = sync_info.last_sync_at.is_some();
+
+            // First sync for this account - clear any stale data
+            if !has_existing_sync {
                 info!("[SYNC] First sync for account {} - clearing account data", account_email);
-                if let Err(e) = store.clear_account_data(account_id) {
+                let search = search_index.as_deref().map(|index| index as &dyn SearchBackend);
+                if let Err(e) = mail::clear_account(store.as_ref(), search, account_id) {
                     warn!("[SYNC] Failed to clear account data: {}", e);
                 }
             }
@@ -1284,12 +2544,28 @@ impl OrionApp {
 
                     let sync_result = background
                         .spawn(async move {
-                            mail::incremental_sync(
-                                &client_for_sync,
+                            match mail::incremental_sync(
+                                client_for_sync.as_ref(),
                                 store_for_sync.as_ref(),
                                 &state_clone,
                                 &options_for_sync,
-                            )
+                            ) {
+                                // Reconcile with the server's message list,
+                                // keeping local mail visible meanwhile
+                                Err(e) if e.downcast_ref::<mail::HistoryExpiredError>().is_some() => {
+                                    info!(
+                                        "[SYNC] Account {} history expired, reconciling with server",
+                                        state_clone.account_id
+                                    );
+                                    mail::reconcile_sync(
+                                        client_for_sync.as_ref(),
+                                        store_for_sync.as_ref(),
+                                        &state_clone,
+                                        &options_for_sync,
+                                    )
+                                }
+                                result => result,
+                            }
                         })
                         .await;
 
@@ -1319,23 +2595,18 @@ impl OrionApp {
                             return;
                         }
                         Err(e) => {
-                            if e.downcast_ref::<mail::HistoryExpiredError>().is_some() {
-                                info!("[SYNC] Account {} history expired, will do full sync", account_email);
-                                let _ = store.delete_sync_state(account_id);
-                            } else {
-                                error!("[SYNC] Account {} incremental sync failed: {}", account_email, e);
-                                cx.update(|cx| {
-                                    this.update(cx, |app, cx| {
-                                        if let Some(state) = app.accounts.get_mut(&account_id) {
-                                            state.is_syncing = false;
-                                            state.sync_error = Some(format!("Sync failed: {}", e));
-                                        }
-                                        cx.notify();
-                                    })
+                            error!("[SYNC] Account {} incremental sync failed: {}", account_email, e);
+                            cx.update(|cx| {
+                                this.update(cx, |app, cx| {
+                                    if let Some(state) = app.accounts.get_mut(&account_id) {
+                                        state.is_syncing = false;
+                                        state.sync_error = Some(format!("Sync failed: {}", e));
+                                    }
+                                    cx.notify();
                                 })
-                                .ok();
-                                return;
-                            }
+                            })
+                            .ok();
+                            return;
                         }
                     }
                 }
@@ -1356,6 +2627,9 @@ impl OrionApp {
             let fetch_done = Arc::new(AtomicBool::new(false));
             let fetch_error: Arc<std::sync::Mutex<Option<String>>> =
                 Arc::new(std::sync::Mutex::new(None));
+            // Set when the fetch stopped at the network budget, leaving the
+            // rest of the initial sync for the next sync
+            let budget_spent = Arc::new(AtomicBool::new(false));
 
             // Fetch phase
             let store_for_fetch = store.clone();
@@ -1363,12 +2637,15 @@ impl OrionApp {
             let options_clone = options.clone();
             let fetch_done_clone = fetch_done.clone();
             let fetch_error_clone = fetch_error.clone();
+            let budget_spent_clone = budget_spent.clone();
 
             background
                 .spawn(async move {
+                    // Metered connection detection may query the system
+                    let options_clone = options_clone.with_network(&network_settings);
                     let mut fetch_stats = SyncStats::default();
-                    match fetch_phase(
-                        &client_clone,
+                    match fetch_all(
+                        client_clone.as_ref(),
                         store_for_fetch.as_ref(),
                         account_id,
                         &options_clone,
@@ -1377,6 +2654,10 @@ impl OrionApp {
                         Ok(_) => {
                             info!("[SYNC] Account {} fetch phase complete", account_id);
                         }
+                        Err(e) if e.downcast_ref::<mail::NetworkBudgetSpentError>().is_some() => {
+                            info!("[SYNC] Account {} fetch stopped: {}", account_id, e);
+                            budget_spent_clone.store(true, Ordering::SeqCst);
+                        }
                         Err(e) => {
                             error!("[SYNC] Account {} fetch phase failed: {}", account_id, e);
                             *fetch_error_clone.lock().unwrap() = Some(e.to_string());
@@ -1432,6 +2713,14 @@ impl OrionApp {
                         stats = updated_stats;
                         let processed = result.processed;
                         let remaining = result.remaining;
+                        push_rule_matches(
+                            &background,
+                            client.clone(),
+                            store.clone(),
+                            notifier.clone(),
+                            account_id,
+                            result.rule_matches,
+                        );
 
                         if processed > 0 {
                             consecutive_empty = 0;
@@ -1439,10 +2728,9 @@ impl OrionApp {
                             if last_ui_update.elapsed() >= ui_debounce_interval {
                                 last_ui_update = std::time::Instant::now();
                                 cx.update(|cx| {
-                                    this.update(cx, |app, cx| {
-                                        if let Some(thread_list) = &app.thread_list_view {
-                                            thread_list.update(cx, |view, cx| view.load_threads(cx));
-                                        }
+                                    // The thread list follows store change notifications,
+                                    // so only the sidebar progress needs a repaint here
+                                    this.update(cx, |_app, cx| {
                                         debug!(
                                             "[SYNC] Account {} processed {} messages, {} remaining",
                                             account_id, processed, remaining
@@ -1499,6 +2787,21 @@ impl OrionApp {
                 }
             }
 
+            // The rest of the mailbox is fetched by the next sync
+            if budget_spent.load(Ordering::SeqCst) {
+                cx.update(|cx| {
+                    this.update(cx, |app, cx| {
+                        if let Some(state) = app.accounts.get_mut(&account_id) {
+                            state.is_syncing = false;
+                            state.sync_error = None;
+                        }
+                        cx.notify();
+                    })
+                })
+                .ok();
+                return;
+            }
+
             // Mark sync complete
             // IMPORTANT: Load existing state to preserve failed_message_ids from fetch_phase
             if let Some(ref history_id) = history_id {
@@ -1528,7 +2831,7 @@ impl OrionApp {
                         state.sync_error = None;
                     }
                     // Also update legacy last_sync_at for UI
-                    app.last_sync_at = Some(chrono::Utc::now());
+                    app.mark_synced();
                     // Refresh thread list
                     if let Some(thread_list) = &app.thread_list_view {
                         thread_list.update(cx, |view, cx| view.load_threads(cx));
@@ -1549,7 +2852,7 @@ impl OrionApp {
     /// When transitioning from unauthenticated to authenticated (first sync after OAuth),
     /// clears the database and search index to start fresh.
     pub fn sync(&mut self, cx: &mut Context<Self>) {
-        use mail::{fetch_phase, process_pending_batch};
+        use mail::process_pending_batch;
         use std::sync::atomic::{AtomicBool, Ordering};
         use std::time::Duration;
 
@@ -1557,6 +2860,16 @@ impl OrionApp {
             return;
         }
 
+        let current_account = self.current_account_id_or_default();
+        if self
+            .accounts
+            .get(&current_account)
+            .is_some_and(|state| !state.account.sync_enabled)
+        {
+            debug!("[SYNC] Account {} is paused", current_account);
+            return;
+        }
+
         let Some(client) = self.gmail_client.clone() else {
             self.sync_error = Some("Gmail client not configured".to_string());
             cx.notify();
@@ -1579,6 +2892,7 @@ impl OrionApp {
 
         let store = self.store.clone();
         let search_index = self.search_index.clone();
+        let notifier = self.notifier.clone();
         let background = cx.background_executor().clone();
         // Use primary account or fallback to 1 for legacy compatibility
         let account_id = self.current_account_id_or_default();
@@ -1685,12 +2999,28 @@ impl OrionApp {
 
                     let sync_result = background
                         .spawn(async move {
-                            mail::incremental_sync(
-                                &client_for_sync,
+                            match mail::incremental_sync(
+                                client_for_sync.as_ref(),
                                 store_for_sync.as_ref(),
                                 &state_clone,
                                 &options_for_sync,
-                            )
+                            ) {
+                                // Reconcile with the server's message list,
+                                // keeping local mail visible meanwhile
+                                Err(e) if e.downcast_ref::<mail::HistoryExpiredError>().is_some() => {
+                                    info!(
+                                        "[SYNC] Account {} history expired, reconciling with server",
+                                        state_clone.account_id
+                                    );
+                                    mail::reconcile_sync(
+                                        client_for_sync.as_ref(),
+                                        store_for_sync.as_ref(),
+                                        &state_clone,
+                                        &options_for_sync,
+                                    )
+                                }
+                                result => result,
+                            }
                         })
                         .await;
 
@@ -1713,7 +3043,7 @@ impl OrionApp {
                             cx.update(|cx| {
                                 this.update(cx, |app, cx| {
                                     app.is_syncing = false;
-                                    app.last_sync_at = Some(Utc::now());
+                                    app.mark_synced();
 
                                     // Reload thread list
                                     if let Some(thread_list) = &app.thread_list_view {
@@ -1735,33 +3065,16 @@ impl OrionApp {
                             return;
                         }
                         Err(e) => {
-                            // Check if it's a history expired error - need full resync
-                            if e.downcast_ref::<mail::HistoryExpiredError>().is_some() {
-                                warn!("[SYNC] History ID expired, falling back to full sync");
-                                // Clear data and continue with full sync below
-                                if let Err(clear_err) = store.clear() {
-                                    error!("[SYNC] Failed to clear store: {}", clear_err);
-                                }
-                                if let Some(ref index) = search_index {
-                                    if let Err(clear_err) = index.clear() {
-                                        error!("[SYNC] Failed to clear search index: {}", clear_err);
-                                    }
-                                }
-                                // Delete sync state to trigger fresh initial sync
-                                let _ = store.delete_sync_state(account_id);
-                            } else {
-                                // Other error - report and stop
-                                error!("[SYNC] Incremental sync failed: {}", e);
-                                cx.update(|cx| {
-                                    this.update(cx, |app, cx| {
-                                        app.sync_error = Some(format!("Sync failed: {}", e));
-                                        app.is_syncing = false;
-                                        cx.notify();
-                                    })
+                            error!("[SYNC] Incremental sync failed: {}", e);
+                            cx.update(|cx| {
+                                this.update(cx, |app, cx| {
+                                    app.sync_error = Some(format!("Sync failed: {}", e));
+                                    app.is_syncing = false;
+                                    cx.notify();
                                 })
-                                .ok();
-                                return;
-                            }
+                            })
+                            .ok();
+                            return;
                         }
                     }
                 }
@@ -1804,8 +3117,8 @@ impl OrionApp {
             background
                 .spawn(async move {
                     let mut fetch_stats = SyncStats::default();
-                    match fetch_phase(
-                        &client_clone,
+                    match fetch_all(
+                        client_clone.as_ref(),
                         store_for_fetch.as_ref(),
                         account_id,
                         &options_clone,
@@ -1873,6 +3186,14 @@ impl OrionApp {
                         stats = updated_stats;
                         let processed = result.processed;
                         let remaining = result.remaining;
+                        push_rule_matches(
+                            &background,
+                            client.clone(),
+                            store.clone(),
+                            notifier.clone(),
+                            account_id,
+                            result.rule_matches,
+                        );
 
                         if processed > 0 {
                             consecutive_empty = 0;
@@ -1880,10 +3201,9 @@ impl OrionApp {
                             if last_ui_update.elapsed() >= ui_debounce_interval {
                                 last_ui_update = std::time::Instant::now();
                                 cx.update(|cx| {
-                                    this.update(cx, |app, cx| {
-                                        if let Some(thread_list) = &app.thread_list_view {
-                                            thread_list.update(cx, |view, cx| view.load_threads(cx));
-                                        }
+                                    // The thread list follows store change notifications,
+                                    // so only the sidebar progress needs a repaint here
+                                    this.update(cx, |_app, cx| {
                                         debug!(
                                             "[SYNC] Processed {} messages, {} remaining",
                                             processed, remaining
@@ -1979,7 +3299,7 @@ impl OrionApp {
             cx.update(|cx| {
                 this.update(cx, |app, cx| {
                     app.is_syncing = false;
-                    app.last_sync_at = Some(Utc::now());
+                    app.mark_synced();
 
                     info!(
                         "Sync complete: {} created, {} skipped",
@@ -2016,11 +3336,26 @@ impl OrionApp {
         let is_syncing =
             self.is_syncing || self.accounts.values().any(|state| state.is_syncing);
         let last_sync = self.last_sync_at;
+        let next_check_secs = self.sync_scheduler.seconds_until_next_check(Utc::now());
+        let index_rebuild_progress = self.index_rebuild_progress;
+        let label_action_progress = self.label_action_progress;
+        let outbox_unsent = self.outbox_unsent;
+        let sync_tooltip = match self.sync_scheduler.last_skip_reason {
+            Some(reason) => format!("{} - click to sync now", reason.description()),
+            None => "Sync now".to_string(),
+        };
 
         // Gather accounts for the account section
-        let accounts: Vec<_> = self.accounts.values().map(|s| s.account.clone()).collect();
+        let accounts: Vec<_> = self
+            .ordered_accounts()
+            .into_iter()
+            .map(|s| s.account.clone())
+            .collect();
         let selected_account = self.selected_account;
         let has_accounts = !accounts.is_empty();
+        let smart_folders = self.smart_folders.clone();
+        let mut nested_labels = Vec::new();
+        visible_label_rows(&self.label_tree, &self.collapsed_labels, 0, &mut nested_labels);
 
         div()
             .flex()
@@ -2091,14 +3426,58 @@ impl OrionApp {
                                 .get(&account_id)
                                 .map(|s| s.is_syncing)
                                 .unwrap_or(false);
+                            let is_paused = !account.sync_enabled;
 
                             div()
                                 .id(ElementId::Name(format!("account-{}", account_id).into()))
+                                .group("account")
+                                .flex()
+                                .items_center()
                                 .on_click(cx.listener(move |app, _event, _window, cx| {
                                     app.set_account_filter(Some(account_id), cx);
                                 }))
                                 .child(
-                                    AccountItem::new(account, is_selected).syncing(is_account_syncing),
+                                    div().flex_1().min_w_0().child(
+                                        AccountItem::new(account, is_selected)
+                                            .syncing(is_account_syncing),
+                                    ),
+                                )
+                                .child(
+                                    div()
+                                        .id(ElementId::Name(
+                                            format!("account-pause-{}", account_id).into(),
+                                        ))
+                                        .px_1()
+                                        .cursor_pointer()
+                                        .invisible()
+                                        .group_hover("account", |s| s.visible())
+                                        .text_xs()
+                                        .text_color(theme.muted_foreground)
+                                        .hover(|s| s.text_color(theme.foreground))
+                                        .on_click(cx.listener(move |app, _event, _window, cx| {
+                                            cx.stop_propagation();
+                                            app.toggle_account_sync(account_id, cx);
+                                        }))
+                                        .child(if is_paused { "Resume" } else { "Pause" }),
+                                )
+                                .child(
+                                    div()
+                                        .id(ElementId::Name(
+                                            format!("account-remove-{}", account_id).into(),
+                                        ))
+                                        .px_1()
+                                        .cursor_pointer()
+                                        .invisible()
+                                        .group_hover("account", |s| s.visible())
+                                        .on_click(cx.listener(move |app, _event, window, cx| {
+                                            cx.stop_propagation();
+                                            app.confirm_remove_account(account_id, window, cx);
+                                        }))
+                                        .child(
+                                            Icon::new(IconName::Close)
+                                                .with_size(ComponentSize::XSmall)
+                                                .text_color(theme.muted_foreground),
+                                        ),
                                 )
                         }))
                         // Add Account button
@@ -2150,7 +3529,202 @@ impl OrionApp {
                                 app.select_label(label_id.clone(), cx);
                             }))
                             .child(crate::components::SidebarItem::new(label, is_selected))
-                    })),
+                    }))
+                    // User labels, nested by name
+                    .when(!nested_labels.is_empty(), |el| {
+                        el.child(
+                            div()
+                                .px_1()
+                                .pt_3()
+                                .pb_1()
+                                .text_xs()
+                                .font_weight(FontWeight::SEMIBOLD)
+                                .text_color(theme.muted_foreground)
+                                .child("LABELS"),
+                        )
+                        .children(nested_labels.into_iter().map(|(depth, is_collapsed, node)| {
+                            let label_id = node.id.0.clone();
+                            let toggle_id = label_id.clone();
+                            let is_selected = label_id == selected;
+                            let text_color = if is_selected {
+                                theme.foreground
+                            } else {
+                                theme.muted_foreground
+                            };
+                            // A collapsed label stands in for its hidden children
+                            let unread_count = if is_collapsed {
+                                node.total_unread_count
+                            } else {
+                                node.unread_count
+                            };
+                            let swatch = node
+                                .color
+                                .as_ref()
+                                .and_then(|color| Rgba::try_from(color.background.as_str()).ok());
+
+                            div()
+                                .id(ElementId::Name(format!("label-{}", label_id).into()))
+                                .pl(px(12.0 + 16.0 * depth as f32))
+                                .pr_3()
+                                .py_1p5()
+                                .my_px()
+                                .rounded_md()
+                                .when(is_selected, |el| el.bg(theme.list_active))
+                                .cursor_pointer()
+                                .hover(|s| s.bg(theme.list_hover))
+                                .flex()
+                                .items_center()
+                                .justify_between()
+                                .gap_2()
+                                .on_click(cx.listener(move |app, _event, _window, cx| {
+                                    app.select_label(label_id.clone(), cx);
+                                }))
+                                .child(
+                                    div()
+                                        .flex()
+                                        .items_center()
+                                        .gap_1()
+                                        .min_w_0()
+                                        .child(
+                                            div()
+                                                .id(ElementId::Name(
+                                                    format!("label-toggle-{}", toggle_id).into(),
+                                                ))
+                                                .w_4()
+                                                .flex_none()
+                                                .when(!node.children.is_empty(), |el| {
+                                                    el.on_click(cx.listener(
+                                                        move |app, _event, _window, cx| {
+                                                            cx.stop_propagation();
+                                                            app.toggle_label_collapsed(
+                                                                toggle_id.clone(),
+                                                                cx,
+                                                            );
+                                                        },
+                                                    ))
+                                                    .child(
+                                                        Icon::new(if is_collapsed {
+                                                            IconName::ChevronRight
+                                                        } else {
+                                                            IconName::ChevronDown
+                                                        })
+                                                        .with_size(ComponentSize::XSmall)
+                                                        .text_color(theme.muted_foreground),
+                                                    )
+                                                }),
+                                        )
+                                        .when_some(swatch, |el, color| {
+                                            el.child(
+                                                div().size_2().flex_none().rounded_full().bg(color),
+                                            )
+                                        })
+                                        .when_some(node.icon.clone(), |el, icon| {
+                                            el.child(div().text_sm().flex_none().child(icon))
+                                        })
+                                        .child(
+                                            div()
+                                                .text_sm()
+                                                .text_color(text_color)
+                                                .truncate()
+                                                .child(node.name),
+                                        ),
+                                )
+                                .when(unread_count > 0, |el| {
+                                    el.child(
+                                        div()
+                                            .text_xs()
+                                            .text_color(theme.muted_foreground)
+                                            .child(format!("{}", unread_count)),
+                                    )
+                                })
+                        }))
+                    })
+                    // Smart folders (pinned saved searches)
+                    .when(!smart_folders.is_empty(), |el| {
+                        el.child(
+                            div()
+                                .px_1()
+                                .pt_3()
+                                .pb_1()
+                                .text_xs()
+                                .font_weight(FontWeight::SEMIBOLD)
+                                .text_color(theme.muted_foreground)
+                                .child("SMART FOLDERS"),
+                        )
+                        .children(smart_folders.into_iter().map(|folder| {
+                            let id = folder.search.id;
+                            let query = folder.search.query.clone();
+
+                            div()
+                                .id(ElementId::Name(format!("smart-folder-{}", id).into()))
+                                .group("smart-folder")
+                                .px_3()
+                                .py_1p5()
+                                .my_px()
+                                .rounded_md()
+                                .cursor_pointer()
+                                .hover(|s| s.bg(theme.list_hover))
+                                .flex()
+                                .items_center()
+                                .justify_between()
+                                .gap_2()
+                                .on_click(cx.listener(move |app, _event, window, cx| {
+                                    app.open_saved_search(query.clone(), window, cx);
+                                }))
+                                .child(
+                                    div()
+                                        .flex()
+                                        .items_center()
+                                        .gap_2()
+                                        .min_w_0()
+                                        .child(
+                                            Icon::new(IconName::Search)
+                                                .with_size(ComponentSize::Small)
+                                                .text_color(theme.muted_foreground),
+                                        )
+                                        .child(
+                                            div()
+                                                .text_sm()
+                                                .text_color(theme.muted_foreground)
+                                                .truncate()
+                                                .child(folder.search.name),
+                                        ),
+                                )
+                                .child(
+                                    div()
+                                        .flex()
+                                        .items_center()
+                                        .gap_1()
+                                        .when(folder.count > 0, |el| {
+                                            el.child(
+                                                div()
+                                                    .text_xs()
+                                                    .text_color(theme.muted_foreground)
+                                                    .child(format!("{}", folder.count)),
+                                            )
+                                        })
+                                        .child(
+                                            div()
+                                                .id(ElementId::Name(
+                                                    format!("smart-folder-remove-{}", id).into(),
+                                                ))
+                                                .invisible()
+                                                .group_hover("smart-folder", |s| s.visible())
+                                                .on_click(cx.listener(
+                                                    move |app, _event, _window, cx| {
+                                                        cx.stop_propagation();
+                                                        app.delete_saved_search(id, cx);
+                                                    },
+                                                ))
+                                                .child(
+                                                    Icon::new(IconName::Close)
+                                                        .with_size(ComponentSize::XSmall)
+                                                        .text_color(theme.muted_foreground),
+                                                ),
+                                        ),
+                                )
+                        }))
+                    }),
             )
             // Sidebar footer with sync and profile
             .child(
@@ -2168,11 +3742,36 @@ impl OrionApp {
                             .items_center()
                             .justify_between()
                             .child(
-                                div().text_xs().text_color(theme.muted_foreground).child(
-                                    last_sync
-                                        .map(|ts| format_relative_time(ts))
-                                        .unwrap_or_else(|| "Not synced".to_string()),
-                                ),
+                                div()
+                                    .flex()
+                                    .flex_col()
+                                    .text_xs()
+                                    .text_color(theme.muted_foreground)
+                                    .child(
+                                        last_sync
+                                            .map(|ts| format_relative_time(ts))
+                                            .unwrap_or_else(|| "Not synced".to_string()),
+                                    )
+                                    .when_some(
+                                        next_check_secs.filter(|_| !is_syncing),
+                                        |el, secs| {
+                                            el.child(format!("Next check in {}", format_countdown(secs)))
+                                        },
+                                    )
+                                    .when_some(index_rebuild_progress, |el, (done, total)| {
+                                        el.child(format!("Rebuilding search index {}/{}", done, total))
+                                    })
+                                    .when_some(label_action_progress, |el, (done, total)| {
+                                        el.child(format!("Updating threads {}/{}", done, total))
+                                    })
+                                    .when(outbox_unsent.0 > 0, |el| {
+                                        let (unsent, failed) = outbox_unsent;
+                                        el.child(if failed > 0 {
+                                            format!("{} unsent in Outbox, {} failed", unsent, failed)
+                                        } else {
+                                            format!("{} unsent in Outbox", unsent)
+                                        })
+                                    }),
                             )
                             .child(
                                 Button::new("sync-button")
@@ -2183,6 +3782,7 @@ impl OrionApp {
                                     .small()
                                     .ghost()
                                     .loading(is_syncing)
+                                    .tooltip(sync_tooltip)
                                     .cursor_pointer()
                                     .on_click(cx.listener(|app, _event, _window, cx| {
                                         app.sync_all_accounts(cx);
@@ -2192,151 +3792,1104 @@ impl OrionApp {
             )
     }
 
-    fn render_content(
-        &mut self,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) -> impl IntoElement + use<> {
-        // Extract theme colors upfront before any mutable borrows
-        let theme = cx.theme();
-        let bg = theme.background;
-        let muted_fg = theme.muted_foreground;
+    /// Banners for accounts whose token was revoked, each with a Reconnect button
+    fn render_reauth_banners(&self, cx: &mut Context<Self>) -> impl IntoElement + use<> {
+        let theme = cx.theme();
+        let (bg, fg) = (theme.warning, theme.warning_foreground);
+
+        let mut accounts: Vec<(i64, String)> = self
+            .accounts
+            .values()
+            .filter(|state| state.health == AccountHealth::NeedsReauth)
+            .map(|state| (state.account.id, state.account.email.clone()))
+            .collect();
+        accounts.sort();
+
+        div()
+            .flex()
+            .flex_col()
+            .children(accounts.into_iter().map(|(account_id, email)| {
+                div()
+                    .w_full()
+                    .px_4()
+                    .py_2()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .bg(bg)
+                    .text_sm()
+                    .text_color(fg)
+                    .child(format!("{} was signed out. Reconnect to resume syncing.", email))
+                    .child(
+                        Button::new(ElementId::Name(format!("reconnect-{}", account_id).into()))
+                            .label("Reconnect")
+                            .small()
+                            .cursor_pointer()
+                            .on_click(cx.listener(move |app, _event, _window, cx| {
+                                app.reconnect_account(account_id, cx);
+                            })),
+                    )
+            }))
+    }
+
+    fn render_content(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement + use<> {
+        // Extract theme colors upfront before any mutable borrows
+        let theme = cx.theme();
+        let muted_fg = theme.muted_foreground;
+        let border = theme.border;
+
+        // Extract data from current_view before any mutable borrows
+        let (html_content, thread_entity, is_search) = match &self.current_view {
+            View::Inbox => (None, None, false),
+            View::Thread { html, .. } => (Some(html.clone()), self.thread_view.clone(), false),
+            View::Search => (None, None, true),
+        };
+
+        // Search results view
+        if is_search {
+            if let Some(search_results) = &self.search_results_view {
+                return search_results.clone().into_any_element();
+            } else {
+                return div()
+                    .text_color(muted_fg)
+                    .child("Search not available")
+                    .into_any_element();
+            }
+        }
+
+        // Inbox view
+        if html_content.is_none() {
+            let Some(thread_list) = self.thread_list_view.clone() else {
+                return div()
+                    .text_color(muted_fg)
+                    .child("Loading...")
+                    .into_any_element();
+            };
+            if !self.layout.is_split() {
+                return thread_list.into_any_element();
+            }
+
+            // Split layout: the list stays visible beside a preview pane
+            self.sync_preview(&thread_list, cx);
+            let preview = match &self.preview {
+                Some(preview) => {
+                    let (view, html) = (preview.view.clone(), preview.html.clone());
+                    self.render_thread_pane(view, html, window, cx)
+                }
+                None => div()
+                    .size_full()
+                    .flex()
+                    .items_center()
+                    .justify_center()
+                    .text_sm()
+                    .text_color(muted_fg)
+                    .child("Select a thread to read")
+                    .into_any_element(),
+            };
+            let container = match self.layout.split {
+                SplitMode::Horizontal => div().flex().flex_col().size_full().child(
+                    div()
+                        .w_full()
+                        .h(relative(0.4))
+                        .border_b_1()
+                        .border_color(border)
+                        .child(thread_list),
+                ),
+                _ => div().flex().flex_row().size_full().child(
+                    div()
+                        .h_full()
+                        .w(relative(0.4))
+                        .border_r_1()
+                        .border_color(border)
+                        .child(thread_list),
+                ),
+            };
+            return container
+                .child(div().flex_1().min_w_0().min_h_0().child(preview))
+                .into_any_element();
+        }
+
+        // Thread view - always use WebView
+        let html = html_content.unwrap();
+        if let Some(thread) = thread_entity {
+            self.render_thread_pane(thread, html, window, cx)
+        } else {
+            div()
+                .text_color(muted_fg)
+                .child("Loading thread...")
+                .into_any_element()
+        }
+    }
+
+    /// Render a thread header above the shared WebView showing `html`
+    fn render_thread_pane(
+        &mut self,
+        thread: Entity<ThreadView>,
+        html: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        let bg = cx.theme().background;
+        let webview = self.get_or_create_webview(window, cx);
+
+        // Only reload HTML if content has changed (avoids re-render on scroll)
+        let needs_reload = self
+            .webview_loaded_html
+            .as_ref()
+            .map(|loaded| loaded != &html)
+            .unwrap_or(true);
+
+        if needs_reload {
+            info!("Loading HTML into WebView ({} bytes)", html.len());
+            webview.update(cx, |wv, _| {
+                let _ = wv.load_html(&html);
+                wv.show();
+            });
+            self.webview_loaded_html = Some(html.clone());
+        }
+
+        let thread_id = thread.read(cx).thread_id().clone();
+        let reply_box = self.reply_box_for(&thread_id, window, cx);
+
+        // Render thread header + WebView container + quick reply
+        div()
+            .flex()
+            .flex_col()
+            .size_full()
+            .bg(bg)
+            .child(thread) // ThreadView renders header only
+            .child(
+                div()
+                    .id("webview-container")
+                    .flex_1()
+                    .w_full()
+                    .min_h_0()
+                    .p_4() // Match native card padding
+                    .child(webview),
+            )
+            .children(reply_box)
+            .into_any_element()
+    }
+
+    /// The quick reply box for a thread, created when the thread is first shown
+    ///
+    /// Replies go to the latest message from someone else, from the account
+    /// that received it. Threads with nothing to reply to get no box.
+    fn reply_box_for(
+        &mut self,
+        thread_id: &ThreadId,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<Entity<ReplyBox>> {
+        if let Some(reply_box) = &self.reply_box
+            && reply_box.read(cx).thread_id() == thread_id
+        {
+            return Some(reply_box.clone());
+        }
+        self.reply_box = None;
+
+        let detail = mail::get_thread_detail(self.store.as_ref(), thread_id)
+            .ok()
+            .flatten()?;
+        let original = detail
+            .messages
+            .iter()
+            .rev()
+            .find(|message| !message.label_ids.iter().any(|label| label == LabelId::SENT))
+            .or(detail.messages.last())?
+            .clone();
+        let account_state = self.accounts.get(&original.account_id)?;
+        let account = account_state.account.clone();
+        let action_handler = account_state.action_handler.clone();
+
+        let reply_box = cx.new(|cx| ReplyBox::new(account, action_handler, original, window, cx));
+        cx.subscribe(&reply_box, |app, _, event, cx| match event {
+            // Pull the sent reply into the thread
+            ReplyBoxEvent::Sent { account_id } => app.sync_account(*account_id, cx),
+        })
+        .detach();
+        self.reply_box = Some(reply_box.clone());
+        Some(reply_box)
+    }
+}
+
+/// Mirror label changes made by mail rules during sync on the Gmail server,
+/// and raise notifications for matches that asked for one
+///
+/// Messages are already relabeled locally, so this runs detached and only
+/// logs failures.
+fn push_rule_matches(
+    background: &BackgroundExecutor,
+    client: Arc<GmailClient>,
+    store: Arc<dyn MailStore>,
+    notifier: Arc<Notifier>,
+    account_id: i64,
+    rule_matches: Vec<RuleMatch>,
+) {
+    if rule_matches.is_empty() {
+        return;
+    }
+    let notify = rule_matches.iter().filter(|m| m.notify).count();
+    if notify > 0 {
+        info!("[RULES] Account {} has {} new messages from VIP rules", account_id, notify);
+    }
+    background
+        .spawn(async move {
+            notifier.notify_matches(store.as_ref(), account_id, &rule_matches);
+            if let Err(e) = push_rule_changes(&client, &rule_matches) {
+                warn!("[RULES] Account {} failed to apply rule changes: {}", account_id, e);
+            }
+        })
+        .detach();
+}
+
+/// Run the fetch phase until every message is listed
+///
+/// The fetch pauses when the pending queue is full; the process loop running
+/// alongside drains it, so a pause just means fetching again.
+fn fetch_all(
+    client: &GmailClient,
+    store: &dyn MailStore,
+    account_id: i64,
+    options: &SyncOptions,
+    stats: &mut SyncStats,
+) -> anyhow::Result<FetchPhaseStats> {
+    let mut total = FetchPhaseStats::default();
+    loop {
+        let fetched = mail::fetch_phase(client, store, account_id, options, stats)?;
+        total.fetched += fetched.fetched;
+        total.pending += fetched.pending;
+        total.skipped += fetched.skipped;
+        total.failed_ids.extend(fetched.failed_ids);
+        if !fetched.backlogged {
+            return Ok(total);
+        }
+    }
+}
+
+/// Folder for PDF exports and saved attachments: Downloads if there is one
+fn export_directory() -> std::path::PathBuf {
+    let home = std::env::var_os("HOME")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_default();
+    let downloads = home.join("Downloads");
+    if downloads.is_dir() { downloads } else { home }
+}
+
+/// Scratch folder attachments are downloaded to for previewing
+fn attachment_cache_dir() -> std::path::PathBuf {
+    std::env::temp_dir().join("orion-attachments")
+}
+
+/// Suggested file name for a thread's PDF, made from its subject
+fn pdf_file_name(subject: &str) -> String {
+    let name: String = subject
+        .chars()
+        .map(|c| if c.is_alphanumeric() || " -_".contains(c) { c } else { '_' })
+        .take(60)
+        .collect();
+    let name = name.trim();
+    if name.is_empty() {
+        "Thread.pdf".to_string()
+    } else {
+        format!("{}.pdf", name)
+    }
+}
+
+/// Flatten the label tree into sidebar rows of depth, whether the label is
+/// collapsed, and the label, skipping the children of collapsed labels
+fn visible_label_rows(
+    nodes: &[LabelNode],
+    collapsed: &HashSet<String>,
+    depth: usize,
+    rows: &mut Vec<(usize, bool, LabelNode)>,
+) {
+    for node in nodes {
+        let is_collapsed = collapsed.contains(node.id.as_str());
+        rows.push((depth, is_collapsed, node.clone()));
+        if !is_collapsed {
+            visible_label_rows(&node.children, collapsed, depth + 1, rows);
+        }
+    }
+}
+
+/// Format a timestamp as a relative time string (e.g., "5 minutes ago")
+/// Format a countdown in seconds as "42s" or "1m 05s"
+fn format_countdown(secs: u64) -> String {
+    if secs < 60 {
+        format!("{}s", secs)
+    } else {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    }
+}
+
+fn format_relative_time(ts: DateTime<Utc>) -> String {
+    let now = Utc::now();
+    let duration = now.signed_duration_since(ts);
+
+    if duration.num_seconds() < 60 {
+        "Just now".to_string()
+    } else if duration.num_minutes() < 60 {
+        let mins = duration.num_minutes();
+        if mins == 1 {
+            "1 minute ago".to_string()
+        } else {
+            format!("{} minutes ago", mins)
+        }
+    } else if duration.num_hours() < 24 {
+        let hours = duration.num_hours();
+        if hours == 1 {
+            "1 hour ago".to_string()
+        } else {
+            format!("{} hours ago", hours)
+        }
+    } else {
+        // Show as local date/time
+        let local: DateTime<Local> = ts.into();
+        local.format("%b %d at %H:%M").to_string()
+    }
+}
+
+impl OrionApp {
+    fn handle_focus_search(
+        &mut self,
+        _: &FocusSearch,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.focus_search(window, cx);
+    }
+
+    fn handle_show_shortcuts(
+        &mut self,
+        _: &ShowShortcuts,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.show_shortcuts_help = !self.show_shortcuts_help;
+        cx.notify();
+    }
+
+    fn handle_show_command_palette(
+        &mut self,
+        _: &ShowCommandPalette,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.command_palette.is_some() {
+            self.close_command_palette(window, cx);
+        } else {
+            self.open_command_palette(window, cx);
+        }
+    }
+
+    fn handle_sync_now(&mut self, _: &SyncNow, _window: &mut Window, cx: &mut Context<Self>) {
+        self.sync_all_accounts(cx);
+    }
+
+    fn handle_compose(&mut self, _: &Compose, _window: &mut Window, cx: &mut Context<Self>) {
+        self.open_compose(cx);
+    }
+
+    /// Filter to the nth account in sidebar order, or all accounts for 0
+    fn handle_switch_to_account(
+        &mut self,
+        action: &SwitchToAccount,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let account_id = match action.0 {
+            0 => None,
+            n => match self.ordered_accounts().get(n - 1) {
+                Some(state) => Some(state.account.id),
+                None => return,
+            },
+        };
+        self.set_account_filter(account_id, cx);
+    }
+
+    fn handle_use_system_theme(
+        &mut self,
+        _: &UseSystemTheme,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_theme_preference(ThemePreference::System, cx);
+    }
+
+    fn handle_use_light_theme(
+        &mut self,
+        _: &UseLightTheme,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_theme_preference(ThemePreference::Light, cx);
+    }
+
+    fn handle_use_dark_theme(
+        &mut self,
+        _: &UseDarkTheme,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_theme_preference(ThemePreference::Dark, cx);
+    }
+
+    fn handle_cycle_row_density(
+        &mut self,
+        _: &CycleRowDensity,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.update_display(DisplayConfig::cycle_density, cx);
+    }
+
+    fn handle_cycle_snippet_lines(
+        &mut self,
+        _: &CycleSnippetLines,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.update_display(DisplayConfig::cycle_snippet_lines, cx);
+    }
+
+    fn handle_cycle_date_format(
+        &mut self,
+        _: &CycleDateFormat,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.update_display(DisplayConfig::cycle_date_format, cx);
+    }
+
+    fn handle_toggle_avatars(
+        &mut self,
+        _: &ToggleAvatars,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.update_display(|display| display.show_avatars = !display.show_avatars, cx);
+    }
+
+    fn handle_toggle_message_counts(
+        &mut self,
+        _: &ToggleMessageCounts,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.update_display(
+            |display| display.show_message_counts = !display.show_message_counts,
+            cx,
+        );
+    }
+
+    fn handle_toggle_conversation_view(
+        &mut self,
+        _: &ToggleConversationView,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(thread_list) = &self.thread_list_view {
+            thread_list.update(cx, |view, cx| view.toggle_conversation_view(false, cx));
+        }
+    }
+
+    fn handle_toggle_conversation_view_for_label(
+        &mut self,
+        _: &ToggleConversationViewForLabel,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(thread_list) = &self.thread_list_view {
+            thread_list.update(cx, |view, cx| view.toggle_conversation_view(true, cx));
+        }
+    }
+
+    /// Change thread list display preferences and save them
+    fn update_display(&mut self, change: impl FnOnce(&mut DisplayConfig), cx: &mut Context<Self>) {
+        let Some(thread_list) = &self.thread_list_view else {
+            return;
+        };
+        thread_list.update(cx, |view, cx| {
+            let mut display = view.display();
+            change(&mut display);
+            display.save();
+            view.set_display(display, cx);
+        });
+    }
+
+    /// Switch to a theme and remember the choice
+    fn set_theme_preference(&mut self, theme: ThemePreference, cx: &mut Context<Self>) {
+        self.appearance.theme = theme;
+        self.appearance.save();
+        self.apply_appearance(cx);
+    }
+
+    /// Follow OS light/dark changes while the theme is set to match them
+    pub fn follow_system_appearance(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        cx.observe_window_appearance(window, |app, _window, cx| {
+            if app.appearance.theme == ThemePreference::System {
+                app.apply_appearance(cx);
+            }
+        })
+        .detach();
+    }
+
+    /// Apply edits to the sync and theme settings without a restart
+    pub fn watch_config_files(&mut self, cx: &mut Context<Self>) {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+        let schedule_sender = sender.clone();
+        match mail::watch_sync_schedule(self.sync_schedule, move |schedule| {
+            let _ = schedule_sender.send(ConfigChange::SyncSchedule(schedule));
+        }) {
+            Ok(watcher) => self.config_watchers.push(watcher),
+            Err(e) => warn!("Not watching sync settings: {:#}", e),
+        }
+        match config::watch_config(APPEARANCE_CONFIG_FILE, move || {
+            let _ = sender.send(ConfigChange::Appearance(AppearanceConfig::load()));
+        }) {
+            Ok(watcher) => self.config_watchers.push(watcher),
+            Err(e) => warn!("Not watching appearance settings: {:#}", e),
+        }
+
+        cx.spawn(async move |this, cx| {
+            while let Some(change) = receiver.recv().await {
+                let applied = cx.update(|cx| {
+                    this.update(cx, |app, cx| app.apply_config_change(change, cx))
+                });
+                if !matches!(applied, Ok(Ok(()))) {
+                    break;
+                }
+            }
+        })
+        .detach();
+    }
+
+    fn apply_config_change(&mut self, change: ConfigChange, cx: &mut Context<Self>) {
+        match change {
+            ConfigChange::SyncSchedule(schedule) => {
+                info!(
+                    "Sync settings changed (cooldown: {}s, poll interval: {}s)",
+                    schedule.cooldown_secs, schedule.poll_interval_secs
+                );
+                let poll_changed =
+                    schedule.poll_interval_secs != self.sync_schedule.poll_interval_secs;
+                self.sync_schedule = schedule;
+                self.sync_scheduler.set_cooldown(schedule.cooldown_secs);
+                // The daemon polls on its own schedule while connected
+                if poll_changed && self.poll_task.is_some() {
+                    self.start_polling(cx);
+                }
+                cx.notify();
+            }
+            ConfigChange::Appearance(appearance) => {
+                // Saving a theme choice from the UI also lands here
+                if appearance != self.appearance {
+                    self.appearance = appearance;
+                    self.apply_appearance(cx);
+                }
+            }
+        }
+    }
+
+    /// Apply the theme settings, restyling thread HTML if light/dark changed
+    fn apply_appearance(&mut self, cx: &mut Context<Self>) {
+        if !self.appearance.apply(cx) {
+            return;
+        }
+
+        // Theme colors are baked into the generated HTML, so regenerate it;
+        // the WebView reloads on the next render because the HTML differs
+        if let View::Thread { thread_id, .. } = &self.current_view {
+            let thread_id = thread_id.clone();
+            let highlight_terms = self.highlight_terms(cx);
+            let html = self.thread_html(&thread_id, &highlight_terms, cx);
+            self.current_view = View::Thread { html, thread_id };
+        }
+        if let Some(thread_id) = self.preview.as_ref().map(|p| p.thread_id.clone()) {
+            let html = self.thread_html(&thread_id, &[], cx);
+            if let Some(preview) = &mut self.preview {
+                preview.html = html;
+            }
+        }
+        cx.notify();
+    }
+
+    /// Open a compose window sending from the current account
+    ///
+    /// Every call opens another window, so several messages can be in
+    /// progress at once; each saves to its own draft.
+    pub fn open_compose(&mut self, cx: &mut Context<Self>) {
+        let Some(account_state) = self
+            .current_account_id()
+            .and_then(|id| self.accounts.get(&id))
+        else {
+            warn!("No account to compose from");
+            return;
+        };
+        let account = account_state.account.clone();
+        let action_handler = account_state.action_handler.clone();
+        let search_index = self.search_index.clone();
 
-        // Extract data from current_view before any mutable borrows
-        let (html_content, thread_entity, is_search) = match &self.current_view {
-            View::Inbox => (None, None, false),
-            View::Thread { html, .. } => (Some(html.clone()), self.thread_view.clone(), false),
-            View::Search => (None, None, true),
+        let (width, height) = COMPOSE_WINDOW_SIZE;
+        let window_options = WindowOptions {
+            window_bounds: Some(WindowBounds::Windowed(Bounds::centered(
+                None,
+                size(px(width), px(height)),
+                cx,
+            ))),
+            ..Default::default()
         };
+        let result = cx.open_window(window_options, |window, cx| {
+            let compose =
+                cx.new(|cx| ComposeView::new(account, action_handler, search_index, window, cx));
+            cx.new(|cx| Root::new(compose, window, cx))
+        });
+        if let Err(e) = result {
+            error!("Failed to open compose window: {}", e);
+        }
+    }
 
-        // Search results view
-        if is_search {
-            if let Some(search_results) = &self.search_results_view {
-                return search_results.clone().into_any_element();
-            } else {
-                return div()
-                    .text_color(muted_fg)
-                    .child("Search not available")
-                    .into_any_element();
-            }
+    /// Open the command palette with the current set of commands
+    fn open_command_palette(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.palette_return_focus = window.focused(cx);
+
+        let commands = self.palette_commands(cx);
+        let palette = cx.new(|cx| CommandPalette::new(commands, window, cx));
+        cx.subscribe_in(&palette, window, Self::handle_command_palette_event)
+            .detach();
+        palette.update(cx, |view, cx| view.focus(window, cx));
+        self.command_palette = Some(palette);
+        cx.notify();
+    }
+
+    /// Close the command palette and give focus back
+    fn close_command_palette(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.command_palette = None;
+        if let Some(focus) = self.palette_return_focus.take() {
+            window.focus(&focus);
         }
+        // The overlay hid the webview; bring it back if a thread is still showing
+        let thread_showing = match self.current_view {
+            View::Thread { .. } => true,
+            View::Inbox => self.layout.is_split() && self.preview.is_some(),
+            View::Search => false,
+        };
+        if thread_showing && let Some(webview) = &self.webview {
+            webview.update(cx, |wv, _| wv.show());
+        }
+        cx.notify();
+    }
 
-        // Inbox view
-        if html_content.is_none() {
-            if let Some(thread_list) = &self.thread_list_view {
-                return thread_list.clone().into_any_element();
-            } else {
-                return div()
-                    .text_color(muted_fg)
-                    .child("Loading...")
-                    .into_any_element();
+    /// Threads to label: the checked threads in the list, else the thread
+    /// being read, else the selected one
+    fn label_targets(&self, cx: &App) -> Vec<(i64, ThreadId)> {
+        let list = match self.current_view {
+            View::Inbox => self.thread_list_view.as_ref().map(|list| list.read(cx)),
+            _ => None,
+        };
+        if let Some(list) = list {
+            let checked = list.checked_threads();
+            if !checked.is_empty() {
+                return checked;
             }
         }
+        let thread_id = self
+            .shown_thread_id()
+            .or_else(|| list.and_then(|list| list.selected_thread_id()));
+        thread_id
+            .and_then(|id| self.store.get_thread(id).ok().flatten())
+            .map(|thread| vec![(thread.account_id, thread.id)])
+            .unwrap_or_default()
+    }
 
-        // Thread view - always use WebView
-        let html = html_content.unwrap();
-        if let Some(thread) = thread_entity {
-            let webview = self.get_or_create_webview(window, cx);
-
-            // Only reload HTML if content has changed (avoids re-render on scroll)
-            let needs_reload = self
-                .webview_loaded_html
-                .as_ref()
-                .map(|loaded| loaded != &html)
-                .unwrap_or(true);
-
-            if needs_reload {
-                info!("Loading HTML into WebView ({} bytes)", html.len());
-                webview.update(cx, |wv, _| {
-                    let _ = wv.load_html(&html);
-                    wv.show();
-                });
-                self.webview_loaded_html = Some(html.clone());
+    fn handle_edit_labels(&mut self, _: &EditLabels, window: &mut Window, cx: &mut Context<Self>) {
+        let threads = self.label_targets(cx);
+        if threads.is_empty() {
+            return;
+        }
+
+        let thread_labels: Vec<Vec<String>> = threads
+            .iter()
+            .map(|(_, thread_id)| {
+                get_thread_label_ids(self.store.as_ref(), thread_id).unwrap_or_else(|e| {
+                    warn!("Failed to load labels for {}: {}", thread_id.as_str(), e);
+                    Vec::new()
+                })
+            })
+            .collect();
+        let options = self
+            .labels
+            .iter()
+            .filter(|label| !UNASSIGNABLE_LABELS.contains(&label.id.as_str()))
+            .map(|label| {
+                let count = thread_labels
+                    .iter()
+                    .filter(|ids| ids.contains(&label.id.0))
+                    .count();
+                let state = if count == 0 {
+                    LabelState::NotApplied
+                } else if count == threads.len() {
+                    LabelState::Applied
+                } else {
+                    LabelState::Mixed
+                };
+                LabelOption {
+                    id: label.id.0.clone(),
+                    name: label.name.clone(),
+                    state,
+                }
+            })
+            .collect();
+
+        self.label_picker_return_focus = window.focused(cx);
+        let thread_count = threads.len();
+        let picker = cx.new(|cx| LabelPicker::new(options, thread_count, window, cx));
+        cx.subscribe_in(&picker, window, Self::handle_label_picker_event)
+            .detach();
+        picker.update(cx, |view, cx| view.focus(window, cx));
+        self.label_picker = Some(picker);
+        self.label_picker_threads = threads;
+        cx.notify();
+    }
+
+    /// Apply a label toggled in the picker, or close it
+    fn handle_label_picker_event(
+        &mut self,
+        _: &Entity<LabelPicker>,
+        event: &LabelPickerEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            LabelPickerEvent::Toggle { label_id, apply } => {
+                let action = if *apply {
+                    BulkAction::AddLabel(label_id.clone())
+                } else {
+                    BulkAction::RemoveLabel(label_id.clone())
+                };
+                self.bulk_modify_threads(self.label_picker_threads.clone(), action, cx);
             }
+            LabelPickerEvent::Closed => self.close_label_picker(window, cx),
+        }
+    }
 
-            // Render thread header + WebView container
-            div()
-                .flex()
-                .flex_col()
-                .size_full()
-                .bg(bg)
-                .child(thread) // ThreadView renders header only
-                .child(
-                    div()
-                        .id("webview-container")
-                        .flex_1()
-                        .w_full()
-                        .min_h_0()
-                        .p_4() // Match native card padding
-                        .child(webview),
-                )
-                .into_any_element()
-        } else {
-            div()
-                .text_color(muted_fg)
-                .child("Loading thread...")
-                .into_any_element()
+    /// Close the label picker and give focus back
+    fn close_label_picker(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.label_picker = None;
+        self.label_picker_threads.clear();
+        if let Some(focus) = self.label_picker_return_focus.take() {
+            window.focus(&focus);
+        }
+        if self.shown_thread_id().is_some()
+            && let Some(webview) = &self.webview
+        {
+            webview.update(cx, |wv, _| wv.show());
         }
+        cx.notify();
     }
-}
 
-/// Format a timestamp as a relative time string (e.g., "5 minutes ago")
-fn format_relative_time(ts: DateTime<Utc>) -> String {
-    let now = Utc::now();
-    let duration = now.signed_duration_since(ts);
+    /// Everything the command palette offers: registered actions, user
+    /// labels, account switching and recent threads
+    fn palette_commands(&self, cx: &App) -> Vec<Command> {
+        let mut commands = action_commands(cx);
+
+        // System labels already have go-to actions
+        let user_labels = self.labels.iter().filter(|label| !label.is_system);
+        commands.extend(user_labels.map(|label| {
+            Command::new(
+                format!("Go to {}", label.name),
+                "Label",
+                CommandTarget::GoToLabel(label.id.0.clone()),
+            )
+        }));
 
-    if duration.num_seconds() < 60 {
-        "Just now".to_string()
-    } else if duration.num_minutes() < 60 {
-        let mins = duration.num_minutes();
-        if mins == 1 {
-            "1 minute ago".to_string()
-        } else {
-            format!("{} minutes ago", mins)
+        if self.accounts.len() > 1 {
+            commands.push(Command::new(
+                "Switch to all accounts",
+                "Account",
+                CommandTarget::SwitchAccount(None),
+            ));
+            let mut accounts: Vec<&Account> =
+                self.accounts.values().map(|state| &state.account).collect();
+            accounts.sort_by_key(|account| account.id);
+            commands.extend(accounts.into_iter().map(|account| {
+                Command::new(
+                    format!("Switch to {}", account.email),
+                    "Account",
+                    CommandTarget::SwitchAccount(Some(account.id)),
+                )
+            }));
         }
-    } else if duration.num_hours() < 24 {
-        let hours = duration.num_hours();
-        if hours == 1 {
-            "1 hour ago".to_string()
-        } else {
-            format!("{} hours ago", hours)
+
+        match self.store.list_threads_for_account(
+            self.selected_account,
+            PALETTE_RECENT_THREADS,
+            0,
+        ) {
+            Ok(threads) => commands.extend(threads.into_iter().map(|thread| {
+                Command::new(thread.subject, "Thread", CommandTarget::OpenThread(thread.id))
+            })),
+            Err(e) => warn!("Failed to list recent threads for command palette: {}", e),
         }
-    } else {
-        // Show as local date/time
-        let local: DateTime<Local> = ts.into();
-        local.format("%b %d at %H:%M").to_string()
+
+        commands
     }
-}
 
-impl OrionApp {
-    fn handle_focus_search(
+    /// Run or dismiss a command chosen in the palette
+    fn handle_command_palette_event(
         &mut self,
-        _: &FocusSearch,
+        _: &Entity<CommandPalette>,
+        event: &CommandPaletteEvent,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        self.focus_search(window, cx);
+        // Restore focus first so actions reach the view the user was in
+        self.close_command_palette(window, cx);
+
+        let CommandPaletteEvent::Run(target) = event else {
+            return;
+        };
+        match target.clone() {
+            CommandTarget::Action(action) => window.dispatch_action(action, cx),
+            CommandTarget::GoToLabel(label_id) => self.select_label(label_id, cx),
+            CommandTarget::SwitchAccount(account_id) => self.set_account_filter(account_id, cx),
+            CommandTarget::OpenThread(thread_id) => self.show_thread(thread_id, cx),
+        }
     }
 
-    fn handle_show_shortcuts(
+    /// Handler that can fetch from `account_id`, falling back to the primary
+    fn handler_for_account(&self, account_id: i64) -> Option<Arc<ActionHandler>> {
+        self.accounts
+            .get(&account_id)
+            .map(|state| state.action_handler.clone())
+            .or_else(|| self.action_handler.clone())
+    }
+
+    /// Download an attachment and preview it
+    ///
+    /// Images show in an overlay; PDFs and everything else open in the
+    /// system viewer once downloaded.
+    pub fn preview_attachment(
         &mut self,
-        _: &ShowShortcuts,
-        _window: &mut Window,
+        account_id: i64,
+        message_id: MessageId,
+        filename: String,
         cx: &mut Context<Self>,
     ) {
-        self.show_shortcuts_help = !self.show_shortcuts_help;
+        let Some(handler) = self.handler_for_account(account_id) else {
+            warn!("Cannot preview attachment: no handler for account {}", account_id);
+            return;
+        };
+        self.attachment_preview = Some(AttachmentPreview::Loading {
+            filename: filename.clone(),
+        });
+        cx.notify();
+
+        let background = cx.background_executor().clone();
+        cx.spawn(async move |this, cx| {
+            let download_name = filename.clone();
+            let result = background
+                .spawn(async move {
+                    let attachment = handler.download_attachment(&message_id, &download_name)?;
+                    // One folder per message, emptied first so previews don't pile up
+                    let dir = attachment_cache_dir().join(message_id.as_str());
+                    let _ = std::fs::remove_dir_all(&dir);
+                    let path = attachment.save_in(&dir)?;
+                    anyhow::Ok((attachment.is_image(), path))
+                })
+                .await;
+
+            cx.update(|cx| {
+                this.update(cx, |app, cx| {
+                    // Ignore downloads whose preview was closed or replaced
+                    let still_loading = matches!(
+                        &app.attachment_preview,
+                        Some(AttachmentPreview::Loading { filename: f }) if *f == filename
+                    );
+                    if !still_loading {
+                        return;
+                    }
+                    match result {
+                        Ok((true, path)) => {
+                            app.attachment_preview =
+                                Some(AttachmentPreview::Image { filename, path });
+                            cx.notify();
+                        }
+                        Ok((false, path)) => {
+                            cx.open_with_system(&path);
+                            app.close_attachment_preview(cx);
+                        }
+                        Err(e) => {
+                            error!("Failed to download attachment {}: {}", filename, e);
+                            app.attachment_preview = Some(AttachmentPreview::Failed {
+                                filename,
+                                error: e.to_string(),
+                            });
+                            cx.notify();
+                        }
+                    }
+                })
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Download an attachment to the Downloads folder and reveal it
+    pub fn save_attachment(
+        &mut self,
+        account_id: i64,
+        message_id: MessageId,
+        filename: String,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(handler) = self.handler_for_account(account_id) else {
+            warn!("Cannot save attachment: no handler for account {}", account_id);
+            return;
+        };
+        let background = cx.background_executor().clone();
+        cx.spawn(async move |_, cx| {
+            let result = background
+                .spawn(async move {
+                    handler
+                        .download_attachment(&message_id, &filename)?
+                        .save_in(&export_directory())
+                })
+                .await;
+            match result {
+                Ok(path) => {
+                    info!("Saved attachment to {}", path.display());
+                    cx.update(|cx| cx.reveal_path(&path)).ok();
+                }
+                Err(e) => error!("Failed to save attachment: {}", e),
+            }
+        })
+        .detach();
+    }
+
+    /// Close the attachment preview, bringing back the thread it covered
+    fn close_attachment_preview(&mut self, cx: &mut Context<Self>) {
+        self.attachment_preview = None;
+        if self.shown_thread_id().is_some()
+            && let Some(webview) = &self.webview
+        {
+            webview.update(cx, |wv, _| wv.show());
+        }
         cx.notify();
     }
 
+    /// Print the thread shown in the WebView through the system print dialog
+    fn handle_print_thread(
+        &mut self,
+        _: &PrintThread,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.shown_thread_id().is_none() {
+            return;
+        }
+        let Some(webview) = &self.webview else {
+            return;
+        };
+        // The thread HTML carries print styles, so output is light whatever the theme
+        if let Err(e) = webview.read(cx).print() {
+            error!("Failed to print thread: {}", e);
+        }
+    }
+
+    /// Save the thread being read as a PDF at a path the user picks
+    fn handle_export_thread_as_pdf(
+        &mut self,
+        _: &ExportThreadAsPdf,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(thread_id) = self.shown_thread_id().cloned() else {
+            return;
+        };
+        let subject = match self.store.get_thread(&thread_id) {
+            Ok(Some(thread)) => thread.subject,
+            _ => String::new(),
+        };
+        let path = cx.prompt_for_new_path(&export_directory(), Some(&pdf_file_name(&subject)));
+        let store = self.store.clone();
+        let background = cx.background_executor().clone();
+        cx.spawn(async move |_, cx| {
+            let Ok(Ok(Some(path))) = path.await else {
+                return;
+            };
+            let export_path = path.clone();
+            let result = background
+                .spawn(async move {
+                    mail::export_thread_pdf(store.as_ref(), &thread_id, &export_path)
+                })
+                .await;
+            match result {
+                Ok(()) => {
+                    info!("Exported thread to {}", path.display());
+                    cx.update(|cx| cx.reveal_path(&path)).ok();
+                }
+                Err(e) => error!("Failed to export thread: {}", e),
+            }
+        })
+        .detach();
+    }
+
+    /// Write the redacted diagnostic log to the config directory and reveal it
+    fn handle_export_diagnostics(
+        &mut self,
+        _: &ExportDiagnostics,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let filename = format!(
+            "cosmos-diagnostics-{}.txt",
+            Utc::now().format("%Y%m%d-%H%M%S")
+        );
+        let Some(path) = config::config_path(&filename) else {
+            error!("Could not determine config directory for diagnostics export");
+            return;
+        };
+
+        match mail::diagnostics().export_to(&path) {
+            Ok(()) => {
+                info!("Exported diagnostics to {}", path.display());
+                cx.reveal_path(&path);
+            }
+            Err(e) => error!("Failed to export diagnostics: {}", e),
+        }
+    }
+
     /// Dismiss current context and ascend view hierarchy.
     /// Priority: Overlay → Thread → Search → Inbox (no-op)
     pub fn dismiss(&mut self, cx: &mut Context<Self>) {
         // First priority: close any overlay
+        if self.command_palette.take().is_some() {
+            self.palette_return_focus = None;
+            cx.notify();
+            return;
+        }
         if self.show_shortcuts_help {
             self.show_shortcuts_help = false;
             cx.notify();
             return;
         }
+        if self.label_picker.take().is_some() {
+            self.label_picker_threads.clear();
+            self.label_picker_return_focus = None;
+            cx.notify();
+            return;
+        }
+        if self.attachment_preview.is_some() {
+            self.close_attachment_preview(cx);
+            return;
+        }
 
         // Second: dismiss based on current view hierarchy
         match &self.current_view {
@@ -2454,6 +5007,21 @@ impl Render for OrionApp {
         }
         self.was_window_active = is_active;
 
+        // Name the active account, and the profile if not the default, in
+        // the title bar
+        let account_title = self
+            .selected_account_email()
+            .unwrap_or("All accounts")
+            .to_string();
+        let window_title = match config::profile() {
+            Some(profile) => format!("Orion — {} ({})", account_title, profile),
+            None => format!("Orion — {}", account_title),
+        };
+        if self.window_title != window_title {
+            window.set_window_title(&window_title);
+            self.window_title = window_title;
+        }
+
         // Handle pending focus on search results (from Enter key in search box)
         if self.pending_focus_results {
             self.pending_focus_results = false;
@@ -2490,11 +5058,13 @@ impl Render for OrionApp {
         let fg = theme.foreground;
         let secondary_bg = theme.secondary;
         let border = theme.border;
+        let muted_fg = theme.muted_foreground;
         let g_indicator_bg = theme.secondary;
         let g_indicator_fg = theme.foreground;
 
         let sidebar = self.render_sidebar(cx);
         let search_box = self.get_or_create_search_box(window, cx);
+        let reauth_banners = self.render_reauth_banners(cx);
         let content = self.render_content(window, cx);
 
         // G-sequence indicator
@@ -2529,10 +5099,46 @@ impl Render for OrionApp {
             None
         };
 
+        // Attachment preview overlay - also hides the webview
+        let attachment_overlay = self.attachment_preview.clone().map(|preview| {
+            if let Some(ref webview) = self.webview {
+                webview.update(cx, |wv, _| wv.hide());
+            }
+            let app = cx.entity().downgrade();
+            AttachmentPreviewModal::new(preview, move |_, cx| {
+                app.update(cx, |app, cx| app.close_attachment_preview(cx)).ok();
+            })
+        });
+
+        // Command palette and label picker overlays - also hide the webview
+        if (self.command_palette.is_some() || self.label_picker.is_some())
+            && let Some(ref webview) = self.webview
+        {
+            webview.update(cx, |wv, _| wv.hide());
+        }
+
         div()
             .key_context("OrionApp")
             .on_action(cx.listener(Self::handle_focus_search))
             .on_action(cx.listener(Self::handle_show_shortcuts))
+            .on_action(cx.listener(Self::handle_show_command_palette))
+            .on_action(cx.listener(Self::handle_sync_now))
+            .on_action(cx.listener(Self::handle_compose))
+            .on_action(cx.listener(Self::handle_switch_to_account))
+            .on_action(cx.listener(Self::handle_edit_labels))
+            .on_action(cx.listener(Self::handle_use_system_theme))
+            .on_action(cx.listener(Self::handle_use_light_theme))
+            .on_action(cx.listener(Self::handle_use_dark_theme))
+            .on_action(cx.listener(Self::handle_cycle_row_density))
+            .on_action(cx.listener(Self::handle_cycle_snippet_lines))
+            .on_action(cx.listener(Self::handle_cycle_date_format))
+            .on_action(cx.listener(Self::handle_toggle_avatars))
+            .on_action(cx.listener(Self::handle_toggle_message_counts))
+            .on_action(cx.listener(Self::handle_toggle_conversation_view))
+            .on_action(cx.listener(Self::handle_toggle_conversation_view_for_label))
+            .on_action(cx.listener(Self::handle_export_diagnostics))
+            .on_action(cx.listener(Self::handle_print_thread))
+            .on_action(cx.listener(Self::handle_export_thread_as_pdf))
             .on_action(cx.listener(Self::handle_dismiss))
             .on_action(cx.listener(Self::handle_go_to_inbox))
             .on_action(cx.listener(Self::handle_go_to_starred))
@@ -2564,7 +5170,7 @@ impl Render for OrionApp {
                     .flex_col()
                     .flex_1()
                     .overflow_hidden()
-                    // Header with search box (right-aligned)
+                    // Header with the active account and search box
                     .child(
                         div()
                             .w_full()
@@ -2573,10 +5179,21 @@ impl Render for OrionApp {
                             .border_b_1()
                             .border_color(border)
                             .flex()
-                            .justify_end()
+                            .justify_between()
                             .items_center()
+                            .gap_4()
+                            .child(
+                                div()
+                                    .min_w_0()
+                                    .text_sm()
+                                    .font_weight(FontWeight::MEDIUM)
+                                    .text_color(muted_fg)
+                                    .text_ellipsis()
+                                    .child(account_title),
+                            )
                             .child(search_box),
                     )
+                    .child(reauth_banners)
                     // Content area
                     .child(div().flex().flex_1().overflow_hidden().child(content)),
             )
@@ -2584,5 +5201,11 @@ impl Render for OrionApp {
             .children(g_sequence_indicator)
             // Shortcuts help overlay
             .children(shortcuts_overlay)
+            // Attachment preview overlay
+            .children(attachment_overlay)
+            // Command palette overlay
+            .children(self.command_palette.clone())
+            // Label picker overlay
+            .children(self.label_picker.clone())
     }
 }
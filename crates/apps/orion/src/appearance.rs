@@ -0,0 +1,79 @@
+//! Theme settings
+//!
+//! Loaded from `orion.appearance.json` in the Cosmos config directory, e.g.
+//! `{"theme": "light"}`. With `"system"` the theme follows the OS
+//! light/dark setting, including changes while Orion is running.
+
+use gpui::{App, WindowAppearance};
+use gpui_component::{Theme, ThemeMode};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// Config file holding theme settings
+pub const APPEARANCE_CONFIG_FILE: &str = "orion.appearance.json";
+
+/// Which theme the user asked for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemePreference {
+    /// Match the OS light/dark setting
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemePreference {
+    /// The theme mode to use while the OS reports `appearance`
+    pub fn mode(self, appearance: WindowAppearance) -> ThemeMode {
+        match self {
+            Self::Light => ThemeMode::Light,
+            Self::Dark => ThemeMode::Dark,
+            Self::System => match appearance {
+                WindowAppearance::Light | WindowAppearance::VibrantLight => ThemeMode::Light,
+                WindowAppearance::Dark | WindowAppearance::VibrantDark => ThemeMode::Dark,
+            },
+        }
+    }
+}
+
+/// Theme settings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppearanceConfig {
+    pub theme: ThemePreference,
+}
+
+impl AppearanceConfig {
+    /// Load settings from the config directory, falling back to defaults
+    pub fn load() -> Self {
+        if !config::config_exists(APPEARANCE_CONFIG_FILE) {
+            return Self::default();
+        }
+        match config::load_json(APPEARANCE_CONFIG_FILE) {
+            Ok(appearance) => appearance,
+            Err(e) => {
+                warn!("Ignoring invalid appearance config: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Persist settings so the choice survives a restart
+    pub fn save(&self) {
+        if let Err(e) = config::save_json(APPEARANCE_CONFIG_FILE, self) {
+            warn!("Failed to save appearance config: {}", e);
+        }
+    }
+
+    /// Switch every window to the preferred theme
+    ///
+    /// Returns true if this changed between light and dark.
+    pub fn apply(&self, cx: &mut App) -> bool {
+        let mode = self.theme.mode(cx.window_appearance());
+        let changed = Theme::global(cx).mode != mode;
+        Theme::change(mode, None, cx);
+        cx.refresh_windows();
+        changed
+    }
+}
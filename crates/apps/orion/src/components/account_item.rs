@@ -134,18 +134,29 @@ impl RenderOnce for AccountItem {
                             .child(display_name),
                     ),
             )
-            // Right side: sync indicator or unread count
+            // Right side: sync indicator, paused label or unread count
             .when(self.is_syncing, |el| {
                 el.child(Spinner::new().with_size(Size::XSmall))
             })
-            .when(!self.is_syncing && self.unread_count > 0, |el| {
+            .when(!self.is_syncing && !self.account.sync_enabled, |el| {
                 el.child(
                     div()
                         .text_xs()
                         .text_color(theme.muted_foreground)
-                        .child(format!("{}", self.unread_count)),
+                        .child("Paused"),
                 )
             })
+            .when(
+                !self.is_syncing && self.account.sync_enabled && self.unread_count > 0,
+                |el| {
+                    el.child(
+                        div()
+                            .text_xs()
+                            .text_color(theme.muted_foreground)
+                            .child(format!("{}", self.unread_count)),
+                    )
+                },
+            )
     }
 }
 
@@ -0,0 +1,152 @@
+//! Attachment preview modal
+//!
+//! Shows a downloaded image attachment over the app, Quick Look style.
+//! Other file types open in the system viewer instead.
+
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use gpui::prelude::*;
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::ActiveTheme;
+
+/// What the preview modal is showing
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttachmentPreview {
+    /// The attachment is still downloading
+    Loading { filename: String },
+    /// A downloaded image, saved at `path`
+    Image { filename: String, path: PathBuf },
+    /// The download failed
+    Failed { filename: String, error: String },
+}
+
+impl AttachmentPreview {
+    pub fn filename(&self) -> &str {
+        match self {
+            Self::Loading { filename }
+            | Self::Image { filename, .. }
+            | Self::Failed { filename, .. } => filename,
+        }
+    }
+}
+
+/// Attachment preview modal component
+#[derive(IntoElement)]
+pub struct AttachmentPreviewModal {
+    preview: AttachmentPreview,
+    on_close: Rc<dyn Fn(&mut Window, &mut App)>,
+}
+
+impl AttachmentPreviewModal {
+    pub fn new(
+        preview: AttachmentPreview,
+        on_close: impl Fn(&mut Window, &mut App) + 'static,
+    ) -> Self {
+        Self {
+            preview,
+            on_close: Rc::new(on_close),
+        }
+    }
+}
+
+impl RenderOnce for AttachmentPreviewModal {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let theme = cx.theme();
+        let filename = self.preview.filename().to_string();
+
+        let body = match &self.preview {
+            AttachmentPreview::Loading { .. } => div()
+                .p_8()
+                .text_sm()
+                .text_color(theme.muted_foreground)
+                .child("Downloading...")
+                .into_any_element(),
+            AttachmentPreview::Image { path, .. } => img(path.clone())
+                .max_w(px(960.))
+                .max_h(px(640.))
+                .object_fit(ObjectFit::Contain)
+                .into_any_element(),
+            AttachmentPreview::Failed { error, .. } => div()
+                .p_8()
+                .text_sm()
+                .text_color(theme.danger)
+                .child(format!("Could not download attachment: {}", error))
+                .into_any_element(),
+        };
+
+        let open_button = match &self.preview {
+            AttachmentPreview::Image { path, .. } => {
+                let path = path.clone();
+                Some(
+                    Button::new("open-attachment")
+                        .label("Open")
+                        .ghost()
+                        .cursor_pointer()
+                        .on_click(move |_, _, cx| cx.open_with_system(&path)),
+                )
+            }
+            _ => None,
+        };
+
+        let on_backdrop_close = self.on_close.clone();
+        let on_button_close = self.on_close;
+
+        // Full-screen overlay with centered modal
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            // Semi-transparent backdrop; clicking it closes the preview
+            .child(
+                div()
+                    .absolute()
+                    .inset_0()
+                    .bg(hsla(0., 0., 0., 0.5))
+                    .on_mouse_down(MouseButton::Left, move |_, window, cx| {
+                        on_backdrop_close(window, cx)
+                    }),
+            )
+            .child(
+                div()
+                    .relative()
+                    .bg(theme.background)
+                    .border_1()
+                    .border_color(theme.border)
+                    .rounded_lg()
+                    .shadow_lg()
+                    .p_4()
+                    .flex()
+                    .flex_col()
+                    .gap_3()
+                    // Header
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap_2()
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .text_base()
+                                    .font_weight(FontWeight::BOLD)
+                                    .text_color(theme.foreground)
+                                    .text_ellipsis()
+                                    .child(filename),
+                            )
+                            .children(open_button)
+                            .child(
+                                Button::new("close-attachment-preview")
+                                    .label("Close")
+                                    .ghost()
+                                    .cursor_pointer()
+                                    .on_click(move |_, window, cx| on_button_close(window, cx)),
+                            ),
+                    )
+                    .child(div().flex().justify_center().child(body)),
+            )
+    }
+}
@@ -0,0 +1,241 @@
+//! Command palette overlay
+//!
+//! Fuzzy-filters a list of commands as the user types. Enter runs the
+//! selected command; the app decides what running it means.
+
+use gpui::prelude::*;
+use gpui::*;
+use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::{ActiveTheme, Icon, IconName, Sizable};
+
+use crate::input::{filter_commands, Command, CommandTarget};
+
+/// Maximum number of matches listed at once
+const MAX_VISIBLE_MATCHES: usize = 12;
+
+/// Events emitted by the CommandPalette
+pub enum CommandPaletteEvent {
+    /// A command was chosen
+    Run(CommandTarget),
+    /// Closed without running anything
+    Cancelled,
+}
+
+impl EventEmitter<CommandPaletteEvent> for CommandPalette {}
+
+/// Command palette component
+pub struct CommandPalette {
+    input_state: Entity<InputState>,
+    commands: Vec<Command>,
+    /// Indices into `commands` matching the current query, best first
+    matches: Vec<usize>,
+    /// Index into `matches`
+    selected: usize,
+    #[allow(dead_code)]
+    input_subscription: Subscription,
+}
+
+impl CommandPalette {
+    pub fn new(commands: Vec<Command>, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let input_state =
+            cx.new(|cx| InputState::new(window, cx).placeholder("Type a command or thread..."));
+        let input_subscription = cx.subscribe(&input_state, Self::on_input_event);
+
+        Self {
+            input_state,
+            matches: (0..commands.len()).collect(),
+            commands,
+            selected: 0,
+            input_subscription,
+        }
+    }
+
+    fn on_input_event(
+        &mut self,
+        _: Entity<InputState>,
+        event: &InputEvent,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            InputEvent::Change => {
+                let query = self.input_state.read(cx).text().to_string();
+                self.matches = filter_commands(&self.commands, &query);
+                self.selected = 0;
+                cx.notify();
+            }
+            InputEvent::PressEnter { .. } => self.confirm(cx),
+            _ => {}
+        }
+    }
+
+    /// Focus the query input
+    pub fn focus(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.input_state.update(cx, |state, cx| {
+            state.focus(window, cx);
+        });
+    }
+
+    fn select_prev(&mut self, cx: &mut Context<Self>) {
+        self.selected = self.selected.saturating_sub(1);
+        cx.notify();
+    }
+
+    fn select_next(&mut self, cx: &mut Context<Self>) {
+        let last = self
+            .matches
+            .len()
+            .min(MAX_VISIBLE_MATCHES)
+            .saturating_sub(1);
+        self.selected = (self.selected + 1).min(last);
+        cx.notify();
+    }
+
+    /// Run the selected command
+    fn confirm(&mut self, cx: &mut Context<Self>) {
+        let Some(&ix) = self.matches.get(self.selected) else {
+            return;
+        };
+        cx.emit(CommandPaletteEvent::Run(self.commands[ix].target.clone()));
+    }
+
+    fn render_match(&self, row: usize, command: &Command, cx: &mut Context<Self>) -> Stateful<Div> {
+        let theme = cx.theme();
+        let is_selected = row == self.selected;
+
+        div()
+            .id(("command-palette-item", row))
+            .flex()
+            .items_center()
+            .gap_2()
+            .px_3()
+            .py_1p5()
+            .rounded_md()
+            .cursor_pointer()
+            .when(is_selected, |el| el.bg(theme.list_active))
+            .when(!is_selected, |el| {
+                el.hover(|style| style.bg(theme.list_hover))
+            })
+            .child(
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .overflow_hidden()
+                    .text_ellipsis()
+                    .whitespace_nowrap()
+                    .text_sm()
+                    .text_color(theme.foreground)
+                    .child(command.title.clone()),
+            )
+            .child(
+                div()
+                    .flex_shrink_0()
+                    .text_xs()
+                    .text_color(theme.muted_foreground)
+                    .child(command.category),
+            )
+            .on_click(cx.listener(move |this, _, _, cx| {
+                this.selected = row;
+                this.confirm(cx);
+            }))
+    }
+}
+
+impl Render for CommandPalette {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let rows: Vec<Stateful<Div>> = self
+            .matches
+            .iter()
+            .take(MAX_VISIBLE_MATCHES)
+            .enumerate()
+            .map(|(row, &ix)| self.render_match(row, &self.commands[ix], cx))
+            .collect();
+        let theme = cx.theme();
+
+        // Full-screen overlay with the palette near the top
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .justify_center()
+            .pt(px(96.))
+            // Semi-transparent backdrop; clicking it closes the palette
+            .child(
+                div()
+                    .absolute()
+                    .inset_0()
+                    .bg(hsla(0., 0., 0., 0.5))
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|_, _, _, cx| cx.emit(CommandPaletteEvent::Cancelled)),
+                    ),
+            )
+            .child(
+                div()
+                    .key_context("CommandPalette")
+                    .on_action(cx.listener(Self::handle_select_prev))
+                    .on_action(cx.listener(Self::handle_select_next))
+                    .on_action(cx.listener(Self::handle_cancel))
+                    .relative()
+                    .w(px(520.))
+                    .flex()
+                    .flex_col()
+                    .bg(theme.background)
+                    .border_1()
+                    .border_color(theme.border)
+                    .rounded_lg()
+                    .shadow_lg()
+                    // Query input
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap_2()
+                            .px_3()
+                            .py_2()
+                            .border_b_1()
+                            .border_color(theme.border)
+                            .child(
+                                Icon::new(IconName::Search)
+                                    .small()
+                                    .text_color(theme.muted_foreground),
+                            )
+                            .child(Input::new(&self.input_state).appearance(false).w_full()),
+                    )
+                    // Matches
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .p_1()
+                            .when(rows.is_empty(), |el| {
+                                el.child(
+                                    div()
+                                        .px_3()
+                                        .py_2()
+                                        .text_sm()
+                                        .text_color(theme.muted_foreground)
+                                        .child("No matching commands"),
+                                )
+                            })
+                            .children(rows),
+                    ),
+            )
+    }
+}
+
+// Actions for keyboard handling
+actions!(command_palette, [SelectPrev, SelectNext, Cancel]);
+
+impl CommandPalette {
+    fn handle_select_prev(&mut self, _: &SelectPrev, _window: &mut Window, cx: &mut Context<Self>) {
+        self.select_prev(cx);
+    }
+
+    fn handle_select_next(&mut self, _: &SelectNext, _window: &mut Window, cx: &mut Context<Self>) {
+        self.select_next(cx);
+    }
+
+    fn handle_cancel(&mut self, _: &Cancel, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(CommandPaletteEvent::Cancelled);
+    }
+}
@@ -0,0 +1,48 @@
+//! Toggleable chip for the thread list quick filter bar
+
+use gpui::prelude::*;
+use gpui::*;
+use gpui_component::ActiveTheme;
+
+/// A single on/off quick filter chip
+#[derive(IntoElement)]
+pub struct FilterChip {
+    label: SharedString,
+    is_active: bool,
+}
+
+impl FilterChip {
+    pub fn new(label: impl Into<SharedString>, is_active: bool) -> Self {
+        Self {
+            label: label.into(),
+            is_active,
+        }
+    }
+}
+
+impl RenderOnce for FilterChip {
+    fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let theme = cx.theme();
+
+        let (bg_color, text_color, border_color) = if self.is_active {
+            (theme.primary, theme.primary_foreground, theme.primary)
+        } else {
+            (theme.transparent, theme.muted_foreground, theme.border)
+        };
+
+        div()
+            .px_2()
+            .py_0p5()
+            .rounded_full()
+            .border_1()
+            .border_color(border_color)
+            .bg(bg_color)
+            .cursor_pointer()
+            .when(!self.is_active, |el| {
+                el.hover(|style| style.bg(theme.list_hover))
+            })
+            .text_xs()
+            .text_color(text_color)
+            .child(self.label)
+    }
+}
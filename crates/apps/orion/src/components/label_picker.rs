@@ -0,0 +1,327 @@
+//! Label picker popover
+//!
+//! Lists the labels that can be applied by hand, fuzzy-filtered as the user
+//! types. Enter or a click toggles the highlighted label on the target
+//! threads and keeps the picker open, so several labels can be changed in
+//! one go.
+
+use gpui::prelude::*;
+use gpui::*;
+use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::{ActiveTheme, Icon, IconName, Sizable};
+
+use crate::input::commands::fuzzy_score;
+
+/// Maximum number of labels listed at once
+const MAX_VISIBLE_LABELS: usize = 12;
+
+/// Whether a label is on the threads being labeled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelState {
+    /// Every target thread has the label
+    Applied,
+    /// Some target threads have it
+    Mixed,
+    NotApplied,
+}
+
+/// A label offered by the picker
+#[derive(Debug, Clone)]
+pub struct LabelOption {
+    pub id: String,
+    pub name: String,
+    pub state: LabelState,
+}
+
+/// Events emitted by the LabelPicker
+pub enum LabelPickerEvent {
+    /// Add (`apply`) or remove a label on the target threads
+    Toggle { label_id: String, apply: bool },
+    /// The picker was closed
+    Closed,
+}
+
+impl EventEmitter<LabelPickerEvent> for LabelPicker {}
+
+/// Label picker component
+pub struct LabelPicker {
+    input_state: Entity<InputState>,
+    options: Vec<LabelOption>,
+    /// Indices into `options` matching the current query, best first
+    matches: Vec<usize>,
+    /// Index into `matches`
+    selected: usize,
+    /// Number of threads the labels apply to, for the header
+    thread_count: usize,
+    #[allow(dead_code)]
+    input_subscription: Subscription,
+}
+
+impl LabelPicker {
+    pub fn new(
+        options: Vec<LabelOption>,
+        thread_count: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let input_state = cx.new(|cx| InputState::new(window, cx).placeholder("Filter labels..."));
+        let input_subscription = cx.subscribe(&input_state, Self::on_input_event);
+
+        Self {
+            input_state,
+            matches: (0..options.len()).collect(),
+            options,
+            selected: 0,
+            thread_count,
+            input_subscription,
+        }
+    }
+
+    fn on_input_event(
+        &mut self,
+        _: Entity<InputState>,
+        event: &InputEvent,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            InputEvent::Change => {
+                let query = self.input_state.read(cx).text().to_string();
+                self.matches = filter_options(&self.options, &query);
+                self.selected = 0;
+                cx.notify();
+            }
+            InputEvent::PressEnter { .. } => self.toggle_selected(cx),
+            _ => {}
+        }
+    }
+
+    /// Focus the filter input
+    pub fn focus(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.input_state.update(cx, |state, cx| {
+            state.focus(window, cx);
+        });
+    }
+
+    fn select_prev(&mut self, cx: &mut Context<Self>) {
+        self.selected = self.selected.saturating_sub(1);
+        cx.notify();
+    }
+
+    fn select_next(&mut self, cx: &mut Context<Self>) {
+        let last = self.matches.len().min(MAX_VISIBLE_LABELS).saturating_sub(1);
+        self.selected = (self.selected + 1).min(last);
+        cx.notify();
+    }
+
+    /// Toggle the highlighted label
+    ///
+    /// A label on only some threads is added to the rest, like a mixed
+    /// checkbox becoming checked.
+    fn toggle_selected(&mut self, cx: &mut Context<Self>) {
+        let Some(&ix) = self.matches.get(self.selected) else {
+            return;
+        };
+        let option = &mut self.options[ix];
+        let apply = option.state != LabelState::Applied;
+        option.state = if apply {
+            LabelState::Applied
+        } else {
+            LabelState::NotApplied
+        };
+        cx.emit(LabelPickerEvent::Toggle {
+            label_id: option.id.clone(),
+            apply,
+        });
+        cx.notify();
+    }
+
+    fn render_option(
+        &self,
+        row: usize,
+        option: &LabelOption,
+        cx: &mut Context<Self>,
+    ) -> Stateful<Div> {
+        let theme = cx.theme();
+        let is_selected = row == self.selected;
+        let mark = match option.state {
+            LabelState::Applied => Some(IconName::Check),
+            LabelState::Mixed => Some(IconName::Minus),
+            LabelState::NotApplied => None,
+        };
+
+        div()
+            .id(("label-picker-item", row))
+            .flex()
+            .items_center()
+            .gap_2()
+            .px_3()
+            .py_1p5()
+            .rounded_md()
+            .cursor_pointer()
+            .when(is_selected, |el| el.bg(theme.list_active))
+            .when(!is_selected, |el| {
+                el.hover(|style| style.bg(theme.list_hover))
+            })
+            // Checkbox
+            .child(
+                div()
+                    .size(px(16.))
+                    .flex_shrink_0()
+                    .flex()
+                    .items_center()
+                    .justify_center()
+                    .rounded_sm()
+                    .border_1()
+                    .border_color(theme.border)
+                    .children(
+                        mark.map(|icon| Icon::new(icon).xsmall().text_color(theme.foreground)),
+                    ),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .overflow_hidden()
+                    .text_ellipsis()
+                    .whitespace_nowrap()
+                    .text_sm()
+                    .text_color(theme.foreground)
+                    .child(option.name.clone()),
+            )
+            .on_click(cx.listener(move |this, _, _, cx| {
+                this.selected = row;
+                this.toggle_selected(cx);
+            }))
+    }
+}
+
+/// Indices of the options matching `query`, best first
+///
+/// An empty query keeps every option in the order given.
+fn filter_options(options: &[LabelOption], query: &str) -> Vec<usize> {
+    if query.trim().is_empty() {
+        return (0..options.len()).collect();
+    }
+    let mut scored: Vec<(i32, usize)> = options
+        .iter()
+        .enumerate()
+        .filter_map(|(ix, option)| Some((fuzzy_score(query, &option.name)?, ix)))
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    scored.into_iter().map(|(_, ix)| ix).collect()
+}
+
+impl Render for LabelPicker {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let rows: Vec<Stateful<Div>> = self
+            .matches
+            .iter()
+            .take(MAX_VISIBLE_LABELS)
+            .enumerate()
+            .map(|(row, &ix)| self.render_option(row, &self.options[ix], cx))
+            .collect();
+        let title = if self.thread_count == 1 {
+            "Label thread".to_string()
+        } else {
+            format!("Label {} threads", self.thread_count)
+        };
+        let theme = cx.theme();
+
+        // Full-screen overlay with the picker near the top
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .justify_center()
+            .pt(px(96.))
+            // Semi-transparent backdrop; clicking it closes the picker
+            .child(
+                div()
+                    .absolute()
+                    .inset_0()
+                    .bg(hsla(0., 0., 0., 0.5))
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|_, _, _, cx| cx.emit(LabelPickerEvent::Closed)),
+                    ),
+            )
+            .child(
+                div()
+                    .key_context("LabelPicker")
+                    .on_action(cx.listener(Self::handle_select_prev))
+                    .on_action(cx.listener(Self::handle_select_next))
+                    .on_action(cx.listener(Self::handle_close))
+                    .relative()
+                    .w(px(360.))
+                    .flex()
+                    .flex_col()
+                    .bg(theme.background)
+                    .border_1()
+                    .border_color(theme.border)
+                    .rounded_lg()
+                    .shadow_lg()
+                    // Header
+                    .child(
+                        div()
+                            .px_3()
+                            .pt_2()
+                            .text_xs()
+                            .font_weight(FontWeight::MEDIUM)
+                            .text_color(theme.muted_foreground)
+                            .child(title),
+                    )
+                    // Filter input
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap_2()
+                            .px_3()
+                            .py_2()
+                            .border_b_1()
+                            .border_color(theme.border)
+                            .child(
+                                Icon::new(IconName::Search)
+                                    .small()
+                                    .text_color(theme.muted_foreground),
+                            )
+                            .child(Input::new(&self.input_state).appearance(false).w_full()),
+                    )
+                    // Labels
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .p_1()
+                            .when(rows.is_empty(), |el| {
+                                el.child(
+                                    div()
+                                        .px_3()
+                                        .py_2()
+                                        .text_sm()
+                                        .text_color(theme.muted_foreground)
+                                        .child("No matching labels"),
+                                )
+                            })
+                            .children(rows),
+                    ),
+            )
+    }
+}
+
+// Actions for keyboard handling
+actions!(label_picker, [SelectPrev, SelectNext, Close]);
+
+impl LabelPicker {
+    fn handle_select_prev(&mut self, _: &SelectPrev, _window: &mut Window, cx: &mut Context<Self>) {
+        self.select_prev(cx);
+    }
+
+    fn handle_select_next(&mut self, _: &SelectNext, _window: &mut Window, cx: &mut Context<Self>) {
+        self.select_next(cx);
+    }
+
+    fn handle_close(&mut self, _: &Close, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(LabelPickerEvent::Closed);
+    }
+}
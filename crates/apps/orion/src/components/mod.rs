@@ -1,6 +1,11 @@
 //! Reusable UI components for Orion
 
 mod account_item;
+mod attachment_preview;
+pub mod command_palette;
+mod filter_chip;
+pub mod label_picker;
+pub mod reply_box;
 pub mod search_box;
 mod search_result_item;
 mod shortcuts_help;
@@ -8,6 +13,11 @@ mod sidebar;
 mod thread_list_item;
 
 pub use account_item::{AccountItem, AllAccountsItem};
+pub use attachment_preview::{AttachmentPreview, AttachmentPreviewModal};
+pub use command_palette::{CommandPalette, CommandPaletteEvent};
+pub use filter_chip::FilterChip;
+pub use label_picker::{LabelOption, LabelPicker, LabelPickerEvent, LabelState};
+pub use reply_box::{ReplyBox, ReplyBoxEvent};
 pub use search_box::{SearchBox, SearchBoxEvent};
 pub use search_result_item::SearchResultItem;
 pub use shortcuts_help::ShortcutsHelp;
@@ -0,0 +1,196 @@
+//! Quick reply box shown under a thread
+//!
+//! Sends a reply to the thread's latest message without opening a compose
+//! window. The message being answered is quoted below the reply.
+
+use gpui::prelude::*;
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::input::{Input, InputState};
+use gpui_component::{ActiveTheme, Disableable, Sizable};
+use log::{error, info};
+use mail::{Account, ActionHandler, Message, OutgoingMessage, ThreadId};
+use std::sync::Arc;
+
+/// Events emitted by the ReplyBox
+pub enum ReplyBoxEvent {
+    /// A reply was sent from this account
+    Sent { account_id: i64 },
+}
+
+impl EventEmitter<ReplyBoxEvent> for ReplyBox {}
+
+/// Quick reply box component
+pub struct ReplyBox {
+    account: Account,
+    handler: Arc<ActionHandler>,
+    /// Message being replied to and quoted
+    original: Message,
+    input_state: Entity<InputState>,
+    window_handle: AnyWindowHandle,
+    sending: bool,
+    error_message: Option<String>,
+}
+
+impl ReplyBox {
+    pub fn new(
+        account: Account,
+        handler: Arc<ActionHandler>,
+        original: Message,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let placeholder = format!("Reply to {}…", sender_name(&original));
+        let input_state = cx.new(|cx| {
+            InputState::new(window, cx)
+                .multi_line(true)
+                .placeholder(placeholder)
+        });
+
+        Self {
+            account,
+            handler,
+            original,
+            input_state,
+            window_handle: window.window_handle(),
+            sending: false,
+            error_message: None,
+        }
+    }
+
+    /// The thread this box replies in
+    pub fn thread_id(&self) -> &ThreadId {
+        &self.original.thread_id
+    }
+
+    /// Send the typed reply, quoting the original
+    fn send(&mut self, cx: &mut Context<Self>) {
+        let text = self.input_state.read(cx).text().to_string();
+        if self.sending || text.trim().is_empty() {
+            return;
+        }
+
+        let message = OutgoingMessage::reply(&self.account, &self.original)
+            .body_markdown(text)
+            .quoting(&self.original);
+        let handler = self.handler.clone();
+        let account_id = self.account.id;
+        self.sending = true;
+        self.error_message = None;
+        cx.notify();
+
+        let background = cx.background_executor().clone();
+        cx.spawn(async move |this, cx| {
+            let result = background
+                .spawn(async move { handler.queue_send(&message, None) })
+                .await;
+
+            cx.update(|cx| {
+                this.update(cx, |reply_box, cx| {
+                    reply_box.sending = false;
+                    match result {
+                        Ok(entry) => {
+                            match entry.sent_message_id {
+                                Some(id) => info!("Sent reply {}", id),
+                                None => info!("Reply {} left in the outbox to retry", entry.id),
+                            }
+                            reply_box.clear(cx);
+                            cx.emit(ReplyBoxEvent::Sent { account_id });
+                        }
+                        Err(e) => {
+                            error!("Failed to send reply: {}", e);
+                            reply_box.error_message = Some(format!("Not sent: {}", e));
+                        }
+                    }
+                    cx.notify();
+                })
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Empty the input once the reply is on its way
+    fn clear(&mut self, cx: &mut Context<Self>) {
+        let input_state = self.input_state.clone();
+        cx.update_window(self.window_handle, |_, window, cx| {
+            input_state.update(cx, |state, cx| state.set_value("", window, cx));
+        })
+        .ok();
+    }
+}
+
+/// Name to address the sender by in the placeholder
+fn sender_name(message: &Message) -> String {
+    message
+        .from
+        .name
+        .clone()
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| message.from.email.clone())
+}
+
+impl Render for ReplyBox {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let hint = match &self.error_message {
+            Some(message) => message.clone(),
+            None => format!(
+                "Quoting {}'s message · ⌘Enter to send",
+                sender_name(&self.original)
+            ),
+        };
+
+        div()
+            .key_context("ReplyBox")
+            .on_action(cx.listener(Self::handle_send_reply))
+            .flex()
+            .flex_col()
+            .gap_2()
+            .mx_4()
+            .mb_4()
+            .p_2()
+            .border_1()
+            .border_color(theme.border)
+            .rounded_md()
+            .child(
+                div()
+                    .h(px(88.))
+                    .child(Input::new(&self.input_state).appearance(false).h_full()),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap_2()
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_xs()
+                            .text_color(if self.error_message.is_some() {
+                                theme.danger
+                            } else {
+                                theme.muted_foreground
+                            })
+                            .child(hint),
+                    )
+                    .child(
+                        Button::new("send-reply")
+                            .label(if self.sending { "Sending…" } else { "Reply" })
+                            .small()
+                            .primary()
+                            .disabled(self.sending)
+                            .on_click(cx.listener(|this, _, _, cx| this.send(cx))),
+                    ),
+            )
+    }
+}
+
+// Actions for keyboard handling
+actions!(reply_box, [SendReply]);
+
+impl ReplyBox {
+    fn handle_send_reply(&mut self, _: &SendReply, _window: &mut Window, cx: &mut Context<Self>) {
+        self.send(cx);
+    }
+}
@@ -4,6 +4,7 @@ use gpui::prelude::*;
 use gpui::*;
 use gpui_component::input::{Input, InputEvent, InputState};
 use gpui_component::{ActiveTheme, Icon, IconName, Sizable};
+use mail::{SearchSuggestion, SuggestionKind};
 use std::time::Duration;
 
 /// Events emitted by the SearchBox
@@ -27,6 +28,8 @@ pub struct SearchBox {
     focus_handle: FocusHandle,
     debounce_task: Option<Task<()>>,
     last_emitted_query: String,
+    /// Typeahead suggestions shown below the input
+    suggestions: Vec<SearchSuggestion>,
     #[allow(dead_code)]
     input_subscription: Subscription,
 }
@@ -43,6 +46,7 @@ impl SearchBox {
             focus_handle: cx.focus_handle(),
             debounce_task: None,
             last_emitted_query: String::new(),
+            suggestions: Vec::new(),
             input_subscription,
         }
     }
@@ -59,6 +63,7 @@ impl SearchBox {
             }
             InputEvent::PressEnter { .. } => {
                 let query = self.query(cx);
+                self.suggestions.clear();
                 cx.emit(SearchBoxEvent::Submitted(query));
             }
             _ => {}
@@ -91,6 +96,88 @@ impl SearchBox {
         cx.emit(SearchBoxEvent::Cleared);
     }
 
+    /// Replace the typeahead suggestions
+    pub fn set_suggestions(&mut self, suggestions: Vec<SearchSuggestion>, cx: &mut Context<Self>) {
+        self.suggestions = suggestions;
+        cx.notify();
+    }
+
+    /// Hide the typeahead suggestions
+    pub fn clear_suggestions(&mut self, cx: &mut Context<Self>) {
+        if !self.suggestions.is_empty() {
+            self.suggestions.clear();
+            cx.notify();
+        }
+    }
+
+    /// Run a suggestion's query as if it had been typed and submitted
+    fn choose_suggestion(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(suggestion) = self.suggestions.get(index) else {
+            return;
+        };
+        let query = suggestion.query.clone();
+        self.set_query(&query, window, cx);
+        self.debounce_task = None;
+        self.suggestions.clear();
+        cx.emit(SearchBoxEvent::Submitted(query));
+    }
+
+    /// Render the suggestion dropdown
+    fn render_suggestions(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+
+        div()
+            .absolute()
+            .top(px(28.))
+            .left_0()
+            .w_full()
+            .py_1()
+            .bg(theme.background)
+            .border_1()
+            .border_color(theme.border)
+            .rounded_md()
+            .shadow_md()
+            .children(self.suggestions.iter().enumerate().map(|(ix, suggestion)| {
+                let tag = match suggestion.kind {
+                    SuggestionKind::RecentQuery => "Recent",
+                    SuggestionKind::Contact => "From",
+                    SuggestionKind::Subject => "Subject",
+                };
+
+                div()
+                    .id(("search-suggestion", ix))
+                    .flex()
+                    .items_center()
+                    .gap_2()
+                    .px_2()
+                    .py_1()
+                    .cursor_pointer()
+                    .hover(|style| style.bg(theme.list_hover))
+                    .child(
+                        div()
+                            .w(px(48.))
+                            .flex_shrink_0()
+                            .text_xs()
+                            .text_color(theme.muted_foreground)
+                            .child(tag),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .overflow_hidden()
+                            .text_ellipsis()
+                            .whitespace_nowrap()
+                            .text_sm()
+                            .text_color(theme.foreground)
+                            .child(suggestion.label.clone()),
+                    )
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.choose_suggestion(ix, window, cx);
+                    }))
+            }))
+    }
+
     /// Focus the search box
     pub fn focus(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         self.input_state.update(cx, |state, cx| {
@@ -113,8 +200,9 @@ impl SearchBox {
         // Start new debounce task
         let query_clone = query.clone();
         self.debounce_task = Some(cx.spawn(async move |this, cx| {
+            // Short enough for instant results, long enough to coalesce fast typing
             cx.background_executor()
-                .timer(Duration::from_millis(150))
+                .timer(Duration::from_millis(50))
                 .await;
 
             let _ = cx.update(|cx| {
@@ -129,14 +217,20 @@ impl SearchBox {
 
 impl Render for SearchBox {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let theme = cx.theme();
         let query = self.query(cx);
         let has_text = !query.is_empty();
+        let suggestions = if self.suggestions.is_empty() {
+            None
+        } else {
+            Some(deferred(self.render_suggestions(cx)).with_priority(1))
+        };
+        let theme = cx.theme();
 
         div()
             .track_focus(&self.focus_handle)
             .key_context("SearchBox")
             .on_action(cx.listener(Self::handle_escape))
+            .relative()
             .flex()
             .items_center()
             .w(px(280.))
@@ -165,6 +259,7 @@ impl Render for SearchBox {
                     ),
                 )
             })
+            .children(suggestions)
     }
 }
 
@@ -173,6 +268,7 @@ actions!(search_box, [Escape]);
 
 impl SearchBox {
     fn handle_escape(&mut self, _: &Escape, _window: &mut Window, cx: &mut Context<Self>) {
+        self.suggestions.clear();
         cx.emit(SearchBoxEvent::Cancelled);
     }
 }
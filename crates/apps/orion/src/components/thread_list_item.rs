@@ -1,10 +1,13 @@
 //! Thread list item component - displays a single thread row in the inbox
 //! Uses Gmail-style single-line layout: Sender | Subject - preview | Date
+//! Display preferences can add avatars and wrap the preview below the subject.
 
 use gpui::prelude::*;
 use gpui::*;
 use gpui_component::ActiveTheme;
-use mail::ThreadSummary;
+use mail::{EmailAddress, Label, ThreadSummary};
+
+use crate::display::DisplayConfig;
 
 /// Props for ThreadListItem
 #[derive(IntoElement)]
@@ -13,6 +16,9 @@ pub struct ThreadListItem {
     is_selected: bool,
     /// Account email to show in unified view (None = single account, no need to show)
     account_email: Option<String>,
+    /// Checkbox state while threads are being multi-selected (None = no checkbox)
+    is_checked: Option<bool>,
+    display: DisplayConfig,
 }
 
 impl ThreadListItem {
@@ -21,9 +27,23 @@ impl ThreadListItem {
             thread,
             is_selected,
             account_email: None,
+            is_checked: None,
+            display: DisplayConfig::default(),
         }
     }
 
+    /// Lay the row out according to display preferences
+    pub fn with_display(mut self, display: DisplayConfig) -> Self {
+        self.display = display;
+        self
+    }
+
+    /// Show a checkbox with the given state (while multi-selecting)
+    pub fn with_checked(mut self, is_checked: Option<bool>) -> Self {
+        self.is_checked = is_checked;
+        self
+    }
+
     /// Set the account email to display (for unified view)
     pub fn with_account(mut self, email: Option<String>) -> Self {
         self.account_email = email;
@@ -31,38 +51,102 @@ impl ThreadListItem {
     }
 
     fn format_date(&self) -> String {
-        use chrono::{Local, Utc};
-        let local = self.thread.last_message_at.with_timezone(&Local);
-        let now = Utc::now().with_timezone(&Local);
-
-        if local.date_naive() == now.date_naive() {
-            // Today: show time
-            local.format("%H:%M").to_string()
-        } else if (now - local).num_days() < 7 {
-            // This week: show day name
-            local.format("%a").to_string()
-        } else {
-            // Older: show date
-            local.format("%b %d").to_string()
-        }
+        self.display.date_format.format(self.thread.last_message_at)
     }
 }
 
+/// Short name for a participant: first name, or the local part of the email
+fn short_name(participant: &EmailAddress) -> &str {
+    participant
+        .name
+        .as_deref()
+        .and_then(|name| name.split_whitespace().next())
+        .unwrap_or_else(|| participant.email.split('@').next().unwrap_or(""))
+}
+
+/// Round badge with the sender's initial, colored per sender
+fn avatar(sender_display: &str, sender_email: &str) -> Div {
+    let initial = sender_display
+        .chars()
+        .find(|c| c.is_alphanumeric())
+        .map(|c| c.to_uppercase().collect::<String>())
+        .unwrap_or_else(|| "?".to_string());
+    // Stable hue per address so a sender keeps their color between rows
+    let hash = sender_email.bytes().fold(0u32, |hash, b| {
+        hash.wrapping_mul(31).wrapping_add(u32::from(b))
+    });
+    let hue = (hash % 360) as f32 / 360.0;
+
+    div()
+        .w(px(24.))
+        .h(px(24.))
+        .flex_shrink_0()
+        .flex()
+        .items_center()
+        .justify_center()
+        .rounded_full()
+        .bg(hsla(hue, 0.45, 0.45, 1.0))
+        .text_xs()
+        .font_weight(FontWeight::SEMIBOLD)
+        .text_color(white())
+        .child(initial)
+}
+
+/// Label chips shown on a row before the rest are summarized as "+N"
+const MAX_LABEL_CHIPS: usize = 3;
+
+/// Small pill with a label's name, in its Gmail or local color
+fn label_chip(label: &Label, fallback_bg: Hsla, fallback_text: Hsla) -> Div {
+    let color = label.color.as_ref();
+    let bg = color
+        .and_then(|c| Rgba::try_from(c.background.as_str()).ok())
+        .map(Hsla::from)
+        .unwrap_or(fallback_bg);
+    let text = color
+        .and_then(|c| Rgba::try_from(c.text.as_str()).ok())
+        .map(Hsla::from)
+        .unwrap_or(fallback_text);
+    // Nested labels show their last segment, like Gmail
+    let name = label.name.rsplit('/').next().unwrap_or(&label.name).to_string();
+
+    div()
+        .flex_shrink_0()
+        .px_1()
+        .rounded(px(3.))
+        .bg(bg)
+        .text_xs()
+        .text_color(text)
+        .child(name)
+}
+
 impl RenderOnce for ThreadListItem {
     fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
         let theme = cx.theme();
         let is_unread = self.thread.is_unread;
 
-        let bg_color = if self.is_selected {
+        let bg_color = if self.is_selected || self.is_checked == Some(true) {
             theme.list_active
         } else {
             theme.list
         };
 
         let date_str = self.format_date();
+        let display = self.display;
         let message_count = self.thread.message_count;
+        let show_count = display.show_message_counts && message_count > 1;
         let subject = self.thread.subject.clone();
         let snippet = self.thread.snippet.clone();
+        let snippet_lines = usize::from(display.snippet_lines());
+        let snippet_inline = snippet_lines == 1 && !snippet.is_empty();
+        let snippet_below = display.snippet_below_subject() && !snippet.is_empty();
+        let hidden_labels = self.thread.labels.len().saturating_sub(MAX_LABEL_CHIPS);
+        let chips: Vec<Div> = self
+            .thread
+            .labels
+            .iter()
+            .take(MAX_LABEL_CHIPS)
+            .map(|label| label_chip(label, theme.secondary, theme.secondary_foreground))
+            .collect();
 
         // Sender display: name or email
         let sender_display = self
@@ -70,6 +154,17 @@ impl RenderOnce for ThreadListItem {
             .sender_name
             .clone()
             .unwrap_or_else(|| self.thread.sender_email.clone());
+        // Threads with several senders list them by short name instead
+        let participants_display = if self.thread.participants.len() > 1 {
+            self.thread
+                .participants
+                .iter()
+                .map(short_name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        } else {
+            sender_display.clone()
+        };
 
         // Text styling based on unread status
         let text_weight = if is_unread {
@@ -94,6 +189,31 @@ impl RenderOnce for ThreadListItem {
                     .flex()
                     .items_center()
                     .gap_2()
+                    // Checkbox (multi-select only)
+                    .when_some(self.is_checked, |el, is_checked| {
+                        el.child(
+                            div()
+                                .w(px(14.))
+                                .h(px(14.))
+                                .flex_shrink_0()
+                                .flex()
+                                .items_center()
+                                .justify_center()
+                                .rounded(px(3.))
+                                .border_1()
+                                .border_color(if is_checked {
+                                    theme.primary
+                                } else {
+                                    theme.muted_foreground
+                                })
+                                .when(is_checked, |el| {
+                                    el.bg(theme.primary)
+                                        .text_xs()
+                                        .text_color(theme.primary_foreground)
+                                        .child("✓")
+                                }),
+                        )
+                    })
                     // Unread indicator dot
                     .child(
                         div()
@@ -103,6 +223,10 @@ impl RenderOnce for ThreadListItem {
                             .flex_shrink_0()
                             .when(is_unread, |el| el.bg(theme.primary)),
                     )
+                    // Sender avatar (optional)
+                    .when(display.show_avatars, |el| {
+                        el.child(avatar(&sender_display, &self.thread.sender_email))
+                    })
                     // Column 1: Sender with message count
                     .child(
                         div()
@@ -118,9 +242,9 @@ impl RenderOnce for ThreadListItem {
                                     .font_weight(text_weight)
                                     .text_color(theme.foreground)
                                     .text_ellipsis()
-                                    .child(sender_display),
+                                    .child(participants_display),
                             )
-                            .when(message_count > 1, |el| {
+                            .when(show_count, |el| {
                                 el.child(
                                     div()
                                         .text_xs()
@@ -130,8 +254,50 @@ impl RenderOnce for ThreadListItem {
                                 )
                             }),
                     )
+                    // Label chips
+                    .when(!chips.is_empty(), |el| {
+                        el.child(
+                            div()
+                                .flex_shrink_0()
+                                .flex()
+                                .items_center()
+                                .gap_1()
+                                .children(chips)
+                                .when(hidden_labels > 0, |el| {
+                                    el.child(
+                                        div()
+                                            .text_xs()
+                                            .text_color(theme.muted_foreground)
+                                            .child(format!("+{}", hidden_labels)),
+                                    )
+                                }),
+                        )
+                    })
                     // Column 2: Subject - preview (fills remaining space)
-                    .child(
+                    .child(if snippet_below {
+                        // Preview wraps onto its own lines under the subject
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .flex()
+                            .flex_col()
+                            .overflow_hidden()
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(text_weight)
+                                    .text_color(theme.foreground)
+                                    .text_ellipsis()
+                                    .child(subject),
+                            )
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .text_color(theme.muted_foreground)
+                                    .line_clamp(snippet_lines)
+                                    .child(snippet),
+                            )
+                    } else {
                         div()
                             .flex_1()
                             .min_w_0()
@@ -147,7 +313,7 @@ impl RenderOnce for ThreadListItem {
                                     .flex_shrink_0()
                                     .child(subject),
                             )
-                            .when(!snippet.is_empty(), |el| {
+                            .when(snippet_inline, |el| {
                                 el.child(
                                     div()
                                         .text_sm()
@@ -156,8 +322,8 @@ impl RenderOnce for ThreadListItem {
                                         .text_ellipsis()
                                         .child(format!("- {}", snippet)),
                                 )
-                            }),
-                    )
+                            })
+                    })
                     // Column 3: Account email (unified view only)
                     .when_some(self.account_email, |el, email| {
                         el.child(
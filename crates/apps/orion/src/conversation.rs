@@ -0,0 +1,80 @@
+//! Conversation view settings
+//!
+//! Loaded from `orion.conversation.json` in the Cosmos config directory,
+//! e.g. `{"enabled": true, "labels": {"SENT": false}}`. With conversation
+//! view off the thread list shows one row per message, classic style,
+//! instead of one per thread. Entries in `labels` override the global
+//! setting for a single label ("ALL" is All Mail).
+
+use std::collections::BTreeMap;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// Config file holding conversation view settings
+pub const CONVERSATION_CONFIG_FILE: &str = "orion.conversation.json";
+
+/// Whether threads are grouped into conversations, globally and per label
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConversationConfig {
+    pub enabled: bool,
+    /// Labels that differ from `enabled`
+    pub labels: BTreeMap<String, bool>,
+}
+
+impl Default for ConversationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            labels: BTreeMap::new(),
+        }
+    }
+}
+
+impl ConversationConfig {
+    /// Load settings from the config directory, falling back to defaults
+    pub fn load() -> Self {
+        if !config::config_exists(CONVERSATION_CONFIG_FILE) {
+            return Self::default();
+        }
+        match config::load_json(CONVERSATION_CONFIG_FILE) {
+            Ok(conversation) => conversation,
+            Err(e) => {
+                warn!("Ignoring invalid conversation config: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Persist settings changed from inside the app
+    pub fn save(&self) {
+        if let Err(e) = config::save_json(CONVERSATION_CONFIG_FILE, self) {
+            warn!("Failed to save conversation config: {}", e);
+        }
+    }
+
+    /// Whether `label` lists whole threads rather than single messages
+    pub fn is_enabled(&self, label: &str) -> bool {
+        self.labels.get(label).copied().unwrap_or(self.enabled)
+    }
+
+    /// Flip the global setting
+    ///
+    /// Label overrides that now match it are dropped as redundant.
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        let enabled = self.enabled;
+        self.labels.retain(|_, on| *on != enabled);
+    }
+
+    /// Flip the setting for one label only
+    pub fn toggle_label(&mut self, label: &str) {
+        let on = !self.is_enabled(label);
+        if on == self.enabled {
+            self.labels.remove(label);
+        } else {
+            self.labels.insert(label.to_string(), on);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Thread list display preferences
+//!
+//! Loaded from `orion.display.json` in the Cosmos config directory, e.g.
+//! `{"density": "compact", "snippet_lines": 2, "date_format": "iso",
+//! "show_avatars": true, "show_message_counts": false}`. The command palette
+//! changes them too, saving the file as it goes.
+
+use chrono::{DateTime, Local, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// Config file holding thread list display preferences
+pub const DISPLAY_CONFIG_FILE: &str = "orion.display.json";
+
+/// Most snippet lines a row can show
+pub const MAX_SNIPPET_LINES: u8 = 3;
+
+/// Height of one extra line of snippet text
+const SNIPPET_LINE_HEIGHT: f32 = 18.0;
+
+/// Vertical space given to each thread row
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Density {
+    Compact,
+    #[default]
+    Comfortable,
+    Spacious,
+}
+
+impl Density {
+    /// Height of a row showing at most one line of text
+    fn base_row_height(self) -> f32 {
+        match self {
+            Self::Compact => 32.0,
+            Self::Comfortable => 40.0,
+            Self::Spacious => 52.0,
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Self::Compact => Self::Comfortable,
+            Self::Comfortable => Self::Spacious,
+            Self::Spacious => Self::Compact,
+        }
+    }
+}
+
+/// How thread dates are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DateFormat {
+    /// Time today, weekday this week, else month and day
+    #[default]
+    Relative,
+    /// Month, day and year, e.g. "Mar 04, 2025"
+    Absolute,
+    /// "2025-03-04"
+    Iso,
+}
+
+impl DateFormat {
+    /// Format a date in local time
+    pub fn format(self, date: DateTime<Utc>) -> String {
+        let local = date.with_timezone(&Local);
+        match self {
+            Self::Relative => {
+                let now = Local::now();
+                if local.date_naive() == now.date_naive() {
+                    local.format("%H:%M").to_string()
+                } else if (now - local).num_days() < 7 {
+                    local.format("%a").to_string()
+                } else {
+                    local.format("%b %d").to_string()
+                }
+            }
+            Self::Absolute => local.format("%b %d, %Y").to_string(),
+            Self::Iso => local.format("%Y-%m-%d").to_string(),
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Self::Relative => Self::Absolute,
+            Self::Absolute => Self::Iso,
+            Self::Iso => Self::Relative,
+        }
+    }
+}
+
+/// Thread list display preferences
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplayConfig {
+    pub density: Density,
+    /// Snippet lines per row: 0 hides snippets, 1 shows them beside the
+    /// subject, more wrap them below it (up to `MAX_SNIPPET_LINES`)
+    pub snippet_lines: u8,
+    pub date_format: DateFormat,
+    /// Show a sender initial badge at the start of each row
+    pub show_avatars: bool,
+    /// Show "(n)" after the sender of threads with several messages
+    pub show_message_counts: bool,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            density: Density::Comfortable,
+            snippet_lines: 1,
+            date_format: DateFormat::Relative,
+            show_avatars: false,
+            show_message_counts: true,
+        }
+    }
+}
+
+impl DisplayConfig {
+    /// Load preferences from the config directory, falling back to defaults
+    pub fn load() -> Self {
+        if !config::config_exists(DISPLAY_CONFIG_FILE) {
+            return Self::default();
+        }
+        match config::load_json(DISPLAY_CONFIG_FILE) {
+            Ok(display) => display,
+            Err(e) => {
+                warn!("Ignoring invalid display config: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Persist preferences changed from inside the app
+    pub fn save(&self) {
+        if let Err(e) = config::save_json(DISPLAY_CONFIG_FILE, self) {
+            warn!("Failed to save display config: {}", e);
+        }
+    }
+
+    /// Snippet lines to show, capped at `MAX_SNIPPET_LINES`
+    pub fn snippet_lines(&self) -> u8 {
+        self.snippet_lines.min(MAX_SNIPPET_LINES)
+    }
+
+    /// Whether snippets wrap below the subject instead of following it
+    pub fn snippet_below_subject(&self) -> bool {
+        self.snippet_lines() > 1
+    }
+
+    /// Height of each thread row
+    pub fn row_height(&self) -> f32 {
+        let base = self.density.base_row_height();
+        if !self.snippet_below_subject() {
+            return base;
+        }
+        // The subject line stays; each snippet line adds to the row
+        base + SNIPPET_LINE_HEIGHT * f32::from(self.snippet_lines())
+    }
+
+    /// Move to the next density, wrapping around
+    pub fn cycle_density(&mut self) {
+        self.density = self.density.next();
+    }
+
+    /// Show one more snippet line, wrapping back to none
+    pub fn cycle_snippet_lines(&mut self) {
+        self.snippet_lines = (self.snippet_lines() + 1) % (MAX_SNIPPET_LINES + 1);
+    }
+
+    /// Move to the next date format, wrapping around
+    pub fn cycle_date_format(&mut self) {
+        self.date_format = self.date_format.next();
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! Actions are organized by context where they apply.
 
-use gpui::actions;
+use gpui::{actions, Action};
 
 // Navigation actions (thread list and thread detail)
 actions!(
@@ -22,6 +22,63 @@ actions!(
         ToggleStar, // S - toggle star
         ToggleRead, // U - toggle read/unread
         Trash,      // # - move to trash
+        EditLabels, // L - add or remove labels
+    ]
+);
+
+// Thread output actions (open thread or preview)
+actions!(
+    orion,
+    [
+        PrintThread,       // Cmd+P - print the thread without theme colors
+        ExportThreadAsPdf, // Command palette - save the thread as a PDF file
+    ]
+);
+
+// Multi-select actions (thread list)
+actions!(
+    orion,
+    [
+        ToggleSelect,   // X - check/uncheck thread for bulk actions
+        ClearSelection, // Escape - uncheck all threads
+    ]
+);
+
+// Compose actions (work in both list and detail views)
+actions!(
+    orion,
+    [
+        Compose, // C - write a new message in its own window
+    ]
+);
+
+/// Ctrl+1..9 - show only the nth account in the sidebar; Ctrl+0 (index 0)
+/// shows all accounts. Has no JSON form, so it stays out of the palette.
+#[derive(Clone, Debug, PartialEq, Action)]
+#[action(namespace = orion, no_json)]
+pub struct SwitchToAccount(pub usize);
+
+// Appearance actions (command palette only)
+actions!(
+    orion,
+    [
+        UseSystemTheme, // Follow the OS light/dark setting
+        UseLightTheme,  // Always use the light theme
+        UseDarkTheme,   // Always use the dark theme
+    ]
+);
+
+// Thread list display actions (command palette only)
+actions!(
+    orion,
+    [
+        CycleRowDensity,                // Compact → comfortable → spacious
+        CycleSnippetLines,              // Preview lines per row: none → 1 → 2 → 3
+        CycleDateFormat,                // Relative → absolute → ISO dates
+        ToggleAvatars,                  // Show or hide sender avatars
+        ToggleMessageCounts,            // Show or hide per-thread message counts
+        ToggleConversationView,         // List threads or single messages, for every label
+        ToggleConversationViewForLabel, // Same, for the current label only
     ]
 );
 
@@ -42,10 +99,13 @@ actions!(
 actions!(
     orion,
     [
-        ShowShortcuts, // ? - show keyboard shortcuts help
+        ShowShortcuts,      // ? - show keyboard shortcuts help
+        ShowCommandPalette, // Cmd+Shift+P - search and run commands
+        SyncNow,            // Command palette - sync now, ignoring the cooldown
+        ExportDiagnostics,  // Help menu - export redacted diagnostic log
         /// Dismiss current context and ascend to parent view.
         /// Hierarchy: Thread → List (search/inbox) → Inbox
-        /// Also closes overlays (shortcuts modal).
+        /// Also closes overlays (shortcuts modal, command palette).
         Dismiss,
     ]
 );
@@ -0,0 +1,149 @@
+//! Command registry for the command palette
+//!
+//! Every action in the `orion` namespace becomes a palette command
+//! automatically, titled from its name; declaring a new action in
+//! `actions.rs` is enough for it to show up. The app adds commands that
+//! depend on its state (labels, accounts, recent threads) when the palette
+//! opens.
+
+use gpui::{Action, App};
+use mail::ThreadId;
+
+/// Action namespace whose actions are offered in the palette
+const ACTION_NAMESPACE: &str = "orion::";
+
+/// Actions that only make sense as direct keystrokes
+const HIDDEN_ACTIONS: &[&str] = &[
+    "MoveUp",
+    "MoveDown",
+    "OpenSelected",
+    "Dismiss",
+    "ShowCommandPalette",
+];
+
+/// What running a command does
+pub enum CommandTarget {
+    /// Dispatch an action to the view that had focus before the palette
+    Action(Box<dyn Action>),
+    /// Show a label's threads
+    GoToLabel(String),
+    /// Filter to one account, or all accounts if None
+    SwitchAccount(Option<i64>),
+    /// Open a thread
+    OpenThread(ThreadId),
+}
+
+impl Clone for CommandTarget {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Action(action) => Self::Action(action.boxed_clone()),
+            Self::GoToLabel(label_id) => Self::GoToLabel(label_id.clone()),
+            Self::SwitchAccount(account_id) => Self::SwitchAccount(*account_id),
+            Self::OpenThread(thread_id) => Self::OpenThread(thread_id.clone()),
+        }
+    }
+}
+
+/// A palette entry
+#[derive(Clone)]
+pub struct Command {
+    /// Text shown and matched against
+    pub title: String,
+    /// Group shown beside the title, e.g. "Label" or "Thread"
+    pub category: &'static str,
+    pub target: CommandTarget,
+}
+
+impl Command {
+    pub fn new(title: impl Into<String>, category: &'static str, target: CommandTarget) -> Self {
+        Self {
+            title: title.into(),
+            category,
+            target,
+        }
+    }
+}
+
+/// Commands for every registered Orion action, sorted by title
+pub fn action_commands(cx: &App) -> Vec<Command> {
+    let mut commands: Vec<Command> = cx
+        .all_action_names()
+        .iter()
+        .filter_map(|name| {
+            let short_name = name.strip_prefix(ACTION_NAMESPACE)?;
+            if HIDDEN_ACTIONS.contains(&short_name) {
+                return None;
+            }
+            let action = cx.build_action(name, None).ok()?;
+            Some(Command::new(
+                humanize(short_name),
+                "Command",
+                CommandTarget::Action(action),
+            ))
+        })
+        .collect();
+    commands.sort_by(|a, b| a.title.cmp(&b.title));
+    commands
+}
+
+/// Turn an action name like "GoToAllMail" into "Go to all mail"
+fn humanize(name: &str) -> String {
+    let mut title = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if i == 0 {
+            title.push(c);
+        } else if c.is_uppercase() {
+            title.push(' ');
+            title.extend(c.to_lowercase());
+        } else {
+            title.push(c);
+        }
+    }
+    title
+}
+
+/// Score how well `query` fuzzy-matches `text`, or None if it doesn't
+///
+/// Every query character must appear in order (ignoring case). Matches at
+/// word starts and runs of consecutive characters score higher, and
+/// shorter texts win ties.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for q in query.chars().flat_map(char::to_lowercase) {
+        if q.is_whitespace() {
+            continue;
+        }
+        let found = position + text[position..].iter().position(|&c| c == q)?;
+        score += 1;
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 8;
+        }
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 5;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+
+    Some(score * 16 - text.len().min(15) as i32)
+}
+
+/// Indices of the commands matching `query`, best first
+///
+/// An empty query keeps every command in registry order.
+pub fn filter_commands(commands: &[Command], query: &str) -> Vec<usize> {
+    if query.trim().is_empty() {
+        return (0..commands.len()).collect();
+    }
+    let mut scored: Vec<(i32, usize)> = commands
+        .iter()
+        .enumerate()
+        .filter_map(|(ix, command)| Some((fuzzy_score(query, &command.title)?, ix)))
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    scored.into_iter().map(|(_, ix)| ix).collect()
+}
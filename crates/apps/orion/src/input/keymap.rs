@@ -6,8 +6,8 @@ use gpui::KeyBinding;
 
 use super::actions::*;
 use crate::app::FocusSearch;
-use crate::components::search_box;
-use crate::views::search_results;
+use crate::components::{command_palette, label_picker, reply_box, search_box};
+use crate::views::{compose, search_results};
 
 /// A category of keyboard shortcuts for display in help modal
 pub struct ShortcutCategory {
@@ -30,6 +30,30 @@ pub fn bindings() -> Vec<KeyBinding> {
         KeyBinding::new("escape", Dismiss, Some("OrionApp")),
         KeyBinding::new("/", FocusSearch, Some("OrionApp")),
         KeyBinding::new("cmd-k", FocusSearch, Some("OrionApp")),
+        KeyBinding::new("cmd-shift-p", ShowCommandPalette, Some("OrionApp")),
+        KeyBinding::new("cmd-p", PrintThread, Some("OrionApp")),
+        KeyBinding::new("ctrl-0", SwitchToAccount(0), Some("OrionApp")),
+        KeyBinding::new("ctrl-1", SwitchToAccount(1), Some("OrionApp")),
+        KeyBinding::new("ctrl-2", SwitchToAccount(2), Some("OrionApp")),
+        KeyBinding::new("ctrl-3", SwitchToAccount(3), Some("OrionApp")),
+        KeyBinding::new("ctrl-4", SwitchToAccount(4), Some("OrionApp")),
+        KeyBinding::new("ctrl-5", SwitchToAccount(5), Some("OrionApp")),
+        KeyBinding::new("ctrl-6", SwitchToAccount(6), Some("OrionApp")),
+        KeyBinding::new("ctrl-7", SwitchToAccount(7), Some("OrionApp")),
+        KeyBinding::new("ctrl-8", SwitchToAccount(8), Some("OrionApp")),
+        KeyBinding::new("ctrl-9", SwitchToAccount(9), Some("OrionApp")),
+        // ===== Command palette =====
+        KeyBinding::new("up", command_palette::SelectPrev, Some("CommandPalette")),
+        KeyBinding::new("ctrl-p", command_palette::SelectPrev, Some("CommandPalette")),
+        KeyBinding::new("down", command_palette::SelectNext, Some("CommandPalette")),
+        KeyBinding::new("ctrl-n", command_palette::SelectNext, Some("CommandPalette")),
+        KeyBinding::new("escape", command_palette::Cancel, Some("CommandPalette")),
+        // ===== Label picker =====
+        KeyBinding::new("up", label_picker::SelectPrev, Some("LabelPicker")),
+        KeyBinding::new("ctrl-p", label_picker::SelectPrev, Some("LabelPicker")),
+        KeyBinding::new("down", label_picker::SelectNext, Some("LabelPicker")),
+        KeyBinding::new("ctrl-n", label_picker::SelectNext, Some("LabelPicker")),
+        KeyBinding::new("escape", label_picker::Close, Some("LabelPicker")),
         // ===== Search box =====
         KeyBinding::new("escape", search_box::Escape, Some("SearchBox")),
         // ===== Search results =====
@@ -52,11 +76,22 @@ pub fn bindings() -> Vec<KeyBinding> {
         KeyBinding::new("s", ToggleStar, Some("ThreadListView")),
         KeyBinding::new("u", ToggleRead, Some("ThreadListView")),
         KeyBinding::new("shift-3", Trash, Some("ThreadListView")), // # key
+        KeyBinding::new("l", EditLabels, Some("ThreadListView")),
+        KeyBinding::new("x", ToggleSelect, Some("ThreadListView")),
+        KeyBinding::new("c", Compose, Some("ThreadListView")),
+        // Falls through to Dismiss when nothing is checked
+        KeyBinding::new("escape", ClearSelection, Some("ThreadListView")),
         // ===== Thread detail (ThreadView context) =====
         KeyBinding::new("e", Archive, Some("ThreadView")),
         KeyBinding::new("s", ToggleStar, Some("ThreadView")),
         KeyBinding::new("u", ToggleRead, Some("ThreadView")),
         KeyBinding::new("shift-3", Trash, Some("ThreadView")), // # key
+        KeyBinding::new("l", EditLabels, Some("ThreadView")),
+        KeyBinding::new("c", Compose, Some("ThreadView")),
+        // ===== Quick reply (under a thread) =====
+        KeyBinding::new("cmd-enter", reply_box::SendReply, Some("ReplyBox")),
+        // ===== Compose window =====
+        KeyBinding::new("cmd-enter", compose::SendMessage, Some("ComposeView")),
         // ===== Go-to folder shortcuts (G sequences) =====
         // These are handled via on_key_down in app.rs for multi-key sequences
     ]
@@ -84,6 +119,14 @@ pub fn shortcuts_help() -> Vec<ShortcutCategory> {
                     keys: "Escape",
                     description: "Go back / Close",
                 },
+                Shortcut {
+                    keys: "⌃1–9",
+                    description: "Show one account",
+                },
+                Shortcut {
+                    keys: "⌃0",
+                    description: "Show all accounts",
+                },
             ],
         },
         ShortcutCategory {
@@ -105,6 +148,44 @@ pub fn shortcuts_help() -> Vec<ShortcutCategory> {
                     keys: "#",
                     description: "Move to trash",
                 },
+                Shortcut {
+                    keys: "L",
+                    description: "Add or remove labels",
+                },
+                Shortcut {
+                    keys: "⌘P",
+                    description: "Print thread",
+                },
+            ],
+        },
+        ShortcutCategory {
+            name: "Compose",
+            shortcuts: vec![
+                Shortcut {
+                    keys: "C",
+                    description: "New message",
+                },
+                Shortcut {
+                    keys: "⌘Enter",
+                    description: "Send message or reply",
+                },
+            ],
+        },
+        ShortcutCategory {
+            name: "Selection",
+            shortcuts: vec![
+                Shortcut {
+                    keys: "X",
+                    description: "Select thread",
+                },
+                Shortcut {
+                    keys: "⇧ Click",
+                    description: "Select range",
+                },
+                Shortcut {
+                    keys: "Escape",
+                    description: "Clear selection",
+                },
             ],
         },
         ShortcutCategory {
@@ -151,10 +232,16 @@ pub fn shortcuts_help() -> Vec<ShortcutCategory> {
         },
         ShortcutCategory {
             name: "Help",
-            shortcuts: vec![Shortcut {
-                keys: "?",
-                description: "Show this help",
-            }],
+            shortcuts: vec![
+                Shortcut {
+                    keys: "⌘⇧P",
+                    description: "Command palette",
+                },
+                Shortcut {
+                    keys: "?",
+                    description: "Show this help",
+                },
+            ],
         },
     ]
 }
@@ -3,7 +3,9 @@
 //! Provides Gmail/Superhuman-style keybindings with context-aware dispatch.
 
 pub mod actions;
+pub mod commands;
 pub mod keymap;
 
 pub use actions::*;
+pub use commands::{action_commands, filter_commands, Command, CommandTarget};
 pub use keymap::{bindings, shortcuts_help, ShortcutCategory};
@@ -0,0 +1,68 @@
+//! Reading layout settings
+//!
+//! Loaded from `orion.layout.json` in the Cosmos config directory, e.g.
+//! `{"split": "vertical", "mark_read_after_ms": 1500}`.
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Config file holding reading layout settings
+pub const LAYOUT_CONFIG_FILE: &str = "orion.layout.json";
+
+/// How the thread list and the reading pane share the window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SplitMode {
+    /// Threads open full-size in place of the list
+    #[default]
+    Off,
+    /// List on the left, preview on the right
+    Vertical,
+    /// List on top, preview below
+    Horizontal,
+}
+
+/// Reading layout settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    pub split: SplitMode,
+    /// How long a thread must stay in the preview pane before it is marked read
+    pub mark_read_after_ms: u64,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            split: SplitMode::Off,
+            mark_read_after_ms: 1500,
+        }
+    }
+}
+
+impl LayoutConfig {
+    /// Load settings from the config directory, falling back to defaults
+    pub fn load() -> Self {
+        if !config::config_exists(LAYOUT_CONFIG_FILE) {
+            return Self::default();
+        }
+        match config::load_json(LAYOUT_CONFIG_FILE) {
+            Ok(layout) => layout,
+            Err(e) => {
+                warn!("Ignoring invalid layout config: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Whether the thread list and preview pane are shown side by side
+    pub fn is_split(&self) -> bool {
+        self.split != SplitMode::Off
+    }
+
+    /// Dwell time before a previewed thread is marked read
+    pub fn mark_read_delay(&self) -> Duration {
+        Duration::from_millis(self.mark_read_after_ms)
+    }
+}
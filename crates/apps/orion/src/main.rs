@@ -5,19 +5,26 @@
 use std::time::Instant;
 
 use gpui::prelude::*;
-use gpui::{px, size, Application, WindowOptions};
-use gpui_component::{Root, Theme, ThemeMode, TitleBar};
+use gpui::{px, size, Application, Menu, MenuItem, WindowOptions};
+use gpui_component::{Root, TitleBar};
 use log::{debug, error, info, warn};
 use mail::GmailCredentials;
 
 mod app;
+mod appearance;
 mod assets;
 mod components;
+mod conversation;
+mod display;
 mod input;
+mod layout;
+mod session;
 mod templates;
+mod tray;
 mod views;
 
 use app::OrionApp;
+use appearance::AppearanceConfig;
 use assets::OrionAssets;
 
 fn main() {
@@ -30,26 +37,61 @@ fn main() {
 
     debug!("[BOOT] Logger initialized: {:?}", startup_start.elapsed());
 
+    // Choose the profile before anything reads a config or data path
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match config::profile_arg(&args).and_then(config::select_profile) {
+        Ok(Some(profile)) => info!("Using profile {}", profile),
+        Ok(None) => {}
+        Err(e) => {
+            error!("{:#}", e);
+            std::process::exit(1);
+        }
+    }
+
     // Bootstrap config directory
     if let Err(e) = config::init() {
         error!("Failed to initialize config directory: {}", e);
     }
+    match config::migrate_data() {
+        Ok(moved) => {
+            for path in moved {
+                info!("Moved mail data to {}", path.display());
+            }
+        }
+        Err(e) => warn!("Failed to move mail data to the data directory: {:#}", e),
+    }
     debug!("[BOOT] Config init: {:?}", startup_start.elapsed());
 
+    // Persist the diagnostic ring buffer so it survives crashes
+    if let Some(path) = config::config_path("diagnostics.json") {
+        mail::init_diagnostics(&path);
+    }
+
     Application::new()
         .with_assets(OrionAssets)
         .run(move |cx| {
         debug!("[BOOT] GPUI Application created: {:?}", startup_start.elapsed());
 
-        // Initialize gpui-component and set dark mode
+        // Initialize gpui-component and apply the configured theme
         gpui_component::init(cx);
         debug!("[BOOT] gpui-component init: {:?}", startup_start.elapsed());
-        Theme::change(ThemeMode::Dark, None, cx);
+        AppearanceConfig::load().apply(cx);
         debug!("[BOOT] Theme set: {:?}", startup_start.elapsed());
 
         // Register keyboard shortcuts from input module
         cx.bind_keys(input::bindings());
 
+        // Application menus
+        cx.set_menus(vec![Menu {
+            name: "Help".into(),
+            items: vec![
+                MenuItem::action("Command Palette", input::ShowCommandPalette),
+                MenuItem::action("Keyboard Shortcuts", input::ShowShortcuts),
+                MenuItem::separator(),
+                MenuItem::action("Export Diagnostics…", input::ExportDiagnostics),
+            ],
+        }]);
+
         let window_options = WindowOptions {
             window_bounds: Some(gpui::WindowBounds::Windowed(gpui::Bounds {
                 origin: gpui::Point::default(),
@@ -97,6 +139,8 @@ fn main() {
             let app_handle = app_entity.clone();
             app_entity.update(cx, |app, cx| {
                 app.wire_navigation(app_handle, cx);
+                app.follow_system_appearance(window, cx);
+                app.watch_config_files(cx);
                 debug!("[BOOT] Navigation wired: {:?}", startup_start.elapsed());
 
                 // Start loading persistent storage in background
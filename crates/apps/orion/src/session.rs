@@ -0,0 +1,43 @@
+//! State remembered between launches
+//!
+//! Saved to `orion.session.json` in the Cosmos config directory, e.g.
+//! `{"selected_account": "me@example.com"}`. The app rewrites it whenever
+//! the state changes, so there is little point editing it by hand.
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// Config file holding the remembered session state
+pub const SESSION_FILE: &str = "orion.session.json";
+
+/// State restored on the next launch
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Email of the account the views are filtered to; None shows all.
+    /// Stored by email because account IDs change if an account is re-added.
+    pub selected_account: Option<String>,
+}
+
+impl Session {
+    /// Load the last session, or start fresh
+    pub fn load() -> Self {
+        if !config::config_exists(SESSION_FILE) {
+            return Self::default();
+        }
+        match config::load_json(SESSION_FILE) {
+            Ok(session) => session,
+            Err(e) => {
+                warn!("Ignoring invalid session file: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Persist the session for the next launch
+    pub fn save(&self) {
+        if let Err(e) = config::save_json(SESSION_FILE, self) {
+            warn!("Failed to save session: {}", e);
+        }
+    }
+}
@@ -5,7 +5,7 @@
 
 use gpui_component::theme::Theme;
 use log::debug;
-use mail::Message;
+use mail::{HIGHLIGHT_CLASS, Message, highlight_html};
 
 /// Convert HSLA color to CSS hex string
 fn hsla_to_hex(color: gpui::Hsla) -> String {
@@ -29,6 +29,8 @@ pub fn html_escape(s: &str) -> String {
 
 /// Theme colors extracted for CSS usage
 struct ThemeColors {
+    /// CSS `color-scheme`, so native widgets match the theme
+    scheme: &'static str,
     background: String,
     foreground: String,
     secondary: String,
@@ -37,11 +39,18 @@ struct ThemeColors {
     link: String,
     danger: String,
     danger_foreground: String,
+    warning: String,
+    warning_foreground: String,
 }
 
 impl ThemeColors {
     fn from_theme(theme: &Theme) -> Self {
         Self {
+            scheme: if theme.mode.is_dark() {
+                "dark"
+            } else {
+                "light"
+            },
             background: hsla_to_hex(theme.background),
             foreground: hsla_to_hex(theme.foreground),
             secondary: hsla_to_hex(theme.secondary),
@@ -50,6 +59,8 @@ impl ThemeColors {
             link: hsla_to_hex(theme.link),
             danger: hsla_to_hex(theme.danger),
             danger_foreground: hsla_to_hex(theme.danger_foreground),
+            warning: hsla_to_hex(theme.warning),
+            warning_foreground: hsla_to_hex(theme.warning_foreground),
         }
     }
 }
@@ -62,12 +73,13 @@ html, body {{
     font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Oxygen, Ubuntu, sans-serif;
     background: {bg} !important;
     color: {fg};
+    color-scheme: {scheme};
     padding: 0;
     margin: 0;
     line-height: 1.5;
     min-height: 100%;
 }}
-/* Dark scrollbar styling */
+/* Scrollbar styling to match the theme */
 ::-webkit-scrollbar {{
     width: 8px;
     height: 8px;
@@ -82,6 +94,7 @@ html, body {{
 ::-webkit-scrollbar-thumb:hover {{
     background: {muted};
 }}"#,
+        scheme = colors.scheme,
         bg = colors.background,
         fg = colors.foreground,
         border = colors.border,
@@ -142,6 +155,59 @@ fn message_styles(colors: &ThemeColors) -> String {
     )
 }
 
+/// Generate CSS styles for search term highlights
+fn highlight_styles(colors: &ThemeColors) -> String {
+    format!(
+        r#"mark.{class} {{
+    background: {bg};
+    color: {fg};
+    border-radius: 2px;
+}}"#,
+        class = HIGHLIGHT_CLASS,
+        bg = colors.warning,
+        fg = colors.warning_foreground,
+    )
+}
+
+/// Generate CSS that prints threads as black text on white paper
+///
+/// Overrides the theme colors so a dark theme doesn't waste ink.
+fn print_styles() -> String {
+    r#"@media print {
+    html, body { background: #fff !important; color: #000; }
+    .orion-message {
+        background: none;
+        border: none;
+        border-bottom: 1px solid #ccc;
+        border-radius: 0;
+        break-inside: avoid-page;
+    }
+    .orion-message-inner { padding: 12px 0; }
+    .orion-header { border-bottom-color: #ccc; }
+    .orion-sender, .orion-body { color: #000; }
+    .orion-email, .orion-date, .orion-recipients { color: #555; }
+    .orion-body a { color: #000; text-decoration: underline; }
+    .orion-body-text { padding: 0; }
+    mark { background: none; color: inherit; }
+    ::-webkit-scrollbar { display: none; }
+}"#
+    .to_string()
+}
+
+/// Script that scrolls the first search highlight into view once loaded
+fn scroll_to_highlight_script() -> String {
+    format!(
+        r#"<script>
+document.addEventListener("DOMContentLoaded", function () {{
+    var hit = document.querySelector("mark.{}");
+    if (hit) {{ hit.scrollIntoView({{ block: "center" }}); }}
+}});
+</script>
+"#,
+        HIGHLIGHT_CLASS
+    )
+}
+
 /// Generate CSS styles for error display
 fn error_styles(colors: &ThemeColors) -> String {
     format!(
@@ -158,7 +224,9 @@ fn error_styles(colors: &ThemeColors) -> String {
 }
 
 /// Generate HTML for a single message
-fn render_message(message: &Message) -> String {
+///
+/// Occurrences of `highlight_terms` in the body are wrapped in marks.
+fn render_message(message: &Message, highlight_terms: &[String]) -> String {
     let sender_name = message
         .from
         .name
@@ -202,6 +270,11 @@ fn render_message(message: &Message) -> String {
             .unwrap_or(&message.body_preview);
         html_escape(text).replace('\n', "<br>")
     };
+    let body_content = if highlight_terms.is_empty() {
+        body_content
+    } else {
+        highlight_html(&body_content, highlight_terms)
+    };
 
     // Use different class for plain text vs HTML bodies
     let body_class = if has_html {
@@ -248,8 +321,10 @@ fn render_message(message: &Message) -> String {
 /// Generate combined HTML for all messages in a thread with theme colors
 ///
 /// This is called by OrionApp before navigation to generate HTML content
-/// that will be loaded into the shared WebView.
-pub fn thread_html(messages: &[Message], theme: &Theme) -> String {
+/// that will be loaded into the shared WebView. When the thread was opened
+/// from search, `highlight_terms` are marked in message bodies and the first
+/// match is scrolled into view.
+pub fn thread_html(messages: &[Message], theme: &Theme, highlight_terms: &[String]) -> String {
     let colors = ThemeColors::from_theme(theme);
 
     let mut html = format!(
@@ -261,18 +336,25 @@ pub fn thread_html(messages: &[Message], theme: &Theme) -> String {
 <style>
 {}
 {}
+{}
+{}
 </style>
 </head>
 <body>
 "#,
         base_styles(&colors),
         message_styles(&colors),
+        highlight_styles(&colors),
+        print_styles(),
     );
 
     for message in messages {
-        html.push_str(&render_message(message));
+        html.push_str(&render_message(message, highlight_terms));
     }
 
+    if !highlight_terms.is_empty() {
+        html.push_str(&scroll_to_highlight_script());
+    }
     html.push_str("</body></html>");
     html
 }
@@ -0,0 +1,159 @@
+//! Menu bar extra (NSStatusItem) for macOS
+//!
+//! Menu items all target one small Objective-C object. Each item's tag
+//! indexes the list of commands for the current menu, and the object sends
+//! the chosen command to the app over a channel.
+
+use std::cell::RefCell;
+
+use log::warn;
+use objc2::rc::Retained;
+use objc2::runtime::NSObject;
+use objc2::{define_class, msg_send, sel, DefinedClass, MainThreadMarker, MainThreadOnly};
+use objc2_app_kit::{NSMenu, NSMenuItem, NSStatusBar, NSStatusItem, NSVariableStatusItemLength};
+use objc2_foundation::NSString;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use super::{TrayCommand, TraySummary};
+
+/// Shown in the menu bar when nothing is unread
+const IDLE_TITLE: &str = "✉";
+
+struct TargetIvars {
+    commands: UnboundedSender<TrayCommand>,
+    /// Commands for the current menu, indexed by item tag
+    menu_commands: RefCell<Vec<TrayCommand>>,
+}
+
+define_class!(
+    // SAFETY: NSObject has no subclassing requirements, and TrayTarget
+    // doesn't implement Drop.
+    #[unsafe(super(NSObject))]
+    #[thread_kind = MainThreadOnly]
+    #[name = "OrionTrayTarget"]
+    #[ivars = TargetIvars]
+    struct TrayTarget;
+
+    impl TrayTarget {
+        #[unsafe(method(runCommand:))]
+        fn run_command(&self, sender: &NSMenuItem) {
+            let ivars = self.ivars();
+            let command = usize::try_from(sender.tag())
+                .ok()
+                .and_then(|ix| ivars.menu_commands.borrow().get(ix).cloned());
+            if let Some(command) = command {
+                ivars.commands.send(command).ok();
+            }
+        }
+    }
+);
+
+impl TrayTarget {
+    fn new(mtm: MainThreadMarker, commands: UnboundedSender<TrayCommand>) -> Retained<Self> {
+        let this = Self::alloc(mtm).set_ivars(TargetIvars {
+            commands,
+            menu_commands: RefCell::new(Vec::new()),
+        });
+        // SAFETY: NSObject's init takes no arguments
+        unsafe { msg_send![super(this), init] }
+    }
+}
+
+struct StatusItem {
+    mtm: MainThreadMarker,
+    item: Retained<NSStatusItem>,
+    target: Retained<TrayTarget>,
+}
+
+/// Menu bar extra showing the unread count
+pub struct Tray {
+    /// None when created off the main thread
+    status_item: Option<StatusItem>,
+}
+
+impl Tray {
+    /// Create the tray and the receiver of chosen menu commands
+    ///
+    /// Must be called on the main thread; anywhere else the tray is inert.
+    pub fn new() -> (Self, UnboundedReceiver<TrayCommand>) {
+        let (sender, commands) = unbounded_channel();
+        let Some(mtm) = MainThreadMarker::new() else {
+            warn!("Tray created off the main thread; menu bar extra disabled");
+            return (Self { status_item: None }, commands);
+        };
+
+        let item = NSStatusBar::systemStatusBar().statusItemWithLength(NSVariableStatusItemLength);
+        let target = TrayTarget::new(mtm, sender);
+        let tray = Self {
+            status_item: Some(StatusItem { mtm, item, target }),
+        };
+        tray.update(&TraySummary::default());
+        (tray, commands)
+    }
+
+    /// Show a new summary, rebuilding the menu
+    pub fn update(&self, summary: &TraySummary) {
+        let Some(status_item) = &self.status_item else {
+            return;
+        };
+        let mtm = status_item.mtm;
+
+        if let Some(button) = status_item.item.button(mtm) {
+            let title = match summary.badge() {
+                badge if badge.is_empty() => IDLE_TITLE.to_string(),
+                badge => format!("{} {}", IDLE_TITLE, badge),
+            };
+            button.setTitle(&NSString::from_str(&title));
+        }
+
+        let menu = NSMenu::new(mtm);
+        let mut menu_commands = Vec::new();
+        let mut add = |title: &str, command: TrayCommand| {
+            menu.addItem(&status_item.menu_item(title, menu_commands.len()));
+            menu_commands.push(command);
+        };
+
+        if summary.threads.is_empty() {
+            add("No mail in Inbox", TrayCommand::ShowApp);
+        }
+        for thread in &summary.threads {
+            let title = match thread.is_unread {
+                true => format!("● {}", thread.title),
+                false => thread.title.clone(),
+            };
+            add(&title, TrayCommand::OpenThread(thread.thread_id.clone()));
+        }
+        menu.addItem(&NSMenuItem::separatorItem(mtm));
+        add("New Message", TrayCommand::Compose);
+        add("Sync Now", TrayCommand::SyncNow);
+        add("Open Orion", TrayCommand::ShowApp);
+
+        *status_item.target.ivars().menu_commands.borrow_mut() = menu_commands;
+        status_item.item.setMenu(Some(&menu));
+    }
+}
+
+impl StatusItem {
+    /// A menu item that runs the command at `index` when chosen
+    fn menu_item(&self, title: &str, index: usize) -> Retained<NSMenuItem> {
+        // SAFETY: runCommand: is implemented by TrayTarget, the item's target
+        let item = unsafe {
+            NSMenuItem::initWithTitle_action_keyEquivalent(
+                NSMenuItem::alloc(self.mtm),
+                &NSString::from_str(title),
+                Some(sel!(runCommand:)),
+                &NSString::new(),
+            )
+        };
+        // SAFETY: the target outlives the menu; both belong to this StatusItem
+        unsafe { item.setTarget(Some(&self.target)) };
+        item.setTag(index as isize);
+        item
+    }
+}
+
+impl Drop for StatusItem {
+    fn drop(&mut self) {
+        NSStatusBar::systemStatusBar().removeStatusItem(&self.item);
+    }
+}
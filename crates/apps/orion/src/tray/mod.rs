@@ -0,0 +1,100 @@
+//! Menu bar unread badge
+//!
+//! On macOS a menu bar extra shows the unified inbox unread count. Its menu
+//! lists the newest inbox threads and a few quick actions. The app refreshes
+//! it when the store reports a change, never on a timer. Other platforms
+//! have no tray backend yet, so the tray there does nothing.
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(not(target_os = "macos"))]
+mod unsupported;
+
+#[cfg(target_os = "macos")]
+pub use macos::Tray;
+#[cfg(not(target_os = "macos"))]
+pub use unsupported::Tray;
+
+use log::warn;
+use mail::{LabelId, MailStore, ThreadId};
+
+/// Number of threads listed in the tray menu
+const TRAY_THREAD_COUNT: usize = 5;
+
+/// Longest thread title shown in the tray menu, in characters
+const MAX_TITLE_CHARS: usize = 48;
+
+/// What choosing a tray menu item does
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrayCommand {
+    /// Bring the app forward
+    ShowApp,
+    /// Bring the app forward on a thread
+    OpenThread(ThreadId),
+    /// Open a compose window
+    Compose,
+    /// Sync every account now
+    SyncNow,
+}
+
+/// A thread listed in the tray menu
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrayThread {
+    pub thread_id: ThreadId,
+    /// "Sender: Subject", shortened to fit the menu
+    pub title: String,
+    pub is_unread: bool,
+}
+
+/// What the tray shows
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraySummary {
+    /// Unread inbox threads across all accounts
+    pub unread: usize,
+    /// Newest inbox threads, newest first
+    pub threads: Vec<TrayThread>,
+}
+
+impl TraySummary {
+    /// Read the current summary from the store
+    pub fn load(store: &dyn MailStore) -> Self {
+        let unread = store
+            .count_unread_threads_by_label(LabelId::INBOX)
+            .unwrap_or_else(|e| {
+                warn!("Failed to count unread threads for the tray: {}", e);
+                0
+            });
+        let threads = store
+            .list_threads_by_label(LabelId::INBOX, TRAY_THREAD_COUNT, 0)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|thread| {
+                let sender = thread.sender_name.unwrap_or(thread.sender_email);
+                TrayThread {
+                    thread_id: thread.id,
+                    title: truncate(&format!("{}: {}", sender, thread.subject)),
+                    is_unread: thread.is_unread,
+                }
+            })
+            .collect();
+        Self { unread, threads }
+    }
+
+    /// Text for the badge, empty when nothing is unread
+    pub fn badge(&self) -> String {
+        match self.unread {
+            0 => String::new(),
+            n if n > 999 => "999+".to_string(),
+            n => n.to_string(),
+        }
+    }
+}
+
+fn truncate(title: &str) -> String {
+    if title.chars().count() <= MAX_TITLE_CHARS {
+        return title.to_string();
+    }
+    let mut short: String = title.chars().take(MAX_TITLE_CHARS - 1).collect();
+    short.push('…');
+    short
+}
@@ -0,0 +1,19 @@
+//! Tray stand-in for platforms without a backend
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+use super::{TrayCommand, TraySummary};
+
+/// Tray that shows nothing and never sends commands
+pub struct Tray;
+
+impl Tray {
+    /// Create the tray and the receiver of chosen menu commands
+    pub fn new() -> (Self, UnboundedReceiver<TrayCommand>) {
+        let (_, commands) = unbounded_channel();
+        (Self, commands)
+    }
+
+    /// Show a new summary
+    pub fn update(&self, _summary: &TraySummary) {}
+}
@@ -0,0 +1,715 @@
+//! Compose window
+//!
+//! Each new message gets its own window holding a ComposeView. The body is
+//! Markdown, sent as plain text with an HTML rendering alongside. Edits are
+//! saved to Gmail drafts a couple of seconds after typing stops, so closing
+//! the window (or the app) never loses more than the last few keystrokes.
+
+use chrono::{DateTime, Local};
+use gpui::prelude::*;
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::{ActiveTheme, Disableable, Icon, IconName, Sizable};
+use log::{error, info, warn};
+use mail::{
+    Account, ActionHandler, EmailAddress, OutgoingAttachment, OutgoingMessage, SearchIndex,
+};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long typing must pause before the draft is saved
+const AUTOSAVE_DELAY: Duration = Duration::from_secs(2);
+
+/// Maximum number of contact suggestions under a recipient field
+const CONTACT_SUGGESTION_LIMIT: usize = 6;
+
+/// A recipient input
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RecipientField {
+    To,
+    Cc,
+    Bcc,
+}
+
+/// What the status line reports
+enum ComposeStatus {
+    Idle,
+    Saving,
+    Saved(DateTime<Local>),
+    Sending,
+    Error(String),
+}
+
+/// A new message being written
+pub struct ComposeView {
+    account: Account,
+    handler: Arc<ActionHandler>,
+    search_index: Option<Arc<SearchIndex>>,
+    window_handle: AnyWindowHandle,
+    to: Entity<InputState>,
+    cc: Entity<InputState>,
+    bcc: Entity<InputState>,
+    subject: Entity<InputState>,
+    body: Entity<InputState>,
+    show_cc_bcc: bool,
+    attachments: Vec<OutgoingAttachment>,
+    /// Contacts matching the last address typed into `suggestion_field`
+    suggestions: Vec<EmailAddress>,
+    suggestion_field: Option<RecipientField>,
+    /// Gmail draft holding the saved copy, once there is one
+    draft_id: Option<String>,
+    /// Bumped on every edit; compared with `saved_revision` to skip no-op saves
+    revision: u64,
+    saved_revision: u64,
+    save_in_flight: bool,
+    /// Send once the save in flight finishes, so it can't create a second draft
+    send_after_save: bool,
+    sending: bool,
+    status: ComposeStatus,
+    autosave_task: Option<Task<()>>,
+    #[allow(dead_code)]
+    subscriptions: Vec<Subscription>,
+}
+
+impl ComposeView {
+    pub fn new(
+        account: Account,
+        handler: Arc<ActionHandler>,
+        search_index: Option<Arc<SearchIndex>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let to = cx.new(|cx| InputState::new(window, cx).placeholder("To"));
+        let cc = cx.new(|cx| InputState::new(window, cx).placeholder("Cc"));
+        let bcc = cx.new(|cx| InputState::new(window, cx).placeholder("Bcc"));
+        let subject = cx.new(|cx| InputState::new(window, cx).placeholder("Subject"));
+        let body = cx.new(|cx| {
+            InputState::new(window, cx)
+                .multi_line(true)
+                .placeholder("Write your message (Markdown supported)")
+        });
+
+        let subscriptions = [&to, &cc, &bcc, &subject, &body]
+            .into_iter()
+            .map(|input| cx.subscribe_in(input, window, Self::on_input_event))
+            .collect();
+
+        to.update(cx, |state, cx| state.focus(window, cx));
+        window.set_window_title(&format!("New Message — {}", account.email));
+
+        Self {
+            account,
+            handler,
+            search_index,
+            window_handle: window.window_handle(),
+            to,
+            cc,
+            bcc,
+            subject,
+            body,
+            show_cc_bcc: false,
+            attachments: Vec::new(),
+            suggestions: Vec::new(),
+            suggestion_field: None,
+            draft_id: None,
+            revision: 0,
+            saved_revision: 0,
+            save_in_flight: false,
+            send_after_save: false,
+            sending: false,
+            status: ComposeStatus::Idle,
+            autosave_task: None,
+            subscriptions,
+        }
+    }
+
+    fn on_input_event(
+        &mut self,
+        input: &Entity<InputState>,
+        event: &InputEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let field = self.recipient_field(input);
+        match event {
+            InputEvent::Change => {
+                self.mark_edited(cx);
+                match field {
+                    Some(field) => self.update_suggestions(field, cx),
+                    None => self.clear_suggestions(cx),
+                }
+            }
+            InputEvent::PressEnter { .. } => {
+                if field.is_some() && !self.suggestions.is_empty() {
+                    self.accept_suggestion(0, window, cx);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn recipient_field(&self, input: &Entity<InputState>) -> Option<RecipientField> {
+        if input == &self.to {
+            Some(RecipientField::To)
+        } else if input == &self.cc {
+            Some(RecipientField::Cc)
+        } else if input == &self.bcc {
+            Some(RecipientField::Bcc)
+        } else {
+            None
+        }
+    }
+
+    fn recipient_input(&self, field: RecipientField) -> &Entity<InputState> {
+        match field {
+            RecipientField::To => &self.to,
+            RecipientField::Cc => &self.cc,
+            RecipientField::Bcc => &self.bcc,
+        }
+    }
+
+    fn text(input: &Entity<InputState>, cx: &App) -> String {
+        input.read(cx).text().to_string()
+    }
+
+    // === Contact autocomplete ===
+
+    /// Suggest contacts for the address being typed, the text after the last comma
+    fn update_suggestions(&mut self, field: RecipientField, cx: &mut Context<Self>) {
+        let text = Self::text(self.recipient_input(field), cx);
+        let partial = text.rsplit(',').next().unwrap_or_default().trim();
+        let Some(index) = self.search_index.as_ref().filter(|_| !partial.is_empty()) else {
+            self.clear_suggestions(cx);
+            return;
+        };
+
+        self.suggestions = match index.suggest_contacts(partial, CONTACT_SUGGESTION_LIMIT) {
+            Ok(contacts) => contacts,
+            Err(e) => {
+                warn!("Contact suggestions failed: {}", e);
+                Vec::new()
+            }
+        };
+        self.suggestion_field = Some(field);
+        cx.notify();
+    }
+
+    fn clear_suggestions(&mut self, cx: &mut Context<Self>) {
+        if !self.suggestions.is_empty() {
+            self.suggestions.clear();
+            self.suggestion_field = None;
+            cx.notify();
+        }
+    }
+
+    /// Replace the partly typed address with a suggested contact
+    fn accept_suggestion(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let (Some(field), Some(contact)) = (self.suggestion_field, self.suggestions.get(index))
+        else {
+            return;
+        };
+        // A comma in the display name would split the address when parsed back
+        let address = match &contact.name {
+            Some(name) if !name.contains(',') => contact.display(),
+            _ => contact.email.clone(),
+        };
+
+        let input = self.recipient_input(field).clone();
+        let text = Self::text(&input, cx);
+        let kept = match text.rfind(',') {
+            Some(comma) => format!("{}, ", text[..comma].trim_end()),
+            None => String::new(),
+        };
+        let value = format!("{}{}, ", kept, address);
+        input.update(cx, |state, cx| state.set_value(value, window, cx));
+
+        self.suggestions.clear();
+        self.suggestion_field = None;
+        cx.notify();
+    }
+
+    // === Building and saving ===
+
+    /// Parse a comma-separated recipient field
+    fn addresses(input: &Entity<InputState>, cx: &App) -> Vec<EmailAddress> {
+        Self::text(input, cx)
+            .split(',')
+            .map(EmailAddress::parse)
+            .filter(|address| !address.email.is_empty())
+            .collect()
+    }
+
+    fn build_message(&self, cx: &App) -> OutgoingMessage {
+        let mut message = OutgoingMessage::compose(&self.account)
+            .subject(Self::text(&self.subject, cx))
+            .body_markdown(Self::text(&self.body, cx));
+        for address in Self::addresses(&self.to, cx) {
+            message = message.to(address);
+        }
+        for address in Self::addresses(&self.cc, cx) {
+            message = message.cc(address);
+        }
+        for address in Self::addresses(&self.bcc, cx) {
+            message = message.bcc(address);
+        }
+        for attachment in &self.attachments {
+            message = message.attach(attachment.clone());
+        }
+        message
+    }
+
+    /// Whether there is nothing worth keeping as a draft
+    fn is_blank(&self, cx: &App) -> bool {
+        self.attachments.is_empty()
+            && [&self.to, &self.cc, &self.bcc, &self.subject, &self.body]
+                .iter()
+                .all(|input| Self::text(input, cx).trim().is_empty())
+    }
+
+    /// Note an edit and restart the autosave timer
+    fn mark_edited(&mut self, cx: &mut Context<Self>) {
+        self.revision += 1;
+        self.schedule_autosave(cx);
+    }
+
+    fn schedule_autosave(&mut self, cx: &mut Context<Self>) {
+        // Replacing the task cancels the previous timer
+        self.autosave_task = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(AUTOSAVE_DELAY).await;
+            cx.update(|cx| this.update(cx, |view, cx| view.save_draft(cx)))
+                .ok();
+        }));
+    }
+
+    /// Save the current content to Gmail drafts if it changed since the last save
+    fn save_draft(&mut self, cx: &mut Context<Self>) {
+        self.autosave_task = None;
+        if self.sending || self.save_in_flight || self.revision == self.saved_revision {
+            return;
+        }
+        if self.draft_id.is_none() && self.is_blank(cx) {
+            return;
+        }
+
+        let message = self.build_message(cx);
+        let handler = self.handler.clone();
+        let draft_id = self.draft_id.clone();
+        let revision = self.revision;
+        self.save_in_flight = true;
+        self.status = ComposeStatus::Saving;
+        cx.notify();
+
+        let background = cx.background_executor().clone();
+        cx.spawn(async move |this, cx| {
+            let result = background
+                .spawn(async move { handler.save_draft(&message, draft_id.as_deref()) })
+                .await;
+
+            cx.update(|cx| {
+                this.update(cx, |view, cx| {
+                    view.save_in_flight = false;
+                    match result {
+                        Ok(id) => {
+                            view.draft_id = Some(id);
+                            view.saved_revision = revision;
+                            view.status = ComposeStatus::Saved(Local::now());
+                        }
+                        Err(e) => {
+                            warn!("Failed to save draft: {}", e);
+                            view.status = ComposeStatus::Error(format!("Draft not saved: {}", e));
+                        }
+                    }
+                    if view.send_after_save {
+                        view.send_after_save = false;
+                        view.send(cx);
+                    } else if view.revision != view.saved_revision {
+                        // Edited while saving
+                        view.schedule_autosave(cx);
+                    }
+                    cx.notify();
+                })
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    // === Sending and discarding ===
+
+    /// Send the message and close the window
+    fn send(&mut self, cx: &mut Context<Self>) {
+        if self.sending {
+            return;
+        }
+        let message = self.build_message(cx);
+        if !message.has_recipients() {
+            self.status = ComposeStatus::Error("Add at least one recipient".to_string());
+            cx.notify();
+            return;
+        }
+        if self.save_in_flight {
+            self.send_after_save = true;
+            self.status = ComposeStatus::Sending;
+            cx.notify();
+            return;
+        }
+
+        self.sending = true;
+        self.autosave_task = None;
+        self.status = ComposeStatus::Sending;
+        cx.notify();
+
+        let handler = self.handler.clone();
+        let draft_id = self.draft_id.clone();
+        let window_handle = self.window_handle;
+        let background = cx.background_executor().clone();
+        cx.spawn(async move |this, cx| {
+            let result = background
+                .spawn(async move { handler.queue_send(&message, draft_id.as_deref()) })
+                .await;
+
+            cx.update(|cx| match result {
+                Ok(entry) => {
+                    // Unsent messages stay in the outbox and are retried by sync
+                    match entry.sent_message_id {
+                        Some(id) => info!("Sent message {}", id),
+                        None => info!("Message {} left in the outbox to retry", entry.id),
+                    }
+                    cx.update_window(window_handle, |_, window, _| window.remove_window())
+                        .ok();
+                }
+                Err(e) => {
+                    error!("Failed to send message: {}", e);
+                    this.update(cx, |view, cx| {
+                        view.sending = false;
+                        view.status = ComposeStatus::Error(format!("Not sent: {}", e));
+                        cx.notify();
+                    })
+                    .ok();
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Delete the draft, if one was saved, and close the window
+    fn discard(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.sending {
+            return;
+        }
+        self.autosave_task = None;
+        // A save still in flight may create a draft after this; it stays in
+        // Gmail's drafts where it can be deleted by hand
+        if let Some(draft_id) = self.draft_id.take() {
+            let handler = self.handler.clone();
+            cx.background_executor()
+                .spawn(async move {
+                    if let Err(e) = handler.delete_draft(&draft_id) {
+                        warn!("Failed to delete draft {}: {}", draft_id, e);
+                    }
+                })
+                .detach();
+        }
+        window.remove_window();
+    }
+
+    // === Attachments ===
+
+    fn pick_attachments(&mut self, cx: &mut Context<Self>) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: true,
+            prompt: Some("Attach".into()),
+        });
+        cx.spawn(async move |this, cx| {
+            let Ok(Ok(Some(paths))) = paths.await else {
+                return;
+            };
+            cx.update(|cx| this.update(cx, |view, cx| view.attach_paths(paths, cx)))
+                .ok();
+        })
+        .detach();
+    }
+
+    /// Attach files picked in the dialog or dropped on the window
+    fn attach_paths(&mut self, paths: Vec<PathBuf>, cx: &mut Context<Self>) {
+        let background = cx.background_executor().clone();
+        cx.spawn(async move |this, cx| {
+            let attachments = background
+                .spawn(async move {
+                    paths
+                        .iter()
+                        .filter_map(|path| match OutgoingAttachment::from_path(path) {
+                            Ok(attachment) => Some(attachment),
+                            Err(e) => {
+                                warn!("{}", e);
+                                None
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .await;
+
+            cx.update(|cx| {
+                this.update(cx, |view, cx| {
+                    view.attachments.extend(attachments);
+                    if let Err(e) = view.build_message(cx).check_attachments() {
+                        view.status = ComposeStatus::Error(e.to_string());
+                    }
+                    view.mark_edited(cx);
+                    cx.notify();
+                })
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn remove_attachment(&mut self, index: usize, cx: &mut Context<Self>) {
+        if index < self.attachments.len() {
+            self.attachments.remove(index);
+            self.mark_edited(cx);
+            cx.notify();
+        }
+    }
+
+    // === Rendering ===
+
+    fn status_text(&self) -> Option<String> {
+        match &self.status {
+            ComposeStatus::Idle => None,
+            ComposeStatus::Saving => Some("Saving…".to_string()),
+            ComposeStatus::Saved(at) => Some(format!("Draft saved {}", at.format("%H:%M"))),
+            ComposeStatus::Sending => Some("Sending…".to_string()),
+            ComposeStatus::Error(message) => Some(message.clone()),
+        }
+    }
+
+    fn render_field(
+        &self,
+        label: &'static str,
+        input: &Entity<InputState>,
+        field: Option<RecipientField>,
+        cx: &mut Context<Self>,
+    ) -> Div {
+        let suggestions = (field.is_some() && field == self.suggestion_field)
+            .then(|| deferred(self.render_suggestions(cx)).with_priority(1));
+        let theme = cx.theme();
+
+        div()
+            .relative()
+            .flex()
+            .items_center()
+            .gap_2()
+            .px_4()
+            .py_1()
+            .border_b_1()
+            .border_color(theme.border)
+            .child(
+                div()
+                    .w(px(56.))
+                    .flex_shrink_0()
+                    .text_sm()
+                    .text_color(theme.muted_foreground)
+                    .child(label),
+            )
+            .child(Input::new(input).appearance(false).w_full())
+            .children(suggestions)
+    }
+
+    fn render_suggestions(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+
+        div()
+            .absolute()
+            .top(px(32.))
+            .left(px(80.))
+            .w(px(360.))
+            .py_1()
+            .bg(theme.background)
+            .border_1()
+            .border_color(theme.border)
+            .rounded_md()
+            .shadow_md()
+            .children(self.suggestions.iter().enumerate().map(|(ix, contact)| {
+                div()
+                    .id(("contact-suggestion", ix))
+                    .px_2()
+                    .py_1()
+                    .cursor_pointer()
+                    .overflow_hidden()
+                    .text_ellipsis()
+                    .whitespace_nowrap()
+                    .text_sm()
+                    .text_color(theme.foreground)
+                    .hover(|style| style.bg(theme.list_hover))
+                    .child(contact.display())
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.accept_suggestion(ix, window, cx);
+                    }))
+            }))
+    }
+
+    fn render_attachments(&self, cx: &mut Context<Self>) -> Div {
+        let theme = cx.theme();
+
+        div().flex().flex_wrap().gap_2().px_4().py_2().children(
+            self.attachments.iter().enumerate().map(|(ix, attachment)| {
+                div()
+                    .id(("attachment", ix))
+                    .flex()
+                    .items_center()
+                    .gap_1()
+                    .px_2()
+                    .py_1()
+                    .rounded_md()
+                    .border_1()
+                    .border_color(theme.border)
+                    .text_xs()
+                    .text_color(theme.foreground)
+                    .child(format!(
+                        "{} ({} KB)",
+                        attachment.filename,
+                        attachment.size().div_ceil(1024)
+                    ))
+                    .child(
+                        div()
+                            .id(("remove-attachment", ix))
+                            .cursor_pointer()
+                            .text_color(theme.muted_foreground)
+                            .hover(|style| style.text_color(theme.foreground))
+                            .child(Icon::new(IconName::Close).xsmall())
+                            .on_click(cx.listener(move |this, _, _, cx| {
+                                this.remove_attachment(ix, cx);
+                            })),
+                    )
+            }),
+        )
+    }
+}
+
+impl Render for ComposeView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let to = self.to.clone();
+        let cc = self.cc.clone();
+        let bcc = self.bcc.clone();
+        let subject = self.subject.clone();
+
+        let to_row = self
+            .render_field("To", &to, Some(RecipientField::To), cx)
+            .when(!self.show_cc_bcc, |row| {
+                row.child(
+                    Button::new("show-cc-bcc")
+                        .label("Cc/Bcc")
+                        .small()
+                        .ghost()
+                        .on_click(cx.listener(|this, _, _, cx| {
+                            this.show_cc_bcc = true;
+                            cx.notify();
+                        })),
+                )
+            });
+        let cc_rows = self.show_cc_bcc.then(|| {
+            vec![
+                self.render_field("Cc", &cc, Some(RecipientField::Cc), cx),
+                self.render_field("Bcc", &bcc, Some(RecipientField::Bcc), cx),
+            ]
+        });
+        let subject_row = self.render_field("Subject", &subject, None, cx);
+        let attachments = (!self.attachments.is_empty()).then(|| self.render_attachments(cx));
+        let status = self.status_text();
+        let is_error = matches!(self.status, ComposeStatus::Error(_));
+        let sending = self.sending || self.send_after_save;
+        let theme = cx.theme();
+
+        div()
+            .key_context("ComposeView")
+            .on_action(cx.listener(Self::handle_send))
+            .on_action(cx.listener(Self::handle_discard))
+            // Files dragged in from Finder become attachments
+            .drag_over::<ExternalPaths>(|style, _, _, cx| style.bg(cx.theme().drop_target))
+            .on_drop(cx.listener(|this, paths: &ExternalPaths, _, cx| {
+                this.attach_paths(paths.paths().to_vec(), cx);
+            }))
+            .size_full()
+            .flex()
+            .flex_col()
+            .bg(theme.background)
+            .text_color(theme.foreground)
+            .child(to_row)
+            .children(cc_rows.into_iter().flatten())
+            .child(subject_row)
+            .child(
+                div()
+                    .flex_1()
+                    .min_h_0()
+                    .px_4()
+                    .py_2()
+                    .child(Input::new(&self.body).appearance(false).h_full()),
+            )
+            .children(attachments)
+            // Toolbar
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap_2()
+                    .px_4()
+                    .py_2()
+                    .border_t_1()
+                    .border_color(theme.border)
+                    .child(
+                        Button::new("send")
+                            .label(if sending { "Sending…" } else { "Send" })
+                            .small()
+                            .primary()
+                            .disabled(sending)
+                            .on_click(cx.listener(|this, _, _, cx| this.send(cx))),
+                    )
+                    .child(
+                        Button::new("attach")
+                            .icon(IconName::Plus)
+                            .label("Attach")
+                            .small()
+                            .ghost()
+                            .on_click(cx.listener(|this, _, _, cx| this.pick_attachments(cx))),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_xs()
+                            .text_color(if is_error {
+                                theme.danger
+                            } else {
+                                theme.muted_foreground
+                            })
+                            .children(status),
+                    )
+                    .child(
+                        Button::new("discard")
+                            .icon(IconName::Delete)
+                            .label("Discard")
+                            .small()
+                            .ghost()
+                            .on_click(cx.listener(|this, _, window, cx| this.discard(window, cx))),
+                    ),
+            )
+    }
+}
+
+// Actions for keyboard handling
+actions!(compose, [SendMessage, DiscardDraft]);
+
+impl ComposeView {
+    fn handle_send(&mut self, _: &SendMessage, _window: &mut Window, cx: &mut Context<Self>) {
+        self.send(cx);
+    }
+
+    fn handle_discard(&mut self, _: &DiscardDraft, window: &mut Window, cx: &mut Context<Self>) {
+        self.discard(window, cx);
+    }
+}
@@ -1,9 +1,11 @@
 //! GPUI view components for Orion mail app
 
+pub mod compose;
 pub mod search_results;
 mod thread;
 mod thread_list;
 
+pub use compose::ComposeView;
 pub use search_results::SearchResultsView;
 pub use thread::ThreadView;
-pub use thread_list::ThreadListView;
+pub use thread_list::{BulkAction, ThreadListView, UNASSIGNABLE_LABELS};
@@ -2,13 +2,17 @@
 
 use gpui::prelude::*;
 use gpui::*;
+use gpui_component::button::{Button, ButtonVariants};
 use gpui_component::scroll::Scrollbar;
 use gpui_component::spinner::Spinner;
 use gpui_component::{
     ActiveTheme, Sizable, Size as ComponentSize, VirtualListScrollHandle, v_virtual_list,
 };
 use log::{error, info};
-use mail::{MailStore, SearchIndex, SearchResult, parse_query, search_threads};
+use mail::{
+    InstantSearchOptions, MailStore, SearchIndex, SearchPage, SearchResult, instant_search,
+    parse_query, search_threads_page,
+};
 use std::rc::Rc;
 use std::sync::Arc;
 
@@ -18,14 +22,31 @@ use crate::components::SearchResultItem;
 /// Height of each search result item (single line Gmail-style)
 const RESULT_ITEM_HEIGHT: f32 = 40.0;
 
+/// Number of results fetched per page
+const SEARCH_PAGE_SIZE: usize = 100;
+
+/// Load the next page when the viewport is within this many rows of the end
+const LOAD_MORE_THRESHOLD: usize = 20;
+
 /// View for displaying search results
 pub struct SearchResultsView {
     store: Arc<dyn MailStore>,
     index: Arc<SearchIndex>,
     query: String,
+    /// Account the results are scoped to (None = all accounts)
+    account_id: Option<i64>,
     results: Vec<SearchResult>,
+    /// Matching threads across all pages
+    total_results: usize,
+    /// Whether `total_results` is an estimate
+    total_is_estimate: bool,
+    /// Offset of the next page, if there are more results
+    next_offset: Option<usize>,
     selected_index: usize,
     is_searching: bool,
+    is_loading_more: bool,
+    /// Incremented per search so results from superseded queries are dropped
+    search_generation: u64,
     error_message: Option<String>,
     app: Option<Entity<OrionApp>>,
     scroll_handle: VirtualListScrollHandle,
@@ -39,9 +60,15 @@ impl SearchResultsView {
             store,
             index,
             query: String::new(),
+            account_id: None,
             results: Vec::new(),
+            total_results: 0,
+            total_is_estimate: false,
+            next_offset: None,
             selected_index: 0,
             is_searching: false,
+            is_loading_more: false,
+            search_generation: 0,
             error_message: None,
             app: None,
             scroll_handle: VirtualListScrollHandle::new(),
@@ -59,42 +86,146 @@ impl SearchResultsView {
         self.focus_handle.focus(window);
     }
 
+    /// Scope results to one account (None = all accounts)
+    ///
+    /// Re-runs the current query, if any, with the new scope.
+    pub fn set_account_filter(&mut self, account_id: Option<i64>, cx: &mut Context<Self>) {
+        if self.account_id == account_id {
+            return;
+        }
+        self.account_id = account_id;
+        if !self.query.is_empty() {
+            self.search(self.query.clone(), cx);
+        }
+    }
+
     /// Execute search with the given query
     pub fn search(&mut self, query: String, cx: &mut Context<Self>) {
+        self.run_search(query, false, cx);
+    }
+
+    /// Execute a search-as-you-type query (prefix/fuzzy matching)
+    pub fn instant_search(&mut self, query: String, cx: &mut Context<Self>) {
+        self.run_search(query, true, cx);
+    }
+
+    fn run_search(&mut self, query: String, instant: bool, cx: &mut Context<Self>) {
         self.query = query.clone();
         self.is_searching = true;
+        self.is_loading_more = false;
         self.error_message = None;
         self.selected_index = 0;
+        self.search_generation += 1;
+        let generation = self.search_generation;
         cx.notify();
 
         // Run search on background thread
         let store = self.store.clone();
         let index = self.index.clone();
+        let account_id = self.account_id;
         let background = cx.background_executor().clone();
 
         cx.spawn(async move |this, cx| {
             let result = background
-                .spawn(async move { search_threads(&index, store.as_ref(), &query, 100) })
+                .spawn(async move {
+                    if instant {
+                        // Instant results are a single preview page; submitting
+                        // the query runs the paginated search
+                        let options = InstantSearchOptions::default();
+                        let limit = SEARCH_PAGE_SIZE;
+                        instant_search(&index, store.as_ref(), &query, limit, account_id, &options)
+                            .map(|instant| SearchPage {
+                                total: instant.results.len(),
+                                total_is_estimate: instant.truncated,
+                                results: instant.results,
+                                next_offset: None,
+                            })
+                    } else {
+                        let limit = SEARCH_PAGE_SIZE;
+                        search_threads_page(&index, store.as_ref(), &query, 0, limit, account_id)
+                    }
+                })
                 .await;
 
             let _ = cx.update(|cx| {
                 let _ = this.update(cx, |view, cx| {
+                    // A newer query has been issued since this one started
+                    if view.search_generation != generation {
+                        return;
+                    }
                     view.is_searching = false;
                     match result {
-                        Ok(results) => {
-                            info!("Search returned {} results", results.len());
-                            view.item_sizes = Rc::new(
-                                results
-                                    .iter()
-                                    .map(|_| size(px(10000.), px(RESULT_ITEM_HEIGHT)))
-                                    .collect(),
+                        Ok(page) => {
+                            info!(
+                                "Search returned {} of {} results",
+                                page.results.len(),
+                                page.total
                             );
-                            view.results = results;
+                            view.results = page.results;
+                            view.total_results = page.total;
+                            view.total_is_estimate = page.total_is_estimate;
+                            view.next_offset = page.next_offset;
+                            view.update_item_sizes();
                         }
                         Err(e) => {
                             error!("Search failed: {}", e);
                             view.error_message = Some(format!("Search failed: {}", e));
                             view.results.clear();
+                            view.total_results = 0;
+                            view.next_offset = None;
+                            view.update_item_sizes();
+                        }
+                    }
+                    cx.notify();
+                });
+            });
+        })
+        .detach();
+    }
+
+    /// Fetch the next page of results for the current query, if any
+    pub fn load_more(&mut self, cx: &mut Context<Self>) {
+        if self.is_searching || self.is_loading_more {
+            return;
+        }
+        let Some(offset) = self.next_offset else {
+            return;
+        };
+        self.is_loading_more = true;
+        let generation = self.search_generation;
+
+        let store = self.store.clone();
+        let index = self.index.clone();
+        let query = self.query.clone();
+        let account_id = self.account_id;
+        let background = cx.background_executor().clone();
+
+        cx.spawn(async move |this, cx| {
+            let result = background
+                .spawn(async move {
+                    let limit = SEARCH_PAGE_SIZE;
+                    search_threads_page(&index, store.as_ref(), &query, offset, limit, account_id)
+                })
+                .await;
+
+            let _ = cx.update(|cx| {
+                let _ = this.update(cx, |view, cx| {
+                    if view.search_generation != generation {
+                        return;
+                    }
+                    view.is_loading_more = false;
+                    match result {
+                        Ok(page) => {
+                            view.results.extend(page.results);
+                            view.total_results = page.total;
+                            view.total_is_estimate = page.total_is_estimate;
+                            view.next_offset = page.next_offset;
+                            view.update_item_sizes();
+                        }
+                        Err(e) => {
+                            // Keep the results already shown; stop paging
+                            error!("Failed to load more search results: {}", e);
+                            view.next_offset = None;
                         }
                     }
                     cx.notify();
@@ -104,6 +235,15 @@ impl SearchResultsView {
         .detach();
     }
 
+    fn update_item_sizes(&mut self) {
+        self.item_sizes = Rc::new(
+            self.results
+                .iter()
+                .map(|_| size(px(10000.), px(RESULT_ITEM_HEIGHT)))
+                .collect(),
+        );
+    }
+
     /// Move selection up
     pub fn select_prev(&mut self, cx: &mut Context<Self>) {
         if self.results.is_empty() {
@@ -130,6 +270,9 @@ impl SearchResultsView {
             self.selected_index += 1;
             cx.notify();
         }
+        if self.selected_index + LOAD_MORE_THRESHOLD >= self.results.len() {
+            self.load_more(cx);
+        }
     }
 
     /// Open the selected result
@@ -145,14 +288,18 @@ impl SearchResultsView {
     }
 
     /// Parse query terms for highlighting
-    fn query_terms(&self) -> Vec<String> {
+    pub fn query_terms(&self) -> Vec<String> {
         let parsed = parse_query(&self.query);
-        parsed.terms
+        parsed.positive_terms()
     }
 
     fn render_header(&self, cx: &mut Context<Self>) -> impl IntoElement {
         let theme = cx.theme();
-        let result_count = self.results.len();
+        let result_count = if self.total_is_estimate {
+            format!("About {}", self.total_results)
+        } else {
+            self.total_results.to_string()
+        };
 
         div()
             .w_full()
@@ -171,6 +318,24 @@ impl SearchResultsView {
                     .text_color(theme.foreground)
                     .child(format!("{} results for \"{}\"", result_count, self.query)),
             )
+            .child(
+                Button::new("save-search")
+                    .label("Save as smart folder")
+                    .small()
+                    .ghost()
+                    .on_click(cx.listener(|view, _event, _window, cx| {
+                        view.save_search(cx);
+                    })),
+            )
+    }
+
+    /// Pin the current query to the sidebar as a smart folder
+    fn save_search(&mut self, cx: &mut Context<Self>) {
+        let Some(app) = &self.app else { return };
+        let query = self.query.clone();
+        app.update(cx, |app, cx| {
+            app.save_search(query, cx);
+        });
     }
 
     fn render_empty(&self, cx: &mut Context<Self>) -> impl IntoElement {
@@ -234,6 +399,9 @@ impl SearchResultsView {
                     self.item_sizes.clone(),
                     move |view, visible_range, _window, cx| {
                         let terms = query_terms.clone();
+                        if visible_range.end + LOAD_MORE_THRESHOLD >= view.results.len() {
+                            view.load_more(cx);
+                        }
                         visible_range
                             .map(|ix| {
                                 let result = view.results[ix].clone();
@@ -38,6 +38,11 @@ impl ThreadView {
         }
     }
 
+    /// The thread being shown
+    pub fn thread_id(&self) -> &ThreadId {
+        &self.thread_id
+    }
+
     /// Focus this view for keyboard input
     pub fn focus(&self, window: &mut Window, _cx: &mut Context<Self>) {
         window.focus(&self.focus_handle);
@@ -251,6 +256,111 @@ impl ThreadView {
                     ),
             )
     }
+
+    /// Row of the thread's attachments: click one to preview it, or save it
+    /// to Downloads with the button beside it
+    fn render_attachments(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        let detail = self.detail.as_ref()?;
+        let attachments: Vec<_> = detail
+            .messages
+            .iter()
+            .flat_map(|message| {
+                message
+                    .attachment_names
+                    .iter()
+                    .map(|filename| (message.account_id, message.id.clone(), filename.clone()))
+            })
+            .collect();
+        if attachments.is_empty() {
+            return None;
+        }
+        let theme = cx.theme();
+
+        Some(
+            div()
+                .w_full()
+                .px_4()
+                .py_2()
+                .border_b_1()
+                .border_color(theme.border)
+                .flex()
+                .flex_wrap()
+                .items_center()
+                .gap_2()
+                .children(attachments.into_iter().enumerate().map(
+                    |(ix, (account_id, message_id, filename))| {
+                        let save_message_id = message_id.clone();
+                        let save_filename = filename.clone();
+                        div()
+                            .flex()
+                            .items_center()
+                            .rounded_md()
+                            .border_1()
+                            .border_color(theme.border)
+                            .child(
+                                Button::new(("attachment", ix))
+                                    .icon(IconName::File)
+                                    .label(filename.clone())
+                                    .small()
+                                    .ghost()
+                                    .cursor_pointer()
+                                    .on_click(cx.listener(move |view, _event, _window, cx| {
+                                        if let Some(app) = &view.app {
+                                            let (message_id, filename) =
+                                                (message_id.clone(), filename.clone());
+                                            app.update(cx, |app, cx| {
+                                                app.preview_attachment(
+                                                    account_id, message_id, filename, cx,
+                                                );
+                                            });
+                                        }
+                                    })),
+                            )
+                            .child(
+                                Button::new(("save-attachment", ix))
+                                    .icon(IconName::ArrowDown)
+                                    .small()
+                                    .ghost()
+                                    .cursor_pointer()
+                                    .tooltip("Save to Downloads")
+                                    .on_click(cx.listener(move |view, _event, _window, cx| {
+                                        if let Some(app) = &view.app {
+                                            let (message_id, filename) =
+                                                (save_message_id.clone(), save_filename.clone());
+                                            app.update(cx, |app, cx| {
+                                                app.save_attachment(
+                                                    account_id, message_id, filename, cx,
+                                                );
+                                            });
+                                        }
+                                    })),
+                            )
+                    },
+                )),
+        )
+    }
+
+    /// Warning shown when a message failed DMARC
+    fn render_auth_warning(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        if !self.detail.as_ref().is_some_and(|d| d.auth_warning) {
+            return None;
+        }
+        let theme = cx.theme();
+
+        Some(
+            div()
+                .w_full()
+                .px_4()
+                .py_2()
+                .bg(theme.warning)
+                .text_sm()
+                .text_color(theme.warning_foreground)
+                .child(
+                    "This message failed DMARC. The sender's address may be forged, \
+                     so be careful with links and attachments.",
+                ),
+        )
+    }
 }
 
 impl Render for ThreadView {
@@ -261,10 +371,14 @@ impl Render for ThreadView {
         div()
             .key_context("ThreadView")
             .track_focus(&self.focus_handle)
+            .flex()
+            .flex_col()
             .on_action(cx.listener(Self::handle_archive))
             .on_action(cx.listener(Self::handle_toggle_star))
             .on_action(cx.listener(Self::handle_toggle_read))
             .on_action(cx.listener(Self::handle_trash))
             .child(self.render_header(cx))
+            .children(self.render_auth_warning(cx))
+            .children(self.render_attachments(cx))
     }
 }
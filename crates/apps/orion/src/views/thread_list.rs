@@ -4,20 +4,73 @@ use gpui::prelude::*;
 use gpui::*;
 use gpui_component::scroll::Scrollbar;
 use gpui_component::skeleton::Skeleton;
-use gpui_component::{ActiveTheme, VirtualListScrollHandle, v_virtual_list};
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::{ActiveTheme, Sizable, VirtualListScrollHandle, v_virtual_list};
 use gpui::ScrollStrategy;
-use log::{debug, error};
-use mail::{MailStore, ThreadId, ThreadSummary};
-use std::collections::HashMap;
+use log::{debug, error, warn};
+use mail::{
+    Label, LabelId, MailStore, StoreEvent, Thread, ThreadFilter, ThreadId, ThreadSummary,
+    attach_thread_labels, diff_thread_lists, list_message_rows, list_threads_filtered,
+};
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::sync::Arc;
+use std::sync::mpsc::TryRecvError;
+use std::time::Duration;
 
 use crate::app::OrionApp;
-use crate::components::ThreadListItem;
-use crate::input::{Archive, MoveDown, MoveUp, OpenSelected, ToggleRead, ToggleStar, Trash};
+use crate::components::{FilterChip, ThreadListItem};
+use crate::conversation::ConversationConfig;
+use crate::display::DisplayConfig;
+use crate::input::{
+    Archive, ClearSelection, MoveDown, MoveUp, OpenSelected, ToggleRead, ToggleSelect, ToggleStar,
+    Trash,
+};
 
-/// Height of each thread list item (single line Gmail-style)
-const THREAD_ITEM_HEIGHT: f32 = 40.0;
+/// Config file for quick filters persisted per label
+const QUICK_FILTERS_FILE: &str = "orion.filters.json";
+
+/// Maximum number of threads loaded into the list
+const THREAD_LOAD_LIMIT: usize = 500;
+
+/// How often pending store events are drained and applied
+const STORE_EVENT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A change applied to every checked thread from the bulk action bar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BulkAction {
+    Archive,
+    MarkRead,
+    Trash,
+    /// Add the label with this ID
+    AddLabel(String),
+    /// Remove the label with this ID
+    RemoveLabel(String),
+}
+
+impl BulkAction {
+    /// Label IDs to add and remove on each message
+    pub fn label_changes(&self) -> (Vec<&str>, Vec<&str>) {
+        match self {
+            Self::Archive => (vec![], vec![LabelId::INBOX]),
+            Self::MarkRead => (vec![], vec![LabelId::UNREAD]),
+            Self::Trash => (vec![LabelId::TRASH], vec![LabelId::INBOX]),
+            Self::AddLabel(label_id) => (vec![label_id.as_str()], vec![]),
+            Self::RemoveLabel(label_id) => (vec![], vec![label_id.as_str()]),
+        }
+    }
+}
+
+/// Message size the "Over 5 MB" quick filter looks for
+const LARGE_MESSAGE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Labels that can't be added by hand (or have their own bulk action)
+pub const UNASSIGNABLE_LABELS: &[&str] = &[
+    LabelId::SENT,
+    LabelId::DRAFTS,
+    LabelId::ALL_MAIL,
+    LabelId::TRASH,
+];
 
 /// Thread list view showing threads filtered by label
 pub struct ThreadListView {
@@ -45,6 +98,20 @@ pub struct ThreadListView {
     unread_count: usize,
     /// Cached account emails for display in unified view (account_id -> email)
     account_emails: HashMap<i64, String>,
+    /// Quick filters per label key (persisted to config)
+    quick_filters: HashMap<String, ThreadFilter>,
+    /// Task applying store change notifications to the list
+    store_events_task: Option<Task<()>>,
+    /// Threads checked for bulk actions
+    checked: HashSet<ThreadId>,
+    /// Index of the last thread checked or unchecked, where shift-click ranges start
+    check_anchor: Option<usize>,
+    /// Whether the bulk action bar's label menu is open
+    label_menu_open: bool,
+    /// Row density and what each row shows
+    display: DisplayConfig,
+    /// Whether labels list threads or single messages
+    conversation: ConversationConfig,
 }
 
 impl ThreadListView {
@@ -66,6 +133,13 @@ impl ThreadListView {
             total_count: 0,
             unread_count: 0,
             account_emails: HashMap::new(),
+            quick_filters: config::load_json(QUICK_FILTERS_FILE).unwrap_or_default(),
+            store_events_task: None,
+            checked: HashSet::new(),
+            check_anchor: None,
+            label_menu_open: false,
+            display: DisplayConfig::load(),
+            conversation: ConversationConfig::load(),
         }
     }
 
@@ -74,6 +148,11 @@ impl ThreadListView {
         window.focus(&self.focus_handle);
     }
 
+    /// The thread under the keyboard selection
+    pub fn selected_thread_id(&self) -> Option<&ThreadId> {
+        self.selected_thread.as_ref()
+    }
+
     /// Move selection up (previous item)
     fn move_up(&mut self, cx: &mut Context<Self>) {
         if self.threads.is_empty() {
@@ -132,6 +211,10 @@ impl ThreadListView {
 
     /// Archive the selected thread (stays in list view)
     fn archive_selected(&mut self, cx: &mut Context<Self>) {
+        if !self.checked.is_empty() {
+            self.run_bulk_action(BulkAction::Archive, cx);
+            return;
+        }
         let Some(app) = &self.app else { return };
         let Some(index) = self.selected_index else { return };
         let Some(thread) = self.threads.get(index) else { return };
@@ -156,6 +239,10 @@ impl ThreadListView {
 
     /// Toggle read status on selected thread (stays in list view)
     fn toggle_read_selected(&mut self, cx: &mut Context<Self>) {
+        if !self.checked.is_empty() {
+            self.run_bulk_action(BulkAction::MarkRead, cx);
+            return;
+        }
         let Some(app) = &self.app else { return };
         let Some(index) = self.selected_index else { return };
         let Some(thread) = self.threads.get(index) else { return };
@@ -168,6 +255,10 @@ impl ThreadListView {
 
     /// Trash the selected thread (stays in list view)
     fn trash_selected(&mut self, cx: &mut Context<Self>) {
+        if !self.checked.is_empty() {
+            self.run_bulk_action(BulkAction::Trash, cx);
+            return;
+        }
         let Some(app) = &self.app else { return };
         let Some(index) = self.selected_index else { return };
         let Some(thread) = self.threads.get(index) else { return };
@@ -178,6 +269,76 @@ impl ThreadListView {
         });
     }
 
+    /// Check or uncheck the thread at `index`
+    fn toggle_checked(&mut self, index: usize, cx: &mut Context<Self>) {
+        let Some(thread) = self.threads.get(index) else { return };
+        if !self.checked.remove(&thread.id) {
+            self.checked.insert(thread.id.clone());
+        }
+        self.check_anchor = Some(index);
+        if self.checked.is_empty() {
+            self.label_menu_open = false;
+        }
+        cx.notify();
+    }
+
+    /// Check every thread between the anchor and `index`, inclusive
+    fn check_range(&mut self, index: usize, cx: &mut Context<Self>) {
+        let anchor = self.check_anchor.or(self.selected_index).unwrap_or(index);
+        let (start, end) = (anchor.min(index), anchor.max(index));
+        let end = end.min(self.threads.len().saturating_sub(1));
+        for thread in self.threads.iter().take(end + 1).skip(start) {
+            self.checked.insert(thread.id.clone());
+        }
+        self.check_anchor = Some(index);
+        cx.notify();
+    }
+
+    /// Uncheck all threads
+    fn clear_checked(&mut self, cx: &mut Context<Self>) {
+        self.checked.clear();
+        self.check_anchor = None;
+        self.label_menu_open = false;
+        cx.notify();
+    }
+
+    /// Checked threads with their accounts, in list order
+    pub fn checked_threads(&self) -> Vec<(i64, ThreadId)> {
+        self.threads
+            .iter()
+            .filter(|t| self.checked.contains(&t.id))
+            .map(|t| (t.account_id, t.id.clone()))
+            .collect()
+    }
+
+    /// Apply a bulk action to every thread with the current label
+    ///
+    /// Runs for the account in view, or every account in the unified view.
+    fn run_label_action(&mut self, action: BulkAction, cx: &mut Context<Self>) {
+        let Some(app) = self.app.clone() else { return };
+        let Some(label_id) = self.label_filter.clone().filter(|label| label != "ALL") else {
+            return;
+        };
+        let account_id = self.account_filter;
+        app.update(cx, |app, cx| {
+            app.bulk_modify_label(label_id, account_id, action, cx);
+        });
+        cx.notify();
+    }
+
+    /// Apply a bulk action to the checked threads and clear the selection
+    fn run_bulk_action(&mut self, action: BulkAction, cx: &mut Context<Self>) {
+        let Some(app) = self.app.clone() else { return };
+        let threads = self.checked_threads();
+        if threads.is_empty() {
+            return;
+        }
+        self.clear_checked(cx);
+        app.update(cx, |app, cx| {
+            app.bulk_modify_threads(threads, action, cx);
+        });
+    }
+
     // Action handlers
     fn handle_move_up(&mut self, _: &MoveUp, _window: &mut Window, cx: &mut Context<Self>) {
         self.move_up(cx);
@@ -212,20 +373,237 @@ impl ThreadListView {
         self.trash_selected(cx);
     }
 
+    fn handle_toggle_select(
+        &mut self,
+        _: &ToggleSelect,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(index) = self.selected_index {
+            self.toggle_checked(index, cx);
+        }
+    }
+
+    fn handle_clear_selection(
+        &mut self,
+        _: &ClearSelection,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.checked.is_empty() {
+            // Nothing to clear; let Escape dismiss as usual
+            cx.propagate();
+            return;
+        }
+        self.clear_checked(cx);
+    }
+
     /// Set the parent app entity for navigation
     pub fn set_app(&mut self, app: Entity<OrionApp>) {
         self.app = Some(app);
     }
 
     /// Update the store (called when persistent storage finishes loading)
-    pub fn set_store(&mut self, store: Arc<dyn MailStore>) {
+    ///
+    /// Subscribes to the store's change notifications so the list updates
+    /// incrementally as sync and actions modify threads.
+    pub fn set_store(&mut self, store: Arc<dyn MailStore>, cx: &mut Context<Self>) {
         self.store = store;
         self.is_store_loading = false;
+        self.subscribe_to_store(cx);
+    }
+
+    /// Start draining change notifications from the current store
+    fn subscribe_to_store(&mut self, cx: &mut Context<Self>) {
+        let events = self.store.subscribe();
+
+        self.store_events_task = Some(cx.spawn(async move |this, cx| {
+            loop {
+                cx.background_executor().timer(STORE_EVENT_INTERVAL).await;
+
+                // Drain everything queued since the last tick into one batch
+                let mut batch = Vec::new();
+                let disconnected = loop {
+                    match events.try_recv() {
+                        Ok(event) => batch.push(event),
+                        Err(TryRecvError::Empty) => break false,
+                        Err(TryRecvError::Disconnected) => break true,
+                    }
+                };
+
+                if !batch.is_empty() {
+                    let alive = cx
+                        .update(|cx| {
+                            this.update(cx, |view, cx| view.apply_store_events(batch, cx))
+                                .is_ok()
+                        })
+                        .unwrap_or(false);
+                    if !alive {
+                        break;
+                    }
+                }
+
+                if disconnected {
+                    break;
+                }
+            }
+        }));
+    }
+
+    /// Apply a batch of store changes to the loaded threads
+    ///
+    /// Threads already in the list are refreshed or removed in place. Changes
+    /// that may add threads to the current view (new threads, label additions,
+    /// bulk clears) fall back to a single reload for the whole batch.
+    fn apply_store_events(&mut self, events: Vec<StoreEvent>, cx: &mut Context<Self>) {
+        // Quick filters depend on message data, so re-run the filtered query
+        if !self.current_filter().is_empty() {
+            self.load_threads(cx);
+            return;
+        }
+
+        let label = self
+            .label_filter
+            .as_deref()
+            .filter(|l| *l != "ALL")
+            .map(str::to_string);
+        let mut needs_reload = false;
+        let mut changed = false;
+
+        for event in events {
+            match event {
+                StoreEvent::ThreadUpserted {
+                    thread_id,
+                    account_id,
+                } => {
+                    if self.account_filter.is_some_and(|id| id != account_id) {
+                        continue;
+                    }
+                    if self.position_of(&thread_id).is_some() {
+                        changed |= self.refresh_thread(&thread_id);
+                    } else {
+                        needs_reload = true;
+                    }
+                }
+                StoreEvent::ThreadRemoved { thread_id } => {
+                    changed |= self.remove_thread(&thread_id);
+                }
+                StoreEvent::LabelChanged {
+                    thread_id,
+                    added,
+                    removed,
+                    ..
+                } => {
+                    let in_list = self.position_of(&thread_id).is_some();
+                    match &label {
+                        Some(label) if removed.contains(label) && in_list => {
+                            // The thread may still carry the label via another message
+                            if self.thread_has_label(&thread_id, label) {
+                                changed |= self.refresh_thread(&thread_id);
+                            } else {
+                                changed |= self.remove_thread(&thread_id);
+                            }
+                        }
+                        Some(label) if added.contains(label) && !in_list => {
+                            needs_reload = true;
+                        }
+                        _ if in_list => {
+                            changed |= self.refresh_thread(&thread_id);
+                        }
+                        _ => {}
+                    }
+                }
+                StoreEvent::Cleared { account_id } => {
+                    // A cleared account only matters if it could be in view
+                    needs_reload |= account_id.is_none()
+                        || self.account_filter.is_none()
+                        || account_id == self.account_filter;
+                }
+            }
+        }
+
+        if needs_reload {
+            self.load_threads(cx);
+        } else if changed {
+            self.sync_item_sizes();
+            self.clamp_selection();
+            (self.total_count, self.unread_count) = self.fetch_counts();
+            cx.notify();
+        }
+    }
+
+    /// Summaries of threads read straight from the store, with label chips
+    fn summaries_with_labels(&self, threads: Vec<Thread>) -> anyhow::Result<Vec<ThreadSummary>> {
+        let mut summaries: Vec<ThreadSummary> =
+            threads.into_iter().map(ThreadSummary::from).collect();
+        attach_thread_labels(self.store.as_ref(), &mut summaries)?;
+        Ok(summaries)
+    }
+
+    /// Index of a thread in the loaded list
+    fn position_of(&self, thread_id: &ThreadId) -> Option<usize> {
+        self.threads.iter().position(|t| &t.id == thread_id)
+    }
+
+    /// Reload a single thread's summary from storage, keeping list order
+    ///
+    /// Returns true if the list changed.
+    fn refresh_thread(&mut self, thread_id: &ThreadId) -> bool {
+        let Some(index) = self.position_of(thread_id) else {
+            return false;
+        };
+        match self.store.get_thread(thread_id) {
+            Ok(Some(thread)) => {
+                match self.summaries_with_labels(vec![thread]) {
+                    Ok(mut summaries) => self.threads[index] = summaries.remove(0),
+                    Err(e) => {
+                        warn!("Failed to refresh thread {}: {}", thread_id.as_str(), e);
+                        return false;
+                    }
+                }
+                // New mail moves a thread up; keep newest-first order
+                self.threads.sort_by(|a, b| {
+                    b.last_message_at
+                        .cmp(&a.last_message_at)
+                        .then_with(|| a.id.as_str().cmp(b.id.as_str()))
+                });
+                true
+            }
+            Ok(None) => self.remove_thread(thread_id),
+            Err(e) => {
+                warn!("Failed to refresh thread {}: {}", thread_id.as_str(), e);
+                false
+            }
+        }
+    }
+
+    /// Remove a thread from the loaded list
+    ///
+    /// Returns true if the thread was present.
+    fn remove_thread(&mut self, thread_id: &ThreadId) -> bool {
+        let Some(index) = self.position_of(thread_id) else {
+            return false;
+        };
+        self.threads.remove(index);
+        true
+    }
+
+    /// Check whether any message in a thread still carries a label
+    fn thread_has_label(&self, thread_id: &ThreadId, label: &str) -> bool {
+        self.store
+            .list_messages_for_thread(thread_id)
+            .map(|messages| {
+                messages
+                    .iter()
+                    .any(|m| m.label_ids.iter().any(|l| l == label))
+            })
+            .unwrap_or(false)
     }
 
     /// Set the label filter and reload threads
     pub fn set_label_filter(&mut self, label: String, cx: &mut Context<Self>) {
         self.label_filter = Some(label);
+        self.clear_checked(cx);
         self.load_threads(cx);
         // Reset selection to first item when changing label
         self.selected_index = if self.threads.is_empty() {
@@ -242,6 +620,7 @@ impl ThreadListView {
     /// Pass `None` for unified view (all accounts), or `Some(id)` for single account.
     pub fn set_account_filter(&mut self, account_id: Option<i64>, cx: &mut Context<Self>) {
         self.account_filter = account_id;
+        self.clear_checked(cx);
         self.load_threads(cx);
         // Reset selection to first item when changing account
         self.selected_index = if self.threads.is_empty() {
@@ -253,6 +632,67 @@ impl ThreadListView {
         cx.notify();
     }
 
+    /// Key used to persist quick filters for the current label
+    fn filter_key(&self) -> String {
+        self.label_filter
+            .clone()
+            .unwrap_or_else(|| "ALL".to_string())
+    }
+
+    /// Quick filter for the current label
+    fn current_filter(&self) -> ThreadFilter {
+        self.quick_filters
+            .get(&self.filter_key())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Toggle a quick filter for the current label, persist, and reload
+    fn toggle_filter(&mut self, toggle: impl FnOnce(&mut ThreadFilter), cx: &mut Context<Self>) {
+        let key = self.filter_key();
+        let filter = self.quick_filters.entry(key.clone()).or_default();
+        toggle(filter);
+        if filter.is_empty() {
+            self.quick_filters.remove(&key);
+        }
+
+        if let Err(e) = config::save_json(QUICK_FILTERS_FILE, &self.quick_filters) {
+            warn!("Failed to save quick filters: {}", e);
+        }
+
+        self.load_threads(cx);
+        // Reset selection to first item when the filter changes
+        self.selected_index = if self.threads.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+        self.selected_thread = self.threads.first().map(|t| t.id.clone());
+        cx.notify();
+    }
+
+    /// Switch between thread and message rows, for every label or just the
+    /// current one, then save and reload
+    pub fn toggle_conversation_view(&mut self, current_label_only: bool, cx: &mut Context<Self>) {
+        if current_label_only {
+            let key = self.filter_key();
+            self.conversation.toggle_label(&key);
+        } else {
+            self.conversation.toggle();
+        }
+        self.conversation.save();
+
+        self.clear_checked(cx);
+        self.load_threads(cx);
+        self.selected_index = if self.threads.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+        self.selected_thread = self.threads.first().map(|t| t.id.clone());
+        cx.notify();
+    }
+
     /// Get the display name for the current label
     fn current_label_name(&self) -> &str {
         match self.label_filter.as_deref() {
@@ -271,7 +711,7 @@ impl ThreadListView {
 
     pub fn load_threads(&mut self, cx: &mut Context<Self>) {
         self.is_loading = true;
-        self.error_message = None;
+        let had_error = self.error_message.take().is_some();
 
         // Load account emails for unified view display
         if self.account_filter.is_none() {
@@ -292,21 +732,42 @@ impl ThreadListView {
         // account_filter of None means unified view (all accounts)
         let label = self.label_filter.as_deref();
         let account_id = self.account_filter;
+        let filter = self.current_filter();
+        let conversation_view = self.conversation.is_enabled(&self.filter_key());
 
         let result = match label {
+            // Quick filters work on threads, so message rows skip them
+            _ if !conversation_view => {
+                debug!(
+                    "Loading messages (conversation view off), label: {:?}, account: {:?}",
+                    label, account_id
+                );
+                let label = label.filter(|label| *label != "ALL");
+                list_message_rows(self.store.as_ref(), label, account_id, THREAD_LOAD_LIMIT)
+            }
+            _ if !filter.is_empty() => {
+                debug!(
+                    "Loading threads with quick filter {:?}, label: {:?}, account: {:?}",
+                    filter, label, account_id
+                );
+                list_threads_filtered(
+                    self.store.as_ref(),
+                    label,
+                    account_id,
+                    &filter,
+                    None,
+                    THREAD_LOAD_LIMIT,
+                )
+                .map(|page| page.threads)
+            }
             None | Some("ALL") => {
                 debug!(
                     "Loading all threads (no label filter, account: {:?})",
                     account_id
                 );
                 self.store
-                    .list_threads_for_account(account_id, 500, 0)
-                    .map(|threads| {
-                        threads
-                            .into_iter()
-                            .map(ThreadSummary::from)
-                            .collect::<Vec<_>>()
-                    })
+                    .list_threads_for_account(account_id, THREAD_LOAD_LIMIT, 0)
+                    .and_then(|threads| self.summaries_with_labels(threads))
             }
             Some(label) => {
                 debug!(
@@ -314,18 +775,64 @@ impl ThreadListView {
                     label, account_id
                 );
                 self.store
-                    .list_threads_by_label_for_account(label, account_id, 500, 0)
-                    .map(|threads| {
-                        threads
-                            .into_iter()
-                            .map(ThreadSummary::from)
-                            .collect::<Vec<_>>()
-                    })
+                    .list_threads_by_label_for_account(label, account_id, THREAD_LOAD_LIMIT, 0)
+                    .and_then(|threads| self.summaries_with_labels(threads))
             }
         };
 
-        // Fetch actual counts from storage (with account filter)
-        let (total, unread) = match label {
+        let (total, unread) = self.fetch_counts();
+
+        match result {
+            Ok(threads) => {
+                debug!("Loaded {} threads (total: {}, unread: {})", threads.len(), total, unread);
+
+                // Skip re-rendering when a reload changed nothing visible.
+                // Message rows share thread IDs, which the diff is keyed on,
+                // so lists holding them are always redrawn.
+                let diff = diff_thread_lists(&self.threads, &threads);
+                let has_message_rows = self
+                    .threads
+                    .iter()
+                    .chain(&threads)
+                    .any(|t| t.message_id.is_some());
+                let counts_changed = total != self.total_count || unread != self.unread_count;
+                self.is_loading = false;
+                if diff.is_empty() && !counts_changed && !had_error && !has_message_rows {
+                    return;
+                }
+                debug!(
+                    "Thread list diff: {} inserted, {} removed, {} moved, {} updated",
+                    diff.inserts.len(),
+                    diff.removals.len(),
+                    diff.moves.len(),
+                    diff.updates.len()
+                );
+
+                self.threads = threads;
+                if !diff.is_content_only() || has_message_rows {
+                    self.sync_item_sizes();
+                }
+                self.total_count = total;
+                self.unread_count = unread;
+
+                // Clamp selection to valid bounds after reload
+                // This ensures selection stays valid after archive/trash removes a thread
+                self.clamp_selection();
+
+                cx.notify();
+            }
+            Err(e) => {
+                error!("Failed to load threads: {}", e);
+                self.error_message = Some(format!("Failed to load threads: {}", e));
+                self.is_loading = false;
+            }
+        }
+    }
+
+    /// Fetch total and unread counts for the current label and account
+    fn fetch_counts(&self) -> (usize, usize) {
+        let account_id = self.account_filter;
+        match self.label_filter.as_deref() {
             None | Some("ALL") => {
                 let total = self
                     .store
@@ -350,47 +857,54 @@ impl ThreadListView {
                     .unwrap_or(0);
                 (total, unread)
             }
-        };
+        }
+    }
 
-        match result {
-            Ok(threads) => {
-                debug!("Loaded {} threads (total: {}, unread: {})", threads.len(), total, unread);
+    /// Rebuild virtual list item sizes to match the loaded threads
+    fn sync_item_sizes(&mut self) {
+        let row_height = self.display.row_height();
+        self.item_sizes = Rc::new(
+            self.threads
+                .iter()
+                .map(|_| size(px(10000.), px(row_height)))
+                .collect(),
+        );
+    }
 
-                // Update item sizes for virtual list
-                self.item_sizes = Rc::new(
-                    threads
-                        .iter()
-                        .map(|_| size(px(10000.), px(THREAD_ITEM_HEIGHT)))
-                        .collect(),
-                );
-                self.threads = threads;
-                self.total_count = total;
-                self.unread_count = unread;
-                self.is_loading = false;
+    /// Current display preferences
+    pub fn display(&self) -> DisplayConfig {
+        self.display
+    }
 
-                // Clamp selection to valid bounds after reload
-                // This ensures selection stays valid after archive/trash removes a thread
-                if let Some(index) = self.selected_index {
-                    if self.threads.is_empty() {
-                        self.selected_index = None;
-                        self.selected_thread = None;
-                    } else if index >= self.threads.len() {
-                        // Selection was past end, move to last item
-                        let new_index = self.threads.len() - 1;
-                        self.selected_index = Some(new_index);
-                        self.selected_thread = Some(self.threads[new_index].id.clone());
-                    } else {
-                        // Keep same index, update thread id (thread at this index may have changed)
-                        self.selected_thread = Some(self.threads[index].id.clone());
-                    }
-                }
+    /// Apply new display preferences, resizing rows to match
+    pub fn set_display(&mut self, display: DisplayConfig, cx: &mut Context<Self>) {
+        self.display = display;
+        self.sync_item_sizes();
+        cx.notify();
+    }
 
-                cx.notify();
-            }
-            Err(e) => {
-                error!("Failed to load threads: {}", e);
-                self.error_message = Some(format!("Failed to load threads: {}", e));
-                self.is_loading = false;
+    /// Keep the selection within bounds after the list changes
+    fn clamp_selection(&mut self) {
+        // Threads that left the list can't be acted on
+        let threads = &self.threads;
+        self.checked.retain(|id| threads.iter().any(|t| &t.id == id));
+        if self.checked.is_empty() {
+            self.check_anchor = None;
+            self.label_menu_open = false;
+        }
+
+        if let Some(index) = self.selected_index {
+            if self.threads.is_empty() {
+                self.selected_index = None;
+                self.selected_thread = None;
+            } else if index >= self.threads.len() {
+                // Selection was past end, move to last item
+                let new_index = self.threads.len() - 1;
+                self.selected_index = Some(new_index);
+                self.selected_thread = Some(self.threads[new_index].id.clone());
+            } else {
+                // Keep same index, update thread id (thread at this index may have changed)
+                self.selected_thread = Some(self.threads[index].id.clone());
             }
         }
     }
@@ -399,6 +913,11 @@ impl ThreadListView {
         self.selected_thread = Some(thread_id.clone());
         // Navigate to thread view via parent app
         if let Some(app) = &self.app {
+            // In the split layout the preview pane follows the selection
+            if app.read(cx).is_split_layout() {
+                cx.notify();
+                return;
+            }
             app.update(cx, |app, cx| {
                 app.show_thread(thread_id, cx);
             });
@@ -406,11 +925,20 @@ impl ThreadListView {
     }
 
     fn render_header(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        // Whole-label actions need a label and aren't run twice at once
+        let label_actions = self.label_filter.as_deref().is_some_and(|label| label != "ALL");
+        let label_busy = self
+            .app
+            .as_ref()
+            .is_some_and(|app| app.read(cx).is_modifying_label());
+        let has_unread = self.unread_count > 0;
         let theme = cx.theme();
         let label_name = self.current_label_name().to_string();
 
         // Use actual counts from storage (not in-memory counts)
-        let stats_text = if self.unread_count > 0 {
+        let stats_text = if !self.current_filter().is_empty() {
+            format!("{} of {} messages", self.threads.len(), self.total_count)
+        } else if self.unread_count > 0 {
             format!("{} messages, {} unread", self.total_count, self.unread_count)
         } else {
             format!("{} messages", self.total_count)
@@ -435,14 +963,109 @@ impl ThreadListView {
             )
             .child(
                 div()
-                    .text_sm()
-                    .text_color(theme.muted_foreground)
-                    .child(stats_text),
+                    .flex()
+                    .items_center()
+                    .gap_2()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_color(theme.muted_foreground)
+                            .child(stats_text),
+                    )
+                    .when(label_actions && has_unread, |el| {
+                        el.child(
+                            Button::new("label-mark-read")
+                                .label("Mark all read")
+                                .small()
+                                .ghost()
+                                .disabled(label_busy)
+                                .on_click(cx.listener(|view, _event, _window, cx| {
+                                    view.run_label_action(BulkAction::MarkRead, cx);
+                                })),
+                        )
+                    })
+                    .when(label_actions, |el| {
+                        el.child(
+                            Button::new("label-archive")
+                                .label("Archive all")
+                                .small()
+                                .ghost()
+                                .disabled(label_busy)
+                                .on_click(cx.listener(|view, _event, _window, cx| {
+                                    view.run_label_action(BulkAction::Archive, cx);
+                                })),
+                        )
+                    }),
+            )
+    }
+
+    fn render_filter_bar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let filter = self.current_filter();
+
+        div()
+            .w_full()
+            .px_4()
+            .py_1p5()
+            .bg(theme.background)
+            .border_b_1()
+            .border_color(theme.border)
+            .flex()
+            .items_center()
+            .gap_2()
+            .child(
+                div()
+                    .id("filter-unread")
+                    .on_click(cx.listener(|view, _event, _window, cx| {
+                        view.toggle_filter(|f| f.unread = !f.unread, cx);
+                    }))
+                    .child(FilterChip::new("Unread", filter.unread)),
+            )
+            .child(
+                div()
+                    .id("filter-starred")
+                    .on_click(cx.listener(|view, _event, _window, cx| {
+                        view.toggle_filter(|f| f.starred = !f.starred, cx);
+                    }))
+                    .child(FilterChip::new("Starred", filter.starred)),
+            )
+            .child(
+                div()
+                    .id("filter-attachments")
+                    .on_click(cx.listener(|view, _event, _window, cx| {
+                        view.toggle_filter(|f| f.has_attachment = !f.has_attachment, cx);
+                    }))
+                    .child(FilterChip::new("Attachments", filter.has_attachment)),
+            )
+            .child(
+                div()
+                    .id("filter-contacts")
+                    .on_click(cx.listener(|view, _event, _window, cx| {
+                        view.toggle_filter(|f| f.from_contacts = !f.from_contacts, cx);
+                    }))
+                    .child(FilterChip::new("From contacts", filter.from_contacts)),
+            )
+            .child(
+                div()
+                    .id("filter-large")
+                    .on_click(cx.listener(|view, _event, _window, cx| {
+                        view.toggle_filter(
+                            |f| {
+                                f.larger = match f.larger {
+                                    Some(_) => None,
+                                    None => Some(LARGE_MESSAGE_BYTES),
+                                }
+                            },
+                            cx,
+                        );
+                    }))
+                    .child(FilterChip::new("Over 5 MB", filter.larger.is_some())),
             )
     }
 
     fn render_skeleton(&self, cx: &mut Context<Self>) -> impl IntoElement {
         let theme = cx.theme();
+        let row_height = self.display.row_height();
 
         // Render skeleton thread items
         div()
@@ -452,7 +1075,7 @@ impl ThreadListView {
             .bg(theme.list)
             .children((0..8).map(|_| {
                 div()
-                    .h(px(THREAD_ITEM_HEIGHT))
+                    .h(px(row_height))
                     .w_full()
                     .px_4()
                     .py_2()
@@ -501,6 +1124,11 @@ impl ThreadListView {
 
     fn render_empty(&self, cx: &mut Context<Self>) -> impl IntoElement {
         let theme = cx.theme();
+        let (title, hint) = if self.current_filter().is_empty() {
+            ("No emails yet", "Sync your inbox to get started")
+        } else {
+            ("No threads match these filters", "Turn off a filter to see more")
+        };
 
         div().flex().flex_1().justify_center().items_center().child(
             div()
@@ -512,20 +1140,145 @@ impl ThreadListView {
                     div()
                         .text_sm()
                         .text_color(theme.muted_foreground)
-                        .child("No emails yet"),
+                        .child(title),
                 )
                 .child(
                     div()
                         .text_xs()
                         .text_color(theme.muted_foreground)
-                        .child("Sync your inbox to get started"),
+                        .child(hint),
                 ),
         )
     }
 
+    fn render_bulk_bar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let labels: Vec<Label> = self
+            .app
+            .as_ref()
+            .map(|app| {
+                app.read(cx)
+                    .labels()
+                    .iter()
+                    .filter(|label| !UNASSIGNABLE_LABELS.contains(&label.id.as_str()))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        let theme = cx.theme();
+
+        // Label picker, opened from the Label button
+        let label_menu = self.label_menu_open.then(|| {
+            div()
+                .absolute()
+                .bottom(px(40.))
+                .right_0()
+                .min_w(px(160.))
+                .py_1()
+                .bg(theme.background)
+                .border_1()
+                .border_color(theme.border)
+                .rounded_md()
+                .shadow_md()
+                .children(labels.into_iter().enumerate().map(|(ix, label)| {
+                    let label_id = label.id.0.clone();
+                    div()
+                        .id(("bulk-label", ix))
+                        .px_3()
+                        .py_1()
+                        .cursor_pointer()
+                        .text_sm()
+                        .text_color(theme.foreground)
+                        .hover(|style| style.bg(theme.list_hover))
+                        .child(label.name)
+                        .on_click(cx.listener(move |view, _event, _window, cx| {
+                            view.run_bulk_action(BulkAction::AddLabel(label_id.clone()), cx);
+                        }))
+                }))
+        });
+
+        div()
+            .absolute()
+            .bottom_4()
+            .left_0()
+            .right_0()
+            .flex()
+            .justify_center()
+            .child(
+                div()
+                    .relative()
+                    .flex()
+                    .items_center()
+                    .gap_1()
+                    .px_3()
+                    .py_1()
+                    .bg(theme.background)
+                    .border_1()
+                    .border_color(theme.border)
+                    .rounded_lg()
+                    .shadow_lg()
+                    .child(
+                        div()
+                            .mr_2()
+                            .text_sm()
+                            .font_weight(FontWeight::MEDIUM)
+                            .text_color(theme.foreground)
+                            .child(format!("{} selected", self.checked.len())),
+                    )
+                    .child(
+                        Button::new("bulk-archive")
+                            .label("Archive")
+                            .small()
+                            .ghost()
+                            .on_click(cx.listener(|view, _event, _window, cx| {
+                                view.run_bulk_action(BulkAction::Archive, cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("bulk-label")
+                            .label("Label")
+                            .small()
+                            .ghost()
+                            .on_click(cx.listener(|view, _event, _window, cx| {
+                                view.label_menu_open = !view.label_menu_open;
+                                cx.notify();
+                            })),
+                    )
+                    .child(
+                        Button::new("bulk-mark-read")
+                            .label("Mark read")
+                            .small()
+                            .ghost()
+                            .on_click(cx.listener(|view, _event, _window, cx| {
+                                view.run_bulk_action(BulkAction::MarkRead, cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("bulk-trash")
+                            .label("Trash")
+                            .small()
+                            .ghost()
+                            .on_click(cx.listener(|view, _event, _window, cx| {
+                                view.run_bulk_action(BulkAction::Trash, cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("bulk-clear")
+                            .label("Clear")
+                            .small()
+                            .ghost()
+                            .on_click(cx.listener(|view, _event, _window, cx| {
+                                view.clear_checked(cx);
+                            })),
+                    )
+                    .children(label_menu),
+            )
+    }
+
     fn render_thread_list(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let bulk_bar = (!self.checked.is_empty()).then(|| self.render_bulk_bar(cx));
         let theme = cx.theme();
         let selected_index = self.selected_index;
+        let display = self.display;
 
         div()
             .relative()
@@ -546,6 +1299,14 @@ impl ThreadListView {
                                 // Use selected_index for keyboard selection
                                 let is_selected = selected_index == Some(ix);
                                 let thread_id = thread.id.clone();
+                                // Message rows of one thread need their own IDs
+                                let row_id = match &thread.message_id {
+                                    Some(message_id) => message_id.as_str().to_string(),
+                                    None => thread_id.0.clone(),
+                                };
+                                // Checkboxes appear once any thread is checked
+                                let is_checked = (!view.checked.is_empty())
+                                    .then(|| view.checked.contains(&thread_id));
 
                                 // In unified view, look up account email for display
                                 let account_email = view
@@ -554,17 +1315,23 @@ impl ThreadListView {
                                     .cloned();
 
                                 div()
-                                    .id(ElementId::Name(thread_id.0.clone().into()))
-                                    .h(px(THREAD_ITEM_HEIGHT))
+                                    .id(ElementId::Name(row_id.into()))
+                                    .h(px(display.row_height()))
                                     .w_full()
                                     .cursor_pointer()
-                                    .on_click(cx.listener(move |view, _event, _window, cx| {
+                                    .on_click(cx.listener(move |view, event: &ClickEvent, _, cx| {
+                                        if event.modifiers().shift {
+                                            view.check_range(ix, cx);
+                                            return;
+                                        }
                                         view.selected_index = Some(ix);
                                         view.select_thread(thread_id.clone(), cx);
                                     }))
                                     .child(
                                         ThreadListItem::new(thread, is_selected)
-                                            .with_account(account_email),
+                                            .with_account(account_email)
+                                            .with_checked(is_checked)
+                                            .with_display(display),
                                     )
                             })
                             .collect()
@@ -574,6 +1341,7 @@ impl ThreadListView {
                 .track_scroll(&self.scroll_handle),
             )
             .child(Scrollbar::vertical(&self.scroll_handle))
+            .children(bulk_bar)
     }
 }
 
@@ -591,11 +1359,14 @@ impl Render for ThreadListView {
             .on_action(cx.listener(Self::handle_toggle_star))
             .on_action(cx.listener(Self::handle_toggle_read))
             .on_action(cx.listener(Self::handle_trash))
+            .on_action(cx.listener(Self::handle_toggle_select))
+            .on_action(cx.listener(Self::handle_clear_selection))
             .flex()
             .flex_col()
             .size_full()
             .bg(theme.background)
             .child(self.render_header(cx))
+            .child(self.render_filter_bar(cx))
             .child(if self.is_store_loading || self.is_loading {
                 self.render_skeleton(cx).into_any_element()
             } else if let Some(ref error) = self.error_message.clone() {
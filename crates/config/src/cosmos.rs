@@ -0,0 +1,476 @@
+//! Shared Cosmos settings
+//!
+//! [`CosmosConfig`] holds the settings the apps share, loaded in layers:
+//! defaults, then `cosmos.json` and `cosmos.toml` from the config
+//! directory, then `COSMOS_*` environment variables. Accounts can override
+//! any of it in an `[accounts."me@example.com"]` table; see
+//! [`CosmosConfig::for_account`].
+//!
+//! Loading checks every key it reads: unknown keys, wrong types and values
+//! out of range are all reported, each with the file and line (or
+//! environment variable) that set it. [`crate::doctor`] reports the same
+//! problems next to the effective settings.
+//!
+//! ```toml
+//! [daemon]
+//! poll_interval_secs = 120
+//! api_server = true
+//!
+//! [accounts."work@example.com".sync]
+//! max_messages = 50000
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::layered::{InvalidConfig, Issue, Layers, Problem, lookup};
+use crate::watch::{ConfigWatcher, watch_files};
+use crate::{config_dir, data_dir};
+
+/// Mail database, in the data directory
+pub(crate) const DB_FILE: &str = "mail.db";
+/// Message body blob store, in the data directory
+pub(crate) const BLOB_DIR: &str = "blobs";
+/// Search index, in the data directory
+pub(crate) const INDEX_DIR: &str = "mail.search.idx";
+
+/// Shared settings file, in TOML
+pub const COSMOS_CONFIG_FILE: &str = "cosmos.toml";
+
+/// Shared settings file, in JSON; `cosmos.toml` wins where both set a key
+pub const COSMOS_JSON_CONFIG_FILE: &str = "cosmos.json";
+
+/// Daemon settings file read before the shared file existed; still merged
+/// as the `daemon` table, below the shared files
+pub const LEGACY_DAEMON_CONFIG_FILE: &str = "cosmosd.json";
+
+/// Prefix of environment variables overriding settings, e.g.
+/// `COSMOS_DAEMON__POLL_INTERVAL_SECS=30`
+pub const ENV_PREFIX: &str = "COSMOS_";
+
+/// Longest allowed poll interval or sync cooldown, a day
+const MAX_INTERVAL_SECS: u64 = 24 * 60 * 60;
+
+/// Settings shared by Orion, cosmosd and cosmos-cli
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CosmosConfig {
+    pub storage: StorageSettings,
+    pub sync: SyncSettings,
+    pub network: NetworkSettings,
+    pub daemon: DaemonSettings,
+    /// Overrides of the settings above, by account email
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub accounts: BTreeMap<String, Value>,
+}
+
+/// Where mail data lives
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageSettings {
+    /// Directory holding the database, blobs and search index; the
+    /// platform data directory (see [`data_dir`]) if unset
+    pub data_dir: Option<PathBuf>,
+}
+
+/// How accounts are synced
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyncSettings {
+    /// Most messages an initial sync fetches; all if unset
+    pub max_messages: Option<usize>,
+    /// Pending messages at which fetching waits for processing; the mail
+    /// crate's default if unset, 0 for no cap
+    pub max_pending: Option<usize>,
+    /// Minimum seconds between syncs Orion starts on its own
+    pub cooldown_secs: u64,
+    /// Seconds between Orion's background syncs while cosmosd isn't running
+    pub poll_interval_secs: u64,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self {
+            max_messages: None,
+            max_pending: None,
+            cooldown_secs: 30,
+            poll_interval_secs: 60,
+        }
+    }
+}
+
+/// How much of the connection syncing may use
+///
+/// Limits left unset are unlimited, except on metered connections, where
+/// the mail crate applies conservative ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkSettings {
+    /// Most megabytes of messages one sync downloads; the rest waits for
+    /// the next sync
+    pub max_mb_per_sync: Option<u64>,
+    /// Most messages downloaded at once
+    pub max_concurrent_fetches: Option<usize>,
+    /// Whether the connection is metered; detected where the platform
+    /// reports it if unset
+    pub metered: Option<bool>,
+}
+
+/// Background daemon (cosmosd) settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DaemonSettings {
+    /// Seconds between sync rounds
+    pub poll_interval_secs: u64,
+    /// Serve the local API for third-party tools
+    pub api_server: bool,
+    /// Let API clients change mail, not just read it
+    pub api_actions: bool,
+    /// OTLP/HTTP metrics URL to push sync metrics to
+    pub otlp_endpoint: Option<String>,
+}
+
+impl Default for DaemonSettings {
+    fn default() -> Self {
+        Self {
+            poll_interval_secs: 60,
+            api_server: false,
+            api_actions: false,
+            otlp_endpoint: None,
+        }
+    }
+}
+
+impl CosmosConfig {
+    /// Load settings from the config directory and environment
+    ///
+    /// Fails with [`InvalidConfig`], listing every problem found, if any
+    /// setting is invalid.
+    pub fn load() -> Result<Self> {
+        let layers = Self::layers()?;
+        let issues = Self::check(&layers);
+        if !issues.is_empty() {
+            return Err(InvalidConfig(issues).into());
+        }
+        layers.build()
+    }
+
+    /// The layers [`CosmosConfig::load`] merges, in order
+    pub fn layers() -> Result<Layers> {
+        let dir = config_dir().context("Could not determine config directory")?;
+        Self::layers_in(&dir, std::env::vars())
+    }
+
+    fn layers_in(dir: &Path, vars: impl IntoIterator<Item = (String, String)>) -> Result<Layers> {
+        let mut layers = Layers::new(&Self::default())?;
+        for (path, table) in config_files(dir) {
+            layers = match table {
+                Some(table) => layers.file_at(&path, table)?,
+                None => layers.file(&path)?,
+            };
+        }
+        Ok(layers.env(ENV_PREFIX, vars))
+    }
+
+    /// Every problem with the settings in `layers`, including account
+    /// overrides
+    ///
+    /// Values are only range checked once every key has the right type.
+    pub fn check(layers: &Layers) -> Vec<Issue> {
+        let mut issues = layers.validate::<Self>(&["accounts"]);
+        if !issues.is_empty() {
+            return issues;
+        }
+        let Ok(config) = layers.build::<Self>() else {
+            return issues;
+        };
+
+        for (path, message) in config.out_of_range() {
+            issues.push(layers.issue(&path, Problem::OutOfRange(message)));
+        }
+        for (email, overrides) in &config.accounts {
+            let Ok(account) = config.for_account(email) else {
+                continue;
+            };
+            // Only report what the override itself sets
+            for (path, message) in account.out_of_range() {
+                if lookup(overrides, &path).is_none() {
+                    continue;
+                }
+                let mut path = path;
+                path.splice(0..0, ["accounts".to_string(), email.clone()]);
+                issues.push(layers.issue(&path, Problem::OutOfRange(message)));
+            }
+        }
+        issues
+    }
+
+    /// Key paths of settings with values outside their allowed range, and
+    /// why
+    fn out_of_range(&self) -> Vec<(Vec<String>, String)> {
+        let mut problems = Vec::new();
+        let mut problem = |key: &str, message: String| {
+            problems.push((key.split('.').map(str::to_string).collect(), message));
+        };
+
+        for (key, secs, min) in [
+            (
+                "daemon.poll_interval_secs",
+                self.daemon.poll_interval_secs,
+                1,
+            ),
+            ("sync.poll_interval_secs", self.sync.poll_interval_secs, 1),
+            ("sync.cooldown_secs", self.sync.cooldown_secs, 0),
+        ] {
+            if !(min..=MAX_INTERVAL_SECS).contains(&secs) {
+                problem(
+                    key,
+                    format!(
+                        "must be between {} and {} seconds, got {}",
+                        min, MAX_INTERVAL_SECS, secs
+                    ),
+                );
+            }
+        }
+        if let Some(endpoint) = &self.daemon.otlp_endpoint
+            && !(endpoint.starts_with("http://") || endpoint.starts_with("https://"))
+        {
+            problem(
+                "daemon.otlp_endpoint",
+                format!("must be an http:// or https:// URL, got {:?}", endpoint),
+            );
+        }
+        if self.sync.max_messages == Some(0) {
+            problem(
+                "sync.max_messages",
+                "must be at least 1; leave it unset to sync every message".to_string(),
+            );
+        }
+        for (key, limit) in [
+            ("network.max_mb_per_sync", self.network.max_mb_per_sync),
+            (
+                "network.max_concurrent_fetches",
+                self.network.max_concurrent_fetches.map(|n| n as u64),
+            ),
+        ] {
+            if limit == Some(0) {
+                problem(
+                    key,
+                    "must be at least 1; leave it unset for no limit".to_string(),
+                );
+            }
+        }
+        if let Some(dir) = &self.storage.data_dir
+            && !dir.is_absolute()
+        {
+            problem(
+                "storage.data_dir",
+                format!("must be an absolute path, got {}", dir.display()),
+            );
+        }
+        problems
+    }
+
+    /// Call `callback` with freshly loaded settings whenever a config file
+    /// changes
+    ///
+    /// The callback runs on a background thread and gets the load error if
+    /// the edited settings are invalid. Watching stops when the returned
+    /// [`ConfigWatcher`] is dropped.
+    pub fn watch<F>(mut callback: F) -> Result<ConfigWatcher>
+    where
+        F: FnMut(Result<Self>) + Send + 'static,
+    {
+        let dir = config_dir().context("Could not determine config directory")?;
+        let paths: Vec<PathBuf> = config_files(&dir)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        watch_files(&paths, move || callback(Self::load()))
+    }
+
+    /// Settings for one account, with its overrides applied
+    pub fn for_account(&self, email: &str) -> Result<Self> {
+        let Some(overrides) = self.accounts.get(email) else {
+            return Ok(self.clone());
+        };
+        let mut base = self.clone();
+        base.accounts.clear();
+        Layers::new(&base)?
+            .value(overrides.clone())
+            .build()
+            .with_context(|| format!("Invalid settings for account {}", email))
+    }
+}
+
+/// Config files [`CosmosConfig::load`] reads from `dir`, in order, with the
+/// table each is merged as
+pub(crate) fn config_files(dir: &Path) -> Vec<(PathBuf, Option<&'static str>)> {
+    vec![
+        (dir.join(LEGACY_DAEMON_CONFIG_FILE), Some("daemon")),
+        (dir.join(COSMOS_JSON_CONFIG_FILE), None),
+        (dir.join(COSMOS_CONFIG_FILE), None),
+    ]
+}
+
+impl StorageSettings {
+    /// Directory holding mail data
+    pub fn dir(&self) -> Result<PathBuf> {
+        match &self.data_dir {
+            Some(dir) => Ok(dir.clone()),
+            None => data_dir().context("Could not determine data directory"),
+        }
+    }
+
+    /// Path of the mail database
+    pub fn db_path(&self) -> Result<PathBuf> {
+        self.path(DB_FILE)
+    }
+
+    /// Directory of the message body blob store
+    pub fn blob_path(&self) -> Result<PathBuf> {
+        self.path(BLOB_DIR)
+    }
+
+    /// Directory of the search index
+    pub fn index_path(&self) -> Result<PathBuf> {
+        self.path(INDEX_DIR)
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        Ok(self.dir()?.join(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_cosmos_config_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(LEGACY_DAEMON_CONFIG_FILE),
+            r#"{"poll_interval_secs": 120, "api_server": true}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join(COSMOS_CONFIG_FILE),
+            "[daemon]\npoll_interval_secs = 300\n\n[accounts.\"work@example.com\".sync]\nmax_messages = 500\n",
+        )
+        .unwrap();
+
+        let layers = CosmosConfig::layers_in(
+            dir.path(),
+            [("COSMOS_SYNC__MAX_PENDING".to_string(), "0".to_string())],
+        )
+        .unwrap();
+        assert!(CosmosConfig::check(&layers).is_empty());
+        let config: CosmosConfig = layers.build().unwrap();
+
+        assert_eq!(config.daemon.poll_interval_secs, 300);
+        assert!(config.daemon.api_server);
+        assert_eq!(config.sync.max_pending, Some(0));
+        assert_eq!(config.sync.max_messages, None);
+
+        let work = config.for_account("work@example.com").unwrap();
+        assert_eq!(work.sync.max_messages, Some(500));
+        assert_eq!(work.sync.max_pending, Some(0));
+        assert_eq!(work.daemon, config.daemon);
+        assert!(work.accounts.is_empty());
+        assert_eq!(config.for_account("home@example.com").unwrap(), config);
+    }
+
+    #[test]
+    fn test_check_reports_where_problems_are() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(COSMOS_CONFIG_FILE);
+        std::fs::write(
+            &path,
+            "[daemon]\npoll_interval_secs = 0\notlp_endpoint = \"localhost:4318\"\n\n\
+             [accounts.\"work@example.com\".sync]\nmax_messages = 0\n\n\
+             [network]\nmax_concurrent_fetches = 0\n",
+        )
+        .unwrap();
+
+        let layers = CosmosConfig::layers_in(
+            dir.path(),
+            [("COSMOS_STORAGE__DATA_DIR".to_string(), "data".to_string())],
+        )
+        .unwrap();
+        let issues = CosmosConfig::check(&layers);
+        let mut summary: Vec<String> = issues
+            .iter()
+            .map(|issue| format!("{:?} {}", issue.line, issue.key))
+            .collect();
+        summary.sort();
+        assert_eq!(
+            summary,
+            vec![
+                "None storage.data_dir",
+                "Some(2) daemon.poll_interval_secs",
+                "Some(3) daemon.otlp_endpoint",
+                "Some(6) accounts.work@example.com.sync.max_messages",
+                "Some(9) network.max_concurrent_fetches",
+            ]
+        );
+        assert!(
+            issues
+                .iter()
+                .all(|issue| matches!(issue.problem, Problem::OutOfRange(_)))
+        );
+    }
+
+    #[test]
+    fn test_check_reports_unknown_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(COSMOS_JSON_CONFIG_FILE),
+            "{\n  \"daemon\": {\n    \"poll_interval\": 30\n  },\n  \"accounts\": {\n    \"work@example.com\": {\"sync\": {\"max_pending\": \"lots\"}}\n  }\n}",
+        )
+        .unwrap();
+
+        let layers = CosmosConfig::layers_in(dir.path(), []).unwrap();
+        let mut issues = CosmosConfig::check(&layers);
+        issues.sort_by(|a, b| a.key.cmp(&b.key));
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].key, "accounts.work@example.com.sync.max_pending");
+        assert_eq!(issues[0].line, Some(6));
+        assert!(matches!(issues[0].problem, Problem::WrongType(_)));
+        assert_eq!(issues[1].key, "daemon.poll_interval");
+        assert_eq!(issues[1].line, Some(3));
+        assert!(matches!(issues[1].problem, Problem::UnknownKey(_)));
+    }
+
+    #[test]
+    fn test_invalid_account_override() {
+        let mut config = CosmosConfig::default();
+        config.accounts.insert(
+            "work@example.com".to_string(),
+            json!({"sync": {"max_messages": "all"}}),
+        );
+        assert!(config.for_account("work@example.com").is_err());
+    }
+
+    #[test]
+    fn test_storage_paths() {
+        let storage = StorageSettings {
+            data_dir: Some(PathBuf::from("/data/cosmos")),
+        };
+        assert_eq!(
+            storage.db_path().unwrap(),
+            PathBuf::from("/data/cosmos/mail.db")
+        );
+        assert_eq!(
+            storage.index_path().unwrap(),
+            PathBuf::from("/data/cosmos/mail.search.idx")
+        );
+
+        let default = StorageSettings::default();
+        assert!(default.blob_path().unwrap().ends_with("cosmos/blobs"));
+    }
+}
@@ -0,0 +1,114 @@
+//! Configuration report
+//!
+//! [`doctor`] summarizes the effective Cosmos settings: which config files
+//! were read, every setting with the file and line (or environment
+//! variable) it came from, and anything wrong with them.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::config_dir;
+use crate::cosmos::{CosmosConfig, config_files};
+use crate::layered::{Issue, Origin, leaf_paths, lookup};
+
+/// A config file [`CosmosConfig::load`] looks for
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    pub exists: bool,
+}
+
+/// One effective setting and where it came from
+#[derive(Debug, Clone, Serialize)]
+pub struct Setting {
+    /// Dotted key path, e.g. `daemon.poll_interval_secs`
+    pub key: String,
+    pub value: Value,
+    pub origin: Origin,
+    /// Line in the file it was set in, where known
+    pub line: Option<usize>,
+}
+
+/// Summary of the effective configuration
+#[derive(Debug, Clone, Serialize)]
+pub struct Doctor {
+    pub files: Vec<ConfigFile>,
+    pub settings: Vec<Setting>,
+    pub issues: Vec<Issue>,
+}
+
+impl Doctor {
+    /// Whether the configuration loads without problems
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Summarize the Cosmos settings from the config directory and environment
+pub fn doctor() -> Result<Doctor> {
+    let dir = config_dir().context("Could not determine config directory")?;
+    let files = config_files(&dir)
+        .into_iter()
+        .map(|(path, _)| ConfigFile {
+            exists: path.exists(),
+            path,
+        })
+        .collect();
+
+    let layers = CosmosConfig::layers()?;
+    let merged = layers.merged();
+    let settings = leaf_paths(merged)
+        .into_iter()
+        .filter_map(|path| {
+            let value = lookup(merged, &path)?.clone();
+            let (origin, line) = layers.source(&path);
+            Some(Setting {
+                key: path.join("."),
+                value,
+                origin,
+                line,
+            })
+        })
+        .collect();
+
+    Ok(Doctor {
+        files,
+        settings,
+        issues: CosmosConfig::check(&layers),
+    })
+}
+
+impl fmt::Display for Doctor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Config files:")?;
+        for file in &self.files {
+            let missing = if file.exists { "" } else { " (not found)" };
+            writeln!(f, "  {}{}", file.path.display(), missing)?;
+        }
+
+        writeln!(f, "\nSettings:")?;
+        for setting in &self.settings {
+            write!(
+                f,
+                "  {} = {}  ({}",
+                setting.key, setting.value, setting.origin
+            )?;
+            if let Some(line) = setting.line {
+                write!(f, ":{}", line)?;
+            }
+            writeln!(f, ")")?;
+        }
+
+        if self.issues.is_empty() {
+            return writeln!(f, "\nNo problems found");
+        }
+        writeln!(f, "\nProblems:")?;
+        for issue in &self.issues {
+            writeln!(f, "  {}", issue)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,694 @@
+//! Layered configuration
+//!
+//! Settings are built up from layers, each overriding the keys it sets in
+//! the ones before it: typically defaults, then config files (TOML or
+//! JSON), then environment variables. Tables merge key by key, so a file
+//! only needs the settings it changes.
+//!
+//! Each layer remembers where it came from, so [`Layers::validate`] can
+//! point at the file and line (or environment variable) behind a bad key.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Separator between table names in environment variable overrides, as in
+/// `COSMOS_DAEMON__POLL_INTERVAL_SECS`
+pub const ENV_SEPARATOR: &str = "__";
+
+/// Where a layer of settings came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "name", rename_all = "snake_case")]
+pub enum Origin {
+    Defaults,
+    File(PathBuf),
+    Env(String),
+    /// A value merged by the app, e.g. an account's overrides
+    Value,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Defaults => write!(f, "default"),
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Env(name) => write!(f, "${}", name),
+            Self::Value => write!(f, "override"),
+        }
+    }
+}
+
+/// What's wrong with a setting
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum Problem {
+    /// The file isn't valid TOML or JSON
+    Syntax(String),
+    /// No setting has this name
+    UnknownKey(String),
+    /// The value has the wrong type, e.g. a string for a number
+    WrongType(String),
+    /// The value has the right type but isn't allowed
+    OutOfRange(String),
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(message) => write!(f, "syntax error: {}", message),
+            Self::UnknownKey(message) | Self::WrongType(message) | Self::OutOfRange(message) => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+/// A problem with one setting, and where it was set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Issue {
+    pub origin: Origin,
+    /// Line in the file, where known
+    pub line: Option<usize>,
+    /// Dotted key path, e.g. `daemon.poll_interval_secs`; empty for files
+    /// that don't parse
+    pub key: String,
+    pub problem: Problem,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.origin)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        if self.key.is_empty() {
+            write!(f, ": {}", self.problem)
+        } else {
+            write!(f, ": {}: {}", self.key, self.problem)
+        }
+    }
+}
+
+/// Settings that failed validation, with every problem found
+#[derive(Debug, Clone)]
+pub struct InvalidConfig(pub Vec<Issue>);
+
+impl fmt::Display for InvalidConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration")?;
+        for issue in &self.0 {
+            write!(f, "\n  {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for InvalidConfig {}
+
+/// One source of settings
+#[derive(Debug, Clone)]
+struct Layer {
+    origin: Origin,
+    value: Value,
+    /// File contents, for finding the line a key is on
+    text: Option<String>,
+    /// Table the file was merged as (see [`Layers::file_at`])
+    table: Option<String>,
+}
+
+/// Settings merged from successive layers
+#[derive(Debug, Clone)]
+pub struct Layers {
+    defaults: Value,
+    value: Value,
+    layers: Vec<Layer>,
+    /// Files that couldn't be parsed
+    syntax_errors: Vec<Issue>,
+}
+
+impl Layers {
+    /// Start from `defaults`
+    pub fn new<T: Serialize>(defaults: &T) -> Result<Self> {
+        let defaults = serde_json::to_value(defaults).context("Failed to serialize defaults")?;
+        Ok(Self {
+            value: defaults.clone(),
+            defaults,
+            layers: Vec::new(),
+            syntax_errors: Vec::new(),
+        })
+    }
+
+    /// Merge a config file, if it exists
+    ///
+    /// `.toml` files are read as TOML and anything else as JSON. A file that
+    /// doesn't parse is skipped and reported by [`Layers::validate`].
+    pub fn file(self, path: &Path) -> Result<Self> {
+        self.add_file(path, None)
+    }
+
+    /// Merge a config file, if it exists, as the table `key`
+    ///
+    /// For files holding a single section, e.g. settings files that predate
+    /// a shared config file.
+    pub fn file_at(self, path: &Path, key: &str) -> Result<Self> {
+        self.add_file(path, Some(key))
+    }
+
+    fn add_file(mut self, path: &Path, table: Option<&str>) -> Result<Self> {
+        if !path.exists() {
+            return Ok(self);
+        }
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        let value = match parse(path, &text) {
+            Ok(value) => value,
+            Err((line, message)) => {
+                self.syntax_errors.push(Issue {
+                    origin: Origin::File(path.to_path_buf()),
+                    line,
+                    key: String::new(),
+                    problem: Problem::Syntax(message),
+                });
+                return Ok(self);
+            }
+        };
+        let value = match table {
+            Some(key) => nest(&[key.to_string()], value),
+            None => value,
+        };
+        self.push(Layer {
+            origin: Origin::File(path.to_path_buf()),
+            value,
+            text: Some(text),
+            table: table.map(str::to_string),
+        });
+        Ok(self)
+    }
+
+    /// Merge environment variables starting with `prefix`
+    ///
+    /// The rest of the name is the lowercased key path, with tables
+    /// separated by [`ENV_SEPARATOR`]: with prefix `COSMOS_`,
+    /// `COSMOS_DAEMON__API_SERVER=true` sets `api_server` in the `daemon`
+    /// table. Values are read as JSON where they parse (numbers, booleans)
+    /// and as strings otherwise. Only names starting with a top-level key of
+    /// the defaults are read, so unrelated variables sharing the prefix
+    /// aren't taken for unknown settings.
+    pub fn env(mut self, prefix: &str, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        let mut vars: Vec<(String, String)> = vars.into_iter().collect();
+        vars.sort();
+        for (name, raw) in vars {
+            let Some(path) = name.strip_prefix(prefix) else {
+                continue;
+            };
+            let keys: Vec<String> = path
+                .split(ENV_SEPARATOR)
+                .map(|key| key.to_lowercase())
+                .collect();
+            if keys.iter().any(String::is_empty) || self.defaults.get(&keys[0]).is_none() {
+                continue;
+            }
+            let value = serde_json::from_str(&raw).unwrap_or(Value::String(raw));
+            self.push(Layer {
+                origin: Origin::Env(name),
+                value: nest(&keys, value),
+                text: None,
+                table: None,
+            });
+        }
+        self
+    }
+
+    /// Merge a value over the layers so far
+    pub fn value(mut self, value: Value) -> Self {
+        self.push(Layer {
+            origin: Origin::Value,
+            value,
+            text: None,
+            table: None,
+        });
+        self
+    }
+
+    fn push(&mut self, layer: Layer) {
+        merge(&mut self.value, layer.value.clone());
+        self.layers.push(layer);
+    }
+
+    /// The merged settings
+    pub fn merged(&self) -> &Value {
+        &self.value
+    }
+
+    /// Deserialize the merged settings
+    ///
+    /// Fails with [`InvalidConfig`], listing every problem, if any layer
+    /// doesn't parse or sets a bad key.
+    pub fn build<T: DeserializeOwned>(&self) -> Result<T> {
+        let issues = self.validate::<T>(&[]);
+        if !issues.is_empty() {
+            return Err(InvalidConfig(issues).into());
+        }
+        serde_json::from_value(self.value.clone()).context("Invalid configuration")
+    }
+
+    /// Check every key each layer sets, returning what's wrong with them
+    ///
+    /// Keys are checked one at a time over the defaults, so each bad key is
+    /// reported on its own: unknown keys (where `T` denies unknown fields),
+    /// wrong types and files that don't parse. Tables under the `nested`
+    /// keys map names to overrides of `T` itself, as per-account settings
+    /// do, and their keys are checked the same way.
+    pub fn validate<T: DeserializeOwned>(&self, nested: &[&str]) -> Vec<Issue> {
+        let mut issues = self.syntax_errors.clone();
+        for layer in &self.layers {
+            for path in leaf_paths(&layer.value) {
+                // Keys of a nested override, e.g. `accounts.<email>.sync`,
+                // are checked as the key under it, e.g. `sync`
+                let checked = match path.split_first() {
+                    Some((first, rest)) if nested.contains(&first.as_str()) && rest.len() > 1 => {
+                        &rest[1..]
+                    }
+                    _ => &path[..],
+                };
+                let Some(leaf) = lookup(&layer.value, &path) else {
+                    continue;
+                };
+                let mut value = self.defaults.clone();
+                merge(&mut value, nest(checked, leaf.clone()));
+                let Err(e) = serde_json::from_value::<T>(value) else {
+                    continue;
+                };
+
+                let message = e.to_string();
+                let mut key = path.clone();
+                let problem = match unknown_field(&message) {
+                    Some(field) => {
+                        // Point at the unknown key, not the values under it
+                        let offset = path.len() - checked.len();
+                        if let Some(at) = checked.iter().position(|key| key == field) {
+                            key.truncate(offset + at + 1);
+                        }
+                        Problem::UnknownKey(message)
+                    }
+                    None => Problem::WrongType(message),
+                };
+                let issue = issue_in(layer, &key, problem);
+                if !issues.contains(&issue) {
+                    issues.push(issue);
+                }
+            }
+        }
+        issues
+    }
+
+    /// Attribute a problem with the setting at `path` to the layer that set
+    /// it last
+    pub fn issue(&self, path: &[String], problem: Problem) -> Issue {
+        match self.layer_setting(path) {
+            Some(layer) => issue_in(layer, path, problem),
+            None => Issue {
+                origin: Origin::Defaults,
+                line: None,
+                key: path.join("."),
+                problem,
+            },
+        }
+    }
+
+    /// Where the setting at `path` was last set, and on which line
+    pub fn source(&self, path: &[String]) -> (Origin, Option<usize>) {
+        match self.layer_setting(path) {
+            Some(layer) => (layer.origin.clone(), line_of(layer, path)),
+            None => (Origin::Defaults, None),
+        }
+    }
+
+    fn layer_setting(&self, path: &[String]) -> Option<&Layer> {
+        self.layers
+            .iter()
+            .rev()
+            .find(|layer| lookup(&layer.value, path).is_some())
+    }
+}
+
+/// Merge `overlay` into `base`
+///
+/// Tables merge key by key; any other value replaces what was there.
+pub fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Key paths of every value in `value` that isn't a non-empty table
+pub fn leaf_paths(value: &Value) -> Vec<Vec<String>> {
+    fn walk(value: &Value, path: &mut Vec<String>, paths: &mut Vec<Vec<String>>) {
+        match value {
+            Value::Object(table) if !table.is_empty() || path.is_empty() => {
+                for (key, value) in table {
+                    path.push(key.clone());
+                    walk(value, path, paths);
+                    path.pop();
+                }
+            }
+            _ => paths.push(path.clone()),
+        }
+    }
+    let mut paths = Vec::new();
+    walk(value, &mut Vec::new(), &mut paths);
+    paths
+}
+
+/// The value at `path`, if set
+pub fn lookup<'a>(value: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, key| value.get(key))
+}
+
+/// `value` nested under the tables in `path`
+fn nest(path: &[String], value: Value) -> Value {
+    path.iter().rev().fold(value, |value, key| {
+        let mut table = Map::new();
+        table.insert(key.clone(), value);
+        Value::Object(table)
+    })
+}
+
+fn issue_in(layer: &Layer, path: &[String], problem: Problem) -> Issue {
+    Issue {
+        origin: layer.origin.clone(),
+        line: line_of(layer, path),
+        key: path.join("."),
+        problem,
+    }
+}
+
+/// The field named in a serde "unknown field" error
+fn unknown_field(message: &str) -> Option<&str> {
+    let rest = message.strip_prefix("unknown field `")?;
+    rest.split('`').next()
+}
+
+/// Parse a TOML or JSON file, or return the line and message of the error
+fn parse(path: &Path, text: &str) -> std::result::Result<Value, (Option<usize>, String)> {
+    if is_toml(path) {
+        toml::from_str(text).map_err(|e| {
+            let line = e.span().map(|span| line_at(text, span.start));
+            (line, e.message().trim().to_string())
+        })
+    } else {
+        serde_json::from_str(text).map_err(|e| {
+            // The issue shows the line, so drop serde_json's " at line N
+            // column M"
+            let message = e.to_string();
+            let message = match message.rfind(" at line ") {
+                Some(at) => message[..at].to_string(),
+                None => message,
+            };
+            (Some(e.line()), message)
+        })
+    }
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "toml")
+}
+
+/// 1-based line of byte `offset` in `text`
+fn line_at(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+/// Line of the key at `path` in a file layer
+fn line_of(layer: &Layer, path: &[String]) -> Option<usize> {
+    let text = layer.text.as_deref()?;
+    let path = match &layer.table {
+        Some(table) if path.first() == Some(table) => &path[1..],
+        _ => path,
+    };
+    if path.is_empty() {
+        return None;
+    }
+    let offset = match &layer.origin {
+        Origin::File(file) if is_toml(file) => toml_key_offset(text, path)?,
+        _ => json_key_offset(text, path)?,
+    };
+    Some(line_at(text, offset))
+}
+
+/// Offset of the key at `path` in a TOML document
+fn toml_key_offset(text: &str, path: &[String]) -> Option<usize> {
+    let document = toml::de::DeTable::parse(text).ok()?;
+    let mut table = document.get_ref();
+    let mut offset = None;
+    for key in path {
+        let (found, value) = table
+            .iter()
+            .find(|(found, _)| *found.get_ref() == key.as_str())?;
+        offset = Some(found.span().start);
+        match value.get_ref().as_table() {
+            Some(inner) => table = inner,
+            None => break,
+        }
+    }
+    offset
+}
+
+/// Offset of the key at `path` in a JSON document, found by looking for
+/// each key in turn after the one before it
+fn json_key_offset(text: &str, path: &[String]) -> Option<usize> {
+    let mut offset = 0;
+    for key in path {
+        let quoted = format!("\"{}\"", key);
+        let mut from = offset;
+        loop {
+            let at = from + text[from..].find(&quoted)?;
+            from = at + quoted.len();
+            if text[from..].trim_start().starts_with(':') {
+                offset = at;
+                break;
+            }
+        }
+    }
+    Some(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    struct Settings {
+        name: String,
+        limit: u64,
+        section: Section,
+    }
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    struct Section {
+        enabled: bool,
+        url: Option<String>,
+    }
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn key(key: &str) -> Vec<String> {
+        key.split('.').map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_layers_merge_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let json_path = dir.path().join("settings.json");
+        std::fs::write(&json_path, r#"{"name": "json", "limit": 5}"#).unwrap();
+        let toml_path = dir.path().join("settings.toml");
+        std::fs::write(&toml_path, "name = \"toml\"\n\n[section]\nenabled = true\n").unwrap();
+
+        let layers = Layers::new(&Settings::default())
+            .unwrap()
+            .file(&json_path)
+            .unwrap()
+            .file(&toml_path)
+            .unwrap()
+            .file(&dir.path().join("missing.toml"))
+            .unwrap()
+            .env(
+                "TEST_",
+                env(&[
+                    ("TEST_LIMIT", "9"),
+                    ("TEST_SECTION__URL", "http://localhost:4318"),
+                    ("TEST_UNRELATED", "1"),
+                    ("OTHER_LIMIT", "1"),
+                ]),
+            );
+        let settings: Settings = layers.build().unwrap();
+
+        assert_eq!(
+            settings,
+            Settings {
+                name: "toml".to_string(),
+                limit: 9,
+                section: Section {
+                    enabled: true,
+                    url: Some("http://localhost:4318".to_string()),
+                },
+            }
+        );
+        assert_eq!(
+            layers.source(&key("name")),
+            (Origin::File(toml_path.clone()), Some(1))
+        );
+        assert_eq!(
+            layers.source(&key("section.enabled")),
+            (Origin::File(toml_path), Some(4))
+        );
+        assert_eq!(
+            layers.source(&key("limit")),
+            (Origin::Env("TEST_LIMIT".to_string()), None)
+        );
+    }
+
+    #[test]
+    fn test_file_at_nests_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("section.json");
+        std::fs::write(&path, "{\n  \"enabled\": true\n}").unwrap();
+
+        let layers = Layers::new(&Settings::default())
+            .unwrap()
+            .file_at(&path, "section")
+            .unwrap();
+        let settings: Settings = layers.build().unwrap();
+        assert!(settings.section.enabled);
+        assert_eq!(
+            layers.source(&key("section.enabled")),
+            (Origin::File(path), Some(2))
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_each_bad_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.toml");
+        std::fs::write(
+            &path,
+            "name = \"ok\"\nlimit = \"lots\"\n\n[section]\nenabeld = true\n\n[extra]\nkey = 1\n",
+        )
+        .unwrap();
+
+        let layers = Layers::new(&Settings::default())
+            .unwrap()
+            .file(&path)
+            .unwrap()
+            .env("TEST_", env(&[("TEST_SECTION__ENABLED", "maybe")]));
+        let issues = layers.validate::<Settings>(&[]);
+        let mut summary: Vec<(String, Option<usize>, bool)> = issues
+            .iter()
+            .map(|issue| {
+                let unknown = matches!(issue.problem, Problem::UnknownKey(_));
+                (issue.key.clone(), issue.line, unknown)
+            })
+            .collect();
+        summary.sort();
+        assert_eq!(
+            summary,
+            vec![
+                ("extra".to_string(), Some(7), true),
+                ("limit".to_string(), Some(2), false),
+                ("section.enabeld".to_string(), Some(5), true),
+                ("section.enabled".to_string(), None, false),
+            ]
+        );
+
+        let limit = issues.iter().find(|issue| issue.key == "limit").unwrap();
+        assert_eq!(
+            limit.to_string(),
+            format!(
+                "{}:2: limit: invalid type: string \"lots\", expected u64",
+                path.display()
+            )
+        );
+        let env_issue = issues.iter().find(|issue| issue.line.is_none()).unwrap();
+        assert_eq!(
+            env_issue.origin,
+            Origin::Env("TEST_SECTION__ENABLED".to_string())
+        );
+
+        let error = layers.build::<Settings>().unwrap_err();
+        assert_eq!(error.downcast_ref::<InvalidConfig>().unwrap().0.len(), 4);
+    }
+
+    #[test]
+    fn test_validate_reports_syntax_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml_path = dir.path().join("broken.toml");
+        std::fs::write(&toml_path, "name = \"ok\"\nlimit = nope\n").unwrap();
+        let json_path = dir.path().join("broken.json");
+        std::fs::write(&json_path, "{\n  \"name\": \"ok\",\n}").unwrap();
+
+        let issues = Layers::new(&Settings::default())
+            .unwrap()
+            .file(&toml_path)
+            .unwrap()
+            .file(&json_path)
+            .unwrap()
+            .validate::<Settings>(&[]);
+        assert_eq!(issues.len(), 2);
+        assert!(matches!(issues[0].problem, Problem::Syntax(_)));
+        assert_eq!(issues[0].line, Some(2));
+        assert_eq!(issues[1].origin, Origin::File(json_path));
+        assert_eq!(issues[1].line, Some(3));
+    }
+
+    #[test]
+    fn test_validate_nested_overrides() {
+        #[derive(Debug, Default, Serialize, Deserialize)]
+        #[serde(default, deny_unknown_fields)]
+        struct WithOverrides {
+            limit: u64,
+            #[serde(skip_serializing_if = "Map::is_empty")]
+            overrides: Map<String, Value>,
+        }
+
+        let layers = Layers::new(&WithOverrides::default())
+            .unwrap()
+            .value(json!({
+                "overrides": {"a": {"limit": 1}, "b": {"limit": -1, "nope": true}}
+            }));
+        let mut keys: Vec<String> = layers
+            .validate::<WithOverrides>(&["overrides"])
+            .into_iter()
+            .map(|issue| issue.key)
+            .collect();
+        keys.sort();
+        assert_eq!(keys, vec!["overrides.b.limit", "overrides.b.nope"]);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut base = json!({"a": {"b": 1, "c": 2}, "d": [1, 2]});
+        merge(&mut base, json!({"a": {"c": 3}, "d": [3], "e": null}));
+        assert_eq!(base, json!({"a": {"b": 1, "c": 3}, "d": [3], "e": null}));
+    }
+}
@@ -1,25 +1,70 @@
 //! Configuration loading for Cosmos applications
 //!
 //! Provides utilities for loading configuration files from the shared
-//! Cosmos config directory (~/.config/cosmos/).
+//! Cosmos config directory (see [`config_dir`]). Mail data lives in the
+//! data directory (see [`data_dir`]), which on Linux is separate.
 //!
-//! Call [`init`] at application startup to bootstrap the config directory.
+//! Call [`select_profile`] first thing at application startup, then
+//! [`init`] to bootstrap the config directory and [`migrate_data`] before
+//! opening mail data. Each named profile has its own directories.
+//!
+//! Settings shared between the apps live in [`CosmosConfig`], layered from
+//! defaults, TOML or JSON files and environment variables (see [`Layers`]).
+//! Invalid settings are reported with the file and line that set them, and
+//! [`doctor`] summarizes the effective configuration, and [`watch_config`]
+//! reports edits so settings can apply without a restart.
+
+mod cosmos;
+mod doctor;
+mod layered;
+mod migrate;
+mod profile;
+mod watch;
 
 use anyhow::{Context, Result};
 use serde::de::DeserializeOwned;
 use std::path::{Path, PathBuf};
 
+pub use cosmos::{
+    COSMOS_CONFIG_FILE, COSMOS_JSON_CONFIG_FILE, CosmosConfig, DaemonSettings, ENV_PREFIX,
+    LEGACY_DAEMON_CONFIG_FILE, NetworkSettings, StorageSettings, SyncSettings,
+};
+pub use doctor::{ConfigFile, Doctor, Setting, doctor};
+pub use layered::{
+    ENV_SEPARATOR, InvalidConfig, Issue, Layers, Origin, Problem, leaf_paths, lookup, merge,
+};
+pub use migrate::migrate_data;
+pub use profile::{PROFILE_ENV_VAR, profile, profile_arg, select_profile};
+pub use watch::{ConfigWatcher, watch_config, watch_files};
+
 /// Initialize the Cosmos config directory.
 ///
-/// Creates ~/.config/cosmos/ if it doesn't exist.
+/// Creates the config directory if it doesn't exist.
 /// Call this once at application startup.
 pub fn init() -> Result<PathBuf> {
     ensure_config_dir()
 }
 
-/// Get the Cosmos config directory (~/.config/cosmos/)
+/// Get the Cosmos config directory
+///
+/// - Linux: `$XDG_CONFIG_HOME/cosmos/` (~/.config/cosmos/)
+/// - macOS: ~/Library/Application Support/cosmos/
+/// - Windows: `%APPDATA%\cosmos\`
+///
+/// Named profiles use `profiles/<name>/` inside it.
 pub fn config_dir() -> Option<PathBuf> {
-    dirs::config_dir().map(|p| p.join("cosmos"))
+    dirs::config_dir().map(|p| profile::profile_dir(p.join("cosmos")))
+}
+
+/// Get the Cosmos data directory, the default home of the mail database,
+/// blobs and search index
+///
+/// - Linux: `$XDG_DATA_HOME/cosmos/` (~/.local/share/cosmos/)
+/// - macOS and Windows: the same as [`config_dir`]
+///
+/// Named profiles use `profiles/<name>/` inside it.
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|p| profile::profile_dir(p.join("cosmos")))
 }
 
 /// Get the path to a config file within the Cosmos config directory
@@ -41,6 +86,20 @@ pub fn load_json_file<T: DeserializeOwned>(path: &Path) -> Result<T> {
         .with_context(|| format!("Failed to parse config file: {}", path.display()))
 }
 
+/// Load and parse a TOML config file from the Cosmos config directory
+pub fn load_toml<T: DeserializeOwned>(filename: &str) -> Result<T> {
+    let path = config_path(filename).context("Could not determine config directory")?;
+    load_toml_file(&path)
+}
+
+/// Load and parse a TOML file from an arbitrary path
+pub fn load_toml_file<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+    toml::from_str(&content)
+        .with_context(|| format!("Failed to parse config file: {}", path.display()))
+}
+
 /// Check if a config file exists in the Cosmos config directory
 pub fn config_exists(filename: &str) -> bool {
     config_path(filename).is_some_and(|p| p.exists())
@@ -75,6 +134,13 @@ mod tests {
         assert!(dir.unwrap().ends_with("cosmos"));
     }
 
+    #[test]
+    fn test_data_dir() {
+        let dir = data_dir();
+        assert!(dir.is_some());
+        assert!(dir.unwrap().ends_with("cosmos"));
+    }
+
     #[test]
     fn test_config_path() {
         let path = config_path("test.json");
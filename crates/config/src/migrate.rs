@@ -0,0 +1,125 @@
+//! Moving mail data out of the config directory
+//!
+//! Older versions kept the mail database, blobs and search index next to
+//! the config files. They now default to the data directory, which on
+//! Linux is `$XDG_DATA_HOME/cosmos` rather than `$XDG_CONFIG_HOME/cosmos`;
+//! [`migrate_data`] moves data left behind in the old place.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::cosmos::{BLOB_DIR, DB_FILE, INDEX_DIR};
+use crate::{config_dir, data_dir};
+
+/// Move mail data from the config directory to the data directory
+///
+/// Returns the paths moved to. Does nothing when the two directories are
+/// the same (macOS, Windows) or the data directory already has a database,
+/// so it's safe to call at every startup, before opening the store.
+pub fn migrate_data() -> Result<Vec<PathBuf>> {
+    let from = config_dir().context("Could not determine config directory")?;
+    let to = data_dir().context("Could not determine data directory")?;
+    migrate_data_between(&from, &to)
+}
+
+fn migrate_data_between(from: &Path, to: &Path) -> Result<Vec<PathBuf>> {
+    if from == to || to.join(DB_FILE).exists() {
+        return Ok(Vec::new());
+    }
+
+    // The database goes last: if moving is interrupted, the data directory
+    // still has no database and the next call picks up the rest
+    let entries = [
+        format!("{}-wal", DB_FILE),
+        format!("{}-shm", DB_FILE),
+        BLOB_DIR.to_string(),
+        INDEX_DIR.to_string(),
+        DB_FILE.to_string(),
+    ];
+    let mut moved = Vec::new();
+    for name in entries {
+        let source = from.join(&name);
+        if !source.exists() {
+            continue;
+        }
+        std::fs::create_dir_all(to)
+            .with_context(|| format!("Failed to create data directory: {}", to.display()))?;
+        let dest = to.join(&name);
+        move_entry(&source, &dest).with_context(|| {
+            format!("Failed to move {} to {}", source.display(), dest.display())
+        })?;
+        moved.push(dest);
+    }
+    Ok(moved)
+}
+
+/// Rename `source`, or copy and delete it when `dest` is on another
+/// filesystem
+fn move_entry(source: &Path, dest: &Path) -> std::io::Result<()> {
+    if std::fs::rename(source, dest).is_ok() {
+        return Ok(());
+    }
+    copy_all(source, dest)?;
+    if source.is_dir() {
+        std::fs::remove_dir_all(source)
+    } else {
+        std::fs::remove_file(source)
+    }
+}
+
+fn copy_all(source: &Path, dest: &Path) -> std::io::Result<()> {
+    if !source.is_dir() {
+        return std::fs::copy(source, dest).map(|_| ());
+    }
+    std::fs::create_dir_all(dest)?;
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        copy_all(&entry.path(), &dest.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_data_moves_mail_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let (from, to) = (dir.path().join("config"), dir.path().join("data"));
+        std::fs::create_dir_all(from.join("blobs/ab")).unwrap();
+        std::fs::write(from.join("blobs/ab/cd"), "body").unwrap();
+        std::fs::write(from.join("mail.db"), "db").unwrap();
+        std::fs::write(from.join("mail.db-wal"), "wal").unwrap();
+        std::fs::write(from.join("cosmos.toml"), "").unwrap();
+
+        let moved = migrate_data_between(&from, &to).unwrap();
+        assert_eq!(
+            moved,
+            vec![to.join("mail.db-wal"), to.join("blobs"), to.join("mail.db")]
+        );
+        assert_eq!(
+            std::fs::read_to_string(to.join("blobs/ab/cd")).unwrap(),
+            "body"
+        );
+        assert!(!from.join("mail.db").exists());
+        assert!(from.join("cosmos.toml").exists());
+
+        // Only once
+        assert!(migrate_data_between(&from, &to).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migrate_data_keeps_existing_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let (from, to) = (dir.path().join("config"), dir.path().join("data"));
+        std::fs::create_dir_all(&from).unwrap();
+        std::fs::create_dir_all(&to).unwrap();
+        std::fs::write(from.join("mail.db"), "old").unwrap();
+        std::fs::write(to.join("mail.db"), "new").unwrap();
+
+        assert!(migrate_data_between(&from, &to).unwrap().is_empty());
+        assert_eq!(std::fs::read_to_string(to.join("mail.db")).unwrap(), "new");
+        assert!(from.join("mail.db").exists());
+    }
+}
@@ -0,0 +1,120 @@
+//! Named profiles
+//!
+//! A profile gets its own config and data directories, so work and personal
+//! setups (or a throwaway test setup) never share a database, search index,
+//! accounts or daemon. Apps choose one at startup with `--profile <name>` or
+//! the `COSMOS_PROFILE` environment variable; without either they use the
+//! default profile, which lives directly in the Cosmos directories.
+
+use anyhow::{Result, anyhow, bail};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Environment variable naming the profile when `--profile` isn't given
+pub const PROFILE_ENV_VAR: &str = "COSMOS_PROFILE";
+
+/// Subdirectory of the Cosmos directories holding named profiles
+const PROFILES_DIR: &str = "profiles";
+
+static PROFILE: OnceLock<Option<String>> = OnceLock::new();
+
+/// Choose the profile for the rest of the process: `name` if given,
+/// otherwise `COSMOS_PROFILE`, otherwise the default profile
+///
+/// Call once at startup, before anything reads a config or data path.
+pub fn select_profile(name: Option<&str>) -> Result<Option<&'static str>> {
+    let name = match name {
+        Some(name) => Some(name.to_string()),
+        None => std::env::var(PROFILE_ENV_VAR)
+            .ok()
+            .filter(|name| !name.is_empty()),
+    };
+    if let Some(name) = &name {
+        validate_profile(name)?;
+    }
+    PROFILE
+        .set(name)
+        .map_err(|_| anyhow!("The profile was already chosen"))?;
+    Ok(profile())
+}
+
+/// The profile chosen by [`select_profile`], or `None` for the default one
+pub fn profile() -> Option<&'static str> {
+    PROFILE.get().and_then(|name| name.as_deref())
+}
+
+/// The value of `--profile <name>` in command-line `args`, if given
+pub fn profile_arg(args: &[String]) -> Result<Option<&str>> {
+    match args.iter().position(|arg| arg == "--profile") {
+        Some(index) => match args.get(index + 1) {
+            Some(name) => Ok(Some(name)),
+            None => bail!("--profile needs a name"),
+        },
+        None => Ok(None),
+    }
+}
+
+/// `dir` for the current profile
+pub(crate) fn profile_dir(dir: PathBuf) -> PathBuf {
+    in_profile(dir, profile())
+}
+
+fn in_profile(dir: PathBuf, profile: Option<&str>) -> PathBuf {
+    match profile {
+        Some(name) => dir.join(PROFILES_DIR).join(name),
+        None => dir,
+    }
+}
+
+/// Profile names become directory names, so keep them to one plain
+/// path component
+fn validate_profile(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        bail!(
+            "Invalid profile name '{}': use letters, digits, '-', '_' and '.'",
+            name
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_profile() {
+        let dir = PathBuf::from("/home/me/.config/cosmos");
+        assert_eq!(in_profile(dir.clone(), None), dir);
+        assert_eq!(
+            in_profile(dir, Some("work")),
+            PathBuf::from("/home/me/.config/cosmos/profiles/work")
+        );
+    }
+
+    #[test]
+    fn test_validate_profile() {
+        for name in ["work", "personal-2", "test_run", "v1.2"] {
+            assert!(validate_profile(name).is_ok(), "{}", name);
+        }
+        for name in ["", ".", "..", ".hidden", "a/b", "a\\b", "work space"] {
+            assert!(validate_profile(name).is_err(), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn test_profile_arg() {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            profile_arg(&args(&["--once", "--profile", "work"])).unwrap(),
+            Some("work")
+        );
+        assert_eq!(profile_arg(&args(&["--once"])).unwrap(), None);
+        assert!(profile_arg(&args(&["--profile"])).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+//! Config file watching
+//!
+//! [`watch_config`] runs a callback whenever a file in the Cosmos config
+//! directory changes, so apps can apply edited settings without a restart.
+//! Changes are debounced: editors often save in several steps (truncate,
+//! write, rename), and the callback should see the finished file once.
+
+use anyhow::{Context, Result};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+use crate::config_path;
+
+/// Quiet period after a change before the callback runs
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Watches config files until dropped
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl std::fmt::Debug for ConfigWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigWatcher").finish_non_exhaustive()
+    }
+}
+
+/// Call `callback` each time `filename` in the Cosmos config directory is
+/// created, changed or removed
+///
+/// The callback runs on a background thread. Watching stops when the
+/// returned [`ConfigWatcher`] is dropped.
+pub fn watch_config<F>(filename: &str, callback: F) -> Result<ConfigWatcher>
+where
+    F: FnMut() + Send + 'static,
+{
+    let path = config_path(filename).context("Could not determine config directory")?;
+    watch_files(&[path], callback)
+}
+
+/// Call `callback` each time any of `paths` is created, changed or removed
+///
+/// See [`watch_config`].
+pub fn watch_files<F>(paths: &[PathBuf], mut callback: F) -> Result<ConfigWatcher>
+where
+    F: FnMut() + Send + 'static,
+{
+    let names: HashSet<OsString> = paths
+        .iter()
+        .filter_map(|path| path.file_name().map(|name| name.to_os_string()))
+        .collect();
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        let watched = event
+            .paths
+            .iter()
+            .any(|path| path.file_name().is_some_and(|name| names.contains(name)));
+        if watched && !event.kind.is_access() {
+            let _ = sender.send(());
+        }
+    })
+    .context("Failed to start watching config files")?;
+
+    // Watch the directories rather than the files: saving by renaming a
+    // temporary file replaces the watched file, and a file that doesn't
+    // exist yet can't be watched at all
+    let dirs: HashSet<&Path> = paths.iter().filter_map(|path| path.parent()).collect();
+    for dir in dirs {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create config directory: {}", dir.display()))?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {}", dir.display()))?;
+    }
+
+    std::thread::Builder::new()
+        .name("config-watch".to_string())
+        .spawn(move || {
+            while receiver.recv().is_ok() {
+                loop {
+                    match receiver.recv_timeout(DEBOUNCE) {
+                        Ok(()) => continue,
+                        Err(RecvTimeoutError::Timeout) => break,
+                        // The watcher was dropped
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                callback();
+            }
+        })
+        .context("Failed to start config watch thread")?;
+
+    Ok(ConfigWatcher { _watcher: watcher })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_files_calls_back_on_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("watched.toml");
+        let (sender, receiver) = mpsc::channel();
+        let _watcher = watch_files(std::slice::from_ref(&path), move || {
+            sender.send(()).unwrap();
+        })
+        .unwrap();
+
+        std::fs::write(dir.path().join("other.toml"), "a = 1").unwrap();
+        assert!(receiver.recv_timeout(DEBOUNCE * 4).is_err());
+
+        // Several writes in a row are one change
+        std::fs::write(&path, "a = 1").unwrap();
+        std::fs::write(&path, "a = 2").unwrap();
+        assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+        assert!(receiver.recv_timeout(DEBOUNCE * 4).is_err());
+    }
+}
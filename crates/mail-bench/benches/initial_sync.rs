@@ -0,0 +1,46 @@
+//! Criterion benchmarks of initial sync into SQLite and Tantivy
+//!
+//! Each sample syncs the whole synthetic mailbox into a fresh store, so
+//! throughput is reported in messages per second. Peak RSS is printed after
+//! each size. Set `MAIL_BENCH_SIZES` to run fewer sizes, e.g. in CI.
+
+use std::time::Duration;
+
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use mail_bench::{SyncTarget, mailbox_sizes, peak_rss_bytes, synthetic_mailbox};
+
+fn initial_sync(c: &mut Criterion) {
+    let mut group = c.benchmark_group("initial_sync");
+    // A sample is a full sync; criterion's minimum sample count is plenty
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(30));
+
+    for messages in mailbox_sizes().expect("valid mailbox sizes") {
+        let gmail = synthetic_mailbox(messages);
+        group.throughput(Throughput::Elements(messages as u64));
+        group.bench_function(BenchmarkId::from_parameter(messages), |b| {
+            b.iter_batched(
+                || SyncTarget::new().expect("benchmark store"),
+                |target| {
+                    let stats = target.sync(&gmail).expect("sync");
+                    assert_eq!(stats.messages_created, messages);
+                    // Dropped outside the measurement
+                    target
+                },
+                BatchSize::PerIteration,
+            );
+        });
+
+        if let Some(bytes) = peak_rss_bytes() {
+            println!(
+                "initial_sync/{}: peak RSS {:.1} MiB",
+                messages,
+                bytes as f64 / (1024.0 * 1024.0)
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, initial_sync);
+criterion_main!(benches);
@@ -0,0 +1,214 @@
+//! Initial sync benchmarks on synthetic mailboxes
+//!
+//! Builds mailboxes of generated mail served by [`MockGmail`] and syncs
+//! them into a [`SqliteMailStore`] with a Tantivy [`SearchIndex`], the way
+//! the apps do, so performance work has baselines to compare against.
+//!
+//! - `cargo bench -p mail-bench` runs the criterion suite, reporting
+//!   messages per second for each mailbox size.
+//! - `cargo run -p mail-bench --release -- 100000` runs one sync and prints
+//!   its throughput and peak RSS as JSON, for regression checks in CI.
+//!
+//! Both take the mailbox sizes to run from `MAIL_BENCH_SIZES`, e.g.
+//! `MAIL_BENCH_SIZES=10000`, defaulting to [`MAILBOX_SIZES`].
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use mail::gmail::mock::{MockGmail, MockMessage};
+use mail::{
+    Account, FileBlobStore, MailStore, SearchIndex, SqliteMailStore, SyncOptions, SyncStats,
+};
+use tempfile::TempDir;
+
+/// Mailbox sizes benchmarked by default, in messages
+pub const MAILBOX_SIZES: [usize; 3] = [10_000, 100_000, 500_000];
+
+/// Environment variable overriding [`MAILBOX_SIZES`], comma separated
+pub const SIZES_VAR: &str = "MAIL_BENCH_SIZES";
+
+/// Messages per block of the synthetic mailbox; each block holds one long
+/// thread and many short ones
+const BLOCK_SIZE: usize = 1_000;
+
+/// Messages in each block's long thread
+const LONG_THREAD_LENGTH: usize = 100;
+
+/// Messages in the short threads
+const SHORT_THREAD_LENGTH: usize = 3;
+
+/// Distinct senders in the synthetic mailbox
+const SENDERS: usize = 500;
+
+const WORDS: [&str; 16] = [
+    "meeting", "invoice", "project", "update", "schedule", "review", "travel", "budget", "report",
+    "launch", "design", "contract", "lunch", "team", "deadline", "notes",
+];
+
+/// Mailbox sizes to run, from [`SIZES_VAR`] or [`MAILBOX_SIZES`]
+pub fn mailbox_sizes() -> Result<Vec<usize>> {
+    match std::env::var(SIZES_VAR) {
+        Ok(sizes) => sizes
+            .split(',')
+            .map(|size| {
+                size.trim()
+                    .replace('_', "")
+                    .parse()
+                    .with_context(|| format!("Invalid {} entry: {:?}", SIZES_VAR, size))
+            })
+            .collect(),
+        Err(_) => Ok(MAILBOX_SIZES.to_vec()),
+    }
+}
+
+/// A mailbox of `messages` generated messages, oldest first
+///
+/// Every block of [`BLOCK_SIZE`] messages has a [`LONG_THREAD_LENGTH`]
+/// message thread; the rest fall into short threads. Senders, subjects,
+/// bodies and labels vary deterministically, so runs are comparable.
+pub fn synthetic_mailbox(messages: usize) -> MockGmail {
+    let mut gmail = MockGmail::new();
+    for i in 0..messages {
+        gmail = gmail.with_message(synthetic_message(i));
+    }
+    gmail
+}
+
+fn synthetic_message(i: usize) -> MockMessage {
+    let block = i / BLOCK_SIZE;
+    let offset = i % BLOCK_SIZE;
+    let thread_id = if offset < LONG_THREAD_LENGTH {
+        format!("long-{}", block)
+    } else {
+        format!(
+            "t-{}-{}",
+            block,
+            (offset - LONG_THREAD_LENGTH) / SHORT_THREAD_LENGTH
+        )
+    };
+
+    let word = |n: usize| WORDS[(i * 7 + n * 13) % WORDS.len()];
+    let body: Vec<&str> = (0..40).map(word).collect();
+
+    let mut labels = Vec::new();
+    if i % 3 == 0 {
+        labels.push("INBOX");
+    }
+    if i % 5 == 0 {
+        labels.push("UNREAD");
+    }
+    if i % 10 == 0 {
+        labels.push("SENT");
+    }
+    if i % 4 == 1 {
+        labels.push("CATEGORY_PROMOTIONS");
+    }
+
+    MockMessage::new(format!("m{}", i), thread_id)
+        .from(format!("Sender {0} <sender{0}@example.com>", i % SENDERS))
+        .subject(format!(
+            "{} {} #{}",
+            word(0),
+            word(1),
+            i / SHORT_THREAD_LENGTH
+        ))
+        .body(body.join(" "))
+        .labels(&labels)
+}
+
+/// A fresh SQLite store and Tantivy index in a temporary directory
+pub struct SyncTarget {
+    store: SqliteMailStore,
+    index: Arc<SearchIndex>,
+    account_id: i64,
+    _dir: TempDir,
+}
+
+impl SyncTarget {
+    pub fn new() -> Result<Self> {
+        let dir = TempDir::new().context("Failed to create benchmark directory")?;
+        let blob_store = Box::new(FileBlobStore::new(dir.path().join("blobs"))?);
+        let store = SqliteMailStore::new(dir.path().join("mail.sqlite"), blob_store)?;
+        let index = Arc::new(SearchIndex::open(dir.path().join("search"))?);
+        let account = store.register_account(Account::new("me@example.com"))?;
+        Ok(Self {
+            store,
+            index,
+            account_id: account.id,
+            _dir: dir,
+        })
+    }
+
+    /// Run an initial sync of `gmail`, indexing as it goes
+    pub fn sync(&self, gmail: &MockGmail) -> Result<SyncStats> {
+        let options = SyncOptions {
+            search_index: Some(self.index.clone()),
+            ..SyncOptions::default()
+        };
+        mail::sync_gmail(gmail, &self.store, self.account_id, options)
+    }
+}
+
+/// Result of one benchmarked sync
+#[derive(Debug, Clone)]
+pub struct SyncReport {
+    /// Messages in the mailbox
+    pub messages: usize,
+    pub elapsed: Duration,
+    /// Peak resident set size of the process so far, including the mock
+    /// mailbox (Linux only)
+    pub peak_rss_bytes: Option<u64>,
+}
+
+impl SyncReport {
+    pub fn messages_per_sec(&self) -> f64 {
+        self.messages as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// One line of JSON, for CI to collect
+    pub fn to_json(&self) -> String {
+        let rss = self
+            .peak_rss_bytes
+            .map_or_else(|| "null".to_string(), |bytes| bytes.to_string());
+        format!(
+            r#"{{"messages": {}, "elapsed_secs": {:.3}, "messages_per_sec": {:.1}, "peak_rss_bytes": {}}}"#,
+            self.messages,
+            self.elapsed.as_secs_f64(),
+            self.messages_per_sec(),
+            rss
+        )
+    }
+}
+
+/// Sync a synthetic mailbox of `messages` into a fresh target and report
+/// how it went
+pub fn run_initial_sync(messages: usize) -> Result<SyncReport> {
+    let gmail = synthetic_mailbox(messages);
+    let target = SyncTarget::new()?;
+
+    let start = Instant::now();
+    let stats = target.sync(&gmail)?;
+    let elapsed = start.elapsed();
+    anyhow::ensure!(
+        stats.messages_created == messages,
+        "Synced {} of {} messages",
+        stats.messages_created,
+        messages
+    );
+
+    Ok(SyncReport {
+        messages,
+        elapsed,
+        peak_rss_bytes: peak_rss_bytes(),
+    })
+}
+
+/// Peak resident set size of this process (`VmHWM`), where the platform
+/// reports it
+pub fn peak_rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
@@ -0,0 +1,29 @@
+//! mail-bench - Run initial sync on synthetic mailboxes and print the numbers
+//!
+//! `mail-bench [MESSAGES...]` syncs one mailbox per size given, or the sizes
+//! from `MAIL_BENCH_SIZES`, printing a line of JSON for each. Build with
+//! `--release`; peak RSS only ever grows, so list sizes smallest first or
+//! run one per process.
+
+use anyhow::Context;
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let sizes = if args.is_empty() {
+        mail_bench::mailbox_sizes()?
+    } else {
+        args.iter()
+            .map(|arg| {
+                arg.replace('_', "")
+                    .parse()
+                    .with_context(|| format!("Invalid mailbox size: {}", arg))
+            })
+            .collect::<anyhow::Result<_>>()?
+    };
+
+    for messages in sizes {
+        let report = mail_bench::run_initial_sync(messages)?;
+        println!("{}", report.to_json());
+    }
+    Ok(())
+}
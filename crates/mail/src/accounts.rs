@@ -0,0 +1,407 @@
+//! Account health, aliases, clearing and removal
+//!
+//! Removing or reconnecting an account touches the store, the search index,
+//! and Google's OAuth server, so it is coordinated here rather than by any
+//! one of them.
+
+use anyhow::{Context, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::error::MailError;
+use crate::gmail::api::GmailSendAs;
+use crate::gmail::{GmailAuth, GmailClient, ReauthRequiredError, StoredToken};
+use crate::models::{Account, Label, LabelColor, SendAsAlias};
+use crate::search::SearchBackend;
+use crate::storage::{MailStore, ThreadCursor};
+
+/// Threads read from the store per batch when removing search documents
+const THREAD_BATCH_SIZE: usize = 200;
+
+/// Whether an account can still talk to Gmail
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccountHealth {
+    /// The account has a usable token
+    Healthy,
+    /// The token was revoked or is missing; sync stops until the user
+    /// signs in again
+    NeedsReauth,
+}
+
+/// Get the health of an account from its stored token
+pub fn account_health(store: &dyn MailStore, account_id: i64) -> Result<AccountHealth> {
+    let account = store
+        .get_account(account_id)?
+        .ok_or_else(|| MailError::NotFound(format!("Account {}", account_id)))?;
+
+    let usable = account
+        .token_data
+        .as_deref()
+        .is_some_and(|data| serde_json::from_str::<StoredToken>(data).is_ok());
+    Ok(if usable {
+        AccountHealth::Healthy
+    } else {
+        AccountHealth::NeedsReauth
+    })
+}
+
+/// Record a failed Gmail request against an account
+///
+/// If `error` is a `ReauthRequiredError`, the account's token is cleared so
+/// it reports `NeedsReauth` until reconnected. Synced mail is kept. Other
+/// errors leave the account alone.
+///
+/// Returns the account's health afterwards.
+pub fn record_auth_failure(
+    store: &dyn MailStore,
+    account_id: i64,
+    error: &anyhow::Error,
+) -> Result<AccountHealth> {
+    if error.downcast_ref::<ReauthRequiredError>().is_some() {
+        warn!("Account {} needs to be reconnected", account_id);
+        store.update_account_token(account_id, None)?;
+    }
+    account_health(store, account_id)
+}
+
+/// Sign an existing account in again, keeping its synced data
+///
+/// Runs the interactive OAuth flow (blocking until the browser redirects
+/// back) and stores the new token. Fails without changing anything if the
+/// user signs in as a different Gmail account.
+///
+/// Returns the account with its new token.
+pub fn reconnect_account(
+    store: &dyn MailStore,
+    account_id: i64,
+    client_id: String,
+    client_secret: String,
+) -> Result<Account> {
+    let account = store
+        .get_account(account_id)?
+        .ok_or_else(|| MailError::NotFound(format!("Account {}", account_id)))?;
+
+    let auth = GmailAuth::with_token_data(client_id, client_secret, None);
+    auth.authorize()?;
+    let client = GmailClient::new(auth);
+    let profile = client.get_profile()?;
+    if !profile.email_address.eq_ignore_ascii_case(&account.email) {
+        anyhow::bail!(
+            "Signed in as {}, but this account is {}",
+            profile.email_address,
+            account.email
+        );
+    }
+
+    let token_data = client.get_token_data();
+    store.update_account_token(account_id, token_data.clone())?;
+    info!("Reconnected account {} (id={})", account.email, account_id);
+    Ok(Account {
+        token_data,
+        ..account
+    })
+}
+
+/// Fetch an account's send-as aliases from Gmail and store them
+///
+/// Returns the stored aliases, default first.
+pub fn refresh_send_as_aliases(
+    client: &GmailClient,
+    store: &dyn MailStore,
+    account_id: i64,
+) -> Result<Vec<SendAsAlias>> {
+    let aliases = client
+        .list_send_as()?
+        .into_iter()
+        .map(|send_as| send_as_alias(account_id, send_as))
+        .collect();
+    store.replace_send_as_aliases(account_id, aliases)?;
+    store.list_send_as_aliases(account_id)
+}
+
+/// Convert a Gmail sendAs resource into an alias
+///
+/// The primary address never has a verification status; it is always usable.
+fn send_as_alias(account_id: i64, send_as: GmailSendAs) -> SendAsAlias {
+    let verified = send_as.is_primary || send_as.verification_status.as_deref() == Some("accepted");
+    SendAsAlias {
+        account_id,
+        email: send_as.send_as_email,
+        display_name: send_as.display_name.filter(|name| !name.is_empty()),
+        is_primary: send_as.is_primary,
+        is_default: send_as.is_default,
+        verified,
+    }
+}
+
+/// Fetch an account's user labels from Gmail and store them
+///
+/// System labels are left out; they are always known. Gmail colors are kept
+/// alongside any local icon and color overrides. Returns the stored labels,
+/// ordered by name, for building the nested label tree.
+pub fn refresh_labels(
+    client: &GmailClient,
+    store: &dyn MailStore,
+    account_id: i64,
+) -> Result<Vec<Label>> {
+    let labels = client
+        .list_labels()?
+        .labels
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|label| label.label_type.as_deref() == Some("user"))
+        .map(|label| {
+            let color = label
+                .color
+                .map(|color| LabelColor::new(color.background_color, color.text_color));
+            Label::new(label.id, label.name).with_color(color)
+        })
+        .collect();
+    store.replace_labels(account_id, labels)?;
+    store.list_labels(account_id)
+}
+
+/// Remove an account and everything stored for it
+///
+/// Revokes the account's OAuth token, removes its threads from the search
+/// index, then deletes the account with its threads, messages, blobs, rules
+/// and sync state. Revocation is best-effort: being offline, or Google
+/// rejecting the request, shouldn't leave an account the user asked to
+/// remove, so failures are only logged.
+///
+/// # Arguments
+/// * `store` - The storage backend
+/// * `search` - The search index, if one is in use
+/// * `account_id` - Account to remove
+pub fn remove_account(
+    store: &dyn MailStore,
+    search: Option<&dyn SearchBackend>,
+    account_id: i64,
+) -> Result<()> {
+    let account = store
+        .get_account(account_id)?
+        .ok_or_else(|| MailError::NotFound(format!("Account {}", account_id)))?;
+
+    if let Some(token_data) = &account.token_data
+        && let Err(e) = GmailAuth::revoke_token_data(token_data)
+    {
+        warn!("Failed to revoke token for {}: {}", account.email, e);
+    }
+
+    if let Some(search) = search {
+        delete_search_documents(store, search, account_id)?;
+    }
+
+    store.delete_account(account_id)?;
+    info!("Removed account {} (id={})", account.email, account_id);
+    Ok(())
+}
+
+/// Delete an account's mail but keep the account, e.g. before a full resync
+///
+/// Removes its search documents, then everything
+/// [`MailStore::clear_account_data`] covers. Other accounts are untouched.
+///
+/// # Arguments
+/// * `store` - The storage backend
+/// * `search` - The search index, if one is in use
+/// * `account_id` - Account to clear
+pub fn clear_account(
+    store: &dyn MailStore,
+    search: Option<&dyn SearchBackend>,
+    account_id: i64,
+) -> Result<()> {
+    if store.get_account(account_id)?.is_none() {
+        return Err(MailError::NotFound(format!("Account {}", account_id)).into());
+    }
+
+    if let Some(search) = search {
+        delete_search_documents(store, search, account_id)?;
+    }
+
+    store.clear_account_data(account_id)?;
+    info!("Cleared mail for account {}", account_id);
+    Ok(())
+}
+
+/// Remove the search documents of every thread in an account
+///
+/// Reads the threads from the store, so call it before deleting them.
+fn delete_search_documents(
+    store: &dyn MailStore,
+    search: &dyn SearchBackend,
+    account_id: i64,
+) -> Result<()> {
+    let mut cursor: Option<ThreadCursor> = None;
+    loop {
+        let batch = store.list_threads_after(
+            None,
+            Some(account_id),
+            cursor.as_ref(),
+            THREAD_BATCH_SIZE,
+        )?;
+        let exhausted = batch.len() < THREAD_BATCH_SIZE;
+
+        for thread in &batch {
+            search.delete_thread(&thread.id)?;
+        }
+
+        cursor = batch.last().map(ThreadCursor::for_thread);
+        if exhausted || cursor.is_none() {
+            break;
+        }
+    }
+    search.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Account, Message, MessageId, Thread, ThreadId};
+    use crate::search::SearchIndex;
+    use crate::storage::InMemoryMailStore;
+    use chrono::Utc;
+    use std::collections::HashSet;
+
+    fn register(store: &InMemoryMailStore, email: &str) -> Account {
+        store
+            .register_account(Account {
+                id: 0,
+                email: email.to_string(),
+                display_name: None,
+                avatar_color: "hsl(210, 70%, 50%)".to_string(),
+                is_primary: false,
+                added_at: Utc::now(),
+                token_data: None,
+                signature: None,
+                sync_enabled: true,
+            })
+            .unwrap()
+    }
+
+    fn add_thread(store: &InMemoryMailStore, index: &SearchIndex, id: &str, account_id: i64) {
+        let thread = Thread::new(
+            ThreadId::new(id),
+            account_id,
+            "Invoice".to_string(),
+            String::new(),
+            Utc::now(),
+            1,
+            None,
+            "billing@example.com".to_string(),
+            false,
+        );
+        let message = Message::builder(MessageId::new(format!("m_{}", id)), thread.id.clone())
+            .account_id(account_id)
+            .subject("Invoice")
+            .build();
+        index.index_message(&message, &thread).unwrap();
+        store.upsert_thread(thread).unwrap();
+        store.upsert_message(message).unwrap();
+    }
+
+    #[test]
+    fn test_send_as_alias() {
+        let send_as: GmailSendAs = serde_json::from_str(
+            r#"{"sendAsEmail":"team@example.com","displayName":"","isDefault":true,
+                "verificationStatus":"pending"}"#,
+        )
+        .unwrap();
+        let alias = send_as_alias(3, send_as);
+        assert_eq!(alias.account_id, 3);
+        assert!(alias.is_default && !alias.is_primary && !alias.verified);
+        assert!(alias.display_name.is_none());
+
+        let send_as: GmailSendAs =
+            serde_json::from_str(r#"{"sendAsEmail":"me@example.com","isPrimary":true}"#).unwrap();
+        assert!(send_as_alias(3, send_as).verified);
+    }
+
+    #[test]
+    fn test_account_health() {
+        let store = InMemoryMailStore::new();
+        let index = SearchIndex::in_memory().unwrap();
+        let work = register(&store, "work@example.com");
+        add_thread(&store, &index, "t1", work.id);
+        assert_eq!(
+            account_health(&store, work.id).unwrap(),
+            AccountHealth::NeedsReauth
+        );
+
+        let token = r#"{"access_token":"a","refresh_token":"r","expires_at":null}"#;
+        store
+            .update_account_token(work.id, Some(token.to_string()))
+            .unwrap();
+        assert_eq!(
+            account_health(&store, work.id).unwrap(),
+            AccountHealth::Healthy
+        );
+
+        // Network failures don't cost the account its token
+        let offline = anyhow::anyhow!("connection refused");
+        let health = record_auth_failure(&store, work.id, &offline).unwrap();
+        assert_eq!(health, AccountHealth::Healthy);
+
+        let revoked = anyhow::Error::from(ReauthRequiredError).context("Failed to get profile");
+        let health = record_auth_failure(&store, work.id, &revoked).unwrap();
+        assert_eq!(health, AccountHealth::NeedsReauth);
+        let account = store.get_account(work.id).unwrap().unwrap();
+        assert!(account.token_data.is_none());
+        assert!(store.get_thread(&ThreadId::new("t1")).unwrap().is_some());
+
+        assert!(account_health(&store, work.id + 1).is_err());
+    }
+
+    #[test]
+    fn test_remove_account() {
+        let store = InMemoryMailStore::new();
+        let index = SearchIndex::in_memory().unwrap();
+        let work = register(&store, "work@example.com");
+        let home = register(&store, "home@example.com");
+        add_thread(&store, &index, "t1", work.id);
+        add_thread(&store, &index, "t2", home.id);
+        index.commit().unwrap();
+
+        remove_account(&store, Some(&index), work.id).unwrap();
+
+        assert!(store.get_account(work.id).unwrap().is_none());
+        assert!(store.get_thread(&ThreadId::new("t1")).unwrap().is_none());
+        let indexed = index.indexed_message_ids().unwrap();
+        assert_eq!(indexed, HashSet::from([MessageId::new("m_t2")]));
+        assert!(store.get_thread(&ThreadId::new("t2")).unwrap().is_some());
+
+        assert!(remove_account(&store, Some(&index), work.id).is_err());
+    }
+
+    #[test]
+    fn test_clear_account() {
+        let store = InMemoryMailStore::new();
+        let index = SearchIndex::in_memory().unwrap();
+        let work = register(&store, "work@example.com");
+        let home = register(&store, "home@example.com");
+        add_thread(&store, &index, "t1", work.id);
+        add_thread(&store, &index, "t2", home.id);
+        index.commit().unwrap();
+        store
+            .store_pending_message(&MessageId::new("p1"), work.id, b"{}", Vec::new())
+            .unwrap();
+        store
+            .store_pending_message(&MessageId::new("p2"), home.id, b"{}", Vec::new())
+            .unwrap();
+
+        clear_account(&store, Some(&index), work.id).unwrap();
+
+        // The account stays, its mail goes
+        assert!(store.get_account(work.id).unwrap().is_some());
+        assert!(store.get_thread(&ThreadId::new("t1")).unwrap().is_none());
+        assert!(store.get_message(&MessageId::new("m_t1")).unwrap().is_none());
+        assert!(!store.has_pending_message(&MessageId::new("p1")).unwrap());
+        let indexed = index.indexed_message_ids().unwrap();
+        assert_eq!(indexed, HashSet::from([MessageId::new("m_t2")]));
+
+        assert!(store.get_thread(&ThreadId::new("t2")).unwrap().is_some());
+        assert!(store.has_pending_message(&MessageId::new("p2")).unwrap());
+
+        assert!(clear_account(&store, Some(&index), work.id + 10).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+//! Downloading received attachments
+//!
+//! Stored messages only keep attachment file names, so a download refetches
+//! the message to find the part, then fetches the part's bytes. Small parts
+//! that Gmail returns inline are decoded without a second request.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use base64::prelude::*;
+
+use super::compose::mime_type_for;
+use crate::gmail::GmailClient;
+use crate::gmail::api::MessagePart;
+use crate::models::MessageId;
+
+/// An attachment downloaded from a received message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    pub filename: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Attachment {
+    /// Whether this is an image the UI can show directly
+    pub fn is_image(&self) -> bool {
+        self.mime_type.starts_with("image/")
+    }
+
+    /// Whether this is a PDF document
+    pub fn is_pdf(&self) -> bool {
+        self.mime_type == "application/pdf"
+    }
+
+    /// Write the attachment into `dir` under its own file name
+    ///
+    /// An existing file is never overwritten; " (2)", " (3)"... is added to
+    /// the name instead. Returns the path written.
+    pub fn save_in(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = unused_path(dir, &safe_file_name(&self.filename));
+        std::fs::write(&path, &self.data)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// Download the attachment named `filename` from a message
+pub(super) fn download_attachment(
+    client: &GmailClient,
+    message_id: &MessageId,
+    filename: &str,
+) -> Result<Attachment> {
+    let message = client.get_message(message_id)?;
+    let part = message
+        .payload
+        .as_ref()
+        .and_then(|payload| payload.parts.as_deref())
+        .and_then(|parts| find_part(parts, filename))
+        .with_context(|| {
+            format!(
+                "Message {} has no attachment named {}",
+                message_id.as_str(),
+                filename
+            )
+        })?;
+
+    let body = part.body.as_ref();
+    let data = match (
+        body.and_then(|b| b.attachment_id.as_deref()),
+        body.and_then(|b| b.data.as_deref()),
+    ) {
+        (Some(attachment_id), _) => client.get_attachment(message_id, attachment_id)?,
+        (None, Some(data)) => BASE64_URL_SAFE_NO_PAD
+            .decode(data.trim_end_matches('='))
+            .context("Attachment data is not valid base64url")?,
+        (None, None) => bail!("Attachment {} has no content", filename),
+    };
+
+    Ok(Attachment {
+        filename: filename.to_string(),
+        mime_type: part_mime_type(part, filename),
+        data,
+    })
+}
+
+/// Find the first part, at any depth, with the given file name
+fn find_part<'a>(parts: &'a [MessagePart], filename: &str) -> Option<&'a MessagePart> {
+    parts.iter().find_map(|part| {
+        if part.filename.as_deref() == Some(filename) {
+            return Some(part);
+        }
+        find_part(part.parts.as_deref()?, filename)
+    })
+}
+
+/// A part's MIME type, guessed from the file name when the sender only
+/// said application/octet-stream
+fn part_mime_type(part: &MessagePart, filename: &str) -> String {
+    match part.mime_type.as_deref().map(str::to_ascii_lowercase) {
+        Some(mime_type) if !mime_type.is_empty() && mime_type != "application/octet-stream" => {
+            mime_type
+        }
+        _ => mime_type_for(filename).to_string(),
+    }
+}
+
+/// Strip path separators and other characters file systems reject
+fn safe_file_name(filename: &str) -> String {
+    let name: String = filename
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let name = name.trim().trim_start_matches('.');
+    if name.is_empty() {
+        "attachment".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// `dir/name`, or `dir/stem (n).ext` for the first n that isn't taken
+fn unused_path(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    if !path.exists() {
+        return path;
+    }
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (name, String::new()),
+    };
+    (2..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, extension)))
+        .find(|path| !path.exists())
+        .expect("some numbered name is free")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gmail::api::MessageBody;
+
+    fn part(filename: &str, mime_type: &str, nested: Vec<MessagePart>) -> MessagePart {
+        MessagePart {
+            part_id: None,
+            mime_type: Some(mime_type.to_string()),
+            filename: Some(filename.to_string()),
+            headers: None,
+            body: Some(MessageBody {
+                size: Some(3),
+                data: None,
+                attachment_id: Some(format!("att-{}", filename)),
+            }),
+            parts: (!nested.is_empty()).then_some(nested),
+        }
+    }
+
+    #[test]
+    fn test_find_part() {
+        let parts = vec![
+            part("", "text/plain", vec![]),
+            part(
+                "",
+                "multipart/mixed",
+                vec![part("photo.JPG", "application/octet-stream", vec![])],
+            ),
+            part("report.pdf", "application/pdf", vec![]),
+        ];
+
+        let found = find_part(&parts, "photo.JPG").unwrap();
+        assert_eq!(part_mime_type(found, "photo.JPG"), "image/jpeg");
+        let found = find_part(&parts, "report.pdf").unwrap();
+        assert_eq!(part_mime_type(found, "report.pdf"), "application/pdf");
+        assert!(find_part(&parts, "missing.txt").is_none());
+    }
+
+    #[test]
+    fn test_save_in() {
+        let dir = tempfile::tempdir().unwrap();
+        let attachment = Attachment {
+            filename: "../notes.txt".to_string(),
+            mime_type: "text/plain".to_string(),
+            data: b"hi".to_vec(),
+        };
+
+        let first = attachment.save_in(dir.path()).unwrap();
+        let second = attachment.save_in(dir.path()).unwrap();
+        assert_eq!(first, dir.path().join("_notes.txt"));
+        assert_eq!(second, dir.path().join("_notes (2).txt"));
+        assert_eq!(std::fs::read(&second).unwrap(), b"hi");
+    }
+}
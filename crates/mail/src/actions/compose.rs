@@ -0,0 +1,724 @@
+//! Outgoing message builders
+//!
+//! Builds RFC 2822 messages for new mail and replies. The sending account's
+//! signature is appended to the body unless the caller opts out. Messages
+//! with attachments are wrapped in multipart/mixed.
+
+use anyhow::{Context, Result};
+use base64::prelude::*;
+use std::path::Path;
+
+use super::markdown::markdown_to_html;
+use super::unsubscribe::{encode_header, single_line};
+use crate::models::{
+    Account, EmailAddress, Message, OutboxMessage, SendAsAlias, Signature, ThreadId,
+};
+
+/// Separator line between a plain text body and its signature (RFC 3676)
+const SIGNATURE_DELIMITER: &str = "-- ";
+
+/// Gmail's limit on the combined size of a message's attachments
+pub const MAX_ATTACHMENTS_SIZE: usize = 25 * 1024 * 1024;
+
+/// Line length for base64-encoded attachment bodies (RFC 2045)
+const BASE64_LINE_LENGTH: usize = 76;
+
+/// An earlier message quoted below a reply
+#[derive(Debug, Clone)]
+struct Quote {
+    /// "On <date>, <sender> wrote:"
+    attribution: String,
+    text: String,
+}
+
+impl Quote {
+    fn new(original: &Message) -> Self {
+        let attribution = format!(
+            "On {}, {} wrote:",
+            original.received_at.format("%a, %b %-d, %Y at %H:%M"),
+            original.from.display()
+        );
+        let text = original
+            .body_text
+            .clone()
+            .unwrap_or_else(|| original.body_preview.clone());
+        Self {
+            attribution,
+            text: text.trim_end().to_string(),
+        }
+    }
+
+    /// Plain text form, each line prefixed with "> "
+    fn to_text(&self) -> String {
+        let lines: Vec<String> = self
+            .text
+            .lines()
+            .map(|line| match line {
+                "" => ">".to_string(),
+                _ => format!("> {}", line),
+            })
+            .collect();
+        format!("{}\r\n{}", self.attribution, lines.join("\r\n"))
+    }
+
+    fn to_html(&self) -> String {
+        format!(
+            "<div class=\"quote\">{}<blockquote style=\"margin:0 0 0 .8ex;\
+             border-left:1px solid #ccc;padding-left:1ex\">{}</blockquote></div>",
+            escape_html(&self.attribution),
+            escape_html(&self.text).replace('\n', "<br>")
+        )
+    }
+}
+
+/// A file attached to an outgoing message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutgoingAttachment {
+    pub filename: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl OutgoingAttachment {
+    /// Attach `data` as `filename`, guessing the MIME type from its extension
+    pub fn new(filename: impl Into<String>, data: Vec<u8>) -> Self {
+        let filename = filename.into();
+        let mime_type = mime_type_for(&filename).to_string();
+        Self {
+            filename,
+            mime_type,
+            data,
+        }
+    }
+
+    /// Read a file from disk
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path)
+            .with_context(|| format!("Failed to read attachment {}", path.display()))?;
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "attachment".to_string());
+        Ok(Self::new(filename, data))
+    }
+
+    /// Size of the file in bytes
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// The MIME part for this attachment, without its boundary line
+    fn mime_part(&self) -> String {
+        let name = encode_header(&single_line(&self.filename)).replace(['"', '\\'], "");
+        let encoded = BASE64_STANDARD.encode(&self.data);
+        let lines: Vec<&str> = encoded
+            .as_bytes()
+            .chunks(BASE64_LINE_LENGTH)
+            .map(|chunk| std::str::from_utf8(chunk).expect("base64 is ASCII"))
+            .collect();
+        format!(
+            "Content-Type: {}; name=\"{n}\"\r\n\
+             Content-Disposition: attachment; filename=\"{n}\"\r\n\
+             Content-Transfer-Encoding: base64\r\n\r\n{}\r\n",
+            self.mime_type,
+            lines.join("\r\n"),
+            n = name
+        )
+    }
+}
+
+/// A new message or reply, ready to turn into RFC 2822
+#[derive(Debug, Clone)]
+pub struct OutgoingMessage {
+    account_id: i64,
+    account_email: String,
+    from: EmailAddress,
+    to: Vec<EmailAddress>,
+    cc: Vec<EmailAddress>,
+    bcc: Vec<EmailAddress>,
+    subject: String,
+    body_text: String,
+    body_html: Option<String>,
+    attachments: Vec<OutgoingAttachment>,
+    signature: Option<Signature>,
+    quote: Option<Quote>,
+    thread_id: Option<ThreadId>,
+    track_opens: bool,
+    /// Tracking image appended to the HTML body, set when sending
+    beacon: Option<String>,
+}
+
+impl OutgoingMessage {
+    /// Start a new message from `account`
+    pub fn compose(account: &Account) -> Self {
+        let from = match &account.display_name {
+            Some(name) => EmailAddress::with_name(name, &account.email),
+            None => EmailAddress::new(&account.email),
+        };
+        Self {
+            account_id: account.id,
+            account_email: account.email.clone(),
+            from,
+            to: Vec::new(),
+            cc: Vec::new(),
+            bcc: Vec::new(),
+            subject: String::new(),
+            body_text: String::new(),
+            body_html: None,
+            attachments: Vec::new(),
+            signature: account.signature.clone(),
+            quote: None,
+            thread_id: None,
+            track_opens: false,
+            beacon: None,
+        }
+    }
+
+    /// Start a reply from `account` to the sender of `original`
+    ///
+    /// The subject gets a "Re: " prefix unless it already has one, and the
+    /// reply is sent into the original's thread.
+    pub fn reply(account: &Account, original: &Message) -> Self {
+        let subject = if original.subject.to_lowercase().starts_with("re:") {
+            original.subject.clone()
+        } else {
+            format!("Re: {}", original.subject)
+        };
+        let mut message = Self::compose(account)
+            .to(original.from.clone())
+            .subject(subject);
+        message.thread_id = Some(original.thread_id.clone());
+        message
+    }
+
+    /// Send from one of the account's send-as aliases instead of its own address
+    ///
+    /// Checked against the stored aliases when sending; see `check_sender`.
+    pub fn from_alias(mut self, alias: &SendAsAlias) -> Self {
+        self.from = alias.address();
+        self
+    }
+
+    /// Add a recipient
+    pub fn to(mut self, address: EmailAddress) -> Self {
+        self.to.push(address);
+        self
+    }
+
+    /// Add a CC recipient
+    pub fn cc(mut self, address: EmailAddress) -> Self {
+        self.cc.push(address);
+        self
+    }
+
+    /// Add a BCC recipient
+    ///
+    /// Gmail delivers to BCC recipients and strips the header before the
+    /// message reaches anyone else.
+    pub fn bcc(mut self, address: EmailAddress) -> Self {
+        self.bcc.push(address);
+        self
+    }
+
+    /// Set the subject
+    pub fn subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = subject.into();
+        self
+    }
+
+    /// Set the plain text body
+    pub fn body_text(mut self, body: impl Into<String>) -> Self {
+        self.body_text = body.into();
+        self
+    }
+
+    /// Set an HTML body, sent alongside the plain text one
+    pub fn body_html(mut self, body: impl Into<String>) -> Self {
+        self.body_html = Some(body.into());
+        self
+    }
+
+    /// Set the body from Markdown
+    ///
+    /// The Markdown source is sent as the plain text part, rendered to HTML
+    /// for the HTML part.
+    pub fn body_markdown(self, markdown: impl Into<String>) -> Self {
+        let markdown = markdown.into();
+        let html = markdown_to_html(&markdown);
+        self.body_text(markdown).body_html(html)
+    }
+
+    /// Quote `original` below the body and signature
+    pub fn quoting(mut self, original: &Message) -> Self {
+        self.quote = Some(Quote::new(original));
+        self
+    }
+
+    /// Attach a file
+    pub fn attach(mut self, attachment: OutgoingAttachment) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
+
+    /// Send without the account's signature
+    pub fn without_signature(mut self) -> Self {
+        self.signature = None;
+        self
+    }
+
+    /// Ask for opens to be tracked
+    ///
+    /// Only takes effect for HTML messages, and only if open tracking is
+    /// enabled in `TrackingConfig`; otherwise the message is sent untracked.
+    pub fn track_opens(mut self, track: bool) -> Self {
+        self.track_opens = track;
+        self
+    }
+
+    /// Whether a tracking beacon can be embedded in this message
+    pub fn tracks_opens(&self) -> bool {
+        self.track_opens && self.body_html.is_some()
+    }
+
+    /// Embed the tracking beacon at `url`
+    pub(super) fn with_beacon(mut self, url: &str) -> Self {
+        self.beacon = Some(crate::tracking::beacon_html(url));
+        self
+    }
+
+    /// The account sending the message
+    pub fn account_id(&self) -> i64 {
+        self.account_id
+    }
+
+    /// The From address
+    pub fn from(&self) -> &EmailAddress {
+        &self.from
+    }
+
+    /// Check the From address is the account's own or a verified alias
+    ///
+    /// Gmail rewrites an unverified From address to the account's own, so
+    /// sending would silently use the wrong address.
+    pub fn check_sender(&self, aliases: &[SendAsAlias]) -> Result<()> {
+        let email = &self.from.email;
+        if email.eq_ignore_ascii_case(&self.account_email) {
+            return Ok(());
+        }
+        match aliases
+            .iter()
+            .find(|alias| alias.email.eq_ignore_ascii_case(email))
+        {
+            Some(alias) if alias.verified => Ok(()),
+            Some(_) => anyhow::bail!("{} has not been verified for sending", email),
+            None => anyhow::bail!("{} is not an alias of {}", email, self.account_email),
+        }
+    }
+
+    /// Check the attachments fit within Gmail's size limit
+    pub fn check_attachments(&self) -> Result<()> {
+        let total: usize = self.attachments.iter().map(OutgoingAttachment::size).sum();
+        if total > MAX_ATTACHMENTS_SIZE {
+            anyhow::bail!(
+                "Attachments total {} MB, over Gmail's {} MB limit",
+                total.div_ceil(1024 * 1024),
+                MAX_ATTACHMENTS_SIZE / (1024 * 1024)
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether the message has any recipient
+    pub fn has_recipients(&self) -> bool {
+        !(self.to.is_empty() && self.cc.is_empty() && self.bcc.is_empty())
+    }
+
+    /// The thread a reply belongs to, for `GmailClient::send_message_in_thread`
+    pub fn thread_id(&self) -> Option<&ThreadId> {
+        self.thread_id.as_ref()
+    }
+
+    /// An outbox entry for this message, already rendered as `raw`
+    pub(super) fn to_outbox(&self, raw: String) -> OutboxMessage {
+        let recipients = self
+            .to
+            .iter()
+            .chain(&self.cc)
+            .chain(&self.bcc)
+            .map(|address| address.email.clone())
+            .collect();
+        OutboxMessage::new(
+            self.account_id,
+            self.thread_id.clone(),
+            self.subject.clone(),
+            recipients,
+            raw,
+        )
+    }
+
+    /// Plain text body with the signature and any quote appended
+    pub fn full_body_text(&self) -> String {
+        let mut text = match &self.signature {
+            Some(signature) => format!(
+                "{}\r\n\r\n{}\r\n{}",
+                self.body_text, SIGNATURE_DELIMITER, signature.text
+            ),
+            None => self.body_text.clone(),
+        };
+        if let Some(quote) = &self.quote {
+            text.push_str("\r\n\r\n");
+            text.push_str(&quote.to_text());
+        }
+        text
+    }
+
+    /// HTML body with the signature, any quote and any tracking beacon
+    /// appended, if there is an HTML body
+    pub fn full_body_html(&self) -> Option<String> {
+        let mut html = self.body_html.clone()?;
+        if let Some(signature) = &self.signature {
+            let signature_html = match &signature.html {
+                Some(html) => html.clone(),
+                None => escape_html(&signature.text).replace('\n', "<br>"),
+            };
+            html.push_str(&format!(
+                "<br><br><div class=\"signature\">{}</div>",
+                signature_html
+            ));
+        }
+        if let Some(quote) = &self.quote {
+            html.push_str("<br><br>");
+            html.push_str(&quote.to_html());
+        }
+        if let Some(beacon) = &self.beacon {
+            html.push_str(beacon);
+        }
+        Some(html)
+    }
+
+    /// Build the RFC 2822 message
+    pub fn to_rfc2822(&self) -> String {
+        let mut raw = format!("From: {}\r\n", format_address(&self.from));
+        if !self.to.is_empty() {
+            raw.push_str(&format!("To: {}\r\n", format_addresses(&self.to)));
+        }
+        if !self.cc.is_empty() {
+            raw.push_str(&format!("Cc: {}\r\n", format_addresses(&self.cc)));
+        }
+        if !self.bcc.is_empty() {
+            raw.push_str(&format!("Bcc: {}\r\n", format_addresses(&self.bcc)));
+        }
+        raw.push_str(&format!(
+            "Subject: {}\r\nMIME-Version: 1.0\r\n",
+            encode_header(&single_line(&self.subject))
+        ));
+
+        let body = self.body_part();
+        if self.attachments.is_empty() {
+            raw.push_str(&body);
+            return raw;
+        }
+
+        let boundary = unused_boundary("cosmos-mixed", &[&body]);
+        raw.push_str(&format!(
+            "Content-Type: multipart/mixed; boundary=\"{b}\"\r\n\r\n--{b}\r\n{}",
+            body,
+            b = boundary
+        ));
+        for attachment in &self.attachments {
+            raw.push_str(&format!("--{}\r\n{}", boundary, attachment.mime_part()));
+        }
+        raw.push_str(&format!("--{}--\r\n", boundary));
+        raw
+    }
+
+    /// The body as a MIME part: plain text, or text and HTML alternatives
+    fn body_part(&self) -> String {
+        let text = self.full_body_text();
+        match self.full_body_html() {
+            None => format!(
+                "Content-Type: text/plain; charset=UTF-8\r\n\r\n{}\r\n",
+                text
+            ),
+            Some(html) => {
+                let boundary = boundary_for(&text, &html);
+                format!(
+                    "Content-Type: multipart/alternative; boundary=\"{b}\"\r\n\r\n\
+                     --{b}\r\nContent-Type: text/plain; charset=UTF-8\r\n\r\n{}\r\n\
+                     --{b}\r\nContent-Type: text/html; charset=UTF-8\r\n\r\n{}\r\n\
+                     --{b}--\r\n",
+                    text,
+                    html,
+                    b = boundary
+                )
+            }
+        }
+    }
+}
+
+/// Format an address for a header, encoding non-ASCII display names
+pub(super) fn format_address(address: &EmailAddress) -> String {
+    let email = single_line(&address.email);
+    match &address.name {
+        Some(name) if name.is_ascii() => {
+            let name = single_line(name).replace(['"', '\\'], "");
+            format!("\"{}\" <{}>", name, email)
+        }
+        Some(name) => format!("{} <{}>", encode_header(&single_line(name)), email),
+        None => email,
+    }
+}
+
+fn format_addresses(addresses: &[EmailAddress]) -> String {
+    addresses
+        .iter()
+        .map(format_address)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A multipart boundary that doesn't occur in either body
+pub(super) fn boundary_for(text: &str, html: &str) -> String {
+    unused_boundary("cosmos-alt", &[text, html])
+}
+
+/// The first `prefix-N` boundary that doesn't occur in any of `parts`
+fn unused_boundary(prefix: &str, parts: &[&str]) -> String {
+    (0u32..)
+        .map(|n| format!("{}-{}", prefix, n))
+        .find(|b| parts.iter().all(|part| !part.contains(b.as_str())))
+        .expect("boundary candidates are unbounded")
+}
+
+/// Guess a MIME type from a file name's extension
+pub(super) fn mime_type_for(filename: &str) -> &'static str {
+    let extension = Path::new(filename)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "txt" | "md" => "text/plain",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "ics" => "text/calendar",
+        "json" => "application/json",
+        "zip" => "application/zip",
+        "doc" => "application/msword",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xls" => "application/vnd.ms-excel",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "ppt" => "application/vnd.ms-powerpoint",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        _ => "application/octet-stream",
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MessageId;
+    use chrono::{TimeZone, Utc};
+
+    fn account() -> Account {
+        Account::with_id(1, "me@example.com")
+            .with_display_name("Me")
+            .with_signature(Signature::text("Me\nExample Co"))
+    }
+
+    #[test]
+    fn test_compose_appends_signature() {
+        let raw = OutgoingMessage::compose(&account())
+            .to(EmailAddress::with_name("Ana", "ana@example.com"))
+            .subject("Lunch")
+            .body_text("Noon?")
+            .to_rfc2822();
+
+        assert!(raw.starts_with("From: \"Me\" <me@example.com>\r\n"));
+        assert!(raw.contains("\r\nTo: \"Ana\" <ana@example.com>\r\n"));
+        assert!(raw.contains("\r\nSubject: Lunch\r\n"));
+        assert!(raw.ends_with("\r\n\r\nNoon?\r\n\r\n-- \r\nMe\nExample Co\r\n"));
+
+        let raw = OutgoingMessage::compose(&account())
+            .body_text("Noon?")
+            .without_signature()
+            .to_rfc2822();
+        assert!(raw.ends_with("\r\n\r\nNoon?\r\n"));
+    }
+
+    #[test]
+    fn test_reply() {
+        let original = Message::builder(MessageId::new("m1"), ThreadId::new("t1"))
+            .from(EmailAddress::new("ana@example.com"))
+            .subject("Lunch")
+            .build();
+
+        let reply = OutgoingMessage::reply(&account(), &original).body_text("Sure");
+        assert_eq!(reply.thread_id(), Some(&ThreadId::new("t1")));
+        let raw = reply.to_rfc2822();
+        assert!(raw.contains("\r\nTo: ana@example.com\r\n"));
+        assert!(raw.contains("\r\nSubject: Re: Lunch\r\n"));
+        assert!(raw.contains("Sure\r\n\r\n-- \r\nMe"));
+
+        let original = Message::builder(MessageId::new("m2"), ThreadId::new("t1"))
+            .subject("RE: Lunch")
+            .build();
+        let raw = OutgoingMessage::reply(&account(), &original).to_rfc2822();
+        assert!(raw.contains("\r\nSubject: RE: Lunch\r\n"));
+    }
+
+    #[test]
+    fn test_quoting() {
+        let original = Message::builder(MessageId::new("m1"), ThreadId::new("t1"))
+            .from(EmailAddress::with_name("Ana", "ana@example.com"))
+            .subject("Lunch")
+            .body_text(Some("Noon?\n\nOr <1pm>".to_string()))
+            .received_at(Utc.with_ymd_and_hms(2024, 3, 5, 11, 30, 0).unwrap())
+            .build();
+
+        let reply = OutgoingMessage::reply(&account(), &original)
+            .body_text("Sure")
+            .quoting(&original);
+        assert!(reply.full_body_text().ends_with(
+            "Sure\r\n\r\n-- \r\nMe\nExample Co\r\n\r\n\
+             On Tue, Mar 5, 2024 at 11:30, Ana <ana@example.com> wrote:\r\n\
+             > Noon?\r\n>\r\n> Or <1pm>"
+        ));
+
+        let html = reply.body_html("<p>Sure</p>").full_body_html().unwrap();
+        assert!(html.contains("Ana &lt;ana@example.com&gt; wrote:<blockquote"));
+        assert!(html.ends_with("Noon?<br><br>Or &lt;1pm&gt;</blockquote></div>"));
+    }
+
+    #[test]
+    fn test_check_sender() {
+        let mut team = SendAsAlias::new(1, "team@example.com");
+        team.display_name = Some("Team".to_string());
+        let mut pending = SendAsAlias::new(1, "pending@example.com");
+        pending.verified = false;
+        let aliases = vec![team.clone(), pending.clone()];
+
+        let message = OutgoingMessage::compose(&account());
+        assert!(message.check_sender(&[]).is_ok());
+
+        let message = message.from_alias(&team);
+        assert!(message.check_sender(&aliases).is_ok());
+        assert!(message.check_sender(&[]).is_err());
+        assert!(
+            message
+                .to_rfc2822()
+                .starts_with("From: \"Team\" <team@example.com>\r\n")
+        );
+
+        let message = OutgoingMessage::compose(&account()).from_alias(&pending);
+        assert!(message.check_sender(&aliases).is_err());
+    }
+
+    #[test]
+    fn test_html_signature() {
+        let message = OutgoingMessage::compose(&account()).body_html("<p>Hi</p>");
+        let html = message.full_body_html().unwrap();
+        assert!(html.ends_with("<div class=\"signature\">Me<br>Example Co</div>"));
+
+        let mut account = account();
+        account.signature = Some(Signature::text("Me").with_html("<b>Me</b>"));
+        let raw = OutgoingMessage::compose(&account)
+            .body_text("Hi")
+            .body_html("<p>Hi</p>")
+            .to_rfc2822();
+        assert!(raw.contains("multipart/alternative; boundary=\"cosmos-alt-0\""));
+        assert!(raw.contains("<div class=\"signature\"><b>Me</b></div>"));
+        assert!(raw.ends_with("--cosmos-alt-0--\r\n"));
+    }
+
+    #[test]
+    fn test_bcc_and_markdown() {
+        let raw = OutgoingMessage::compose(&account())
+            .to(EmailAddress::new("ana@example.com"))
+            .bcc(EmailAddress::new("boss@example.com"))
+            .body_markdown("**Noon?**")
+            .without_signature()
+            .to_rfc2822();
+        assert!(raw.contains("\r\nBcc: boss@example.com\r\n"));
+        assert!(raw.contains("charset=UTF-8\r\n\r\n**Noon?**\r\n"));
+        assert!(raw.contains("charset=UTF-8\r\n\r\n<p><strong>Noon?</strong></p>\r\n"));
+    }
+
+    #[test]
+    fn test_attachments() {
+        let attachment = OutgoingAttachment::new("Notes.PDF", vec![7; 100]);
+        assert_eq!(attachment.mime_type, "application/pdf");
+        assert_eq!(
+            OutgoingAttachment::new("data.bin", vec![]).mime_type,
+            "application/octet-stream"
+        );
+
+        let raw = OutgoingMessage::compose(&account())
+            .body_text("See attached")
+            .without_signature()
+            .attach(attachment)
+            .to_rfc2822();
+        assert!(raw.contains("Content-Type: multipart/mixed; boundary=\"cosmos-mixed-0\"\r\n"));
+        assert!(raw.contains(
+            "--cosmos-mixed-0\r\nContent-Type: text/plain; charset=UTF-8\r\n\r\nSee attached\r\n"
+        ));
+        assert!(raw.contains(
+            "Content-Type: application/pdf; name=\"Notes.PDF\"\r\n\
+             Content-Disposition: attachment; filename=\"Notes.PDF\"\r\n\
+             Content-Transfer-Encoding: base64\r\n\r\n"
+        ));
+        assert!(raw.ends_with("--cosmos-mixed-0--\r\n"));
+
+        // 100 bytes encode to 136 characters, wrapped at 76
+        let body = raw.split("base64\r\n\r\n").nth(1).unwrap();
+        let lines: Vec<&str> = body.split("\r\n").collect();
+        assert_eq!(lines[0].len(), 76);
+        assert_eq!(lines[1].len(), 60);
+        assert_eq!(lines[2], "--cosmos-mixed-0--");
+    }
+
+    #[test]
+    fn test_check_attachments() {
+        let message = OutgoingMessage::compose(&account());
+        assert!(message.check_attachments().is_ok());
+        let message = message.attach(OutgoingAttachment::new(
+            "big.zip",
+            vec![0; MAX_ATTACHMENTS_SIZE + 1],
+        ));
+        assert!(message.check_attachments().is_err());
+    }
+
+    #[test]
+    fn test_beacon_follows_signature() {
+        let plain = OutgoingMessage::compose(&account())
+            .body_text("Hi")
+            .track_opens(true);
+        assert!(!plain.tracks_opens());
+
+        let message = OutgoingMessage::compose(&account())
+            .body_html("<p>Hi</p>")
+            .track_opens(true);
+        assert!(message.tracks_opens());
+        assert!(!message.full_body_html().unwrap().contains("<img"));
+
+        let html = message
+            .with_beacon("https://t.example.com/open?t=abc")
+            .full_body_html()
+            .unwrap();
+        assert!(html.ends_with(
+            "Example Co</div><img src=\"https://t.example.com/open?t=abc\" width=\"1\" \
+             height=\"1\" alt=\"\" style=\"display:none\">"
+        ));
+    }
+}
@@ -2,13 +2,26 @@
 //!
 //! Coordinates between Gmail API and local storage for mutations.
 
-use anyhow::Result;
-use log::info;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use log::{info, warn};
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::gmail::GmailClient;
-use crate::models::ThreadId;
-use crate::storage::MailStore;
+use super::attachment::{Attachment, download_attachment};
+use super::compose::OutgoingMessage;
+use super::invite::rsvp_email;
+use super::outbox::{self, OutboxFlush};
+use super::unsubscribe::{UnsubscribeOutcome, one_click_unsubscribe, unsubscribe_email};
+use crate::error::MailError;
+use crate::gmail::api::MessageRef;
+use crate::gmail::{GmailClient, is_transient_error};
+use crate::models::{
+    EmailAddress, MessageId, OutboxMessage, OutboxState, QueuedAction, RsvpResponse, ThreadId,
+};
+use crate::search::SearchIndex;
+use crate::storage::{MailStore, ThreadCursor};
+use crate::tracking::{self, TrackingConfig};
 
 /// Label IDs used by Gmail for common states
 pub mod labels {
@@ -20,6 +33,51 @@ pub mod labels {
     pub const SPAM: &str = "SPAM";
 }
 
+/// Most message IDs Gmail accepts in one batchModify request
+const BATCH_MODIFY_LIMIT: usize = 1000;
+
+/// Threads modified per batch by label-wide actions
+const LABEL_BATCH_SIZE: usize = 250;
+
+/// Error returned when Gmail couldn't be reached and a change was queued
+///
+/// The change has already been applied to local storage and the search
+/// index; it is sent to Gmail by the next successful action or
+/// [`ActionHandler::flush_queued_actions`].
+#[derive(Debug, thiserror::Error)]
+#[error("Gmail is unreachable; the change was saved and will be sent when back online")]
+pub struct ActionQueuedError;
+
+/// Outcome of replaying queued actions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueFlush {
+    /// Actions Gmail accepted
+    pub sent: usize,
+    /// Actions Gmail permanently rejected, which were dropped
+    pub rejected: usize,
+    /// Actions still queued because Gmail remains unreachable
+    pub remaining: usize,
+}
+
+/// Add and remove labels in a message's label list
+fn apply_label_edit(label_ids: &mut Vec<String>, add_labels: &[&str], remove_labels: &[&str]) {
+    label_ids.retain(|l| !remove_labels.contains(&l.as_str()));
+    for label in add_labels {
+        if !label_ids.iter().any(|l| l == label) {
+            label_ids.push(label.to_string());
+        }
+    }
+}
+
+/// A label together with another the threads must also carry
+fn with_label<'a>(label_id: &'a str, also: &'a str) -> Vec<&'a str> {
+    if label_id == also {
+        vec![label_id]
+    } else {
+        vec![label_id, also]
+    }
+}
+
 /// Handler for email actions like archive, star, read/unread
 ///
 /// Actions are performed in two steps:
@@ -27,16 +85,216 @@ pub mod labels {
 /// 2. Update local storage to reflect the change
 ///
 /// This ensures the server is the source of truth, and local state
-/// is kept in sync.
+/// is kept in sync. When a search index is attached, label changes are
+/// mirrored into it as well.
+///
+/// With the offline queue on, label changes that fail because Gmail can't
+/// be reached are applied locally anyway and queued for later, and the
+/// action returns [`ActionQueuedError`].
 pub struct ActionHandler {
     gmail: Arc<GmailClient>,
     store: Arc<dyn MailStore>,
+    search_index: Option<Arc<SearchIndex>>,
+    tracking: TrackingConfig,
+    offline_queue: bool,
 }
 
 impl ActionHandler {
     /// Create a new action handler
     pub fn new(gmail: Arc<GmailClient>, store: Arc<dyn MailStore>) -> Self {
-        Self { gmail, store }
+        Self {
+            gmail,
+            store,
+            search_index: None,
+            tracking: TrackingConfig::default(),
+            offline_queue: false,
+        }
+    }
+
+    /// Keep the given search index in sync with label changes
+    pub fn with_search_index(mut self, search_index: Arc<SearchIndex>) -> Self {
+        self.search_index = Some(search_index);
+        self
+    }
+
+    /// Allow open tracking for messages that ask for it
+    ///
+    /// Without this, or with a disabled config, messages are always sent
+    /// without a beacon.
+    pub fn with_tracking(mut self, tracking: TrackingConfig) -> Self {
+        self.tracking = tracking;
+        self
+    }
+
+    /// Queue label changes while Gmail is unreachable instead of failing
+    pub fn with_offline_queue(mut self) -> Self {
+        self.offline_queue = true;
+        self
+    }
+
+    /// Send a label change for some threads to Gmail
+    ///
+    /// Returns true if the change was queued rather than sent. Queued
+    /// changes from earlier are sent first so they can't override this one;
+    /// if they still can't be sent, this change joins the queue behind them.
+    fn send_label_edit(
+        &self,
+        threads: &[(&ThreadId, &[MessageId])],
+        add_labels: &[&str],
+        remove_labels: &[&str],
+    ) -> Result<bool> {
+        // Threads to queue the change for, with their accounts
+        let mut queueable = Vec::new();
+        if self.offline_queue {
+            for (thread_id, _) in threads {
+                if let Some(thread) = self.store.get_thread(thread_id)? {
+                    queueable.push((thread.account_id, (*thread_id).clone()));
+                }
+            }
+        }
+        let mut accounts: Vec<i64> = queueable.iter().map(|(account_id, _)| *account_id).collect();
+        accounts.sort_unstable();
+        accounts.dedup();
+        let mut behind_queue = false;
+        for account_id in accounts {
+            behind_queue |= self.flush_queued_actions(account_id)?.remaining > 0;
+        }
+
+        if !behind_queue {
+            let id_strs: Vec<&str> = threads
+                .iter()
+                .flat_map(|(_, msg_ids)| msg_ids.iter().map(|id| id.as_str()))
+                .collect();
+            match self.batch_modify_chunked(&id_strs, add_labels, remove_labels) {
+                Ok(()) => return Ok(false),
+                Err(e) if queueable.is_empty() || !is_transient_error(&e) => return Err(e),
+                Err(e) => warn!("Gmail unreachable, queueing label change: {}", e),
+            }
+        }
+
+        for (account_id, thread_id) in queueable {
+            self.store.queue_action(QueuedAction::new(
+                account_id,
+                thread_id,
+                add_labels,
+                remove_labels,
+            ))?;
+        }
+        Ok(true)
+    }
+
+    /// Modify messages in as few batchModify requests as possible
+    fn batch_modify_chunked(
+        &self,
+        id_strs: &[&str],
+        add_labels: &[&str],
+        remove_labels: &[&str],
+    ) -> Result<()> {
+        for chunk in id_strs.chunks(BATCH_MODIFY_LIMIT) {
+            self.gmail.batch_modify_messages(chunk, add_labels, remove_labels)?;
+        }
+        Ok(())
+    }
+
+    /// Change labels on every message of a thread, on Gmail then locally
+    fn modify_thread(
+        &self,
+        thread_id: &ThreadId,
+        msg_ids: &[MessageId],
+        add_labels: &[&str],
+        remove_labels: &[&str],
+    ) -> Result<()> {
+        let queued = self.send_label_edit(&[(thread_id, msg_ids)], add_labels, remove_labels)?;
+        self.update_local_labels(thread_id, msg_ids, |new_labels| {
+            apply_label_edit(new_labels, add_labels, remove_labels);
+        })?;
+        self.commit_search_index();
+        if queued {
+            return Err(ActionQueuedError.into());
+        }
+        Ok(())
+    }
+
+    /// Send an account's queued label changes to Gmail, oldest first
+    ///
+    /// Stops at the first change Gmail can't be reached for, leaving it and
+    /// everything after it queued. Changes Gmail rejects outright are
+    /// dropped; their local labels stay as they were set offline.
+    pub fn flush_queued_actions(&self, account_id: i64) -> Result<QueueFlush> {
+        let actions = self.store.list_queued_actions(account_id)?;
+        let mut flush = QueueFlush::default();
+
+        for (i, action) in actions.iter().enumerate() {
+            let msg_ids = self.store.get_message_ids_for_thread(&action.thread_id)?;
+            let id_strs: Vec<&str> = msg_ids.iter().map(|id| id.as_str()).collect();
+            let add: Vec<&str> = action.add_labels.iter().map(String::as_str).collect();
+            let remove: Vec<&str> = action.remove_labels.iter().map(String::as_str).collect();
+
+            match self.batch_modify_chunked(&id_strs, &add, &remove) {
+                Ok(()) => flush.sent += 1,
+                Err(e) if is_transient_error(&e) => {
+                    flush.remaining = actions.len() - i;
+                    break;
+                }
+                Err(e) => {
+                    warn!(
+                        "Gmail rejected queued change to thread {}: {}",
+                        action.thread_id.as_str(),
+                        e
+                    );
+                    flush.rejected += 1;
+                }
+            }
+            self.store.delete_queued_action(action.id)?;
+        }
+
+        if flush.sent > 0 || flush.rejected > 0 {
+            info!(
+                "Flushed queued actions for account {}: {} sent, {} rejected, {} remaining",
+                account_id, flush.sent, flush.rejected, flush.remaining
+            );
+        }
+        Ok(flush)
+    }
+
+    /// Apply a label edit to every message in local storage and the search index
+    ///
+    /// The index isn't committed; call [`Self::commit_search_index`] once
+    /// the edits are done.
+    fn update_local_labels(
+        &self,
+        thread_id: &ThreadId,
+        msg_ids: &[MessageId],
+        edit: impl Fn(&mut Vec<String>),
+    ) -> Result<()> {
+        let mut updated = HashMap::new();
+        for msg_id in msg_ids {
+            if let Some(msg) = self.store.get_message(msg_id)? {
+                let mut new_labels = msg.label_ids.clone();
+                edit(&mut new_labels);
+                self.store.update_message_labels(msg_id, new_labels.clone())?;
+                updated.insert(msg_id.clone(), new_labels);
+            }
+        }
+
+        // The server and store are already updated, so an index failure
+        // only leaves search stale until the next rebuild
+        if let Some(ref index) = self.search_index
+            && let Err(e) = index.update_labels(thread_id, &updated)
+        {
+            warn!("Failed to update search index for thread {}: {}", thread_id.as_str(), e);
+        }
+
+        Ok(())
+    }
+
+    /// Make label changes in the search index visible to searches
+    fn commit_search_index(&self) {
+        if let Some(ref index) = self.search_index
+            && let Err(e) = index.commit()
+        {
+            warn!("Failed to commit search index: {}", e);
+        }
     }
 
     /// Archive a thread (remove from INBOX)
@@ -51,18 +309,7 @@ impl ActionHandler {
 
         info!("Archiving thread {} ({} messages)", thread_id.as_str(), msg_ids.len());
 
-        // Use batch modify for efficiency
-        let id_strs: Vec<&str> = msg_ids.iter().map(|id| id.as_str()).collect();
-        self.gmail.batch_modify_messages(&id_strs, &[], &[labels::INBOX])?;
-
-        // Update local storage
-        for msg_id in &msg_ids {
-            if let Some(msg) = self.store.get_message(msg_id)? {
-                let mut new_labels = msg.label_ids.clone();
-                new_labels.retain(|l| l != labels::INBOX);
-                self.store.update_message_labels(msg_id, new_labels)?;
-            }
-        }
+        self.modify_thread(thread_id, &msg_ids, &[], &[labels::INBOX])?;
 
         info!("Archived thread {}", thread_id.as_str());
         Ok(())
@@ -77,19 +324,7 @@ impl ActionHandler {
 
         info!("Unarchiving thread {} ({} messages)", thread_id.as_str(), msg_ids.len());
 
-        let id_strs: Vec<&str> = msg_ids.iter().map(|id| id.as_str()).collect();
-        self.gmail.batch_modify_messages(&id_strs, &[labels::INBOX], &[])?;
-
-        // Update local storage
-        for msg_id in &msg_ids {
-            if let Some(msg) = self.store.get_message(msg_id)? {
-                let mut new_labels = msg.label_ids.clone();
-                if !new_labels.contains(&labels::INBOX.to_string()) {
-                    new_labels.push(labels::INBOX.to_string());
-                }
-                self.store.update_message_labels(msg_id, new_labels)?;
-            }
-        }
+        self.modify_thread(thread_id, &msg_ids, &[labels::INBOX], &[])?;
 
         info!("Unarchived thread {}", thread_id.as_str());
         Ok(())
@@ -122,26 +357,10 @@ impl ActionHandler {
             if new_starred { "starred" } else { "unstarred" }
         );
 
-        let id_strs: Vec<&str> = msg_ids.iter().map(|id| id.as_str()).collect();
         if new_starred {
-            self.gmail.batch_modify_messages(&id_strs, &[labels::STARRED], &[])?;
+            self.modify_thread(thread_id, &msg_ids, &[labels::STARRED], &[])?;
         } else {
-            self.gmail.batch_modify_messages(&id_strs, &[], &[labels::STARRED])?;
-        }
-
-        // Update local storage
-        for msg_id in &msg_ids {
-            if let Some(msg) = self.store.get_message(msg_id)? {
-                let mut new_labels = msg.label_ids.clone();
-                if new_starred {
-                    if !new_labels.contains(&labels::STARRED.to_string()) {
-                        new_labels.push(labels::STARRED.to_string());
-                    }
-                } else {
-                    new_labels.retain(|l| l != labels::STARRED);
-                }
-                self.store.update_message_labels(msg_id, new_labels)?;
-            }
+            self.modify_thread(thread_id, &msg_ids, &[], &[labels::STARRED])?;
         }
 
         Ok(new_starred)
@@ -162,26 +381,12 @@ impl ActionHandler {
             if is_read { "read" } else { "unread" }
         );
 
-        let id_strs: Vec<&str> = msg_ids.iter().map(|id| id.as_str()).collect();
         if is_read {
             // Remove UNREAD label to mark as read
-            self.gmail.batch_modify_messages(&id_strs, &[], &[labels::UNREAD])?;
+            self.modify_thread(thread_id, &msg_ids, &[], &[labels::UNREAD])?;
         } else {
             // Add UNREAD label to mark as unread
-            self.gmail.batch_modify_messages(&id_strs, &[labels::UNREAD], &[])?;
-        }
-
-        // Update local storage
-        for msg_id in &msg_ids {
-            if let Some(msg) = self.store.get_message(msg_id)? {
-                let mut new_labels = msg.label_ids.clone();
-                if is_read {
-                    new_labels.retain(|l| l != labels::UNREAD);
-                } else if !new_labels.contains(&labels::UNREAD.to_string()) {
-                    new_labels.push(labels::UNREAD.to_string());
-                }
-                self.store.update_message_labels(msg_id, new_labels)?;
-            }
+            self.modify_thread(thread_id, &msg_ids, &[labels::UNREAD], &[])?;
         }
 
         Ok(())
@@ -222,24 +427,495 @@ impl ActionHandler {
 
         info!("Trashing thread {} ({} messages)", thread_id.as_str(), msg_ids.len());
 
-        let id_strs: Vec<&str> = msg_ids.iter().map(|id| id.as_str()).collect();
         // Add TRASH and remove INBOX
-        self.gmail.batch_modify_messages(&id_strs, &[labels::TRASH], &[labels::INBOX])?;
+        self.modify_thread(thread_id, &msg_ids, &[labels::TRASH], &[labels::INBOX])?;
 
-        // Update local storage
-        for msg_id in &msg_ids {
-            if let Some(msg) = self.store.get_message(msg_id)? {
-                let mut new_labels = msg.label_ids.clone();
-                new_labels.retain(|l| l != labels::INBOX);
-                if !new_labels.contains(&labels::TRASH.to_string()) {
-                    new_labels.push(labels::TRASH.to_string());
+        info!("Trashed thread {}", thread_id.as_str());
+        Ok(())
+    }
+
+    /// Add and remove labels on every message of many threads at once
+    ///
+    /// Messages are sent to Gmail in as few batchModify requests as
+    /// possible, then local storage and the search index are updated per
+    /// thread, with one index commit at the end. Returns the number of
+    /// messages modified.
+    pub fn batch_modify(
+        &self,
+        thread_ids: &[ThreadId],
+        add_labels: &[&str],
+        remove_labels: &[&str],
+    ) -> Result<usize> {
+        let mut threads = Vec::with_capacity(thread_ids.len());
+        for thread_id in thread_ids {
+            let msg_ids = self.store.get_message_ids_for_thread(thread_id)?;
+            if !msg_ids.is_empty() {
+                threads.push((thread_id, msg_ids));
+            }
+        }
+
+        let message_count: usize = threads.iter().map(|(_, msg_ids)| msg_ids.len()).sum();
+        if message_count == 0 {
+            return Ok(0);
+        }
+
+        info!(
+            "Batch modifying {} threads ({} messages): +{:?} -{:?}",
+            threads.len(),
+            message_count,
+            add_labels,
+            remove_labels
+        );
+        let edits: Vec<(&ThreadId, &[MessageId])> = threads
+            .iter()
+            .map(|(thread_id, msg_ids)| (*thread_id, msg_ids.as_slice()))
+            .collect();
+        let queued = self.send_label_edit(&edits, add_labels, remove_labels)?;
+
+        for (thread_id, msg_ids) in &threads {
+            self.update_local_labels(thread_id, msg_ids, |new_labels| {
+                apply_label_edit(new_labels, add_labels, remove_labels);
+            })?;
+        }
+        self.commit_search_index();
+
+        if queued {
+            return Err(ActionQueuedError.into());
+        }
+        Ok(message_count)
+    }
+
+    /// Mark every unread thread with a label as read
+    ///
+    /// Covers one account. Threads are modified in batches, so a label with
+    /// thousands of unread threads doesn't become one giant request;
+    /// `on_progress` is called with (threads_done, threads_total) after each
+    /// batch. Returns the number of messages modified.
+    pub fn mark_label_read<F>(
+        &self,
+        account_id: i64,
+        label_id: &str,
+        on_progress: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(usize, usize),
+    {
+        let selection = with_label(label_id, labels::UNREAD);
+        let total = self
+            .store
+            .count_unread_threads_by_label_for_account(label_id, Some(account_id))?;
+        info!("Marking {} threads in {} read", total, label_id);
+        let remove = [labels::UNREAD];
+        self.modify_label_threads(account_id, &selection, total, &[], &remove, on_progress)
+    }
+
+    /// Archive every inbox thread with a label
+    ///
+    /// Batched and reported like [`Self::mark_label_read`]. Returns the
+    /// number of messages modified.
+    pub fn archive_label<F>(
+        &self,
+        account_id: i64,
+        label_id: &str,
+        on_progress: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(usize, usize),
+    {
+        let selection = with_label(label_id, labels::INBOX);
+        let total = self.store.count_threads_with_labels(&selection, &[], Some(account_id))?;
+        info!("Archiving {} threads in {}", total, label_id);
+        let remove = [labels::INBOX];
+        self.modify_label_threads(account_id, &selection, total, &[], &remove, on_progress)
+    }
+
+    /// Batch-modify an account's threads carrying all of `selection`
+    ///
+    /// Modified threads usually leave the selection, but paging by cursor
+    /// keeps threads that don't (such as ones without stored messages) from
+    /// being listed again. A change queued offline doesn't stop the run; the
+    /// queue is reported once every batch is done.
+    fn modify_label_threads<F>(
+        &self,
+        account_id: i64,
+        selection: &[&str],
+        total: usize,
+        add_labels: &[&str],
+        remove_labels: &[&str],
+        mut on_progress: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(usize, usize),
+    {
+        let mut threads_done = 0;
+        let mut modified = 0;
+        let mut queued = false;
+        let mut cursor: Option<ThreadCursor> = None;
+
+        loop {
+            let batch = self.store.list_threads_with_labels_after(
+                selection,
+                &[],
+                Some(account_id),
+                cursor.as_ref(),
+                LABEL_BATCH_SIZE,
+            )?;
+            let exhausted = batch.len() < LABEL_BATCH_SIZE;
+
+            let thread_ids: Vec<ThreadId> = batch.iter().map(|t| t.id.clone()).collect();
+            match self.batch_modify(&thread_ids, add_labels, remove_labels) {
+                Ok(count) => modified += count,
+                Err(e) if e.downcast_ref::<ActionQueuedError>().is_some() => queued = true,
+                Err(e) => return Err(e),
+            }
+            threads_done += batch.len();
+            on_progress(threads_done, total.max(threads_done));
+
+            cursor = batch.last().map(ThreadCursor::for_thread);
+            if exhausted || cursor.is_none() {
+                break;
+            }
+        }
+
+        if queued {
+            return Err(ActionQueuedError.into());
+        }
+        Ok(modified)
+    }
+
+    /// Send a composed message or reply through Gmail
+    ///
+    /// Fails if the message is from an alias that isn't verified. The sent
+    /// copy reaches local storage on the next sync.
+    ///
+    /// Messages that ask for open tracking get a beacon if tracking is
+    /// configured; the token is saved against the sent message ID.
+    pub fn send(&self, message: &OutgoingMessage) -> Result<MessageRef> {
+        let (raw, token) = self.prepare_send(message)?;
+
+        let sent = self.gmail.send_message_in_thread(&raw, message.thread_id())?;
+        info!("Sent message {} in thread {}", sent.id, sent.thread_id);
+
+        self.save_sent_token(token, &sent);
+        Ok(sent)
+    }
+
+    /// Save a message as a Gmail draft
+    ///
+    /// Creates a new draft, or replaces `draft_id` if given. Returns the
+    /// draft ID to pass on the next save. Drafts are saved without a
+    /// tracking beacon; one is added only when the draft is sent.
+    pub fn save_draft(&self, message: &OutgoingMessage, draft_id: Option<&str>) -> Result<String> {
+        message.check_attachments()?;
+        let raw = message.to_rfc2822();
+        let draft = match draft_id {
+            Some(id) => self.gmail.update_draft(id, &raw, message.thread_id())?,
+            None => self.gmail.create_draft(&raw, message.thread_id())?,
+        };
+        Ok(draft.id)
+    }
+
+    /// Send a saved draft with the final content of `message`
+    ///
+    /// The draft is updated first so what is sent matches `message` exactly,
+    /// including any tracking beacon.
+    pub fn send_draft(&self, draft_id: &str, message: &OutgoingMessage) -> Result<MessageRef> {
+        let (raw, token) = self.prepare_send(message)?;
+
+        self.gmail.update_draft(draft_id, &raw, message.thread_id())?;
+        let sent = self.gmail.send_draft(draft_id)?;
+        info!("Sent message {} in thread {}", sent.id, sent.thread_id);
+
+        self.save_sent_token(token, &sent);
+        Ok(sent)
+    }
+
+    /// Discard a saved draft
+    pub fn delete_draft(&self, draft_id: &str) -> Result<()> {
+        self.gmail.delete_draft(draft_id)
+    }
+
+    /// Put a message in the outbox and try to send it straight away
+    ///
+    /// Returns the entry as `Sent`, or `Queued` to retry if Gmail couldn't
+    /// be reached. A message that can't be sent at all (an unverified From
+    /// address, oversized attachments, rejected by Gmail) isn't kept and
+    /// fails here, so the compose window can show why. `draft_id` is the
+    /// Gmail draft the message was composed in, deleted once it's sent.
+    pub fn queue_send(
+        &self,
+        message: &OutgoingMessage,
+        draft_id: Option<&str>,
+    ) -> Result<OutboxMessage> {
+        let (raw, token) = self.prepare_send(message)?;
+
+        let mut entry = message.to_outbox(raw);
+        entry.draft_id = draft_id.map(String::from);
+        entry.tracking_token = token;
+        let entry = self.store.add_outbox_message(entry)?;
+        info!("Queued message {} for sending", entry.id);
+
+        let entry = self.send_outbox_message(entry.id)?;
+        if entry.state == OutboxState::Failed {
+            self.store.delete_outbox_message(entry.id)?;
+            anyhow::bail!(entry.last_error.unwrap_or_else(|| "Gmail rejected it".to_string()));
+        }
+        Ok(entry)
+    }
+
+    /// Make one attempt at sending an outbox message
+    ///
+    /// Messages already sending or sent are returned unchanged. A failed
+    /// attempt is retried later with backoff if Gmail couldn't be reached,
+    /// and marked `Failed` otherwise.
+    pub fn send_outbox_message(&self, id: i64) -> Result<OutboxMessage> {
+        let mut entry = self
+            .store
+            .get_outbox_message(id)?
+            .ok_or_else(|| MailError::NotFound(format!("Outbox message {}", id)))?;
+        if matches!(entry.state, OutboxState::Sending | OutboxState::Sent) {
+            return Ok(entry);
+        }
+
+        entry.state = OutboxState::Sending;
+        entry.attempts += 1;
+        entry.next_attempt_at = Utc::now();
+        self.store.update_outbox_message(&entry)?;
+
+        match self.gmail.send_message_in_thread(&entry.raw, entry.thread_id.as_ref()) {
+            Ok(sent) => {
+                info!("Sent message {} in thread {}", sent.id, sent.thread_id);
+                entry.state = OutboxState::Sent;
+                entry.last_error = None;
+                entry.sent_message_id = Some(sent.id.clone());
+                self.store.update_outbox_message(&entry)?;
+
+                self.save_sent_token(entry.tracking_token.clone(), &sent);
+                if let Some(draft_id) = &entry.draft_id
+                    && let Err(e) = self.gmail.delete_draft(draft_id)
+                {
+                    warn!("Failed to delete draft {} after sending: {}", draft_id, e);
                 }
-                self.store.update_message_labels(msg_id, new_labels)?;
+            }
+            Err(e) => {
+                let transient = is_transient_error(&e);
+                warn!("Failed to send outbox message {} (attempt {}): {}", id, entry.attempts, e);
+                outbox::record_failure(&mut entry, e.to_string(), transient, Utc::now());
+                self.store.update_outbox_message(&entry)?;
             }
         }
+        Ok(entry)
+    }
 
-        info!("Trashed thread {}", thread_id.as_str());
-        Ok(())
+    /// Retry an account's due outbox messages and tidy up the rest
+    ///
+    /// Sent messages leave the outbox once their sent copy has synced, and
+    /// sends that were interrupted (the app quit mid-send) are marked failed,
+    /// since Gmail may or may not have them.
+    pub fn process_outbox(&self, account_id: i64) -> Result<OutboxFlush> {
+        let now = Utc::now();
+        let mut flush = OutboxFlush::default();
+
+        for mut entry in self.store.list_outbox_messages(Some(account_id))? {
+            if entry.state == OutboxState::Sent {
+                let synced = match &entry.sent_message_id {
+                    Some(id) => self.store.has_message(&MessageId::new(id))?,
+                    None => true,
+                };
+                if synced {
+                    self.store.delete_outbox_message(entry.id)?;
+                }
+                continue;
+            }
+            if outbox::is_interrupted(&entry, now) {
+                entry.state = OutboxState::Failed;
+                entry.last_error =
+                    Some("Interrupted while sending; check Sent before resending".to_string());
+                self.store.update_outbox_message(&entry)?;
+            }
+            if entry.is_due(now) {
+                entry = self.send_outbox_message(entry.id)?;
+                if entry.state == OutboxState::Sent {
+                    flush.sent += 1;
+                }
+            }
+            match entry.state {
+                OutboxState::Failed => flush.failed += 1,
+                OutboxState::Queued | OutboxState::Sending => flush.remaining += 1,
+                OutboxState::Sent => {}
+            }
+        }
+
+        if flush.sent > 0 {
+            info!(
+                "Processed outbox for account {}: {} sent, {} failed, {} remaining",
+                account_id, flush.sent, flush.failed, flush.remaining
+            );
+        }
+        Ok(flush)
+    }
+
+    /// Send a failed outbox message again, with a fresh set of attempts
+    pub fn resend_outbox_message(&self, id: i64) -> Result<OutboxMessage> {
+        let mut entry = self
+            .store
+            .get_outbox_message(id)?
+            .ok_or_else(|| MailError::NotFound(format!("Outbox message {}", id)))?;
+        if entry.state == OutboxState::Failed {
+            entry.state = OutboxState::Queued;
+            entry.attempts = 0;
+            entry.last_error = None;
+            self.store.update_outbox_message(&entry)?;
+        }
+        self.send_outbox_message(id)
+    }
+
+    /// Remove an unsent message from the outbox without sending it
+    ///
+    /// The draft it was composed in, if any, stays in Gmail's drafts. A
+    /// message being sent right now can't be discarded.
+    pub fn discard_outbox_message(&self, id: i64) -> Result<()> {
+        if let Some(entry) = self.store.get_outbox_message(id)?
+            && entry.state == OutboxState::Sending
+        {
+            anyhow::bail!("Message {} is being sent", id);
+        }
+        self.store.delete_outbox_message(id)
+    }
+
+    /// Check a message can be sent and build its raw form
+    ///
+    /// Returns the raw message and, if a tracking beacon was embedded, the
+    /// beacon's token.
+    fn prepare_send(&self, message: &OutgoingMessage) -> Result<(String, Option<String>)> {
+        let aliases = self.store.list_send_as_aliases(message.account_id())?;
+        message.check_sender(&aliases)?;
+        message.check_attachments()?;
+
+        let token = tracking::new_token();
+        let beacon_url = message
+            .tracks_opens()
+            .then(|| self.tracking.beacon_url(&token))
+            .flatten();
+        let raw = match &beacon_url {
+            Some(url) => message.clone().with_beacon(url).to_rfc2822(),
+            None => message.to_rfc2822(),
+        };
+        Ok((raw, beacon_url.map(|_| token)))
+    }
+
+    /// Record the tracking token for a sent message, if it has one
+    fn save_sent_token(&self, token: Option<String>, sent: &MessageRef) {
+        let Some(token) = token else {
+            return;
+        };
+        let sent_id = MessageId::new(&sent.id);
+        if let Err(e) = self.store.save_tracking_token(&token, &sent_id) {
+            warn!("Failed to save tracking token for {}: {}", sent.id, e);
+        }
+    }
+
+    /// Download a received attachment by its file name
+    pub fn download_attachment(
+        &self,
+        message_id: &MessageId,
+        filename: &str,
+    ) -> Result<Attachment> {
+        download_attachment(&self.gmail, message_id, filename)
+    }
+
+    /// Answer the calendar invite carried by a message
+    ///
+    /// The reply goes to the organizer from whichever of the account's
+    /// addresses (its own or a verified alias) was invited, and the local
+    /// copy of the invite is updated with the new status.
+    pub fn rsvp(&self, message_id: &MessageId, response: RsvpResponse) -> Result<MessageRef> {
+        let mut message = self
+            .store
+            .get_message(message_id)?
+            .ok_or_else(|| MailError::NotFound(format!("Message {}", message_id.as_str())))?;
+        let invite = message
+            .invite
+            .as_mut()
+            .with_context(|| format!("Message {} has no invite", message_id.as_str()))?;
+        if !invite.can_respond() {
+            anyhow::bail!("Invite {} does not accept replies", invite.uid);
+        }
+
+        let account = self
+            .store
+            .get_account(message.account_id)?
+            .ok_or_else(|| MailError::NotFound(format!("Account {}", message.account_id)))?;
+        let aliases = self.store.list_send_as_aliases(account.id)?;
+        let attendee = aliases
+            .iter()
+            .filter(|alias| alias.verified)
+            .map(|alias| alias.address())
+            .chain(std::iter::once(match &account.display_name {
+                Some(name) => EmailAddress::with_name(name, &account.email),
+                None => EmailAddress::new(&account.email),
+            }))
+            .find(|address| invite.attendee(&address.email).is_some())
+            .unwrap_or_else(|| EmailAddress::new(&account.email));
+
+        let raw = rsvp_email(&attendee, invite, response, Utc::now())?;
+        let sent = self
+            .gmail
+            .send_message_in_thread(&raw, Some(&message.thread_id))?;
+
+        invite.set_status(&attendee, response.status());
+        let uid = invite.uid.clone();
+        self.store.upsert_message(message)?;
+        info!("Sent {:?} for invite {} as {}", response, uid, attendee.email);
+        Ok(sent)
+    }
+
+    /// Unsubscribe from the mailing list a thread came from
+    ///
+    /// Uses the List-Unsubscribe targets of the newest message that has
+    /// them. A one-click HTTPS endpoint is tried first, then an unsubscribe
+    /// email sent from the thread's account. When the sender only offers a
+    /// web page, its URL is returned for the caller to open.
+    pub fn unsubscribe(&self, thread_id: &ThreadId) -> Result<UnsubscribeOutcome> {
+        let messages = self.store.list_messages_for_thread(thread_id)?;
+        let Some((message, unsubscribe)) = messages
+            .iter()
+            .rev()
+            .find_map(|m| m.unsubscribe.as_ref().map(|u| (m, u)))
+        else {
+            anyhow::bail!("Thread {} has no unsubscribe option", thread_id.as_str());
+        };
+
+        if unsubscribe.one_click
+            && let Some(url) = &unsubscribe.url
+        {
+            match one_click_unsubscribe(url) {
+                Ok(()) => {
+                    info!("Unsubscribed from thread {} (one-click)", thread_id.as_str());
+                    return Ok(UnsubscribeOutcome::OneClick);
+                }
+                Err(e) if unsubscribe.mailto.is_some() => {
+                    warn!("{}, falling back to email", e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        if let Some(mailto) = &unsubscribe.mailto {
+            let account = self
+                .store
+                .get_account(message.account_id)?
+                .ok_or_else(|| MailError::NotFound(format!("Account {}", message.account_id)))?;
+            let email = unsubscribe_email(&account.email, mailto)?;
+            self.gmail.send_message(&email.raw)?;
+            info!("Sent unsubscribe email for thread {} to {}", thread_id.as_str(), email.to);
+            return Ok(UnsubscribeOutcome::EmailSent(email.to));
+        }
+
+        match &unsubscribe.url {
+            Some(url) => Ok(UnsubscribeOutcome::OpenUrl(url.clone())),
+            None => anyhow::bail!("Thread {} has no unsubscribe option", thread_id.as_str()),
+        }
     }
 
     /// Check if a thread is in the inbox
@@ -329,6 +1005,22 @@ mod tests {
         assert!(msg.label_ids.contains(&"STARRED".to_string()));
     }
 
+    #[test]
+    fn test_apply_label_edit() {
+        let mut label_ids = vec!["INBOX".to_string(), "UNREAD".to_string()];
+        apply_label_edit(&mut label_ids, &["TRASH", "UNREAD"], &["INBOX"]);
+        assert_eq!(label_ids, vec!["UNREAD", "TRASH"]);
+
+        apply_label_edit(&mut label_ids, &[], &["UNREAD", "MISSING"]);
+        assert_eq!(label_ids, vec!["TRASH"]);
+    }
+
+    #[test]
+    fn test_with_label() {
+        assert_eq!(with_label("CATEGORY_PROMOTIONS", "INBOX"), vec!["CATEGORY_PROMOTIONS", "INBOX"]);
+        assert_eq!(with_label("INBOX", "INBOX"), vec!["INBOX"]);
+    }
+
     #[test]
     fn test_is_unread() {
         let store = Arc::new(InMemoryMailStore::new());
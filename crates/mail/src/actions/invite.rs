@@ -0,0 +1,161 @@
+//! Calendar invite replies (iTIP REPLY, RFC 5546)
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+
+use super::compose::{boundary_for, format_address};
+use super::unsubscribe::{encode_header, single_line};
+use crate::models::{EmailAddress, EventInvite, RsvpResponse};
+
+/// Build the email that answers an invite on behalf of `attendee`
+///
+/// The message carries a short text part for people reading it and a
+/// `text/calendar; method=REPLY` part that the organizer's calendar applies.
+pub(super) fn rsvp_email(
+    attendee: &EmailAddress,
+    invite: &EventInvite,
+    response: RsvpResponse,
+    now: DateTime<Utc>,
+) -> Result<String> {
+    let organizer = invite
+        .organizer
+        .as_ref()
+        .context("Invite has no organizer")?;
+    let name = attendee.name.as_deref().unwrap_or(&attendee.email);
+    let summary = if invite.summary.is_empty() {
+        "(no title)"
+    } else {
+        invite.summary.as_str()
+    };
+
+    let text = format!(
+        "{} has {} this invitation.",
+        name,
+        response.label().to_lowercase()
+    );
+    let calendar = reply_calendar(attendee, organizer, invite, response, now);
+    let boundary = boundary_for(&text, &calendar);
+
+    Ok(format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nMIME-Version: 1.0\r\n\
+         Content-Type: multipart/alternative; boundary=\"{b}\"\r\n\r\n\
+         --{b}\r\nContent-Type: text/plain; charset=UTF-8\r\n\r\n{}\r\n\
+         --{b}\r\nContent-Type: text/calendar; charset=UTF-8; method=REPLY\r\n\r\n{}\
+         --{b}--\r\n",
+        format_address(attendee),
+        format_address(organizer),
+        encode_header(&single_line(&format!("{}: {}", response.label(), summary))),
+        text,
+        calendar,
+        b = boundary
+    ))
+}
+
+/// The VCALENDAR body of a reply
+fn reply_calendar(
+    attendee: &EmailAddress,
+    organizer: &EmailAddress,
+    invite: &EventInvite,
+    response: RsvpResponse,
+    now: DateTime<Utc>,
+) -> String {
+    let mut attendee_line = format!("ATTENDEE;PARTSTAT={}", response.status().as_ical());
+    if let Some(name) = &attendee.name {
+        attendee_line.push_str(&format!(";CN={}", quote_param(name)));
+    }
+    attendee_line.push_str(&format!(":mailto:{}", single_line(&attendee.email)));
+
+    let lines = [
+        "BEGIN:VCALENDAR".to_string(),
+        "PRODID:-//Cosmos//Orion Mail//EN".to_string(),
+        "VERSION:2.0".to_string(),
+        "METHOD:REPLY".to_string(),
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", single_line(&invite.uid)),
+        format!("SEQUENCE:{}", invite.sequence),
+        format!("DTSTAMP:{}", now.format("%Y%m%dT%H%M%SZ")),
+        format!("ORGANIZER:mailto:{}", single_line(&organizer.email)),
+        attendee_line,
+        format!("SUMMARY:{}", escape_text(&invite.summary)),
+        "END:VEVENT".to_string(),
+        "END:VCALENDAR".to_string(),
+    ];
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// Quote a parameter value; DQUOTE can't be escaped, so it is dropped
+fn quote_param(value: &str) -> String {
+    format!("\"{}\"", single_line(value).replace('"', ""))
+}
+
+/// Escape a TEXT value
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace(['\r', '\n'], "\\n")
+}
+
+/// Fold a content line at 75 octets and terminate it with CRLF
+fn fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn invite() -> EventInvite {
+        EventInvite::parse_ics(
+            "BEGIN:VCALENDAR\nMETHOD:REQUEST\nBEGIN:VEVENT\nUID:evt-1\nSEQUENCE:3\n\
+             SUMMARY:Budget\\, Q3\nORGANIZER;CN=Lead:mailto:lead@example.com\n\
+             ATTENDEE;PARTSTAT=NEEDS-ACTION:mailto:me@example.com\nEND:VEVENT\nEND:VCALENDAR\n",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rsvp_email() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 10, 9, 30, 0).unwrap();
+        let me = EmailAddress::with_name("Me", "me@example.com");
+        let raw = rsvp_email(&me, &invite(), RsvpResponse::Accept, now).unwrap();
+
+        assert!(raw.starts_with("From: \"Me\" <me@example.com>\r\n"));
+        assert!(raw.contains("\r\nTo: \"Lead\" <lead@example.com>\r\n"));
+        assert!(raw.contains("\r\nSubject: Accepted: Budget, Q3\r\n"));
+        assert!(raw.contains("Content-Type: text/calendar; charset=UTF-8; method=REPLY"));
+        assert!(raw.contains("\r\nMETHOD:REPLY\r\n"));
+        assert!(raw.contains("\r\nUID:evt-1\r\nSEQUENCE:3\r\nDTSTAMP:20260110T093000Z\r\n"));
+        assert!(raw.contains("\r\nATTENDEE;PARTSTAT=ACCEPTED;CN=\"Me\":mailto:me@example.com\r\n"));
+        assert!(raw.contains("\r\nSUMMARY:Budget\\, Q3\r\n"));
+        assert!(raw.ends_with("END:VCALENDAR\r\n--cosmos-alt-0--\r\n"));
+
+        let mut no_organizer = invite();
+        no_organizer.organizer = None;
+        assert!(rsvp_email(&me, &no_organizer, RsvpResponse::Decline, now).is_err());
+    }
+
+    #[test]
+    fn test_fold() {
+        let folded = fold(&"x".repeat(160));
+        let lines: Vec<&str> = folded.trim_end().split("\r\n").collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].len(), 75);
+        assert!(lines[1].starts_with(' ') && lines[1].len() == 75);
+        assert_eq!(lines.concat().replace(' ', "").len(), 160);
+    }
+}
@@ -0,0 +1,227 @@
+//! Minimal Markdown to HTML rendering for composed mail
+//!
+//! Covers what people actually type into an email: paragraphs, headings,
+//! bullet and numbered lists, block quotes, fenced code, and inline
+//! emphasis, code and links. Everything else passes through as escaped
+//! text, so the output never contains markup the author didn't write.
+
+/// Render `markdown` as an HTML fragment
+pub fn markdown_to_html(markdown: &str) -> String {
+    let mut html = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut list: Option<&'static str> = None;
+    let mut in_code = false;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+
+        if in_code {
+            if trimmed.starts_with("```") {
+                html.push_str("</code></pre>");
+                in_code = false;
+            } else {
+                html.push_str(&escape(line));
+                html.push('\n');
+            }
+            continue;
+        }
+
+        // Anything but a list item ends the current list
+        let item = list_item(trimmed);
+        if item.is_none_or(|(tag, _)| Some(tag) != list)
+            && let Some(tag) = list.take()
+        {
+            html.push_str(&format!("</{}>", tag));
+        }
+
+        if trimmed.is_empty() {
+            flush_paragraph(&mut html, &mut paragraph);
+        } else if trimmed.starts_with("```") {
+            flush_paragraph(&mut html, &mut paragraph);
+            html.push_str("<pre><code>");
+            in_code = true;
+        } else if let Some((level, text)) = heading(trimmed) {
+            flush_paragraph(&mut html, &mut paragraph);
+            html.push_str(&format!("<h{0}>{1}</h{0}>", level, render_inline(text)));
+        } else if let Some((tag, text)) = item {
+            flush_paragraph(&mut html, &mut paragraph);
+            if list.is_none() {
+                html.push_str(&format!("<{}>", tag));
+                list = Some(tag);
+            }
+            html.push_str(&format!("<li>{}</li>", render_inline(text)));
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            flush_paragraph(&mut html, &mut paragraph);
+            html.push_str(&format!(
+                "<blockquote>{}</blockquote>",
+                render_inline(quote.trim_start())
+            ));
+        } else {
+            paragraph.push(trimmed);
+        }
+    }
+
+    if in_code {
+        html.push_str("</code></pre>");
+    }
+    if let Some(tag) = list {
+        html.push_str(&format!("</{}>", tag));
+    }
+    flush_paragraph(&mut html, &mut paragraph);
+    html
+}
+
+/// Emit the pending paragraph lines, keeping the author's line breaks
+fn flush_paragraph(html: &mut String, lines: &mut Vec<&str>) {
+    if lines.is_empty() {
+        return;
+    }
+    let body: Vec<String> = lines.iter().map(|line| render_inline(line)).collect();
+    html.push_str(&format!("<p>{}</p>", body.join("<br>")));
+    lines.clear();
+}
+
+/// Parse an ATX heading like "## Agenda"
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let text = line[level..].strip_prefix(' ')?;
+    Some((level, text.trim()))
+}
+
+/// Parse a list item, returning its list tag and text
+fn list_item(line: &str) -> Option<(&'static str, &str)> {
+    if let Some(text) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+        return Some(("ul", text));
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits == 0 {
+        return None;
+    }
+    let text = line[digits..].strip_prefix(". ")?;
+    Some(("ol", text))
+}
+
+/// Render inline markup in a single line of text
+fn render_inline(text: &str) -> String {
+    inline(&escape(text))
+}
+
+/// Render inline markup in text that has already been escaped
+fn inline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '`'
+            && let Some(end) = rest[1..].find('`')
+        {
+            out.push_str(&format!("<code>{}</code>", &rest[1..1 + end]));
+            rest = &rest[end + 2..];
+            continue;
+        }
+        if let Some(inner) = rest.strip_prefix("**")
+            && let Some(end) = inner.find("**")
+            && end > 0
+        {
+            out.push_str(&format!("<strong>{}</strong>", inline(&inner[..end])));
+            rest = &inner[end + 2..];
+            continue;
+        }
+        if let Some(inner) = rest.strip_prefix('*')
+            && !inner.starts_with([' ', '*'])
+            && let Some(end) = inner.find('*')
+            && end > 0
+        {
+            out.push_str(&format!("<em>{}</em>", inline(&inner[..end])));
+            rest = &inner[end + 1..];
+            continue;
+        }
+        if c == '['
+            && let Some((label, url, len)) = link(rest)
+        {
+            out.push_str(&format!("<a href=\"{}\">{}</a>", url, inline(label)));
+            rest = &rest[len..];
+            continue;
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// Parse a `[label](url)` link at the start of `text`
+///
+/// Returns the label, URL and length of the whole link. Only web and
+/// mailto links are accepted.
+fn link(text: &str) -> Option<(&str, &str, usize)> {
+    let label_end = text.find("](")?;
+    let url_start = label_end + 2;
+    let url_len = text[url_start..].find(')')?;
+    let url = &text[url_start..url_start + url_len];
+    let allowed = ["https://", "http://", "mailto:"]
+        .iter()
+        .any(|scheme| url.starts_with(scheme));
+    if !allowed || label_end <= 1 || url.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((&text[1..label_end], url, url_start + url_len + 1))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks() {
+        let html = markdown_to_html(
+            "# Agenda\n\nFirst line\nsecond line\n\n- one\n- two\n\n1. a\n2. b\n> quoted",
+        );
+        assert_eq!(
+            html,
+            "<h1>Agenda</h1><p>First line<br>second line</p>\
+             <ul><li>one</li><li>two</li></ul><ol><li>a</li><li>b</li></ol>\
+             <blockquote>quoted</blockquote>"
+        );
+    }
+
+    #[test]
+    fn test_code_fence_is_verbatim() {
+        let html = markdown_to_html("```\nlet x = *a* < b;\n```\nafter");
+        assert_eq!(
+            html,
+            "<pre><code>let x = *a* &lt; b;\n</code></pre><p>after</p>"
+        );
+    }
+
+    #[test]
+    fn test_inline() {
+        assert_eq!(
+            markdown_to_html("**bold** and *it* with `a*b*c`"),
+            "<p><strong>bold</strong> and <em>it</em> with <code>a*b*c</code></p>"
+        );
+        assert_eq!(
+            markdown_to_html("see [the docs](https://example.com/?a=1&b=2)"),
+            "<p>see <a href=\"https://example.com/?a=1&amp;b=2\">the docs</a></p>"
+        );
+        assert_eq!(markdown_to_html("2 * 3 * 4"), "<p>2 * 3 * 4</p>");
+    }
+
+    #[test]
+    fn test_escapes_html_and_unsafe_links() {
+        assert_eq!(
+            markdown_to_html("<script>x</script> [click](javascript:alert(1))"),
+            "<p>&lt;script&gt;x&lt;/script&gt; [click](javascript:alert(1))</p>"
+        );
+        assert_eq!(markdown_to_html("#hashtag"), "<p>#hashtag</p>");
+    }
+}
@@ -1,8 +1,20 @@
 //! Email actions module
 //!
 //! Provides high-level action handlers for common email operations
-//! like archive, star, and read/unread status changes.
+//! like archive, star, read/unread status changes, unsubscribing and
+//! answering calendar invites, plus builders for outgoing mail and drafts,
+//! the outbox that delivers them, and downloads of received attachments.
 
+mod attachment;
+mod compose;
 mod handler;
+mod invite;
+mod markdown;
+mod outbox;
+mod unsubscribe;
 
-pub use handler::ActionHandler;
+pub use attachment::Attachment;
+pub use compose::{MAX_ATTACHMENTS_SIZE, OutgoingAttachment, OutgoingMessage};
+pub use handler::{ActionHandler, ActionQueuedError, QueueFlush};
+pub use outbox::{MAX_SEND_ATTEMPTS, OutboxFlush, retry_delay};
+pub use unsubscribe::UnsubscribeOutcome;
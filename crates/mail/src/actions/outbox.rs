@@ -0,0 +1,114 @@
+//! Retry policy for the outbox
+//!
+//! Sent messages go through the outbox so a send that fails after the
+//! compose window closes isn't lost. Messages Gmail can't be reached for
+//! are retried with exponential backoff; ones it rejects, or that run out
+//! of attempts, are marked failed for the user to resend or discard.
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::models::{OutboxMessage, OutboxState};
+
+/// Attempts before a message Gmail can't be reached for is marked failed
+pub const MAX_SEND_ATTEMPTS: u32 = 5;
+
+/// Wait before the first retry; each further retry waits twice as long
+const FIRST_RETRY_SECS: i64 = 30;
+
+/// Longest wait between retries
+const MAX_RETRY_SECS: i64 = 30 * 60;
+
+/// A send still marked in progress after this long was interrupted
+pub(super) const SENDING_TIMEOUT_MINS: i64 = 10;
+
+/// Outcome of processing an account's outbox
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutboxFlush {
+    /// Messages Gmail accepted
+    pub sent: usize,
+    /// Messages that failed for good and need the user
+    pub failed: usize,
+    /// Messages waiting for a later retry
+    pub remaining: usize,
+}
+
+/// How long to wait after a message's `attempts`-th failed attempt
+pub fn retry_delay(attempts: u32) -> Duration {
+    let doublings = attempts.saturating_sub(1).min(16);
+    Duration::seconds((FIRST_RETRY_SECS << doublings).min(MAX_RETRY_SECS))
+}
+
+/// Record a failed attempt, scheduling a retry if one is worthwhile
+///
+/// `transient` failures (Gmail unreachable or overloaded) are retried
+/// until [`MAX_SEND_ATTEMPTS`]; anything else fails straight away.
+pub(super) fn record_failure(
+    message: &mut OutboxMessage,
+    error: String,
+    transient: bool,
+    now: DateTime<Utc>,
+) {
+    message.last_error = Some(error);
+    if transient && message.attempts < MAX_SEND_ATTEMPTS {
+        message.state = OutboxState::Queued;
+        message.next_attempt_at = now + retry_delay(message.attempts);
+    } else {
+        message.state = OutboxState::Failed;
+    }
+}
+
+/// Whether a message marked as sending was abandoned mid-send
+pub(super) fn is_interrupted(message: &OutboxMessage, now: DateTime<Utc>) -> bool {
+    message.state == OutboxState::Sending
+        && message.next_attempt_at + Duration::minutes(SENDING_TIMEOUT_MINS) < now
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message() -> OutboxMessage {
+        OutboxMessage::new(1, None, "Hello", Vec::new(), String::new())
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), Duration::seconds(30));
+        assert_eq!(retry_delay(2), Duration::seconds(60));
+        assert_eq!(retry_delay(4), Duration::seconds(240));
+        assert_eq!(retry_delay(20), Duration::seconds(MAX_RETRY_SECS));
+    }
+
+    #[test]
+    fn test_record_failure() {
+        let now = Utc::now();
+
+        let mut unreachable = message();
+        unreachable.attempts = 1;
+        record_failure(&mut unreachable, "Timed out".to_string(), true, now);
+        assert_eq!(unreachable.state, OutboxState::Queued);
+        assert_eq!(unreachable.next_attempt_at, now + Duration::seconds(30));
+        assert!(!unreachable.is_due(now));
+
+        unreachable.attempts = MAX_SEND_ATTEMPTS;
+        record_failure(&mut unreachable, "Timed out".to_string(), true, now);
+        assert_eq!(unreachable.state, OutboxState::Failed);
+
+        let mut rejected = message();
+        rejected.attempts = 1;
+        record_failure(&mut rejected, "Invalid To header".to_string(), false, now);
+        assert_eq!(rejected.state, OutboxState::Failed);
+        assert_eq!(rejected.last_error.as_deref(), Some("Invalid To header"));
+    }
+
+    #[test]
+    fn test_is_interrupted() {
+        let now = Utc::now();
+        let mut sending = message();
+        sending.state = OutboxState::Sending;
+        sending.next_attempt_at = now - Duration::minutes(1);
+        assert!(!is_interrupted(&sending, now));
+        sending.next_attempt_at = now - Duration::minutes(SENDING_TIMEOUT_MINS + 1);
+        assert!(is_interrupted(&sending, now));
+    }
+}
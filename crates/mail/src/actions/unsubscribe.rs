@@ -0,0 +1,145 @@
+//! List-Unsubscribe handling
+//!
+//! Performs RFC 8058 one-click unsubscribes and builds unsubscribe emails
+//! from RFC 6068 `mailto:` URIs.
+
+use anyhow::{Context, Result};
+use base64::prelude::*;
+
+/// How an unsubscribe request was carried out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnsubscribeOutcome {
+    /// The sender's one-click endpoint accepted the request
+    OneClick,
+    /// An unsubscribe email was sent to the address
+    EmailSent(String),
+    /// The sender only offers a web page, which the caller should open
+    OpenUrl(String),
+}
+
+/// An unsubscribe email ready to send
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UnsubscribeEmail {
+    /// Recipient addresses, comma-separated
+    pub to: String,
+    /// RFC 2822 message
+    pub raw: String,
+}
+
+/// POST the one-click unsubscribe body to the sender's URL
+pub(crate) fn one_click_unsubscribe(url: &str) -> Result<()> {
+    ureq::post(url)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .send("List-Unsubscribe=One-Click")
+        .with_context(|| format!("One-click unsubscribe failed for {}", url))?;
+    Ok(())
+}
+
+/// Build the unsubscribe email for a `mailto:` URI
+///
+/// The URI's subject and body parameters are used when present; otherwise
+/// both default to "unsubscribe".
+pub(crate) fn unsubscribe_email(from: &str, mailto: &str) -> Result<UnsubscribeEmail> {
+    let target = mailto
+        .get(..7)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("mailto:"))
+        .map(|_| &mailto[7..])
+        .context("Not a mailto URI")?;
+    let (to, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let to = single_line(&urlencoding::decode(to)?);
+    if to.is_empty() {
+        anyhow::bail!("mailto URI has no recipient");
+    }
+
+    let mut subject = "unsubscribe".to_string();
+    let mut body = "unsubscribe".to_string();
+    // Unlike form encoding, '+' is a literal plus in mailto URIs
+    for (key, value) in query.split('&').filter_map(|p| p.split_once('=')) {
+        let value = urlencoding::decode(value)?.into_owned();
+        if key.eq_ignore_ascii_case("subject") {
+            subject = single_line(&value);
+        } else if key.eq_ignore_ascii_case("body") {
+            body = value;
+        }
+    }
+
+    let raw = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nMIME-Version: 1.0\r\n\
+         Content-Type: text/plain; charset=UTF-8\r\n\r\n{}\r\n",
+        from,
+        to,
+        encode_header(&subject),
+        body
+    );
+
+    Ok(UnsubscribeEmail { to, raw })
+}
+
+/// Strip line breaks so a value can't inject extra headers
+pub(super) fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], " ").trim().to_string()
+}
+
+/// RFC 2047 encode a header value if it isn't plain ASCII
+pub(super) fn encode_header(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", BASE64_STANDARD.encode(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unsubscribe_email() {
+        let email = unsubscribe_email(
+            "me@example.com",
+            "mailto:list%2Bunsub@example.com?subject=Remove%20me&body=a+b",
+        )
+        .unwrap();
+        assert_eq!(email.to, "list+unsub@example.com");
+        assert!(
+            email
+                .raw
+                .starts_with("From: me@example.com\r\nTo: list+unsub@example.com\r\n")
+        );
+        assert!(email.raw.contains("\r\nSubject: Remove me\r\n"));
+        assert!(email.raw.ends_with("\r\n\r\na+b\r\n"));
+    }
+
+    #[test]
+    fn test_unsubscribe_email_defaults() {
+        let email = unsubscribe_email("me@example.com", "MAILTO:unsub@example.com").unwrap();
+        assert_eq!(email.to, "unsub@example.com");
+        assert!(email.raw.contains("\r\nSubject: unsubscribe\r\n"));
+
+        assert!(unsubscribe_email("me@example.com", "https://example.com").is_err());
+        assert!(unsubscribe_email("me@example.com", "mailto:?subject=x").is_err());
+    }
+
+    #[test]
+    fn test_unsubscribe_email_headers() {
+        // Encoded line breaks can't add headers
+        let email = unsubscribe_email(
+            "me@example.com",
+            "mailto:unsub@example.com?subject=x%0D%0ABcc:%20victim@example.com",
+        )
+        .unwrap();
+        assert!(!email.raw.contains("\r\nBcc:"));
+
+        let email = unsubscribe_email(
+            "me@example.com",
+            "mailto:unsub@example.com?subject=d%C3%A9sabonner",
+        )
+        .unwrap();
+        assert!(
+            email
+                .raw
+                .contains("\r\nSubject: =?UTF-8?B?ZMOpc2Fib25uZXI=?=\r\n")
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! Mailbox analytics
+//!
+//! Aggregates computed by the mail store: who sends the most mail, how much
+//! arrives each day, how quickly the account replies, and how much space
+//! each label takes. Results are plain serializable structs for dashboard
+//! views and FFI.
+//!
+//! Times are taken from Gmail's internal date and days are UTC days. The
+//! account's own sent mail is not counted as received mail.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::MailStore;
+
+/// Order for [`top_senders`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SenderRanking {
+    /// Most messages first
+    #[default]
+    Volume,
+    /// Most bytes first
+    Size,
+}
+
+/// Mail received from one sender
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SenderStats {
+    /// Sender address (lowercase)
+    pub email: String,
+    /// Display name from the sender's newest message
+    pub name: Option<String>,
+    /// Messages received from the sender
+    pub message_count: usize,
+    /// Total estimated size of those messages
+    pub total_bytes: i64,
+    /// When the newest message arrived
+    pub last_received_at: DateTime<Utc>,
+}
+
+/// Mail received on one day
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyVolume {
+    /// UTC day
+    pub date: NaiveDate,
+    /// Messages received that day
+    pub message_count: usize,
+}
+
+/// How long the account takes to reply
+///
+/// A reply is a sent message that directly follows a received message in
+/// the same thread; its latency is the time between the two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponseLatency {
+    /// Replies measured
+    pub replies: usize,
+    /// Median latency in seconds
+    pub median_secs: i64,
+    /// Mean latency in seconds
+    pub mean_secs: i64,
+    /// 90th percentile latency in seconds
+    pub p90_secs: i64,
+}
+
+/// Space taken by the messages with one label
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelStorage {
+    /// Gmail label ID
+    pub label_id: String,
+    /// Messages with the label
+    pub message_count: usize,
+    /// Total estimated size of those messages
+    pub total_bytes: i64,
+}
+
+/// All mailbox insights for a dashboard
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MailboxAnalytics {
+    /// Senders with the most messages
+    pub top_senders_by_volume: Vec<SenderStats>,
+    /// Senders using the most space
+    pub top_senders_by_size: Vec<SenderStats>,
+    /// Mail received per day, oldest first, including empty days
+    pub daily_volume: Vec<DailyVolume>,
+    /// Reply latency over the period, or None without replies
+    pub response_latency: Option<ResponseLatency>,
+    /// Space per label, largest first
+    pub label_storage: Vec<LabelStorage>,
+}
+
+/// Senders with the most mail
+///
+/// # Arguments
+/// * `store` - The storage backend
+/// * `account_id` - Optional account filter (None = all accounts)
+/// * `ranking` - Rank by message count or total size
+/// * `limit` - Maximum number of senders to return
+pub fn top_senders(
+    store: &dyn MailStore,
+    account_id: Option<i64>,
+    ranking: SenderRanking,
+    limit: usize,
+) -> Result<Vec<SenderStats>> {
+    store.sender_stats(account_id, ranking, limit)
+}
+
+/// Mail received on each of the last `days` days, oldest first
+///
+/// Days without mail are included with a count of zero, so the result
+/// always has `days` entries ending today.
+pub fn messages_per_day(
+    store: &dyn MailStore,
+    account_id: Option<i64>,
+    days: u32,
+) -> Result<Vec<DailyVolume>> {
+    messages_per_day_until(store, account_id, days, Utc::now().date_naive())
+}
+
+fn messages_per_day_until(
+    store: &dyn MailStore,
+    account_id: Option<i64>,
+    days: u32,
+    today: NaiveDate,
+) -> Result<Vec<DailyVolume>> {
+    if days == 0 {
+        return Ok(Vec::new());
+    }
+    let first = today - Duration::days(i64::from(days) - 1);
+    let counts = store.daily_message_counts(account_id, day_start(first))?;
+
+    let mut counts = counts.into_iter().peekable();
+    let mut volume = Vec::with_capacity(days as usize);
+    for date in first.iter_days().take(days as usize) {
+        let message_count = match counts.next_if(|c| c.date == date) {
+            Some(c) => c.message_count,
+            None => 0,
+        };
+        volume.push(DailyVolume {
+            date,
+            message_count,
+        });
+    }
+    Ok(volume)
+}
+
+/// Reply latency over the last `days` days, or None without replies
+pub fn response_latency(
+    store: &dyn MailStore,
+    account_id: Option<i64>,
+    days: u32,
+) -> Result<Option<ResponseLatency>> {
+    let since = Utc::now() - Duration::days(i64::from(days));
+    let delays = store.reply_delays_ms(account_id, since)?;
+    Ok(summarize_delays(delays))
+}
+
+/// Message count and size per label, largest first
+pub fn storage_by_label(
+    store: &dyn MailStore,
+    account_id: Option<i64>,
+) -> Result<Vec<LabelStorage>> {
+    store.label_storage(account_id)
+}
+
+/// Compute every insight for the last `days` days
+///
+/// Sender and label figures cover the whole mailbox; daily volume and
+/// reply latency cover the period.
+pub fn mailbox_analytics(
+    store: &dyn MailStore,
+    account_id: Option<i64>,
+    days: u32,
+    top: usize,
+) -> Result<MailboxAnalytics> {
+    Ok(MailboxAnalytics {
+        top_senders_by_volume: top_senders(store, account_id, SenderRanking::Volume, top)?,
+        top_senders_by_size: top_senders(store, account_id, SenderRanking::Size, top)?,
+        daily_volume: messages_per_day(store, account_id, days)?,
+        response_latency: response_latency(store, account_id, days)?,
+        label_storage: storage_by_label(store, account_id)?,
+    })
+}
+
+/// Midnight UTC at the start of a day
+fn day_start(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0)
+        .expect("midnight is a valid time")
+        .and_utc()
+}
+
+fn summarize_delays(mut delays: Vec<i64>) -> Option<ResponseLatency> {
+    if delays.is_empty() {
+        return None;
+    }
+    delays.sort_unstable();
+
+    let n = delays.len();
+    let percentile = |p: usize| delays[((n - 1) * p).div_ceil(100)] / 1000;
+    let median_ms = if n.is_multiple_of(2) {
+        (delays[n / 2 - 1] + delays[n / 2]) / 2
+    } else {
+        delays[n / 2]
+    };
+
+    Some(ResponseLatency {
+        replies: n,
+        median_secs: median_ms / 1000,
+        mean_secs: delays.iter().sum::<i64>() / n as i64 / 1000,
+        p90_secs: percentile(90),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EmailAddress, Message, MessageId, ThreadId};
+    use crate::storage::InMemoryMailStore;
+
+    /// (message ID, thread ID, sender, time, size, label)
+    type Fixture<'a> = (&'a str, &'a str, &'a str, DateTime<Utc>, i64, &'a str);
+
+    fn store_with(messages: &[Fixture]) -> InMemoryMailStore {
+        let store = InMemoryMailStore::new();
+        for &(id, thread_id, from, at, size, label) in messages {
+            store
+                .upsert_message(
+                    Message::builder(MessageId::new(id), ThreadId::new(thread_id))
+                        .account_id(1)
+                        .from(EmailAddress::parse(from))
+                        .received_at(at)
+                        .internal_date(at.timestamp_millis())
+                        .size_bytes(size)
+                        .label_ids(vec![label.to_string()])
+                        .build(),
+                )
+                .unwrap();
+        }
+        store
+    }
+
+    #[test]
+    fn test_top_senders() {
+        let now = Utc::now();
+        let hour = Duration::hours(1);
+        let store = store_with(&[
+            (
+                "m1",
+                "t1",
+                "news@shop.example",
+                now - hour * 3,
+                100,
+                "INBOX",
+            ),
+            (
+                "m2",
+                "t2",
+                "News <NEWS@shop.example>",
+                now - hour,
+                100,
+                "INBOX",
+            ),
+            ("m3", "t3", "big@files.example", now, 5000, "INBOX"),
+            ("m4", "t3", "me@example.com", now, 9000, "SENT"),
+        ]);
+
+        let by_volume = top_senders(&store, None, SenderRanking::Volume, 10).unwrap();
+        assert_eq!(by_volume.len(), 2);
+        assert_eq!(by_volume[0].email, "news@shop.example");
+        assert_eq!(by_volume[0].message_count, 2);
+        assert_eq!(by_volume[0].total_bytes, 200);
+        assert_eq!(by_volume[0].name.as_deref(), Some("News"));
+
+        let by_size = top_senders(&store, None, SenderRanking::Size, 1).unwrap();
+        assert_eq!(by_size.len(), 1);
+        assert_eq!(by_size[0].email, "big@files.example");
+
+        let other = top_senders(&store, Some(2), SenderRanking::Volume, 10).unwrap();
+        assert!(other.is_empty());
+    }
+
+    #[test]
+    fn test_messages_per_day() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let at = |days_ago: i64| day_start(today - Duration::days(days_ago)) + Duration::hours(12);
+        let store = store_with(&[
+            ("m1", "t1", "a@example.com", at(0), 1, "INBOX"),
+            ("m2", "t2", "a@example.com", at(0), 1, "INBOX"),
+            ("m3", "t3", "a@example.com", at(2), 1, "INBOX"),
+            ("m4", "t4", "a@example.com", at(5), 1, "INBOX"),
+            ("m5", "t1", "me@example.com", at(0), 1, "SENT"),
+        ]);
+
+        let volume = messages_per_day_until(&store, None, 3, today).unwrap();
+        let counts: Vec<usize> = volume.iter().map(|v| v.message_count).collect();
+        assert_eq!(counts, vec![1, 0, 2]);
+        assert_eq!(volume[0].date, NaiveDate::from_ymd_opt(2024, 3, 8).unwrap());
+        assert_eq!(volume[2].date, today);
+    }
+
+    #[test]
+    fn test_response_latency() {
+        let start = Utc::now() - Duration::days(1);
+        let minutes = |m: i64| start + Duration::minutes(m);
+        let store = store_with(&[
+            // Replied after 10 minutes, then a follow-up that isn't a reply
+            ("a1", "ta", "x@example.com", minutes(0), 1, "INBOX"),
+            ("a2", "ta", "me@example.com", minutes(10), 1, "SENT"),
+            ("a3", "ta", "me@example.com", minutes(20), 1, "SENT"),
+            // Replied after 30 minutes to the newer of two messages
+            ("b1", "tb", "y@example.com", minutes(0), 1, "INBOX"),
+            ("b2", "tb", "y@example.com", minutes(30), 1, "INBOX"),
+            ("b3", "tb", "me@example.com", minutes(60), 1, "SENT"),
+            // A thread started by the account
+            ("c1", "tc", "me@example.com", minutes(0), 1, "SENT"),
+        ]);
+
+        let latency = response_latency(&store, None, 7).unwrap().unwrap();
+        assert_eq!(latency.replies, 2);
+        assert_eq!(latency.median_secs, 20 * 60);
+        assert_eq!(latency.mean_secs, 20 * 60);
+        assert_eq!(latency.p90_secs, 30 * 60);
+
+        assert_eq!(response_latency(&store, Some(2), 7).unwrap(), None);
+    }
+
+    #[test]
+    fn test_storage_by_label() {
+        let now = Utc::now();
+        let store = store_with(&[
+            ("m1", "t1", "a@example.com", now, 100, "INBOX"),
+            ("m2", "t2", "a@example.com", now, 300, "INBOX"),
+            ("m3", "t3", "me@example.com", now, 1000, "SENT"),
+            ("m4", "t4", "a@example.com", now, 300, "TRASH"),
+        ]);
+
+        let storage = storage_by_label(&store, None).unwrap();
+        let summary: Vec<(&str, usize, i64)> = storage
+            .iter()
+            .map(|s| (s.label_id.as_str(), s.message_count, s.total_bytes))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("SENT", 1, 1000), ("INBOX", 2, 400), ("TRASH", 1, 300)]
+        );
+    }
+
+    #[test]
+    fn test_summarize_delays() {
+        assert_eq!(summarize_delays(Vec::new()), None);
+
+        let delays = (1..=10).map(|s| s * 1000).collect();
+        let latency = summarize_delays(delays).unwrap();
+        assert_eq!(latency.replies, 10);
+        assert_eq!(latency.median_secs, 5);
+        assert_eq!(latency.mean_secs, 5);
+        assert_eq!(latency.p90_secs, 10);
+    }
+}
@@ -0,0 +1,408 @@
+//! Text extraction from attachments, for search
+//!
+//! PDFs go through `pdf-extract`; Office Open XML (docx, xlsx, pptx) and
+//! OpenDocument (odt, ods, odp) files are zip archives whose text lives in
+//! a few known XML entries. Only compiled with the `attachment-text` feature.
+
+use std::io::{Cursor, Read};
+
+use anyhow::{Context, Result, bail};
+use log::debug;
+
+use crate::gmail::GmailApi;
+use crate::gmail::api::{GmailMessage, MessagePart};
+use crate::models::MessageId;
+
+/// Attachments larger than this are not downloaded
+pub const MAX_ATTACHMENT_BYTES: u32 = 10 * 1024 * 1024;
+
+/// Extracted text is truncated to this many bytes per message
+pub const MAX_TEXT_BYTES: usize = 256 * 1024;
+
+/// Largest XML entry read out of an office archive
+const MAX_XML_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Kind of document text can be extracted from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DocumentKind {
+    Pdf,
+    Docx,
+    Xlsx,
+    Pptx,
+    OpenDocument,
+    PlainText,
+}
+
+impl DocumentKind {
+    /// Identify a document from its MIME type, falling back to the extension
+    fn detect(mime_type: &str, filename: &str) -> Option<Self> {
+        let mime_type = mime_type.to_ascii_lowercase();
+        let by_mime = match mime_type.as_str() {
+            "application/pdf" => Some(Self::Pdf),
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => {
+                Some(Self::Docx)
+            }
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => Some(Self::Xlsx),
+            "application/vnd.openxmlformats-officedocument.presentationml.presentation" => {
+                Some(Self::Pptx)
+            }
+            "application/vnd.oasis.opendocument.text"
+            | "application/vnd.oasis.opendocument.spreadsheet"
+            | "application/vnd.oasis.opendocument.presentation" => Some(Self::OpenDocument),
+            "text/plain" | "text/csv" | "text/markdown" => Some(Self::PlainText),
+            _ => None,
+        };
+        if by_mime.is_some() {
+            return by_mime;
+        }
+
+        // Attachments are often sent as application/octet-stream
+        let extension = filename.rsplit_once('.')?.1.to_ascii_lowercase();
+        match extension.as_str() {
+            "pdf" => Some(Self::Pdf),
+            "docx" => Some(Self::Docx),
+            "xlsx" => Some(Self::Xlsx),
+            "pptx" => Some(Self::Pptx),
+            "odt" | "ods" | "odp" => Some(Self::OpenDocument),
+            "txt" | "csv" | "md" => Some(Self::PlainText),
+            _ => None,
+        }
+    }
+}
+
+/// Whether text can be extracted from an attachment of this type
+pub fn is_supported(mime_type: &str, filename: &str) -> bool {
+    DocumentKind::detect(mime_type, filename).is_some()
+}
+
+/// Extract the text of an attachment
+///
+/// Returns `Ok(None)` for unsupported types.
+pub fn extract_text(mime_type: &str, filename: &str, bytes: &[u8]) -> Result<Option<String>> {
+    let Some(kind) = DocumentKind::detect(mime_type, filename) else {
+        return Ok(None);
+    };
+
+    let text = match kind {
+        DocumentKind::Pdf => extract_pdf(bytes)?,
+        DocumentKind::Docx => extract_zip_xml(bytes, |name| name == "word/document.xml")?,
+        DocumentKind::Xlsx => extract_zip_xml(bytes, |name| name == "xl/sharedStrings.xml")?,
+        DocumentKind::Pptx => extract_zip_xml(bytes, |name| {
+            name.starts_with("ppt/slides/slide") && name.ends_with(".xml")
+        })?,
+        DocumentKind::OpenDocument => extract_zip_xml(bytes, |name| name == "content.xml")?,
+        DocumentKind::PlainText => String::from_utf8_lossy(bytes).into_owned(),
+    };
+
+    Ok(Some(collapse_whitespace(&text)))
+}
+
+/// Download a message's supported attachments and store their text in
+/// `attachment_text`
+///
+/// Failures for individual attachments are logged and skipped, so a broken
+/// PDF never holds up sync.
+pub fn fill_attachment_text(client: &dyn GmailApi, message: &mut GmailMessage) {
+    let Some(parts) = message.payload.as_ref().and_then(|p| p.parts.as_deref()) else {
+        return;
+    };
+
+    let mut candidates = Vec::new();
+    collect_candidates(parts, &mut candidates);
+    if candidates.is_empty() {
+        return;
+    }
+
+    let message_id = MessageId::new(&message.id);
+    let mut texts = Vec::new();
+    let mut total = 0;
+    for candidate in candidates {
+        if total >= MAX_TEXT_BYTES {
+            break;
+        }
+        let text = client
+            .get_attachment(&message_id, &candidate.attachment_id)
+            .and_then(|bytes| extract_text(&candidate.mime_type, &candidate.filename, &bytes));
+        match text {
+            Ok(Some(text)) if !text.is_empty() => {
+                total += text.len();
+                texts.push(text);
+            }
+            Ok(_) => {}
+            Err(e) => debug!(
+                "Skipping text of {} in message {}: {:#}",
+                candidate.filename, message.id, e
+            ),
+        }
+    }
+
+    if !texts.is_empty() {
+        message.attachment_text = Some(truncate(texts.join("\n\n"), MAX_TEXT_BYTES));
+    }
+}
+
+/// An attachment worth downloading
+struct Candidate {
+    attachment_id: String,
+    mime_type: String,
+    filename: String,
+}
+
+/// Collect supported, reasonably sized attachments in MIME order
+fn collect_candidates(parts: &[MessagePart], out: &mut Vec<Candidate>) {
+    for part in parts {
+        if let Some(filename) = part.filename.as_ref().filter(|f| !f.is_empty())
+            && let Some(body) = &part.body
+            && let Some(attachment_id) = &body.attachment_id
+            && body.size.unwrap_or(0) <= MAX_ATTACHMENT_BYTES
+        {
+            let mime_type = part.mime_type.clone().unwrap_or_default();
+            if is_supported(&mime_type, filename) {
+                out.push(Candidate {
+                    attachment_id: attachment_id.clone(),
+                    mime_type,
+                    filename: filename.clone(),
+                });
+            }
+        }
+        if let Some(nested) = &part.parts {
+            collect_candidates(nested, out);
+        }
+    }
+}
+
+/// Extract text from a PDF
+fn extract_pdf(bytes: &[u8]) -> Result<String> {
+    // pdf-extract panics on some malformed files instead of returning errors
+    let result = std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem(bytes));
+    match result {
+        Ok(text) => text.context("Failed to extract PDF text"),
+        Err(_) => bail!("PDF extraction panicked"),
+    }
+}
+
+/// Concatenate the text of the XML entries in a zip archive that match
+/// `wanted`, shortest name first
+fn extract_zip_xml(bytes: &[u8], wanted: impl Fn(&str) -> bool) -> Result<String> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(bytes)).context("Attachment is not a zip archive")?;
+
+    let mut names: Vec<String> = archive
+        .file_names()
+        .filter(|name| wanted(name))
+        .map(str::to_string)
+        .collect();
+    // Zip order isn't slide order; sort slide2 before slide10
+    names.sort_by_key(|name| (name.len(), name.clone()));
+
+    let mut text = String::new();
+    for name in names {
+        let entry = archive.by_name(&name)?;
+        let mut xml = String::new();
+        entry
+            .take(MAX_XML_BYTES)
+            .read_to_string(&mut xml)
+            .with_context(|| format!("Failed to read {}", name))?;
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(&xml_text(&xml));
+    }
+    Ok(text)
+}
+
+/// Strip tags from an XML document, keeping text content
+///
+/// Paragraph, cell and line-break elements become whitespace so words from
+/// adjacent elements don't run together.
+fn xml_text(xml: &str) -> String {
+    let mut out = String::with_capacity(xml.len() / 4);
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&decode_entities(&rest[..start]));
+        let Some(end) = rest[start..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        if is_break_tag(tag) {
+            out.push(' ');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(&decode_entities(rest));
+    out
+}
+
+/// Whether a tag ends a run of text (paragraphs, cells, breaks, tabs)
+fn is_break_tag(tag: &str) -> bool {
+    let name = tag
+        .trim_start_matches('/')
+        .split(|c: char| c.is_whitespace() || c == '/')
+        .next()
+        .unwrap_or_default();
+    matches!(
+        name,
+        "w:p"
+            | "w:br"
+            | "w:tab"
+            | "w:cr"
+            | "si"
+            | "a:p"
+            | "a:br"
+            | "text:p"
+            | "text:h"
+            | "text:line-break"
+            | "text:tab"
+            | "table:table-cell"
+    )
+}
+
+/// Decode the predefined XML entities and numeric character references
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').and_then(|semi| {
+            let entity = &rest[1..semi];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse::<u32>()))
+                    .and_then(|n| n.ok())
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, semi))
+        });
+        match decoded {
+            Some((c, semi)) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Collapse runs of whitespace into single spaces, keeping paragraph breaks
+fn collapse_whitespace(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Truncate to at most `max` bytes on a character boundary
+fn truncate(mut text: String, max: usize) -> String {
+    if text.len() > max {
+        let mut end = max;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn zip_with(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in entries {
+            writer
+                .start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            DocumentKind::detect("application/pdf", "x"),
+            Some(DocumentKind::Pdf)
+        );
+        assert_eq!(
+            DocumentKind::detect("application/octet-stream", "Report.DOCX"),
+            Some(DocumentKind::Docx)
+        );
+        assert_eq!(DocumentKind::detect("image/png", "photo.png"), None);
+        assert!(!is_supported("application/zip", "archive.zip"));
+    }
+
+    #[test]
+    fn test_extract_docx() {
+        let docx = zip_with(&[
+            ("[Content_Types].xml", "<Types/>"),
+            (
+                "word/document.xml",
+                "<w:document><w:body><w:p><w:r><w:t>Invoice 4821</w:t></w:r></w:p>\
+                 <w:p><w:r><w:t>Total &amp; tax</w:t></w:r></w:p></w:body></w:document>",
+            ),
+        ]);
+        let text = extract_text("", "invoice.docx", &docx).unwrap().unwrap();
+        assert_eq!(text, "Invoice 4821 Total & tax");
+    }
+
+    #[test]
+    fn test_extract_pptx_slide_order() {
+        let pptx = zip_with(&[
+            (
+                "ppt/slides/slide10.xml",
+                "<p:sld><a:p><a:t>ten</a:t></a:p></p:sld>",
+            ),
+            (
+                "ppt/slides/slide2.xml",
+                "<p:sld><a:p><a:t>two</a:t></a:p></p:sld>",
+            ),
+            ("ppt/slides/_rels/slide2.xml.rels", "<Relationships/>"),
+        ]);
+        let text = extract_text("", "deck.pptx", &pptx).unwrap().unwrap();
+        assert_eq!(text, "two\nten");
+    }
+
+    #[test]
+    fn test_extract_unsupported_and_invalid() {
+        assert_eq!(
+            extract_text("image/png", "a.png", b"\x89PNG").unwrap(),
+            None
+        );
+        assert!(extract_text("", "broken.xlsx", b"not a zip").is_err());
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(
+            decode_entities("a &lt;b&gt; &#233;&#x41; &bogus; &"),
+            "a <b> éA &bogus; &"
+        );
+    }
+
+    #[test]
+    fn test_truncate_char_boundary() {
+        assert_eq!(truncate("héllo".to_string(), 2), "h");
+        assert_eq!(truncate("abc".to_string(), 10), "abc");
+    }
+}
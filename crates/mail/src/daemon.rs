@@ -0,0 +1,217 @@
+//! Local IPC between the `cosmosd` sync daemon and the apps
+//!
+//! The daemon listens on a Unix socket in the Cosmos config directory and
+//! writes every [`DaemonEvent`] to each connected client as one line of
+//! JSON, e.g. `{"event":"sync_completed","account_id":1,...}`. Clients only
+//! listen; the socket carries no requests.
+//!
+//! Apps connect with [`connect_daemon`] at startup. A failed connection means
+//! no daemon is running and the app should sync on its own.
+
+use std::io::{self, BufRead, BufReader};
+#[cfg(unix)]
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::sync::{Arc, Mutex};
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::models::ThreadId;
+
+/// Socket file the daemon listens on, in the Cosmos config directory
+pub const DAEMON_SOCKET_FILE: &str = "cosmosd.sock";
+
+/// Path of the daemon socket
+pub fn daemon_socket_path() -> Option<PathBuf> {
+    config::config_path(DAEMON_SOCKET_FILE)
+}
+
+/// Something the daemon did that connected apps may want to reflect
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum DaemonEvent {
+    /// A scheduled sync started for an account
+    SyncStarted { account_id: i64 },
+    /// A sync finished and the store holds new data
+    SyncCompleted {
+        account_id: i64,
+        messages_created: usize,
+        messages_updated: usize,
+    },
+    /// A sync failed; the daemon retries on its next round
+    SyncFailed { account_id: i64, error: String },
+    /// The user clicked a notification the daemon raised
+    OpenThread { thread_id: ThreadId },
+}
+
+/// Accepts app connections and fans events out to them
+#[cfg(unix)]
+pub struct EventServer {
+    path: PathBuf,
+    clients: Arc<Mutex<Vec<UnixStream>>>,
+}
+
+#[cfg(unix)]
+impl EventServer {
+    /// Listen on `path`, accepting clients on a background thread
+    ///
+    /// A socket file left behind by a daemon that exited uncleanly is
+    /// replaced, but one that still accepts connections is an error so two
+    /// daemons never sync side by side.
+    pub fn bind(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("another daemon is listening on {}", path.display()),
+                ));
+            }
+            std::fs::remove_file(&path)?;
+        }
+
+        let listener = UnixListener::bind(&path)?;
+        let clients: Arc<Mutex<Vec<UnixStream>>> = Arc::default();
+        let accepted = clients.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        debug!("App connected to daemon socket");
+                        if let Ok(mut clients) = accepted.lock() {
+                            clients.push(stream);
+                        }
+                    }
+                    Err(e) => warn!("Failed to accept daemon client: {}", e),
+                }
+            }
+        });
+
+        Ok(Self { path, clients })
+    }
+
+    /// Send an event to every connected app, dropping ones that went away
+    pub fn broadcast(&self, event: &DaemonEvent) {
+        let line = match serde_json::to_string(event) {
+            Ok(json) => json + "\n",
+            Err(e) => {
+                warn!("Failed to encode daemon event: {}", e);
+                return;
+            }
+        };
+        let Ok(mut clients) = self.clients.lock() else {
+            return;
+        };
+        clients.retain_mut(|client| client.write_all(line.as_bytes()).is_ok());
+    }
+
+    /// Number of apps currently connected
+    pub fn client_count(&self) -> usize {
+        self.clients.lock().map(|clients| clients.len()).unwrap_or(0)
+    }
+}
+
+#[cfg(unix)]
+impl Drop for EventServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Events read from a daemon connection, ending when the daemon exits
+pub struct DaemonEvents<R> {
+    reader: BufReader<R>,
+}
+
+impl<R: io::Read> Iterator for DaemonEvents<R> {
+    type Item = DaemonEvent;
+
+    fn next(&mut self) -> Option<DaemonEvent> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) | Err(_) => return None,
+                Ok(_) => match serde_json::from_str(line.trim()) {
+                    Ok(event) => return Some(event),
+                    // Events from a newer daemon are skipped, not fatal
+                    Err(e) => debug!("Ignoring unknown daemon event: {}", e),
+                },
+            }
+        }
+    }
+}
+
+/// Connect to a running daemon and stream its events
+///
+/// Iterating blocks, so read the events on a dedicated thread.
+#[cfg(unix)]
+pub fn connect_daemon(path: impl AsRef<Path>) -> io::Result<DaemonEvents<UnixStream>> {
+    let stream = UnixStream::connect(path)?;
+    Ok(DaemonEvents {
+        reader: BufReader::new(stream),
+    })
+}
+
+/// Connect to a running daemon and stream its events
+///
+/// The daemon only serves Unix sockets, so this always fails here.
+#[cfg(not(unix))]
+pub fn connect_daemon(_path: impl AsRef<Path>) -> io::Result<DaemonEvents<io::Empty>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "the sync daemon is only available on Unix",
+    ))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_event_json_shape() {
+        let event = DaemonEvent::SyncFailed {
+            account_id: 2,
+            error: "offline".to_string(),
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"event":"sync_failed","account_id":2,"error":"offline"}"#);
+        assert_eq!(serde_json::from_str::<DaemonEvent>(&json).unwrap(), event);
+    }
+
+    #[test]
+    fn test_broadcast_reaches_connected_app() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DAEMON_SOCKET_FILE);
+        let server = EventServer::bind(&path).unwrap();
+        let mut events = connect_daemon(&path).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while server.client_count() == 0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let event = DaemonEvent::OpenThread {
+            thread_id: ThreadId::new("t1"),
+        };
+        server.broadcast(&event);
+        assert_eq!(events.next(), Some(event));
+
+        drop(server);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_bind_refuses_running_daemon() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DAEMON_SOCKET_FILE);
+        let _server = EventServer::bind(&path).unwrap();
+        let err = EventServer::bind(&path).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+    }
+}
@@ -0,0 +1,333 @@
+//! Diagnostic event log for bug reports
+//!
+//! Keeps a bounded ring buffer of recent sync events, storage errors, and
+//! API failures. The buffer is persisted to the data directory so context
+//! survives a crash, and can be exported as a single redacted text file.
+//!
+//! The process-wide log is reached through [`diagnostics`]. Call
+//! [`init_diagnostics`] once at startup to enable persistence.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Default number of events retained
+pub const DEFAULT_DIAGNOSTIC_CAPACITY: usize = 500;
+
+/// Global diagnostic log
+static DIAGNOSTICS: OnceLock<DiagnosticLog> = OnceLock::new();
+
+/// Category of a diagnostic event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticKind {
+    /// Sync lifecycle (started, completed, resumed, history expired)
+    Sync,
+    /// Storage read/write failure
+    Storage,
+    /// Gmail API request failure
+    Api,
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagnosticKind::Sync => write!(f, "sync"),
+            DiagnosticKind::Storage => write!(f, "storage"),
+            DiagnosticKind::Api => write!(f, "api"),
+        }
+    }
+}
+
+/// A single recorded event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticEvent {
+    /// When the event was recorded
+    pub at: DateTime<Utc>,
+    /// Event category
+    pub kind: DiagnosticKind,
+    /// Account the event relates to, if any
+    #[serde(default)]
+    pub account_id: Option<i64>,
+    /// Human-readable description (unredacted)
+    pub message: String,
+}
+
+/// Bounded, optionally persisted log of diagnostic events
+pub struct DiagnosticLog {
+    capacity: usize,
+    path: Mutex<Option<PathBuf>>,
+    events: Mutex<VecDeque<DiagnosticEvent>>,
+}
+
+impl DiagnosticLog {
+    /// Create an in-memory log holding at most `capacity` events
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            path: Mutex::new(None),
+            events: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Persist the log to `path`, loading any events already saved there
+    ///
+    /// A missing or unreadable file starts an empty log.
+    pub fn attach_file(&self, path: &Path) {
+        let saved: Vec<DiagnosticEvent> = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        {
+            let mut events = self.events.lock().unwrap();
+            // Saved events are older than anything recorded this session
+            for event in saved.into_iter().rev() {
+                events.push_front(event);
+            }
+            while events.len() > self.capacity {
+                events.pop_front();
+            }
+        }
+
+        *self.path.lock().unwrap() = Some(path.to_path_buf());
+        self.persist();
+    }
+
+    /// Record an event, evicting the oldest when full
+    pub fn record(
+        &self,
+        kind: DiagnosticKind,
+        account_id: Option<i64>,
+        message: impl Into<String>,
+    ) {
+        {
+            let mut events = self.events.lock().unwrap();
+            if events.len() == self.capacity {
+                events.pop_front();
+            }
+            events.push_back(DiagnosticEvent {
+                at: Utc::now(),
+                kind,
+                account_id,
+                message: message.into(),
+            });
+        }
+        self.persist();
+    }
+
+    /// Snapshot of recorded events, oldest first
+    pub fn events(&self) -> Vec<DiagnosticEvent> {
+        self.events.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Remove all events (and the persisted copy)
+    pub fn clear(&self) {
+        self.events.lock().unwrap().clear();
+        self.persist();
+    }
+
+    /// Render the log as a redacted plain-text report
+    pub fn export_report(&self) -> String {
+        let mut report = String::new();
+        report.push_str("Cosmos diagnostic report\n");
+        report.push_str(&format!("Generated: {}\n", Utc::now().to_rfc3339()));
+        report.push_str(&format!(
+            "Version: {} ({} {})\n",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        ));
+
+        let events = self.events();
+        report.push_str(&format!("Events: {}\n\n", events.len()));
+
+        for event in events {
+            let account = event
+                .account_id
+                .map(|id| format!(" account={}", id))
+                .unwrap_or_default();
+            report.push_str(&format!(
+                "{} [{}]{} {}\n",
+                event.at.to_rfc3339(),
+                event.kind,
+                account,
+                redact(&event.message)
+            ));
+        }
+
+        report
+    }
+
+    /// Write the redacted report to `path`
+    pub fn export_to(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.export_report())
+            .with_context(|| format!("Failed to write diagnostics: {}", path.display()))
+    }
+
+    /// Save the buffer to the attached file, if any
+    ///
+    /// Failures are ignored: diagnostics must never break the caller.
+    fn persist(&self) {
+        let Some(path) = self.path.lock().unwrap().clone() else {
+            return;
+        };
+        let events = self.events();
+        if let Ok(content) = serde_json::to_string(&events) {
+            let _ = std::fs::write(path, content);
+        }
+    }
+}
+
+/// Get the process-wide diagnostic log
+pub fn diagnostics() -> &'static DiagnosticLog {
+    DIAGNOSTICS.get_or_init(|| DiagnosticLog::new(DEFAULT_DIAGNOSTIC_CAPACITY))
+}
+
+/// Enable persistence of the process-wide log at `path`
+pub fn init_diagnostics(path: &Path) {
+    diagnostics().attach_file(path);
+}
+
+/// Record an event in the process-wide log
+pub fn record_diagnostic(
+    kind: DiagnosticKind,
+    account_id: Option<i64>,
+    message: impl Into<String>,
+) {
+    diagnostics().record(kind, account_id, message);
+}
+
+/// Query parameters whose values are credentials
+const SECRET_PARAMS: &[&str] = &[
+    "access_token=",
+    "refresh_token=",
+    "code=",
+    "key=",
+    "client_secret=",
+];
+
+/// Strip personal data and credentials from a message
+///
+/// Email addresses keep only their domain, bearer tokens and credential
+/// query parameters are replaced with `***`.
+pub fn redact(text: &str) -> String {
+    let mut out = Vec::new();
+    let mut after_bearer = false;
+
+    for word in text.split(' ') {
+        if after_bearer {
+            out.push("***".to_string());
+            after_bearer = false;
+            continue;
+        }
+        if word.eq_ignore_ascii_case("bearer") {
+            after_bearer = true;
+            out.push(word.to_string());
+            continue;
+        }
+        out.push(redact_word(word));
+    }
+
+    out.join(" ")
+}
+
+/// Redact a single whitespace-delimited word
+fn redact_word(word: &str) -> String {
+    for param in SECRET_PARAMS {
+        if let Some(pos) = word.find(param) {
+            let value_start = pos + param.len();
+            let value_end = word[value_start..]
+                .find('&')
+                .map(|i| value_start + i)
+                .unwrap_or(word.len());
+            return format!(
+                "{}***{}",
+                &word[..value_start],
+                redact_word(&word[value_end..])
+            );
+        }
+    }
+
+    if let Some(at) = word.find('@') {
+        // Local part starts after the last delimiter before '@'
+        let start = word[..at]
+            .rfind(|c: char| c == '<' || c == '(' || c == '"' || c == '\'' || c == ':' || c == '=')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        if at > start {
+            return format!("{}***{}", &word[..start], &word[at..]);
+        }
+    }
+
+    word.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_buffer_evicts_oldest() {
+        let log = DiagnosticLog::new(2);
+        log.record(DiagnosticKind::Sync, None, "one");
+        log.record(DiagnosticKind::Api, None, "two");
+        log.record(DiagnosticKind::Storage, Some(1), "three");
+
+        let messages: Vec<_> = log.events().into_iter().map(|e| e.message).collect();
+        assert_eq!(messages, vec!["two", "three"]);
+    }
+
+    #[test]
+    fn test_persists_across_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("diagnostics.json");
+
+        let log = DiagnosticLog::new(10);
+        log.attach_file(&path);
+        log.record(DiagnosticKind::Sync, Some(1), "sync started");
+
+        let reopened = DiagnosticLog::new(10);
+        reopened.attach_file(&path);
+        reopened.record(DiagnosticKind::Sync, Some(1), "sync completed");
+
+        let messages: Vec<_> = reopened.events().into_iter().map(|e| e.message).collect();
+        assert_eq!(messages, vec!["sync started", "sync completed"]);
+    }
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(
+            redact("Failed for alice@example.com"),
+            "Failed for ***@example.com"
+        );
+        assert_eq!(
+            redact("From: \"Bob\" <bob.smith@mail.org>"),
+            "From: \"Bob\" <***@mail.org>"
+        );
+        assert_eq!(
+            redact("Authorization: Bearer ya29.secret"),
+            "Authorization: Bearer ***"
+        );
+        assert_eq!(
+            redact("GET /token?access_token=abc&alt=json"),
+            "GET /token?access_token=***&alt=json"
+        );
+        assert_eq!(redact("status 503"), "status 503");
+    }
+
+    #[test]
+    fn test_export_report_is_redacted() {
+        let log = DiagnosticLog::new(10);
+        log.record(DiagnosticKind::Api, Some(2), "401 for carol@example.com");
+
+        let report = log.export_report();
+        assert!(report.contains("[api] account=2 401 for ***@example.com"));
+        assert!(!report.contains("carol@"));
+    }
+}
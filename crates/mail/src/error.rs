@@ -0,0 +1,188 @@
+//! Error kinds callers can act on
+//!
+//! Most of the crate returns `anyhow::Error`, with typed errors at the
+//! sources: Gmail's status codes, SQLite's result codes, and the crate's own
+//! markers such as [`ReauthRequiredError`]. [`MailError`] turns any of those
+//! into a kind the UI or FFI can branch on - sign in again, wait, retry,
+//! resync - without matching on error messages. Code that knows the kind
+//! when it fails (a missing account, say) raises a `MailError` directly.
+
+use std::time::Duration;
+
+use crate::gmail::{HistoryExpiredError, ReauthRequiredError};
+
+/// Result with a classified error
+pub type MailResult<T> = std::result::Result<T, MailError>;
+
+/// Kind of failure
+///
+/// Convert an `anyhow::Error` with `MailError::from` to classify it.
+#[derive(Debug, thiserror::Error)]
+pub enum MailError {
+    /// The account has to be signed in again
+    #[error("Authentication required: {0}")]
+    Auth(String),
+
+    /// Gmail is throttling the account
+    ///
+    /// `retry_after` is set when Gmail said how long to wait.
+    #[error("Rate limited by Gmail")]
+    RateLimited { retry_after: Option<Duration> },
+
+    /// Gmail couldn't be reached, or failed in a way worth retrying
+    #[error("Network error: {0}")]
+    Network(String),
+
+    /// The database, blob store or file system failed
+    #[error("Storage error: {0}")]
+    Storage(String),
+
+    /// Stored or downloaded data couldn't be read back
+    #[error("Corrupt data: {0}")]
+    Corruption(String),
+
+    /// Gmail no longer has the history an incremental sync needs; resync
+    #[error("Sync history expired")]
+    HistoryExpired,
+
+    /// The requested item doesn't exist, e.g. "Thread abc"
+    #[error("{0} not found")]
+    NotFound(String),
+
+    /// Anything else, e.g. a request Gmail rejected as invalid
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl MailError {
+    /// Whether the same call may succeed later without user action
+    pub fn is_transient(&self) -> bool {
+        matches!(self, MailError::RateLimited { .. } | MailError::Network(_))
+    }
+}
+
+impl From<anyhow::Error> for MailError {
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<MailError>() {
+            Ok(classified) => return classified,
+            Err(error) => error,
+        };
+        classify(&error).unwrap_or(MailError::Other(error))
+    }
+}
+
+/// Kind of the first cause in `error`'s chain that has a known kind
+fn classify(error: &anyhow::Error) -> Option<MailError> {
+    let message = format!("{:#}", error);
+    error.chain().find_map(|cause| {
+        if cause.is::<ReauthRequiredError>() {
+            Some(MailError::Auth(message.clone()))
+        } else if cause.is::<HistoryExpiredError>() {
+            Some(MailError::HistoryExpired)
+        } else if let Some(e) = cause.downcast_ref::<ureq::Error>() {
+            classify_http(e, &message)
+        } else if let Some(e) = cause.downcast_ref::<rusqlite::Error>() {
+            Some(classify_sqlite(e, &message))
+        } else if cause.is::<serde_json::Error>() || cause.is::<base64::DecodeError>() {
+            Some(MailError::Corruption(message.clone()))
+        } else if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            Some(MailError::Storage(format!("{} ({})", message, e.kind())))
+        } else {
+            None
+        }
+    })
+}
+
+/// Kind of a failed Gmail request
+///
+/// Gmail reports quota exhaustion as 403 with a rate limit reason, which
+/// only shows in the message.
+fn classify_http(error: &ureq::Error, message: &str) -> Option<MailError> {
+    let kind = match error {
+        ureq::Error::StatusCode(401) => MailError::Auth(message.to_string()),
+        ureq::Error::StatusCode(429) => MailError::RateLimited { retry_after: None },
+        ureq::Error::StatusCode(403) if is_quota_message(message) => {
+            MailError::RateLimited { retry_after: None }
+        }
+        ureq::Error::StatusCode(404) => MailError::NotFound(message.to_string()),
+        ureq::Error::StatusCode(408 | 500 | 502 | 503 | 504) => {
+            MailError::Network(message.to_string())
+        }
+        ureq::Error::StatusCode(_) => return None,
+        ureq::Error::Json(_) => MailError::Corruption(message.to_string()),
+        _ => MailError::Network(message.to_string()),
+    };
+    Some(kind)
+}
+
+/// Whether a Gmail error message reports an exhausted quota
+pub(crate) fn is_quota_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("quota") || message.contains("rate limit")
+}
+
+/// Kind of a failed database call
+fn classify_sqlite(error: &rusqlite::Error, message: &str) -> MailError {
+    use rusqlite::ErrorCode;
+
+    match error.sqlite_error_code() {
+        Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase) => {
+            MailError::Corruption(message.to_string())
+        }
+        _ if matches!(error, rusqlite::Error::QueryReturnedNoRows) => {
+            MailError::NotFound(message.to_string())
+        }
+        _ => MailError::Storage(message.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_classify_sources() {
+        let auth: anyhow::Error = anyhow::Error::new(ReauthRequiredError).context("Sync failed");
+        assert!(matches!(MailError::from(auth), MailError::Auth(_)));
+
+        let expired = anyhow::Error::new(HistoryExpiredError);
+        assert!(matches!(MailError::from(expired), MailError::HistoryExpired));
+
+        let throttled = anyhow::Error::new(ureq::Error::StatusCode(429));
+        let throttled = MailError::from(throttled);
+        assert!(matches!(throttled, MailError::RateLimited { .. }));
+        assert!(throttled.is_transient());
+
+        let quota = anyhow::Error::new(ureq::Error::StatusCode(403))
+            .context("API error 403: Quota exceeded for quota metric 'Queries'");
+        assert!(matches!(MailError::from(quota), MailError::RateLimited { .. }));
+
+        let forbidden = anyhow::Error::new(ureq::Error::StatusCode(403)).context("Forbidden");
+        assert!(matches!(MailError::from(forbidden), MailError::Other(_)));
+
+        let offline = anyhow::Error::new(ureq::Error::ConnectionFailed);
+        assert!(MailError::from(offline).is_transient());
+
+        let corrupt = rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CORRUPT),
+            None,
+        );
+        let corrupt = Err::<(), _>(corrupt).context("Failed to list threads").unwrap_err();
+        assert!(matches!(MailError::from(corrupt), MailError::Corruption(_)));
+    }
+
+    #[test]
+    fn test_classified_errors_pass_through() {
+        let missing: anyhow::Error = MailError::NotFound("Thread t1".to_string()).into();
+        let missing = missing.context("Failed to export thread");
+        match MailError::from(missing) {
+            MailError::NotFound(resource) => assert_eq!(resource, "Thread t1"),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let other = MailError::from(anyhow::anyhow!("Invite has no organizer"));
+        assert_eq!(other.to_string(), "Invite has no organizer");
+        assert!(!other.is_transient());
+    }
+}
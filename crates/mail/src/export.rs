@@ -0,0 +1,524 @@
+//! Thread export to PDF
+//!
+//! Renders a thread as a plain printable document, black text on white
+//! pages whatever theme the app shows: the subject, then each message's
+//! headers and body. HTML bodies are reduced to text, dropping images and
+//! styling. The PDF uses the standard Helvetica fonts, so no font files are
+//! embedded, and characters those fonts lack print as "?".
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::Local;
+
+use crate::error::MailError;
+use crate::models::{Message, ThreadId};
+use crate::query::get_thread_detail;
+use crate::storage::MailStore;
+
+/// US Letter, in points
+const PAGE_WIDTH: f32 = 612.0;
+const PAGE_HEIGHT: f32 = 792.0;
+const MARGIN: f32 = 54.0;
+
+/// Line height as a multiple of the font size
+const LINE_SPACING: f32 = 1.35;
+
+/// Average Helvetica glyph width as a fraction of the font size, used to
+/// wrap lines without font metrics; slightly generous so lines never spill
+const AVERAGE_GLYPH_WIDTH: f32 = 0.52;
+
+const TITLE_SIZE: f32 = 15.0;
+const SENDER_SIZE: f32 = 11.0;
+const HEADER_SIZE: f32 = 9.0;
+const BODY_SIZE: f32 = 10.0;
+
+/// HTML elements whose content is never shown
+const HIDDEN_ELEMENTS: &[&str] = &["head", "script", "style", "title"];
+
+/// Export a stored thread to a PDF file at `path`
+pub fn export_thread_pdf(store: &dyn MailStore, thread_id: &ThreadId, path: &Path) -> Result<()> {
+    let Some(detail) = get_thread_detail(store, thread_id)? else {
+        return Err(MailError::NotFound(format!("Thread {}", thread_id.as_str())).into());
+    };
+    let pdf = thread_pdf(&detail.thread.subject, &detail.messages);
+    std::fs::write(path, pdf).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Render a thread's messages as a PDF document
+pub fn thread_pdf(subject: &str, messages: &[Message]) -> Vec<u8> {
+    let mut layout = Layout::default();
+    let subject = if subject.trim().is_empty() {
+        "(no subject)"
+    } else {
+        subject
+    };
+    layout.paragraph(Font::Bold, TITLE_SIZE, subject);
+    layout.gap(TITLE_SIZE);
+
+    for (i, message) in messages.iter().enumerate() {
+        if i > 0 {
+            layout.rule();
+        }
+        let sender = message.from.name.as_deref().unwrap_or(&message.from.email);
+        layout.paragraph(Font::Bold, SENDER_SIZE, sender);
+        layout.header("From", &message.from.display());
+        if !message.to.is_empty() {
+            let to: Vec<String> = message.to.iter().map(|a| a.display()).collect();
+            layout.header("To", &to.join(", "));
+        }
+        if !message.cc.is_empty() {
+            let cc: Vec<String> = message.cc.iter().map(|a| a.display()).collect();
+            layout.header("Cc", &cc.join(", "));
+        }
+        let date = message.received_at.with_timezone(&Local);
+        layout.header("Date", &date.format("%b %d, %Y at %H:%M").to_string());
+        if !message.attachment_names.is_empty() {
+            layout.header("Attachments", &message.attachment_names.join(", "));
+        }
+        layout.gap(BODY_SIZE);
+        layout.paragraph(Font::Regular, BODY_SIZE, &body_text(message));
+    }
+
+    layout.into_pdf()
+}
+
+/// Text to print for a message body, converting HTML when there's no
+/// plain text part
+fn body_text(message: &Message) -> String {
+    if let Some(text) = message.body_text.as_ref().filter(|t| !t.trim().is_empty()) {
+        return text.clone();
+    }
+    match message.body_html.as_ref().filter(|h| !h.trim().is_empty()) {
+        Some(html) => html_to_text(html),
+        None => message.body_preview.clone(),
+    }
+}
+
+/// Reduce HTML to readable plain text
+///
+/// Block elements and `<br>` become line breaks, list items get a dash,
+/// everything else is stripped and whitespace collapsed like a browser
+/// would.
+fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len() / 2);
+    let mut rest = html;
+    // Set while inside a hidden element, to the element's name
+    let mut skip_until: Option<&str> = None;
+
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            if skip_until.is_none() {
+                push_text(&mut text, rest);
+            }
+            break;
+        };
+        if skip_until.is_none() {
+            push_text(&mut text, &rest[..start]);
+        }
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim().to_ascii_lowercase();
+        rest = &rest[start + end + 1..];
+
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        if let Some(until) = skip_until {
+            if closing && name == until {
+                skip_until = None;
+            }
+            continue;
+        }
+        if !closing && let Some(hidden) = HIDDEN_ELEMENTS.iter().find(|e| **e == name) {
+            skip_until = Some(hidden);
+            continue;
+        }
+        match name.as_str() {
+            "br" => text.push('\n'),
+            "li" if !closing => {
+                line_break(&mut text);
+                text.push_str("- ");
+            }
+            "p" | "div" | "tr" | "table" | "ul" | "ol" | "blockquote" | "pre" | "hr" | "h1"
+            | "h2" | "h3" | "h4" | "h5" | "h6" | "li" => line_break(&mut text),
+            "td" | "th" if closing => text.push(' '),
+            _ => {}
+        }
+    }
+
+    // Trim each line and allow at most one blank line in a row
+    let mut out = String::with_capacity(text.len());
+    let mut blank_lines = 0;
+    for line in decode_entities(&text).lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            blank_lines += 1;
+            if blank_lines > 1 || out.is_empty() {
+                continue;
+            }
+        } else {
+            blank_lines = 0;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.trim_end().to_string()
+}
+
+/// Append HTML text content, collapsing whitespace runs to one space
+fn push_text(text: &mut String, content: &str) {
+    for c in content.chars() {
+        if c.is_whitespace() {
+            if !text.ends_with([' ', '\n']) && !text.is_empty() {
+                text.push(' ');
+            }
+        } else {
+            text.push(c);
+        }
+    }
+}
+
+/// Start a new line unless already at the start of one
+fn line_break(text: &mut String) {
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+/// Decode the common named entities and all numeric ones
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .map(|end| &rest[1..end + 1]);
+        let decoded = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => {
+                let number = entity.strip_prefix('#')?;
+                let code = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => number.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+        match (entity, decoded) {
+            (Some(entity), Some(c)) => {
+                out.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    /// Resource name in each page's font dictionary
+    fn resource(self) -> &'static str {
+        match self {
+            Self::Regular => "F1",
+            Self::Bold => "F2",
+        }
+    }
+}
+
+/// Lays text out top to bottom, starting new pages as they fill
+struct Layout {
+    /// Content stream of each finished page, then the current one
+    pages: Vec<String>,
+    /// Baseline of the next line
+    y: f32,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            pages: vec![String::new()],
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+}
+
+impl Layout {
+    /// Add text wrapped to the page width; newlines start new lines
+    fn paragraph(&mut self, font: Font, size: f32, text: &str) {
+        let max_chars = ((PAGE_WIDTH - 2.0 * MARGIN) / (size * AVERAGE_GLYPH_WIDTH)) as usize;
+        for line in text.lines() {
+            let wrapped = wrap(line, max_chars);
+            if wrapped.is_empty() {
+                self.gap(size);
+            }
+            for line in wrapped {
+                self.line(font, size, &line);
+            }
+        }
+    }
+
+    /// Add a "Label: value" message header line
+    fn header(&mut self, label: &str, value: &str) {
+        self.paragraph(Font::Regular, HEADER_SIZE, &format!("{}: {}", label, value));
+    }
+
+    /// Leave an empty line's worth of space
+    fn gap(&mut self, size: f32) {
+        self.y -= size * LINE_SPACING;
+    }
+
+    /// Draw a thin separator between messages
+    fn rule(&mut self) {
+        self.gap(BODY_SIZE);
+        self.ensure_room(BODY_SIZE * 2.0);
+        let y = self.y + BODY_SIZE;
+        let page = self.pages.last_mut().expect("layout always has a page");
+        let _ = writeln!(
+            page,
+            "0.8 G 0.5 w {} {:.1} m {} {:.1} l S 0 G",
+            MARGIN,
+            y,
+            PAGE_WIDTH - MARGIN,
+            y
+        );
+        self.gap(BODY_SIZE);
+    }
+
+    fn line(&mut self, font: Font, size: f32, text: &str) {
+        let height = size * LINE_SPACING;
+        self.ensure_room(height);
+        self.y -= height;
+        let page = self.pages.last_mut().expect("layout always has a page");
+        let _ = writeln!(
+            page,
+            "BT /{} {} Tf {} {:.1} Td ({}) Tj ET",
+            font.resource(),
+            size,
+            MARGIN,
+            self.y,
+            pdf_string(text)
+        );
+    }
+
+    /// Start a new page unless `height` more fits on this one
+    fn ensure_room(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.pages.push(String::new());
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+
+    /// Assemble the finished PDF file
+    fn into_pdf(self) -> Vec<u8> {
+        // Objects: 1 catalog, 2 page tree, 3-4 fonts, then a page and its
+        // content stream for each page
+        let page_ids: Vec<usize> = (0..self.pages.len()).map(|i| 5 + i * 2).collect();
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                page_ids
+                    .iter()
+                    .map(|id| format!("{} 0 R", id))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                page_ids.len()
+            ),
+            font_object("Helvetica"),
+            font_object("Helvetica-Bold"),
+        ];
+        for (page, id) in self.pages.iter().zip(&page_ids) {
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                id + 1
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                page.len(),
+                page
+            ));
+        }
+
+        let mut pdf = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            let _ = write!(pdf, "{} 0 obj\n{}\nendobj\n", i + 1, object);
+        }
+        let xref_offset = pdf.len();
+        let _ = write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = write!(pdf, "{:010} 00000 n \n", offset);
+        }
+        let _ = write!(
+            pdf,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        );
+        pdf.into_bytes()
+    }
+}
+
+fn font_object(base_font: &str) -> String {
+    format!(
+        "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+        base_font
+    )
+}
+
+/// Wrap a line at word boundaries to at most `max_chars` characters,
+/// breaking words that are longer than a whole line
+fn wrap(line: &str, max_chars: usize) -> Vec<String> {
+    let max_chars = max_chars.max(1);
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let mut current_len = 0;
+    for word in line.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        while word.len() > max_chars {
+            if current_len > 0 {
+                lines.push(std::mem::take(&mut current));
+                current_len = 0;
+            }
+            lines.push(word.drain(..max_chars).collect());
+        }
+        if current_len > 0 && current_len + 1 + word.len() > max_chars {
+            lines.push(std::mem::take(&mut current));
+            current_len = 0;
+        }
+        if current_len > 0 {
+            current.push(' ');
+            current_len += 1;
+        }
+        current_len += word.len();
+        current.extend(word);
+    }
+    if current_len > 0 {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Encode text as the body of a PDF literal string in WinAnsiEncoding
+///
+/// The output is plain ASCII: non-ASCII bytes become octal escapes.
+fn pdf_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        let byte = match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+                continue;
+            }
+            '\t' => b' ',
+            ' '..='~' => c as u8,
+            '\u{a0}'..='\u{ff}' => c as u32 as u8,
+            '‘' | '’' => b'\'',
+            '“' | '”' => b'"',
+            '–' => 0x96,
+            '—' => 0x97,
+            '•' => 0x95,
+            '…' => 0x85,
+            '€' => 0x80,
+            _ => b'?',
+        };
+        if byte.is_ascii() {
+            out.push(byte as char);
+        } else {
+            let _ = write!(out, "\\{:03o}", byte);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EmailAddress, MessageId};
+
+    #[test]
+    fn test_html_to_text() {
+        let html = "<html><head><style>p { color: red; }</style></head><body>\
+                    <p>Hello &amp; welcome,\n   friend</p><ul><li>One</li><li>Two</li></ul>\
+                    Line<br>break &#8211; &#x41;&nbsp;&unknown;</body></html>";
+        assert_eq!(
+            html_to_text(html),
+            "Hello & welcome, friend\n- One\n- Two\nLine\nbreak – A &unknown;"
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("the quick brown fox", 9),
+            vec!["the quick", "brown fox"]
+        );
+        assert_eq!(wrap("abcdefghij xy", 4), vec!["abcd", "efgh", "ij", "xy"]);
+        assert!(wrap("   ", 10).is_empty());
+    }
+
+    #[test]
+    fn test_pdf_string() {
+        assert_eq!(pdf_string("a (b) \\ c"), "a \\(b\\) \\\\ c");
+        assert_eq!(pdf_string("café – ok"), "caf\\351 \\226 ok");
+        assert_eq!(pdf_string("日本"), "??");
+    }
+
+    #[test]
+    fn test_thread_pdf() {
+        let message = Message::builder(MessageId::new("m1"), ThreadId::new("t1"))
+            .from(EmailAddress::with_name("Ana", "ana@example.com"))
+            .subject("Lunch")
+            .body_text(Some("Noon?\n".repeat(200)))
+            .build();
+        let pdf = String::from_utf8(thread_pdf("Lunch", &[message])).unwrap();
+
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        assert!(pdf.contains("(Lunch) Tj"));
+        assert!(pdf.contains("(From: Ana <ana@example.com>) Tj"));
+        // 200 body lines don't fit on one page
+        assert!(!pdf.contains("/Count 1 "));
+
+        // Every xref entry points at the object it names
+        let xref = pdf.rfind("xref\n").unwrap();
+        for (i, entry) in pdf[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|l| l.ends_with(" n "))
+            .enumerate()
+        {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+    }
+}
@@ -33,13 +33,29 @@
 //!     clientSecret: clientSecret,
 //!     callback: progressCallback
 //! )
+//!
+//! // Or sync without blocking, with the option to cancel
+//! let cancellation = CancellationToken()
+//! let stats = try await service.syncAccountAsync(
+//!     accountId: 1,
+//!     tokenJson: tokenJson,
+//!     clientId: clientId,
+//!     clientSecret: clientSecret,
+//!     listener: progressListener,
+//!     cancellation: cancellation
+//! )
+//!
+//! // Reload views as the store changes; keep the subscription alive
+//! let subscription = service.observeStore(observer: storeObserver)
 //! ```
 
 mod logging;
+mod observer;
 mod service;
 mod types;
 
 // Re-export all FFI types and the MailService
 pub use logging::{init_ffi_logger, set_log_callback, set_log_level};
+pub use observer::*;
 pub use service::*;
 pub use types::*;
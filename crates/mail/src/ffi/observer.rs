@@ -0,0 +1,223 @@
+//! Store change notifications for foreign observers
+//!
+//! Forwards `StoreEvent`s to a `StoreObserver` on a background thread. After
+//! each burst of events the affected accounts' inbox unread counts are
+//! recounted once, and reported only when they actually moved, so badge
+//! views don't have to recount after every change.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use log::warn;
+
+use crate::storage::{MailStore, SqliteMailStore, StoreEvent};
+
+/// How often the forwarding thread checks whether it was cancelled
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Label whose unread count is reported
+const UNREAD_COUNT_LABEL: &str = "INBOX";
+
+/// FFI-friendly store change
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiStoreEvent {
+    /// A thread was inserted or its metadata changed
+    ThreadUpserted { thread_id: String, account_id: i64 },
+    /// A thread was removed (its last message was deleted)
+    ThreadRemoved { thread_id: String },
+    /// Labels on a message changed
+    LabelsChanged {
+        thread_id: String,
+        message_id: String,
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+    /// Mail was cleared in bulk; reload everything for the account, or for
+    /// every account when `account_id` is None
+    Cleared { account_id: Option<i64> },
+    /// An account's count of unread inbox threads changed
+    UnreadCountChanged { account_id: i64, count: u32 },
+}
+
+impl From<StoreEvent> for FfiStoreEvent {
+    fn from(e: StoreEvent) -> Self {
+        match e {
+            StoreEvent::ThreadUpserted {
+                thread_id,
+                account_id,
+            } => Self::ThreadUpserted {
+                thread_id: thread_id.0,
+                account_id,
+            },
+            StoreEvent::ThreadRemoved { thread_id } => Self::ThreadRemoved {
+                thread_id: thread_id.0,
+            },
+            StoreEvent::LabelChanged {
+                thread_id,
+                message_id,
+                added,
+                removed,
+            } => Self::LabelsChanged {
+                thread_id: thread_id.0,
+                message_id: message_id.0,
+                added,
+                removed,
+            },
+            StoreEvent::Cleared { account_id } => Self::Cleared { account_id },
+        }
+    }
+}
+
+/// Callback interface for store changes
+///
+/// Called on a background thread; hop to the main thread before updating UI.
+#[uniffi::export(callback_interface)]
+pub trait StoreObserver: Send + Sync {
+    fn on_event(&self, event: FfiStoreEvent);
+}
+
+/// Handle keeping a store observer registered
+///
+/// Events stop once `cancel()` is called or the handle is released.
+#[derive(uniffi::Object)]
+pub struct StoreSubscription {
+    cancelled: Arc<AtomicBool>,
+}
+
+#[uniffi::export]
+impl StoreSubscription {
+    /// Stop delivering events
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+impl StoreSubscription {
+    /// Start forwarding events from `store` to `observer`
+    pub(crate) fn start(store: Arc<SqliteMailStore>, observer: Box<dyn StoreObserver>) -> Arc<Self> {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let events = store.subscribe();
+        let flag = cancelled.clone();
+        std::thread::spawn(move || {
+            forward_events(store.as_ref(), &events, observer.as_ref(), &flag);
+        });
+        Arc::new(Self { cancelled })
+    }
+}
+
+impl Drop for StoreSubscription {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Accounts whose unread count may have changed during a burst
+#[derive(Default)]
+struct Recount {
+    accounts: HashSet<i64>,
+    /// Recount every account (the event didn't say which)
+    all: bool,
+}
+
+impl Recount {
+    fn note(&mut self, store: &dyn MailStore, event: &StoreEvent) {
+        let account_id = match event {
+            StoreEvent::ThreadUpserted { account_id, .. } => Some(*account_id),
+            StoreEvent::ThreadRemoved { .. } => None,
+            StoreEvent::LabelChanged {
+                thread_id,
+                added,
+                removed,
+                ..
+            } => {
+                let touches_count = added
+                    .iter()
+                    .chain(removed)
+                    .any(|label| label == "UNREAD" || label == UNREAD_COUNT_LABEL);
+                if !touches_count {
+                    return;
+                }
+                store
+                    .get_thread(thread_id)
+                    .ok()
+                    .flatten()
+                    .map(|thread| thread.account_id)
+            }
+            StoreEvent::Cleared { account_id } => *account_id,
+        };
+        match account_id {
+            Some(account_id) => {
+                self.accounts.insert(account_id);
+            }
+            None => self.all = true,
+        }
+    }
+}
+
+/// Deliver events until cancelled or the store goes away
+fn forward_events(
+    store: &dyn MailStore,
+    events: &Receiver<StoreEvent>,
+    observer: &dyn StoreObserver,
+    cancelled: &AtomicBool,
+) {
+    // Seed the counts so the first burst only reports real changes
+    let everything = Recount {
+        all: true,
+        ..Default::default()
+    };
+    let mut unread_counts = HashMap::new();
+    update_unread_counts(store, &everything, &mut unread_counts, |_, _| {});
+
+    while !cancelled.load(Ordering::SeqCst) {
+        let first = match events.recv_timeout(CANCEL_POLL_INTERVAL) {
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        let mut recount = Recount::default();
+        for event in std::iter::once(first).chain(events.try_iter()) {
+            recount.note(store, &event);
+            observer.on_event(event.into());
+        }
+        update_unread_counts(store, &recount, &mut unread_counts, |account_id, count| {
+            observer.on_event(FfiStoreEvent::UnreadCountChanged { account_id, count });
+        });
+    }
+}
+
+/// Recount unread threads, calling `changed` for counts that moved
+fn update_unread_counts(
+    store: &dyn MailStore,
+    recount: &Recount,
+    counts: &mut HashMap<i64, u32>,
+    mut changed: impl FnMut(i64, u32),
+) {
+    let account_ids: Vec<i64> = if recount.all {
+        match store.list_accounts() {
+            Ok(accounts) => accounts.into_iter().map(|account| account.id).collect(),
+            Err(e) => {
+                warn!("Failed to list accounts for unread counts: {}", e);
+                return;
+            }
+        }
+    } else {
+        recount.accounts.iter().copied().collect()
+    };
+
+    for account_id in account_ids {
+        match store.count_unread_threads_by_label_for_account(UNREAD_COUNT_LABEL, Some(account_id)) {
+            Ok(count) => {
+                let count = count as u32;
+                if counts.insert(account_id, count) != Some(count) {
+                    changed(account_id, count);
+                }
+            }
+            Err(e) => warn!("Failed to count unread threads for account {}: {}", account_id, e),
+        }
+    }
+}
@@ -6,12 +6,41 @@
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use crate::ffi::observer::{StoreObserver, StoreSubscription};
 use crate::ffi::types::*;
-use crate::gmail::{GmailAuth, GmailClient, StoredToken};
-use crate::models::{Account, ThreadId};
-use crate::search::SearchIndex;
+use crate::gmail::{GmailAuth, GmailClient, ReauthRequiredError, StoredToken};
+use crate::models::{Account, AccountSettings, MessageId, Signature, ThreadId};
+use crate::search::{SearchBackend, SearchConfig, SearchIndex};
 use crate::storage::{FileBlobStore, MailStore, SqliteMailStore};
-use crate::sync::SyncOptions;
+use crate::sync::{SyncCancel, SyncCancelledError, SyncOptions};
+
+/// Handle for cancelling an in-flight async sync
+///
+/// Create one per sync and pass it to `sync_account_async`; calling
+/// `cancel()` stops the sync at its next page or batch, and the call then
+/// fails with `MailError::Cancelled`. Progress made so far is kept, so the
+/// next sync resumes where this one stopped.
+#[derive(uniffi::Object, Default)]
+pub struct CancellationToken {
+    flag: SyncCancel,
+}
+
+#[uniffi::export]
+impl CancellationToken {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Ask the sync to stop
+    pub fn cancel(&self) {
+        self.flag.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.is_cancelled()
+    }
+}
 
 /// Main service object for mail operations
 ///
@@ -65,6 +94,7 @@ impl MailService {
         let search_index = SearchIndex::open(&search_index_path).map_err(|e| MailError::Database {
             message: format!("Failed to open search index: {}", e),
         })?;
+        let search_index = search_index.with_config(SearchConfig::load());
 
         Ok(Arc::new(Self {
             store: Arc::new(store),
@@ -104,11 +134,90 @@ impl MailService {
     }
 
     /// Delete an account and all its data
+    ///
+    /// Also removes its search documents and revokes its OAuth token.
     pub fn delete_account(&self, account_id: i64) -> Result<(), MailError> {
-        self.store.delete_account(account_id)?;
+        let search: &dyn SearchBackend = self.search_index.as_ref();
+        crate::accounts::remove_account(self.store.as_ref(), Some(search), account_id)?;
         Ok(())
     }
 
+    /// Update an account's display name, avatar color and signature
+    ///
+    /// An HTML signature is ignored without a plain text one.
+    pub fn update_account_settings(
+        &self,
+        account_id: i64,
+        display_name: Option<String>,
+        avatar_color: String,
+        signature_text: Option<String>,
+        signature_html: Option<String>,
+    ) -> Result<(), MailError> {
+        let settings = AccountSettings {
+            display_name,
+            avatar_color,
+            signature: signature_text.map(|text| Signature {
+                text,
+                html: signature_html,
+            }),
+        };
+        self.store.update_account_settings(account_id, settings)?;
+        Ok(())
+    }
+
+    /// List an account's stored send-as aliases, default first
+    pub fn list_send_as_aliases(&self, account_id: i64) -> Result<Vec<FfiSendAsAlias>, MailError> {
+        let aliases = self.store.list_send_as_aliases(account_id)?;
+        Ok(aliases.into_iter().map(FfiSendAsAlias::from).collect())
+    }
+
+    /// Fetch an account's send-as aliases from Gmail and store them
+    ///
+    /// # Arguments
+    /// * `account_id` - The account whose aliases to fetch
+    /// * `token_json` - JSON-serialized token with access_token, refresh_token, expires_at
+    /// * `client_id` - OAuth client ID
+    /// * `client_secret` - OAuth client secret
+    pub fn refresh_send_as_aliases(
+        &self,
+        account_id: i64,
+        token_json: String,
+        client_id: String,
+        client_secret: String,
+    ) -> Result<Vec<FfiSendAsAlias>, MailError> {
+        let auth = GmailAuth::with_token_data(client_id, client_secret, Some(token_json));
+        let gmail = GmailClient::new(auth);
+        let aliases =
+            crate::accounts::refresh_send_as_aliases(&gmail, self.store.as_ref(), account_id)?;
+        Ok(aliases.into_iter().map(FfiSendAsAlias::from).collect())
+    }
+
+    /// Mirror an account's Gmail filters as local rules
+    ///
+    /// # Arguments
+    /// * `account_id` - The account whose filters to import
+    /// * `token_json` - JSON-serialized token with access_token, refresh_token, expires_at
+    /// * `client_id` - OAuth client ID
+    /// * `client_secret` - OAuth client secret
+    pub fn import_gmail_filters(
+        &self,
+        account_id: i64,
+        token_json: String,
+        client_id: String,
+        client_secret: String,
+    ) -> Result<FfiFilterImport, MailError> {
+        let auth = GmailAuth::with_token_data(client_id, client_secret, Some(token_json));
+        let gmail = GmailClient::new(auth);
+        let diff = crate::filters::import_gmail_filters(&gmail, self.store.as_ref(), account_id)?;
+        Ok(diff.into())
+    }
+
+    /// Get whether an account can sync or needs to be signed in again
+    pub fn account_health(&self, account_id: i64) -> Result<FfiAccountHealth, MailError> {
+        let health = crate::accounts::account_health(self.store.as_ref(), account_id)?;
+        Ok(health.into())
+    }
+
     /// Update the OAuth token for an account
     ///
     /// The token_json should be a JSON-serialized token object.
@@ -168,6 +277,146 @@ impl MailService {
         Ok(detail.map(FfiThreadDetail::from))
     }
 
+    /// Get a message's full body
+    ///
+    /// Returns the plain text and HTML bodies along with a sanitized copy of
+    /// the HTML for rendering, or None if the message isn't stored.
+    pub fn get_message_body(&self, message_id: String) -> Result<Option<FfiMessageBody>, MailError> {
+        let body = self.store.get_message_body(&MessageId::new(message_id))?;
+        Ok(body.map(FfiMessageBody::from))
+    }
+
+    /// Set the user's private note on a thread; an empty note removes it
+    pub fn set_thread_note(&self, thread_id: String, markdown: String) -> Result<(), MailError> {
+        crate::query::set_thread_note(
+            self.store.as_ref(),
+            Some(self.search_index.as_ref()),
+            &ThreadId::new(thread_id),
+            &markdown,
+        )?;
+        Ok(())
+    }
+
+    /// Fetch new opens of tracked sent messages from the tracking endpoint
+    ///
+    /// Returns the number of opens recorded. Does nothing unless open
+    /// tracking is enabled in `mail.tracking.json`.
+    pub fn sync_message_opens(&self) -> Result<u32, MailError> {
+        let config = crate::tracking::TrackingConfig::load();
+        if !config.is_active() {
+            return Ok(0);
+        }
+        let recorded = crate::tracking::sync_opens(self.store.as_ref(), &config)?;
+        Ok(recorded as u32)
+    }
+
+    /// List sent threads with no reply for at least `after_days` days
+    ///
+    /// Dismissed and snoozed reminders are left out.
+    pub fn list_followup_candidates(
+        &self,
+        account_id: Option<i64>,
+        after_days: u32,
+    ) -> Result<Vec<FfiFollowupCandidate>, MailError> {
+        let candidates =
+            crate::query::list_followup_candidates(self.store.as_ref(), account_id, after_days)?;
+        Ok(candidates
+            .into_iter()
+            .map(FfiFollowupCandidate::from)
+            .collect())
+    }
+
+    /// Dismiss a thread's follow-up reminder until the user sends again
+    pub fn dismiss_followup(&self, thread_id: String) -> Result<(), MailError> {
+        crate::query::dismiss_followup(self.store.as_ref(), &ThreadId::new(thread_id))?;
+        Ok(())
+    }
+
+    /// Hide a thread's follow-up reminder until a Unix timestamp
+    pub fn snooze_followup(&self, thread_id: String, until: i64) -> Result<(), MailError> {
+        let until =
+            chrono::DateTime::from_timestamp(until, 0).ok_or_else(|| MailError::InvalidArgument {
+                message: format!("Invalid timestamp {}", until),
+            })?;
+        crate::query::snooze_followup(self.store.as_ref(), &ThreadId::new(thread_id), until)?;
+        Ok(())
+    }
+
+    /// Get a one-paragraph summary of a thread
+    ///
+    /// Summaries are cached until the thread gains or loses messages, so
+    /// `summarizer` is only called when the thread has changed.
+    pub fn get_thread_summary(
+        &self,
+        thread_id: String,
+        summarizer: Box<dyn SummarizerCallback>,
+    ) -> Result<Option<String>, MailError> {
+        let summarizer = CallbackSummarizer(summarizer);
+        let summary = crate::query::get_thread_summary(
+            self.store.as_ref(),
+            &summarizer,
+            &ThreadId::new(thread_id),
+        )?;
+        Ok(summary)
+    }
+
+    /// Suggest short replies to the latest message in a thread
+    ///
+    /// Returns an empty list if the latest message was sent by the user.
+    pub fn suggest_replies(
+        &self,
+        thread_id: String,
+        suggester: Box<dyn ReplySuggesterCallback>,
+    ) -> Result<Vec<String>, MailError> {
+        let suggester = CallbackReplySuggester(suggester);
+        let replies = crate::query::suggest_replies(
+            self.store.as_ref(),
+            &suggester,
+            &ThreadId::new(thread_id),
+        )?;
+        Ok(replies)
+    }
+
+    /// Get a message's body translated into `target_lang` (ISO 639-3)
+    ///
+    /// Translations are cached, so `translator` is only called the first time
+    /// a body is requested in a language.
+    pub fn get_translated_body(
+        &self,
+        message_id: String,
+        target_lang: String,
+        translator: Box<dyn TranslatorCallback>,
+    ) -> Result<Option<FfiTranslatedBody>, MailError> {
+        let translator = CallbackTranslator(translator);
+        let body = crate::translate::get_translated_body(
+            self.store.as_ref(),
+            &translator,
+            &MessageId::new(message_id),
+            &target_lang,
+        )?;
+        Ok(body.map(FfiTranslatedBody::from))
+    }
+
+    /// Fetch a message's original RFC 2822 source from Gmail ("Show original")
+    ///
+    /// # Arguments
+    /// * `message_id` - The message to fetch
+    /// * `token_json` - JSON-serialized token with access_token, refresh_token, expires_at
+    /// * `client_id` - OAuth client ID
+    /// * `client_secret` - OAuth client secret
+    pub fn get_original_source(
+        &self,
+        message_id: String,
+        token_json: String,
+        client_id: String,
+        client_secret: String,
+    ) -> Result<String, MailError> {
+        let auth = GmailAuth::with_token_data(client_id, client_secret, Some(token_json));
+        let gmail = GmailClient::new(auth);
+        let source = crate::query::get_original_source(&gmail, &MessageId::new(message_id))?;
+        Ok(source)
+    }
+
     /// Count threads (optionally filtered by label and/or account)
     pub fn count_threads(
         &self,
@@ -203,6 +452,20 @@ impl MailService {
         Ok(count as u32)
     }
 
+    // ========================================================================
+    // Change Notifications
+    // ========================================================================
+
+    /// Observe changes to the store
+    ///
+    /// `observer` is called on a background thread for every committed
+    /// change, and with `UnreadCountChanged` when an account's unread inbox
+    /// count moves. Events stop when the returned subscription is cancelled
+    /// or released.
+    pub fn observe_store(&self, observer: Box<dyn StoreObserver>) -> Arc<StoreSubscription> {
+        StoreSubscription::start(self.store.clone(), observer)
+    }
+
     // ========================================================================
     // Search
     // ========================================================================
@@ -210,21 +473,26 @@ impl MailService {
     /// Search threads by query string
     ///
     /// Supports Gmail-style operators like `from:`, `to:`, `subject:`,
-    /// `is:unread`, `in:inbox`, `before:`, `after:`.
+    /// `is:unread`, `in:inbox`, `before:`, `after:`. Queries are parsed here,
+    /// the same way desktop search parses them, and each result carries the
+    /// highlight spans for its matching fields. Pass `offset` to page through
+    /// results; a page shorter than `limit` is the last one.
     pub fn search(
         &self,
         query: String,
         limit: u32,
+        offset: u32,
         account_id: Option<i64>,
     ) -> Result<Vec<FfiSearchResult>, MailError> {
-        let results = crate::search::search_threads_for_account(
+        let page = crate::search::search_threads_page(
             &self.search_index,
             self.store.as_ref(),
             &query,
+            offset as usize,
             limit as usize,
             account_id,
         )?;
-        Ok(results.into_iter().map(FfiSearchResult::from).collect())
+        Ok(page.results.into_iter().map(FfiSearchResult::from).collect())
     }
 
     // ========================================================================
@@ -267,6 +535,7 @@ impl MailService {
             max_messages: None,
             full_resync: false,
             search_index: Some(self.search_index.clone()),
+            cancel: SyncCancel::default(),
         };
 
         // Notify starting
@@ -287,6 +556,10 @@ impl MailService {
         ).map_err(|e| {
             log::error!("sync_gmail error: {}", e);
             callback.on_error(e.to_string());
+            if e.downcast_ref::<ReauthRequiredError>().is_some() {
+                let _ = crate::accounts::record_auth_failure(self.store.as_ref(), account_id, &e);
+                return MailError::AuthRequired;
+            }
             MailError::Sync {
                 message: e.to_string(),
             }
@@ -304,6 +577,76 @@ impl MailService {
         Ok(FfiSyncStats::from(stats))
     }
 
+    /// Sync an account without blocking the caller
+    ///
+    /// Runs the same sync as `sync_account` on a worker thread and resolves
+    /// when it finishes. `listener` receives structured progress from the
+    /// worker thread; `cancellation` can stop the sync early, in which case
+    /// this fails with `MailError::Cancelled`.
+    pub async fn sync_account_async(
+        &self,
+        account_id: i64,
+        token_json: String,
+        client_id: String,
+        client_secret: String,
+        listener: Box<dyn SyncProgressListener>,
+        cancellation: Arc<CancellationToken>,
+    ) -> Result<FfiSyncStats, MailError> {
+        let store = self.store.clone();
+        let options = SyncOptions {
+            search_index: Some(self.search_index.clone()),
+            cancel: cancellation.flag.clone(),
+            ..Default::default()
+        };
+
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        std::thread::spawn(move || {
+            let auth = GmailAuth::with_token_data(client_id, client_secret, Some(token_json));
+            let gmail = GmailClient::new(auth);
+
+            listener.on_progress(FfiSyncProgress::from_engine(0, "Starting sync..."));
+            let result = crate::sync::sync_gmail_with_progress(
+                &gmail,
+                store.as_ref(),
+                account_id,
+                options,
+                |fetched, phase| listener.on_progress(FfiSyncProgress::from_engine(fetched, phase)),
+            );
+
+            let result = match result {
+                Ok(stats) => {
+                    listener.on_progress(FfiSyncProgress {
+                        phase: FfiSyncPhase::Complete,
+                        messages_fetched: stats.messages_fetched as u32,
+                        messages_total: Some(stats.messages_fetched as u32),
+                        description: "Sync complete".to_string(),
+                    });
+                    Ok(FfiSyncStats::from(stats))
+                }
+                Err(e) if e.downcast_ref::<SyncCancelledError>().is_some() => {
+                    log::info!("Sync for account {} cancelled", account_id);
+                    Err(MailError::Cancelled)
+                }
+                Err(e) if e.downcast_ref::<ReauthRequiredError>().is_some() => {
+                    let _ = crate::accounts::record_auth_failure(store.as_ref(), account_id, &e);
+                    Err(MailError::AuthRequired)
+                }
+                Err(e) => {
+                    log::error!("sync_account_async error: {}", e);
+                    Err(MailError::Sync {
+                        message: e.to_string(),
+                    })
+                }
+            };
+            // The caller may have dropped the future; nothing to report then
+            let _ = sender.send(result);
+        });
+
+        receiver.await.map_err(|_| MailError::Sync {
+            message: "Sync worker exited unexpectedly".to_string(),
+        })?
+    }
+
     /// Perform a full resync, clearing existing data
     pub fn full_resync(
         &self,
@@ -322,6 +665,7 @@ impl MailService {
             max_messages: None,
             full_resync: true,
             search_index: Some(self.search_index.clone()),
+            cancel: SyncCancel::default(),
         };
 
         callback.on_progress(0, None, "Starting full resync...".to_string());
@@ -343,6 +687,50 @@ impl MailService {
         Ok(FfiSyncStats::from(stats))
     }
 
+    /// Run one time-boxed slice of sync
+    ///
+    /// Does as much fetching and processing as fits in `budget_ms`, then
+    /// stops at a checkpoint saved in the database, so short background
+    /// windows (iOS BackgroundTasks) make progress across launches. Work
+    /// can overrun by one page or batch, so ask for less than the system
+    /// grants. Call again while the result isn't `complete`.
+    pub fn sync_step(
+        &self,
+        account_id: i64,
+        budget_ms: u64,
+        token_json: String,
+        client_id: String,
+        client_secret: String,
+    ) -> Result<FfiSyncStep, MailError> {
+        let auth = GmailAuth::with_token_data(client_id, client_secret, Some(token_json));
+        let gmail = GmailClient::new(auth);
+
+        let options = SyncOptions {
+            search_index: Some(self.search_index.clone()),
+            ..Default::default()
+        };
+
+        let step = crate::sync::sync_step(
+            &gmail,
+            self.store.as_ref(),
+            account_id,
+            &options,
+            std::time::Duration::from_millis(budget_ms),
+        )
+        .map_err(|e| {
+            if e.downcast_ref::<ReauthRequiredError>().is_some() {
+                let _ = crate::accounts::record_auth_failure(self.store.as_ref(), account_id, &e);
+                return MailError::AuthRequired;
+            }
+            log::error!("sync_step error: {}", e);
+            MailError::Sync {
+                message: e.to_string(),
+            }
+        })?;
+
+        Ok(FfiSyncStep::from(step))
+    }
+
     // ========================================================================
     // Concurrent Sync (like GPUI)
     // ========================================================================
@@ -374,23 +762,34 @@ impl MailService {
 
         callback.on_progress(0, None, "Fetching messages...".to_string());
 
-        // fetch_phase_with_progress callback is (fetched_count, phase_description)
-        let result = crate::sync::fetch_phase_with_progress(
-            &gmail,
-            self.store.as_ref(),
-            account_id,
-            &options,
-            &mut stats,
-            &|fetched, phase| {
-                callback.on_progress(fetched as u32, None, phase.to_string());
-            }
-        ).map_err(|e| {
-            log::error!("fetch_messages error: {}", e);
-            callback.on_error(e.to_string());
-            MailError::Sync {
-                message: e.to_string(),
+        // fetch_phase_with_progress callback is (fetched_count, phase_description).
+        // process_pending_batch runs alongside, so a fetch that finds the queue
+        // full and stalled just tries again
+        let mut result = crate::sync::FetchPhaseStats::default();
+        loop {
+            let fetched = crate::sync::fetch_phase_with_progress(
+                &gmail,
+                self.store.as_ref(),
+                account_id,
+                &options,
+                &mut stats,
+                &|fetched, phase| {
+                    callback.on_progress(fetched as u32, None, phase.to_string());
+                }
+            ).map_err(|e| {
+                log::error!("fetch_messages error: {}", e);
+                callback.on_error(e.to_string());
+                MailError::Sync {
+                    message: e.to_string(),
+                }
+            })?;
+            result.fetched += fetched.fetched;
+            result.pending += fetched.pending;
+            result.skipped += fetched.skipped;
+            if !fetched.backlogged {
+                break;
             }
-        })?;
+        }
 
         callback.on_progress(result.fetched as u32, None, "Fetch complete".to_string());
 
@@ -450,6 +849,11 @@ impl MailService {
     // ========================================================================
     // Actions
     // ========================================================================
+    //
+    // Label changes (archive, star, read, trash, labels) are applied locally
+    // even when Gmail can't be reached: they fail with `MailError::Queued`
+    // and are sent by the next action or `flush_queued_actions`. Changes
+    // Gmail refuses fail with `MailError::Rejected`.
 
     /// Archive a thread (remove INBOX label)
     pub fn archive_thread(
@@ -458,16 +862,41 @@ impl MailService {
         token_json: String,
         client_id: String,
         client_secret: String,
+    ) -> Result<(), MailError> {
+        let handler = self.action_handler(token_json, client_id, client_secret);
+        handler
+            .archive_thread(&ThreadId::new(thread_id))
+            .map_err(MailError::from_action)
+    }
+
+    /// Move an archived thread back to the inbox
+    pub fn unarchive_thread(
+        &self,
+        thread_id: String,
+        token_json: String,
+        client_id: String,
+        client_secret: String,
+    ) -> Result<(), MailError> {
+        let handler = self.action_handler(token_json, client_id, client_secret);
+        handler
+            .unarchive_thread(&ThreadId::new(thread_id))
+            .map_err(MailError::from_action)
+    }
+
+    /// Answer a calendar invite
+    pub fn rsvp(
+        &self,
+        message_id: String,
+        response: FfiRsvpResponse,
+        token_json: String,
+        client_id: String,
+        client_secret: String,
     ) -> Result<(), MailError> {
         let auth = GmailAuth::with_token_data(client_id, client_secret, Some(token_json));
         let gmail = GmailClient::new(auth);
         let handler = crate::actions::ActionHandler::new(Arc::new(gmail), self.store.clone());
 
-        handler
-            .archive_thread(&ThreadId::new(thread_id))
-            .map_err(|e| MailError::Network {
-                message: e.to_string(),
-            })?;
+        handler.rsvp(&MessageId::new(message_id), response.into())?;
         Ok(())
     }
 
@@ -481,16 +910,10 @@ impl MailService {
         client_id: String,
         client_secret: String,
     ) -> Result<bool, MailError> {
-        let auth = GmailAuth::with_token_data(client_id, client_secret, Some(token_json));
-        let gmail = GmailClient::new(auth);
-        let handler = crate::actions::ActionHandler::new(Arc::new(gmail), self.store.clone());
-
-        let is_starred = handler
+        let handler = self.action_handler(token_json, client_id, client_secret);
+        handler
             .toggle_star(&ThreadId::new(thread_id))
-            .map_err(|e| MailError::Network {
-                message: e.to_string(),
-            })?;
-        Ok(is_starred)
+            .map_err(MailError::from_action)
     }
 
     /// Set the read state of a thread
@@ -502,16 +925,26 @@ impl MailService {
         client_id: String,
         client_secret: String,
     ) -> Result<(), MailError> {
-        let auth = GmailAuth::with_token_data(client_id, client_secret, Some(token_json));
-        let gmail = GmailClient::new(auth);
-        let handler = crate::actions::ActionHandler::new(Arc::new(gmail), self.store.clone());
-
+        let handler = self.action_handler(token_json, client_id, client_secret);
         handler
             .set_read(&ThreadId::new(thread_id), is_read)
-            .map_err(|e| MailError::Network {
-                message: e.to_string(),
-            })?;
-        Ok(())
+            .map_err(MailError::from_action)
+    }
+
+    /// Toggle the read state of a thread
+    ///
+    /// Returns the new read state (true = read, false = unread).
+    pub fn toggle_read(
+        &self,
+        thread_id: String,
+        token_json: String,
+        client_id: String,
+        client_secret: String,
+    ) -> Result<bool, MailError> {
+        let handler = self.action_handler(token_json, client_id, client_secret);
+        handler
+            .toggle_read(&ThreadId::new(thread_id))
+            .map_err(MailError::from_action)
     }
 
     /// Move a thread to trash
@@ -522,16 +955,69 @@ impl MailService {
         client_id: String,
         client_secret: String,
     ) -> Result<(), MailError> {
-        let auth = GmailAuth::with_token_data(client_id, client_secret, Some(token_json));
-        let gmail = GmailClient::new(auth);
-        let handler = crate::actions::ActionHandler::new(Arc::new(gmail), self.store.clone());
-
+        let handler = self.action_handler(token_json, client_id, client_secret);
         handler
             .trash_thread(&ThreadId::new(thread_id))
-            .map_err(|e| MailError::Network {
-                message: e.to_string(),
-            })?;
-        Ok(())
+            .map_err(MailError::from_action)
+    }
+
+    /// Add and remove labels on every message of some threads
+    ///
+    /// Returns the number of messages changed.
+    pub fn modify_labels(
+        &self,
+        thread_ids: Vec<String>,
+        add_labels: Vec<String>,
+        remove_labels: Vec<String>,
+        token_json: String,
+        client_id: String,
+        client_secret: String,
+    ) -> Result<u32, MailError> {
+        let handler = self.action_handler(token_json, client_id, client_secret);
+        let thread_ids: Vec<ThreadId> = thread_ids.into_iter().map(ThreadId::new).collect();
+        let add: Vec<&str> = add_labels.iter().map(String::as_str).collect();
+        let remove: Vec<&str> = remove_labels.iter().map(String::as_str).collect();
+        let modified = handler
+            .batch_modify(&thread_ids, &add, &remove)
+            .map_err(MailError::from_action)?;
+        Ok(modified as u32)
+    }
+
+    /// Count label changes waiting to be sent to Gmail
+    pub fn count_queued_actions(&self, account_id: i64) -> Result<u32, MailError> {
+        Ok(self.store.list_queued_actions(account_id)?.len() as u32)
+    }
+
+    /// Send label changes made offline to Gmail, oldest first
+    ///
+    /// Call when connectivity returns. Changes Gmail rejects are dropped and
+    /// counted in `rejected`.
+    pub fn flush_queued_actions(
+        &self,
+        account_id: i64,
+        token_json: String,
+        client_id: String,
+        client_secret: String,
+    ) -> Result<FfiQueueFlush, MailError> {
+        let handler = self.action_handler(token_json, client_id, client_secret);
+        let flush = handler.flush_queued_actions(account_id)?;
+        Ok(flush.into())
+    }
+}
+
+impl MailService {
+    /// Action handler for one call, queueing label changes while offline
+    fn action_handler(
+        &self,
+        token_json: String,
+        client_id: String,
+        client_secret: String,
+    ) -> crate::actions::ActionHandler {
+        let auth = GmailAuth::with_token_data(client_id, client_secret, Some(token_json));
+        let gmail = GmailClient::new(auth);
+        crate::actions::ActionHandler::new(Arc::new(gmail), self.store.clone())
+            .with_search_index(self.search_index.clone())
+            .with_offline_queue()
     }
 }
 
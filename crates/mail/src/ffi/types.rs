@@ -5,10 +5,21 @@
 //! - `ThreadId`/`MessageId` → `String`
 //! - Complex enums → simpler representations
 
-use crate::models::{Account, EmailAddress, Label, Message, SyncState, Thread};
-use crate::query::{ThreadDetail, ThreadSummary};
+use crate::accounts::AccountHealth;
+use crate::actions::{ActionQueuedError, QueueFlush};
+use crate::filters::FilterDiff;
+use crate::gmail::{ReauthRequiredError, is_transient_error};
+use crate::models::{
+    Account, EmailAddress, EventTime, InviteMethod, Label, Message, RsvpResponse, SendAsAlias,
+    SyncState, Thread,
+};
+use crate::query::{
+    FollowupCandidate, ReplySuggester, Summarizer, ThreadDetail, ThreadInvite, ThreadSummary,
+};
 use crate::search::{FieldHighlight, HighlightSpan, SearchResult};
-use crate::sync::SyncStats;
+use crate::storage::MessageBody;
+use crate::sync::{SyncCancelledError, SyncStats, SyncStep, SyncStepPhase};
+use crate::translate::{BodyFormat, TranslatedBody, Translator};
 
 // ============================================================================
 // Error Types
@@ -34,18 +45,65 @@ pub enum MailError {
 
     #[error("Sync error: {message}")]
     Sync { message: String },
+
+    #[error("Cancelled")]
+    Cancelled,
+
+    /// Gmail couldn't be reached; the change was applied locally and queued
+    #[error("Queued until online: {message}")]
+    Queued { message: String },
+
+    /// Gmail refused the change and it wasn't applied
+    #[error("Rejected by Gmail: {message}")]
+    Rejected { message: String },
+}
+
+impl MailError {
+    /// Classify an error from an `ActionHandler` call
+    ///
+    /// Separates changes queued while offline from ones Gmail rejected, so
+    /// apps can keep optimistic UI for the former and roll back the latter.
+    pub(crate) fn from_action(e: anyhow::Error) -> Self {
+        if e.downcast_ref::<ActionQueuedError>().is_some() {
+            return MailError::Queued {
+                message: e.to_string(),
+            };
+        }
+        if e.downcast_ref::<ReauthRequiredError>().is_some() {
+            return MailError::AuthRequired;
+        }
+        if is_transient_error(&e) {
+            return MailError::Network {
+                message: e.to_string(),
+            };
+        }
+        if e.chain().any(|cause| cause.downcast_ref::<ureq::Error>().is_some()) {
+            return MailError::Rejected {
+                message: format!("{:#}", e),
+            };
+        }
+        MailError::from(e)
+    }
 }
 
 impl From<anyhow::Error> for MailError {
     fn from(e: anyhow::Error) -> Self {
-        // Check for specific error types
-        let msg = e.to_string();
-        if msg.contains("database") || msg.contains("sqlite") || msg.contains("SQL") {
-            MailError::Database { message: msg }
-        } else if msg.contains("network") || msg.contains("connection") || msg.contains("HTTP") {
-            MailError::Network { message: msg }
-        } else {
-            MailError::Database { message: msg }
+        if e.downcast_ref::<SyncCancelledError>().is_some() {
+            return MailError::Cancelled;
+        }
+        match crate::error::MailError::from(e) {
+            crate::error::MailError::Auth(_) => MailError::AuthRequired,
+            e @ (crate::error::MailError::RateLimited { .. }
+            | crate::error::MailError::Network(_)) => MailError::Network {
+                message: e.to_string(),
+            },
+            crate::error::MailError::NotFound(resource) => MailError::NotFound { resource },
+            e @ crate::error::MailError::HistoryExpired => MailError::Sync {
+                message: e.to_string(),
+            },
+            e => MailError::Database {
+                message: e.to_string(),
+            },
         }
     }
 }
@@ -64,10 +122,18 @@ pub struct FfiAccount {
     pub is_primary: bool,
     /// Unix timestamp (seconds since epoch)
     pub added_at: i64,
+    /// Plain text signature for outgoing mail
+    pub signature_text: Option<String>,
+    /// HTML signature for outgoing mail
+    pub signature_html: Option<String>,
 }
 
 impl From<Account> for FfiAccount {
     fn from(a: Account) -> Self {
+        let (signature_text, signature_html) = match a.signature {
+            Some(signature) => (Some(signature.text), signature.html),
+            None => (None, None),
+        };
         Self {
             id: a.id,
             email: a.email,
@@ -75,6 +141,75 @@ impl From<Account> for FfiAccount {
             avatar_color: a.avatar_color,
             is_primary: a.is_primary,
             added_at: a.added_at.timestamp(),
+            signature_text,
+            signature_html,
+        }
+    }
+}
+
+/// FFI-friendly send-as alias
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiSendAsAlias {
+    pub email: String,
+    pub display_name: Option<String>,
+    pub is_primary: bool,
+    pub is_default: bool,
+    /// Only verified aliases can be used as the From address
+    pub verified: bool,
+}
+
+/// FFI-friendly summary of a Gmail filter import
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiFilterImport {
+    pub added: u32,
+    pub changed: u32,
+    pub removed: u32,
+    pub unchanged: u32,
+    /// Filters that couldn't be imported, with the reason
+    pub unsupported: Vec<String>,
+}
+
+impl From<FilterDiff> for FfiFilterImport {
+    fn from(d: FilterDiff) -> Self {
+        Self {
+            added: d.added.len() as u32,
+            changed: d.changed.len() as u32,
+            removed: d.removed.len() as u32,
+            unchanged: d.unchanged as u32,
+            unsupported: d
+                .unsupported
+                .into_iter()
+                .map(|f| format!("{}: {}", f.filter_id, f.reason))
+                .collect(),
+        }
+    }
+}
+
+impl From<SendAsAlias> for FfiSendAsAlias {
+    fn from(a: SendAsAlias) -> Self {
+        Self {
+            email: a.email,
+            display_name: a.display_name,
+            is_primary: a.is_primary,
+            is_default: a.is_default,
+            verified: a.verified,
+        }
+    }
+}
+
+/// FFI-friendly account health
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiAccountHealth {
+    Healthy,
+    /// The token was revoked; show a reconnect prompt
+    NeedsReauth,
+}
+
+impl From<AccountHealth> for FfiAccountHealth {
+    fn from(h: AccountHealth) -> Self {
+        match h {
+            AccountHealth::Healthy => FfiAccountHealth::Healthy,
+            AccountHealth::NeedsReauth => FfiAccountHealth::NeedsReauth,
         }
     }
 }
@@ -205,13 +340,119 @@ impl From<ThreadSummary> for FfiThreadSummary {
 pub struct FfiThreadDetail {
     pub thread: FfiThread,
     pub messages: Vec<FfiMessage>,
+    /// Whether any message failed DMARC (show a spoofing warning)
+    pub auth_warning: bool,
+    pub invites: Vec<FfiEventInvite>,
+    /// Opens of messages sent with a tracking beacon
+    pub opens: Vec<FfiMessageOpens>,
+    /// The user's private note (Markdown)
+    pub note: Option<String>,
 }
 
 impl From<ThreadDetail> for FfiThreadDetail {
     fn from(d: ThreadDetail) -> Self {
+        let mut opens: Vec<FfiMessageOpens> = d
+            .opens
+            .into_iter()
+            .map(|(id, status)| FfiMessageOpens {
+                message_id: id.0,
+                open_count: status.open_count,
+                first_opened_at: status.first_opened_at.map(|t| t.timestamp()),
+                last_opened_at: status.last_opened_at.map(|t| t.timestamp()),
+            })
+            .collect();
+        opens.sort_by(|a, b| a.message_id.cmp(&b.message_id));
+
         Self {
             thread: d.thread.into(),
             messages: d.messages.into_iter().map(FfiMessage::from).collect(),
+            auth_warning: d.auth_warning,
+            invites: d.invites.into_iter().map(FfiEventInvite::from).collect(),
+            opens,
+            note: d.note.map(|n| n.markdown),
+        }
+    }
+}
+
+/// FFI-friendly open status of a tracked sent message
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiMessageOpens {
+    pub message_id: String,
+    pub open_count: u32,
+    /// Unix timestamp of the first open
+    pub first_opened_at: Option<i64>,
+    /// Unix timestamp of the most recent open
+    pub last_opened_at: Option<i64>,
+}
+
+/// FFI-friendly sent thread awaiting a reply
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiFollowupCandidate {
+    pub thread: FfiThreadSummary,
+    /// Unix timestamp of the unanswered message
+    pub sent_at: i64,
+    pub recipients: Vec<FfiEmailAddress>,
+}
+
+impl From<FollowupCandidate> for FfiFollowupCandidate {
+    fn from(c: FollowupCandidate) -> Self {
+        Self {
+            thread: c.thread.into(),
+            sent_at: c.sent_at.timestamp(),
+            recipients: c.recipients.into_iter().map(FfiEmailAddress::from).collect(),
+        }
+    }
+}
+
+/// FFI-friendly calendar invite
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiEventInvite {
+    /// Message that carried the invite (pass to `rsvp`)
+    pub message_id: String,
+    pub uid: String,
+    pub summary: String,
+    pub organizer: Option<FfiEmailAddress>,
+    pub location: Option<String>,
+    /// Unix timestamp of the start, when given as an absolute time
+    pub start: Option<i64>,
+    /// Whether the invite can be accepted or declined
+    pub can_respond: bool,
+    pub cancelled: bool,
+}
+
+impl From<ThreadInvite> for FfiEventInvite {
+    fn from(t: ThreadInvite) -> Self {
+        let invite = t.invite;
+        Self {
+            message_id: t.message_id.0,
+            can_respond: invite.can_respond(),
+            cancelled: invite.method == InviteMethod::Cancel,
+            start: match invite.start {
+                Some(EventTime::Utc { time }) => Some(time.timestamp()),
+                _ => None,
+            },
+            uid: invite.uid,
+            summary: invite.summary,
+            organizer: invite.organizer.map(FfiEmailAddress::from),
+            location: invite.location,
+        }
+    }
+}
+
+/// FFI-friendly invite response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiRsvpResponse {
+    Accept,
+    Decline,
+    Tentative,
+}
+
+impl From<FfiRsvpResponse> for RsvpResponse {
+    fn from(r: FfiRsvpResponse) -> Self {
+        match r {
+            FfiRsvpResponse::Accept => RsvpResponse::Accept,
+            FfiRsvpResponse::Decline => RsvpResponse::Decline,
+            FfiRsvpResponse::Tentative => RsvpResponse::Tentative,
         }
     }
 }
@@ -237,10 +478,15 @@ pub struct FfiMessage {
     pub received_at: i64,
     pub internal_date: i64,
     pub label_ids: Vec<String>,
+    /// Whether the message failed DMARC
+    pub failed_dmarc: bool,
+    /// Detected body language (ISO 639-3, e.g. "eng")
+    pub lang: Option<String>,
 }
 
 impl From<Message> for FfiMessage {
     fn from(m: Message) -> Self {
+        let failed_dmarc = m.auth_results.as_ref().is_some_and(|a| a.failed_dmarc());
         Self {
             id: m.id.0,
             thread_id: m.thread_id.0,
@@ -255,6 +501,52 @@ impl From<Message> for FfiMessage {
             received_at: m.received_at.timestamp(),
             internal_date: m.internal_date,
             label_ids: m.label_ids,
+            failed_dmarc,
+            lang: m.lang,
+        }
+    }
+}
+
+/// FFI-friendly outcome of sending queued offline changes
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiQueueFlush {
+    pub sent: u32,
+    /// Changes Gmail rejected, which were dropped
+    pub rejected: u32,
+    /// Changes still queued because Gmail remains unreachable
+    pub remaining: u32,
+}
+
+impl From<QueueFlush> for FfiQueueFlush {
+    fn from(f: QueueFlush) -> Self {
+        Self {
+            sent: f.sent as u32,
+            rejected: f.rejected as u32,
+            remaining: f.remaining as u32,
+        }
+    }
+}
+
+/// FFI-friendly full message body
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiMessageBody {
+    pub text: Option<String>,
+    /// HTML exactly as received
+    pub html: Option<String>,
+    /// HTML with scripts, frames and event handlers removed, safe to load
+    /// into a web view
+    pub sanitized_html: Option<String>,
+    /// Text extracted from attachments
+    pub attachment_text: Option<String>,
+}
+
+impl From<MessageBody> for FfiMessageBody {
+    fn from(b: MessageBody) -> Self {
+        Self {
+            sanitized_html: b.html.as_deref().map(crate::query::sanitize_html),
+            text: b.text,
+            html: b.html,
+            attachment_text: b.attachment_text,
         }
     }
 }
@@ -299,6 +591,8 @@ pub struct FfiSyncStats {
     pub was_incremental: bool,
     pub errors: u32,
     pub duration_ms: u64,
+    pub quota_units: u64,
+    pub quota_burn_rate: f64,
 }
 
 impl From<SyncStats> for FfiSyncStats {
@@ -314,6 +608,8 @@ impl From<SyncStats> for FfiSyncStats {
             was_incremental: s.was_incremental,
             errors: s.errors as u32,
             duration_ms: s.duration_ms,
+            quota_units: s.quota.units(),
+            quota_burn_rate: s.quota.burn_rate(),
         }
     }
 }
@@ -323,21 +619,34 @@ impl From<SyncStats> for FfiSyncStats {
 // ============================================================================
 
 /// FFI-friendly highlight span
+///
+/// Offsets count UTF-16 code units into the field text, matching `NSRange`
+/// and Swift's `String.UTF16View`, rather than the byte offsets used inside
+/// the crate.
 #[derive(Debug, Clone, uniffi::Record)]
 pub struct FfiHighlightSpan {
     pub start: u32,
     pub end: u32,
 }
 
-impl From<HighlightSpan> for FfiHighlightSpan {
-    fn from(h: HighlightSpan) -> Self {
+impl FfiHighlightSpan {
+    fn from_span(text: &str, span: &HighlightSpan) -> Self {
         Self {
-            start: h.start as u32,
-            end: h.end as u32,
+            start: utf16_offset(text, span.start),
+            end: utf16_offset(text, span.end),
         }
     }
 }
 
+/// Convert a byte offset into `text` to a UTF-16 offset
+fn utf16_offset(text: &str, byte_offset: usize) -> u32 {
+    let end = byte_offset.min(text.len());
+    text.char_indices()
+        .take_while(|(i, _)| *i < end)
+        .map(|(_, c)| c.len_utf16() as u32)
+        .sum()
+}
+
 /// FFI-friendly field highlight
 #[derive(Debug, Clone, uniffi::Record)]
 pub struct FfiFieldHighlight {
@@ -348,10 +657,15 @@ pub struct FfiFieldHighlight {
 
 impl From<FieldHighlight> for FfiFieldHighlight {
     fn from(f: FieldHighlight) -> Self {
+        let highlights = f
+            .highlights
+            .iter()
+            .map(|span| FfiHighlightSpan::from_span(&f.text, span))
+            .collect();
         Self {
             field: f.field,
             text: f.text,
-            highlights: f.highlights.into_iter().map(FfiHighlightSpan::from).collect(),
+            highlights,
         }
     }
 }
@@ -426,6 +740,206 @@ pub trait SyncProgressCallback: Send + Sync {
     fn on_error(&self, message: String);
 }
 
+/// Stage of a sync, for progress display
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiSyncPhase {
+    Starting,
+    Fetching,
+    Processing,
+    Complete,
+}
+
+/// Structured progress report from an async sync
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiSyncProgress {
+    pub phase: FfiSyncPhase,
+    /// Messages fetched from Gmail so far
+    pub messages_fetched: u32,
+    /// Total expected, when known
+    pub messages_total: Option<u32>,
+    /// Human-readable status, e.g. "Fetched 500 messages (1000 listed)..."
+    pub description: String,
+}
+
+impl FfiSyncProgress {
+    /// Progress from the sync engine's (count, description) callback
+    pub(crate) fn from_engine(fetched: usize, description: &str) -> Self {
+        let phase = if description.starts_with("Processing") {
+            FfiSyncPhase::Processing
+        } else if description.starts_with("Fetch") {
+            FfiSyncPhase::Fetching
+        } else {
+            FfiSyncPhase::Starting
+        };
+        Self {
+            phase,
+            messages_fetched: fetched as u32,
+            messages_total: None,
+            description: description.to_string(),
+        }
+    }
+}
+
+/// Result of one time-boxed sync slice
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiSyncStep {
+    /// Work left: `Fetching` or `Processing` until the initial sync is done,
+    /// then `Complete`
+    pub phase: FfiSyncPhase,
+    /// Whether the account is fully synced; schedule another slice if not
+    pub complete: bool,
+    /// Messages fetched but not yet processed
+    pub pending: u32,
+    /// Work done during this slice
+    pub stats: FfiSyncStats,
+    /// Opaque checkpoint; an unchanged token means the slice made no progress
+    pub resume_token: String,
+}
+
+impl From<SyncStep> for FfiSyncStep {
+    fn from(step: SyncStep) -> Self {
+        Self {
+            phase: match step.checkpoint.phase {
+                SyncStepPhase::Fetching => FfiSyncPhase::Fetching,
+                SyncStepPhase::Processing => FfiSyncPhase::Processing,
+                SyncStepPhase::UpToDate => FfiSyncPhase::Complete,
+            },
+            complete: step.is_complete(),
+            pending: step.checkpoint.pending as u32,
+            resume_token: step.checkpoint.token(),
+            stats: FfiSyncStats::from(step.stats),
+        }
+    }
+}
+
+/// Callback interface for structured progress from async syncs
+#[uniffi::export(callback_interface)]
+pub trait SyncProgressListener: Send + Sync {
+    /// Called from the sync's worker thread whenever progress changes
+    fn on_progress(&self, progress: FfiSyncProgress);
+}
+
+// ============================================================================
+// Translation
+// ============================================================================
+
+/// Format of a body passed to or returned from translation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum FfiBodyFormat {
+    Text,
+    Html,
+}
+
+impl From<BodyFormat> for FfiBodyFormat {
+    fn from(format: BodyFormat) -> Self {
+        match format {
+            BodyFormat::Text => FfiBodyFormat::Text,
+            BodyFormat::Html => FfiBodyFormat::Html,
+        }
+    }
+}
+
+/// FFI-friendly translated body
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiTranslatedBody {
+    pub body: String,
+    pub format: FfiBodyFormat,
+    /// False if the body was already in the target language
+    pub translated: bool,
+}
+
+impl From<TranslatedBody> for FfiTranslatedBody {
+    fn from(t: TranslatedBody) -> Self {
+        Self {
+            body: t.body,
+            format: t.format.into(),
+            translated: t.translated,
+        }
+    }
+}
+
+/// Callback interface for translating message bodies
+///
+/// Implemented by the app with a local model or a translation API.
+#[uniffi::export(callback_interface)]
+pub trait TranslatorCallback: Send + Sync {
+    /// Translate `text` into `target_lang` (ISO 639-3)
+    ///
+    /// Returns None if the text couldn't be translated.
+    fn translate(
+        &self,
+        text: String,
+        format: FfiBodyFormat,
+        source_lang: Option<String>,
+        target_lang: String,
+    ) -> Option<String>;
+}
+
+/// Adapts a foreign `TranslatorCallback` to the `Translator` trait
+pub(crate) struct CallbackTranslator(pub Box<dyn TranslatorCallback>);
+
+impl Translator for CallbackTranslator {
+    fn translate(
+        &self,
+        text: &str,
+        format: BodyFormat,
+        source_lang: Option<&str>,
+        target_lang: &str,
+    ) -> anyhow::Result<String> {
+        self.0
+            .translate(
+                text.to_string(),
+                format.into(),
+                source_lang.map(str::to_string),
+                target_lang.to_string(),
+            )
+            .ok_or_else(|| anyhow::anyhow!("Translation into {} failed", target_lang))
+    }
+}
+
+/// Callback interface for summarizing threads
+///
+/// Implemented by the app with a local or remote model.
+#[uniffi::export(callback_interface)]
+pub trait SummarizerCallback: Send + Sync {
+    /// Summarize a thread in one paragraph
+    ///
+    /// `messages` are in chronological order and include bodies. Returns
+    /// None if no summary could be produced.
+    fn summarize(&self, subject: String, messages: Vec<FfiMessage>) -> Option<String>;
+}
+
+/// Adapts a foreign `SummarizerCallback` to the `Summarizer` trait
+pub(crate) struct CallbackSummarizer(pub Box<dyn SummarizerCallback>);
+
+impl Summarizer for CallbackSummarizer {
+    fn summarize(&self, thread: &Thread, messages: &[Message]) -> anyhow::Result<String> {
+        let messages = messages.iter().cloned().map(FfiMessage::from).collect();
+        self.0
+            .summarize(thread.subject.clone(), messages)
+            .ok_or_else(|| anyhow::anyhow!("Summarizing thread {} failed", thread.id.as_str()))
+    }
+}
+
+/// Callback interface for smart reply suggestions
+///
+/// The prompt is built by the mail crate, so every app sends a model the
+/// same context.
+#[uniffi::export(callback_interface)]
+pub trait ReplySuggesterCallback: Send + Sync {
+    /// Suggest replies for `prompt`, one per entry
+    fn suggest(&self, prompt: String) -> Vec<String>;
+}
+
+/// Adapts a foreign `ReplySuggesterCallback` to the `ReplySuggester` trait
+pub(crate) struct CallbackReplySuggester(pub Box<dyn ReplySuggesterCallback>);
+
+impl ReplySuggester for CallbackReplySuggester {
+    fn suggest(&self, prompt: &str) -> anyhow::Result<Vec<String>> {
+        Ok(self.0.suggest(prompt.to_string()))
+    }
+}
+
 // ============================================================================
 // Log Callback
 // ============================================================================
@@ -0,0 +1,414 @@
+//! Gmail filter import
+//!
+//! Gmail filters only run on Google's servers. Importing mirrors each one as
+//! a local [`Rule`] tagged with the filter's ID, so the rules engine applies
+//! it during sync too. Filters whose criteria or actions have no local
+//! equivalent are reported rather than imported.
+
+use anyhow::Result;
+use log::info;
+
+use crate::gmail::GmailClient;
+use crate::gmail::api::GmailFilter;
+use crate::models::{Rule, RuleAction, RulePredicate};
+use crate::storage::MailStore;
+
+/// A Gmail filter that couldn't be mirrored locally
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedFilter {
+    /// Gmail filter ID
+    pub filter_id: String,
+    /// What the rules engine can't express
+    pub reason: String,
+}
+
+/// An imported rule whose server filter has changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleChange {
+    /// Rule as stored locally
+    pub local: Rule,
+    /// Rule as converted from the server filter
+    pub server: Rule,
+}
+
+/// Differences between an account's imported rules and its Gmail filters
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilterDiff {
+    /// Filters with no local rule yet
+    pub added: Vec<Rule>,
+    /// Filters whose criteria or actions differ from the local rule
+    pub changed: Vec<RuleChange>,
+    /// Imported rules whose filter no longer exists on the server
+    pub removed: Vec<Rule>,
+    /// Number of rules that already match their filter
+    pub unchanged: usize,
+    /// Filters that can't be expressed as rules
+    pub unsupported: Vec<UnsupportedFilter>,
+}
+
+impl FilterDiff {
+    /// Whether applying the diff would change any rules
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Convert a Gmail filter to an unsaved rule for the account
+///
+/// Returns the reason when the filter uses a criterion or action the rules
+/// engine doesn't support.
+pub fn rule_from_filter(account_id: i64, filter: &GmailFilter) -> Result<Rule, String> {
+    let criteria = &filter.criteria;
+    let action = &filter.action;
+
+    if criteria.negated_query.is_some() {
+        return Err("negated queries are not supported".to_string());
+    }
+    if criteria.size.is_some() {
+        return Err("size criteria are not supported".to_string());
+    }
+
+    let mut predicates = Vec::new();
+    let mut terms = Vec::new();
+    if let Some(from) = non_empty(&criteria.from) {
+        predicates.push(sender_predicate(from)?);
+        terms.push(format!("from:{}", from));
+    }
+    if let Some(to) = non_empty(&criteria.to) {
+        if !is_plain_address(to) {
+            return Err(format!("unsupported recipient criterion \"{}\"", to));
+        }
+        predicates.push(RulePredicate::Recipient(to.to_string()));
+        terms.push(format!("to:{}", to));
+    }
+    if let Some(subject) = non_empty(&criteria.subject) {
+        predicates.push(RulePredicate::Subject(subject.to_string()));
+        terms.push(format!("subject:{}", subject));
+    }
+    if let Some(query) = non_empty(&criteria.query) {
+        if !is_plain_keyword(query) {
+            return Err(format!("unsupported search query \"{}\"", query));
+        }
+        predicates.push(RulePredicate::Keyword(query.to_string()));
+        terms.push(query.to_string());
+    }
+    if criteria.has_attachment {
+        predicates.push(RulePredicate::HasAttachment);
+        terms.push("has:attachment".to_string());
+    }
+    if predicates.is_empty() {
+        return Err("filter has no criteria".to_string());
+    }
+
+    if let Some(forward) = &action.forward {
+        return Err(format!("forwarding to {} is not supported", forward));
+    }
+    let mut actions: Vec<RuleAction> = action
+        .add_label_ids
+        .iter()
+        .map(|label| RuleAction::AddLabel(label.clone()))
+        .collect();
+    for label in &action.remove_label_ids {
+        match label.as_str() {
+            "INBOX" => actions.push(RuleAction::Archive),
+            "UNREAD" => actions.push(RuleAction::MarkRead),
+            other => return Err(format!("removing label {} is not supported", other)),
+        }
+    }
+    if actions.is_empty() {
+        return Err("filter has no actions".to_string());
+    }
+
+    let mut rule = Rule::new(format!("Gmail: {}", terms.join(" ")))
+        .for_account(account_id)
+        .with_gmail_filter(&filter.id);
+    rule.predicates = predicates;
+    rule.actions = actions;
+    Ok(rule)
+}
+
+/// Compare an account's imported rules with its Gmail filters
+///
+/// Only rules belonging to `account_id` and carrying a Gmail filter ID take
+/// part; rules created locally are never reported as removed.
+pub fn diff_filters(local: &[Rule], filters: &[GmailFilter], account_id: i64) -> FilterDiff {
+    let mut imported: Vec<&Rule> = local
+        .iter()
+        .filter(|rule| rule.account_id == Some(account_id) && rule.gmail_filter_id.is_some())
+        .collect();
+    let mut diff = FilterDiff::default();
+
+    for filter in filters {
+        let existing = imported
+            .iter()
+            .position(|rule| rule.gmail_filter_id.as_deref() == Some(filter.id.as_str()))
+            .map(|index| imported.swap_remove(index));
+
+        let server = match rule_from_filter(account_id, filter) {
+            Ok(rule) => rule,
+            Err(reason) => {
+                diff.unsupported.push(UnsupportedFilter {
+                    filter_id: filter.id.clone(),
+                    reason,
+                });
+                // A filter that stopped being importable no longer has a rule
+                if let Some(local) = existing {
+                    diff.removed.push(local.clone());
+                }
+                continue;
+            }
+        };
+
+        match existing {
+            None => diff.added.push(server),
+            Some(local)
+                if local.name == server.name
+                    && local.predicates == server.predicates
+                    && local.actions == server.actions =>
+            {
+                diff.unchanged += 1;
+            }
+            Some(local) => diff.changed.push(RuleChange {
+                local: local.clone(),
+                server,
+            }),
+        }
+    }
+
+    diff.removed.extend(imported.into_iter().cloned());
+    diff
+}
+
+/// Apply a diff to the store
+///
+/// Changed rules keep their local ID, enabled flag and creation time, so a
+/// filter the user disabled locally stays disabled.
+pub fn apply_filter_diff(store: &dyn MailStore, diff: &FilterDiff) -> Result<()> {
+    for rule in &diff.added {
+        store.save_rule(rule.clone())?;
+    }
+    for change in &diff.changed {
+        store.save_rule(Rule {
+            id: change.local.id,
+            enabled: change.local.enabled,
+            created_at: change.local.created_at,
+            ..change.server.clone()
+        })?;
+    }
+    for rule in &diff.removed {
+        store.delete_rule(rule.id)?;
+    }
+    Ok(())
+}
+
+/// Fetch the account's Gmail filters and mirror them as local rules
+///
+/// Returns the diff that was applied.
+pub fn import_gmail_filters(
+    client: &GmailClient,
+    store: &dyn MailStore,
+    account_id: i64,
+) -> Result<FilterDiff> {
+    let filters = client.list_filters()?;
+    let diff = diff_filters(&store.list_rules()?, &filters, account_id);
+    apply_filter_diff(store, &diff)?;
+
+    info!(
+        "Imported Gmail filters for account {}: {} added, {} changed, {} removed, {} unsupported",
+        account_id,
+        diff.added.len(),
+        diff.changed.len(),
+        diff.removed.len(),
+        diff.unsupported.len()
+    );
+    Ok(diff)
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// Map a `from` criterion to a sender or domain predicate
+fn sender_predicate(from: &str) -> Result<RulePredicate, String> {
+    if let Some(domain) = from.strip_prefix('@')
+        && is_plain_keyword(domain)
+    {
+        Ok(RulePredicate::Domain(domain.to_string()))
+    } else if is_plain_address(from) {
+        Ok(RulePredicate::Sender(from.to_string()))
+    } else if is_plain_keyword(from) && from.contains('.') {
+        Ok(RulePredicate::Domain(from.to_string()))
+    } else {
+        Err(format!("unsupported sender criterion \"{}\"", from))
+    }
+}
+
+/// A single address, not a Gmail search expression
+fn is_plain_address(value: &str) -> bool {
+    is_plain_keyword(value) && value.matches('@').count() == 1 && !value.starts_with('@')
+}
+
+/// A single term without search operators, quotes or grouping
+fn is_plain_keyword(value: &str) -> bool {
+    !value.is_empty()
+        && !value.starts_with('-')
+        && !value.contains(|c: char| c.is_whitespace() || "\"(){}|:*".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gmail::api::{FilterAction, FilterCriteria};
+    use crate::storage::InMemoryMailStore;
+
+    fn filter(id: &str, criteria: FilterCriteria, action: FilterAction) -> GmailFilter {
+        GmailFilter {
+            id: id.to_string(),
+            criteria,
+            action,
+        }
+    }
+
+    fn archive() -> FilterAction {
+        FilterAction {
+            remove_label_ids: vec!["INBOX".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rule_from_filter() {
+        let f = filter(
+            "f1",
+            FilterCriteria {
+                from: Some("@news.example.com".to_string()),
+                subject: Some("Digest".to_string()),
+                has_attachment: true,
+                ..Default::default()
+            },
+            FilterAction {
+                add_label_ids: vec!["Label_1".to_string()],
+                remove_label_ids: vec!["INBOX".to_string(), "UNREAD".to_string()],
+                ..Default::default()
+            },
+        );
+        let rule = rule_from_filter(1, &f).unwrap();
+        assert_eq!(
+            rule.name,
+            "Gmail: from:@news.example.com subject:Digest has:attachment"
+        );
+        assert_eq!(rule.account_id, Some(1));
+        assert_eq!(rule.gmail_filter_id.as_deref(), Some("f1"));
+        assert_eq!(
+            rule.predicates,
+            vec![
+                RulePredicate::Domain("news.example.com".to_string()),
+                RulePredicate::Subject("Digest".to_string()),
+                RulePredicate::HasAttachment,
+            ]
+        );
+        assert_eq!(
+            rule.actions,
+            vec![
+                RuleAction::AddLabel("Label_1".to_string()),
+                RuleAction::Archive,
+                RuleAction::MarkRead,
+            ]
+        );
+
+        let sender = FilterCriteria {
+            from: Some("boss@example.com".to_string()),
+            ..Default::default()
+        };
+        let rule = rule_from_filter(1, &filter("f2", sender, archive())).unwrap();
+        assert_eq!(
+            rule.predicates,
+            vec![RulePredicate::Sender("boss@example.com".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_rule_from_filter_unsupported() {
+        let from = |from: &str| FilterCriteria {
+            from: Some(from.to_string()),
+            ..Default::default()
+        };
+        assert!(rule_from_filter(1, &filter("f", from("a@x.com OR b@x.com"), archive())).is_err());
+        assert!(rule_from_filter(1, &filter("f", FilterCriteria::default(), archive())).is_err());
+
+        let forward = FilterAction {
+            forward: Some("other@example.com".to_string()),
+            ..Default::default()
+        };
+        assert!(rule_from_filter(1, &filter("f", from("a@x.com"), forward)).is_err());
+
+        let trash = FilterAction {
+            add_label_ids: vec!["TRASH".to_string()],
+            remove_label_ids: vec!["IMPORTANT".to_string()],
+            ..Default::default()
+        };
+        let err = rule_from_filter(1, &filter("f", from("a@x.com"), trash)).unwrap_err();
+        assert!(err.contains("IMPORTANT"));
+
+        let query = FilterCriteria {
+            query: Some("list:dev.example.com".to_string()),
+            ..Default::default()
+        };
+        assert!(rule_from_filter(1, &filter("f", query, archive())).is_err());
+    }
+
+    #[test]
+    fn test_diff_and_apply() {
+        let store = InMemoryMailStore::new();
+        let from = |from: &str| FilterCriteria {
+            from: Some(from.to_string()),
+            ..Default::default()
+        };
+
+        let filters = vec![
+            filter("f1", from("a@example.com"), archive()),
+            filter("f2", from("b@example.com"), archive()),
+        ];
+        let diff = diff_filters(&store.list_rules().unwrap(), &filters, 1);
+        assert_eq!(diff.added.len(), 2);
+        apply_filter_diff(&store, &diff).unwrap();
+
+        // A local rule and another account's rules are left alone
+        store
+            .save_rule(Rule::vip("vip@example.com").for_account(1))
+            .unwrap();
+        let other = rule_from_filter(2, &filters[0]).unwrap();
+        store.save_rule(other).unwrap();
+
+        // Disable f1 locally, then change it on the server and drop f2
+        let mut f1 = store
+            .list_rules()
+            .unwrap()
+            .into_iter()
+            .find(|r| r.account_id == Some(1) && r.gmail_filter_id.as_deref() == Some("f1"))
+            .unwrap();
+        f1.enabled = false;
+        store.save_rule(f1.clone()).unwrap();
+
+        let filters = vec![filter("f1", from("@example.com"), archive())];
+        let diff = diff_filters(&store.list_rules().unwrap(), &filters, 1);
+        assert!(diff.added.is_empty());
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].gmail_filter_id.as_deref(), Some("f2"));
+        apply_filter_diff(&store, &diff).unwrap();
+
+        let rules = store.list_rules().unwrap();
+        assert_eq!(rules.len(), 3);
+        let updated = rules.iter().find(|r| r.id == f1.id).unwrap();
+        assert!(!updated.enabled);
+        assert_eq!(
+            updated.predicates,
+            vec![RulePredicate::Domain("example.com".to_string())]
+        );
+
+        let diff = diff_filters(&rules, &filters, 1);
+        assert!(diff.is_empty());
+        assert_eq!(diff.unchanged, 1);
+    }
+}
@@ -16,6 +16,14 @@ use std::net::TcpListener;
 use std::path::PathBuf;
 use std::sync::RwLock;
 
+/// Error indicating Google no longer accepts the account's stored token
+///
+/// Raised when the refresh token was revoked or has expired, or when there
+/// is no token at all. Only signing in again fixes it.
+#[derive(Debug, thiserror::Error)]
+#[error("Account needs to be reconnected")]
+pub struct ReauthRequiredError;
+
 /// Token storage mode
 enum TokenStorage {
     /// Store tokens in a file (legacy mode)
@@ -53,6 +61,7 @@ impl GmailAuth {
     /// Gmail API OAuth2 endpoints
     const AUTH_URL: &'static str = "https://accounts.google.com/o/oauth2/v2/auth";
     const TOKEN_URL: &'static str = "https://oauth2.googleapis.com/token";
+    const REVOKE_URL: &'static str = "https://oauth2.googleapis.com/revoke";
 
     /// Required scope for Gmail access (modify allows read + label changes)
     const GMAIL_MODIFY_SCOPE: &'static str = "https://www.googleapis.com/auth/gmail.modify";
@@ -157,6 +166,9 @@ impl GmailAuth {
 
     /// Get a valid access token, refreshing or re-authenticating as needed
     pub fn get_access_token(&self) -> Result<String> {
+        // Set when refreshing failed for a reason other than Google rejecting the token
+        let mut refresh_error = None;
+
         // Try to load existing token
         match self.load_token() {
             Ok(token) => {
@@ -183,6 +195,9 @@ impl GmailAuth {
                         }
                         Err(e) => {
                             log::warn!("Token refresh failed: {}", e);
+                            if !Self::is_rejected(&e) {
+                                refresh_error = Some(e);
+                            }
                         }
                     }
                 } else {
@@ -197,7 +212,11 @@ impl GmailAuth {
         // For in-memory storage (FFI/mobile), we cannot do interactive auth
         // Return an error so the caller can re-authenticate through the native flow
         if matches!(&self.storage, TokenStorage::Memory(_)) {
-            anyhow::bail!("Token expired or invalid. Please re-authenticate through the app.");
+            // A network failure doesn't mean the token is bad; let the caller retry
+            if let Some(e) = refresh_error {
+                return Err(e);
+            }
+            return Err(ReauthRequiredError.into());
         }
 
         // Need to authenticate from scratch (only for file-based desktop auth)
@@ -255,6 +274,15 @@ impl GmailAuth {
         Ok(token)
     }
 
+    /// Run the interactive OAuth flow, replacing any stored token
+    ///
+    /// Opens the browser and blocks until Google redirects back, so call it
+    /// off the UI thread. Used to reconnect accounts whose token was revoked.
+    pub fn authorize(&self) -> Result<()> {
+        let token = self.authorization_code_auth()?;
+        self.save_token_response(&token)
+    }
+
     /// Start a local TCP server on an available port
     fn start_local_server(&self) -> Result<(TcpListener, u16)> {
         for port in Self::PORT_RANGE_START..=Self::PORT_RANGE_END {
@@ -360,6 +388,17 @@ impl GmailAuth {
         Ok(token)
     }
 
+    /// Whether a token request failed because Google rejected the token
+    ///
+    /// The token endpoint answers `invalid_grant` (400) for revoked or
+    /// expired refresh tokens.
+    fn is_rejected(error: &anyhow::Error) -> bool {
+        matches!(
+            error.downcast_ref::<ureq::Error>(),
+            Some(ureq::Error::StatusCode(400 | 401))
+        )
+    }
+
     /// Load stored token
     fn load_token(&self) -> Result<StoredToken> {
         let content = match &self.storage {
@@ -437,6 +476,30 @@ impl GmailAuth {
         Ok(())
     }
 
+    /// Revoke the stored token with Google, then clear it
+    pub fn revoke(&self) -> Result<()> {
+        if let Some(token_data) = self.get_token_data() {
+            Self::revoke_token_data(&token_data)?;
+        }
+        self.logout()
+    }
+
+    /// Revoke JSON-serialized token data (as stored in the database)
+    ///
+    /// Revoking the refresh token also invalidates the access tokens issued
+    /// from it. A token Google no longer accepts (expired or already
+    /// revoked) counts as revoked.
+    pub fn revoke_token_data(token_data: &str) -> Result<()> {
+        let token: StoredToken =
+            serde_json::from_str(token_data).context("Failed to parse token JSON")?;
+        let token = token.refresh_token.unwrap_or(token.access_token);
+
+        match ureq::post(Self::REVOKE_URL).send_form([("token", token.as_str())]) {
+            Ok(_) | Err(ureq::Error::StatusCode(400)) => Ok(()),
+            Err(e) => Err(e).context("Failed to revoke token"),
+        }
+    }
+
     /// Discover all account emails with saved token files
     ///
     /// Scans the config directory for `gmail-tokens-*.json` files and
@@ -4,24 +4,135 @@
 //! Uses synchronous HTTP (ureq) to be executor-agnostic.
 
 use anyhow::{Context, Result};
-use log::info;
-use std::time::Duration;
+use log::{debug, info, warn};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use super::api::{
-    BatchModifyRequest, BatchResponse, GmailMessage, HistoryResponse, ListLabelsResponse,
-    ListMessagesResponse, ModifyMessageRequest, ProfileResponse,
+    AttachmentResponse, BatchModifyRequest, BatchResponse, DraftRef, DraftRequest, GmailFilter,
+    GmailMessage, GmailRawMessage, GmailSendAs, HistoryResponse, ListFiltersResponse,
+    ListLabelsResponse, ListMessagesResponse, ListSendAsResponse, MessageRef, ModifyMessageRequest,
+    ProfileResponse, SendDraftRequest, SendMessageRequest,
 };
-use super::GmailAuth;
-use crate::models::MessageId;
+use super::conditional::ValidatorCache;
+use super::traffic::{TrafficRecorder, TrafficReplay};
+use super::{GmailAuth, VacationSettings};
+use crate::diagnostics::{DiagnosticKind, record_diagnostic};
+use crate::error::is_quota_message;
+use crate::models::{MessageId, ThreadId};
+use crate::telemetry;
+
+/// Largest attachment response body accepted (base64 inflates the file by ~4/3)
+const MAX_ATTACHMENT_RESPONSE_BYTES: u64 = 40 * 1024 * 1024;
 
 /// Error indicating the history ID has expired
 #[derive(Debug, thiserror::Error)]
 #[error("History ID expired or invalid")]
 pub struct HistoryExpiredError;
 
+/// Gmail API calls the sync engine depends on
+///
+/// Implemented by [`GmailClient`] and by
+/// [`MockGmail`](super::mock::MockGmail), which serves fixture data so sync
+/// can be exercised without credentials or network.
+pub trait GmailApi: Send + Sync {
+    /// List message IDs, newest first (see [`GmailClient::list_messages`])
+    fn list_messages(
+        &self,
+        max_results: usize,
+        page_token: Option<&str>,
+        label_id: Option<&str>,
+    ) -> Result<ListMessagesResponse>;
+
+    /// List IDs of messages received after `after_secs`, newest first (see
+    /// [`GmailClient::list_messages_after`])
+    fn list_messages_after(
+        &self,
+        after_secs: i64,
+        max_results: usize,
+        page_token: Option<&str>,
+    ) -> Result<ListMessagesResponse>;
+
+    /// Fetch full messages, one result per ID in order
+    fn get_messages_batch(&self, ids: &[MessageId]) -> Vec<Result<GmailMessage>>;
+
+    /// Download an attachment's decoded bytes
+    fn get_attachment(&self, message_id: &MessageId, attachment_id: &str) -> Result<Vec<u8>>;
+
+    /// List one page of history since `start_history_id`
+    ///
+    /// Fails with [`HistoryExpiredError`] when Gmail no longer has that history.
+    fn list_history(
+        &self,
+        start_history_id: &str,
+        page_token: Option<&str>,
+    ) -> Result<HistoryResponse>;
+
+    /// Get the profile, including the mailbox's current history ID
+    fn get_profile(&self) -> Result<ProfileResponse>;
+
+    /// Whether requests count against Gmail's per-user quota
+    ///
+    /// Sync paces its fetches to the quota only for APIs that do.
+    fn has_quota(&self) -> bool {
+        true
+    }
+
+    /// List all history pages since a given historyId
+    ///
+    /// Automatically handles pagination to fetch all history records.
+    fn list_history_all(&self, start_history_id: &str) -> Result<HistoryResponse> {
+        let mut all_records = Vec::new();
+        let mut final_history_id = None;
+        let mut page_token = None;
+
+        loop {
+            let response = self.list_history(start_history_id, page_token.as_deref())?;
+
+            // Collect history records
+            if let Some(records) = response.history {
+                all_records.extend(records);
+            }
+
+            // Update final history ID
+            if response.history_id.is_some() {
+                final_history_id = response.history_id;
+            }
+
+            // Check for next page
+            match response.next_page_token {
+                Some(token) => page_token = Some(token),
+                None => break,
+            }
+        }
+
+        Ok(HistoryResponse {
+            history_id: final_history_id,
+            history: if all_records.is_empty() {
+                None
+            } else {
+                Some(all_records)
+            },
+            next_page_token: None,
+        })
+    }
+}
+
+/// Where a client's sync requests go
+enum Traffic {
+    Live,
+    /// Live, with responses captured for fixtures
+    Record(Arc<TrafficRecorder>),
+    /// Answered from a fixture, without network access
+    Replay(Arc<TrafficReplay>),
+}
+
 /// Gmail API client for fetching messages
 pub struct GmailClient {
     auth: GmailAuth,
+    traffic: Traffic,
+    /// Responses revalidated with conditional requests
+    validators: ValidatorCache,
 }
 
 impl GmailClient {
@@ -30,7 +141,99 @@ impl GmailClient {
 
     /// Create a new Gmail client
     pub fn new(auth: GmailAuth) -> Self {
-        Self { auth }
+        Self {
+            auth,
+            traffic: Traffic::Live,
+            validators: ValidatorCache::default(),
+        }
+    }
+
+    /// Record sync traffic (listings, messages, attachments, history and
+    /// profile) into `recorder`, scrubbed of personal data
+    pub fn with_recorder(mut self, recorder: Arc<TrafficRecorder>) -> Self {
+        self.traffic = Traffic::Record(recorder);
+        self
+    }
+
+    /// A client answering sync requests from recorded traffic
+    ///
+    /// Needs no credentials. Requests outside the recording, including all
+    /// mutations, fail.
+    pub fn replay(replay: Arc<TrafficReplay>) -> Self {
+        Self {
+            auth: GmailAuth::with_token_data(String::new(), String::new(), None),
+            traffic: Traffic::Replay(replay),
+            validators: ValidatorCache::default(),
+        }
+    }
+
+    /// Run a sync request, recording or replaying it as configured
+    ///
+    /// `request` is the API path and query relative to `BASE_URL`; `live`
+    /// performs it against Gmail.
+    fn exchange(
+        &self,
+        request: &str,
+        live: impl FnOnce() -> Result<serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        match &self.traffic {
+            Traffic::Live => live(),
+            Traffic::Replay(replay) => replay.respond(request),
+            Traffic::Record(recorder) => {
+                let result = live();
+                match &result {
+                    Ok(response) => recorder.record(request, response.clone()),
+                    Err(e) => recorder.record_error(request, e),
+                }
+                result
+            }
+        }
+    }
+
+    /// GET a rarely changing resource, revalidating the last response
+    ///
+    /// Sends the cached `ETag` as `If-None-Match`; on `304 Not Modified`
+    /// the cached body is returned without Gmail sending it again.
+    fn get_conditional(&self, request: &str) -> Result<serde_json::Value> {
+        let access_token = self.auth.get_access_token()?;
+        let url = format!("{}{}", Self::BASE_URL, request);
+        let cached = self.validators.get(request);
+
+        let mut response = with_retry(
+            || {
+                let mut get = ureq::get(&url)
+                    .header("Authorization", &format!("Bearer {}", access_token));
+                if let Some(cached) = &cached {
+                    get = get.header("If-None-Match", &cached.etag);
+                }
+                get.call()
+            },
+            3,
+        )?;
+
+        if response.status() == 304
+            && let Some(cached) = cached
+        {
+            debug!("{} not modified", request);
+            return Ok(cached.body);
+        }
+
+        let etag = response
+            .headers()
+            .get("etag")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body: serde_json::Value = response
+            .body_mut()
+            .read_json()
+            .context("Failed to read response body")?;
+        self.validators.store(request, etag, &body);
+        Ok(body)
+    }
+
+    /// Request path for fetching a full message
+    fn message_request(id: &MessageId) -> String {
+        format!("/users/me/messages/{}?format=full", id.as_str())
     }
 
     /// Get token data for database storage
@@ -50,39 +253,69 @@ impl GmailClient {
         page_token: Option<&str>,
         label_id: Option<&str>,
     ) -> Result<ListMessagesResponse> {
-        let access_token = self.auth.get_access_token()?;
-
         // Include spam and trash for full Gmail parity
-        let mut url = format!(
-            "{}/users/me/messages?maxResults={}&includeSpamTrash=true",
-            Self::BASE_URL,
+        let mut request = format!(
+            "/users/me/messages?maxResults={}&includeSpamTrash=true",
             max_results.min(500)
         );
 
         if let Some(token) = page_token {
-            url.push_str(&format!("&pageToken={}", token));
+            request.push_str(&format!("&pageToken={}", token));
         }
 
         if let Some(label) = label_id {
-            url.push_str(&format!("&labelIds={}", label));
+            request.push_str(&format!("&labelIds={}", label));
         }
 
-        let mut response = with_retry(
-            || {
-                ureq::get(&url)
-                    .header("Authorization", &format!("Bearer {}", access_token))
-                    .call()
-            },
-            3,
-        )
-        .context("Failed to send list messages request")?;
+        self.send_list_messages(&request)
+    }
 
-        let list: ListMessagesResponse = response
-            .body_mut()
-            .read_json()
-            .context("Failed to parse list messages response")?;
+    /// List IDs of messages received after `after_secs` (seconds since the
+    /// epoch), newest first
+    ///
+    /// Gmail's `after:` search has one-second resolution and may skip
+    /// messages right at the boundary, so callers should overlap with what
+    /// they already have.
+    pub fn list_messages_after(
+        &self,
+        after_secs: i64,
+        max_results: usize,
+        page_token: Option<&str>,
+    ) -> Result<ListMessagesResponse> {
+        let mut request = format!(
+            "/users/me/messages?maxResults={}&includeSpamTrash=true&q=after%3A{}",
+            max_results.min(500),
+            after_secs.max(0)
+        );
+
+        if let Some(token) = page_token {
+            request.push_str(&format!("&pageToken={}", token));
+        }
+
+        self.send_list_messages(&request)
+    }
+
+    fn send_list_messages(&self, request: &str) -> Result<ListMessagesResponse> {
+        let response = self.exchange(request, || {
+            let access_token = self.auth.get_access_token()?;
+            let url = format!("{}{}", Self::BASE_URL, request);
+            let mut response = with_retry(
+                || {
+                    ureq::get(&url)
+                        .header("Authorization", &format!("Bearer {}", access_token))
+                        .call()
+                },
+                3,
+            )
+            .context("Failed to send list messages request")?;
+
+            response
+                .body_mut()
+                .read_json()
+                .context("Failed to parse list messages response")
+        })?;
 
-        Ok(list)
+        serde_json::from_value(response).context("Failed to parse list messages response")
     }
 
     /// List ALL message IDs from the user's mailbox
@@ -180,6 +413,82 @@ impl GmailClient {
         Ok(message)
     }
 
+    /// Get a message's full RFC 2822 source by ID
+    ///
+    /// The source is returned base64url encoded in `raw`.
+    ///
+    /// # Arguments
+    /// * `id` - The message ID to fetch
+    pub fn get_message_raw(&self, id: &MessageId) -> Result<GmailRawMessage> {
+        let access_token = self.auth.get_access_token()?;
+
+        let url = format!(
+            "{}/users/me/messages/{}?format=raw",
+            Self::BASE_URL,
+            id.as_str()
+        );
+
+        let mut response = with_retry(
+            || {
+                ureq::get(&url)
+                    .header("Authorization", &format!("Bearer {}", access_token))
+                    .call()
+            },
+            3,
+        )
+        .context("Failed to send get raw message request")?;
+
+        let message: GmailRawMessage = response
+            .body_mut()
+            .read_json()
+            .context("Failed to parse raw message response")?;
+
+        Ok(message)
+    }
+
+    /// Download an attachment's bytes
+    ///
+    /// # Arguments
+    /// * `message_id` - The message the attachment belongs to
+    /// * `attachment_id` - The part's `body.attachmentId`
+    pub fn get_attachment(&self, message_id: &MessageId, attachment_id: &str) -> Result<Vec<u8>> {
+        use base64::prelude::*;
+
+        let request = format!(
+            "/users/me/messages/{}/attachments/{}",
+            message_id.as_str(),
+            attachment_id
+        );
+
+        let response = self.exchange(&request, || {
+            let access_token = self.auth.get_access_token()?;
+            let url = format!("{}{}", Self::BASE_URL, request);
+            let mut response = with_retry(
+                || {
+                    ureq::get(&url)
+                        .header("Authorization", &format!("Bearer {}", access_token))
+                        .call()
+                },
+                3,
+            )
+            .context("Failed to send get attachment request")?;
+
+            response
+                .body_mut()
+                .with_config()
+                .limit(MAX_ATTACHMENT_RESPONSE_BYTES)
+                .read_json()
+                .context("Failed to parse attachment response")
+        })?;
+
+        let attachment: AttachmentResponse =
+            serde_json::from_value(response).context("Failed to parse attachment response")?;
+
+        BASE64_URL_SAFE_NO_PAD
+            .decode(attachment.data.trim_end_matches('='))
+            .context("Attachment data is not valid base64url")
+    }
+
     /// Get multiple messages using Gmail Batch API
     ///
     /// Uses the batch endpoint to combine up to 100 requests per HTTP call,
@@ -192,6 +501,35 @@ impl GmailClient {
             return Vec::new();
         }
 
+        match &self.traffic {
+            Traffic::Live => self.fetch_messages(ids),
+            // Replayed messages are served one by one, as if fetched singly
+            Traffic::Replay(replay) => ids
+                .iter()
+                .map(|id| {
+                    let message = replay.respond(&Self::message_request(id))?;
+                    serde_json::from_value(message).context("Failed to parse message")
+                })
+                .collect(),
+            Traffic::Record(recorder) => {
+                let results = self.fetch_messages(ids);
+                for (id, result) in ids.iter().zip(&results) {
+                    let request = Self::message_request(id);
+                    match result {
+                        Ok(message) => match serde_json::to_value(message) {
+                            Ok(message) => recorder.record(&request, message),
+                            Err(e) => warn!("Failed to record message {}: {}", id.as_str(), e),
+                        },
+                        Err(e) => recorder.record_error(&request, e),
+                    }
+                }
+                results
+            }
+        }
+    }
+
+    /// Fetch messages through the batch endpoint, retrying rate-limited parts
+    fn fetch_messages(&self, ids: &[MessageId]) -> Vec<Result<GmailMessage>> {
         let access_token = match self.auth.get_access_token() {
             Ok(token) => token,
             Err(e) => {
@@ -263,15 +601,10 @@ impl GmailClient {
                 // Retry: 408 (timeout), 429 (rate limit), 403 (quota exceeded), 5xx (server errors)
                 let mut next_pending = Vec::new();
                 for ((chunk_idx, id), result) in pending.into_iter().zip(batch_results) {
-                    let is_retriable = result.as_ref().is_err_and(|e| {
-                        let msg = e.to_string();
-                        msg.contains("408")
-                            || msg.contains("429")
-                            || (msg.contains("403") && msg.to_lowercase().contains("quota"))
-                            || msg.contains("500")
-                            || msg.contains("502")
-                            || msg.contains("503")
-                            || msg.contains("504")
+                    let is_retriable = result.as_ref().is_err_and(|e| match http_status(e) {
+                        Some(408 | 429 | 500 | 502 | 503 | 504) => true,
+                        Some(403) => is_quota_message(&e.to_string()),
+                        _ => false,
                     });
 
                     if is_retriable {
@@ -297,6 +630,7 @@ impl GmailClient {
                         (backoff_ms * 2).min(16000)
                     };
                     retry_count += 1;
+                    telemetry::record_retry();
                 }
 
                 pending = next_pending;
@@ -318,7 +652,6 @@ impl GmailClient {
         batch_num: usize,
         total_batches: usize,
     ) -> Vec<Result<GmailMessage>> {
-        use log::debug;
         use std::io::Read;
 
         let boundary = format!("batch_{}", std::process::id());
@@ -344,6 +677,7 @@ impl GmailClient {
         );
 
         // Send batch request
+        let started = Instant::now();
         let response = ureq::post("https://www.googleapis.com/batch/gmail/v1")
             .header("Authorization", &format!("Bearer {}", access_token))
             .header(
@@ -351,6 +685,8 @@ impl GmailClient {
                 &format!("multipart/mixed; boundary={}", boundary),
             )
             .send(body.as_bytes());
+        telemetry::record_request(response.as_ref().err());
+        telemetry::record_batch(ids.len(), started.elapsed());
 
         match response {
             Ok(mut resp) => {
@@ -375,15 +711,16 @@ impl GmailClient {
                 self.parse_batch_response(&content_type, &response_body, ids)
             }
             Err(e) => {
-                // Extract status code for retry logic if available
-                let error_msg = match &e {
-                    ureq::Error::StatusCode(code) => {
-                        format!("Batch request failed ({}): {}", code, e)
-                    }
-                    _ => format!("Batch request failed: {}", e),
-                };
+                // Keep the status code for retry logic and error classification
+                let error_msg = format!("Batch request failed: {}", e);
                 ids.iter()
-                    .map(|_| Err(anyhow::anyhow!("{}", error_msg)))
+                    .map(|_| match &e {
+                        ureq::Error::StatusCode(code) => Err(anyhow::Error::new(
+                            ureq::Error::StatusCode(*code),
+                        )
+                        .context(error_msg.clone())),
+                        _ => Err(anyhow::anyhow!("{}", error_msg)),
+                    })
                     .collect()
             }
         }
@@ -442,7 +779,8 @@ impl GmailClient {
                     results.push(Ok(msg));
                 }
                 Ok(BatchResponse::Error(err)) => {
-                    let error_msg = match err.error.code {
+                    let code = err.error.code;
+                    let error_msg = match code {
                         408 => "Request timeout (408)".to_string(),
                         429 => "Rate limited (429)".to_string(),
                         500 => "Internal server error (500)".to_string(),
@@ -451,11 +789,13 @@ impl GmailClient {
                         504 => "Gateway timeout (504)".to_string(),
                         code => {
                             warn!("Gmail API error {}: {}", code, err.error.message);
-                            // Include code and message so retry logic can detect quota errors
+                            // Include the message so quota errors can be told apart
                             format!("API error {}: {}", code, err.error.message)
                         }
                     };
-                    results.push(Err(anyhow::anyhow!("{}", error_msg)));
+                    // Carry the part's status as if it were a request of its own
+                    let error = anyhow::Error::new(ureq::Error::StatusCode(code)).context(error_msg);
+                    results.push(Err(error));
                 }
                 Err(e) => {
                     let preview: String = json.chars().take(200).collect();
@@ -488,10 +828,62 @@ impl GmailClient {
     // === Labels API ===
 
     /// List all labels (folders) in the user's mailbox
+    ///
+    /// Revalidated with a conditional request when listed before.
     pub fn list_labels(&self) -> Result<ListLabelsResponse> {
+        let response = self
+            .get_conditional("/users/me/labels")
+            .context("Failed to send list labels request")?;
+
+        serde_json::from_value(response).context("Failed to parse labels response")
+    }
+
+    // === Settings API ===
+
+    /// List the addresses the account can send mail as
+    ///
+    /// Includes the account's own address. Revalidated with a conditional
+    /// request when listed before.
+    pub fn list_send_as(&self) -> Result<Vec<GmailSendAs>> {
+        let response = self
+            .get_conditional("/users/me/settings/sendAs")
+            .context("Failed to send list send-as request")?;
+
+        let send_as: ListSendAsResponse =
+            serde_json::from_value(response).context("Failed to parse send-as response")?;
+
+        Ok(send_as.send_as.unwrap_or_default())
+    }
+
+    /// List the user's server-side filters
+    pub fn list_filters(&self) -> Result<Vec<GmailFilter>> {
+        let access_token = self.auth.get_access_token()?;
+
+        let url = format!("{}/users/me/settings/filters", Self::BASE_URL);
+
+        let mut response = with_retry(
+            || {
+                ureq::get(&url)
+                    .header("Authorization", &format!("Bearer {}", access_token))
+                    .call()
+            },
+            3,
+        )
+        .context("Failed to send list filters request")?;
+
+        let filters: ListFiltersResponse = response
+            .body_mut()
+            .read_json()
+            .context("Failed to parse filters response")?;
+
+        Ok(filters.filter.unwrap_or_default())
+    }
+
+    /// Get the vacation responder settings
+    pub fn get_vacation(&self) -> Result<VacationSettings> {
         let access_token = self.auth.get_access_token()?;
 
-        let url = format!("{}/users/me/labels", Self::BASE_URL);
+        let url = format!("{}/users/me/settings/vacation", Self::BASE_URL);
 
         let mut response = with_retry(
             || {
@@ -501,14 +893,46 @@ impl GmailClient {
             },
             3,
         )
-        .context("Failed to send list labels request")?;
+        .context("Failed to send get vacation request")?;
 
-        let labels: ListLabelsResponse = response
+        let settings: VacationSettings = response
             .body_mut()
             .read_json()
-            .context("Failed to parse labels response")?;
+            .context("Failed to parse vacation settings")?;
 
-        Ok(labels)
+        Ok(settings)
+    }
+
+    /// Replace the vacation responder settings
+    ///
+    /// Returns the settings as Gmail stored them.
+    pub fn update_vacation(&self, settings: &VacationSettings) -> Result<VacationSettings> {
+        settings.validate()?;
+
+        let access_token = self.auth.get_access_token()?;
+
+        let url = format!("{}/users/me/settings/vacation", Self::BASE_URL);
+
+        // PUT replaces the whole resource, so retrying is safe
+        let mut response = with_retry(
+            || {
+                ureq::put(&url)
+                    .header("Authorization", &format!("Bearer {}", access_token))
+                    .header("Content-Type", "application/json")
+                    .send_json(settings)
+            },
+            3,
+        )
+        .context("Failed to update vacation settings")?;
+
+        let settings: VacationSettings = response
+            .body_mut()
+            .read_json()
+            .context("Failed to parse vacation settings")?;
+
+        info!("Updated vacation responder (enabled={})", settings.enable_auto_reply);
+
+        Ok(settings)
     }
 
     // === Phase 2: History API Methods ===
@@ -529,19 +953,32 @@ impl GmailClient {
         start_history_id: &str,
         page_token: Option<&str>,
     ) -> Result<HistoryResponse> {
-        let access_token = self.auth.get_access_token()?;
-
         // Request all relevant history types: new messages and label changes
-        let mut url = format!(
-            "{}/users/me/history?startHistoryId={}&historyTypes=messageAdded&historyTypes=labelAdded&historyTypes=labelRemoved",
-            Self::BASE_URL,
+        let mut request = format!(
+            "/users/me/history?startHistoryId={}&historyTypes=messageAdded&historyTypes=labelAdded&historyTypes=labelRemoved",
             start_history_id
         );
 
         if let Some(token) = page_token {
-            url.push_str(&format!("&pageToken={}", token));
+            request.push_str(&format!("&pageToken={}", token));
         }
 
+        let response = self
+            .exchange(&request, || self.fetch_history(&request))
+            .map_err(|e| match e.downcast_ref::<ureq::Error>() {
+                // A replayed expiry arrives as its status code
+                Some(ureq::Error::StatusCode(404 | 400)) => HistoryExpiredError.into(),
+                _ => e,
+            })?;
+
+        serde_json::from_value(response).context("Failed to parse history response")
+    }
+
+    /// Perform a history.list request against Gmail
+    fn fetch_history(&self, request: &str) -> Result<serde_json::Value> {
+        let access_token = self.auth.get_access_token()?;
+        let url = format!("{}{}", Self::BASE_URL, request);
+
         // Retry loop with special handling for history expired errors
         let mut delay = Duration::from_millis(100);
         let max_retries = 3u32;
@@ -550,14 +987,14 @@ impl GmailClient {
             let response = ureq::get(&url)
                 .header("Authorization", &format!("Bearer {}", access_token))
                 .call();
+            telemetry::record_request(response.as_ref().err());
 
             match response {
                 Ok(mut resp) => {
-                    let history: HistoryResponse = resp
+                    return resp
                         .body_mut()
                         .read_json()
-                        .context("Failed to parse history response")?;
-                    return Ok(history);
+                        .context("Failed to parse history response");
                 }
                 Err(ureq::Error::StatusCode(404)) | Err(ureq::Error::StatusCode(400)) => {
                     // History ID expired, invalid, or malformed - triggers full resync
@@ -565,83 +1002,41 @@ impl GmailClient {
                     return Err(HistoryExpiredError.into());
                 }
                 Err(ref e) if is_retriable_error(e) && attempt < max_retries - 1 => {
+                    telemetry::record_retry();
                     let jitter = Duration::from_millis(rand_jitter());
                     std::thread::sleep(delay + jitter);
                     delay = (delay * 2).min(Duration::from_secs(16));
                 }
-                Err(e) => return Err(anyhow::anyhow!("Failed to fetch history: {}", e)),
+                Err(e) => {
+                    record_diagnostic(
+                        DiagnosticKind::Api,
+                        None,
+                        format!("history.list failed: {}", e),
+                    );
+                    return Err(anyhow::anyhow!("Failed to fetch history: {}", e));
+                }
             }
         }
 
         Err(anyhow::anyhow!("Failed to fetch history after {} retries", max_retries))
     }
 
-    /// List all history pages since a given historyId
-    ///
-    /// Automatically handles pagination to fetch all history records.
-    pub fn list_history_all(&self, start_history_id: &str) -> Result<HistoryResponse> {
-        let mut all_records = Vec::new();
-        let mut final_history_id = None;
-        let mut page_token = None;
-
-        loop {
-            let response = self.list_history(start_history_id, page_token.as_deref())?;
-
-            // Collect history records
-            if let Some(records) = response.history {
-                all_records.extend(records);
-            }
-
-            // Update final history ID
-            if response.history_id.is_some() {
-                final_history_id = response.history_id;
-            }
-
-            // Check for next page
-            match response.next_page_token {
-                Some(token) => page_token = Some(token),
-                None => break,
-            }
-        }
-
-        Ok(HistoryResponse {
-            history_id: final_history_id,
-            history: if all_records.is_empty() {
-                None
-            } else {
-                Some(all_records)
-            },
-            next_page_token: None,
-        })
-    }
-
     // === Profile Methods ===
 
     /// Get the user's Gmail profile
     ///
     /// Returns profile information including the current history ID,
-    /// which is needed for incremental sync.
+    /// which is needed for incremental sync. Revalidated with a conditional
+    /// request, so an idle mailbox's profile isn't sent again.
     pub fn get_profile(&self) -> Result<ProfileResponse> {
-        let access_token = self.auth.get_access_token()?;
+        let request = "/users/me/profile";
 
-        let url = format!("{}/users/me/profile", Self::BASE_URL);
+        let response = self.exchange(request, || {
+            self.get_conditional(request)
+                .context("Failed to get Gmail profile")
+        })?;
 
-        let mut response = with_retry(
-            || {
-                ureq::get(&url)
-                    .header("Authorization", &format!("Bearer {}", access_token))
-                    .call()
-            },
-            3,
-        )
-        .context("Failed to get Gmail profile")?;
-
-        let profile: ProfileResponse = response
-            .body_mut()
-            .read_json()
-            .context("Failed to parse profile response")?;
-
-        Ok(profile)
+        serde_json::from_value(response).context("Failed to parse profile response")
     }
 
     // === Message Mutation Methods ===
@@ -752,6 +1147,224 @@ impl GmailClient {
 
         Ok(())
     }
+
+    /// Send an RFC 2822 message from the authenticated account
+    ///
+    /// Returns the ID and thread ID of the sent message.
+    pub fn send_message(&self, raw: &str) -> Result<MessageRef> {
+        self.send_message_in_thread(raw, None)
+    }
+
+    /// Send an RFC 2822 message, adding it to `thread_id` if given
+    ///
+    /// Gmail only threads the message if its subject matches the thread's.
+    pub fn send_message_in_thread(
+        &self,
+        raw: &str,
+        thread_id: Option<&ThreadId>,
+    ) -> Result<MessageRef> {
+        use base64::prelude::*;
+
+        let access_token = self.auth.get_access_token()?;
+
+        let url = format!("{}/users/me/messages/send", Self::BASE_URL);
+
+        let request = SendMessageRequest {
+            raw: BASE64_URL_SAFE_NO_PAD.encode(raw),
+            thread_id: thread_id.map(|id| id.as_str().to_string()),
+        };
+
+        // Not retried: a request that timed out may still have been sent
+        let mut response = with_retry(
+            || {
+                ureq::post(&url)
+                    .header("Authorization", &format!("Bearer {}", access_token))
+                    .header("Content-Type", "application/json")
+                    .send_json(&request)
+            },
+            1,
+        )
+        .context("Failed to send message")?;
+
+        let sent: MessageRef = response
+            .body_mut()
+            .read_json()
+            .context("Failed to parse send message response")?;
+
+        info!("Sent message {}", sent.id);
+
+        Ok(sent)
+    }
+
+    /// Save an RFC 2822 message as a new draft, in `thread_id` if given
+    pub fn create_draft(&self, raw: &str, thread_id: Option<&ThreadId>) -> Result<DraftRef> {
+        let access_token = self.auth.get_access_token()?;
+
+        let url = format!("{}/users/me/drafts", Self::BASE_URL);
+        let request = draft_request(raw, thread_id);
+
+        // Not retried: a timed-out request may still have created the draft
+        let mut response = with_retry(
+            || {
+                ureq::post(&url)
+                    .header("Authorization", &format!("Bearer {}", access_token))
+                    .header("Content-Type", "application/json")
+                    .send_json(&request)
+            },
+            1,
+        )
+        .context("Failed to create draft")?;
+
+        let draft: DraftRef = response
+            .body_mut()
+            .read_json()
+            .context("Failed to parse create draft response")?;
+
+        debug!("Created draft {}", draft.id);
+
+        Ok(draft)
+    }
+
+    /// Replace the content of an existing draft
+    pub fn update_draft(
+        &self,
+        draft_id: &str,
+        raw: &str,
+        thread_id: Option<&ThreadId>,
+    ) -> Result<DraftRef> {
+        let access_token = self.auth.get_access_token()?;
+
+        let url = format!("{}/users/me/drafts/{}", Self::BASE_URL, draft_id);
+        let request = draft_request(raw, thread_id);
+
+        // PUT replaces the whole draft, so retrying is safe
+        let mut response = with_retry(
+            || {
+                ureq::put(&url)
+                    .header("Authorization", &format!("Bearer {}", access_token))
+                    .header("Content-Type", "application/json")
+                    .send_json(&request)
+            },
+            3,
+        )
+        .context("Failed to update draft")?;
+
+        let draft: DraftRef = response
+            .body_mut()
+            .read_json()
+            .context("Failed to parse update draft response")?;
+
+        debug!("Updated draft {}", draft.id);
+
+        Ok(draft)
+    }
+
+    /// Permanently delete a draft
+    pub fn delete_draft(&self, draft_id: &str) -> Result<()> {
+        let access_token = self.auth.get_access_token()?;
+
+        let url = format!("{}/users/me/drafts/{}", Self::BASE_URL, draft_id);
+
+        with_retry(
+            || {
+                ureq::delete(&url)
+                    .header("Authorization", &format!("Bearer {}", access_token))
+                    .call()
+            },
+            3,
+        )
+        .context("Failed to delete draft")?;
+
+        info!("Deleted draft {}", draft_id);
+
+        Ok(())
+    }
+
+    /// Send a saved draft, which removes it from drafts
+    ///
+    /// Returns the ID and thread ID of the sent message.
+    pub fn send_draft(&self, draft_id: &str) -> Result<MessageRef> {
+        let access_token = self.auth.get_access_token()?;
+
+        let url = format!("{}/users/me/drafts/send", Self::BASE_URL);
+        let request = SendDraftRequest {
+            id: draft_id.to_string(),
+        };
+
+        // Not retried: a request that timed out may still have been sent
+        let mut response = with_retry(
+            || {
+                ureq::post(&url)
+                    .header("Authorization", &format!("Bearer {}", access_token))
+                    .header("Content-Type", "application/json")
+                    .send_json(&request)
+            },
+            1,
+        )
+        .context("Failed to send draft")?;
+
+        let sent: MessageRef = response
+            .body_mut()
+            .read_json()
+            .context("Failed to parse send draft response")?;
+
+        info!("Sent draft {} as message {}", draft_id, sent.id);
+
+        Ok(sent)
+    }
+}
+
+impl GmailApi for GmailClient {
+    fn list_messages(
+        &self,
+        max_results: usize,
+        page_token: Option<&str>,
+        label_id: Option<&str>,
+    ) -> Result<ListMessagesResponse> {
+        GmailClient::list_messages(self, max_results, page_token, label_id)
+    }
+
+    fn list_messages_after(
+        &self,
+        after_secs: i64,
+        max_results: usize,
+        page_token: Option<&str>,
+    ) -> Result<ListMessagesResponse> {
+        GmailClient::list_messages_after(self, after_secs, max_results, page_token)
+    }
+
+    fn get_messages_batch(&self, ids: &[MessageId]) -> Vec<Result<GmailMessage>> {
+        GmailClient::get_messages_batch(self, ids)
+    }
+
+    fn get_attachment(&self, message_id: &MessageId, attachment_id: &str) -> Result<Vec<u8>> {
+        GmailClient::get_attachment(self, message_id, attachment_id)
+    }
+
+    fn list_history(&self, start_history_id: &str, page_token: Option<&str>) -> Result<HistoryResponse> {
+        GmailClient::list_history(self, start_history_id, page_token)
+    }
+
+    fn get_profile(&self) -> Result<ProfileResponse> {
+        GmailClient::get_profile(self)
+    }
+
+    fn has_quota(&self) -> bool {
+        // Replayed traffic never reaches Gmail
+        !matches!(self.traffic, Traffic::Replay(_))
+    }
+}
+
+/// Build the request body for saving a draft
+fn draft_request(raw: &str, thread_id: Option<&ThreadId>) -> DraftRequest {
+    use base64::prelude::*;
+
+    DraftRequest {
+        message: SendMessageRequest {
+            raw: BASE64_URL_SAFE_NO_PAD.encode(raw),
+            thread_id: thread_id.map(|id| id.as_str().to_string()),
+        },
+    }
 }
 
 /// Generate a pseudo-random jitter value (0-100ms)
@@ -776,6 +1389,36 @@ fn is_retriable_error(e: &ureq::Error) -> bool {
     )
 }
 
+/// Whether a failed Gmail request may succeed if tried again later
+///
+/// True when the network was unreachable or timed out, or Gmail answered
+/// with a transient status (rate limits, server errors). Rejections such as
+/// 400 or 404 are permanent.
+pub fn is_transient_error(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<ureq::Error>())
+        .any(|e| {
+            is_retriable_error(e)
+                || matches!(
+                    e,
+                    ureq::Error::Io(_)
+                        | ureq::Error::Timeout(_)
+                        | ureq::Error::HostNotFound
+                        | ureq::Error::ConnectionFailed
+                        | ureq::Error::BodyStalled
+                )
+        })
+}
+
+/// HTTP status behind a failed Gmail request, if Gmail answered at all
+pub(crate) fn http_status(error: &anyhow::Error) -> Option<u16> {
+    error.chain().find_map(|cause| match cause.downcast_ref::<ureq::Error>() {
+        Some(ureq::Error::StatusCode(status)) => Some(*status),
+        _ => None,
+    })
+}
+
 /// Execute an HTTP request with retry for transient errors
 fn with_retry<T, F>(mut f: F, max_retries: u32) -> Result<T>
 where
@@ -784,14 +1427,25 @@ where
     let mut delay = Duration::from_millis(100);
 
     for attempt in 0..max_retries {
-        match f() {
+        let result = f();
+        telemetry::record_request(result.as_ref().err());
+        match result {
             Ok(result) => return Ok(result),
             Err(e) if is_retriable_error(&e) && attempt < max_retries - 1 => {
+                telemetry::record_retry();
                 let jitter = Duration::from_millis(rand_jitter());
                 std::thread::sleep(delay + jitter);
                 delay = (delay * 2).min(Duration::from_secs(16));
             }
-            Err(e) => return Err(anyhow::anyhow!("{}", e)),
+            Err(e) => {
+                record_diagnostic(
+                    DiagnosticKind::Api,
+                    None,
+                    format!("Request failed after {} attempt(s): {}", attempt + 1, e),
+                );
+                // Keep the ureq error so callers can tell transient failures apart
+                return Err(e.into());
+            }
         }
     }
 
@@ -0,0 +1,76 @@
+//! Conditional requests for rarely changing resources
+//!
+//! Labels, the profile and send-as settings are read on every sync cycle
+//! but seldom change. Gmail tags those responses with an `ETag`; the client
+//! keeps the last response per request and revalidates it with
+//! `If-None-Match`, so an unchanged resource comes back as an empty
+//! `304 Not Modified` instead of the full body.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// A response body and the validator Gmail sent with it
+#[derive(Debug, Clone)]
+pub(crate) struct Validated {
+    pub etag: String,
+    pub body: serde_json::Value,
+}
+
+/// Last validated response per request path
+///
+/// Lives as long as its client, so it carries across sync cycles wherever
+/// the app keeps the client between them.
+#[derive(Debug, Default)]
+pub(crate) struct ValidatorCache {
+    entries: Mutex<HashMap<String, Validated>>,
+}
+
+impl ValidatorCache {
+    /// The cached response for `request`, to revalidate
+    pub fn get(&self, request: &str) -> Option<Validated> {
+        self.entries.lock().unwrap().get(request).cloned()
+    }
+
+    /// Remember a response, or forget the old one if it came without a
+    /// validator
+    pub fn store(&self, request: &str, etag: Option<String>, body: &serde_json::Value) {
+        let mut entries = self.entries.lock().unwrap();
+        match etag {
+            Some(etag) => {
+                entries.insert(
+                    request.to_string(),
+                    Validated {
+                        etag,
+                        body: body.clone(),
+                    },
+                );
+            }
+            None => {
+                entries.remove(request);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validator_cache() {
+        let cache = ValidatorCache::default();
+        assert!(cache.get("/users/me/labels").is_none());
+
+        let body = json!({"labels": [{"id": "INBOX", "name": "INBOX"}]});
+        cache.store("/users/me/labels", Some("\"v1\"".to_string()), &body);
+        let cached = cache.get("/users/me/labels").unwrap();
+        assert_eq!(cached.etag, "\"v1\"");
+        assert_eq!(cached.body, body);
+        assert!(cache.get("/users/me/profile").is_none());
+
+        // A response without an ETag can't be revalidated
+        cache.store("/users/me/labels", None, &body);
+        assert!(cache.get("/users/me/labels").is_none());
+    }
+}
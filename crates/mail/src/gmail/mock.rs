@@ -0,0 +1,702 @@
+//! In-memory Gmail API for deterministic sync tests
+//!
+//! [`MockGmail`] implements [`GmailApi`] over a scripted mailbox, so the sync
+//! engine runs end to end without credentials or network. Listings are paged,
+//! deliveries and label changes are recorded as history, and requests can be
+//! made to fail (rate limits, server errors, dropped connections) or history
+//! to expire, which covers interrupted and resynced syncs.
+//!
+//! Failures are returned as-is, as if [`GmailClient`](super::GmailClient)
+//! had already used up its retries.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+
+use anyhow::{Context, Result};
+use base64::prelude::*;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use super::api::{
+    GmailMessage, HistoryResponse, ListMessagesResponse, MessageRef, ProfileResponse,
+};
+use super::client::{GmailApi, HistoryExpiredError};
+use crate::models::MessageId;
+
+/// History ID of a new mock mailbox
+const INITIAL_HISTORY_ID: u64 = 1000;
+
+/// Internal date (ms) of the first message without an explicit date
+const BASE_INTERNAL_DATE: i64 = 1_700_000_000_000;
+
+/// Requests that can be counted and made to fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MockEndpoint {
+    ListMessages,
+    /// One message of a batch fetch
+    GetMessage,
+    GetAttachment,
+    /// One page of history
+    ListHistory,
+    GetProfile,
+}
+
+/// A scripted request failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockFailure {
+    /// Gmail answered with this HTTP status, e.g. 429 when rate limited
+    Status(u16),
+    /// The connection dropped before Gmail answered
+    Network,
+}
+
+impl MockFailure {
+    fn into_error(self) -> anyhow::Error {
+        match self {
+            Self::Status(code) => ureq::Error::StatusCode(code).into(),
+            Self::Network => ureq::Error::ConnectionFailed.into(),
+        }
+    }
+}
+
+/// An attachment on a [`MockMessage`]
+#[derive(Debug, Clone)]
+struct MockAttachment {
+    filename: String,
+    mime_type: String,
+    data: Vec<u8>,
+}
+
+/// A message to put in a [`MockGmail`] mailbox
+#[derive(Debug, Clone)]
+pub struct MockMessage {
+    id: String,
+    thread_id: String,
+    from: String,
+    to: String,
+    subject: String,
+    body: String,
+    labels: Vec<String>,
+    internal_date: Option<i64>,
+    attachments: Vec<MockAttachment>,
+}
+
+impl MockMessage {
+    /// A plain text message in the inbox
+    pub fn new(id: impl Into<String>, thread_id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            thread_id: thread_id.into(),
+            from: "Sender <sender@example.com>".to_string(),
+            to: "me@example.com".to_string(),
+            subject: String::new(),
+            body: String::new(),
+            labels: vec!["INBOX".to_string()],
+            internal_date: None,
+            attachments: Vec::new(),
+        }
+    }
+
+    pub fn from(mut self, from: impl Into<String>) -> Self {
+        self.from = from.into();
+        self
+    }
+
+    pub fn to(mut self, to: impl Into<String>) -> Self {
+        self.to = to.into();
+        self
+    }
+
+    pub fn subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = subject.into();
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Replace the message's labels
+    pub fn labels(mut self, labels: &[&str]) -> Self {
+        self.labels = labels.iter().map(|l| l.to_string()).collect();
+        self
+    }
+
+    /// Set the receive time in milliseconds since the epoch
+    ///
+    /// Without one, messages get increasing times in insertion order.
+    pub fn internal_date(mut self, millis: i64) -> Self {
+        self.internal_date = Some(millis);
+        self
+    }
+
+    pub fn attachment(
+        mut self,
+        filename: impl Into<String>,
+        mime_type: impl Into<String>,
+        data: Vec<u8>,
+    ) -> Self {
+        self.attachments.push(MockAttachment {
+            filename: filename.into(),
+            mime_type: mime_type.into(),
+            data,
+        });
+        self
+    }
+}
+
+/// Mailbox contents and scripted behavior
+#[derive(Default)]
+struct Mailbox {
+    /// Messages as Gmail API JSON, oldest first
+    messages: Vec<Value>,
+    /// Position in `messages` by message ID
+    positions: HashMap<String, usize>,
+    /// Attachment bytes by attachment ID
+    attachments: HashMap<String, Vec<u8>>,
+    history_id: u64,
+    /// History records as Gmail API JSON, with their IDs
+    history: Vec<(u64, Value)>,
+    /// History before this ID has expired
+    oldest_history_id: u64,
+    /// Messages inserted so far, for default dates
+    inserted: i64,
+    /// Scripted failures by endpoint and 1-based request number
+    failures: HashMap<MockEndpoint, BTreeMap<usize, MockFailure>>,
+    requests: HashMap<MockEndpoint, usize>,
+}
+
+impl Mailbox {
+    /// Count a request and return its scripted failure, if any
+    fn request(&mut self, endpoint: MockEndpoint) -> Result<()> {
+        let count = self.requests.entry(endpoint).or_default();
+        *count += 1;
+        let count = *count;
+        match self
+            .failures
+            .get_mut(&endpoint)
+            .and_then(|f| f.remove(&count))
+        {
+            Some(failure) => Err(failure.into_error()),
+            None => Ok(()),
+        }
+    }
+
+    fn message(&self, id: &str) -> Option<&Value> {
+        self.positions.get(id).map(|&i| &self.messages[i])
+    }
+
+    fn message_mut(&mut self, id: &str) -> Option<&mut Value> {
+        self.positions.get(id).map(|&i| &mut self.messages[i])
+    }
+
+    /// Rebuild `positions` after messages were replaced or removed
+    fn reindex(&mut self) {
+        self.positions = self
+            .messages
+            .iter()
+            .enumerate()
+            .filter_map(|(i, m)| Some((m["id"].as_str()?.to_string(), i)))
+            .collect();
+    }
+
+    /// Append a history record of `kind` for a message
+    fn record(&mut self, kind: &str, message: &Value, label_ids: Option<&[String]>) {
+        self.history_id += 1;
+        let mut change = json!({
+            "message": {
+                "id": message["id"],
+                "threadId": message["threadId"],
+                "labelIds": message["labelIds"],
+            }
+        });
+        if let Some(label_ids) = label_ids {
+            change["labelIds"] = json!(label_ids);
+        }
+        let mut record = json!({ "id": self.history_id.to_string() });
+        record[kind] = json!([change]);
+        self.history.push((self.history_id, record));
+    }
+
+    /// Gmail API JSON for a new message, registering its attachments
+    fn insert(&mut self, message: MockMessage) -> Value {
+        self.inserted += 1;
+        let internal_date = message
+            .internal_date
+            .unwrap_or(BASE_INTERNAL_DATE + self.inserted * 60_000);
+
+        let headers = json!([
+            { "name": "From", "value": message.from },
+            { "name": "To", "value": message.to },
+            { "name": "Subject", "value": message.subject },
+            { "name": "Message-ID", "value": format!("<{}@mock.example.com>", message.id) },
+        ]);
+        let text = json!({
+            "size": message.body.len(),
+            "data": BASE64_URL_SAFE_NO_PAD.encode(&message.body),
+        });
+        let payload = if message.attachments.is_empty() {
+            json!({ "mimeType": "text/plain", "headers": headers, "body": text })
+        } else {
+            let mut parts = vec![json!({ "partId": "0", "mimeType": "text/plain", "body": text })];
+            for (i, attachment) in message.attachments.into_iter().enumerate() {
+                let attachment_id = format!("{}-att-{}", message.id, i);
+                parts.push(json!({
+                    "partId": (i + 1).to_string(),
+                    "mimeType": attachment.mime_type,
+                    "filename": attachment.filename,
+                    "body": { "size": attachment.data.len(), "attachmentId": attachment_id },
+                }));
+                self.attachments.insert(attachment_id, attachment.data);
+            }
+            json!({ "mimeType": "multipart/mixed", "headers": headers, "parts": parts })
+        };
+
+        let snippet: String = message.body.chars().take(100).collect();
+        let value = json!({
+            "id": message.id,
+            "threadId": message.thread_id,
+            "labelIds": message.labels,
+            "snippet": snippet,
+            "internalDate": internal_date.to_string(),
+            "sizeEstimate": message.body.len(),
+            "payload": payload,
+        });
+        self.positions.insert(message.id, self.messages.len());
+        self.messages.push(value.clone());
+        value
+    }
+}
+
+/// A mailbox saved as JSON, for [`MockGmail::from_fixture`]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Fixture {
+    #[serde(default)]
+    email_address: Option<String>,
+    #[serde(default)]
+    history_id: Option<String>,
+    /// Full messages as returned by `messages.get`
+    messages: Vec<Value>,
+}
+
+/// Scripted Gmail mailbox implementing [`GmailApi`]
+pub struct MockGmail {
+    email: String,
+    page_size: usize,
+    mailbox: Mutex<Mailbox>,
+}
+
+impl Default for MockGmail {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockGmail {
+    /// An empty mailbox for me@example.com
+    pub fn new() -> Self {
+        Self {
+            email: "me@example.com".to_string(),
+            page_size: 500,
+            mailbox: Mutex::new(Mailbox {
+                history_id: INITIAL_HISTORY_ID,
+                oldest_history_id: INITIAL_HISTORY_ID,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Load a mailbox from JSON: `{"historyId": "...", "messages": [...]}`
+    /// with messages in `messages.get` format, oldest first
+    pub fn from_fixture(json: &str) -> Result<Self> {
+        let fixture: Fixture = serde_json::from_str(json).context("Invalid mock Gmail fixture")?;
+        let mut mock = Self::new();
+        if let Some(email) = fixture.email_address {
+            mock.email = email;
+        }
+        {
+            let mailbox = mock.mailbox.get_mut().unwrap();
+            if let Some(history_id) = fixture.history_id {
+                mailbox.history_id = history_id.parse().context("Invalid fixture historyId")?;
+                mailbox.oldest_history_id = mailbox.history_id;
+            }
+            mailbox.messages = fixture.messages;
+            mailbox.reindex();
+        }
+        Ok(mock)
+    }
+
+    /// Return at most `page_size` messages or history records per page
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Add a message that was already there before syncing started
+    pub fn with_message(self, message: MockMessage) -> Self {
+        self.lock().insert(message);
+        self
+    }
+
+    /// Deliver a new message, recording it in history
+    pub fn deliver(&self, message: MockMessage) {
+        let mut mailbox = self.lock();
+        let value = mailbox.insert(message);
+        mailbox.record("messagesAdded", &value, None);
+    }
+
+    /// Add labels to a message, recording the change in history
+    pub fn add_labels(&self, message_id: &str, labels: &[&str]) {
+        self.change_labels(message_id, labels, true);
+    }
+
+    /// Remove labels from a message, recording the change in history
+    pub fn remove_labels(&self, message_id: &str, labels: &[&str]) {
+        self.change_labels(message_id, labels, false);
+    }
+
+    fn change_labels(&self, message_id: &str, labels: &[&str], add: bool) {
+        let mut mailbox = self.lock();
+        let Some(message) = mailbox.message_mut(message_id) else {
+            return;
+        };
+        let mut current: Vec<String> =
+            serde_json::from_value(message["labelIds"].clone()).unwrap_or_default();
+        let changed: Vec<String> = labels
+            .iter()
+            .filter(|label| current.iter().any(|l| l == *label) != add)
+            .map(|label| label.to_string())
+            .collect();
+        if changed.is_empty() {
+            return;
+        }
+        if add {
+            current.extend(changed.iter().cloned());
+        } else {
+            current.retain(|l| !changed.contains(l));
+        }
+        message["labelIds"] = json!(current);
+
+        let message = message.clone();
+        let kind = if add { "labelsAdded" } else { "labelsRemoved" };
+        mailbox.record(kind, &message, Some(&changed));
+    }
+
+    /// Move a message to another thread, as Gmail does when a subject or
+    /// References change rethreads it, recording it in history
+    pub fn move_message(&self, message_id: &str, thread_id: &str) {
+        let mut mailbox = self.lock();
+        let Some(message) = mailbox.message_mut(message_id) else {
+            return;
+        };
+        message["threadId"] = json!(thread_id);
+        let message = message.clone();
+        mailbox.record("messagesAdded", &message, None);
+    }
+
+    /// Delete a message permanently, recording it in history
+    pub fn delete_message(&self, message_id: &str) {
+        let mut mailbox = self.lock();
+        let Some(index) = mailbox.positions.get(message_id).copied() else {
+            return;
+        };
+        let message = mailbox.messages.remove(index);
+        mailbox.reindex();
+        mailbox.record("messagesDeleted", &message, None);
+    }
+
+    /// Drop all history, so syncing from any earlier history ID fails with
+    /// [`HistoryExpiredError`]
+    pub fn expire_history(&self) {
+        let mut mailbox = self.lock();
+        mailbox.history.clear();
+        mailbox.history_id += 1;
+        mailbox.oldest_history_id = mailbox.history_id;
+    }
+
+    /// Fail the next request to `endpoint`
+    ///
+    /// Failures queue up, so calling this twice fails the next two requests.
+    pub fn fail_next(&self, endpoint: MockEndpoint, failure: MockFailure) {
+        let mut mailbox = self.lock();
+        let mut request = mailbox.requests.get(&endpoint).copied().unwrap_or(0) + 1;
+        let failures = mailbox.failures.entry(endpoint).or_default();
+        while failures.contains_key(&request) {
+            request += 1;
+        }
+        failures.insert(request, failure);
+    }
+
+    /// Fail the `request`th request to `endpoint`, counting from 1 since
+    /// the mock was created, e.g. the second page of a listing
+    pub fn fail_request(&self, endpoint: MockEndpoint, request: usize, failure: MockFailure) {
+        self.lock()
+            .failures
+            .entry(endpoint)
+            .or_default()
+            .insert(request, failure);
+    }
+
+    /// Number of requests made to `endpoint`, including failed ones
+    pub fn request_count(&self, endpoint: MockEndpoint) -> usize {
+        self.lock().requests.get(&endpoint).copied().unwrap_or(0)
+    }
+
+    /// The mailbox's current history ID
+    pub fn history_id(&self) -> String {
+        self.lock().history_id.to_string()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Mailbox> {
+        self.mailbox.lock().unwrap()
+    }
+
+    /// One page of the messages `filter` keeps, newest first like Gmail
+    fn list_matching(
+        &self,
+        max_results: usize,
+        page_token: Option<&str>,
+        filter: impl Fn(&Value) -> bool,
+    ) -> Result<ListMessagesResponse> {
+        let mut mailbox = self.lock();
+        mailbox.request(MockEndpoint::ListMessages)?;
+
+        let matching: Vec<&Value> = mailbox
+            .messages
+            .iter()
+            .rev()
+            .filter(|m| filter(m))
+            .collect();
+
+        let offset = page_offset(page_token)?;
+        let end = (offset + max_results.min(self.page_size)).min(matching.len());
+        let messages: Vec<MessageRef> = matching
+            .get(offset..end)
+            .unwrap_or_default()
+            .iter()
+            .map(|m| MessageRef {
+                id: m["id"].as_str().unwrap_or_default().to_string(),
+                thread_id: m["threadId"].as_str().unwrap_or_default().to_string(),
+            })
+            .collect();
+
+        Ok(ListMessagesResponse {
+            next_page_token: (end < matching.len()).then(|| end.to_string()),
+            result_size_estimate: Some(matching.len() as u32),
+            messages: if messages.is_empty() {
+                None
+            } else {
+                Some(messages)
+            },
+        })
+    }
+}
+
+/// Offset encoded in a mock page token
+fn page_offset(page_token: Option<&str>) -> Result<usize> {
+    page_token
+        .map(|token| token.parse().context("Invalid mock page token"))
+        .transpose()
+        .map(|offset| offset.unwrap_or(0))
+}
+
+impl GmailApi for MockGmail {
+    fn list_messages(
+        &self,
+        max_results: usize,
+        page_token: Option<&str>,
+        label_id: Option<&str>,
+    ) -> Result<ListMessagesResponse> {
+        self.list_matching(max_results, page_token, |m| {
+            label_id.is_none_or(|label| {
+                m["labelIds"]
+                    .as_array()
+                    .is_some_and(|ids| ids.iter().any(|id| id == label))
+            })
+        })
+    }
+
+    fn list_messages_after(
+        &self,
+        after_secs: i64,
+        max_results: usize,
+        page_token: Option<&str>,
+    ) -> Result<ListMessagesResponse> {
+        self.list_matching(max_results, page_token, |m| {
+            m["internalDate"]
+                .as_str()
+                .and_then(|millis| millis.parse::<i64>().ok())
+                .is_some_and(|millis| millis / 1000 > after_secs)
+        })
+    }
+
+    fn get_messages_batch(&self, ids: &[MessageId]) -> Vec<Result<GmailMessage>> {
+        let mut mailbox = self.lock();
+        ids.iter()
+            .map(|id| {
+                mailbox.request(MockEndpoint::GetMessage)?;
+                let message = mailbox
+                    .message(id.as_str())
+                    .cloned()
+                    .ok_or(ureq::Error::StatusCode(404))?;
+                serde_json::from_value(message).context("Invalid mock message")
+            })
+            .collect()
+    }
+
+    fn get_attachment(&self, _message_id: &MessageId, attachment_id: &str) -> Result<Vec<u8>> {
+        let mut mailbox = self.lock();
+        mailbox.request(MockEndpoint::GetAttachment)?;
+        mailbox
+            .attachments
+            .get(attachment_id)
+            .cloned()
+            .ok_or_else(|| ureq::Error::StatusCode(404).into())
+    }
+
+    fn list_history(
+        &self,
+        start_history_id: &str,
+        page_token: Option<&str>,
+    ) -> Result<HistoryResponse> {
+        let mut mailbox = self.lock();
+        mailbox.request(MockEndpoint::ListHistory)?;
+
+        let start: u64 = match start_history_id.parse() {
+            Ok(start) if start >= mailbox.oldest_history_id => start,
+            _ => return Err(HistoryExpiredError.into()),
+        };
+        let records: Vec<&Value> = mailbox
+            .history
+            .iter()
+            .filter(|(id, _)| *id > start)
+            .map(|(_, record)| record)
+            .collect();
+
+        let offset = page_offset(page_token)?;
+        let end = (offset + self.page_size).min(records.len());
+        let page = records.get(offset..end).unwrap_or_default();
+
+        serde_json::from_value(json!({
+            "historyId": mailbox.history_id.to_string(),
+            "history": page,
+            "nextPageToken": (end < records.len()).then(|| end.to_string()),
+        }))
+        .context("Invalid mock history")
+    }
+
+    fn get_profile(&self) -> Result<ProfileResponse> {
+        let mut mailbox = self.lock();
+        mailbox.request(MockEndpoint::GetProfile)?;
+
+        let threads: HashSet<&str> = mailbox
+            .messages
+            .iter()
+            .filter_map(|m| m["threadId"].as_str())
+            .collect();
+        Ok(ProfileResponse {
+            email_address: self.email.clone(),
+            messages_total: Some(mailbox.messages.len() as u32),
+            threads_total: Some(threads.len() as u32),
+            history_id: mailbox.history_id.to_string(),
+        })
+    }
+
+    fn has_quota(&self) -> bool {
+        // Scripted 429s stand in for rate limits
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gmail::is_transient_error;
+
+    fn listed_ids(response: &ListMessagesResponse) -> Vec<String> {
+        response
+            .messages
+            .iter()
+            .flatten()
+            .map(|m| m.id.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_list_messages_pages_newest_first() {
+        let gmail = MockGmail::new()
+            .with_page_size(2)
+            .with_message(MockMessage::new("m1", "t1"))
+            .with_message(MockMessage::new("m2", "t1"))
+            .with_message(MockMessage::new("m3", "t2").labels(&["SENT"]));
+
+        let first = gmail.list_messages(500, None, None).unwrap();
+        assert_eq!(listed_ids(&first), vec!["m3", "m2"]);
+        let second = gmail
+            .list_messages(500, first.next_page_token.as_deref(), None)
+            .unwrap();
+        assert_eq!(listed_ids(&second), vec!["m1"]);
+        assert!(second.next_page_token.is_none());
+
+        let inbox = gmail.list_messages(500, None, Some("INBOX")).unwrap();
+        assert_eq!(listed_ids(&inbox), vec!["m2", "m1"]);
+        assert_eq!(gmail.request_count(MockEndpoint::ListMessages), 3);
+    }
+
+    #[test]
+    fn test_history_records_changes() {
+        let gmail = MockGmail::new().with_message(MockMessage::new("m1", "t1"));
+        let start = gmail.history_id();
+
+        gmail.deliver(MockMessage::new("m2", "t1").subject("Re: hello"));
+        gmail.add_labels("m1", &["STARRED"]);
+        // Adding a label that's already there changes nothing
+        gmail.add_labels("m1", &["STARRED"]);
+        gmail.remove_labels("m1", &["INBOX"]);
+
+        let history = gmail.list_history_all(&start).unwrap();
+        let records = history.history.unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records[0].messages_added.as_ref().unwrap()[0].message.id,
+            "m2"
+        );
+        assert_eq!(
+            records[1].labels_added.as_ref().unwrap()[0].label_ids,
+            vec!["STARRED"]
+        );
+        assert_eq!(
+            records[2].labels_removed.as_ref().unwrap()[0].label_ids,
+            vec!["INBOX"]
+        );
+        assert_eq!(history.history_id, Some(gmail.history_id()));
+
+        let message = gmail
+            .get_messages_batch(&[MessageId::new("m1")])
+            .remove(0)
+            .unwrap();
+        assert_eq!(message.label_ids, Some(vec!["STARRED".to_string()]));
+    }
+
+    #[test]
+    fn test_expired_history_and_failures() {
+        let gmail = MockGmail::new();
+        let start = gmail.history_id();
+        gmail.expire_history();
+        let err = gmail.list_history(&start, None).unwrap_err();
+        assert!(err.downcast_ref::<HistoryExpiredError>().is_some());
+        assert!(gmail.list_history(&gmail.history_id(), None).is_ok());
+
+        gmail.fail_next(MockEndpoint::GetProfile, MockFailure::Status(429));
+        let err = gmail.get_profile().unwrap_err();
+        assert!(is_transient_error(&err));
+        assert!(gmail.get_profile().is_ok());
+
+        let missing = gmail
+            .get_messages_batch(&[MessageId::new("nope")])
+            .remove(0);
+        assert!(!is_transient_error(&missing.unwrap_err()));
+    }
+}
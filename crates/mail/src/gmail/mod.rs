@@ -3,15 +3,31 @@
 //! This module provides:
 //! - OAuth2 authentication flow
 //! - Gmail API client for fetching messages
+//! - `GmailApi` trait with a scripted mock for deterministic sync tests
+//! - Quota accounting and adaptive batch sizing for sync
+//! - Conditional requests for labels, profile and send-as settings
+//! - Recording of sanitized API traffic and offline replay of it
+//! - Typed account settings (vacation responder)
 //! - Response normalization to domain models
 
 mod auth;
 mod client;
+mod conditional;
+pub mod mock;
 mod normalize;
+mod quota;
+mod settings;
+mod traffic;
 
-pub use auth::{GmailAuth, StoredToken};
-pub use client::{GmailClient, HistoryExpiredError};
+pub use auth::{GmailAuth, ReauthRequiredError, StoredToken};
+pub use client::{GmailApi, GmailClient, HistoryExpiredError, is_transient_error};
+pub(crate) use client::http_status;
 pub use normalize::normalize_message;
+pub use quota::{
+    ApiCall, ChunkSizer, DEFAULT_CHUNK_SIZE, QuotaMeter, USER_QUOTA_PER_SEC, is_rate_limited,
+};
+pub use settings::VacationSettings;
+pub use traffic::{Exchange, TrafficRecorder, TrafficReplay};
 
 /// Gmail API request and response types
 pub mod api {
@@ -47,6 +63,41 @@ pub mod api {
         pub remove_label_ids: Vec<String>,
     }
 
+    /// Request body for sending a message
+    /// POST /gmail/v1/users/me/messages/send
+    #[derive(Debug, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SendMessageRequest {
+        /// RFC 2822 message, base64url encoded
+        pub raw: String,
+        /// Thread to add the message to (replies)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub thread_id: Option<String>,
+    }
+
+    /// Request body for creating or replacing a draft
+    /// POST /gmail/v1/users/me/drafts, PUT /gmail/v1/users/me/drafts/{id}
+    #[derive(Debug, Serialize)]
+    pub struct DraftRequest {
+        /// The draft's message, like a send request
+        pub message: SendMessageRequest,
+    }
+
+    /// Request body for sending a saved draft
+    /// POST /gmail/v1/users/me/drafts/send
+    #[derive(Debug, Serialize)]
+    pub struct SendDraftRequest {
+        /// ID of the draft to send
+        pub id: String,
+    }
+
+    /// Reference to a draft and its current message
+    #[derive(Debug, Deserialize)]
+    pub struct DraftRef {
+        pub id: String,
+        pub message: MessageRef,
+    }
+
     /// Response from listing messages
     #[derive(Debug, Deserialize)]
     #[serde(rename_all = "camelCase")]
@@ -95,6 +146,22 @@ pub mod api {
         pub snippet: String,
         pub internal_date: String,
         pub payload: Option<MessagePayload>,
+        /// Estimated size of the raw message in bytes
+        pub size_estimate: Option<u32>,
+        /// Text extracted from attachments at fetch time (not part of the
+        /// API response; carried with the message until it is processed)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub attachment_text: Option<String>,
+    }
+
+    /// Message fetched with format=RAW
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GmailRawMessage {
+        pub id: String,
+        pub thread_id: String,
+        /// Full RFC 2822 message, base64url encoded
+        pub raw: String,
     }
 
     /// Message payload containing headers and body
@@ -116,9 +183,22 @@ pub mod api {
 
     /// Message body (may be base64 encoded)
     #[derive(Debug, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
     pub struct MessageBody {
         pub size: Option<u32>,
         pub data: Option<String>,
+        /// Set instead of `data` for attachments, which are fetched separately
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub attachment_id: Option<String>,
+    }
+
+    /// Attachment content
+    /// GET /gmail/v1/users/me/messages/{messageId}/attachments/{id}
+    #[derive(Debug, Deserialize)]
+    pub struct AttachmentResponse {
+        pub size: Option<u32>,
+        /// Attachment bytes, base64url encoded
+        pub data: String,
     }
 
     /// Message part (for multipart messages)
@@ -229,5 +309,93 @@ pub mod api {
         pub threads_total: Option<u32>,
         /// Number of unread threads
         pub threads_unread: Option<u32>,
+        /// Colors set in Gmail; absent for uncolored labels
+        pub color: Option<GmailLabelColor>,
+    }
+
+    /// Colors of a Gmail label, as `#rrggbb` hex
+    #[derive(Debug, Clone, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GmailLabelColor {
+        pub text_color: String,
+        pub background_color: String,
+    }
+
+    // === Settings API Types ===
+
+    /// Response from listing send-as aliases
+    /// GET /gmail/v1/users/me/settings/sendAs
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ListSendAsResponse {
+        pub send_as: Option<Vec<GmailSendAs>>,
+    }
+
+    /// An address the account can send mail as
+    #[derive(Debug, Clone, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GmailSendAs {
+        /// Email address in the From header
+        pub send_as_email: String,
+        /// Name in the From header
+        pub display_name: Option<String>,
+        /// Whether this is the account's own address
+        #[serde(default)]
+        pub is_primary: bool,
+        /// Whether this is the default From address
+        #[serde(default)]
+        pub is_default: bool,
+        /// "accepted" once ownership is verified, "pending" before
+        /// (absent for the primary address)
+        pub verification_status: Option<String>,
+    }
+
+    /// Response from listing filters
+    /// GET /gmail/v1/users/me/settings/filters
+    #[derive(Debug, Deserialize)]
+    pub struct ListFiltersResponse {
+        pub filter: Option<Vec<GmailFilter>>,
+    }
+
+    /// A server-side Gmail filter
+    #[derive(Debug, Clone, Default, Deserialize)]
+    pub struct GmailFilter {
+        pub id: String,
+        #[serde(default)]
+        pub criteria: FilterCriteria,
+        #[serde(default)]
+        pub action: FilterAction,
+    }
+
+    /// Which messages a filter applies to (all set fields must match)
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct FilterCriteria {
+        pub from: Option<String>,
+        pub to: Option<String>,
+        pub subject: Option<String>,
+        /// Gmail search query the message must match
+        pub query: Option<String>,
+        /// Gmail search query the message must not match
+        pub negated_query: Option<String>,
+        #[serde(default)]
+        pub has_attachment: bool,
+        #[serde(default)]
+        pub exclude_chats: bool,
+        /// Size in bytes, compared using `size_comparison`
+        pub size: Option<i64>,
+        pub size_comparison: Option<String>,
+    }
+
+    /// What a filter does to matching messages
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct FilterAction {
+        #[serde(default)]
+        pub add_label_ids: Vec<String>,
+        #[serde(default)]
+        pub remove_label_ids: Vec<String>,
+        /// Address to forward matching messages to
+        pub forward: Option<String>,
     }
 }
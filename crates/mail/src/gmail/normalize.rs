@@ -7,7 +7,11 @@ use base64::prelude::*;
 use chrono::{TimeZone, Utc};
 
 use super::api::{GmailMessage, MessagePart, MessagePayload};
-use crate::models::{EmailAddress, Message, MessageId, ThreadId};
+use crate::models::{
+    AuthResults, AuthVerdict, EmailAddress, EventInvite, Message, MessageId, ThreadId,
+    Unsubscribe,
+};
+use crate::translate::detect_language;
 
 /// Normalize a Gmail API message to an Orion Message
 pub fn normalize_message(gmail_msg: GmailMessage, account_id: i64) -> Result<Message> {
@@ -34,6 +38,18 @@ pub fn normalize_message(gmail_msg: GmailMessage, account_id: i64) -> Result<Mes
 
     let subject = extract_header(payload, "Subject").unwrap_or_default();
 
+    let unsubscribe = extract_header(payload, "List-Unsubscribe").and_then(|header| {
+        let post = extract_header(payload, "List-Unsubscribe-Post");
+        parse_list_unsubscribe(&header, post.as_deref())
+    });
+
+    // Only the topmost header is trusted: it was added by Gmail's own
+    // receiving server, while lower ones may have come from the sender
+    let auth_results =
+        extract_header(payload, "Authentication-Results").and_then(|h| parse_auth_results(&h));
+
+    let rfc_message_id = extract_header(payload, "Message-ID").and_then(|h| parse_message_id(&h));
+
     // Parse internal date (milliseconds since epoch)
     let internal_date: i64 = gmail_msg.internal_date.parse().unwrap_or(0);
     let received_at = Utc
@@ -44,6 +60,12 @@ pub fn normalize_message(gmail_msg: GmailMessage, account_id: i64) -> Result<Mes
     // Extract full body content (both text and HTML)
     let body_text = extract_plain_text_body(payload);
     let body_html = extract_html_body(payload);
+    let mut attachment_names = Vec::new();
+    if let Some(parts) = &payload.parts {
+        collect_attachment_names(parts, &mut attachment_names);
+    }
+    let has_attachments = !attachment_names.is_empty();
+    let invite = extract_calendar(payload).and_then(|ics| EventInvite::parse_ics(&ics));
 
     // Extract body preview - prefer the snippet, fall back to extracting from body
     let body_preview = if !gmail_msg.snippet.is_empty() {
@@ -52,8 +74,12 @@ pub fn normalize_message(gmail_msg: GmailMessage, account_id: i64) -> Result<Mes
         body_text.clone().unwrap_or_default()
     };
 
+    // Detect language from the full body, or the snippet for HTML-only mail
+    let lang = detect_language(body_text.as_deref().unwrap_or(&body_preview));
+
     // Extract label IDs
     let label_ids = gmail_msg.label_ids.unwrap_or_default();
+    let size_bytes = gmail_msg.size_estimate.map(i64::from).unwrap_or(0);
 
     Ok(Message::builder(id, thread_id)
         .account_id(account_id)
@@ -64,9 +90,18 @@ pub fn normalize_message(gmail_msg: GmailMessage, account_id: i64) -> Result<Mes
         .body_preview(body_preview)
         .body_text(body_text)
         .body_html(body_html)
+        .attachment_text(gmail_msg.attachment_text)
         .received_at(received_at)
         .internal_date(internal_date)
         .label_ids(label_ids)
+        .has_attachments(has_attachments)
+        .attachment_names(attachment_names)
+        .size_bytes(size_bytes)
+        .unsubscribe(unsubscribe)
+        .auth_results(auth_results)
+        .invite(invite)
+        .lang(lang)
+        .rfc_message_id(rfc_message_id)
         .build())
 }
 
@@ -88,6 +123,106 @@ fn parse_address_list(s: &str) -> Vec<EmailAddress> {
         .collect()
 }
 
+/// Parse the List-Unsubscribe header, with List-Unsubscribe-Post if present
+///
+/// The header holds comma-separated URIs in angle brackets; the first HTTP(S)
+/// and first mailto: URI are kept. One-click (RFC 8058) needs both the POST
+/// header and an HTTPS URL.
+fn parse_list_unsubscribe(header: &str, post: Option<&str>) -> Option<Unsubscribe> {
+    let mut unsubscribe = Unsubscribe::default();
+
+    for (uri, _) in header.split('<').skip(1).filter_map(|t| t.split_once('>')) {
+        // Folded headers can leave whitespace inside a URI
+        let uri: String = uri.split_whitespace().collect();
+        let scheme = uri.split(':').next().unwrap_or_default().to_ascii_lowercase();
+        match scheme.as_str() {
+            "mailto" if unsubscribe.mailto.is_none() => unsubscribe.mailto = Some(uri),
+            "http" | "https" if unsubscribe.url.is_none() => unsubscribe.url = Some(uri),
+            _ => {}
+        }
+    }
+
+    if unsubscribe.url.is_none() && unsubscribe.mailto.is_none() {
+        return None;
+    }
+
+    unsubscribe.one_click = post.is_some_and(|p| {
+        p.split_whitespace()
+            .collect::<String>()
+            .eq_ignore_ascii_case("List-Unsubscribe=One-Click")
+    }) && unsubscribe
+        .url
+        .as_ref()
+        .is_some_and(|u| u.to_ascii_lowercase().starts_with("https://"));
+
+    Some(unsubscribe)
+}
+
+/// Parse an Authentication-Results header (RFC 8601)
+///
+/// The header is the authserv-id followed by `;`-separated `method=result`
+/// entries, e.g. `mx.google.com; dkim=pass header.i=@example.com; spf=fail
+/// (reason) smtp.mailfrom=example.com; dmarc=fail (p=REJECT) header.from=...`.
+/// Returns None when no SPF, DKIM or DMARC result is present.
+fn parse_auth_results(header: &str) -> Option<AuthResults> {
+    let mut results = AuthResults::default();
+
+    for entry in strip_comments(header).split(';').skip(1) {
+        let Some((method, rest)) = entry.trim().split_once('=') else {
+            continue;
+        };
+        let Some(verdict) = rest.split_whitespace().next().and_then(AuthVerdict::parse) else {
+            continue;
+        };
+        let slot = match method.trim().to_ascii_lowercase().as_str() {
+            "spf" => &mut results.spf,
+            "dkim" => &mut results.dkim,
+            "dmarc" => &mut results.dmarc,
+            _ => continue,
+        };
+        // A message can carry several DKIM signatures; one passing is enough
+        if slot.is_none() || verdict == AuthVerdict::Pass {
+            *slot = Some(verdict);
+        }
+    }
+
+    if results == AuthResults::default() {
+        return None;
+    }
+    Some(results)
+}
+
+/// Parse the Message-ID header, dropping the angle brackets
+///
+/// Returns None for an empty header, so messages without one are never
+/// taken for duplicates of each other.
+fn parse_message_id(header: &str) -> Option<String> {
+    let id = header.trim().trim_start_matches('<').trim_end_matches('>').trim();
+    if id.is_empty() {
+        return None;
+    }
+    Some(id.to_string())
+}
+
+/// Remove parenthesized comments, which may contain `;` or `=`
+fn strip_comments(header: &str) -> String {
+    let mut depth = 0usize;
+    header
+        .chars()
+        .filter(|&c| match c {
+            '(' => {
+                depth += 1;
+                false
+            }
+            ')' => {
+                depth = depth.saturating_sub(1);
+                false
+            }
+            _ => depth == 0,
+        })
+        .collect()
+}
+
 /// Extract plain text body from message payload
 fn extract_plain_text_body(payload: &MessagePayload) -> Option<String> {
     // Check if this is a simple message with body data
@@ -193,6 +328,50 @@ fn find_html_in_parts(parts: &[MessagePart]) -> Option<String> {
     None
 }
 
+/// Recursively collect the filenames of file attachments
+///
+/// Inline parts without a filename (e.g. embedded images referenced by
+/// Content-ID) are not counted.
+fn collect_attachment_names(parts: &[MessagePart], names: &mut Vec<String>) {
+    for part in parts {
+        if let Some(filename) = part.filename.as_ref().filter(|f| !f.is_empty()) {
+            names.push(filename.clone());
+        }
+        if let Some(nested) = &part.parts {
+            collect_attachment_names(nested, names);
+        }
+    }
+}
+
+/// Find the first text/calendar part with inline data
+///
+/// Invites usually carry the same calendar both inline and as an .ics
+/// attachment; Gmail only includes data for the inline one.
+fn extract_calendar(payload: &MessagePayload) -> Option<String> {
+    if is_calendar(payload.mime_type.as_deref())
+        && let Some(data) = payload.body.as_ref().and_then(|b| b.data.as_ref())
+    {
+        return decode_base64_body(data);
+    }
+    find_calendar_in_parts(payload.parts.as_deref()?)
+}
+
+/// Recursively search message parts for text/calendar content
+fn find_calendar_in_parts(parts: &[MessagePart]) -> Option<String> {
+    parts.iter().find_map(|part| {
+        if is_calendar(part.mime_type.as_deref())
+            && let Some(data) = part.body.as_ref().and_then(|b| b.data.as_ref())
+        {
+            return decode_base64_body(data);
+        }
+        find_calendar_in_parts(part.parts.as_deref()?)
+    })
+}
+
+fn is_calendar(mime_type: Option<&str>) -> bool {
+    mime_type.is_some_and(|m| m.to_ascii_lowercase().starts_with("text/calendar"))
+}
+
 /// Decode base64-encoded body data
 ///
 /// Gmail uses URL-safe base64 but padding can vary, so we try multiple decoders.
@@ -242,6 +421,7 @@ mod tests {
             body: Some(MessageBody {
                 size: Some(0),
                 data: None,
+                attachment_id: None,
             }),
             parts: None,
             mime_type: Some("text/plain".to_string()),
@@ -284,6 +464,76 @@ mod tests {
         assert_eq!(addrs[1].name, Some("Bob".to_string()));
     }
 
+    #[test]
+    fn test_parse_list_unsubscribe() {
+        let unsubscribe = parse_list_unsubscribe(
+            "<mailto:unsub@list.example.com?subject=unsubscribe>, <https://example.com/u?id=1>",
+            Some("List-Unsubscribe=One-Click"),
+        )
+        .unwrap();
+        assert_eq!(
+            unsubscribe.mailto.as_deref(),
+            Some("mailto:unsub@list.example.com?subject=unsubscribe")
+        );
+        assert_eq!(unsubscribe.url.as_deref(), Some("https://example.com/u?id=1"));
+        assert!(unsubscribe.one_click);
+
+        // One-click needs the POST header and HTTPS
+        let unsubscribe = parse_list_unsubscribe("<http://example.com/u>", None).unwrap();
+        assert_eq!(unsubscribe.mailto, None);
+        assert!(!unsubscribe.one_click);
+        let unsubscribe =
+            parse_list_unsubscribe("<http://example.com/u>", Some("List-Unsubscribe=One-Click"))
+                .unwrap();
+        assert!(!unsubscribe.one_click);
+
+        // Folded header
+        let unsubscribe =
+            parse_list_unsubscribe("<https://example.com/\r\n unsubscribe>", None).unwrap();
+        assert_eq!(unsubscribe.url.as_deref(), Some("https://example.com/unsubscribe"));
+
+        assert_eq!(parse_list_unsubscribe("unsub@example.com", None), None);
+        assert_eq!(parse_list_unsubscribe("<ftp://example.com/u>", None), None);
+    }
+
+    #[test]
+    fn test_parse_auth_results() {
+        let results = parse_auth_results(
+            "mx.google.com;\r\n       dkim=fail header.i=@example.com header.s=s1;\r\n       \
+             dkim=pass header.i=@esp.example.net;\r\n       \
+             spf=softfail (google.com: domain of x@example.com does not designate \
+             1.2.3.4 as permitted sender; see docs) smtp.mailfrom=x@example.com;\r\n \
+             dmarc=FAIL (p=REJECT sp=REJECT dis=REJECT) header.from=example.com",
+        )
+        .unwrap();
+        assert_eq!(results.dkim, Some(AuthVerdict::Pass));
+        assert_eq!(results.spf, Some(AuthVerdict::SoftFail));
+        assert_eq!(results.dmarc, Some(AuthVerdict::Fail));
+        assert!(results.failed_dmarc());
+
+        let results = parse_auth_results("mx.google.com; spf=pass smtp.mailfrom=a.com").unwrap();
+        assert_eq!(results.spf, Some(AuthVerdict::Pass));
+        assert_eq!(results.dmarc, None);
+        assert!(!results.failed_dmarc());
+
+        assert_eq!(parse_auth_results("mx.google.com; none"), None);
+        assert_eq!(parse_auth_results("mx.google.com; arc=pass"), None);
+    }
+
+    #[test]
+    fn test_parse_message_id() {
+        assert_eq!(
+            parse_message_id(" <CAF1x2y3@mail.gmail.com>\r\n").as_deref(),
+            Some("CAF1x2y3@mail.gmail.com")
+        );
+        assert_eq!(
+            parse_message_id("abc@example.com").as_deref(),
+            Some("abc@example.com")
+        );
+        assert_eq!(parse_message_id("<>"), None);
+        assert_eq!(parse_message_id("  "), None);
+    }
+
     #[test]
     fn test_decode_html_entities() {
         let input = "Hello &amp; welcome &lt;user&gt;";
@@ -291,6 +541,38 @@ mod tests {
         assert_eq!(output, "Hello & welcome <user>");
     }
 
+    #[test]
+    fn test_collect_attachment_names() {
+        let part = |filename: Option<&str>, parts: Option<Vec<MessagePart>>| MessagePart {
+            part_id: None,
+            mime_type: None,
+            filename: filename.map(String::from),
+            headers: None,
+            body: None,
+            parts,
+        };
+
+        let names = |parts: &[MessagePart]| {
+            let mut names = Vec::new();
+            collect_attachment_names(parts, &mut names);
+            names
+        };
+
+        assert!(names(&[part(Some(""), None), part(None, None)]).is_empty());
+        assert_eq!(names(&[part(Some("report.pdf"), None)]), vec!["report.pdf"]);
+        // Attachments nested inside multipart/mixed
+        assert_eq!(
+            names(&[
+                part(Some("a.txt"), None),
+                part(
+                    None,
+                    Some(vec![part(None, None), part(Some("photo.jpg"), None)])
+                )
+            ]),
+            vec!["a.txt", "photo.jpg"]
+        );
+    }
+
     #[test]
     fn test_decode_base64_body() {
         // "Hello, World!" in base64url
@@ -0,0 +1,330 @@
+//! Gmail API quota accounting
+//!
+//! Gmail charges every API method a number of quota units and limits each
+//! user to a moving average of [`USER_QUOTA_PER_SEC`] units per second.
+//! [`QuotaMeter`] tallies the units a sync spends per call type along with
+//! its recent burn rate, and [`ChunkSizer`] scales message fetches to keep
+//! that rate under the limit.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::error::is_quota_message;
+use crate::telemetry;
+
+use super::client::http_status;
+
+/// Gmail's per-user quota, in units per second
+pub const USER_QUOTA_PER_SEC: f64 = 250.0;
+
+/// Share of the per-user quota sync aims to use, leaving room for the app's
+/// own requests (mutations, attachments) running alongside it
+const QUOTA_HEADROOM: f64 = 0.8;
+
+/// Span the burn rate is averaged over
+const BURN_WINDOW: Duration = Duration::from_secs(5);
+
+/// Messages fetched per batch call when a sync starts
+pub const DEFAULT_CHUNK_SIZE: usize = 25;
+
+/// Smallest batch a rate-limited sync shrinks to
+const MIN_CHUNK_SIZE: usize = 5;
+
+/// Largest batch Gmail's batch endpoint accepts
+const MAX_CHUNK_SIZE: usize = 100;
+
+/// Messages added to the batch after each chunk that ran comfortably
+/// under quota
+const CHUNK_GROWTH: usize = 5;
+
+/// A Gmail API method sync calls, with its quota cost
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApiCall {
+    /// `messages.list`, per page
+    ListMessages,
+    /// `messages.get`, per message of a batch
+    GetMessage,
+    /// `history.list`, per page
+    ListHistory,
+    /// `getProfile`
+    GetProfile,
+}
+
+impl ApiCall {
+    /// Quota units Gmail charges for one call
+    pub fn units(self) -> u64 {
+        match self {
+            Self::ListMessages | Self::GetMessage => 5,
+            Self::ListHistory => 2,
+            Self::GetProfile => 1,
+        }
+    }
+
+    /// Name used in metrics and logs
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ListMessages => "messages.list",
+            Self::GetMessage => "messages.get",
+            Self::ListHistory => "history.list",
+            Self::GetProfile => "getProfile",
+        }
+    }
+}
+
+/// Quota units spent by a sync, per call type and over the last few seconds
+#[derive(Debug, Clone)]
+pub struct QuotaMeter {
+    units: HashMap<ApiCall, u64>,
+    /// Charges inside the burn window, oldest first
+    recent: VecDeque<(Instant, u64)>,
+}
+
+impl Default for QuotaMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QuotaMeter {
+    pub fn new() -> Self {
+        Self {
+            units: HashMap::new(),
+            recent: VecDeque::new(),
+        }
+    }
+
+    /// Charge `count` calls of `call`
+    pub fn record(&mut self, call: ApiCall, count: usize) {
+        self.record_at(call, count, Instant::now());
+        telemetry::record_quota(call, call.units() * count as u64, self.burn_rate());
+    }
+
+    fn record_at(&mut self, call: ApiCall, count: usize, now: Instant) {
+        let units = call.units() * count as u64;
+        if units == 0 {
+            return;
+        }
+        *self.units.entry(call).or_default() += units;
+        self.recent.push_back((now, units));
+        self.expire(now);
+    }
+
+    /// Add another meter's charges, e.g. from a catch-up sync
+    pub fn merge(&mut self, other: &QuotaMeter) {
+        for (call, units) in &other.units {
+            *self.units.entry(*call).or_default() += units;
+        }
+        self.recent.extend(other.recent.iter().copied());
+        self.recent.make_contiguous().sort_by_key(|(at, _)| *at);
+        self.expire(Instant::now());
+    }
+
+    /// Total units spent
+    pub fn units(&self) -> u64 {
+        self.units.values().sum()
+    }
+
+    /// Units spent on `call`
+    pub fn units_for(&self, call: ApiCall) -> u64 {
+        self.units.get(&call).copied().unwrap_or(0)
+    }
+
+    /// Units per second spent over the last few seconds
+    pub fn burn_rate(&self) -> f64 {
+        self.burn_rate_at(Instant::now())
+    }
+
+    fn burn_rate_at(&self, now: Instant) -> f64 {
+        let units: u64 = self
+            .recent
+            .iter()
+            .filter(|(at, _)| now.duration_since(*at) < BURN_WINDOW)
+            .map(|(_, units)| units)
+            .sum();
+        units as f64 / BURN_WINDOW.as_secs_f64()
+    }
+
+    /// How long to wait before spending `count` calls of `call` keeps the
+    /// burn rate within sync's share of the quota
+    pub fn delay_for(&self, call: ApiCall, count: usize) -> Duration {
+        self.delay_for_at(call, count, Instant::now())
+    }
+
+    fn delay_for_at(&self, call: ApiCall, count: usize, now: Instant) -> Duration {
+        let budget = (budget_per_sec() * BURN_WINDOW.as_secs_f64()) as u64;
+        let mut spent: u64 = self
+            .recent
+            .iter()
+            .filter(|(at, _)| now.duration_since(*at) < BURN_WINDOW)
+            .map(|(_, units)| units)
+            .sum();
+        let needed = call.units() * count as u64;
+
+        // Wait for the oldest charges to leave the window until this one fits
+        for (at, units) in &self.recent {
+            if spent + needed <= budget {
+                break;
+            }
+            let age = now.duration_since(*at);
+            if age >= BURN_WINDOW {
+                continue;
+            }
+            spent -= units;
+            if spent + needed <= budget {
+                return BURN_WINDOW - age;
+            }
+        }
+        Duration::ZERO
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some((at, _)) = self.recent.front() {
+            if now.duration_since(*at) < BURN_WINDOW {
+                break;
+            }
+            self.recent.pop_front();
+        }
+    }
+}
+
+/// Units per second sync aims to stay under
+fn budget_per_sec() -> f64 {
+    USER_QUOTA_PER_SEC * QUOTA_HEADROOM
+}
+
+/// Whether a failed request was refused for exceeding a rate limit or quota
+pub fn is_rate_limited(error: &anyhow::Error) -> bool {
+    match http_status(error) {
+        Some(429) => true,
+        Some(403) => is_quota_message(&error.to_string()),
+        _ => false,
+    }
+}
+
+/// Number of messages to fetch per batch call
+///
+/// Starts at [`DEFAULT_CHUNK_SIZE`], halves when Gmail rate limits a chunk
+/// or the burn rate passes sync's share of the quota, and grows again while
+/// the burn rate stays well below it, up to its cap.
+#[derive(Debug, Clone)]
+pub struct ChunkSizer {
+    size: usize,
+    max: usize,
+}
+
+impl Default for ChunkSizer {
+    fn default() -> Self {
+        Self {
+            size: DEFAULT_CHUNK_SIZE,
+            max: MAX_CHUNK_SIZE,
+        }
+    }
+}
+
+impl ChunkSizer {
+    /// A sizer that never requests more than `max` messages at once
+    pub fn capped(max: usize) -> Self {
+        let max = max.clamp(1, MAX_CHUNK_SIZE);
+        Self {
+            size: DEFAULT_CHUNK_SIZE.min(max),
+            max,
+        }
+    }
+
+    /// Messages to request in the next chunk
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Adjust after a chunk, given whether any of it was rate limited and
+    /// the burn rate afterwards
+    pub fn adjust(&mut self, rate_limited: bool, burn_rate: f64) {
+        let budget = budget_per_sec();
+        if rate_limited || burn_rate > budget {
+            self.size = (self.size / 2).max(MIN_CHUNK_SIZE).min(self.max);
+        } else if burn_rate < budget / 2.0 {
+            self.size = (self.size + CHUNK_GROWTH).min(self.max);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_meter_counts_units_per_call() {
+        let mut meter = QuotaMeter::new();
+        let now = Instant::now();
+        meter.record_at(ApiCall::GetProfile, 1, now);
+        meter.record_at(ApiCall::ListMessages, 2, now);
+        meter.record_at(ApiCall::GetMessage, 25, now);
+        meter.record_at(ApiCall::ListHistory, 3, now);
+
+        assert_eq!(meter.units_for(ApiCall::GetProfile), 1);
+        assert_eq!(meter.units_for(ApiCall::ListMessages), 10);
+        assert_eq!(meter.units_for(ApiCall::GetMessage), 125);
+        assert_eq!(meter.units_for(ApiCall::ListHistory), 6);
+        assert_eq!(meter.units(), 142);
+        assert_eq!(meter.burn_rate_at(now), 142.0 / 5.0);
+
+        // Old charges leave the burn rate but not the totals
+        let later = now + BURN_WINDOW;
+        meter.record_at(ApiCall::GetProfile, 1, later);
+        assert_eq!(meter.burn_rate_at(later), 1.0 / 5.0);
+        assert_eq!(meter.units(), 143);
+    }
+
+    #[test]
+    fn test_quota_meter_delays_spending_over_budget() {
+        let mut meter = QuotaMeter::new();
+        let start = Instant::now();
+        // Budget is 200 units/s, i.e. 1000 units over the window
+        meter.record_at(ApiCall::GetMessage, 100, start);
+        meter.record_at(ApiCall::GetMessage, 80, start + Duration::from_secs(1));
+
+        let now = start + Duration::from_secs(2);
+        assert_eq!(
+            meter.delay_for_at(ApiCall::GetProfile, 1, now),
+            Duration::ZERO
+        );
+        // 900 units spent; 100 more messages fit once the first chunk expires
+        assert_eq!(
+            meter.delay_for_at(ApiCall::GetMessage, 100, now),
+            Duration::from_secs(3)
+        );
+    }
+
+    #[test]
+    fn test_chunk_sizer_adapts_to_rate_limits() {
+        let mut sizer = ChunkSizer::default();
+        assert_eq!(sizer.size(), DEFAULT_CHUNK_SIZE);
+
+        sizer.adjust(true, 0.0);
+        assert_eq!(sizer.size(), 12);
+        sizer.adjust(false, budget_per_sec() + 1.0);
+        assert_eq!(sizer.size(), 6);
+        sizer.adjust(true, 0.0);
+        assert_eq!(sizer.size(), MIN_CHUNK_SIZE);
+
+        // Holds steady near budget, grows well below it
+        sizer.adjust(false, budget_per_sec() * 0.75);
+        assert_eq!(sizer.size(), MIN_CHUNK_SIZE);
+        for _ in 0..100 {
+            sizer.adjust(false, 0.0);
+        }
+        assert_eq!(sizer.size(), MAX_CHUNK_SIZE);
+    }
+
+    #[test]
+    fn test_capped_chunk_sizer() {
+        let mut sizer = ChunkSizer::capped(3);
+        assert_eq!(sizer.size(), 3);
+        sizer.adjust(false, 0.0);
+        assert_eq!(sizer.size(), 3);
+        sizer.adjust(true, 0.0);
+        assert_eq!(sizer.size(), 3);
+
+        assert_eq!(ChunkSizer::capped(1000).size(), DEFAULT_CHUNK_SIZE);
+    }
+}
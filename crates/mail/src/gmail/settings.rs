@@ -0,0 +1,147 @@
+//! Gmail account settings
+//!
+//! Typed versions of the settings resources under `users.settings`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Vacation responder (out-of-office auto-reply) settings
+///
+/// Serializes to and from Gmail's `VacationSettings` resource.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VacationSettings {
+    /// Whether the responder replies to incoming mail
+    pub enable_auto_reply: bool,
+    /// Subject of the reply; Gmail uses "Re: <subject>" when empty
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_subject: Option<String>,
+    /// Plain text reply body
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_body_plain_text: Option<String>,
+    /// HTML reply body, preferred over the plain text one when set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_body_html: Option<String>,
+    /// Only reply to senders in the user's contacts
+    pub restrict_to_contacts: bool,
+    /// Only reply to senders in the user's domain (Workspace accounts)
+    pub restrict_to_domain: bool,
+    /// When replies start; immediately if unset
+    #[serde(with = "epoch_millis", skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<DateTime<Utc>>,
+    /// When replies stop; never if unset
+    #[serde(with = "epoch_millis", skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl VacationSettings {
+    /// Whether the responder is replying at `now`
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        self.enable_auto_reply
+            && self.start_time.is_none_or(|start| start <= now)
+            && self.end_time.is_none_or(|end| now < end)
+    }
+
+    /// Check the settings before sending them to Gmail
+    ///
+    /// An enabled responder needs a reply body, and its end time must come
+    /// after its start time.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !self.enable_auto_reply {
+            return Ok(());
+        }
+        let has_body = [&self.response_body_plain_text, &self.response_body_html]
+            .iter()
+            .any(|body| body.as_deref().is_some_and(|b| !b.trim().is_empty()));
+        if !has_body {
+            anyhow::bail!("Vacation responder needs a reply message");
+        }
+        if let (Some(start), Some(end)) = (self.start_time, self.end_time)
+            && end <= start
+        {
+            anyhow::bail!("Vacation responder must end after it starts");
+        }
+        Ok(())
+    }
+}
+
+/// Gmail's int64 millisecond timestamps, which JSON carries as strings
+mod epoch_millis {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        time: &Option<DateTime<Utc>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match time {
+            Some(time) => serializer.serialize_str(&time.timestamp_millis().to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Utc>>, D::Error> {
+        let Some(millis) = Option::<String>::deserialize(deserializer)? else {
+            return Ok(None);
+        };
+        let millis: i64 = millis.parse().map_err(serde::de::Error::custom)?;
+        Ok(DateTime::from_timestamp_millis(millis))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_vacation_settings_json() {
+        let json = r#"{
+            "enableAutoReply": true,
+            "responseSubject": "Away",
+            "responseBodyPlainText": "Back Monday",
+            "restrictToContacts": true,
+            "startTime": "1767225600000",
+            "endTime": "1767830400000"
+        }"#;
+        let settings: VacationSettings = serde_json::from_str(json).unwrap();
+        assert!(settings.enable_auto_reply);
+        assert_eq!(settings.response_subject.as_deref(), Some("Away"));
+        assert!(settings.restrict_to_contacts && !settings.restrict_to_domain);
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(settings.start_time, Some(start));
+
+        let value = serde_json::to_value(&settings).unwrap();
+        assert_eq!(value["startTime"], "1767225600000");
+        assert!(value.get("responseBodyHtml").is_none());
+        let roundtrip: VacationSettings = serde_json::from_value(value).unwrap();
+        assert_eq!(roundtrip, settings);
+
+        let disabled: VacationSettings =
+            serde_json::from_str(r#"{"enableAutoReply":false}"#).unwrap();
+        assert_eq!(disabled, VacationSettings::default());
+    }
+
+    #[test]
+    fn test_vacation_settings_validate() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let mut settings = VacationSettings {
+            enable_auto_reply: true,
+            start_time: Some(start),
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+
+        settings.response_body_plain_text = Some("Back Monday".to_string());
+        assert!(settings.validate().is_ok());
+        assert!(!settings.is_active_at(start - chrono::Duration::hours(1)));
+        assert!(settings.is_active_at(start));
+
+        settings.end_time = Some(start);
+        assert!(settings.validate().is_err());
+        assert!(!settings.is_active_at(start));
+        assert!(VacationSettings::default().validate().is_ok());
+    }
+}
@@ -0,0 +1,500 @@
+//! Recording and replaying Gmail API traffic
+//!
+//! A [`TrafficRecorder`] attached to a [`GmailClient`](super::GmailClient)
+//! captures the sync-related requests it makes (listings, messages,
+//! attachments, history, profile) together with Gmail's responses. Personal
+//! data is scrubbed as responses are recorded: addresses become stable
+//! pseudonyms and text keeps its shape but not its letters. A client built
+//! with [`GmailClient::replay`](super::GmailClient::replay) answers the same
+//! requests from such a fixture without network access, for regression tests
+//! on real mailbox shapes and for offline development.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::client::{HistoryExpiredError, http_status};
+
+/// Headers kept verbatim; they describe structure, not people
+const STRUCTURAL_HEADERS: [&str; 5] = [
+    "content-type",
+    "content-transfer-encoding",
+    "mime-version",
+    "date",
+    "list-unsubscribe-post",
+];
+
+/// Headers holding addresses, which are pseudonymized
+const ADDRESS_HEADERS: [&str; 9] = [
+    "from",
+    "to",
+    "cc",
+    "bcc",
+    "reply-to",
+    "sender",
+    "delivered-to",
+    "return-path",
+    "x-original-to",
+];
+
+/// One recorded request and Gmail's answer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exchange {
+    /// API path and query, relative to the Gmail API base URL
+    pub request: String,
+    /// HTTP status; the response is only meaningful for 200
+    pub status: u16,
+    #[serde(default)]
+    pub response: Value,
+}
+
+/// Fixture file contents
+#[derive(Default, Serialize, Deserialize)]
+struct Fixture {
+    exchanges: Vec<Exchange>,
+}
+
+/// Captures sanitized request/response pairs from a live client
+#[derive(Default)]
+pub struct TrafficRecorder {
+    exchanges: Mutex<Vec<Exchange>>,
+    pseudonyms: Mutex<HashMap<String, String>>,
+}
+
+impl TrafficRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a successful response, scrubbing it first
+    pub fn record(&self, request: &str, mut response: Value) {
+        {
+            let mut pseudonyms = self.pseudonyms.lock().unwrap();
+            sanitize_value(&mut response, None, &mut pseudonyms);
+        }
+        self.push(Exchange {
+            request: request.to_string(),
+            status: 200,
+            response,
+        });
+    }
+
+    /// Record a request Gmail answered with an error status
+    ///
+    /// Network failures carry no status and aren't recorded.
+    pub fn record_error(&self, request: &str, error: &anyhow::Error) {
+        if let Some(status) = error_status(error) {
+            self.push(Exchange {
+                request: request.to_string(),
+                status,
+                response: Value::Null,
+            });
+        }
+    }
+
+    fn push(&self, exchange: Exchange) {
+        self.exchanges.lock().unwrap().push(exchange);
+    }
+
+    /// Everything recorded so far, in request order
+    pub fn exchanges(&self) -> Vec<Exchange> {
+        self.exchanges.lock().unwrap().clone()
+    }
+
+    /// Write the recording as a fixture for [`TrafficReplay::load`]
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let fixture = Fixture {
+            exchanges: self.exchanges(),
+        };
+        let json = serde_json::to_string_pretty(&fixture)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("Failed to write traffic fixture {}", path.display()))
+    }
+}
+
+/// Answers requests from recorded exchanges
+///
+/// Repeated requests get the recorded responses in order; once they run out
+/// the last one is repeated, so polling a fixture stays stable.
+pub struct TrafficReplay {
+    responses: Mutex<HashMap<String, VecDeque<Exchange>>>,
+}
+
+impl TrafficReplay {
+    /// Replay the exchanges in a fixture file
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read traffic fixture {}", path.display()))?;
+        Self::from_json(&json)
+    }
+
+    /// Replay the exchanges in a fixture's JSON
+    pub fn from_json(json: &str) -> Result<Self> {
+        let fixture: Fixture = serde_json::from_str(json).context("Invalid traffic fixture")?;
+        Ok(Self::new(fixture.exchanges))
+    }
+
+    pub fn new(exchanges: Vec<Exchange>) -> Self {
+        let mut responses: HashMap<String, VecDeque<Exchange>> = HashMap::new();
+        for exchange in exchanges {
+            responses
+                .entry(exchange.request.clone())
+                .or_default()
+                .push_back(exchange);
+        }
+        Self {
+            responses: Mutex::new(responses),
+        }
+    }
+
+    /// The recorded response to `request`
+    ///
+    /// Error statuses come back as the same `ureq` error a live request
+    /// would produce.
+    pub fn respond(&self, request: &str) -> Result<Value> {
+        let mut responses = self.responses.lock().unwrap();
+        let exchange = responses
+            .get_mut(request)
+            .and_then(|queue| {
+                if queue.len() > 1 {
+                    queue.pop_front()
+                } else {
+                    queue.front().cloned()
+                }
+            })
+            .with_context(|| format!("No recorded response for {}", request))?;
+
+        match exchange.status {
+            200 => Ok(exchange.response),
+            status => Err(ureq::Error::StatusCode(status).into()),
+        }
+    }
+}
+
+/// HTTP status behind a failed request, if Gmail answered at all
+fn error_status(error: &anyhow::Error) -> Option<u16> {
+    if error.downcast_ref::<HistoryExpiredError>().is_some() {
+        return Some(404);
+    }
+    http_status(error)
+}
+
+/// Scrub personal data from a response, keeping its structure
+///
+/// `key` is the field `value` was found under.
+fn sanitize_value(value: &mut Value, key: Option<&str>, pseudonyms: &mut HashMap<String, String>) {
+    match value {
+        Value::Object(fields) => {
+            // Header lists are {name, value} pairs
+            if let (Some(Value::String(name)), Some(Value::String(header))) =
+                (fields.get("name").cloned(), fields.get_mut("value"))
+            {
+                *header = sanitize_header(&name, header, pseudonyms);
+                return;
+            }
+            for (field, value) in fields.iter_mut() {
+                sanitize_value(value, Some(field), pseudonyms);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                sanitize_value(item, key, pseudonyms);
+            }
+        }
+        Value::String(text) => {
+            *text = match key {
+                Some("emailAddress") => pseudonymize_addresses(text, pseudonyms),
+                Some("snippet") => redact_text(text),
+                Some("filename") => redact_filename(text),
+                Some("data") => redact_body_data(text),
+                _ => return,
+            };
+        }
+        _ => {}
+    }
+}
+
+/// Scrub a header value according to what it holds
+fn sanitize_header(name: &str, value: &str, pseudonyms: &mut HashMap<String, String>) -> String {
+    let name = name.to_ascii_lowercase();
+    if STRUCTURAL_HEADERS.contains(&name.as_str()) {
+        value.to_string()
+    } else if ADDRESS_HEADERS.contains(&name.as_str()) {
+        pseudonymize_addresses(value, pseudonyms)
+    } else {
+        redact_text(value)
+    }
+}
+
+/// Replace each address with a stable pseudonym and redact display names
+///
+/// The same address always maps to the same pseudonym within a recording,
+/// so threads and senders keep their shape.
+fn pseudonymize_addresses(value: &str, pseudonyms: &mut HashMap<String, String>) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(at) = rest.find('@') {
+        let is_boundary =
+            |c: char| c.is_whitespace() || matches!(c, '<' | '>' | ',' | '"' | ';' | ':');
+        let start = rest[..at].rfind(is_boundary).map(|i| i + 1).unwrap_or(0);
+        let end = rest[at..]
+            .find(is_boundary)
+            .map(|i| at + i)
+            .unwrap_or(rest.len());
+
+        out.push_str(&redact_text(&rest[..start]));
+        let address = rest[start..end].to_ascii_lowercase();
+        let next = pseudonyms.len() + 1;
+        let pseudonym = pseudonyms
+            .entry(address)
+            .or_insert_with(|| format!("user{}@example.com", next));
+        out.push_str(pseudonym);
+        rest = &rest[end..];
+    }
+    out.push_str(&redact_text(rest));
+    out
+}
+
+/// Replace letters and digits, keeping length, spacing and punctuation
+fn redact_text(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_uppercase() {
+                'X'
+            } else if c.is_alphabetic() {
+                'x'
+            } else if c.is_numeric() {
+                '0'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Redact an attachment name but keep its extension
+fn redact_filename(name: &str) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, extension)) => format!("{}.{}", redact_text(stem), extension),
+        None => redact_text(name),
+    }
+}
+
+/// Redact base64url body data: text is redacted outside of markup, binary
+/// content is zeroed
+fn redact_body_data(data: &str) -> String {
+    let Ok(bytes) = BASE64_URL_SAFE_NO_PAD.decode(data.trim_end_matches('=')) else {
+        return String::new();
+    };
+    let redacted = match String::from_utf8(bytes) {
+        Ok(text) => redact_markup(&text).into_bytes(),
+        Err(e) => vec![0; e.as_bytes().len()],
+    };
+    BASE64_URL_SAFE_NO_PAD.encode(redacted)
+}
+
+/// Redact text and quoted attribute values, keeping tag and attribute names
+fn redact_markup(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+    let mut quote = None;
+    for c in text.chars() {
+        match (in_tag, quote) {
+            (true, Some(q)) => {
+                if c == q {
+                    quote = None;
+                    out.push(c);
+                } else {
+                    out.push_str(&redact_text(c.encode_utf8(&mut [0; 4])));
+                }
+            }
+            (true, None) => {
+                match c {
+                    '"' | '\'' => quote = Some(c),
+                    '>' => in_tag = false,
+                    _ => {}
+                }
+                out.push(c);
+            }
+            (false, _) => {
+                if c == '<' {
+                    in_tag = true;
+                    out.push(c);
+                } else {
+                    out.push_str(&redact_text(c.encode_utf8(&mut [0; 4])));
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn encode(text: &str) -> String {
+        BASE64_URL_SAFE_NO_PAD.encode(text)
+    }
+
+    #[test]
+    fn test_record_sanitizes_messages() {
+        let recorder = TrafficRecorder::new();
+        recorder.record(
+            "/users/me/messages/m1?format=full",
+            json!({
+                "id": "m1",
+                "threadId": "t1",
+                "snippet": "Meet at 5?",
+                "payload": {
+                    "headers": [
+                        { "name": "From", "value": "Alice Smith <Alice@corp.com>" },
+                        { "name": "To", "value": "bob@corp.com, alice@corp.com" },
+                        { "name": "Subject", "value": "Q3 plans" },
+                        { "name": "Content-Type", "value": "text/html; charset=UTF-8" },
+                    ],
+                    "body": { "data": encode("<a href=\"https://x.io/Secret\">Hi Bob</a>") },
+                    "parts": [{ "filename": "Budget 2024.pdf", "body": { "attachmentId": "a1" } }],
+                },
+            }),
+        );
+
+        let response = &recorder.exchanges()[0].response;
+        assert_eq!(response["id"], "m1");
+        assert_eq!(response["snippet"], "Xxxx xx 0?");
+        let headers = &response["payload"]["headers"];
+        assert_eq!(headers[0]["value"], "Xxxxx Xxxxx <user1@example.com>");
+        // The same address gets the same pseudonym
+        assert_eq!(headers[1]["value"], "user2@example.com, user1@example.com");
+        assert_eq!(headers[2]["value"], "X0 xxxxx");
+        assert_eq!(headers[3]["value"], "text/html; charset=UTF-8");
+
+        let body = response["payload"]["body"]["data"].as_str().unwrap();
+        let body = String::from_utf8(BASE64_URL_SAFE_NO_PAD.decode(body).unwrap()).unwrap();
+        assert_eq!(body, "<a href=\"xxxxx://x.xx/Xxxxxx\">Xx Xxx</a>");
+        assert_eq!(
+            response["payload"]["parts"][0]["filename"],
+            "Xxxxxx 0000.pdf"
+        );
+        assert_eq!(
+            response["payload"]["parts"][0]["body"]["attachmentId"],
+            "a1"
+        );
+    }
+
+    #[test]
+    fn test_replay_in_order_then_repeats() {
+        let replay = TrafficReplay::new(vec![
+            Exchange {
+                request: "/users/me/profile".to_string(),
+                status: 200,
+                response: json!({ "historyId": "1" }),
+            },
+            Exchange {
+                request: "/users/me/profile".to_string(),
+                status: 200,
+                response: json!({ "historyId": "2" }),
+            },
+            Exchange {
+                request: "/users/me/history?startHistoryId=1".to_string(),
+                status: 404,
+                response: Value::Null,
+            },
+        ]);
+
+        assert_eq!(
+            replay.respond("/users/me/profile").unwrap()["historyId"],
+            "1"
+        );
+        assert_eq!(
+            replay.respond("/users/me/profile").unwrap()["historyId"],
+            "2"
+        );
+        assert_eq!(
+            replay.respond("/users/me/profile").unwrap()["historyId"],
+            "2"
+        );
+
+        let err = replay
+            .respond("/users/me/history?startHistoryId=1")
+            .unwrap_err();
+        assert_eq!(error_status(&err), Some(404));
+        assert!(replay.respond("/users/me/labels").is_err());
+    }
+
+    #[test]
+    fn test_fixture_roundtrip() {
+        let recorder = TrafficRecorder::new();
+        recorder.record(
+            "/users/me/profile",
+            json!({ "emailAddress": "me@corp.com", "historyId": "7" }),
+        );
+        recorder.record_error(
+            "/users/me/history?startHistoryId=1",
+            &HistoryExpiredError.into(),
+        );
+        recorder.record_error("/users/me/profile", &anyhow::anyhow!("connection reset"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("traffic.json");
+        recorder.save(&path).unwrap();
+
+        let replay = TrafficReplay::load(&path).unwrap();
+        let profile = replay.respond("/users/me/profile").unwrap();
+        assert_eq!(profile["emailAddress"], "user1@example.com");
+        assert_eq!(profile["historyId"], "7");
+        assert!(
+            replay
+                .respond("/users/me/history?startHistoryId=1")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_client_replays_sync_requests() {
+        use crate::gmail::GmailClient;
+        use crate::models::MessageId;
+        use std::sync::Arc;
+
+        let replay = TrafficReplay::new(vec![
+            Exchange {
+                request: "/users/me/messages?maxResults=10&includeSpamTrash=true".to_string(),
+                status: 200,
+                response: json!({ "messages": [{ "id": "m1", "threadId": "t1" }] }),
+            },
+            Exchange {
+                request: "/users/me/messages/m1?format=full".to_string(),
+                status: 200,
+                response: json!({
+                    "id": "m1",
+                    "threadId": "t1",
+                    "snippet": "Xx",
+                    "internalDate": "1700000000000",
+                }),
+            },
+            Exchange {
+                request: "/users/me/history?startHistoryId=5&historyTypes=messageAdded&historyTypes=labelAdded&historyTypes=labelRemoved".to_string(),
+                status: 404,
+                response: Value::Null,
+            },
+        ]);
+        let client = GmailClient::replay(Arc::new(replay));
+
+        let list = client.list_messages(10, None, None).unwrap();
+        assert_eq!(list.messages.unwrap()[0].id, "m1");
+
+        let messages = client.get_messages_batch(&[MessageId::new("m1"), MessageId::new("m2")]);
+        assert_eq!(messages[0].as_ref().unwrap().thread_id, "t1");
+        assert!(messages[1].is_err());
+
+        // An expired history ID still reads as expired
+        let err = client.list_history("5", None).unwrap_err();
+        assert!(err.downcast_ref::<HistoryExpiredError>().is_some());
+    }
+}
@@ -0,0 +1,382 @@
+//! Data integrity checks
+//!
+//! Extends the store's own check (`MailStore::check_integrity`) with search
+//! documents left behind for messages that no longer exist. Apps can run
+//! the check on startup; whether they do, and whether problems are repaired
+//! or only logged, is read from `mail.integrity.json` in the Cosmos config
+//! directory.
+//!
+//! `check_thread_invariants` goes further and recomputes every thread from
+//! its messages. It is meant for tests and debugging: a stale thread row
+//! shows wrong subjects, snippets or unread state in every list view.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{MessageId, Thread, ThreadId};
+use crate::search::SearchBackend;
+use crate::storage::{
+    IntegrityReport, MailStore, MessageMetadata, ThreadCursor, summarize_thread,
+};
+
+/// Config file holding startup integrity check settings
+pub const INTEGRITY_CONFIG_FILE: &str = "mail.integrity.json";
+
+/// Threads read from the store per batch when collecting message IDs
+const THREAD_BATCH_SIZE: usize = 200;
+
+/// Whether to check (and repair) data integrity on startup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IntegrityConfig {
+    /// Run the check when the app starts
+    pub check_on_startup: bool,
+    /// Repair what the startup check finds instead of only logging it
+    pub repair_on_startup: bool,
+}
+
+impl IntegrityConfig {
+    /// Load settings from the config directory, falling back to defaults
+    ///
+    /// An unreadable or malformed file is logged and ignored.
+    pub fn load() -> Self {
+        if !config::config_exists(INTEGRITY_CONFIG_FILE) {
+            return Self::default();
+        }
+        match config::load_json(INTEGRITY_CONFIG_FILE) {
+            Ok(integrity_config) => integrity_config,
+            Err(e) => {
+                warn!("Ignoring invalid integrity config: {}", e);
+                Self::default()
+            }
+        }
+    }
+}
+
+/// Check the store and, if given, the search index for orphaned data
+///
+/// Reads every stored thread, so run it off the UI thread.
+pub fn check_integrity(
+    store: &dyn MailStore,
+    search: Option<&dyn SearchBackend>,
+) -> Result<IntegrityReport> {
+    let mut report = store.check_integrity()?;
+    if let Some(search) = search {
+        report.search_documents_without_messages =
+            orphaned_documents(store, search, &report.messages_without_threads)?;
+    }
+    Ok(report)
+}
+
+/// Repair the store, then remove search documents for missing messages
+///
+/// Returns the problems that were repaired.
+pub fn repair_integrity(
+    store: &dyn MailStore,
+    search: Option<&dyn SearchBackend>,
+) -> Result<IntegrityReport> {
+    let mut report = store.repair()?;
+    if let Some(search) = search {
+        // Messages whose threads were just rebuilt are reachable again
+        let orphans = orphaned_documents(store, search, &[])?;
+        for id in &orphans {
+            search.delete_message(id)?;
+        }
+        if !orphans.is_empty() {
+            search.commit()?;
+        }
+        report.search_documents_without_messages = orphans;
+    }
+    Ok(report)
+}
+
+/// Run the startup check if `config` enables it, logging what it finds
+///
+/// Returns None when the check is disabled.
+pub fn run_startup_check(
+    store: &dyn MailStore,
+    search: Option<&dyn SearchBackend>,
+    config: &IntegrityConfig,
+) -> Result<Option<IntegrityReport>> {
+    if !config.check_on_startup {
+        return Ok(None);
+    }
+
+    let report = if config.repair_on_startup {
+        repair_integrity(store, search)?
+    } else {
+        check_integrity(store, search)?
+    };
+
+    if report.is_clean() {
+        info!("Integrity check passed");
+    } else {
+        warn!(
+            "Integrity check {} {} problems: {} messages without threads, {} stale thread \
+             labels, {} orphaned blobs, {} orphaned search documents",
+            if config.repair_on_startup {
+                "repaired"
+            } else {
+                "found"
+            },
+            report.issue_count(),
+            report.messages_without_threads.len(),
+            report.thread_labels_without_threads.len(),
+            report.blobs_without_messages.len(),
+            report.search_documents_without_messages.len()
+        );
+    }
+
+    Ok(Some(report))
+}
+
+/// A stored thread that disagrees with its messages
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadMismatch {
+    pub thread_id: ThreadId,
+    /// Names of the thread fields that differ, or "messages" when the
+    /// thread has none
+    pub fields: Vec<&'static str>,
+}
+
+/// Check every stored thread against a summary recomputed from its messages
+///
+/// Threads must have at least one message, all of the thread's account,
+/// and their subject, snippet, sender, participants, timestamp, unread state
+/// and message count must be exactly what sync would compute. Reads every message's
+/// metadata, so it is slow on large mailboxes.
+pub fn check_thread_invariants(store: &dyn MailStore) -> Result<Vec<ThreadMismatch>> {
+    let mut mismatches = Vec::new();
+    for_each_thread(store, |thread| {
+        let messages = store.list_messages_for_thread(&thread.id)?;
+        let refs: Vec<&MessageMetadata> = messages.iter().collect();
+        let fields = match summarize_thread(&thread.id, thread.account_id, &refs) {
+            Some(expected) => {
+                let mut fields = thread_differences(thread, &expected);
+                if messages.iter().any(|m| m.account_id != thread.account_id) {
+                    fields.push("account_id");
+                }
+                fields
+            }
+            None => vec!["messages"],
+        };
+        if !fields.is_empty() {
+            mismatches.push(ThreadMismatch {
+                thread_id: thread.id.clone(),
+                fields,
+            });
+        }
+        Ok(())
+    })?;
+    Ok(mismatches)
+}
+
+/// Names of the fields where `thread` differs from `expected`
+fn thread_differences(thread: &Thread, expected: &Thread) -> Vec<&'static str> {
+    let checks = [
+        ("subject", thread.subject == expected.subject),
+        ("snippet", thread.snippet == expected.snippet),
+        ("last_message_at", thread.last_message_at == expected.last_message_at),
+        ("message_count", thread.message_count == expected.message_count),
+        ("sender_name", thread.sender_name == expected.sender_name),
+        ("sender_email", thread.sender_email == expected.sender_email),
+        ("is_unread", thread.is_unread == expected.is_unread),
+        ("participants", thread.participants == expected.participants),
+    ];
+    checks
+        .into_iter()
+        .filter(|(_, same)| !same)
+        .map(|(field, _)| field)
+        .collect()
+}
+
+/// Call `f` with every stored thread, newest first
+fn for_each_thread(
+    store: &dyn MailStore,
+    mut f: impl FnMut(&Thread) -> Result<()>,
+) -> Result<()> {
+    let mut cursor: Option<ThreadCursor> = None;
+    loop {
+        let batch = store.list_threads_after(None, None, cursor.as_ref(), THREAD_BATCH_SIZE)?;
+        let exhausted = batch.len() < THREAD_BATCH_SIZE;
+
+        for thread in &batch {
+            f(thread)?;
+        }
+
+        cursor = batch.last().map(ThreadCursor::for_thread);
+        if exhausted || cursor.is_none() {
+            return Ok(());
+        }
+    }
+}
+
+/// Indexed messages that aren't in the store
+///
+/// Stored messages are found through their threads; `threadless` lists
+/// those without one, which are stored all the same.
+fn orphaned_documents(
+    store: &dyn MailStore,
+    search: &dyn SearchBackend,
+    threadless: &[MessageId],
+) -> Result<Vec<MessageId>> {
+    let mut stored: HashSet<MessageId> = threadless.iter().cloned().collect();
+    for_each_thread(store, |thread| {
+        stored.extend(store.get_message_ids_for_thread(&thread.id)?);
+        Ok(())
+    })?;
+
+    let mut orphans: Vec<MessageId> = search
+        .indexed_message_ids()?
+        .into_iter()
+        .filter(|id| !stored.contains(id))
+        .collect();
+    orphans.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    Ok(orphans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Message, Thread, ThreadId};
+    use crate::search::SearchIndex;
+    use crate::storage::InMemoryMailStore;
+    use chrono::Utc;
+
+    fn message(id: &str, thread_id: &str) -> Message {
+        Message::builder(MessageId::new(id), ThreadId::new(thread_id))
+            .account_id(1)
+            .subject("Quarterly report")
+            .label_ids(vec!["INBOX".to_string()])
+            .build()
+    }
+
+    fn thread(id: &str) -> Thread {
+        Thread::new(
+            ThreadId::new(id),
+            1,
+            "Quarterly report".to_string(),
+            String::new(),
+            Utc::now(),
+            1,
+            None,
+            "sender@example.com".to_string(),
+            false,
+        )
+    }
+
+    #[test]
+    fn test_check_and_repair() {
+        let store = InMemoryMailStore::new();
+        let index = SearchIndex::in_memory().unwrap();
+
+        // A healthy thread, a message whose thread is missing, and a search
+        // document for a message that was never stored
+        store.upsert_thread(thread("t1")).unwrap();
+        for (id, thread_id) in [("m1", "t1"), ("m2", "t2"), ("m3", "t3")] {
+            let message = message(id, thread_id);
+            index.index_message(&message, &thread(thread_id)).unwrap();
+            if id != "m3" {
+                store.upsert_message(message).unwrap();
+            }
+        }
+        index.commit().unwrap();
+
+        let report = check_integrity(&store, Some(&index)).unwrap();
+        assert_eq!(report.messages_without_threads, vec![MessageId::new("m2")]);
+        assert_eq!(
+            report.thread_labels_without_threads,
+            vec![ThreadId::new("t2")]
+        );
+        assert_eq!(
+            report.search_documents_without_messages,
+            vec![MessageId::new("m3")]
+        );
+        assert_eq!(report.issue_count(), 3);
+
+        let repaired = repair_integrity(&store, Some(&index)).unwrap();
+        assert_eq!(
+            repaired.messages_without_threads,
+            vec![MessageId::new("m2")]
+        );
+        // The rebuilt thread makes its label rows valid again
+        assert!(repaired.thread_labels_without_threads.is_empty());
+        assert_eq!(
+            repaired.search_documents_without_messages,
+            vec![MessageId::new("m3")]
+        );
+
+        let thread = store.get_thread(&ThreadId::new("t2")).unwrap().unwrap();
+        assert_eq!(thread.subject, "Quarterly report");
+        assert!(check_integrity(&store, Some(&index)).unwrap().is_clean());
+    }
+
+    #[test]
+    fn test_check_thread_invariants() {
+        use crate::models::EmailAddress;
+
+        let store = InMemoryMailStore::new();
+        let at = Utc::now();
+        let stored = |id: &str, thread_id: &str, labels: &[&str]| {
+            let message = Message::builder(MessageId::new(id), ThreadId::new(thread_id))
+                .account_id(1)
+                .from(EmailAddress::new("sender@example.com"))
+                .subject("Quarterly report")
+                .received_at(at)
+                .label_ids(labels.iter().map(|l| l.to_string()).collect())
+                .build();
+            store.upsert_message(message).unwrap();
+        };
+        let stored_thread = |id: &str, message_count: usize| {
+            let mut thread = thread(id);
+            thread.last_message_at = at;
+            thread.message_count = message_count;
+            store.upsert_thread(thread).unwrap();
+        };
+
+        stored_thread("t1", 1);
+        stored("m1", "t1", &["INBOX"]);
+        // Claims two messages, and is read while its message is unread
+        stored_thread("t2", 2);
+        stored("m2", "t2", &["INBOX", "UNREAD"]);
+        // No messages at all
+        stored_thread("t3", 1);
+
+        let mut mismatches = check_thread_invariants(&store).unwrap();
+        mismatches.sort_by(|a, b| a.thread_id.as_str().cmp(b.thread_id.as_str()));
+        assert_eq!(
+            mismatches,
+            vec![
+                ThreadMismatch {
+                    thread_id: ThreadId::new("t2"),
+                    fields: vec!["message_count", "is_unread"],
+                },
+                ThreadMismatch {
+                    thread_id: ThreadId::new("t3"),
+                    fields: vec!["messages"],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_startup_check() {
+        let store = InMemoryMailStore::new();
+        store.upsert_message(message("m1", "t1")).unwrap();
+
+        let report = run_startup_check(&store, None, &IntegrityConfig::default()).unwrap();
+        assert!(report.is_none());
+
+        // Checking alone leaves the problem in place
+        let config = IntegrityConfig {
+            check_on_startup: true,
+            repair_on_startup: false,
+        };
+        let report = run_startup_check(&store, None, &config).unwrap().unwrap();
+        assert_eq!(report.issue_count(), 2);
+        assert!(store.get_thread(&ThreadId::new("t1")).unwrap().is_none());
+    }
+}
@@ -7,6 +7,25 @@
 //! - Idempotent sync engine
 //! - Query API for UI consumption
 //! - Action handlers for mutations (archive, star, read/unread)
+//! - Rules engine for incoming mail (VIP senders, auto-archive, auto-label)
+//! - Gmail filter import into local rules
+//! - Attachment text extraction for search (`attachment-text` feature)
+//! - Body language detection and a pluggable translation hook
+//! - Cached thread summaries from a pluggable summarizer
+//! - Smart reply suggestions from a pluggable provider
+//! - Opt-in open tracking for sent mail via a user-run beacon endpoint
+//! - Follow-up reminders for sent mail that got no reply
+//! - Private, searchable thread notes
+//! - Inbox categories (Primary, Social, Promotions, Updates, Forums)
+//! - Mailbox analytics (top senders, daily volume, reply latency, storage)
+//! - Integrity checks for orphaned rows, blobs and search documents
+//! - Local purge of Trash and Spam past Gmail's 30-day retention
+//! - Account health, reconnection and removal
+//! - Printable PDF export of threads
+//! - Mailbox snapshots for moving to another machine without a resync
+//! - Desktop notifications for mail matching notify rules
+//! - Event socket shared by the `cosmosd` sync daemon and the apps
+//! - Local socket API for third-party tools (`server` feature)
 //!
 //! This crate has zero UI dependencies and provides UniFFI bindings
 //! for Swift/Kotlin via the `ffi` module.
@@ -14,33 +33,112 @@
 // UniFFI scaffolding - generates the FFI glue code
 uniffi::setup_scaffolding!();
 
+pub mod accounts;
 pub mod actions;
+pub mod analytics;
+#[cfg(feature = "attachment-text")]
+pub mod attachment_text;
 pub mod config;
+pub mod daemon;
+pub mod diagnostics;
+pub mod error;
+pub mod export;
 pub mod ffi;
+pub mod filters;
 pub mod gmail;
+pub mod integrity;
 pub mod models;
+pub mod notifications;
 pub mod query;
+pub mod retention;
+pub mod rules;
 pub mod search;
+#[cfg(all(feature = "server", unix))]
+pub mod server;
+pub mod snapshot;
 pub mod storage;
 pub mod sync;
+pub mod telemetry;
+pub mod tracking;
+pub mod translate;
 
-pub use actions::ActionHandler;
+pub use accounts::{
+    AccountHealth, account_health, clear_account, reconnect_account, record_auth_failure,
+    refresh_labels, refresh_send_as_aliases, remove_account,
+};
+pub use actions::{
+    ActionHandler, ActionQueuedError, Attachment, MAX_ATTACHMENTS_SIZE, MAX_SEND_ATTEMPTS,
+    OutboxFlush, OutgoingAttachment, OutgoingMessage, QueueFlush, UnsubscribeOutcome, retry_delay,
+};
+pub use analytics::{
+    DailyVolume, LabelStorage, MailboxAnalytics, ResponseLatency, SenderRanking, SenderStats,
+    mailbox_analytics, messages_per_day, response_latency, storage_by_label, top_senders,
+};
 pub use config::GmailCredentials;
-pub use gmail::{GmailAuth, GmailClient, HistoryExpiredError, api::ProfileResponse};
-pub use models::{label_icon, label_sort_order, Account, EmailAddress, Label, LabelId, Message, MessageId, SyncState, Thread, ThreadId};
-pub use query::{ThreadDetail, ThreadSummary, get_thread_detail, list_threads, list_threads_by_label};
-pub use search::{FieldHighlight, HighlightSpan, ParsedQuery, SearchIndex, SearchResult, parse_query, search_threads};
+#[cfg(unix)]
+pub use daemon::EventServer;
+pub use daemon::{DAEMON_SOCKET_FILE, DaemonEvent, DaemonEvents, connect_daemon, daemon_socket_path};
+pub use diagnostics::{
+    DiagnosticEvent, DiagnosticKind, DiagnosticLog, diagnostics, init_diagnostics,
+    record_diagnostic,
+};
+pub use error::{MailError, MailResult};
+pub use export::{export_thread_pdf, thread_pdf};
+pub use filters::{FilterDiff, RuleChange, UnsupportedFilter, diff_filters, import_gmail_filters};
+pub use gmail::{
+    ApiCall, GmailApi, GmailAuth, GmailClient, HistoryExpiredError, QuotaMeter,
+    ReauthRequiredError, TrafficRecorder, TrafficReplay, VacationSettings, api::ProfileResponse,
+    is_transient_error,
+};
+pub use integrity::{
+    IntegrityConfig, ThreadMismatch, check_integrity, check_thread_invariants, repair_integrity,
+    run_startup_check,
+};
+pub use models::{label_icon, label_sort_order, Account, AccountSettings, AuthResults, AuthVerdict, Category, EmailAddress, EventInvite, EventTime, FollowupState, InviteMethod, Label, LabelColor, LabelId, LabelNode, Message, MessageId, OpenStatus, OutboxMessage, OutboxState, QueuedAction, Rule, RuleAction, RulePredicate, RsvpResponse, RsvpStatus, SavedSearch, SendAsAlias, Signature, SyncState, Thread, ThreadId, ThreadNote, Unsubscribe};
+pub use notifications::{NOTIFICATIONS_CONFIG_FILE, NotificationConfig, Notifier, QuietHours};
+pub use query::{
+    CategoryCount, DEFAULT_FOLLOWUP_DAYS, FollowupCandidate, LargeThread, ReplySuggester,
+    SavedSearchSummary, Summarizer, ThreadDetail, ThreadFilter, ThreadInvite, ThreadListDiff,
+    ThreadMove, ThreadPage, ThreadSummary, UnsubscribeSender, attach_thread_labels,
+    build_reply_prompt,
+    count_unread_by_category, count_unsent, decode_raw_source, diff_thread_lists,
+    dismiss_followup, get_original_source, get_thread_detail, get_thread_label_ids,
+    get_thread_summary, label_tree, list_followup_candidates, list_largest_threads, list_message_rows,
+    list_outbox, list_saved_searches_with_counts, list_threads,
+    list_threads_by_category, list_threads_by_label, list_threads_filtered,
+    list_unsubscribe_senders, sanitize_html, set_thread_note, snooze_followup, suggest_replies,
+};
+pub use retention::{TRASH_RETENTION_DAYS, purge_expired_trash};
+pub use rules::{RuleMatch, apply_rules, push_rule_changes};
+#[cfg(feature = "fts5")]
+pub use search::FtsSearchIndex;
+pub use search::{FieldHighlight, HIGHLIGHT_CLASS, HighlightSpan, IndexVerification, InstantSearchOptions, InstantSearchResults, ParsedQuery, SearchBackend, SearchConfig, SearchIndex, SearchPage, SearchResult, SearchSuggestion, SuggestionKind, body_highlights, find_term_spans, highlight_html, instant_search, parse_query, search_threads, search_threads_page, suggest};
+pub use snapshot::{
+    SNAPSHOT_FORMAT, SnapshotManifest, export_snapshot, import_snapshot, read_snapshot_manifest,
+};
 pub use storage::{
-    BlobKey, BlobStore, ContentType, FileBlobStore, InMemoryMailStore, MailStore,
-    MessageBody, MessageMetadata, PendingMessage, SqliteMailStore,
+    BlobEntry, BlobKey, BlobStore, CompactionReport, ContentType, ContentUsage, FileBlobStore,
+    InMemoryMailStore, IntegrityReport, MailStore, MessageBody, MessageMetadata, PendingMessage,
+    SqliteMailStore, StorageStats, StoreConfig, StoreEvent, ThreadCursor,
 };
 pub use sync::{
     // Sync execution
-    FetchPhaseStats, ProcessBatchResult, SyncOptions, SyncStats, SyncTiming,
-    fetch_phase, process_pending_batch, sync_gmail, incremental_sync,
+    DEFAULT_MAX_PENDING, FetchPhaseStats, ProcessBatchResult, SyncCancel, SyncCancelledError,
+    SyncOptions, SyncStats, SyncTiming,
+    fetch_phase, process_pending_batch, sync_gmail, incremental_sync, backfill_sync,
+    history_has_gap, reconcile_sync,
+    // Time-boxed sync slices (for mobile background tasks)
+    SyncCheckpoint, SyncStep, SyncStepPhase, sync_step,
     // Sync decision (for app startup logic)
     SyncAction, SyncStateInfo, ResumeProgress,
     determine_sync_action, should_auto_sync_on_startup, get_sync_state_info,
     // Sync timing (for UI cooldown management)
-    cooldown_elapsed,
+    cooldown_elapsed, next_allowed_sync_at, seconds_until, check_sync_allowed,
+    SchedulerState, SyncSchedule, SyncSkipReason, watch_sync_schedule,
+    // Pausing and resuming accounts
+    SyncOrchestrator, SyncTicket,
+    // Network budgets (for metered connections)
+    NetworkBudget, NetworkBudgetSpentError, is_metered_connection,
 };
+pub use tracking::{TRACKING_CONFIG_FILE, TrackingConfig, sync_opens};
+pub use translate::{BodyFormat, TranslatedBody, Translator, detect_language, get_translated_body};
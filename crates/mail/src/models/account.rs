@@ -20,6 +20,53 @@ pub struct Account {
     pub added_at: DateTime<Utc>,
     /// OAuth token data (JSON-serialized)
     pub token_data: Option<String>,
+    /// Signature appended to mail sent from this account
+    #[serde(default)]
+    pub signature: Option<Signature>,
+    /// Whether the account syncs; paused accounts keep their mail but
+    /// aren't synced until resumed
+    #[serde(default = "default_true")]
+    pub sync_enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Signature appended to outgoing mail
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Signature {
+    /// Plain text signature, used for plain text bodies
+    pub text: String,
+    /// HTML signature for HTML bodies (the escaped text is used if absent)
+    pub html: Option<String>,
+}
+
+impl Signature {
+    /// Create a plain text signature
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            html: None,
+        }
+    }
+
+    /// Set the HTML version of the signature
+    pub fn with_html(mut self, html: impl Into<String>) -> Self {
+        self.html = Some(html.into());
+        self
+    }
+}
+
+/// The user-editable settings of an account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountSettings {
+    /// Display name shown in the sidebar and used in the From header
+    pub display_name: Option<String>,
+    /// Avatar color (HSL string)
+    pub avatar_color: String,
+    /// Signature appended to outgoing mail
+    pub signature: Option<Signature>,
 }
 
 impl Account {
@@ -35,6 +82,8 @@ impl Account {
             is_primary: false,
             added_at: Utc::now(),
             token_data: None,
+            signature: None,
+            sync_enabled: true,
         }
     }
 
@@ -50,6 +99,8 @@ impl Account {
             is_primary: false,
             added_at: Utc::now(),
             token_data: None,
+            signature: None,
+            sync_enabled: true,
         }
     }
 
@@ -71,6 +122,28 @@ impl Account {
         self
     }
 
+    /// Set the signature for outgoing mail
+    pub fn with_signature(mut self, signature: Signature) -> Self {
+        self.signature = Some(signature);
+        self
+    }
+
+    /// The account's current editable settings
+    pub fn settings(&self) -> AccountSettings {
+        AccountSettings {
+            display_name: self.display_name.clone(),
+            avatar_color: self.avatar_color.clone(),
+            signature: self.signature.clone(),
+        }
+    }
+
+    /// Replace the account's editable settings
+    pub fn apply_settings(&mut self, settings: AccountSettings) {
+        self.display_name = settings.display_name;
+        self.avatar_color = settings.avatar_color;
+        self.signature = settings.signature;
+    }
+
     /// Generate a consistent color based on email address
     fn generate_color(email: &str) -> String {
         // Simple hash-based color generation
@@ -125,6 +198,19 @@ mod tests {
         assert_eq!(account.avatar_letter(), "T");
     }
 
+    #[test]
+    fn test_apply_settings() {
+        let mut account = Account::new("test@example.com");
+        let mut settings = account.settings();
+        settings.display_name = Some("Test User".to_string());
+        settings.avatar_color = "hsl(150, 70%, 40%)".to_string();
+        settings.signature = Some(Signature::text("Test").with_html("<b>Test</b>"));
+
+        account.apply_settings(settings.clone());
+        assert_eq!(account.settings(), settings);
+        assert_eq!(account.email, "test@example.com");
+    }
+
     #[test]
     fn test_consistent_color() {
         let account1 = Account::new("test@example.com");
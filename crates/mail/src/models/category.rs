@@ -0,0 +1,275 @@
+//! Inbox categories (Primary, Social, Promotions, Updates, Forums)
+//!
+//! Gmail marks inbox mail with `CATEGORY_*` labels. Messages that arrive
+//! without one (other providers, or Gmail with tabs turned off) are sorted
+//! by a local heuristic and given the matching label, so every tab can be
+//! listed through the same label queries.
+
+use serde::{Deserialize, Serialize};
+
+use super::Message;
+
+/// Sender domains whose mail is social network activity
+const SOCIAL_DOMAINS: &[&str] = &[
+    "facebook.com",
+    "facebookmail.com",
+    "instagram.com",
+    "linkedin.com",
+    "meetup.com",
+    "nextdoor.com",
+    "pinterest.com",
+    "reddit.com",
+    "redditmail.com",
+    "tiktok.com",
+    "twitter.com",
+    "x.com",
+];
+
+/// Domains hosting mailing lists and discussion groups
+const FORUM_DOMAINS: &[&str] = &["googlegroups.com", "groups.io", "discoursemail.com"];
+
+/// Sender local parts used for automated notifications
+const NOTIFICATION_SENDERS: &[&str] = &[
+    "alerts",
+    "billing",
+    "no-reply",
+    "noreply",
+    "notifications",
+    "notify",
+    "receipts",
+    "security",
+];
+
+/// Words marking transactional mail
+const UPDATE_WORDS: &[&str] = &[
+    "confirmation",
+    "delivered",
+    "invoice",
+    "order",
+    "password",
+    "payment",
+    "receipt",
+    "reservation",
+    "shipped",
+    "statement",
+    "verify",
+];
+
+/// Sender local parts used for marketing mail
+const PROMOTION_SENDERS: &[&str] = &[
+    "deals",
+    "marketing",
+    "news",
+    "newsletter",
+    "offers",
+    "promo",
+];
+
+/// Words marking marketing mail
+const PROMOTION_WORDS: &[&str] = &[
+    "coupon", "deal", "deals", "discount", "offer", "promo", "sale", "savings",
+];
+
+/// Phrases marking marketing mail
+const PROMOTION_PHRASES: &[&str] = &["% off", "free shipping", "limited time", "shop now"];
+
+/// An inbox tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    /// Person-to-person mail and anything not sorted elsewhere
+    Primary,
+    /// Social networks and media sites
+    Social,
+    /// Deals, offers, and marketing
+    Promotions,
+    /// Receipts, bills, statements, and notifications
+    Updates,
+    /// Mailing lists and discussion groups
+    Forums,
+}
+
+impl Category {
+    /// All categories in tab order
+    pub const ALL: [Category; 5] = [
+        Category::Primary,
+        Category::Social,
+        Category::Promotions,
+        Category::Updates,
+        Category::Forums,
+    ];
+
+    /// Gmail label ID for the category
+    pub fn label_id(self) -> &'static str {
+        match self {
+            Category::Primary => "CATEGORY_PERSONAL",
+            Category::Social => "CATEGORY_SOCIAL",
+            Category::Promotions => "CATEGORY_PROMOTIONS",
+            Category::Updates => "CATEGORY_UPDATES",
+            Category::Forums => "CATEGORY_FORUMS",
+        }
+    }
+
+    /// Category for a Gmail `CATEGORY_*` label ID
+    pub fn from_label_id(label_id: &str) -> Option<Category> {
+        Category::ALL.into_iter().find(|c| c.label_id() == label_id)
+    }
+
+    /// Tab title
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Category::Primary => "Primary",
+            Category::Social => "Social",
+            Category::Promotions => "Promotions",
+            Category::Updates => "Updates",
+            Category::Forums => "Forums",
+        }
+    }
+
+    /// Category given by the labels, if any
+    pub fn from_labels(label_ids: &[String]) -> Option<Category> {
+        label_ids.iter().find_map(|l| Category::from_label_id(l))
+    }
+
+    /// Guess the category of a message from its sender and text
+    pub fn classify(message: &Message) -> Category {
+        let email = message.from.email.to_lowercase();
+        let (local, domain) = email.rsplit_once('@').unwrap_or(("", email.as_str()));
+        let on_domain = |domains: &[&str]| {
+            domains
+                .iter()
+                .any(|d| domain == *d || domain.ends_with(&format!(".{}", d)))
+        };
+
+        if on_domain(SOCIAL_DOMAINS) {
+            return Category::Social;
+        }
+        let to_list = message.to.iter().chain(&message.cc).any(|a| {
+            let address = a.email.to_lowercase();
+            FORUM_DOMAINS
+                .iter()
+                .any(|d| address.ends_with(&format!("@{}", d)))
+        });
+        if on_domain(FORUM_DOMAINS) || to_list {
+            return Category::Forums;
+        }
+
+        let text = format!("{} {}", message.subject, message.body_preview).to_lowercase();
+        let words: Vec<&str> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect();
+        let has_word = |list: &[&str]| words.iter().any(|w| list.contains(w));
+
+        if PROMOTION_SENDERS.contains(&local)
+            || has_word(PROMOTION_WORDS)
+            || PROMOTION_PHRASES.iter().any(|p| text.contains(p))
+        {
+            return Category::Promotions;
+        }
+        if NOTIFICATION_SENDERS.contains(&local) || has_word(UPDATE_WORDS) {
+            return Category::Updates;
+        }
+        Category::Primary
+    }
+
+    /// Give a received message a category label if it has none
+    ///
+    /// Primary needs no label, since that tab holds inbox mail in no other
+    /// category. Sent and draft messages are left alone, as in Gmail. Returns
+    /// the message's category, or None for messages that aren't categorized.
+    pub fn assign(message: &mut Message) -> Option<Category> {
+        if let Some(category) = Category::from_labels(&message.label_ids) {
+            return Some(category);
+        }
+        let outgoing = message
+            .label_ids
+            .iter()
+            .any(|l| l == "SENT" || l == "DRAFT");
+        if outgoing {
+            return None;
+        }
+        let category = Category::classify(message);
+        if category != Category::Primary {
+            message.label_ids.push(category.label_id().to_string());
+        }
+        Some(category)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EmailAddress, MessageId, ThreadId};
+
+    fn message(from: &str, subject: &str) -> Message {
+        Message::builder(MessageId::new("m1"), ThreadId::new("t1"))
+            .from(EmailAddress::new(from))
+            .subject(subject)
+            .label_ids(vec!["INBOX".to_string()])
+            .build()
+    }
+
+    #[test]
+    fn test_label_roundtrip() {
+        for category in Category::ALL {
+            assert_eq!(Category::from_label_id(category.label_id()), Some(category));
+        }
+        assert_eq!(Category::from_label_id("INBOX"), None);
+    }
+
+    #[test]
+    fn test_classify() {
+        let classify = |from: &str, subject: &str| Category::classify(&message(from, subject));
+
+        assert_eq!(classify("friend@gmail.com", "Lunch?"), Category::Primary);
+        assert_eq!(
+            classify("notification@facebookmail.com", "New post"),
+            Category::Social
+        );
+        assert_eq!(
+            classify("digest@lists.googlegroups.com", "Weekly"),
+            Category::Forums
+        );
+        assert_eq!(
+            classify("shop@store.example", "Summer sale: 30% off"),
+            Category::Promotions
+        );
+        assert_eq!(
+            classify("newsletter@store.example", "Hello"),
+            Category::Promotions
+        );
+        assert_eq!(
+            classify("shop@store.example", "Your order has shipped"),
+            Category::Updates
+        );
+        assert_eq!(classify("noreply@bank.example", "Hello"), Category::Updates);
+        // Whole words only: "salesforce" is not a sale
+        assert_eq!(
+            classify("friend@gmail.com", "Salesforce demo"),
+            Category::Primary
+        );
+    }
+
+    #[test]
+    fn test_assign() {
+        // Gmail's own category wins
+        let mut msg = message("shop@store.example", "Summer sale");
+        msg.label_ids.push("CATEGORY_PERSONAL".to_string());
+        assert_eq!(Category::assign(&mut msg), Some(Category::Primary));
+        assert_eq!(msg.label_ids.len(), 2);
+
+        let mut msg = message("shop@store.example", "Summer sale");
+        assert_eq!(Category::assign(&mut msg), Some(Category::Promotions));
+        assert!(msg.label_ids.contains(&"CATEGORY_PROMOTIONS".to_string()));
+
+        let mut msg = message("friend@gmail.com", "Lunch?");
+        assert_eq!(Category::assign(&mut msg), Some(Category::Primary));
+        assert_eq!(msg.label_ids, vec!["INBOX"]);
+
+        let mut msg = message("me@example.com", "Summer sale");
+        msg.label_ids = vec!["SENT".to_string()];
+        assert_eq!(Category::assign(&mut msg), None);
+        assert_eq!(msg.label_ids, vec!["SENT"]);
+    }
+}
@@ -0,0 +1,35 @@
+//! Follow-up reminder state for sent threads awaiting a reply
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::ThreadId;
+
+/// What the user did with a thread's follow-up reminder
+///
+/// Threads without a stored state get reminders as usual.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FollowupState {
+    pub thread_id: ThreadId,
+    /// When the reminder was dismissed; a later sent message brings it back
+    pub dismissed_at: Option<DateTime<Utc>>,
+    /// Hide the reminder until this time
+    pub snoozed_until: Option<DateTime<Utc>>,
+}
+
+impl FollowupState {
+    /// A state with nothing dismissed or snoozed
+    pub fn new(thread_id: ThreadId) -> Self {
+        Self {
+            thread_id,
+            dismissed_at: None,
+            snoozed_until: None,
+        }
+    }
+
+    /// Whether the reminder for a message sent at `sent_at` is hidden at `now`
+    pub fn hides(&self, sent_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        self.dismissed_at.is_some_and(|at| at >= sent_at)
+            || self.snoozed_until.is_some_and(|until| until > now)
+    }
+}
@@ -0,0 +1,438 @@
+//! Calendar invite model parsed from text/calendar parts (RFC 5545/5546)
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::EmailAddress;
+
+/// What an invite asks of the recipient (iCalendar METHOD)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InviteMethod {
+    /// Informational only, no reply expected (also used when METHOD is absent)
+    Publish,
+    /// An invitation or update that attendees can reply to
+    Request,
+    /// An attendee's reply to the organizer
+    Reply,
+    /// The event was cancelled
+    Cancel,
+}
+
+/// An attendee's participation status (PARTSTAT)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RsvpStatus {
+    NeedsAction,
+    Accepted,
+    Declined,
+    Tentative,
+}
+
+impl RsvpStatus {
+    /// The PARTSTAT value
+    pub fn as_ical(&self) -> &'static str {
+        match self {
+            RsvpStatus::NeedsAction => "NEEDS-ACTION",
+            RsvpStatus::Accepted => "ACCEPTED",
+            RsvpStatus::Declined => "DECLINED",
+            RsvpStatus::Tentative => "TENTATIVE",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "ACCEPTED" => RsvpStatus::Accepted,
+            "DECLINED" => RsvpStatus::Declined,
+            "TENTATIVE" => RsvpStatus::Tentative,
+            _ => RsvpStatus::NeedsAction,
+        }
+    }
+}
+
+/// A reply to an invite
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RsvpResponse {
+    Accept,
+    Decline,
+    Tentative,
+}
+
+impl RsvpResponse {
+    /// The attendee status the response sets
+    pub fn status(&self) -> RsvpStatus {
+        match self {
+            RsvpResponse::Accept => RsvpStatus::Accepted,
+            RsvpResponse::Decline => RsvpStatus::Declined,
+            RsvpResponse::Tentative => RsvpStatus::Tentative,
+        }
+    }
+
+    /// Subject prefix of the reply, as calendar clients word it
+    pub fn label(&self) -> &'static str {
+        match self {
+            RsvpResponse::Accept => "Accepted",
+            RsvpResponse::Decline => "Declined",
+            RsvpResponse::Tentative => "Tentatively accepted",
+        }
+    }
+}
+
+/// When an event starts or ends
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventTime {
+    /// An absolute time
+    Utc { time: DateTime<Utc> },
+    /// Wall-clock time in the named zone, or floating if `tzid` is None
+    Local {
+        time: NaiveDateTime,
+        tzid: Option<String>,
+    },
+    /// An all-day date
+    Date { date: NaiveDate },
+}
+
+impl EventTime {
+    /// Parse a DTSTART/DTEND value with its TZID parameter
+    fn parse(value: &str, tzid: Option<&str>) -> Option<Self> {
+        let value = value.trim();
+        if value.len() == 8 {
+            let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+            return Some(EventTime::Date { date });
+        }
+        if let Some(utc) = value.strip_suffix('Z') {
+            let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+            return Some(EventTime::Utc {
+                time: time.and_utc(),
+            });
+        }
+        let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+        Some(EventTime::Local {
+            time,
+            tzid: tzid.map(str::to_string),
+        })
+    }
+}
+
+/// An invited participant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attendee {
+    pub address: EmailAddress,
+    pub status: RsvpStatus,
+}
+
+/// A calendar event carried by a message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventInvite {
+    /// Event UID, shared by every update to the same event
+    pub uid: String,
+    pub method: InviteMethod,
+    /// Revision number; a higher sequence supersedes earlier invites
+    pub sequence: i64,
+    pub summary: String,
+    pub organizer: Option<EmailAddress>,
+    pub start: Option<EventTime>,
+    pub end: Option<EventTime>,
+    pub location: Option<String>,
+    pub attendees: Vec<Attendee>,
+}
+
+impl EventInvite {
+    /// Parse the first VEVENT of an iCalendar object
+    ///
+    /// Returns None if there is no VEVENT or it has no UID.
+    pub fn parse_ics(ics: &str) -> Option<Self> {
+        let mut method = InviteMethod::Publish;
+        let mut invite: Option<EventInvite> = None;
+        let mut uid = None;
+        // Nesting inside the VEVENT (VALARM blocks have their own properties)
+        let mut depth = 0usize;
+        let mut in_event = false;
+
+        for line in unfold(ics) {
+            let Some((name, params, value)) = parse_line(&line) else {
+                continue;
+            };
+            match name.as_str() {
+                "BEGIN" if in_event => depth += 1,
+                "BEGIN" if value.eq_ignore_ascii_case("VEVENT") && invite.is_none() => {
+                    in_event = true;
+                    invite = Some(EventInvite {
+                        uid: String::new(),
+                        method,
+                        sequence: 0,
+                        summary: String::new(),
+                        organizer: None,
+                        start: None,
+                        end: None,
+                        location: None,
+                        attendees: Vec::new(),
+                    });
+                }
+                "END" if in_event && depth > 0 => depth -= 1,
+                "END" if in_event => in_event = false,
+                "METHOD" if !in_event => method = parse_method(&value),
+                _ if in_event && depth == 0 => {
+                    let Some(event) = invite.as_mut() else {
+                        continue;
+                    };
+                    let param = |key: &str| {
+                        params
+                            .iter()
+                            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                            .map(|(_, v)| v.as_str())
+                    };
+                    match name.as_str() {
+                        "UID" => uid = Some(value),
+                        "SEQUENCE" => event.sequence = value.trim().parse().unwrap_or(0),
+                        "SUMMARY" => event.summary = unescape(&value),
+                        "LOCATION" => {
+                            event.location = Some(unescape(&value)).filter(|l| !l.is_empty())
+                        }
+                        "DTSTART" => event.start = EventTime::parse(&value, param("TZID")),
+                        "DTEND" => event.end = EventTime::parse(&value, param("TZID")),
+                        "ORGANIZER" => event.organizer = calendar_address(&value, param("CN")),
+                        "ATTENDEE" => {
+                            if let Some(address) = calendar_address(&value, param("CN")) {
+                                let status = param("PARTSTAT")
+                                    .map_or(RsvpStatus::NeedsAction, RsvpStatus::parse);
+                                event.attendees.push(Attendee { address, status });
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut invite = invite?;
+        invite.uid = uid.filter(|u| !u.trim().is_empty())?;
+        Some(invite)
+    }
+
+    /// The attendee entry for an address (case-insensitive)
+    pub fn attendee(&self, email: &str) -> Option<&Attendee> {
+        self.attendees
+            .iter()
+            .find(|a| a.address.email.eq_ignore_ascii_case(email))
+    }
+
+    /// An address's participation status, if it was invited
+    pub fn status_for(&self, email: &str) -> Option<RsvpStatus> {
+        self.attendee(email).map(|a| a.status)
+    }
+
+    /// Record an attendee's status, adding them if they weren't listed
+    pub fn set_status(&mut self, address: &EmailAddress, status: RsvpStatus) {
+        match self
+            .attendees
+            .iter_mut()
+            .find(|a| a.address.email.eq_ignore_ascii_case(&address.email))
+        {
+            Some(attendee) => attendee.status = status,
+            None => self.attendees.push(Attendee {
+                address: address.clone(),
+                status,
+            }),
+        }
+    }
+
+    /// Whether the recipient can RSVP to this invite
+    pub fn can_respond(&self) -> bool {
+        self.method == InviteMethod::Request && self.organizer.is_some()
+    }
+}
+
+fn parse_method(value: &str) -> InviteMethod {
+    match value.trim().to_ascii_uppercase().as_str() {
+        "REQUEST" => InviteMethod::Request,
+        "REPLY" => InviteMethod::Reply,
+        "CANCEL" => InviteMethod::Cancel,
+        _ => InviteMethod::Publish,
+    }
+}
+
+/// Join folded content lines (a line break followed by a space or tab)
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Split a content line into its uppercased name, parameters and value
+///
+/// Parameter values may be quoted, in which case they can contain `:` and
+/// `;`.
+fn parse_line(line: &str) -> Option<(String, Vec<(String, String)>, String)> {
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(i),
+        _ => None,
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    in_quotes = false;
+    for c in head.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => parts.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    parts.push(current);
+
+    let mut parts = parts.into_iter();
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|p| {
+            p.split_once('=')
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+        })
+        .collect();
+    Some((name, params, value.to_string()))
+}
+
+/// An ORGANIZER or ATTENDEE value (`mailto:` URI) with its CN parameter
+fn calendar_address(value: &str, name: Option<&str>) -> Option<EmailAddress> {
+    let value = value.trim();
+    let email = value
+        .get(..7)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("mailto:"))
+        .map_or(value, |_| &value[7..]);
+    if !email.contains('@') {
+        return None;
+    }
+    Some(match name.filter(|n| !n.is_empty()) {
+        Some(name) => EmailAddress::with_name(name, email),
+        None => EmailAddress::new(email),
+    })
+}
+
+/// Undo TEXT value escaping
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const INVITE: &str = "BEGIN:VCALENDAR\r\n\
+        PRODID:-//Google Inc//Google Calendar 70.9054//EN\r\n\
+        METHOD:REQUEST\r\n\
+        BEGIN:VEVENT\r\n\
+        DTSTART:20260115T150000Z\r\n\
+        DTEND;TZID=Europe/London:20260115T160000\r\n\
+        DTSTAMP:20260110T120000Z\r\n\
+        ORGANIZER;CN=\"Lead, Team\":mailto:lead@example.com\r\n\
+        UID:abc123@google.com\r\n\
+        ATTENDEE;CUTYPE=INDIVIDUAL;ROLE=REQ-PARTICIPANT;PARTSTAT=NEEDS-ACTION;CN=me@ex\r\n \
+        ample.com;X-NUM-GUESTS=0:mailto:me@example.com\r\n\
+        ATTENDEE;PARTSTAT=ACCEPTED;CN=Lead:mailto:lead@example.com\r\n\
+        SEQUENCE:2\r\n\
+        LOCATION:Room 4\\, Floor 2\r\n\
+        SUMMARY:Planning\\nreview\r\n\
+        BEGIN:VALARM\r\n\
+        ACTION:DISPLAY\r\n\
+        SUMMARY:Reminder\r\n\
+        END:VALARM\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    #[test]
+    fn test_parse_ics() {
+        let invite = EventInvite::parse_ics(INVITE).unwrap();
+        assert_eq!(invite.uid, "abc123@google.com");
+        assert_eq!(invite.method, InviteMethod::Request);
+        assert_eq!(invite.sequence, 2);
+        assert_eq!(invite.summary, "Planning\nreview");
+        assert_eq!(invite.location.as_deref(), Some("Room 4, Floor 2"));
+        let organizer = invite.organizer.as_ref().unwrap();
+        assert_eq!(organizer.display(), "Lead, Team <lead@example.com>");
+        assert_eq!(
+            invite.start,
+            Some(EventTime::Utc {
+                time: Utc.with_ymd_and_hms(2026, 1, 15, 15, 0, 0).unwrap()
+            })
+        );
+        assert!(matches!(
+            &invite.end,
+            Some(EventTime::Local { tzid: Some(tz), .. }) if tz == "Europe/London"
+        ));
+        assert_eq!(invite.attendees.len(), 2);
+        assert_eq!(
+            invite.status_for("ME@example.com"),
+            Some(RsvpStatus::NeedsAction)
+        );
+        assert_eq!(
+            invite.status_for("lead@example.com"),
+            Some(RsvpStatus::Accepted)
+        );
+        assert!(invite.can_respond());
+    }
+
+    #[test]
+    fn test_parse_ics_variants() {
+        let cancel = "BEGIN:VCALENDAR\nMETHOD:CANCEL\nBEGIN:VEVENT\nUID:x\n\
+                      DTSTART;VALUE=DATE:20260201\nEND:VEVENT\nEND:VCALENDAR\n";
+        let invite = EventInvite::parse_ics(cancel).unwrap();
+        assert_eq!(invite.method, InviteMethod::Cancel);
+        assert!(!invite.can_respond());
+        assert_eq!(
+            invite.start,
+            Some(EventTime::Date {
+                date: NaiveDate::from_ymd_opt(2026, 2, 1).unwrap()
+            })
+        );
+
+        assert!(EventInvite::parse_ics("BEGIN:VCALENDAR\nEND:VCALENDAR\n").is_none());
+        assert!(EventInvite::parse_ics("BEGIN:VEVENT\nSUMMARY:No uid\nEND:VEVENT\n").is_none());
+    }
+
+    #[test]
+    fn test_set_status() {
+        let mut invite = EventInvite::parse_ics(INVITE).unwrap();
+        invite.set_status(&EmailAddress::new("me@example.com"), RsvpStatus::Accepted);
+        assert_eq!(
+            invite.status_for("me@example.com"),
+            Some(RsvpStatus::Accepted)
+        );
+
+        invite.set_status(
+            &EmailAddress::new("alias@example.com"),
+            RsvpStatus::Declined,
+        );
+        assert_eq!(invite.attendees.len(), 3);
+        assert_eq!(
+            invite.status_for("alias@example.com"),
+            Some(RsvpStatus::Declined)
+        );
+    }
+}
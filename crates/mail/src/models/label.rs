@@ -39,8 +39,24 @@ impl From<&str> for LabelId {
     }
 }
 
+/// Colors a label is shown in, as `#rrggbb` hex
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelColor {
+    pub background: String,
+    pub text: String,
+}
+
+impl LabelColor {
+    pub fn new(background: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            background: background.into(),
+            text: text.into(),
+        }
+    }
+}
+
 /// A mail label (folder)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Label {
     /// Label ID (e.g., "INBOX", "SENT", "Label_123")
     pub id: LabelId,
@@ -52,6 +68,12 @@ pub struct Label {
     pub message_count: u32,
     /// Number of unread messages
     pub unread_count: u32,
+    /// Color set in Gmail, or the local override
+    #[serde(default)]
+    pub color: Option<LabelColor>,
+    /// Icon chosen locally, replacing the default from [`label_icon`]
+    #[serde(default)]
+    pub icon: Option<String>,
 }
 
 impl Label {
@@ -63,6 +85,8 @@ impl Label {
             is_system: false,
             message_count: 0,
             unread_count: 0,
+            color: None,
+            icon: None,
         }
     }
 
@@ -74,6 +98,8 @@ impl Label {
             is_system: true,
             message_count: 0,
             unread_count: 0,
+            color: None,
+            icon: None,
         }
     }
 
@@ -88,6 +114,43 @@ impl Label {
         self.unread_count = count;
         self
     }
+
+    /// Builder method to set the color
+    pub fn with_color(mut self, color: Option<LabelColor>) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// The icon to show: the local override, or the default for the label
+    pub fn display_icon(&self) -> &str {
+        self.icon.as_deref().unwrap_or_else(|| label_icon(self.id.as_str()))
+    }
+}
+
+/// Separator Gmail uses in nested label names ("work/clients/acme")
+pub const LABEL_SEPARATOR: char = '/';
+
+/// A user label placed in the hierarchy Gmail encodes in label names
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelNode {
+    pub id: LabelId,
+    /// Full label name ("work/clients/acme")
+    pub path: String,
+    /// Name shown under the parent ("acme")
+    pub name: String,
+    /// Label this one is nested under, None at the top level
+    pub parent_id: Option<LabelId>,
+    /// Unread threads with this label
+    pub unread_count: u32,
+    /// Unread threads with this label or any label nested under it; a
+    /// thread with several of them is counted for each
+    pub total_unread_count: u32,
+    /// Color set in Gmail, or the local override
+    pub color: Option<LabelColor>,
+    /// Icon chosen locally
+    pub icon: Option<String>,
+    /// Labels nested directly under this one, by name
+    pub children: Vec<LabelNode>,
 }
 
 /// Get the display icon for a label
@@ -31,7 +31,7 @@ impl From<&str> for MessageId {
 }
 
 /// An email address with optional display name
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EmailAddress {
     /// Display name (e.g., "John Doe")
     pub name: Option<String>,
@@ -93,6 +93,77 @@ impl EmailAddress {
     }
 }
 
+/// Unsubscribe targets from a message's List-Unsubscribe headers (RFC 2369)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Unsubscribe {
+    /// HTTP(S) unsubscribe URL
+    pub url: Option<String>,
+    /// Full `mailto:` URI, including any subject and body parameters
+    pub mailto: Option<String>,
+    /// Whether `url` accepts an RFC 8058 one-click POST
+    #[serde(default)]
+    pub one_click: bool,
+}
+
+/// Outcome of one sender authentication check (RFC 8601 result values)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthVerdict {
+    Pass,
+    Fail,
+    SoftFail,
+    Neutral,
+    None,
+    TempError,
+    PermError,
+    Policy,
+}
+
+impl AuthVerdict {
+    /// Parse a result keyword such as "pass" or "softfail"
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "pass" => Some(Self::Pass),
+            "fail" => Some(Self::Fail),
+            "softfail" => Some(Self::SoftFail),
+            "neutral" => Some(Self::Neutral),
+            "none" => Some(Self::None),
+            "temperror" => Some(Self::TempError),
+            "permerror" => Some(Self::PermError),
+            "policy" => Some(Self::Policy),
+            _ => Option::None,
+        }
+    }
+}
+
+/// SPF, DKIM and DMARC results from the receiving server's
+/// Authentication-Results header
+///
+/// A method missing from the header is None.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthResults {
+    pub spf: Option<AuthVerdict>,
+    /// Passes if any of the message's DKIM signatures verified
+    pub dkim: Option<AuthVerdict>,
+    pub dmarc: Option<AuthVerdict>,
+}
+
+impl AuthResults {
+    /// Whether the sender's domain policy rejected the message, i.e. the
+    /// From address is likely spoofed
+    pub fn failed_dmarc(&self) -> bool {
+        self.dmarc == Some(AuthVerdict::Fail)
+    }
+}
+
+/// Opens recorded for a sent message carrying a tracking beacon
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenStatus {
+    pub open_count: u32,
+    pub first_opened_at: Option<DateTime<Utc>>,
+    pub last_opened_at: Option<DateTime<Utc>>,
+}
+
 /// A single email message within a thread
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
@@ -118,12 +189,40 @@ pub struct Message {
     /// Full HTML body content
     #[serde(default)]
     pub body_html: Option<String>,
+    /// Text extracted from PDF and office attachments, for search
+    #[serde(default)]
+    pub attachment_text: Option<String>,
     /// When the message was received
     pub received_at: DateTime<Utc>,
     /// Gmail's internal timestamp (milliseconds since epoch)
     pub internal_date: i64,
     /// Gmail label IDs (e.g., "INBOX", "SENT", "UNREAD")
     pub label_ids: Vec<String>,
+    /// Whether the message has at least one file attachment
+    #[serde(default)]
+    pub has_attachments: bool,
+    /// Filenames of file attachments, in MIME order
+    #[serde(default)]
+    pub attachment_names: Vec<String>,
+    /// Estimated size of the raw message in bytes (Gmail's sizeEstimate)
+    #[serde(default)]
+    pub size_bytes: i64,
+    /// Unsubscribe targets, for mailing list and marketing mail
+    #[serde(default)]
+    pub unsubscribe: Option<Unsubscribe>,
+    /// Sender authentication results, if the server recorded any
+    #[serde(default)]
+    pub auth_results: Option<AuthResults>,
+    /// Calendar invite from a text/calendar part
+    #[serde(default)]
+    pub invite: Option<EventInvite>,
+    /// Detected body language (ISO 639-3 code, e.g. "eng")
+    #[serde(default)]
+    pub lang: Option<String>,
+    /// RFC 2822 Message-ID header, without angle brackets. Shared by every
+    /// delivery of the same message, e.g. a mailing list copy and a direct CC
+    #[serde(default)]
+    pub rfc_message_id: Option<String>,
 }
 
 impl Message {
@@ -145,9 +244,18 @@ pub struct MessageBuilder {
     body_preview: String,
     body_text: Option<String>,
     body_html: Option<String>,
+    attachment_text: Option<String>,
     received_at: Option<DateTime<Utc>>,
     internal_date: i64,
     label_ids: Vec<String>,
+    has_attachments: bool,
+    attachment_names: Vec<String>,
+    size_bytes: i64,
+    unsubscribe: Option<Unsubscribe>,
+    auth_results: Option<AuthResults>,
+    invite: Option<EventInvite>,
+    lang: Option<String>,
+    rfc_message_id: Option<String>,
 }
 
 impl MessageBuilder {
@@ -163,9 +271,18 @@ impl MessageBuilder {
             body_preview: String::new(),
             body_text: None,
             body_html: None,
+            attachment_text: None,
             received_at: None,
             internal_date: 0,
             label_ids: Vec::new(),
+            has_attachments: false,
+            attachment_names: Vec::new(),
+            size_bytes: 0,
+            unsubscribe: None,
+            auth_results: None,
+            invite: None,
+            lang: None,
+            rfc_message_id: None,
         }
     }
 
@@ -209,6 +326,11 @@ impl MessageBuilder {
         self
     }
 
+    pub fn attachment_text(mut self, attachment_text: Option<String>) -> Self {
+        self.attachment_text = attachment_text;
+        self
+    }
+
     pub fn received_at(mut self, received_at: DateTime<Utc>) -> Self {
         self.received_at = Some(received_at);
         self
@@ -224,6 +346,46 @@ impl MessageBuilder {
         self
     }
 
+    pub fn has_attachments(mut self, has_attachments: bool) -> Self {
+        self.has_attachments = has_attachments;
+        self
+    }
+
+    pub fn attachment_names(mut self, attachment_names: Vec<String>) -> Self {
+        self.attachment_names = attachment_names;
+        self
+    }
+
+    pub fn size_bytes(mut self, size_bytes: i64) -> Self {
+        self.size_bytes = size_bytes;
+        self
+    }
+
+    pub fn unsubscribe(mut self, unsubscribe: Option<Unsubscribe>) -> Self {
+        self.unsubscribe = unsubscribe;
+        self
+    }
+
+    pub fn auth_results(mut self, auth_results: Option<AuthResults>) -> Self {
+        self.auth_results = auth_results;
+        self
+    }
+
+    pub fn invite(mut self, invite: Option<EventInvite>) -> Self {
+        self.invite = invite;
+        self
+    }
+
+    pub fn lang(mut self, lang: Option<String>) -> Self {
+        self.lang = lang;
+        self
+    }
+
+    pub fn rfc_message_id(mut self, rfc_message_id: Option<String>) -> Self {
+        self.rfc_message_id = rfc_message_id;
+        self
+    }
+
     pub fn build(self) -> Message {
         Message {
             id: self.id,
@@ -238,9 +400,18 @@ impl MessageBuilder {
             body_preview: self.body_preview,
             body_text: self.body_text,
             body_html: self.body_html,
+            attachment_text: self.attachment_text,
             received_at: self.received_at.unwrap_or_else(Utc::now),
             internal_date: self.internal_date,
             label_ids: self.label_ids,
+            has_attachments: self.has_attachments,
+            attachment_names: self.attachment_names,
+            size_bytes: self.size_bytes,
+            unsubscribe: self.unsubscribe,
+            auth_results: self.auth_results,
+            invite: self.invite,
+            lang: self.lang,
+            rfc_message_id: self.rfc_message_id,
         }
     }
 }
@@ -1,13 +1,31 @@
 //! Domain models for mail entities
 
 mod account;
+mod category;
+mod followup;
+mod invite;
 mod label;
 mod message;
+mod outbox;
+mod queued_action;
+mod rule;
+mod saved_search;
+mod send_as;
 mod sync_state;
 mod thread;
 
-pub use account::Account;
-pub use label::{label_icon, label_sort_order, Label, LabelId};
-pub use message::{EmailAddress, Message, MessageId};
+pub use account::{Account, AccountSettings, Signature};
+pub use category::Category;
+pub use followup::FollowupState;
+pub use invite::{Attendee, EventInvite, EventTime, InviteMethod, RsvpResponse, RsvpStatus};
+pub use label::{label_icon, label_sort_order, Label, LabelColor, LabelId, LabelNode, LABEL_SEPARATOR};
+pub use message::{
+    AuthResults, AuthVerdict, EmailAddress, Message, MessageId, OpenStatus, Unsubscribe,
+};
+pub use outbox::{OutboxMessage, OutboxState};
+pub use queued_action::QueuedAction;
+pub use rule::{Rule, RuleAction, RulePredicate};
+pub use saved_search::SavedSearch;
+pub use send_as::SendAsAlias;
 pub use sync_state::SyncState;
-pub use thread::{Thread, ThreadId};
+pub use thread::{Thread, ThreadId, ThreadNote, MAX_THREAD_PARTICIPANTS};
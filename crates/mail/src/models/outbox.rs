@@ -0,0 +1,111 @@
+//! Outgoing mail waiting to be sent, or that failed to send
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::ThreadId;
+
+/// Where an outbox message is in its delivery
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxState {
+    /// Waiting for its next attempt
+    Queued,
+    /// Being handed to Gmail right now
+    Sending,
+    /// Gave up; needs the user to resend or discard it
+    Failed,
+    /// Accepted by Gmail; kept until the sent copy syncs
+    Sent,
+}
+
+impl OutboxState {
+    /// The value stored for this state
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutboxState::Queued => "queued",
+            OutboxState::Sending => "sending",
+            OutboxState::Failed => "failed",
+            OutboxState::Sent => "sent",
+        }
+    }
+
+    /// Parse a stored state, treating anything unknown as failed
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "queued" => OutboxState::Queued,
+            "sending" => OutboxState::Sending,
+            "sent" => OutboxState::Sent,
+            _ => OutboxState::Failed,
+        }
+    }
+}
+
+/// A composed message handed to the outbox
+///
+/// The message is kept in its final RFC 2822 form, so a retry sends exactly
+/// what the user saw when they pressed Send.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxMessage {
+    /// Unique integer identifier (database primary key, 0 if unsaved)
+    pub id: i64,
+    /// Account sending the message
+    pub account_id: i64,
+    /// Thread the message replies in, if any
+    pub thread_id: Option<ThreadId>,
+    /// Gmail draft the message was composed in, deleted once it's sent
+    pub draft_id: Option<String>,
+    /// Subject, for display
+    pub subject: String,
+    /// To, CC and BCC addresses, for display
+    pub recipients: Vec<String>,
+    /// The message as sent (RFC 2822)
+    pub raw: String,
+    /// Open tracking token embedded in the message, if any
+    pub tracking_token: Option<String>,
+    pub state: OutboxState,
+    /// Send attempts made so far
+    pub attempts: u32,
+    /// Why the last attempt failed
+    pub last_error: Option<String>,
+    /// When a queued message is next tried, or a sending one was started
+    pub next_attempt_at: DateTime<Utc>,
+    /// Gmail's ID for the sent message
+    pub sent_message_id: Option<String>,
+    /// When the user sent the message
+    pub created_at: DateTime<Utc>,
+}
+
+impl OutboxMessage {
+    /// Create a new unsaved message, due straight away
+    pub fn new(
+        account_id: i64,
+        thread_id: Option<ThreadId>,
+        subject: impl Into<String>,
+        recipients: Vec<String>,
+        raw: String,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: 0,
+            account_id,
+            thread_id,
+            draft_id: None,
+            subject: subject.into(),
+            recipients,
+            raw,
+            tracking_token: None,
+            state: OutboxState::Queued,
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: now,
+            sent_message_id: None,
+            created_at: now,
+        }
+    }
+
+    /// Whether the message is queued and its next attempt is due at `now`
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.state == OutboxState::Queued && self.next_attempt_at <= now
+    }
+}
@@ -0,0 +1,45 @@
+//! Label changes made offline, waiting to be sent to Gmail
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::ThreadId;
+
+/// A thread label change applied locally while Gmail was unreachable
+///
+/// Archive, star, read and trash are all label changes, so one shape covers
+/// every queued action. Actions are replayed oldest first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedAction {
+    /// Unique integer identifier (database primary key, 0 if unsaved)
+    pub id: i64,
+    /// Account the thread belongs to
+    pub account_id: i64,
+    /// Thread whose messages are changed
+    pub thread_id: ThreadId,
+    /// Label IDs to add
+    pub add_labels: Vec<String>,
+    /// Label IDs to remove
+    pub remove_labels: Vec<String>,
+    /// When the change was made
+    pub queued_at: DateTime<Utc>,
+}
+
+impl QueuedAction {
+    /// Create a new unsaved action (id will be assigned by storage)
+    pub fn new(
+        account_id: i64,
+        thread_id: ThreadId,
+        add_labels: &[&str],
+        remove_labels: &[&str],
+    ) -> Self {
+        Self {
+            id: 0,
+            account_id,
+            thread_id,
+            add_labels: add_labels.iter().map(|l| l.to_string()).collect(),
+            remove_labels: remove_labels.iter().map(|l| l.to_string()).collect(),
+            queued_at: Utc::now(),
+        }
+    }
+}
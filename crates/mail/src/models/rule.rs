@@ -0,0 +1,172 @@
+//! Mail rule model: predicates on incoming messages and the actions they trigger
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::Message;
+
+/// A condition on an incoming message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum RulePredicate {
+    /// Sender address equals the value (case-insensitive)
+    Sender(String),
+    /// Sender address is at the domain or one of its subdomains
+    Domain(String),
+    /// Message carries the label ID
+    Label(String),
+    /// Subject or body contains the keyword (case-insensitive)
+    Keyword(String),
+    /// A To or Cc address equals the value (case-insensitive)
+    Recipient(String),
+    /// Subject contains the text (case-insensitive)
+    Subject(String),
+    /// Message has at least one file attachment
+    HasAttachment,
+}
+
+impl RulePredicate {
+    /// Whether the message satisfies this predicate
+    pub fn matches(&self, message: &Message) -> bool {
+        match self {
+            RulePredicate::Sender(email) => message.from.email.eq_ignore_ascii_case(email.trim()),
+            RulePredicate::Domain(domain) => {
+                let domain = domain.trim().trim_start_matches('@').to_lowercase();
+                let Some((_, sender_domain)) = message.from.email.rsplit_once('@') else {
+                    return false;
+                };
+                let sender_domain = sender_domain.to_lowercase();
+                !domain.is_empty()
+                    && (sender_domain == domain || sender_domain.ends_with(&format!(".{}", domain)))
+            }
+            RulePredicate::Label(label) => message.label_ids.iter().any(|l| l == label),
+            RulePredicate::Keyword(keyword) => {
+                let keyword = keyword.trim().to_lowercase();
+                if keyword.is_empty() {
+                    return false;
+                }
+                [
+                    Some(message.subject.as_str()),
+                    Some(message.body_preview.as_str()),
+                    message.body_text.as_deref(),
+                ]
+                .into_iter()
+                .flatten()
+                .any(|text| text.to_lowercase().contains(&keyword))
+            }
+            RulePredicate::Recipient(email) => message
+                .to
+                .iter()
+                .chain(&message.cc)
+                .any(|address| address.email.eq_ignore_ascii_case(email.trim())),
+            RulePredicate::Subject(text) => {
+                let text = text.trim().to_lowercase();
+                !text.is_empty() && message.subject.to_lowercase().contains(&text)
+            }
+            RulePredicate::HasAttachment => message.has_attachments,
+        }
+    }
+}
+
+/// What to do with a message that matches a rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum RuleAction {
+    /// Raise a notification for the message
+    Notify,
+    /// Remove the message from the inbox
+    Archive,
+    /// Add the label ID to the message
+    AddLabel(String),
+    /// Mark the message as read
+    MarkRead,
+}
+
+/// A rule evaluated against each newly synced message
+///
+/// A rule matches when all of its predicates do; a rule without predicates
+/// never matches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    /// Unique integer identifier (database primary key, 0 if unsaved)
+    pub id: i64,
+    /// Account the rule applies to, or None for all accounts
+    pub account_id: Option<i64>,
+    /// Display name
+    pub name: String,
+    /// Conditions that must all hold
+    pub predicates: Vec<RulePredicate>,
+    /// Actions applied to matching messages, in order
+    pub actions: Vec<RuleAction>,
+    /// Whether the rule is evaluated during sync
+    pub enabled: bool,
+    /// When the rule was created
+    pub created_at: DateTime<Utc>,
+    /// ID of the Gmail filter the rule was imported from
+    #[serde(default)]
+    pub gmail_filter_id: Option<String>,
+}
+
+impl Rule {
+    /// Create a new unsaved, enabled rule for all accounts
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            id: 0,
+            account_id: None,
+            name: name.into(),
+            predicates: Vec::new(),
+            actions: Vec::new(),
+            enabled: true,
+            created_at: Utc::now(),
+            gmail_filter_id: None,
+        }
+    }
+
+    /// A VIP rule: notify for every message from the sender
+    pub fn vip(email: impl Into<String>) -> Self {
+        let email = email.into();
+        Self::new(format!("VIP: {}", email))
+            .with_predicate(RulePredicate::Sender(email))
+            .with_action(RuleAction::Notify)
+    }
+
+    /// Restrict the rule to one account
+    pub fn for_account(mut self, account_id: i64) -> Self {
+        self.account_id = Some(account_id);
+        self
+    }
+
+    /// Add a condition
+    pub fn with_predicate(mut self, predicate: RulePredicate) -> Self {
+        self.predicates.push(predicate);
+        self
+    }
+
+    /// Add an action
+    pub fn with_action(mut self, action: RuleAction) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    /// Mark the rule as imported from a Gmail filter
+    pub fn with_gmail_filter(mut self, filter_id: impl Into<String>) -> Self {
+        self.gmail_filter_id = Some(filter_id.into());
+        self
+    }
+
+    /// Set whether the rule is evaluated
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Whether the rule applies to the message
+    ///
+    /// Checks the account scope and enabled flag as well as the predicates.
+    pub fn matches(&self, message: &Message) -> bool {
+        self.enabled
+            && self.account_id.is_none_or(|id| id == message.account_id)
+            && !self.predicates.is_empty()
+            && self.predicates.iter().all(|p| p.matches(message))
+    }
+}
@@ -0,0 +1,38 @@
+//! Saved search model for named queries and smart folders
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A named search query that can be pinned to the sidebar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSearch {
+    /// Unique integer identifier (database primary key, 0 if unsaved)
+    pub id: i64,
+    /// Display name (unique)
+    pub name: String,
+    /// Search query string (Gmail-style operators)
+    pub query: String,
+    /// Whether the search is shown as a smart folder in the sidebar
+    pub pinned: bool,
+    /// When the search was created
+    pub created_at: DateTime<Utc>,
+}
+
+impl SavedSearch {
+    /// Create a new unsaved search (id will be assigned by storage)
+    pub fn new(name: impl Into<String>, query: impl Into<String>) -> Self {
+        Self {
+            id: 0,
+            name: name.into(),
+            query: query.into(),
+            pinned: false,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Set whether the search is pinned to the sidebar
+    pub fn with_pinned(mut self, pinned: bool) -> Self {
+        self.pinned = pinned;
+        self
+    }
+}
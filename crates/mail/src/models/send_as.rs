@@ -0,0 +1,58 @@
+//! Send-as alias model for alternative From addresses
+
+use serde::{Deserialize, Serialize};
+
+use super::EmailAddress;
+
+/// An address an account can send mail as (Gmail "send mail as" setting)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SendAsAlias {
+    /// Account the alias belongs to
+    pub account_id: i64,
+    /// Email address in the From header
+    pub email: String,
+    /// Name in the From header
+    pub display_name: Option<String>,
+    /// Whether this is the account's own address
+    pub is_primary: bool,
+    /// Whether this is the default From address
+    pub is_default: bool,
+    /// Whether Gmail has verified the account owns the address
+    pub verified: bool,
+}
+
+impl SendAsAlias {
+    /// Create a verified, non-default alias
+    pub fn new(account_id: i64, email: impl Into<String>) -> Self {
+        Self {
+            account_id,
+            email: email.into(),
+            display_name: None,
+            is_primary: false,
+            is_default: false,
+            verified: true,
+        }
+    }
+
+    /// The alias as a From address
+    pub fn address(&self) -> EmailAddress {
+        match &self.display_name {
+            Some(name) => EmailAddress::with_name(name, &self.email),
+            None => EmailAddress::new(&self.email),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address() {
+        let mut alias = SendAsAlias::new(1, "team@example.com");
+        assert_eq!(alias.address().display(), "team@example.com");
+
+        alias.display_name = Some("Team".to_string());
+        assert_eq!(alias.address().display(), "Team <team@example.com>");
+    }
+}
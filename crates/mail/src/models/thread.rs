@@ -3,6 +3,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use super::EmailAddress;
+
+/// Most distinct senders kept on a thread for its list row
+pub const MAX_THREAD_PARTICIPANTS: usize = 4;
+
 /// Unique identifier for a thread (Gmail thread ID)
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ThreadId(pub String);
@@ -30,7 +35,7 @@ impl From<&str> for ThreadId {
 }
 
 /// A thread represents a conversation containing one or more messages
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Thread {
     /// Gmail thread ID
     pub id: ThreadId,
@@ -53,6 +58,10 @@ pub struct Thread {
     /// Whether the thread has unread messages
     #[serde(default)]
     pub is_unread: bool,
+    /// Distinct senders in the order they first wrote, up to
+    /// [`MAX_THREAD_PARTICIPANTS`]
+    #[serde(default)]
+    pub participants: Vec<EmailAddress>,
 }
 
 impl Thread {
@@ -78,6 +87,37 @@ impl Thread {
             sender_name,
             sender_email,
             is_unread,
+            participants: Vec::new(),
+        }
+    }
+
+    /// Record a message's sender as a participant
+    ///
+    /// Senders are told apart by address, ignoring case. A sender who wrote
+    /// before everyone else (`first`) moves to the front; otherwise a new
+    /// sender is added at the end while there is room.
+    pub fn add_participant(&mut self, sender: &EmailAddress, first: bool) {
+        let existing = self
+            .participants
+            .iter()
+            .position(|p| p.email.eq_ignore_ascii_case(&sender.email));
+        if first {
+            if let Some(index) = existing {
+                self.participants.remove(index);
+            }
+            self.participants.insert(0, sender.clone());
+            self.participants.truncate(MAX_THREAD_PARTICIPANTS);
+        } else if existing.is_none() && self.participants.len() < MAX_THREAD_PARTICIPANTS {
+            self.participants.push(sender.clone());
         }
     }
 }
+
+/// A private note the user attached to a thread (Markdown, never synced)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadNote {
+    pub thread_id: ThreadId,
+    pub markdown: String,
+    /// When the note was last edited
+    pub updated_at: DateTime<Utc>,
+}
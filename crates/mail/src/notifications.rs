@@ -0,0 +1,214 @@
+//! Desktop notifications for new mail
+//!
+//! Mail rules with a Notify action (VIP senders) raise a native notification
+//! when a matching message arrives during sync, whether Orion or the
+//! `cosmosd` daemon ran it. Notifications go through the
+//! platform's notifier command so no native bindings are needed:
+//! `notify-send` on Linux, and on macOS `alerter` if it is installed, else
+//! `osascript`. Clicking a notification opens its thread, except with
+//! `osascript`, which can't report clicks.
+//!
+//! Settings are loaded from `orion.notifications.json` in the Cosmos config
+//! directory, e.g. `{"muted_accounts": ["work@example.com"],
+//! "quiet_hours": {"start": "22:00", "end": "07:00"}}`.
+
+use chrono::{Local, NaiveTime};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::io;
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use std::process::Command;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+use crate::models::ThreadId;
+use crate::rules::RuleMatch;
+use crate::storage::MailStore;
+
+/// Config file holding notification settings
+pub const NOTIFICATIONS_CONFIG_FILE: &str = "orion.notifications.json";
+
+/// Messages notified one by one per sync batch; the rest share a summary
+const MAX_INDIVIDUAL_NOTIFICATIONS: usize = 3;
+
+/// Notification settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    /// Addresses of accounts that never raise notifications
+    pub muted_accounts: Vec<String>,
+    /// Do-not-disturb period, in local time
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            muted_accounts: Vec::new(),
+            quiet_hours: None,
+        }
+    }
+}
+
+/// Daily do-not-disturb period as "HH:MM" times; may wrap past midnight
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+impl QuietHours {
+    /// Whether `time` falls inside the period
+    ///
+    /// An unparseable time disables quiet hours rather than silencing
+    /// everything.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        let parse = |s: &str| NaiveTime::parse_from_str(s.trim(), "%H:%M").ok();
+        let (Some(start), Some(end)) = (parse(&self.start), parse(&self.end)) else {
+            return false;
+        };
+        if start <= end {
+            start <= time && time < end
+        } else {
+            time >= start || time < end
+        }
+    }
+}
+
+impl NotificationConfig {
+    /// Load settings from the config directory, falling back to defaults
+    pub fn load() -> Self {
+        if !config::config_exists(NOTIFICATIONS_CONFIG_FILE) {
+            return Self::default();
+        }
+        match config::load_json(NOTIFICATIONS_CONFIG_FILE) {
+            Ok(config) => config,
+            Err(e) => {
+                warn!("Ignoring invalid notification config: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Whether mail for `account_email` may raise a notification at `now`
+    pub fn allows(&self, account_email: &str, now: NaiveTime) -> bool {
+        let muted = self
+            .muted_accounts
+            .iter()
+            .any(|email| email.eq_ignore_ascii_case(account_email));
+        let quiet = self.quiet_hours.as_ref().is_some_and(|q| q.contains(now));
+        self.enabled && !muted && !quiet
+    }
+}
+
+/// Raises new mail notifications and reports which ones were clicked
+pub struct Notifier {
+    config: NotificationConfig,
+    clicks: UnboundedSender<ThreadId>,
+}
+
+impl Notifier {
+    /// Create a notifier and the receiver of clicked notifications' threads
+    pub fn new(config: NotificationConfig) -> (Self, UnboundedReceiver<ThreadId>) {
+        let (clicks, clicked) = unbounded_channel();
+        (Self { config, clicks }, clicked)
+    }
+
+    /// Notify for new messages whose rules asked for it
+    ///
+    /// Reads the store, so call it off the main thread.
+    pub fn notify_matches(&self, store: &dyn MailStore, account_id: i64, matches: &[RuleMatch]) {
+        let matches: Vec<&RuleMatch> = matches.iter().filter(|m| m.notify).collect();
+        if matches.is_empty() {
+            return;
+        }
+        let account_email = match store.get_account(account_id) {
+            Ok(Some(account)) => account.email,
+            _ => return,
+        };
+        if !self.config.allows(&account_email, Local::now().time()) {
+            debug!(
+                "Notifications suppressed for {} ({} messages)",
+                account_email,
+                matches.len()
+            );
+            return;
+        }
+
+        for rule_match in matches.iter().take(MAX_INDIVIDUAL_NOTIFICATIONS) {
+            let Ok(Some(message)) = store.get_message(&rule_match.message_id) else {
+                continue;
+            };
+            let sender = message.from.name.clone().unwrap_or(message.from.email);
+            self.show(sender, message.subject, Some(rule_match.thread_id.clone()));
+        }
+        let rest = matches.len().saturating_sub(MAX_INDIVIDUAL_NOTIFICATIONS);
+        if rest > 0 {
+            self.show(format!("{} more new messages", rest), account_email, None);
+        }
+    }
+
+    /// Show a notification without blocking; a click opens `thread_id`
+    fn show(&self, title: String, body: String, thread_id: Option<ThreadId>) {
+        let clicks = self.clicks.clone();
+        // Clickable notifiers block until the notification closes
+        std::thread::spawn(move || match show_native(&title, &body) {
+            Ok(true) => {
+                if let Some(thread_id) = thread_id {
+                    clicks.send(thread_id).ok();
+                }
+            }
+            Ok(false) => {}
+            Err(e) => warn!("Failed to show notification: {}", e),
+        });
+    }
+}
+
+/// Show a notification and wait for it to close; true if it was clicked
+#[cfg(target_os = "linux")]
+fn show_native(title: &str, body: &str) -> io::Result<bool> {
+    let output = Command::new("notify-send")
+        .args(["--app-name=Orion", "--action=default=Open", "--wait", "--"])
+        .args([title, body])
+        .output()?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim() == "default")
+}
+
+/// Show a notification and wait for it to close; true if it was clicked
+#[cfg(target_os = "macos")]
+fn show_native(title: &str, body: &str) -> io::Result<bool> {
+    let alerter = Command::new("alerter")
+        .args(["-title", title, "-message", body])
+        .args(["-sender", "com.cosmos.orion", "-timeout", "30"])
+        .output();
+    match alerter {
+        Ok(output) => Ok(String::from_utf8_lossy(&output.stdout).trim() == "@CONTENTCLICKED"),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let script = format!(
+                "display notification {} with title {}",
+                applescript_string(body),
+                applescript_string(title)
+            );
+            Command::new("osascript").args(["-e", &script]).status()?;
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Show a notification and wait for it to close; true if it was clicked
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn show_native(title: &str, _body: &str) -> io::Result<bool> {
+    debug!(
+        "No notifier on this platform, dropping notification: {}",
+        title
+    );
+    Ok(false)
+}
+
+/// Quote a string as an AppleScript literal
+#[cfg(target_os = "macos")]
+fn applescript_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,173 @@
+//! Inbox category tabs
+//!
+//! Lists the inbox split by [`Category`]. Primary holds inbox threads in no
+//! other category, so threads without any category label still show up.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::threads::ThreadPage;
+use crate::models::{Category, LabelId};
+use crate::storage::{MailStore, ThreadCursor};
+
+/// Unread thread count for one inbox tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryCount {
+    /// The tab
+    pub category: Category,
+    /// Unread threads in the tab
+    pub unread: usize,
+}
+
+/// Labels a thread needs, and must not have, to appear in a category's tab
+fn category_labels(category: Category) -> (Vec<&'static str>, Vec<&'static str>) {
+    match category {
+        Category::Primary => (
+            vec![LabelId::INBOX],
+            Category::ALL
+                .into_iter()
+                .filter(|c| *c != Category::Primary)
+                .map(Category::label_id)
+                .collect(),
+        ),
+        other => (vec![other.label_id(), LabelId::INBOX], Vec::new()),
+    }
+}
+
+/// List inbox threads in a category with keyset pagination
+///
+/// # Arguments
+/// * `store` - The storage backend
+/// * `category` - The tab to list
+/// * `account_id` - Optional account filter (None = all accounts)
+/// * `cursor` - Cursor from the previous page, or None for the first page
+/// * `limit` - Maximum number of threads to return
+pub fn list_threads_by_category(
+    store: &dyn MailStore,
+    category: Category,
+    account_id: Option<i64>,
+    cursor: Option<&ThreadCursor>,
+    limit: usize,
+) -> Result<ThreadPage> {
+    let (labels, excluded) = category_labels(category);
+    let threads =
+        store.list_threads_with_labels_after(&labels, &excluded, account_id, cursor, limit + 1)?;
+    ThreadPage::from_threads(store, threads, limit)
+}
+
+/// Unread thread counts for every inbox tab, in tab order
+pub fn count_unread_by_category(
+    store: &dyn MailStore,
+    account_id: Option<i64>,
+) -> Result<Vec<CategoryCount>> {
+    Category::ALL
+        .into_iter()
+        .map(|category| {
+            let (labels, excluded) = category_labels(category);
+            Ok(CategoryCount {
+                category,
+                unread: store.count_unread_threads_with_labels(&labels, &excluded, account_id)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EmailAddress, Message, MessageId, Thread, ThreadId};
+    use crate::storage::InMemoryMailStore;
+    use chrono::{Duration, Utc};
+
+    fn add_thread(store: &InMemoryMailStore, id: &str, labels: &[&str], unread: bool, age: i64) {
+        let at = Utc::now() - Duration::minutes(age);
+        store
+            .upsert_thread(Thread::new(
+                ThreadId::new(id),
+                1,
+                id.to_string(),
+                String::new(),
+                at,
+                1,
+                None,
+                "a@example.com".to_string(),
+                unread,
+            ))
+            .unwrap();
+        store
+            .upsert_message(
+                Message::builder(MessageId::new(format!("m_{}", id)), ThreadId::new(id))
+                    .from(EmailAddress::new("a@example.com"))
+                    .received_at(at)
+                    .label_ids(labels.iter().map(|l| l.to_string()).collect())
+                    .build(),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_category_tabs() {
+        let store = InMemoryMailStore::new();
+        add_thread(&store, "personal", &["INBOX", "CATEGORY_PERSONAL"], true, 1);
+        add_thread(&store, "uncategorized", &["INBOX"], false, 2);
+        add_thread(
+            &store,
+            "promo",
+            &["INBOX", "CATEGORY_PROMOTIONS", "UNREAD"],
+            true,
+            3,
+        );
+        add_thread(&store, "archived_promo", &["CATEGORY_PROMOTIONS"], true, 4);
+
+        let ids = |category| -> Vec<String> {
+            list_threads_by_category(&store, category, None, None, 10)
+                .unwrap()
+                .threads
+                .into_iter()
+                .map(|t| t.id.as_str().to_string())
+                .collect()
+        };
+        assert_eq!(ids(Category::Primary), vec!["personal", "uncategorized"]);
+        assert_eq!(ids(Category::Promotions), vec!["promo"]);
+        assert!(ids(Category::Social).is_empty());
+
+        let counts = count_unread_by_category(&store, None).unwrap();
+        assert_eq!(counts.len(), Category::ALL.len());
+        assert_eq!(counts[0].unread, 1);
+        assert_eq!(
+            counts
+                .iter()
+                .find(|c| c.category == Category::Promotions)
+                .unwrap()
+                .unread,
+            1
+        );
+    }
+
+    #[test]
+    fn test_category_pagination() {
+        let store = InMemoryMailStore::new();
+        for i in 0..3 {
+            add_thread(
+                &store,
+                &format!("t{}", i),
+                &["INBOX", "CATEGORY_UPDATES"],
+                false,
+                i,
+            );
+        }
+
+        let first = list_threads_by_category(&store, Category::Updates, None, None, 2).unwrap();
+        assert_eq!(first.threads.len(), 2);
+        let second = list_threads_by_category(
+            &store,
+            Category::Updates,
+            None,
+            first.next_cursor.as_ref(),
+            2,
+        )
+        .unwrap();
+        assert_eq!(second.threads.len(), 1);
+        assert!(!second.has_more);
+    }
+}
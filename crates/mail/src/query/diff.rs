@@ -0,0 +1,205 @@
+//! Thread list diffing
+//!
+//! Computes the row-level changes between two snapshots of a thread list so
+//! a UI can animate inserts, moves, and removals instead of re-rendering the
+//! whole list after a sync touches a handful of threads.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::threads::ThreadSummary;
+use crate::models::ThreadId;
+
+/// A thread that changed position between snapshots
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadMove {
+    /// The moved thread
+    pub thread_id: ThreadId,
+    /// Index in the previous list
+    pub from: usize,
+    /// Index in the new list
+    pub to: usize,
+}
+
+/// Row changes that turn a previous thread list into a new one
+///
+/// Removal indices refer to the previous list; insert, move target, and
+/// update indices refer to the new list.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadListDiff {
+    /// Threads only in the new list, with their new index
+    pub inserts: Vec<(usize, ThreadId)>,
+    /// Threads only in the previous list, with their previous index
+    pub removals: Vec<(usize, ThreadId)>,
+    /// Threads whose order relative to the others changed
+    pub moves: Vec<ThreadMove>,
+    /// Threads present in both lists whose summary changed, with their new index
+    pub updates: Vec<(usize, ThreadId)>,
+}
+
+impl ThreadListDiff {
+    /// Check if the lists are identical
+    pub fn is_empty(&self) -> bool {
+        self.inserts.is_empty()
+            && self.removals.is_empty()
+            && self.moves.is_empty()
+            && self.updates.is_empty()
+    }
+
+    /// Check if only row contents changed (no rows added, removed, or reordered)
+    pub fn is_content_only(&self) -> bool {
+        self.inserts.is_empty() && self.removals.is_empty() && self.moves.is_empty()
+    }
+}
+
+/// Diff two thread list snapshots keyed by ThreadId
+///
+/// Moves are kept minimal: threads on the longest run that kept its relative
+/// order are treated as stationary, so one thread jumping to the top is a
+/// single move rather than every row below it shifting.
+///
+/// # Arguments
+/// * `prev` - The list currently displayed
+/// * `new` - The freshly loaded list
+pub fn diff_thread_lists(prev: &[ThreadSummary], new: &[ThreadSummary]) -> ThreadListDiff {
+    let prev_index: HashMap<&ThreadId, usize> =
+        prev.iter().enumerate().map(|(i, t)| (&t.id, i)).collect();
+    let new_index: HashMap<&ThreadId, usize> =
+        new.iter().enumerate().map(|(i, t)| (&t.id, i)).collect();
+
+    let mut diff = ThreadListDiff::default();
+
+    for (i, thread) in prev.iter().enumerate() {
+        if !new_index.contains_key(&thread.id) {
+            diff.removals.push((i, thread.id.clone()));
+        }
+    }
+
+    // Threads in both lists, in new order, paired with their previous index
+    let mut common: Vec<(usize, usize)> = Vec::new();
+    for (i, thread) in new.iter().enumerate() {
+        match prev_index.get(&thread.id) {
+            Some(&from) => {
+                common.push((from, i));
+                if prev[from] != *thread {
+                    diff.updates.push((i, thread.id.clone()));
+                }
+            }
+            None => diff.inserts.push((i, thread.id.clone())),
+        }
+    }
+
+    let stationary = longest_increasing_run(&common);
+    for (k, &(from, to)) in common.iter().enumerate() {
+        if !stationary[k] {
+            diff.moves.push(ThreadMove {
+                thread_id: new[to].id.clone(),
+                from,
+                to,
+            });
+        }
+    }
+
+    diff
+}
+
+/// Mark the entries on a longest increasing subsequence of previous indices
+fn longest_increasing_run(pairs: &[(usize, usize)]) -> Vec<bool> {
+    // Patience sorting: tails[len] holds the index (into pairs) of the smallest
+    // tail of an increasing subsequence of length len + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut parent: Vec<Option<usize>> = vec![None; pairs.len()];
+
+    for (k, &(from, _)) in pairs.iter().enumerate() {
+        let pos = tails.partition_point(|&t| pairs[t].0 < from);
+        parent[k] = pos.checked_sub(1).map(|p| tails[p]);
+        if pos == tails.len() {
+            tails.push(k);
+        } else {
+            tails[pos] = k;
+        }
+    }
+
+    let mut on_run = vec![false; pairs.len()];
+    let mut cursor = tails.last().copied();
+    while let Some(k) = cursor {
+        on_run[k] = true;
+        cursor = parent[k];
+    }
+    on_run
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn summary(id: &str, minute: u32) -> ThreadSummary {
+        ThreadSummary {
+            id: ThreadId::new(id),
+            account_id: 1,
+            subject: format!("Subject {}", id),
+            snippet: String::new(),
+            last_message_at: Utc.with_ymd_and_hms(2025, 1, 1, 0, minute, 0).unwrap(),
+            message_count: 1,
+            sender_name: None,
+            sender_email: "a@example.com".to_string(),
+            is_unread: false,
+            participants: Vec::new(),
+            message_id: None,
+            labels: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_identical_lists() {
+        let list = vec![summary("a", 3), summary("b", 2)];
+        assert!(diff_thread_lists(&list, &list).is_empty());
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let prev = vec![summary("a", 3), summary("b", 2), summary("c", 1)];
+        let new = vec![summary("d", 4), summary("a", 3), summary("c", 1)];
+        let diff = diff_thread_lists(&prev, &new);
+
+        assert_eq!(diff.inserts, vec![(0, ThreadId::new("d"))]);
+        assert_eq!(diff.removals, vec![(1, ThreadId::new("b"))]);
+        assert!(diff.moves.is_empty());
+        assert!(diff.updates.is_empty());
+    }
+
+    #[test]
+    fn test_single_move_to_top() {
+        let prev = vec![summary("a", 4), summary("b", 3), summary("c", 2)];
+        let mut bumped = summary("c", 5);
+        bumped.message_count = 2;
+        let new = vec![bumped, summary("a", 4), summary("b", 3)];
+        let diff = diff_thread_lists(&prev, &new);
+
+        assert_eq!(
+            diff.moves,
+            vec![ThreadMove {
+                thread_id: ThreadId::new("c"),
+                from: 2,
+                to: 0,
+            }]
+        );
+        assert_eq!(diff.updates, vec![(0, ThreadId::new("c"))]);
+        assert!(diff.inserts.is_empty());
+        assert!(diff.removals.is_empty());
+    }
+
+    #[test]
+    fn test_content_only_update() {
+        let prev = vec![summary("a", 2), summary("b", 1)];
+        let mut read = summary("b", 1);
+        read.is_unread = true;
+        let new = vec![summary("a", 2), read];
+        let diff = diff_thread_lists(&prev, &new);
+
+        assert!(diff.is_content_only());
+        assert_eq!(diff.updates, vec![(1, ThreadId::new("b"))]);
+    }
+}
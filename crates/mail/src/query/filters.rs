@@ -0,0 +1,392 @@
+//! Quick filters for thread lists
+//!
+//! One-click triage filters (unread, starred, attachments, known contacts,
+//! message size) that compose with label/account scoping and keyset
+//! pagination.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::threads::ThreadPage;
+use crate::models::{LabelId, Thread};
+use crate::storage::{MailStore, ThreadCursor};
+
+/// Number of threads scanned per storage round-trip while filtering
+const SCAN_BATCH_SIZE: usize = 200;
+
+/// Quick filters applied on top of a label/account thread list
+///
+/// All enabled filters must match (AND semantics). The default filter
+/// matches every thread.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadFilter {
+    /// Only threads with unread messages
+    #[serde(default)]
+    pub unread: bool,
+    /// Only threads with at least one starred message
+    #[serde(default)]
+    pub starred: bool,
+    /// Only threads with at least one attachment
+    #[serde(default)]
+    pub has_attachment: bool,
+    /// Only threads whose sender the account has previously written to
+    #[serde(default)]
+    pub from_contacts: bool,
+    /// Only threads with a message larger than this many bytes, like
+    /// Gmail's `larger:` operator
+    #[serde(default)]
+    pub larger: Option<u64>,
+}
+
+impl ThreadFilter {
+    /// Check if no filters are enabled
+    pub fn is_empty(&self) -> bool {
+        !self.unread
+            && !self.starred
+            && !self.has_attachment
+            && !self.from_contacts
+            && self.larger.is_none()
+    }
+
+    /// Whether matching requires loading the thread's messages
+    fn needs_messages(&self) -> bool {
+        self.starred || self.has_attachment || self.larger.is_some()
+    }
+}
+
+/// List threads matching quick filters with keyset pagination
+///
+/// Scans the label/account list in batches and keeps threads that match
+/// every enabled filter. The returned cursor points at the last returned
+/// thread, so paging continues where the previous page stopped.
+///
+/// # Arguments
+/// * `store` - The storage backend
+/// * `label` - Optional label to scope the list (None or "ALL" = all mail)
+/// * `account_id` - Optional account filter (None = all accounts)
+/// * `filter` - Quick filters to apply
+/// * `cursor` - Cursor from the previous page, or None for the first page
+/// * `limit` - Maximum number of threads to return
+pub fn list_threads_filtered(
+    store: &dyn MailStore,
+    label: Option<&str>,
+    account_id: Option<i64>,
+    filter: &ThreadFilter,
+    cursor: Option<&ThreadCursor>,
+    limit: usize,
+) -> Result<ThreadPage> {
+    let label = label.filter(|l| *l != LabelId::ALL_MAIL);
+
+    // Fast path: no filters means a plain keyset page
+    if filter.is_empty() {
+        let threads = store.list_threads_after(label, account_id, cursor, limit + 1)?;
+        return ThreadPage::from_threads(store, threads, limit);
+    }
+
+    let contacts = if filter.from_contacts {
+        Some(known_contacts(store, account_id)?)
+    } else {
+        None
+    };
+
+    // Collect one extra match to know whether another page exists
+    let mut matched: Vec<Thread> = Vec::new();
+    let mut scan_cursor = cursor.cloned();
+
+    while matched.len() <= limit {
+        let batch =
+            store.list_threads_after(label, account_id, scan_cursor.as_ref(), SCAN_BATCH_SIZE)?;
+        let exhausted = batch.len() < SCAN_BATCH_SIZE;
+
+        for thread in batch {
+            scan_cursor = Some(ThreadCursor::for_thread(&thread));
+            if thread_matches(store, &thread, filter, contacts.as_ref())? {
+                matched.push(thread);
+                if matched.len() > limit {
+                    break;
+                }
+            }
+        }
+
+        if exhausted {
+            break;
+        }
+    }
+
+    ThreadPage::from_threads(store, matched, limit)
+}
+
+/// Check a single thread against the enabled filters
+fn thread_matches(
+    store: &dyn MailStore,
+    thread: &Thread,
+    filter: &ThreadFilter,
+    contacts: Option<&HashSet<String>>,
+) -> Result<bool> {
+    if filter.unread && !thread.is_unread {
+        return Ok(false);
+    }
+
+    if let Some(contacts) = contacts
+        && !contacts.contains(&thread.sender_email.to_lowercase())
+    {
+        return Ok(false);
+    }
+
+    if filter.needs_messages() {
+        let messages = store.list_messages_for_thread(&thread.id)?;
+
+        if filter.starred
+            && !messages
+                .iter()
+                .any(|m| m.label_ids.iter().any(|l| l == LabelId::STARRED))
+        {
+            return Ok(false);
+        }
+
+        if filter.has_attachment && !messages.iter().any(|m| m.has_attachments) {
+            return Ok(false);
+        }
+
+        if let Some(larger) = filter.larger
+            && !messages
+                .iter()
+                .any(|m| u64::try_from(m.size_bytes).is_ok_and(|size| size > larger))
+        {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Collect lowercase addresses the account has sent mail to
+///
+/// Recipients (To and Cc) of every SENT message are treated as contacts.
+fn known_contacts(store: &dyn MailStore, account_id: Option<i64>) -> Result<HashSet<String>> {
+    let mut contacts = HashSet::new();
+    let mut cursor: Option<ThreadCursor> = None;
+
+    loop {
+        let batch = store.list_threads_after(
+            Some(LabelId::SENT),
+            account_id,
+            cursor.as_ref(),
+            SCAN_BATCH_SIZE,
+        )?;
+        let exhausted = batch.len() < SCAN_BATCH_SIZE;
+
+        for thread in &batch {
+            for message in store.list_messages_for_thread(&thread.id)? {
+                if message.label_ids.iter().any(|l| l == LabelId::SENT) {
+                    contacts.extend(
+                        message
+                            .to
+                            .iter()
+                            .chain(message.cc.iter())
+                            .map(|addr| addr.email.to_lowercase()),
+                    );
+                }
+            }
+        }
+
+        cursor = batch.last().map(ThreadCursor::for_thread);
+        if exhausted || cursor.is_none() {
+            break;
+        }
+    }
+
+    Ok(contacts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EmailAddress, Message, MessageId, ThreadId};
+    use crate::storage::InMemoryMailStore;
+    use chrono::Utc;
+
+    /// Build a thread with one message carrying the given labels
+    fn add_thread(
+        store: &InMemoryMailStore,
+        id: &str,
+        age_hours: i64,
+        sender: &str,
+        labels: &[&str],
+        has_attachments: bool,
+    ) {
+        add_sized_thread(store, id, age_hours, sender, labels, has_attachments, 1024);
+    }
+
+    fn add_sized_thread(
+        store: &InMemoryMailStore,
+        id: &str,
+        age_hours: i64,
+        sender: &str,
+        labels: &[&str],
+        has_attachments: bool,
+        size_bytes: i64,
+    ) {
+        let ts = Utc::now() - chrono::Duration::hours(age_hours);
+        let thread = Thread::new(
+            ThreadId::new(id),
+            1,
+            format!("Thread {}", id),
+            "Snippet".to_string(),
+            ts,
+            1,
+            None,
+            sender.to_string(),
+            labels.contains(&LabelId::UNREAD),
+        );
+        store.upsert_thread(thread).unwrap();
+
+        let msg = Message::builder(MessageId::new(format!("m_{}", id)), ThreadId::new(id))
+            .account_id(1)
+            .from(EmailAddress::new(sender))
+            .to(vec![EmailAddress::new("carol@example.com")])
+            .received_at(ts)
+            .label_ids(labels.iter().map(|l| l.to_string()).collect())
+            .has_attachments(has_attachments)
+            .size_bytes(size_bytes)
+            .build();
+        store.upsert_message(msg).unwrap();
+    }
+
+    fn setup_store() -> InMemoryMailStore {
+        let store = InMemoryMailStore::new();
+        add_thread(
+            &store,
+            "t0",
+            0,
+            "alice@example.com",
+            &["INBOX", "UNREAD"],
+            false,
+        );
+        add_thread(
+            &store,
+            "t1",
+            1,
+            "bob@example.com",
+            &["INBOX", "STARRED"],
+            true,
+        );
+        add_thread(
+            &store,
+            "t2",
+            2,
+            "carol@example.com",
+            &["INBOX", "UNREAD", "STARRED"],
+            false,
+        );
+        add_thread(&store, "t3", 3, "dave@example.com", &["INBOX"], true);
+        // Sent mail establishes carol as a contact
+        add_thread(&store, "t4", 4, "me@example.com", &["SENT"], false);
+        store
+    }
+
+    fn ids(page: &ThreadPage) -> Vec<&str> {
+        page.threads.iter().map(|t| t.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_empty_filter_matches_all() {
+        let store = setup_store();
+        let page = list_threads_filtered(
+            &store,
+            Some("INBOX"),
+            None,
+            &ThreadFilter::default(),
+            None,
+            10,
+        )
+        .unwrap();
+        assert_eq!(ids(&page), vec!["t0", "t1", "t2", "t3"]);
+    }
+
+    #[test]
+    fn test_individual_filters() {
+        let store = setup_store();
+
+        let unread = ThreadFilter {
+            unread: true,
+            ..Default::default()
+        };
+        let page = list_threads_filtered(&store, Some("INBOX"), None, &unread, None, 10).unwrap();
+        assert_eq!(ids(&page), vec!["t0", "t2"]);
+
+        let starred = ThreadFilter {
+            starred: true,
+            ..Default::default()
+        };
+        let page = list_threads_filtered(&store, Some("INBOX"), None, &starred, None, 10).unwrap();
+        assert_eq!(ids(&page), vec!["t1", "t2"]);
+
+        let attachments = ThreadFilter {
+            has_attachment: true,
+            ..Default::default()
+        };
+        let page =
+            list_threads_filtered(&store, Some("INBOX"), None, &attachments, None, 10).unwrap();
+        assert_eq!(ids(&page), vec!["t1", "t3"]);
+
+        let contacts = ThreadFilter {
+            from_contacts: true,
+            ..Default::default()
+        };
+        let page = list_threads_filtered(&store, Some("INBOX"), None, &contacts, None, 10).unwrap();
+        assert_eq!(ids(&page), vec!["t2"]);
+    }
+
+    #[test]
+    fn test_larger_filter() {
+        let store = setup_store();
+        add_sized_thread(&store, "t5", 5, "erin@example.com", &["INBOX"], true, 8_000_000);
+
+        let larger = ThreadFilter {
+            larger: Some(5_000_000),
+            ..Default::default()
+        };
+        let page = list_threads_filtered(&store, Some("INBOX"), None, &larger, None, 10).unwrap();
+        assert_eq!(ids(&page), vec!["t5"]);
+    }
+
+    #[test]
+    fn test_filters_compose() {
+        let store = setup_store();
+        let filter = ThreadFilter {
+            unread: true,
+            starred: true,
+            ..Default::default()
+        };
+        let page = list_threads_filtered(&store, Some("INBOX"), None, &filter, None, 10).unwrap();
+        assert_eq!(ids(&page), vec!["t2"]);
+    }
+
+    #[test]
+    fn test_filtered_pagination() {
+        let store = setup_store();
+        let filter = ThreadFilter {
+            starred: true,
+            ..Default::default()
+        };
+
+        let page1 = list_threads_filtered(&store, Some("INBOX"), None, &filter, None, 1).unwrap();
+        assert_eq!(ids(&page1), vec!["t1"]);
+        assert!(page1.has_more);
+
+        let page2 = list_threads_filtered(
+            &store,
+            Some("INBOX"),
+            None,
+            &filter,
+            page1.next_cursor.as_ref(),
+            1,
+        )
+        .unwrap();
+        assert_eq!(ids(&page2), vec!["t2"]);
+        assert!(!page2.has_more);
+    }
+}
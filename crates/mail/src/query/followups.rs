@@ -0,0 +1,264 @@
+//! Follow-up reminders for unanswered sent mail
+//!
+//! A thread is a follow-up candidate when its latest message is one the user
+//! sent, at least N days ago, with no reply since. Reminders can be dismissed
+//! (until the user sends again) or snoozed; that state lives in the store.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+
+use super::threads::ThreadSummary;
+use crate::models::{EmailAddress, FollowupState, LabelId, ThreadId};
+use crate::storage::{MailStore, ThreadCursor};
+
+/// Days without a reply before a sent thread is suggested for follow-up
+pub const DEFAULT_FOLLOWUP_DAYS: u32 = 3;
+
+/// Sent messages older than this are no longer worth a reminder
+pub const FOLLOWUP_LOOKBACK_DAYS: i64 = 30;
+
+/// Threads fetched per page while scanning sent mail
+const SCAN_PAGE_SIZE: usize = 100;
+
+/// A sent thread still waiting for a reply
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FollowupCandidate {
+    pub thread: ThreadSummary,
+    /// When the unanswered message was sent
+    pub sent_at: DateTime<Utc>,
+    /// Who it was sent to (To and CC)
+    pub recipients: Vec<EmailAddress>,
+}
+
+/// List sent threads with no reply for at least `after_days` days
+///
+/// Threads whose reminder is dismissed or snoozed are left out, as are
+/// messages sent only to the sender's own address. Newest first.
+///
+/// # Arguments
+/// * `store` - The storage backend
+/// * `account_id` - Only this account's threads, or all accounts if None
+/// * `after_days` - Days to wait for a reply (see [`DEFAULT_FOLLOWUP_DAYS`])
+pub fn list_followup_candidates(
+    store: &dyn MailStore,
+    account_id: Option<i64>,
+    after_days: u32,
+) -> Result<Vec<FollowupCandidate>> {
+    followup_candidates_at(store, account_id, after_days, Utc::now())
+}
+
+fn followup_candidates_at(
+    store: &dyn MailStore,
+    account_id: Option<i64>,
+    after_days: u32,
+    now: DateTime<Utc>,
+) -> Result<Vec<FollowupCandidate>> {
+    let cutoff = now - Duration::days(after_days as i64);
+    let oldest = cutoff - Duration::days(FOLLOWUP_LOOKBACK_DAYS);
+
+    // Threads are ordered by last activity, so start just before the cutoff
+    // and stop once past the lookback window
+    let mut cursor = ThreadCursor {
+        last_message_at: cutoff,
+        thread_id: ThreadId::new(""),
+    };
+    let mut candidates = Vec::new();
+    loop {
+        let page = store.list_threads_after(
+            Some(LabelId::SENT),
+            account_id,
+            Some(&cursor),
+            SCAN_PAGE_SIZE,
+        )?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        cursor = ThreadCursor::for_thread(last);
+        let done = page.len() < SCAN_PAGE_SIZE || last.last_message_at < oldest;
+
+        for thread in page {
+            let messages = store.list_messages_for_thread(&thread.id)?;
+            let Some(latest) = messages.last() else {
+                continue;
+            };
+            let unanswered = latest.label_ids.iter().any(|l| l == LabelId::SENT)
+                && latest.received_at <= cutoff
+                && latest.received_at >= oldest;
+            if !unanswered {
+                continue;
+            }
+
+            let recipients: Vec<EmailAddress> =
+                latest.to.iter().chain(&latest.cc).cloned().collect();
+            let only_self = recipients
+                .iter()
+                .all(|r| r.email.eq_ignore_ascii_case(&latest.from.email));
+            if only_self {
+                continue;
+            }
+
+            let state = store.get_followup_state(&thread.id)?;
+            if state.is_some_and(|s| s.hides(latest.received_at, now)) {
+                continue;
+            }
+
+            candidates.push(FollowupCandidate {
+                sent_at: latest.received_at,
+                recipients,
+                thread: ThreadSummary::from(thread),
+            });
+        }
+
+        if done {
+            break;
+        }
+    }
+
+    Ok(candidates)
+}
+
+/// Dismiss a thread's follow-up reminder
+///
+/// The reminder comes back if the user sends another message to the thread.
+pub fn dismiss_followup(store: &dyn MailStore, thread_id: &ThreadId) -> Result<()> {
+    let mut state = store
+        .get_followup_state(thread_id)?
+        .unwrap_or_else(|| FollowupState::new(thread_id.clone()));
+    state.dismissed_at = Some(Utc::now());
+    store.save_followup_state(&state)
+}
+
+/// Hide a thread's follow-up reminder until `until`
+pub fn snooze_followup(
+    store: &dyn MailStore,
+    thread_id: &ThreadId,
+    until: DateTime<Utc>,
+) -> Result<()> {
+    let mut state = store
+        .get_followup_state(thread_id)?
+        .unwrap_or_else(|| FollowupState::new(thread_id.clone()));
+    state.snoozed_until = Some(until);
+    store.save_followup_state(&state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Message, MessageId, Thread};
+    use crate::storage::InMemoryMailStore;
+
+    const ME: &str = "me@example.com";
+
+    fn add_message(
+        store: &InMemoryMailStore,
+        (thread, id): (&str, &str),
+        (from, to): (&str, &str),
+        label: &str,
+        received_at: DateTime<Utc>,
+    ) {
+        store
+            .upsert_thread(Thread {
+                id: ThreadId::new(thread),
+                account_id: 1,
+                subject: format!("Thread {}", thread),
+                snippet: String::new(),
+                last_message_at: received_at,
+                message_count: 1,
+                sender_name: None,
+                sender_email: from.to_string(),
+                is_unread: false,
+                participants: Vec::new(),
+            })
+            .unwrap();
+        let message = Message::builder(MessageId::new(id), ThreadId::new(thread))
+            .account_id(1)
+            .from(EmailAddress::new(from))
+            .to(vec![EmailAddress::new(to)])
+            .received_at(received_at)
+            .label_ids(vec![label.to_string()])
+            .build();
+        store.upsert_message(message).unwrap();
+    }
+
+    fn send(store: &InMemoryMailStore, thread: &str, id: &str, to: &str, at: DateTime<Utc>) {
+        add_message(store, (thread, id), (ME, to), LabelId::SENT, at);
+    }
+
+    fn candidate_ids(store: &InMemoryMailStore, now: DateTime<Utc>) -> Vec<String> {
+        followup_candidates_at(store, Some(1), DEFAULT_FOLLOWUP_DAYS, now)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.thread.id.0)
+            .collect()
+    }
+
+    #[test]
+    fn test_followup_candidates() {
+        let store = InMemoryMailStore::new();
+        let now = Utc::now();
+        let days_ago = |d| now - Duration::days(d);
+
+        send(&store, "unanswered", "a", "ana@example.com", days_ago(5));
+        send(&store, "too-recent", "b", "bo@example.com", days_ago(1));
+        send(&store, "answered", "c1", "cy@example.com", days_ago(9));
+        let reply = ("cy@example.com", ME);
+        add_message(
+            &store,
+            ("answered", "c2"),
+            reply,
+            LabelId::INBOX,
+            days_ago(8),
+        );
+        send(&store, "note-to-self", "d", ME, days_ago(6));
+        send(&store, "too-old", "e", "ed@example.com", days_ago(60));
+        send(
+            &store,
+            "unanswered-older",
+            "f",
+            "fi@example.com",
+            days_ago(7),
+        );
+
+        assert_eq!(
+            candidate_ids(&store, now),
+            vec!["unanswered", "unanswered-older"]
+        );
+        let candidates = list_followup_candidates(&store, Some(1), 3).unwrap();
+        assert_eq!(candidates[0].sent_at, days_ago(5));
+        assert_eq!(candidates[0].recipients[0].email, "ana@example.com");
+    }
+
+    #[test]
+    fn test_dismiss_and_snooze() {
+        let store = InMemoryMailStore::new();
+        let now = Utc::now();
+        send(
+            &store,
+            "t1",
+            "a",
+            "ana@example.com",
+            now - Duration::days(5),
+        );
+        send(&store, "t2", "b", "bo@example.com", now - Duration::days(6));
+
+        dismiss_followup(&store, &ThreadId::new("t1")).unwrap();
+        snooze_followup(&store, &ThreadId::new("t2"), now + Duration::days(2)).unwrap();
+        assert!(candidate_ids(&store, now).is_empty());
+
+        // The snooze expires
+        assert_eq!(candidate_ids(&store, now + Duration::days(3)), vec!["t2"]);
+
+        // Sending again brings a dismissed reminder back
+        send(
+            &store,
+            "t1",
+            "a2",
+            "ana@example.com",
+            now + Duration::hours(1),
+        );
+        assert_eq!(
+            candidate_ids(&store, now + Duration::days(4)),
+            vec!["t1", "t2"]
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Nested label tree
+//!
+//! Gmail has no real label hierarchy; nesting is encoded in names, so
+//! "work/clients/acme" shows under "work/clients". The tree is rebuilt from
+//! the stored names, with unread counts summed up the hierarchy for
+//! collapsed labels.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::models::{LABEL_SEPARATOR, Label, LabelId, LabelNode};
+use crate::storage::MailStore;
+
+/// Get an account's user labels as a tree, with unread thread counts
+///
+/// Labels are ordered by name at each level.
+///
+/// # Arguments
+/// * `store` - The storage backend
+/// * `account_id` - The account whose labels to list
+pub fn label_tree(store: &dyn MailStore, account_id: i64) -> Result<Vec<LabelNode>> {
+    let unread = store.count_unread_threads_per_label(Some(account_id))?;
+    let mut labels = store.list_labels(account_id)?;
+    for label in &mut labels {
+        label.unread_count = unread.get(label.id.as_str()).copied().unwrap_or(0) as u32;
+    }
+    Ok(build_label_tree(labels))
+}
+
+/// Arrange labels into a tree by their names
+///
+/// A label nests under the label named by the longest `/`-separated prefix
+/// of its own name. Gmail doesn't require that label to exist, so without
+/// "work/clients", "work/clients/acme" nests under "work" as "clients/acme".
+fn build_label_tree(mut labels: Vec<Label>) -> Vec<LabelNode> {
+    labels.sort_by_key(|label| label.name.to_lowercase());
+    let ids: HashMap<&str, &LabelId> = labels.iter().map(|l| (l.name.as_str(), &l.id)).collect();
+
+    let mut children: HashMap<Option<LabelId>, Vec<LabelNode>> = HashMap::new();
+    for label in &labels {
+        let mut parent = None;
+        let mut prefix = label.name.as_str();
+        while let Some((head, _)) = prefix.rsplit_once(LABEL_SEPARATOR) {
+            if let Some(&id) = ids.get(head) {
+                parent = Some((head.len(), id.clone()));
+                break;
+            }
+            prefix = head;
+        }
+
+        let name = match &parent {
+            Some((len, _)) => label.name[len + 1..].to_string(),
+            None => label.name.clone(),
+        };
+        let parent_id = parent.map(|(_, id)| id);
+        children
+            .entry(parent_id.clone())
+            .or_default()
+            .push(LabelNode {
+                id: label.id.clone(),
+                path: label.name.clone(),
+                name,
+                parent_id,
+                unread_count: label.unread_count,
+                total_unread_count: label.unread_count,
+                color: label.color.clone(),
+                icon: label.icon.clone(),
+                children: Vec::new(),
+            });
+    }
+
+    attach_children(None, &mut children)
+}
+
+/// Take the nodes under `parent_id`, with their own children attached and
+/// unread counts summed
+fn attach_children(
+    parent_id: Option<LabelId>,
+    children: &mut HashMap<Option<LabelId>, Vec<LabelNode>>,
+) -> Vec<LabelNode> {
+    let mut nodes = children.remove(&parent_id).unwrap_or_default();
+    for node in &mut nodes {
+        node.children = attach_children(Some(node.id.clone()), children);
+        node.total_unread_count += node
+            .children
+            .iter()
+            .map(|child| child.total_unread_count)
+            .sum::<u32>();
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EmailAddress, Message, MessageId, Thread, ThreadId};
+    use crate::storage::InMemoryMailStore;
+    use chrono::Utc;
+
+    fn names(nodes: &[LabelNode]) -> Vec<&str> {
+        nodes.iter().map(|node| node.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_build_label_tree() {
+        let tree = build_label_tree(vec![
+            Label::new("Label_4", "work/clients/acme").with_unread_count(2),
+            Label::new("Label_1", "work").with_unread_count(1),
+            Label::new("Label_5", "receipts"),
+            Label::new("Label_3", "work/clients").with_unread_count(3),
+            Label::new("Label_6", "travel/2024/japan").with_unread_count(4),
+            Label::new("Label_7", "travel"),
+            Label::new("Label_2", "work/admin"),
+        ]);
+
+        assert_eq!(names(&tree), vec!["receipts", "travel", "work"]);
+
+        let work = &tree[2];
+        assert_eq!(work.parent_id, None);
+        assert_eq!(names(&work.children), vec!["admin", "clients"]);
+        assert_eq!(work.unread_count, 1);
+        assert_eq!(work.total_unread_count, 6);
+
+        let clients = &work.children[1];
+        assert_eq!(clients.parent_id, Some(LabelId::new("Label_1")));
+        assert_eq!(clients.total_unread_count, 5);
+        let acme = &clients.children[0];
+        assert_eq!(acme.name, "acme");
+        assert_eq!(acme.path, "work/clients/acme");
+        assert_eq!(acme.parent_id, Some(LabelId::new("Label_3")));
+
+        // "travel/2024" doesn't exist, so "2024/japan" sits under "travel"
+        let travel = &tree[1];
+        assert_eq!(names(&travel.children), vec!["2024/japan"]);
+        assert_eq!(travel.total_unread_count, 4);
+    }
+
+    #[test]
+    fn test_label_tree_counts_unread_threads() {
+        let store = InMemoryMailStore::new();
+        store
+            .replace_labels(
+                1,
+                vec![Label::new("Label_1", "work"), Label::new("Label_2", "work/acme")],
+            )
+            .unwrap();
+        for (thread_id, label, unread) in [
+            ("t1", "Label_1", true),
+            ("t2", "Label_2", true),
+            ("t3", "Label_2", false),
+        ] {
+            store
+                .upsert_thread(Thread::new(
+                    ThreadId::new(thread_id),
+                    1,
+                    "Subject".to_string(),
+                    "Snippet".to_string(),
+                    Utc::now(),
+                    1,
+                    None,
+                    "sender@example.com".to_string(),
+                    unread,
+                ))
+                .unwrap();
+            let mut labels = vec![label.to_string()];
+            if unread {
+                labels.push("UNREAD".to_string());
+            }
+            let message = Message::builder(
+                MessageId::new(format!("{}_m", thread_id)),
+                ThreadId::new(thread_id),
+            )
+            .account_id(1)
+            .from(EmailAddress::new("sender@example.com"))
+            .label_ids(labels)
+            .build();
+            store.upsert_message(message).unwrap();
+        }
+
+        let tree = label_tree(&store, 1).unwrap();
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].unread_count, 1);
+        assert_eq!(tree[0].total_unread_count, 2);
+        assert_eq!(tree[0].children[0].unread_count, 1);
+
+        assert!(label_tree(&store, 2).unwrap().is_empty());
+    }
+}
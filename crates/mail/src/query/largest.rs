@@ -0,0 +1,99 @@
+//! Large-mail finder
+//!
+//! Ranks threads by the combined size of their messages, as Gmail
+//! estimates it, so the space hogs can be found and deleted.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::threads::ThreadSummary;
+use crate::storage::MailStore;
+
+/// A thread and the space its messages take
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LargeThread {
+    pub thread: ThreadSummary,
+    /// Total estimated size of the thread's messages
+    pub total_bytes: i64,
+}
+
+/// List the threads taking the most space, largest first
+///
+/// # Arguments
+/// * `store` - The storage backend
+/// * `account_id` - Only this account's threads, or all accounts if None
+/// * `limit` - Maximum number of threads to return
+pub fn list_largest_threads(
+    store: &dyn MailStore,
+    account_id: Option<i64>,
+    limit: usize,
+) -> Result<Vec<LargeThread>> {
+    let mut largest = Vec::new();
+    for (thread_id, total_bytes) in store.largest_threads(account_id, limit)? {
+        // Messages whose thread row is missing are left to the integrity check
+        if let Some(thread) = store.get_thread(&thread_id)? {
+            largest.push(LargeThread {
+                thread: ThreadSummary::from(thread),
+                total_bytes,
+            });
+        }
+    }
+    Ok(largest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EmailAddress, Message, MessageId, Thread, ThreadId};
+    use crate::storage::InMemoryMailStore;
+    use chrono::Utc;
+
+    #[test]
+    fn test_list_largest_threads() {
+        let store = InMemoryMailStore::new();
+        for (thread_id, sizes) in [
+            ("t1", vec![100, 200]),
+            ("t2", vec![5_000_000]),
+            ("t3", vec![]),
+        ] {
+            store
+                .upsert_thread(Thread::new(
+                    ThreadId::new(thread_id),
+                    1,
+                    format!("Thread {}", thread_id),
+                    "Snippet".to_string(),
+                    Utc::now(),
+                    sizes.len(),
+                    None,
+                    "sender@example.com".to_string(),
+                    false,
+                ))
+                .unwrap();
+            for (i, size) in sizes.into_iter().enumerate() {
+                let message = Message::builder(
+                    MessageId::new(format!("{}_m{}", thread_id, i)),
+                    ThreadId::new(thread_id),
+                )
+                .account_id(1)
+                .from(EmailAddress::new("sender@example.com"))
+                .size_bytes(size)
+                .build();
+                store.upsert_message(message).unwrap();
+            }
+        }
+
+        let largest = list_largest_threads(&store, None, 10).unwrap();
+        let sizes: Vec<(&str, i64)> = largest
+            .iter()
+            .map(|t| (t.thread.id.as_str(), t.total_bytes))
+            .collect();
+        assert_eq!(sizes, vec![("t2", 5_000_000), ("t1", 300)]);
+
+        assert_eq!(list_largest_threads(&store, None, 1).unwrap().len(), 1);
+        assert!(
+            list_largest_threads(&store, Some(2), 10)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -3,6 +3,43 @@
 //! Provides high-level query functions that return data formatted
 //! for display in the UI.
 
+mod categories;
+mod diff;
+mod filters;
+mod followups;
+mod labels;
+mod largest;
+mod notes;
+mod outbox;
+mod replies;
+mod sanitize;
+mod saved_searches;
+mod source;
+mod subscriptions;
+mod summaries;
 mod threads;
 
-pub use threads::{ThreadDetail, ThreadSummary, get_thread_detail, list_threads, list_threads_by_label};
+pub use categories::{CategoryCount, count_unread_by_category, list_threads_by_category};
+pub use diff::{ThreadListDiff, ThreadMove, diff_thread_lists};
+pub use filters::{ThreadFilter, list_threads_filtered};
+pub use followups::{
+    DEFAULT_FOLLOWUP_DAYS, FOLLOWUP_LOOKBACK_DAYS, FollowupCandidate, dismiss_followup,
+    list_followup_candidates, snooze_followup,
+};
+pub use labels::label_tree;
+pub use largest::{LargeThread, list_largest_threads};
+pub use notes::set_thread_note;
+pub use outbox::{count_unsent, list_outbox};
+pub use replies::{
+    MAX_REPLY_SUGGESTIONS, REPLY_CONTEXT_MESSAGES, ReplySuggester, build_reply_prompt,
+    suggest_replies,
+};
+pub use sanitize::sanitize_html;
+pub use saved_searches::{SavedSearchSummary, list_saved_searches_with_counts};
+pub use source::{decode_raw_source, get_original_source};
+pub use subscriptions::{UnsubscribeSender, list_unsubscribe_senders};
+pub use summaries::{Summarizer, get_thread_summary};
+pub use threads::{
+    ThreadDetail, ThreadInvite, ThreadPage, ThreadSummary, attach_thread_labels, get_thread_detail,
+    get_thread_label_ids, list_message_rows, list_threads, list_threads_by_label,
+};
@@ -0,0 +1,84 @@
+//! Private thread notes
+//!
+//! Notes are stored locally and never synced to Gmail. Saving one also
+//! updates the search index so the thread can be found by its note.
+
+use anyhow::Result;
+
+use crate::error::MailError;
+use crate::models::ThreadId;
+use crate::search::SearchBackend;
+use crate::storage::MailStore;
+
+/// Set the user's note on a thread, or remove it with blank Markdown
+///
+/// # Arguments
+/// * `store` - The storage backend
+/// * `search` - Index to update, if any; committed before returning
+/// * `thread_id` - The thread to annotate
+/// * `markdown` - Note content
+pub fn set_thread_note(
+    store: &dyn MailStore,
+    search: Option<&dyn SearchBackend>,
+    thread_id: &ThreadId,
+    markdown: &str,
+) -> Result<()> {
+    let thread = store
+        .get_thread(thread_id)?
+        .ok_or_else(|| MailError::NotFound(format!("Thread {}", thread_id.as_str())))?;
+    store.set_note(thread_id, markdown)?;
+
+    if let Some(search) = search {
+        search.index_note(&thread, Some(markdown))?;
+        search.commit()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Thread;
+    use crate::search::{SearchIndex, parse_query};
+    use crate::storage::InMemoryMailStore;
+    use chrono::Utc;
+
+    #[test]
+    fn test_set_thread_note() {
+        let store = InMemoryMailStore::new();
+        let index = SearchIndex::in_memory().unwrap();
+        let thread_id = ThreadId::new("t1");
+        let missing = set_thread_note(&store, Some(&index), &thread_id, "Hi");
+        assert!(missing.is_err());
+
+        store
+            .upsert_thread(Thread {
+                id: thread_id.clone(),
+                account_id: 1,
+                subject: "Contract".to_string(),
+                snippet: String::new(),
+                last_message_at: Utc::now(),
+                message_count: 1,
+                sender_name: None,
+                sender_email: "legal@example.com".to_string(),
+                is_unread: false,
+                participants: Vec::new(),
+            })
+            .unwrap();
+        set_thread_note(&store, Some(&index), &thread_id, "Waiting on *review*").unwrap();
+
+        let note = store.get_note(&thread_id).unwrap().unwrap();
+        assert_eq!(note.markdown, "Waiting on *review*");
+        let results = index
+            .search(&parse_query("review"), 10, &store, None)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        set_thread_note(&store, Some(&index), &thread_id, "").unwrap();
+        assert!(store.get_note(&thread_id).unwrap().is_none());
+        let results = index
+            .search(&parse_query("review"), 10, &store, None)
+            .unwrap();
+        assert!(results.is_empty());
+    }
+}
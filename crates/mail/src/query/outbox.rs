@@ -0,0 +1,73 @@
+//! Outbox folder
+//!
+//! Lists mail waiting to be sent, being retried or that failed, for an
+//! Outbox folder with resend and discard actions (see
+//! `ActionHandler::resend_outbox_message` and
+//! `ActionHandler::discard_outbox_message`).
+
+use anyhow::Result;
+
+use crate::models::{OutboxMessage, OutboxState};
+use crate::storage::MailStore;
+
+/// List outbox messages, newest first
+///
+/// Sent messages stay listed until their sent copy has synced, so a message
+/// doesn't vanish before it shows up in Sent.
+///
+/// # Arguments
+/// * `store` - The storage backend
+/// * `account_id` - Only this account's messages, or all accounts if None
+pub fn list_outbox(store: &dyn MailStore, account_id: Option<i64>) -> Result<Vec<OutboxMessage>> {
+    let mut messages = store.list_outbox_messages(account_id)?;
+    messages.reverse();
+    Ok(messages)
+}
+
+/// Count messages not yet sent, and how many of those failed
+///
+/// Returns `(unsent, failed)`, for the Outbox folder's badge.
+pub fn count_unsent(store: &dyn MailStore, account_id: Option<i64>) -> Result<(usize, usize)> {
+    let messages = store.list_outbox_messages(account_id)?;
+    let unsent = messages
+        .iter()
+        .filter(|m| m.state != OutboxState::Sent)
+        .count();
+    let failed = messages
+        .iter()
+        .filter(|m| m.state == OutboxState::Failed)
+        .count();
+    Ok((unsent, failed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryMailStore;
+
+    #[test]
+    fn test_outbox_listing() {
+        let store = InMemoryMailStore::new();
+        for (account_id, subject, state) in [
+            (1, "First", OutboxState::Sent),
+            (1, "Second", OutboxState::Failed),
+            (1, "Third", OutboxState::Queued),
+            (2, "Other", OutboxState::Queued),
+        ] {
+            let mut message =
+                OutboxMessage::new(account_id, None, subject, Vec::new(), String::new());
+            message.state = state;
+            store.add_outbox_message(message).unwrap();
+        }
+
+        let subjects: Vec<String> = list_outbox(&store, Some(1))
+            .unwrap()
+            .into_iter()
+            .map(|m| m.subject)
+            .collect();
+        assert_eq!(subjects, vec!["Third", "Second", "First"]);
+
+        assert_eq!(count_unsent(&store, Some(1)).unwrap(), (2, 1));
+        assert_eq!(count_unsent(&store, None).unwrap(), (3, 1));
+    }
+}
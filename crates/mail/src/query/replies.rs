@@ -0,0 +1,306 @@
+//! Smart reply suggestions
+//!
+//! Like summaries, suggestions come from a pluggable [`ReplySuggester`]. The
+//! prompt is assembled here so every frontend gives the model the same
+//! context: the subject and the last few messages with their senders, the
+//! user's own messages marked as "You", and quoted history stripped.
+
+use anyhow::Result;
+
+use crate::models::{Message, ThreadId};
+use crate::storage::MailStore;
+
+/// Messages from the end of the thread included in the prompt
+pub const REPLY_CONTEXT_MESSAGES: usize = 5;
+
+/// Suggestions returned at most
+pub const MAX_REPLY_SUGGESTIONS: usize = 3;
+
+/// Characters of each message body included in the prompt
+const MAX_BODY_CHARS: usize = 1500;
+
+/// Produces short reply suggestions from a prompt
+pub trait ReplySuggester: Send + Sync {
+    /// Suggest replies for `prompt`, as built by [`build_reply_prompt`]
+    fn suggest(&self, prompt: &str) -> Result<Vec<String>>;
+}
+
+/// Suggest short replies to the latest message in a thread
+///
+/// Returns an empty list if the thread doesn't exist, has no messages, or
+/// its latest message was sent by the user.
+///
+/// # Arguments
+/// * `store` - The storage backend
+/// * `suggester` - Produces suggestions from the assembled prompt
+/// * `thread_id` - The thread to reply to
+pub fn suggest_replies(
+    store: &dyn MailStore,
+    suggester: &dyn ReplySuggester,
+    thread_id: &ThreadId,
+) -> Result<Vec<String>> {
+    let Some(thread) = store.get_thread(thread_id)? else {
+        return Ok(Vec::new());
+    };
+    let messages = store.list_messages_for_thread_with_bodies(thread_id)?;
+
+    let mut own_addresses = Vec::new();
+    if let Some(account) = store.get_account(thread.account_id)? {
+        own_addresses.push(account.email.to_lowercase());
+    }
+    for alias in store.list_send_as_aliases(thread.account_id)? {
+        own_addresses.push(alias.email.to_lowercase());
+    }
+
+    let Some(latest) = messages.last() else {
+        return Ok(Vec::new());
+    };
+    if is_own(latest, &own_addresses) {
+        return Ok(Vec::new());
+    }
+
+    let prompt = build_reply_prompt(&thread.subject, &messages, &own_addresses);
+    let suggestions = suggester.suggest(&prompt)?;
+    Ok(clean_suggestions(suggestions))
+}
+
+/// Build the prompt for reply suggestions
+///
+/// `messages` are in chronological order; only the last
+/// [`REPLY_CONTEXT_MESSAGES`] are included. Messages from `own_addresses`
+/// (lowercase) are attributed to "You".
+pub fn build_reply_prompt(subject: &str, messages: &[Message], own_addresses: &[String]) -> String {
+    let mut prompt = format!(
+        "Suggest up to {} short, distinct replies to the latest message in this email \
+         thread, written as \"You\". Put each reply on its own line, without numbering \
+         or quotes.\n\nSubject: {}\n",
+        MAX_REPLY_SUGGESTIONS, subject
+    );
+
+    let start = messages.len().saturating_sub(REPLY_CONTEXT_MESSAGES);
+    for message in &messages[start..] {
+        let sender = if is_own(message, own_addresses) {
+            "You".to_string()
+        } else {
+            message.from.display()
+        };
+        prompt.push_str(&format!(
+            "\n---\nFrom: {}\nDate: {}\n\n{}\n",
+            sender,
+            message.received_at.format("%a, %d %b %Y %H:%M UTC"),
+            new_content(message)
+        ));
+    }
+    prompt
+}
+
+/// Whether the user sent a message
+fn is_own(message: &Message, own_addresses: &[String]) -> bool {
+    let from = message.from.email.to_lowercase();
+    own_addresses.contains(&from)
+}
+
+/// The text a message adds to the thread, without quoted history
+fn new_content(message: &Message) -> String {
+    let body = message
+        .body_text
+        .as_deref()
+        .filter(|text| !text.trim().is_empty())
+        .unwrap_or(&message.body_preview);
+
+    let mut lines = Vec::new();
+    for line in body.lines() {
+        let trimmed = line.trim();
+        if is_quote_header(trimmed) {
+            break;
+        }
+        if !trimmed.starts_with('>') {
+            lines.push(line.trim_end());
+        }
+    }
+    let text = lines.join("\n");
+    let text = text.trim();
+
+    if text.chars().count() > MAX_BODY_CHARS {
+        let truncated: String = text.chars().take(MAX_BODY_CHARS).collect();
+        format!("{}…", truncated.trim_end())
+    } else {
+        text.to_string()
+    }
+}
+
+/// Whether a line introduces quoted history ("On ... wrote:", forwarded headers)
+fn is_quote_header(line: &str) -> bool {
+    (line.starts_with("On ") && line.ends_with("wrote:"))
+        || line.starts_with("-----Original Message-----")
+        || line.starts_with("---------- Forwarded message")
+}
+
+/// Tidy suggestions: strip list markers and quotes, drop blanks and
+/// duplicates, and keep at most [`MAX_REPLY_SUGGESTIONS`]
+fn clean_suggestions(suggestions: Vec<String>) -> Vec<String> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for suggestion in suggestions {
+        let text = strip_list_marker(suggestion.trim());
+        let text = text
+            .strip_prefix('"')
+            .and_then(|t| t.strip_suffix('"'))
+            .unwrap_or(text)
+            .trim();
+        if text.is_empty() || cleaned.iter().any(|c| c.eq_ignore_ascii_case(text)) {
+            continue;
+        }
+        cleaned.push(text.to_string());
+        if cleaned.len() == MAX_REPLY_SUGGESTIONS {
+            break;
+        }
+    }
+    cleaned
+}
+
+/// Strip a leading "-", "*", "1." or "1)" marker
+fn strip_list_marker(text: &str) -> &str {
+    if let Some(rest) = text.strip_prefix(['-', '*', '•']) {
+        return rest.trim_start();
+    }
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits > 0
+        && let Some(rest) = text[digits..].strip_prefix(['.', ')'])
+    {
+        return rest.trim_start();
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Account, EmailAddress, MessageId, Thread};
+    use crate::storage::InMemoryMailStore;
+    use chrono::{Duration, Utc};
+    use std::sync::Mutex;
+
+    /// Records the prompt and returns canned suggestions
+    struct CannedSuggester {
+        prompt: Mutex<Option<String>>,
+    }
+
+    impl ReplySuggester for CannedSuggester {
+        fn suggest(&self, prompt: &str) -> Result<Vec<String>> {
+            *self.prompt.lock().unwrap() = Some(prompt.to_string());
+            Ok(vec![
+                "1. Sounds good!".to_string(),
+                "- \"Thursday works for me.\"".to_string(),
+                "sounds good!".to_string(),
+                "  ".to_string(),
+                "Can we do Friday?".to_string(),
+                "Let me check.".to_string(),
+            ])
+        }
+    }
+
+    fn message(id: &str, from: EmailAddress, body: &str, minutes_ago: i64) -> Message {
+        Message::builder(MessageId::new(id), ThreadId::new("t1"))
+            .account_id(1)
+            .from(from)
+            .subject("Offsite")
+            .body_text(Some(body.to_string()))
+            .received_at(Utc::now() - Duration::minutes(minutes_ago))
+            .build()
+    }
+
+    fn setup() -> InMemoryMailStore {
+        let store = InMemoryMailStore::new();
+        store
+            .register_account(Account::new("me@example.com"))
+            .unwrap();
+        store
+            .upsert_thread(Thread {
+                id: ThreadId::new("t1"),
+                account_id: 1,
+                subject: "Offsite".to_string(),
+                snippet: String::new(),
+                last_message_at: Utc::now(),
+                message_count: 2,
+                sender_name: None,
+                sender_email: "alice@example.com".to_string(),
+                is_unread: false,
+                participants: Vec::new(),
+            })
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn test_suggest_replies() {
+        let store = setup();
+        let alice = EmailAddress::with_name("Alice", "alice@example.com");
+        let me = EmailAddress::new("Me@Example.com");
+        store
+            .upsert_message(message("m1", me, "Shall we meet next week?", 30))
+            .unwrap();
+        store
+            .upsert_message(message(
+                "m2",
+                alice,
+                "Thursday?\n\nOn Mon, Me wrote:\n> Shall we meet next week?",
+                5,
+            ))
+            .unwrap();
+
+        let suggester = CannedSuggester {
+            prompt: Mutex::new(None),
+        };
+        let replies = suggest_replies(&store, &suggester, &ThreadId::new("t1")).unwrap();
+        assert_eq!(
+            replies,
+            vec![
+                "Sounds good!",
+                "Thursday works for me.",
+                "Can we do Friday?"
+            ]
+        );
+
+        let prompt = suggester.prompt.lock().unwrap().clone().unwrap();
+        assert!(prompt.contains("Subject: Offsite\n"));
+        assert!(prompt.contains("From: You\n"));
+        assert!(prompt.contains("From: Alice <alice@example.com>\n"));
+        assert!(prompt.contains("\n\nThursday?\n"));
+        assert!(!prompt.contains("> Shall we"));
+        assert!(prompt.find("From: You").unwrap() < prompt.find("From: Alice").unwrap());
+    }
+
+    #[test]
+    fn test_no_suggestions_after_own_message() {
+        let store = setup();
+        store
+            .upsert_message(message("m1", EmailAddress::new("me@example.com"), "Hi", 5))
+            .unwrap();
+
+        let suggester = CannedSuggester {
+            prompt: Mutex::new(None),
+        };
+        let replies = suggest_replies(&store, &suggester, &ThreadId::new("t1")).unwrap();
+        assert!(replies.is_empty());
+        assert!(suggester.prompt.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_prompt_keeps_last_messages() {
+        let bob = EmailAddress::new("bob@example.com");
+        let messages: Vec<Message> = (0..8)
+            .map(|i| {
+                message(
+                    &format!("m{}", i),
+                    bob.clone(),
+                    &format!("note {}", i),
+                    60 - i,
+                )
+            })
+            .collect();
+        let prompt = build_reply_prompt("Notes", &messages, &[]);
+        assert_eq!(prompt.matches("\n---\n").count(), REPLY_CONTEXT_MESSAGES);
+        assert!(!prompt.contains("note 2\n"));
+        assert!(prompt.contains("note 3\n") && prompt.contains("note 7\n"));
+    }
+}
@@ -0,0 +1,285 @@
+//! Sanitizing of HTML message bodies
+//!
+//! Orion renders bodies in a locked-down webview, but other clients (the
+//! iOS app, third-party tools) get the stored HTML as-is. This strips active
+//! content - scripts, frames, event handlers and script URLs - so a body can
+//! be handed to any web view. Like [`highlight_html`](crate::highlight_html)
+//! it scans tags rather than building a DOM, so layout and inline styles
+//! survive untouched.
+
+/// Elements removed together with everything inside them
+const DROPPED_ELEMENTS: [&str; 7] = [
+    "script", "iframe", "object", "applet", "frameset", "noscript", "template",
+];
+
+/// Tags removed on their own, keeping any content
+const DROPPED_TAGS: [&str; 8] = [
+    "base", "link", "meta", "embed", "frame", "form", "input", "button",
+];
+
+/// Elements whose content is text, not markup
+const RAW_TEXT_ELEMENTS: [&str; 3] = ["style", "title", "textarea"];
+
+/// Attributes holding a URL that a browser may navigate to or load
+const URL_ATTRIBUTES: [&str; 9] = [
+    "href", "src", "action", "background", "poster", "cite", "lowsrc", "dynsrc", "xlink:href",
+];
+
+/// Strip active content from an HTML body
+///
+/// Comments, doctypes, scripts, frames and embedded objects are removed,
+/// `on*` event handlers are dropped, and URL attributes pointing at
+/// `javascript:`, `vbscript:` or non-image `data:` URLs are removed. Text and
+/// the remaining markup are passed through.
+pub fn sanitize_html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(tag_start) = rest.find('<') else {
+            out.push_str(rest);
+            break;
+        };
+        out.push_str(&rest[..tag_start]);
+        rest = &rest[tag_start..];
+
+        // A '<' that can't start a tag is text
+        let starts_tag = rest[1..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?'));
+        if !starts_tag {
+            out.push_str("&lt;");
+            rest = &rest[1..];
+            continue;
+        }
+
+        // Comments may contain '>' so they end at "-->"
+        let tag_end = if rest.starts_with("<!--") {
+            rest.find("-->").map(|i| i + 3)
+        } else {
+            rest.find('>').map(|i| i + 1)
+        }
+        .unwrap_or(rest.len());
+        let tag = &rest[..tag_end];
+        rest = &rest[tag_end..];
+
+        let closing = tag.starts_with("</");
+        let self_closing = tag.ends_with("/>");
+        let name = tag_name(tag).to_ascii_lowercase();
+
+        // Comments, doctypes and processing instructions
+        if tag.starts_with("<!") || tag.starts_with("<?") || name.is_empty() {
+            continue;
+        }
+        if DROPPED_ELEMENTS.contains(&name.as_str()) {
+            if !closing && !self_closing {
+                rest = &rest[closing_tag_start(rest, &name)..];
+            }
+            continue;
+        }
+        if DROPPED_TAGS.contains(&name.as_str()) {
+            continue;
+        }
+
+        if closing {
+            out.push_str(&format!("</{}>", name));
+            continue;
+        }
+        out.push_str(&sanitize_tag(&name, tag));
+        // Raw text only ends at its closing tag, exactly as a browser reads
+        // it, so it is copied without looking for markup
+        if RAW_TEXT_ELEMENTS.contains(&name.as_str()) && !self_closing {
+            let end = closing_tag_start(rest, &name);
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+        }
+    }
+
+    out
+}
+
+/// Offset of the closing tag for `name` in `html`, or its length if unclosed
+fn closing_tag_start(html: &str, name: &str) -> usize {
+    // ASCII lowercasing keeps byte offsets unchanged
+    html.to_ascii_lowercase()
+        .find(&format!("</{}", name))
+        .unwrap_or(html.len())
+}
+
+/// Rebuild an opening tag with only its safe attributes
+fn sanitize_tag(name: &str, tag: &str) -> String {
+    let inner = tag.trim_start_matches('<').trim_end_matches('>');
+    let self_closing = inner.ends_with('/');
+    let attributes = inner.get(name.len()..).unwrap_or("").trim_end_matches('/');
+
+    let mut out = format!("<{}", name);
+    for (attr, value) in parse_attributes(attributes) {
+        if !allowed_attribute(&attr, value.as_deref()) {
+            continue;
+        }
+        out.push(' ');
+        out.push_str(&attr);
+        if let Some(value) = value {
+            out.push_str("=\"");
+            out.push_str(&value.replace('"', "&quot;"));
+            out.push('"');
+        }
+    }
+    out.push_str(if self_closing { " />" } else { ">" });
+    out
+}
+
+/// Split the attribute part of a tag into lowercase names and raw values
+fn parse_attributes(mut s: &str) -> Vec<(String, Option<String>)> {
+    let mut attributes = Vec::new();
+    loop {
+        s = s.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if s.is_empty() {
+            break;
+        }
+        let name_end = s
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(s.len());
+        let name = s[..name_end].to_ascii_lowercase();
+        s = s[name_end..].trim_start();
+
+        let value = match s.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                let (value, remaining) = match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let body = &after[1..];
+                        let end = body.find(quote).unwrap_or(body.len());
+                        (&body[..end], body.get(end + 1..).unwrap_or(""))
+                    }
+                    _ => {
+                        let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                        (&after[..end], &after[end..])
+                    }
+                };
+                s = remaining;
+                Some(value.to_string())
+            }
+            None => None,
+        };
+        if !name.is_empty() {
+            attributes.push((name, value));
+        }
+    }
+    attributes
+}
+
+/// Whether an attribute can be kept
+fn allowed_attribute(name: &str, value: Option<&str>) -> bool {
+    if name.starts_with("on") || name == "srcdoc" || name == "formaction" {
+        return false;
+    }
+    let Some(value) = value else {
+        return true;
+    };
+    if URL_ATTRIBUTES.contains(&name) {
+        return is_safe_url(value);
+    }
+    if name == "style" {
+        let style = value.to_ascii_lowercase();
+        return !style.contains("expression(") && !style.contains("javascript:");
+    }
+    true
+}
+
+/// Whether a URL attribute value can't run script
+fn is_safe_url(value: &str) -> bool {
+    // Browsers ignore whitespace and control characters inside the scheme
+    let url: String = value
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    // Character references could spell out a scheme ("&#106;avascript:")
+    let scheme_part = url.split(['/', '?', '#']).next().unwrap_or("");
+    if scheme_part.contains('&') {
+        return false;
+    }
+    if url.starts_with("data:") {
+        return url.starts_with("data:image/");
+    }
+    !url.starts_with("javascript:") && !url.starts_with("vbscript:")
+}
+
+/// Element name of a tag like `<div class="x">` or `</div>`
+fn tag_name(tag: &str) -> &str {
+    let name = tag.trim_start_matches('<').trim_start_matches('/');
+    let end = name
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(name.len());
+    &name[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_html_removes_active_content() {
+        let html = concat!(
+            "<!DOCTYPE html><html><head><meta http-equiv=\"refresh\" content=\"0\">",
+            "<script>alert(1)</script><style>p { color: red; }</style></head>",
+            "<body onload=\"steal()\"><p class=\"intro\">Hi <b>there</b></p>",
+            "<iframe src=\"https://evil.example\"><p>fallback</p></iframe>",
+            "<!-- tracking --><img src=\"https://cdn.example/logo.png\" onerror=alert(1)>",
+            "</body></html>"
+        );
+        assert_eq!(
+            sanitize_html(html),
+            concat!(
+                "<html><head><style>p { color: red; }</style></head>",
+                "<body><p class=\"intro\">Hi <b>there</b></p>",
+                "<img src=\"https://cdn.example/logo.png\">",
+                "</body></html>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_sanitize_html_urls() {
+        assert_eq!(
+            sanitize_html("<a href=\"JavaScript:alert(1)\" title=x>link</a>"),
+            "<a title=\"x\">link</a>"
+        );
+        assert_eq!(
+            sanitize_html("<a href=\" java\tscript:alert(1)\">link</a>"),
+            "<a>link</a>"
+        );
+        assert_eq!(
+            sanitize_html("<a href=\"&#106;avascript:alert(1)\">link</a>"),
+            "<a>link</a>"
+        );
+        assert_eq!(
+            sanitize_html("<img src='data:text/html;base64,AAAA'><img src=\"data:image/png;base64,AAAA\"/>"),
+            "<img><img src=\"data:image/png;base64,AAAA\" />"
+        );
+        assert_eq!(
+            sanitize_html("<a href=\"https://example.com/?a=1&amp;b=2\">ok</a>"),
+            "<a href=\"https://example.com/?a=1&amp;b=2\">ok</a>"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_html_text() {
+        // Stray angle brackets stay text
+        assert_eq!(sanitize_html("1 < 2 and 3 > 2"), "1 &lt; 2 and 3 > 2");
+        // Raw text is never markup, so tags inside it are left alone
+        assert_eq!(
+            sanitize_html("<title><script>x</script></title>"),
+            "<title><script>x</script></title>"
+        );
+        // Markup-like text inside a dropped script doesn't end it early
+        assert_eq!(
+            sanitize_html("<script>if (a<b) {}</script><p>after</p>"),
+            "<p>after</p>"
+        );
+        // An unclosed script drops the rest of the body
+        assert_eq!(sanitize_html("<p>a</p><script>b"), "<p>a</p>");
+    }
+}
@@ -0,0 +1,134 @@
+//! Saved search queries
+//!
+//! Lists saved searches alongside live result counts from the search index,
+//! for rendering smart folders in the sidebar.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::models::SavedSearch;
+use crate::search::{SearchIndex, parse_query};
+use crate::storage::MailStore;
+
+/// A saved search with its current number of matching threads
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSearchSummary {
+    /// The saved search
+    pub search: SavedSearch,
+    /// Number of threads currently matching the query
+    pub count: usize,
+}
+
+/// List saved searches with live result counts
+///
+/// Searches are returned pinned first, then by name. A query that fails to
+/// execute is reported with a count of zero rather than failing the list.
+///
+/// # Arguments
+/// * `store` - The storage backend
+/// * `index` - The search index used to count matches
+/// * `account_id` - Optional account filter for counts (None = all accounts)
+/// * `pinned_only` - Only return searches pinned to the sidebar
+pub fn list_saved_searches_with_counts(
+    store: &dyn MailStore,
+    index: &SearchIndex,
+    account_id: Option<i64>,
+    pinned_only: bool,
+) -> Result<Vec<SavedSearchSummary>> {
+    let accounts = store.list_accounts()?;
+    let summaries = store
+        .list_saved_searches()?
+        .into_iter()
+        .filter(|search| !pinned_only || search.pinned)
+        .map(|search| {
+            let parsed = parse_query(&search.query).resolve_accounts(&accounts);
+            let count = index
+                .count_threads(&parsed, account_id)
+                .unwrap_or_else(|e| {
+                    log::warn!("Failed to count saved search {:?}: {}", search.name, e);
+                    0
+                });
+            SavedSearchSummary { search, count }
+        })
+        .collect();
+
+    Ok(summaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EmailAddress, Message, MessageId, Thread, ThreadId};
+    use crate::storage::InMemoryMailStore;
+    use chrono::Utc;
+
+    fn index_message(
+        index: &SearchIndex,
+        store: &InMemoryMailStore,
+        id: &str,
+        from: &str,
+        labels: &[&str],
+    ) {
+        let thread = Thread::new(
+            ThreadId::new(id),
+            1,
+            format!("Subject {}", id),
+            "Snippet".to_string(),
+            Utc::now(),
+            1,
+            None,
+            from.to_string(),
+            labels.contains(&"UNREAD"),
+        );
+        let message = Message::builder(MessageId::new(format!("m_{}", id)), ThreadId::new(id))
+            .account_id(1)
+            .from(EmailAddress::new(from))
+            .subject(format!("Subject {}", id))
+            .received_at(Utc::now())
+            .label_ids(labels.iter().map(|l| l.to_string()).collect())
+            .build();
+        store.upsert_thread(thread.clone()).unwrap();
+        store.upsert_message(message.clone()).unwrap();
+        index.index_message(&message, &thread).unwrap();
+    }
+
+    #[test]
+    fn test_saved_search_counts() {
+        let store = InMemoryMailStore::new();
+        let index = SearchIndex::in_memory().unwrap();
+        index_message(
+            &index,
+            &store,
+            "t1",
+            "boss@example.com",
+            &["INBOX", "UNREAD"],
+        );
+        index_message(&index, &store, "t2", "boss@example.com", &["INBOX"]);
+        index_message(
+            &index,
+            &store,
+            "t3",
+            "friend@example.com",
+            &["INBOX", "UNREAD"],
+        );
+        index.commit().unwrap();
+
+        store
+            .save_search(SavedSearch::new("Boss unread", "from:boss is:unread").with_pinned(true))
+            .unwrap();
+        store
+            .save_search(SavedSearch::new("All unread", "is:unread"))
+            .unwrap();
+
+        let all = list_saved_searches_with_counts(&store, &index, None, false).unwrap();
+        let counts: Vec<_> = all
+            .iter()
+            .map(|s| (s.search.name.as_str(), s.count))
+            .collect();
+        assert_eq!(counts, vec![("Boss unread", 1), ("All unread", 2)]);
+
+        let pinned = list_saved_searches_with_counts(&store, &index, None, true).unwrap();
+        assert_eq!(pinned.len(), 1);
+        assert_eq!(pinned[0].search.query, "from:boss is:unread");
+    }
+}
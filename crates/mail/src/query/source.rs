@@ -0,0 +1,51 @@
+//! Original message source ("Show original")
+//!
+//! Bodies in the store are normalized and headers are mostly dropped, so the
+//! raw RFC 2822 source is fetched from Gmail on demand.
+
+use anyhow::{Context, Result};
+use base64::prelude::*;
+
+use crate::gmail::GmailClient;
+use crate::models::MessageId;
+
+/// Fetch a message's original RFC 2822 source, headers included
+pub fn get_original_source(client: &GmailClient, message_id: &MessageId) -> Result<String> {
+    let message = client.get_message_raw(message_id)?;
+    decode_raw_source(&message.raw)
+        .with_context(|| format!("Failed to decode source of message {}", message_id.as_str()))
+}
+
+/// Decode Gmail's base64url `raw` field to message text
+///
+/// Bytes that aren't valid UTF-8 (8-bit bodies in legacy charsets) are
+/// replaced rather than rejected, since the source is for display only.
+pub fn decode_raw_source(raw: &str) -> Result<String> {
+    let raw = raw.trim_end_matches('=');
+    let bytes = BASE64_URL_SAFE_NO_PAD
+        .decode(raw)
+        .context("Raw message is not valid base64url")?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_raw_source() {
+        let source = "From: a@example.com\r\nSubject: Hi?\r\n\r\nBody\r\n";
+        let padded = BASE64_URL_SAFE.encode(source);
+        assert_eq!(decode_raw_source(&padded).unwrap(), source);
+        let unpadded = BASE64_URL_SAFE_NO_PAD.encode(source);
+        assert_eq!(decode_raw_source(&unpadded).unwrap(), source);
+
+        let latin1 = BASE64_URL_SAFE_NO_PAD.encode(b"Subject: caf\xe9\r\n");
+        assert_eq!(
+            decode_raw_source(&latin1).unwrap(),
+            "Subject: caf\u{fffd}\r\n"
+        );
+
+        assert!(decode_raw_source("not base64!").is_err());
+    }
+}
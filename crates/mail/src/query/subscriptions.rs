@@ -0,0 +1,131 @@
+//! Mailing list subscriptions
+//!
+//! Groups messages carrying List-Unsubscribe headers by sender, for a
+//! "manage subscriptions" view.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{ThreadId, Unsubscribe};
+use crate::storage::MailStore;
+
+/// A sender that can be unsubscribed from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnsubscribeSender {
+    /// Account that receives the mail
+    pub account_id: i64,
+    /// Sender address (lowercase)
+    pub email: String,
+    /// Sender display name from the newest message
+    pub name: Option<String>,
+    /// Unsubscribe targets from the newest message
+    pub unsubscribe: Unsubscribe,
+    /// Thread of the newest message, for `ActionHandler::unsubscribe`
+    pub thread_id: ThreadId,
+    /// Messages from the sender with unsubscribe targets
+    pub message_count: usize,
+    /// When the newest message was received
+    pub last_received_at: DateTime<Utc>,
+}
+
+/// List senders offering unsubscribe, most prolific first
+///
+/// # Arguments
+/// * `store` - The storage backend
+/// * `account_id` - Optional account filter (None = all accounts)
+pub fn list_unsubscribe_senders(
+    store: &dyn MailStore,
+    account_id: Option<i64>,
+) -> Result<Vec<UnsubscribeSender>> {
+    let mut senders: Vec<UnsubscribeSender> = Vec::new();
+    let mut positions: HashMap<(i64, String), usize> = HashMap::new();
+
+    // Newest first, so the first message seen from a sender sets its details
+    for message in store.list_unsubscribable_messages(account_id)? {
+        let Some(unsubscribe) = message.unsubscribe else {
+            continue;
+        };
+        let email = message.from.email.to_lowercase();
+        match positions.get(&(message.account_id, email.clone())) {
+            Some(&i) => senders[i].message_count += 1,
+            None => {
+                positions.insert((message.account_id, email.clone()), senders.len());
+                senders.push(UnsubscribeSender {
+                    account_id: message.account_id,
+                    email,
+                    name: message.from.name,
+                    unsubscribe,
+                    thread_id: message.thread_id,
+                    message_count: 1,
+                    last_received_at: message.received_at,
+                });
+            }
+        }
+    }
+
+    senders.sort_by(|a, b| {
+        b.message_count
+            .cmp(&a.message_count)
+            .then(b.last_received_at.cmp(&a.last_received_at))
+    });
+    Ok(senders)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EmailAddress, Message, MessageId};
+    use crate::storage::InMemoryMailStore;
+    use chrono::Duration;
+
+    fn add_message(store: &InMemoryMailStore, id: &str, from: &str, age: i64, list: bool) {
+        let unsubscribe = Unsubscribe {
+            url: Some(format!("https://example.com/unsubscribe/{}", id)),
+            ..Default::default()
+        };
+        store
+            .upsert_message(
+                Message::builder(MessageId::new(id), ThreadId::new(format!("t_{}", id)))
+                    .account_id(1)
+                    .from(EmailAddress::parse(from))
+                    .received_at(Utc::now() - Duration::minutes(age))
+                    .unsubscribe(list.then_some(unsubscribe))
+                    .build(),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_list_unsubscribe_senders() {
+        let store = InMemoryMailStore::new();
+        add_message(&store, "m1", "News <news@shop.example>", 1, true);
+        add_message(&store, "m2", "news@shop.example", 5, true);
+        add_message(&store, "m3", "Digest <Digest@Forum.example>", 2, true);
+        add_message(&store, "m4", "friend@example.com", 0, false);
+
+        let senders = list_unsubscribe_senders(&store, None).unwrap();
+        assert_eq!(senders.len(), 2);
+
+        assert_eq!(senders[0].email, "news@shop.example");
+        assert_eq!(senders[0].message_count, 2);
+        // Details come from the newest message
+        assert_eq!(senders[0].name.as_deref(), Some("News"));
+        assert_eq!(senders[0].thread_id.as_str(), "t_m1");
+        assert_eq!(
+            senders[0].unsubscribe.url.as_deref(),
+            Some("https://example.com/unsubscribe/m1")
+        );
+
+        assert_eq!(senders[1].email, "digest@forum.example");
+        assert_eq!(senders[1].message_count, 1);
+
+        assert!(
+            list_unsubscribe_senders(&store, Some(2))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
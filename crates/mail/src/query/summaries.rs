@@ -0,0 +1,144 @@
+//! Thread summaries
+//!
+//! The crate doesn't depend on any model: an integration (a local or remote
+//! LLM) implements [`Summarizer`]. Summaries are cached in the store against
+//! the set of messages they were written from, so a new reply produces a
+//! fresh summary while re-opening an unchanged thread costs nothing.
+
+use anyhow::Result;
+
+use crate::models::{Message, Thread, ThreadId};
+use crate::storage::{MailStore, MessageMetadata};
+
+/// Writes one-paragraph summaries of threads
+pub trait Summarizer: Send + Sync {
+    /// Summarize a thread
+    ///
+    /// `messages` are in chronological order and include bodies.
+    fn summarize(&self, thread: &Thread, messages: &[Message]) -> Result<String>;
+}
+
+/// Get a one-paragraph summary of a thread
+///
+/// Returns the cached summary if the thread hasn't changed since it was
+/// written; otherwise asks `summarizer` and caches the result. Returns None
+/// if the thread doesn't exist or has no messages.
+///
+/// # Arguments
+/// * `store` - The storage backend
+/// * `summarizer` - Produces summaries on a cache miss
+/// * `thread_id` - The thread to summarize
+pub fn get_thread_summary(
+    store: &dyn MailStore,
+    summarizer: &dyn Summarizer,
+    thread_id: &ThreadId,
+) -> Result<Option<String>> {
+    let Some(thread) = store.get_thread(thread_id)? else {
+        return Ok(None);
+    };
+    let metadata = store.list_messages_for_thread(thread_id)?;
+    if metadata.is_empty() {
+        return Ok(None);
+    }
+
+    let source_key = source_key(&metadata);
+    if let Some(summary) = store.get_cached_summary(thread_id, &source_key)? {
+        return Ok(Some(summary));
+    }
+
+    let messages = store.list_messages_for_thread_with_bodies(thread_id)?;
+    let summary = summarizer.summarize(&thread, &messages)?.trim().to_string();
+    store.cache_summary(thread_id, &source_key, &summary)?;
+
+    Ok(Some(summary))
+}
+
+/// Identify the messages a summary covers: their IDs, sorted
+fn source_key(messages: &[MessageMetadata]) -> String {
+    let mut ids: Vec<&str> = messages.iter().map(|m| m.id.as_str()).collect();
+    ids.sort_unstable();
+    ids.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EmailAddress, MessageId};
+    use crate::storage::InMemoryMailStore;
+    use chrono::{Duration, Utc};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Joins message bodies under the subject and counts calls
+    struct CountingSummarizer {
+        calls: AtomicUsize,
+    }
+
+    impl Summarizer for CountingSummarizer {
+        fn summarize(&self, thread: &Thread, messages: &[Message]) -> Result<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let bodies: Vec<_> = messages
+                .iter()
+                .filter_map(|m| m.body_text.as_deref())
+                .collect();
+            Ok(format!("  {}: {}\n", thread.subject, bodies.join(" / ")))
+        }
+    }
+
+    fn add_message(store: &InMemoryMailStore, id: &str, body: &str, minutes_ago: i64) {
+        let message = Message::builder(MessageId::new(id), ThreadId::new("t1"))
+            .from(EmailAddress::new("alice@example.com"))
+            .subject("Offsite")
+            .body_text(Some(body.to_string()))
+            .received_at(Utc::now() - Duration::minutes(minutes_ago))
+            .build();
+        store.upsert_message(message).unwrap();
+    }
+
+    #[test]
+    fn test_get_thread_summary_caches_until_thread_changes() {
+        let store = InMemoryMailStore::new();
+        store
+            .upsert_thread(Thread {
+                id: ThreadId::new("t1"),
+                account_id: 1,
+                subject: "Offsite".to_string(),
+                snippet: String::new(),
+                last_message_at: Utc::now(),
+                message_count: 2,
+                sender_name: None,
+                sender_email: "alice@example.com".to_string(),
+                is_unread: false,
+                participants: Vec::new(),
+            })
+            .unwrap();
+        add_message(&store, "m2", "Thursday works", 5);
+        add_message(&store, "m1", "When should we meet?", 10);
+
+        let summarizer = CountingSummarizer {
+            calls: AtomicUsize::new(0),
+        };
+        let thread_id = ThreadId::new("t1");
+        let summary = || {
+            get_thread_summary(&store, &summarizer, &thread_id)
+                .unwrap()
+                .unwrap()
+        };
+
+        let expected = "Offsite: When should we meet? / Thursday works";
+        assert_eq!(summary(), expected);
+        assert_eq!(summary(), expected);
+        assert_eq!(summarizer.calls.load(Ordering::SeqCst), 1);
+
+        // A reply invalidates the cached summary
+        add_message(&store, "m3", "Booked", 1);
+        assert!(summary().ends_with("/ Booked"));
+        assert_eq!(summarizer.calls.load(Ordering::SeqCst), 2);
+
+        let missing = ThreadId::new("nope");
+        assert!(
+            get_thread_summary(&store, &summarizer, &missing)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -3,12 +3,16 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-use crate::models::{Message, Thread, ThreadId};
-use crate::storage::MailStore;
+use crate::models::{
+    AuthResults, EmailAddress, EventInvite, Label, Message, MessageId, OpenStatus, Thread,
+    ThreadId, ThreadNote,
+};
+use crate::storage::{MailStore, MessageMetadata, ThreadCursor};
 
 /// Summary information for displaying a thread in a list
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ThreadSummary {
     /// Thread ID
     pub id: ThreadId,
@@ -28,6 +32,19 @@ pub struct ThreadSummary {
     pub sender_email: String,
     /// Whether the thread has unread messages
     pub is_unread: bool,
+    /// Distinct senders in the order they first wrote, up to
+    /// [`MAX_THREAD_PARTICIPANTS`](crate::models::MAX_THREAD_PARTICIPANTS)
+    #[serde(default)]
+    pub participants: Vec<EmailAddress>,
+    /// Set when the row stands for one message rather than a whole thread
+    /// (conversation view off); the other fields then describe that message
+    #[serde(default)]
+    pub message_id: Option<MessageId>,
+    /// User labels on the thread, by name, for label chips; system labels
+    /// are left out. Filled in by the list queries (see
+    /// [`attach_thread_labels`]).
+    #[serde(default)]
+    pub labels: Vec<Label>,
 }
 
 impl From<Thread> for ThreadSummary {
@@ -42,6 +59,29 @@ impl From<Thread> for ThreadSummary {
             sender_name: thread.sender_name,
             sender_email: thread.sender_email,
             is_unread: thread.is_unread,
+            participants: thread.participants,
+            message_id: None,
+            labels: Vec::new(),
+        }
+    }
+}
+
+impl From<MessageMetadata> for ThreadSummary {
+    fn from(message: MessageMetadata) -> Self {
+        let is_unread = message.label_ids.iter().any(|l| l == "UNREAD");
+        Self {
+            id: message.thread_id,
+            account_id: message.account_id,
+            subject: message.subject,
+            snippet: message.body_preview,
+            last_message_at: message.received_at,
+            message_count: 1,
+            participants: vec![message.from.clone()],
+            sender_name: message.from.name,
+            sender_email: message.from.email,
+            is_unread,
+            message_id: Some(message.id),
+            labels: Vec::new(),
         }
     }
 }
@@ -51,28 +91,111 @@ impl From<Thread> for ThreadSummary {
 pub struct ThreadDetail {
     /// The thread metadata
     pub thread: Thread,
-    /// All messages in the thread, ordered chronologically
+    /// Messages in the thread, ordered chronologically, with repeat
+    /// deliveries of a message collapsed into its first copy
     pub messages: Vec<Message>,
+    /// Whether any message failed DMARC, meaning its sender may be spoofed
+    pub auth_warning: bool,
+    /// Calendar invites in the thread, latest revision of each event
+    pub invites: Vec<ThreadInvite>,
+    /// Opens of messages sent with a tracking beacon; untracked messages
+    /// have no entry
+    pub opens: HashMap<MessageId, OpenStatus>,
+    /// The user's private note on the thread
+    pub note: Option<ThreadNote>,
+    /// Gmail IDs of the copies collapsed into each shown message; messages
+    /// that arrived once have no entry. Thread actions still apply to every
+    /// copy, as they act on all of the thread's stored messages.
+    pub duplicates: HashMap<MessageId, Vec<MessageId>>,
+}
+
+/// A calendar invite with the message that carried it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadInvite {
+    /// Message to pass to `ActionHandler::rsvp`
+    pub message_id: MessageId,
+    pub invite: EventInvite,
+}
+
+/// A page of thread summaries with a cursor for the next page
+#[derive(Debug, Clone)]
+pub struct ThreadPage {
+    /// Threads in this page, newest first
+    pub threads: Vec<ThreadSummary>,
+    /// Cursor to pass back for the next page (None when this is the last page)
+    pub next_cursor: Option<ThreadCursor>,
+    /// Whether more threads exist after this page
+    pub has_more: bool,
+}
+
+impl ThreadPage {
+    /// Build a page from `limit + 1` fetched rows
+    ///
+    /// The extra row only signals that another page exists; it is dropped.
+    /// The kept threads' label chips are loaded in one lookup.
+    pub(crate) fn from_threads(
+        store: &dyn MailStore,
+        mut threads: Vec<Thread>,
+        limit: usize,
+    ) -> Result<Self> {
+        let has_more = threads.len() > limit;
+        threads.truncate(limit);
+
+        let next_cursor = if has_more {
+            threads.last().map(ThreadCursor::for_thread)
+        } else {
+            None
+        };
+
+        let mut threads: Vec<ThreadSummary> =
+            threads.into_iter().map(ThreadSummary::from).collect();
+        attach_thread_labels(store, &mut threads)?;
+
+        Ok(Self {
+            threads,
+            next_cursor,
+            has_more,
+        })
+    }
+}
+
+/// Fill in the user labels of a list of summaries, with one store lookup
+///
+/// For lists built straight from the store rather than through the query
+/// functions here, which already do this.
+pub fn attach_thread_labels(
+    store: &dyn MailStore,
+    summaries: &mut [ThreadSummary],
+) -> Result<()> {
+    let thread_ids: Vec<ThreadId> = summaries.iter().map(|s| s.id.clone()).collect();
+    let labels = store.list_user_labels_for_threads(&thread_ids)?;
+    for summary in summaries.iter_mut() {
+        // Message rows of one thread each get the thread's labels
+        summary.labels = labels.get(&summary.id).cloned().unwrap_or_default();
+    }
+    Ok(())
 }
 
-/// List threads with pagination
+/// List threads with keyset pagination
 ///
 /// Returns threads sorted by last_message_at descending (newest first).
+/// Pages are anchored to the previous page's last thread rather than an
+/// offset, so new mail arriving at the top does not shift later pages.
 ///
 /// # Arguments
 /// * `store` - The storage backend
+/// * `cursor` - Cursor from the previous page, or None for the first page
 /// * `limit` - Maximum number of threads to return
-/// * `offset` - Number of threads to skip
 pub fn list_threads(
     store: &dyn MailStore,
+    cursor: Option<&ThreadCursor>,
     limit: usize,
-    offset: usize,
-) -> Result<Vec<ThreadSummary>> {
-    let threads = store.list_threads(limit, offset)?;
-    Ok(threads.into_iter().map(ThreadSummary::from).collect())
+) -> Result<ThreadPage> {
+    let threads = store.list_threads_after(None, None, cursor, limit + 1)?;
+    ThreadPage::from_threads(store, threads, limit)
 }
 
-/// List threads by label with pagination
+/// List threads by label with keyset pagination
 ///
 /// Returns threads that have at least one message with the given label,
 /// sorted by last_message_at descending (newest first).
@@ -80,16 +203,38 @@ pub fn list_threads(
 /// # Arguments
 /// * `store` - The storage backend
 /// * `label` - The label ID to filter by (e.g., "INBOX", "SENT")
+/// * `cursor` - Cursor from the previous page, or None for the first page
 /// * `limit` - Maximum number of threads to return
-/// * `offset` - Number of threads to skip
 pub fn list_threads_by_label(
     store: &dyn MailStore,
     label: &str,
+    cursor: Option<&ThreadCursor>,
+    limit: usize,
+) -> Result<ThreadPage> {
+    let threads = store.list_threads_after(Some(label), None, cursor, limit + 1)?;
+    ThreadPage::from_threads(store, threads, limit)
+}
+
+/// List messages as rows of their own, for when conversation view is off
+///
+/// Each row is a one-message `ThreadSummary` carrying `message_id`, so
+/// opening it still opens the whole thread. Newest first.
+///
+/// # Arguments
+/// * `store` - The storage backend
+/// * `label` - Label ID to filter by, or None for all mail
+/// * `account_id` - Account to filter by, or None for all accounts
+/// * `limit` - Maximum number of rows to return
+pub fn list_message_rows(
+    store: &dyn MailStore,
+    label: Option<&str>,
+    account_id: Option<i64>,
     limit: usize,
-    offset: usize,
 ) -> Result<Vec<ThreadSummary>> {
-    let threads = store.list_threads_by_label(label, limit, offset)?;
-    Ok(threads.into_iter().map(ThreadSummary::from).collect())
+    let messages = store.list_messages_for_account(label, account_id, limit, 0)?;
+    let mut rows: Vec<ThreadSummary> = messages.into_iter().map(ThreadSummary::from).collect();
+    attach_thread_labels(store, &mut rows)?;
+    Ok(rows)
 }
 
 /// Get detailed thread information including all messages with bodies
@@ -110,9 +255,73 @@ pub fn get_thread_detail(
     };
 
     // Load full messages with bodies for rendering
-    let messages = store.list_messages_for_thread_with_bodies(thread_id)?;
+    let mut messages = store.list_messages_for_thread_with_bodies(thread_id)?;
+
+    // The same message delivered twice (a mailing list copy and a direct
+    // CC) is shown once, if its first copy is in this thread
+    let mut duplicates: HashMap<MessageId, Vec<MessageId>> = HashMap::new();
+    for (duplicate, canonical) in store.list_duplicate_messages(thread_id)? {
+        if messages.iter().any(|m| m.id == canonical) {
+            duplicates.entry(canonical).or_default().push(duplicate);
+        }
+    }
+    messages.retain(|m| !duplicates.values().flatten().any(|id| *id == m.id));
+
+    let auth_warning = messages
+        .iter()
+        .any(|m| m.auth_results.as_ref().is_some_and(AuthResults::failed_dmarc));
+
+    // Updates to an event reuse its UID with a higher sequence
+    let mut invites: Vec<ThreadInvite> = Vec::new();
+    for message in &messages {
+        let Some(invite) = &message.invite else {
+            continue;
+        };
+        let current = ThreadInvite {
+            message_id: message.id.clone(),
+            invite: invite.clone(),
+        };
+        match invites.iter_mut().find(|i| i.invite.uid == invite.uid) {
+            Some(existing) if existing.invite.sequence <= invite.sequence => *existing = current,
+            Some(_) => {}
+            None => invites.push(current),
+        }
+    }
+
+    let mut opens = HashMap::new();
+    for message in &messages {
+        if let Some(status) = store.get_open_status(&message.id)? {
+            opens.insert(message.id.clone(), status);
+        }
+    }
+
+    let note = store.get_note(thread_id)?;
+
+    Ok(Some(ThreadDetail {
+        thread,
+        messages,
+        auth_warning,
+        invites,
+        opens,
+        note,
+        duplicates,
+    }))
+}
 
-    Ok(Some(ThreadDetail { thread, messages }))
+/// Get every label on any message of a thread, in first-seen order
+///
+/// Gmail labels messages, not threads; a thread "has" a label when at least
+/// one of its messages does.
+pub fn get_thread_label_ids(store: &dyn MailStore, thread_id: &ThreadId) -> Result<Vec<String>> {
+    let mut label_ids: Vec<String> = Vec::new();
+    for message in store.list_messages_for_thread(thread_id)? {
+        for label_id in message.label_ids {
+            if !label_ids.contains(&label_id) {
+                label_ids.push(label_id);
+            }
+        }
+    }
+    Ok(label_ids)
 }
 
 #[cfg(test)]
@@ -161,24 +370,93 @@ mod tests {
     fn test_list_threads() {
         let store = setup_test_store();
 
-        let threads = list_threads(&store, 3, 0).unwrap();
-        assert_eq!(threads.len(), 3);
+        let page = list_threads(&store, None, 3).unwrap();
+        assert_eq!(page.threads.len(), 3);
+        assert!(page.has_more);
         // Should be sorted by last_message_at descending
-        assert_eq!(threads[0].id.0, "t0");
-        assert_eq!(threads[1].id.0, "t1");
-        assert_eq!(threads[2].id.0, "t2");
+        assert_eq!(page.threads[0].id.0, "t0");
+        assert_eq!(page.threads[1].id.0, "t1");
+        assert_eq!(page.threads[2].id.0, "t2");
     }
 
     #[test]
     fn test_list_threads_pagination() {
         let store = setup_test_store();
 
-        let page1 = list_threads(&store, 2, 0).unwrap();
-        let page2 = list_threads(&store, 2, 2).unwrap();
+        let page1 = list_threads(&store, None, 2).unwrap();
+        let page2 = list_threads(&store, page1.next_cursor.as_ref(), 2).unwrap();
+        let page3 = list_threads(&store, page2.next_cursor.as_ref(), 2).unwrap();
+
+        assert_eq!(page1.threads.len(), 2);
+        assert_eq!(page2.threads.len(), 2);
+        assert_eq!(page3.threads.len(), 1);
+        assert_ne!(page1.threads[0].id, page2.threads[0].id);
+        assert_eq!(page3.threads[0].id.0, "t4");
+        assert!(!page3.has_more);
+        assert!(page3.next_cursor.is_none());
+    }
+
+    #[test]
+    fn test_list_threads_cursor_stable_with_new_mail() {
+        let store = setup_test_store();
+
+        let page1 = list_threads(&store, None, 2).unwrap();
+
+        // New mail lands at the top between page loads
+        let thread = Thread::new(
+            ThreadId::new("t_new"),
+            1,
+            "Fresh".to_string(),
+            "Fresh snippet".to_string(),
+            Utc::now() + chrono::Duration::minutes(1),
+            1,
+            None,
+            "new@example.com".to_string(),
+            true,
+        );
+        store.upsert_thread(thread).unwrap();
+
+        let page2 = list_threads(&store, page1.next_cursor.as_ref(), 2).unwrap();
+        assert_eq!(page2.threads[0].id.0, "t2");
+        assert_eq!(page2.threads[1].id.0, "t3");
+    }
+
+    #[test]
+    fn test_list_threads_by_label_pagination() {
+        let store = setup_test_store();
+
+        for i in 0..5 {
+            store
+                .update_message_labels(
+                    &MessageId::new(format!("m{}_0", i)),
+                    vec!["INBOX".to_string()],
+                )
+                .unwrap();
+        }
+
+        let page1 = list_threads_by_label(&store, "INBOX", None, 3).unwrap();
+        let page2 = list_threads_by_label(&store, "INBOX", page1.next_cursor.as_ref(), 3).unwrap();
+
+        let ids: Vec<_> = page1
+            .threads
+            .iter()
+            .chain(page2.threads.iter())
+            .map(|t| t.id.0.as_str())
+            .collect();
+        assert_eq!(ids, vec!["t0", "t1", "t2", "t3", "t4"]);
+        assert!(!page2.has_more);
+    }
+
+    #[test]
+    fn test_thread_cursor_roundtrip() {
+        let store = setup_test_store();
+
+        let page = list_threads(&store, None, 2).unwrap();
+        let cursor = page.next_cursor.unwrap();
+        let decoded = ThreadCursor::decode(&cursor.encode()).unwrap();
+        assert_eq!(decoded, cursor);
 
-        assert_eq!(page1.len(), 2);
-        assert_eq!(page2.len(), 2);
-        assert_ne!(page1[0].id, page2[0].id);
+        assert!(ThreadCursor::decode("not a cursor").is_err());
     }
 
     #[test]
@@ -191,6 +469,222 @@ mod tests {
         let detail = detail.unwrap();
         assert_eq!(detail.thread.id.0, "t0");
         assert_eq!(detail.messages.len(), 2);
+        assert!(!detail.auth_warning);
+    }
+
+    #[test]
+    fn test_get_thread_detail_auth_warning() {
+        use crate::models::AuthVerdict;
+
+        let store = setup_test_store();
+        let spoofed = Message::builder(MessageId::new("m0_2"), ThreadId::new("t0"))
+            .from(EmailAddress::new("ceo@example.com"))
+            .auth_results(Some(AuthResults {
+                spf: Some(AuthVerdict::Pass),
+                dkim: None,
+                dmarc: Some(AuthVerdict::Fail),
+            }))
+            .build();
+        store.upsert_message(spoofed).unwrap();
+
+        let detail = get_thread_detail(&store, &ThreadId::new("t0"))
+            .unwrap()
+            .unwrap();
+        assert!(detail.auth_warning);
+    }
+
+    #[test]
+    fn test_get_thread_detail_invites() {
+        let store = setup_test_store();
+        let ics = |sequence: i64, summary: &str| {
+            format!(
+                "BEGIN:VCALENDAR\nMETHOD:REQUEST\nBEGIN:VEVENT\nUID:evt\nSEQUENCE:{}\n\
+                 SUMMARY:{}\nEND:VEVENT\nEND:VCALENDAR\n",
+                sequence, summary
+            )
+        };
+        for (id, sequence, summary, hours_ago) in [("i1", 1, "Moved", 3), ("i0", 0, "Old", 4)] {
+            let message = Message::builder(MessageId::new(id), ThreadId::new("t1"))
+                .received_at(Utc::now() - chrono::Duration::hours(hours_ago))
+                .invite(EventInvite::parse_ics(&ics(sequence, summary)))
+                .build();
+            store.upsert_message(message).unwrap();
+        }
+
+        let detail = get_thread_detail(&store, &ThreadId::new("t1"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(detail.invites.len(), 1);
+        assert_eq!(detail.invites[0].message_id.as_str(), "i1");
+        assert_eq!(detail.invites[0].invite.summary, "Moved");
+    }
+
+    #[test]
+    fn test_get_thread_detail_opens() {
+        let store = setup_test_store();
+        for id in ["s1", "s2"] {
+            let message = Message::builder(MessageId::new(id), ThreadId::new("t1")).build();
+            store.upsert_message(message).unwrap();
+        }
+        store
+            .save_tracking_token("tok", &MessageId::new("s1"))
+            .unwrap();
+        store.record_message_open("tok", Utc::now()).unwrap();
+
+        let detail = get_thread_detail(&store, &ThreadId::new("t1"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(detail.opens.len(), 1);
+        assert_eq!(detail.opens[&MessageId::new("s1")].open_count, 1);
+    }
+
+    #[test]
+    fn test_get_thread_detail_note() {
+        let store = setup_test_store();
+        let thread_id = ThreadId::new("t1");
+        let detail = get_thread_detail(&store, &thread_id).unwrap().unwrap();
+        assert!(detail.note.is_none());
+
+        store.set_note(&thread_id, "Waiting on legal").unwrap();
+        let detail = get_thread_detail(&store, &thread_id).unwrap().unwrap();
+        assert_eq!(detail.note.unwrap().markdown, "Waiting on legal");
+    }
+
+    #[test]
+    fn test_get_thread_detail_duplicates() {
+        let store = setup_test_store();
+        // A list copy and a direct CC of one message, plus a copy whose
+        // first delivery is in another thread
+        for (id, thread_id, hours_ago, rfc_message_id) in [
+            ("direct", "t1", 6, "abc@example.com"),
+            ("list", "t1", 5, "abc@example.com"),
+            ("moved", "t2", 9, "xyz@example.com"),
+            ("stray", "t1", 4, "xyz@example.com"),
+        ] {
+            let received_at = Utc::now() - chrono::Duration::hours(hours_ago);
+            let message = Message::builder(MessageId::new(id), ThreadId::new(thread_id))
+                .account_id(1)
+                .received_at(received_at)
+                .internal_date(received_at.timestamp_millis())
+                .rfc_message_id(Some(rfc_message_id.to_string()))
+                .build();
+            store.upsert_message(message).unwrap();
+        }
+
+        let detail = get_thread_detail(&store, &ThreadId::new("t1"))
+            .unwrap()
+            .unwrap();
+        let ids: Vec<&str> = detail.messages.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["direct", "stray", "m1_1", "m1_0"]);
+        assert_eq!(detail.duplicates.len(), 1);
+        assert_eq!(
+            detail.duplicates[&MessageId::new("direct")],
+            vec![MessageId::new("list")]
+        );
+
+        // Deleting the first copy makes the next one canonical
+        store.delete_message(&MessageId::new("direct")).unwrap();
+        let detail = get_thread_detail(&store, &ThreadId::new("t1"))
+            .unwrap()
+            .unwrap();
+        assert!(detail.messages.iter().any(|m| m.id.as_str() == "list"));
+        assert!(detail.duplicates.is_empty());
+    }
+
+    #[test]
+    fn test_get_thread_label_ids() {
+        let store = InMemoryMailStore::new();
+        store
+            .upsert_thread(Thread::new(
+                ThreadId::new("t1"),
+                1,
+                "Labels".to_string(),
+                String::new(),
+                Utc::now(),
+                2,
+                None,
+                "test@example.com".to_string(),
+                false,
+            ))
+            .unwrap();
+        for (id, labels) in [
+            ("m1", vec!["INBOX", "Label_1"]),
+            ("m2", vec!["SENT", "INBOX"]),
+        ] {
+            let msg = crate::models::Message::builder(MessageId::new(id), ThreadId::new("t1"))
+                .from(EmailAddress::new("test@example.com"))
+                .label_ids(labels.into_iter().map(String::from).collect())
+                .build();
+            store.upsert_message(msg).unwrap();
+        }
+
+        let label_ids = get_thread_label_ids(&store, &ThreadId::new("t1")).unwrap();
+        assert_eq!(label_ids, vec!["INBOX", "Label_1", "SENT"]);
+        let missing = get_thread_label_ids(&store, &ThreadId::new("missing")).unwrap();
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn test_list_message_rows() {
+        let store = setup_test_store();
+        let inbox = crate::models::Message::builder(MessageId::new("m1_new"), ThreadId::new("t1"))
+            .from(EmailAddress::with_name("New Sender", "new@example.com"))
+            .subject("Re: Thread 1")
+            .label_ids(vec!["INBOX".to_string(), "UNREAD".to_string()])
+            .received_at(Utc::now() + chrono::Duration::minutes(1))
+            .build();
+        store.upsert_message(inbox).unwrap();
+
+        // Every message is its own row, newest first
+        let rows = list_message_rows(&store, None, None, 3).unwrap();
+        let ids: Vec<_> = rows
+            .iter()
+            .map(|r| r.message_id.as_ref().unwrap().as_str())
+            .collect();
+        assert_eq!(ids, vec!["m1_new", "m0_0", "m0_1"]);
+        assert_eq!(rows[1].id, rows[2].id);
+
+        let rows = list_message_rows(&store, Some("INBOX"), None, 10).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].id, ThreadId::new("t1"));
+        assert_eq!(rows[0].message_count, 1);
+        assert_eq!(rows[0].sender_name.as_deref(), Some("New Sender"));
+        assert!(rows[0].is_unread);
+
+        let other_account = list_message_rows(&store, None, Some(2), 10).unwrap();
+        assert!(other_account.is_empty());
+    }
+
+    #[test]
+    fn test_list_threads_label_chips() {
+        let store = setup_test_store();
+        store
+            .replace_labels(
+                1,
+                vec![Label::new("Label_2", "work"), Label::new("Label_1", "receipts")],
+            )
+            .unwrap();
+        let msg = crate::models::Message::builder(MessageId::new("m1_2"), ThreadId::new("t1"))
+            .from(EmailAddress::new("test@example.com"))
+            .label_ids(vec![
+                "INBOX".to_string(),
+                "Label_2".to_string(),
+                "Label_1".to_string(),
+            ])
+            .build();
+        store.upsert_message(msg).unwrap();
+
+        let page = list_threads(&store, None, 10).unwrap();
+        let chips = |id: &str| -> Vec<String> {
+            let thread = page.threads.iter().find(|t| t.id.as_str() == id).unwrap();
+            thread.labels.iter().map(|l| l.name.clone()).collect()
+        };
+        // System labels are left out
+        assert_eq!(chips("t1"), vec!["receipts", "work"]);
+        assert!(chips("t0").is_empty());
+
+        let page = list_threads_by_label(&store, "INBOX", None, 10).unwrap();
+        assert_eq!(page.threads[0].labels.len(), 2);
     }
 
     #[test]
@@ -0,0 +1,204 @@
+//! Trash and Spam retention
+//!
+//! Gmail permanently deletes mail that has been in Trash or Spam for 30
+//! days. Those deletions reach the store with the next incremental sync,
+//! but a store that isn't syncing (a paused account, a laptop left offline)
+//! would keep them indefinitely. [`purge_expired_trash`] mirrors the purge
+//! locally so the store stays close to what Gmail has between syncs.
+//!
+//! The store doesn't record when a message was trashed, so age is counted
+//! from when it was received.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use log::{info, warn};
+
+use crate::models::{LabelId, MessageId, ThreadId};
+use crate::search::SearchBackend;
+use crate::storage::{MailStore, MessageMetadata, summarize_thread};
+
+/// Days Gmail keeps messages in Trash and Spam
+pub const TRASH_RETENTION_DAYS: i64 = 30;
+
+/// Messages read from the store per page while looking for expired ones
+const PAGE_SIZE: usize = 500;
+
+/// Whether a message is only in Trash or Spam
+///
+/// Read state, importance and inbox categories don't place a message
+/// anywhere, so they're ignored; any other label keeps it.
+fn is_trash_only(label_ids: &[String]) -> bool {
+    let mut in_trash = false;
+    for label in label_ids {
+        match label.as_str() {
+            LabelId::TRASH | LabelId::SPAM => in_trash = true,
+            LabelId::UNREAD | LabelId::IMPORTANT => {}
+            label if label.starts_with("CATEGORY_") => {}
+            _ => return false,
+        }
+    }
+    in_trash
+}
+
+/// Delete messages that have been only in Trash or Spam for longer than
+/// Gmail keeps them
+///
+/// Covers one account, or every account when `account_id` is None. Threads
+/// that lose messages are recomputed, and the messages leave the search
+/// index too when one is given. Returns how many messages were deleted.
+pub fn purge_expired_trash(
+    store: &dyn MailStore,
+    search: Option<&dyn SearchBackend>,
+    account_id: Option<i64>,
+) -> Result<usize> {
+    purge_expired_trash_at(store, search, account_id, Utc::now())
+}
+
+fn purge_expired_trash_at(
+    store: &dyn MailStore,
+    search: Option<&dyn SearchBackend>,
+    account_id: Option<i64>,
+    now: DateTime<Utc>,
+) -> Result<usize> {
+    let cutoff = now - Duration::days(TRASH_RETENTION_DAYS);
+
+    // Collected before deleting so paging isn't thrown off
+    let mut expired: HashMap<MessageId, (ThreadId, i64)> = HashMap::new();
+    for label in [LabelId::TRASH, LabelId::SPAM] {
+        let mut offset = 0;
+        loop {
+            let page =
+                store.list_messages_for_account(Some(label), account_id, PAGE_SIZE, offset)?;
+            let len = page.len();
+            expired.extend(
+                page.into_iter()
+                    .filter(|m| m.received_at < cutoff && is_trash_only(&m.label_ids))
+                    .map(|m| (m.id, (m.thread_id, m.account_id))),
+            );
+            if len < PAGE_SIZE {
+                break;
+            }
+            offset += len;
+        }
+    }
+    if expired.is_empty() {
+        return Ok(0);
+    }
+
+    let mut threads: HashMap<ThreadId, i64> = HashMap::new();
+    for (message_id, (thread_id, account_id)) in &expired {
+        store.delete_message(message_id)?;
+        if let Some(search) = search
+            && let Err(e) = search.delete_message(message_id)
+        {
+            warn!(
+                "Failed to remove message {} from index: {}",
+                message_id.as_str(),
+                e
+            );
+        }
+        threads.insert(thread_id.clone(), *account_id);
+    }
+
+    for (thread_id, account_id) in threads {
+        let messages = store.list_messages_for_thread(&thread_id)?;
+        let refs: Vec<&MessageMetadata> = messages.iter().collect();
+        match summarize_thread(&thread_id, account_id, &refs) {
+            Some(thread) => store.upsert_thread(thread)?,
+            None => {
+                if let Some(search) = search
+                    && let Err(e) = search.delete_thread(&thread_id)
+                {
+                    warn!(
+                        "Failed to remove thread {} from index: {}",
+                        thread_id.as_str(),
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    if let Some(search) = search
+        && let Err(e) = search.commit()
+    {
+        warn!("Failed to commit search index: {}", e);
+    }
+
+    info!(
+        "Purged {} messages in Trash or Spam for over {} days",
+        expired.len(),
+        TRASH_RETENTION_DAYS
+    );
+    Ok(expired.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrity::check_thread_invariants;
+    use crate::models::{EmailAddress, Message};
+    use crate::storage::InMemoryMailStore;
+
+    #[test]
+    fn test_is_trash_only() {
+        let labels = |list: &[&str]| list.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        assert!(is_trash_only(&labels(&["TRASH"])));
+        assert!(is_trash_only(&labels(&[
+            "SPAM",
+            "UNREAD",
+            "CATEGORY_PROMOTIONS"
+        ])));
+        assert!(!is_trash_only(&labels(&["TRASH", "Label_7"])));
+        assert!(!is_trash_only(&labels(&["TRASH", "STARRED"])));
+        assert!(!is_trash_only(&labels(&["UNREAD"])));
+        assert!(!is_trash_only(&[]));
+    }
+
+    #[test]
+    fn test_purge_expired_trash() {
+        let store = InMemoryMailStore::new();
+        let now = Utc::now();
+        let stored = |id: &str, thread_id: &str, days_old: i64, labels: &[&str]| {
+            let message = Message::builder(MessageId::new(id), ThreadId::new(thread_id))
+                .account_id(1)
+                .from(EmailAddress::new("sender@example.com"))
+                .subject("Offer")
+                .received_at(now - Duration::days(days_old))
+                .label_ids(labels.iter().map(|l| l.to_string()).collect())
+                .build();
+            let messages = store.list_messages_for_thread(&message.thread_id).unwrap();
+            let mut refs: Vec<&MessageMetadata> = messages.iter().collect();
+            let metadata = MessageMetadata::from(&message);
+            refs.push(&metadata);
+            let thread = summarize_thread(&message.thread_id, 1, &refs).unwrap();
+            store.upsert_thread(thread).unwrap();
+            store.upsert_message(message).unwrap();
+        };
+
+        // Expired trash and spam, one sharing a thread with inbox mail
+        stored("m1", "t1", 45, &["TRASH"]);
+        stored("m2", "t2", 40, &["SPAM", "UNREAD"]);
+        stored("m3", "t2", 1, &["INBOX"]);
+        // Too recent, or also labelled elsewhere
+        stored("m4", "t3", 10, &["TRASH"]);
+        stored("m5", "t4", 60, &["TRASH", "Label_1"]);
+
+        assert_eq!(purge_expired_trash_at(&store, None, None, now).unwrap(), 2);
+        assert!(!store.has_message(&MessageId::new("m1")).unwrap());
+        assert!(!store.has_message(&MessageId::new("m2")).unwrap());
+        assert!(store.get_thread(&ThreadId::new("t1")).unwrap().is_none());
+        let thread = store.get_thread(&ThreadId::new("t2")).unwrap().unwrap();
+        assert_eq!(thread.message_count, 1);
+        assert!(!thread.is_unread);
+        for id in ["m3", "m4", "m5"] {
+            assert!(store.has_message(&MessageId::new(id)).unwrap(), "{}", id);
+        }
+        assert!(check_thread_invariants(&store).unwrap().is_empty());
+
+        // Other accounts' mail is left alone
+        assert_eq!(purge_expired_trash_at(&store, None, Some(2), now).unwrap(), 0);
+    }
+}
@@ -0,0 +1,215 @@
+//! Rules engine for incoming mail
+//!
+//! [`Rule`]s are persisted in the mail store and evaluated by
+//! `process_pending_batch` as each new message is processed. Label actions
+//! (archive, mark read, add label) are applied to the message before it is
+//! stored; the returned [`RuleMatch`]es let the caller mirror those changes
+//! to Gmail with [`push_rule_changes`] and raise notifications.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::gmail::GmailClient;
+use crate::models::{Message, MessageId, Rule, RuleAction, ThreadId};
+
+/// Outcome of the rules that matched one message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleMatch {
+    /// Matched message
+    pub message_id: MessageId,
+    /// Thread of the matched message
+    pub thread_id: ThreadId,
+    /// IDs of the rules that matched, in evaluation order
+    pub rule_ids: Vec<i64>,
+    /// Labels the rules added
+    pub added_labels: Vec<String>,
+    /// Labels the rules removed
+    pub removed_labels: Vec<String>,
+    /// Whether any matching rule asked for a notification
+    pub notify: bool,
+}
+
+impl RuleMatch {
+    /// Whether the rules changed the message's labels
+    pub fn changes_labels(&self) -> bool {
+        !self.added_labels.is_empty() || !self.removed_labels.is_empty()
+    }
+}
+
+/// Evaluate rules against a message, applying their label actions to it
+///
+/// Rules run in order, each seeing the labels left by the previous ones, so
+/// one rule can match a label another added. Returns None when no rule
+/// matched.
+pub fn apply_rules(rules: &[Rule], message: &mut Message) -> Option<RuleMatch> {
+    let original = message.label_ids.clone();
+    let mut rule_ids = Vec::new();
+    let mut notify = false;
+
+    for rule in rules {
+        if !rule.matches(message) {
+            continue;
+        }
+        rule_ids.push(rule.id);
+        for action in &rule.actions {
+            match action {
+                RuleAction::Notify => notify = true,
+                RuleAction::Archive => message.label_ids.retain(|l| l != "INBOX"),
+                RuleAction::MarkRead => message.label_ids.retain(|l| l != "UNREAD"),
+                RuleAction::AddLabel(label) => {
+                    if !message.label_ids.contains(label) {
+                        message.label_ids.push(label.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    if rule_ids.is_empty() {
+        return None;
+    }
+
+    Some(RuleMatch {
+        message_id: message.id.clone(),
+        thread_id: message.thread_id.clone(),
+        rule_ids,
+        added_labels: message
+            .label_ids
+            .iter()
+            .filter(|l| !original.contains(l))
+            .cloned()
+            .collect(),
+        removed_labels: original
+            .into_iter()
+            .filter(|l| !message.label_ids.contains(l))
+            .collect(),
+        notify,
+    })
+}
+
+/// Apply the label changes of rule matches on the Gmail server
+///
+/// Messages with identical changes are modified in one batch request.
+/// Returns the number of messages modified.
+pub fn push_rule_changes(gmail: &GmailClient, matches: &[RuleMatch]) -> Result<usize> {
+    let mut batches: HashMap<(&[String], &[String]), Vec<&str>> = HashMap::new();
+    for rule_match in matches.iter().filter(|m| m.changes_labels()) {
+        batches
+            .entry((
+                rule_match.added_labels.as_slice(),
+                rule_match.removed_labels.as_slice(),
+            ))
+            .or_default()
+            .push(rule_match.message_id.as_str());
+    }
+
+    let mut modified = 0;
+    for ((added, removed), message_ids) in batches {
+        let added: Vec<&str> = added.iter().map(String::as_str).collect();
+        let removed: Vec<&str> = removed.iter().map(String::as_str).collect();
+        gmail.batch_modify_messages(&message_ids, &added, &removed)?;
+        modified += message_ids.len();
+    }
+    Ok(modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EmailAddress, RulePredicate};
+
+    fn message(from: &str, subject: &str) -> Message {
+        Message::builder(MessageId::new("m1"), ThreadId::new("t1"))
+            .from(EmailAddress::new(from))
+            .subject(subject)
+            .label_ids(vec!["INBOX".to_string(), "UNREAD".to_string()])
+            .build()
+    }
+
+    #[test]
+    fn test_predicates() {
+        let msg = message("Alerts@Billing.Example.com", "Your invoice is ready");
+
+        assert!(RulePredicate::Sender("alerts@billing.example.com".into()).matches(&msg));
+        assert!(RulePredicate::Domain("example.com".into()).matches(&msg));
+        assert!(RulePredicate::Domain("@billing.example.com".into()).matches(&msg));
+        assert!(!RulePredicate::Domain("ample.com".into()).matches(&msg));
+        assert!(RulePredicate::Label("UNREAD".into()).matches(&msg));
+        assert!(RulePredicate::Keyword("INVOICE".into()).matches(&msg));
+        assert!(!RulePredicate::Keyword("receipt".into()).matches(&msg));
+        assert!(RulePredicate::Subject("invoice is".into()).matches(&msg));
+        assert!(!RulePredicate::HasAttachment.matches(&msg));
+
+        let mut msg = msg;
+        msg.cc = vec![EmailAddress::new("Team@Example.com")];
+        msg.has_attachments = true;
+        assert!(RulePredicate::Recipient("team@example.com".into()).matches(&msg));
+        assert!(!RulePredicate::Recipient("other@example.com".into()).matches(&msg));
+        assert!(RulePredicate::HasAttachment.matches(&msg));
+    }
+
+    #[test]
+    fn test_apply_rules() {
+        let rules = vec![
+            Rule {
+                id: 1,
+                ..Rule::new("Billing")
+                    .with_predicate(RulePredicate::Domain("example.com".into()))
+                    .with_action(RuleAction::AddLabel("Label_billing".into()))
+            },
+            // Sees the label added by the first rule
+            Rule {
+                id: 2,
+                ..Rule::new("File billing")
+                    .with_predicate(RulePredicate::Label("Label_billing".into()))
+                    .with_action(RuleAction::Archive)
+                    .with_action(RuleAction::MarkRead)
+            },
+            Rule {
+                id: 3,
+                ..Rule::vip("boss@example.com")
+            },
+            Rule {
+                id: 4,
+                ..Rule::new("Disabled")
+                    .with_predicate(RulePredicate::Keyword("invoice".into()))
+                    .with_action(RuleAction::Notify)
+                    .with_enabled(false)
+            },
+        ];
+
+        let mut msg = message("billing@example.com", "Invoice");
+        let outcome = apply_rules(&rules, &mut msg).unwrap();
+        assert_eq!(outcome.rule_ids, vec![1, 2]);
+        assert_eq!(outcome.added_labels, vec!["Label_billing"]);
+        assert_eq!(outcome.removed_labels, vec!["INBOX", "UNREAD"]);
+        assert!(!outcome.notify);
+        assert_eq!(msg.label_ids, vec!["Label_billing"]);
+
+        let mut msg = message("boss@example.com", "Hi");
+        let outcome = apply_rules(&rules[2..], &mut msg).unwrap();
+        assert!(outcome.notify);
+        assert!(!outcome.changes_labels());
+
+        let mut msg = message("someone@elsewhere.org", "Invoice");
+        assert!(apply_rules(&rules, &mut msg).is_none());
+    }
+
+    #[test]
+    fn test_account_scope() {
+        let rule = Rule::vip("boss@example.com").for_account(2);
+        let mut msg = message("boss@example.com", "Hi");
+        msg.account_id = 1;
+        assert!(!rule.matches(&msg));
+        msg.account_id = 2;
+        assert!(rule.matches(&msg));
+
+        // A rule without predicates never matches
+        assert!(
+            !Rule::new("Empty")
+                .with_action(RuleAction::Archive)
+                .matches(&msg)
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Common interface of the full-text search backends
+//!
+//! [`SearchIndex`] (Tantivy) is the default backend. With the `fts5` feature,
+//! `FtsSearchIndex` offers the same operations on SQLite FTS5 for platforms
+//! where Tantivy's memory and disk footprint is too large.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+
+use super::index::SearchIndex;
+use super::{ParsedQuery, SearchPage, SearchResult};
+use crate::models::{Message, MessageId, Thread, ThreadId};
+use crate::storage::{MailStore, ThreadCursor};
+
+/// Threads read from the store per batch by the default `rebuild`
+const REBUILD_BATCH_SIZE: usize = 200;
+
+/// Operations shared by the search backends
+///
+/// Writes are only guaranteed to be visible to searches after `commit`.
+pub trait SearchBackend: Send + Sync {
+    /// Index a message, replacing any existing document for the same ID
+    fn index_message(&self, message: &Message, thread: &Thread) -> Result<()>;
+
+    /// Index a thread's note, replacing any previous one; `None` removes it
+    ///
+    /// Backends that don't index notes ignore them.
+    fn index_note(&self, _thread: &Thread, _note: Option<&str>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Remove all documents for a thread
+    fn delete_thread(&self, thread_id: &ThreadId) -> Result<()>;
+
+    /// Remove the document for a single message
+    fn delete_message(&self, message_id: &MessageId) -> Result<()>;
+
+    /// Replace the labels of indexed messages in a thread
+    ///
+    /// Returns the number of documents updated.
+    fn update_labels(
+        &self,
+        thread_id: &ThreadId,
+        labels: &HashMap<MessageId, Vec<String>>,
+    ) -> Result<usize>;
+
+    /// Make pending writes visible to searches
+    fn commit(&self) -> Result<()>;
+
+    /// Remove every document
+    fn clear(&self) -> Result<()>;
+
+    /// IDs of all indexed messages
+    fn indexed_message_ids(&self) -> Result<HashSet<MessageId>>;
+
+    /// Search for threads, best match first, deduplicated by thread
+    fn search(
+        &self,
+        query: &ParsedQuery,
+        limit: usize,
+        store: &dyn MailStore,
+        account_id: Option<i64>,
+    ) -> Result<Vec<SearchResult>>;
+
+    /// Fetch one page of distinct matching threads, plus the total hit count
+    fn search_page(
+        &self,
+        query: &ParsedQuery,
+        offset: usize,
+        limit: usize,
+        store: &dyn MailStore,
+        account_id: Option<i64>,
+    ) -> Result<SearchPage>;
+
+    /// Count distinct threads matching the query
+    fn count_threads(&self, query: &ParsedQuery, account_id: Option<i64>) -> Result<usize>;
+
+    /// Clear the index and re-index every message in the store
+    ///
+    /// Returns the number of messages indexed.
+    fn rebuild(&self, store: &dyn MailStore) -> Result<usize> {
+        self.clear()?;
+
+        let mut count = 0;
+        let mut cursor: Option<ThreadCursor> = None;
+        loop {
+            let batch =
+                store.list_threads_after(None, None, cursor.as_ref(), REBUILD_BATCH_SIZE)?;
+            for thread in &batch {
+                for message in store.list_messages_for_thread_with_bodies(&thread.id)? {
+                    self.index_message(&message, thread)?;
+                    count += 1;
+                }
+                if let Some(note) = store.get_note(&thread.id)? {
+                    self.index_note(thread, Some(&note.markdown))?;
+                }
+            }
+            self.commit()?;
+
+            if batch.len() < REBUILD_BATCH_SIZE {
+                break;
+            }
+            cursor = batch.last().map(ThreadCursor::for_thread);
+        }
+
+        Ok(count)
+    }
+}
+
+impl SearchBackend for SearchIndex {
+    fn index_message(&self, message: &Message, thread: &Thread) -> Result<()> {
+        SearchIndex::index_message(self, message, thread)
+    }
+
+    fn index_note(&self, thread: &Thread, note: Option<&str>) -> Result<()> {
+        SearchIndex::index_note(self, thread, note)
+    }
+
+    fn delete_thread(&self, thread_id: &ThreadId) -> Result<()> {
+        SearchIndex::delete_thread(self, thread_id)
+    }
+
+    fn delete_message(&self, message_id: &MessageId) -> Result<()> {
+        SearchIndex::delete_message(self, message_id)
+    }
+
+    fn update_labels(
+        &self,
+        thread_id: &ThreadId,
+        labels: &HashMap<MessageId, Vec<String>>,
+    ) -> Result<usize> {
+        SearchIndex::update_labels(self, thread_id, labels)
+    }
+
+    fn commit(&self) -> Result<()> {
+        SearchIndex::commit(self)
+    }
+
+    fn clear(&self) -> Result<()> {
+        SearchIndex::clear(self)
+    }
+
+    fn indexed_message_ids(&self) -> Result<HashSet<MessageId>> {
+        SearchIndex::indexed_message_ids(self)
+    }
+
+    fn search(
+        &self,
+        query: &ParsedQuery,
+        limit: usize,
+        store: &dyn MailStore,
+        account_id: Option<i64>,
+    ) -> Result<Vec<SearchResult>> {
+        SearchIndex::search(self, query, limit, store, account_id)
+    }
+
+    fn search_page(
+        &self,
+        query: &ParsedQuery,
+        offset: usize,
+        limit: usize,
+        store: &dyn MailStore,
+        account_id: Option<i64>,
+    ) -> Result<SearchPage> {
+        SearchIndex::search_page(self, query, offset, limit, store, account_id)
+    }
+
+    fn count_threads(&self, query: &ParsedQuery, account_id: Option<i64>) -> Result<usize> {
+        SearchIndex::count_threads(self, query, account_id)
+    }
+
+    fn rebuild(&self, store: &dyn MailStore) -> Result<usize> {
+        SearchIndex::rebuild(self, store)
+    }
+}
@@ -0,0 +1,874 @@
+//! SQLite FTS5 search backend
+//!
+//! A lighter alternative to the Tantivy [`SearchIndex`](super::SearchIndex)
+//! for platforms where its memory and disk footprint is too large, such as
+//! iOS background extensions. Each message is a row in `fts_messages`
+//! (filters and flags) sharing its rowid with a row in the `messages_fts`
+//! virtual table (tokenized text).
+//!
+//! Queries follow the Tantivy backend's semantics: free-text terms match
+//! whole words in the subject, body, snippet, and sender, ranked by BM25
+//! with the configured field boosts and recency decay.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::types::Value;
+use rusqlite::{Connection, params};
+use rusqlite_migration::{M, Migrations};
+
+use super::backend::SearchBackend;
+use super::index::with_account_ids;
+use super::{FieldHighlight, ParsedQuery, SearchConfig, SearchPage, SearchResult, find_term_spans};
+use crate::models::{Message, MessageId, Thread, ThreadId};
+use crate::storage::MailStore;
+
+/// Columns searched by free-text terms
+const TEXT_COLUMNS: &str = "{subject body_text snippet from_name from_email}";
+
+/// Minimum number of threads ranked by BM25 before the recency rerank
+const MIN_RANK_WINDOW: usize = 200;
+
+fn migrations() -> Migrations<'static> {
+    Migrations::new(vec![M::up(
+        r#"
+        CREATE TABLE fts_messages (
+            id INTEGER PRIMARY KEY,
+            message_id TEXT NOT NULL UNIQUE,
+            thread_id TEXT NOT NULL,
+            account_id INTEGER NOT NULL,
+            labels TEXT NOT NULL,
+            is_unread INTEGER NOT NULL,
+            is_starred INTEGER NOT NULL,
+            has_attachment INTEGER NOT NULL,
+            received_at_ms INTEGER NOT NULL,
+            size_bytes INTEGER NOT NULL
+        );
+        CREATE INDEX idx_fts_messages_thread ON fts_messages(thread_id);
+
+        CREATE VIRTUAL TABLE messages_fts USING fts5(
+            subject, body_text, snippet, from_name, from_email, to_addrs, cc_addrs, filename,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+        "#,
+    )])
+}
+
+/// Full-text search index stored in a SQLite database using FTS5
+///
+/// Writes are batched in a transaction that `commit` ends, so indexing many
+/// messages costs one sync rather than one per message.
+pub struct FtsSearchIndex {
+    conn: Mutex<Connection>,
+    needs_rebuild: AtomicBool,
+    config: SearchConfig,
+}
+
+impl std::fmt::Debug for FtsSearchIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FtsSearchIndex").finish_non_exhaustive()
+    }
+}
+
+impl FtsSearchIndex {
+    /// Open or create an index database at the given path
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create index directory")?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open search database at {:?}", path))?;
+        conn.execute_batch(
+            r#"
+            PRAGMA journal_mode = WAL;
+            PRAGMA synchronous = NORMAL;
+            "#,
+        )?;
+        Self::from_connection(conn)
+    }
+
+    /// Create an in-memory index (for testing)
+    pub fn in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(mut conn: Connection) -> Result<Self> {
+        let user_version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        migrations()
+            .to_latest(&mut conn)
+            .context("Failed to run search index migrations")?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            needs_rebuild: AtomicBool::new(user_version == 0),
+            config: SearchConfig::default(),
+        })
+    }
+
+    /// Use the given relevance weights for ranking
+    pub fn with_config(mut self, config: SearchConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Relevance weights used for ranking
+    pub fn config(&self) -> &SearchConfig {
+        &self.config
+    }
+
+    /// Whether the index was created empty and should be rebuilt from the store
+    pub fn needs_rebuild(&self) -> bool {
+        self.needs_rebuild.load(Ordering::Relaxed)
+    }
+
+    /// Lock the connection, opening a write transaction if none is pending
+    fn writer(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        let conn = self.conn.lock().unwrap();
+        if conn.is_autocommit() {
+            conn.execute_batch("BEGIN")?;
+        }
+        Ok(conn)
+    }
+
+    /// Index a message (upsert semantics)
+    pub fn index_message(&self, message: &Message, thread: &Thread) -> Result<()> {
+        let conn = self.writer()?;
+        delete_rows(&conn, "message_id = ?", message.id.as_str())?;
+
+        let labels = &message.label_ids;
+        conn.execute(
+            "INSERT INTO fts_messages (message_id, thread_id, account_id, labels, is_unread,
+                                       is_starred, has_attachment, received_at_ms, size_bytes)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                message.id.as_str(),
+                thread.id.as_str(),
+                thread.account_id,
+                encode_labels(labels),
+                labels.iter().any(|l| l == "UNREAD"),
+                labels.iter().any(|l| l == "STARRED"),
+                message.has_attachments,
+                message.received_at.timestamp_millis(),
+                message.size_bytes,
+            ],
+        )?;
+
+        let join = |addresses: &[crate::models::EmailAddress]| {
+            addresses
+                .iter()
+                .map(|a| a.display())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        conn.execute(
+            "INSERT INTO messages_fts (rowid, subject, body_text, snippet, from_name, from_email,
+                                       to_addrs, cc_addrs, filename)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                conn.last_insert_rowid(),
+                message.subject,
+                message.body_text.as_deref().unwrap_or_default(),
+                message.body_preview,
+                message.from.name.as_deref().unwrap_or_default(),
+                message.from.email,
+                join(&message.to),
+                join(&message.cc),
+                message.attachment_names.join("\n"),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Delete all documents for a thread
+    pub fn delete_thread(&self, thread_id: &ThreadId) -> Result<()> {
+        let conn = self.writer()?;
+        delete_rows(&conn, "thread_id = ?", thread_id.as_str())
+    }
+
+    /// Delete the document for a single message
+    pub fn delete_message(&self, message_id: &MessageId) -> Result<()> {
+        let conn = self.writer()?;
+        delete_rows(&conn, "message_id = ?", message_id.as_str())
+    }
+
+    /// Replace the labels of indexed messages in a thread
+    ///
+    /// Messages not in the index are skipped. Returns the number updated.
+    pub fn update_labels(
+        &self,
+        thread_id: &ThreadId,
+        labels: &HashMap<MessageId, Vec<String>>,
+    ) -> Result<usize> {
+        let conn = self.writer()?;
+        let mut stmt = conn.prepare_cached(
+            "UPDATE fts_messages SET labels = ?, is_unread = ?, is_starred = ?
+             WHERE message_id = ? AND thread_id = ?",
+        )?;
+
+        let mut updated = 0;
+        for (message_id, message_labels) in labels {
+            updated += stmt.execute(params![
+                encode_labels(message_labels),
+                message_labels.iter().any(|l| l == "UNREAD"),
+                message_labels.iter().any(|l| l == "STARRED"),
+                message_id.as_str(),
+                thread_id.as_str(),
+            ])?;
+        }
+        Ok(updated)
+    }
+
+    /// Commit pending writes
+    pub fn commit(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        if !conn.is_autocommit() {
+            conn.execute_batch("COMMIT")?;
+        }
+        Ok(())
+    }
+
+    /// Clear all documents from the index
+    pub fn clear(&self) -> Result<()> {
+        {
+            let conn = self.writer()?;
+            conn.execute_batch("DELETE FROM messages_fts; DELETE FROM fts_messages;")?;
+        }
+        self.commit()
+    }
+
+    /// IDs of all indexed messages
+    pub fn indexed_message_ids(&self) -> Result<HashSet<MessageId>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT message_id FROM fts_messages")?;
+        let ids = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .map(|id| id.map(MessageId::new))
+            .collect::<Result<HashSet<_>, _>>()?;
+        Ok(ids)
+    }
+
+    /// Search for threads matching the query
+    ///
+    /// Returns deduplicated results by thread_id, sorted by relevance score.
+    /// If `account_id` is Some, only returns results from that account.
+    pub fn search(
+        &self,
+        query: &ParsedQuery,
+        limit: usize,
+        store: &dyn MailStore,
+        account_id: Option<i64>,
+    ) -> Result<Vec<SearchResult>> {
+        let query = &*with_account_ids(query, store)?;
+        let hits = self.ranked_threads(query, account_id, limit)?;
+        Ok(self.load_results(hits, query, store))
+    }
+
+    /// Fetch one page of distinct matching threads, plus the total hit count
+    ///
+    /// Unlike the Tantivy backend, the total is always exact.
+    pub fn search_page(
+        &self,
+        query: &ParsedQuery,
+        offset: usize,
+        limit: usize,
+        store: &dyn MailStore,
+        account_id: Option<i64>,
+    ) -> Result<SearchPage> {
+        let query = &*with_account_ids(query, store)?;
+        let total = self.count_threads(query, account_id)?;
+
+        let wanted = offset + limit;
+        let hits = self.ranked_threads(query, account_id, wanted + 1)?;
+        let next_offset = (hits.len() > wanted).then_some(wanted);
+        let page_hits = hits.into_iter().skip(offset).take(limit).collect();
+
+        Ok(SearchPage {
+            results: self.load_results(page_hits, query, store),
+            total,
+            total_is_estimate: false,
+            next_offset,
+        })
+    }
+
+    /// Count distinct threads matching the query
+    ///
+    /// If `account_id` is Some, only counts threads from that account.
+    pub fn count_threads(&self, query: &ParsedQuery, account_id: Option<i64>) -> Result<usize> {
+        let mut params = Vec::new();
+        let condition = scoped_condition(query, account_id, false, &mut params);
+        let sql = format!(
+            "SELECT COUNT(DISTINCT m.thread_id) FROM fts_messages m WHERE {}",
+            condition
+        );
+
+        let conn = self.conn.lock().unwrap();
+        let count: i64 =
+            conn.query_row(&sql, rusqlite::params_from_iter(params), |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    /// Rebuild entire index from storage
+    ///
+    /// Returns the number of messages indexed.
+    pub fn rebuild(&self, store: &dyn MailStore) -> Result<usize> {
+        let count = SearchBackend::rebuild(self, store)?;
+        self.needs_rebuild.store(false, Ordering::Relaxed);
+        Ok(count)
+    }
+
+    /// The best `limit` matching threads with their scores, best first
+    ///
+    /// With free-text terms, threads are scored by their best BM25 match,
+    /// then the top candidates are reranked with the recency multiplier of
+    /// their latest message. Filter-only queries are ordered by recency.
+    fn ranked_threads(
+        &self,
+        query: &ParsedQuery,
+        account_id: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<(ThreadId, f32)>> {
+        let terms = terms_expression(&query.terms);
+        let mut params = Vec::new();
+
+        let sql = if let Some(ref terms) = terms {
+            let config = &self.config;
+            let weights = [
+                config.subject_boost,
+                config.body_boost,
+                config.body_boost,
+                config.from_boost,
+                config.from_boost,
+            ];
+            params.extend(weights.iter().map(|&w| Value::Real(f64::from(w))));
+            params.push(Value::Text(terms.clone()));
+            let condition = scoped_condition(query, account_id, true, &mut params);
+            params.push(Value::Integer((limit * 4).max(MIN_RANK_WINDOW) as i64));
+            format!(
+                "SELECT m.thread_id, MIN(s.score) AS best, MAX(m.received_at_ms)
+                 FROM fts_messages m
+                 JOIN (SELECT rowid, bm25(messages_fts, ?, ?, ?, ?, ?, 1.0, 1.0, 1.0) AS score
+                       FROM messages_fts WHERE messages_fts MATCH ?) s ON s.rowid = m.id
+                 WHERE {}
+                 GROUP BY m.thread_id
+                 ORDER BY best
+                 LIMIT ?",
+                condition
+            )
+        } else {
+            let condition = scoped_condition(query, account_id, false, &mut params);
+            params.push(Value::Integer(limit as i64));
+            format!(
+                "SELECT m.thread_id, 0.0, MAX(m.received_at_ms) AS latest
+                 FROM fts_messages m
+                 WHERE {}
+                 GROUP BY m.thread_id
+                 ORDER BY latest DESC
+                 LIMIT ?",
+                condition
+            )
+        };
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&sql)?;
+        let now_ms = Utc::now().timestamp_millis();
+        let mut hits = stmt
+            .query_map(rusqlite::params_from_iter(params), |row| {
+                let thread_id = ThreadId::new(row.get::<_, String>(0)?);
+                // bm25() is negative, lower meaning more relevant
+                let relevance = if terms.is_some() {
+                    -row.get::<_, f64>(1)? as f32
+                } else {
+                    1.0
+                };
+                let latest_ms: i64 = row.get(2)?;
+                let recency = self.config.recency_multiplier(now_ms - latest_ms);
+                Ok((thread_id, relevance * recency))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        hits.sort_by(|a, b| b.1.total_cmp(&a.1));
+        hits.truncate(limit);
+        Ok(hits)
+    }
+
+    /// Load thread metadata for ranked hits, skipping threads not in the store
+    fn load_results(
+        &self,
+        hits: Vec<(ThreadId, f32)>,
+        query: &ParsedQuery,
+        store: &dyn MailStore,
+    ) -> Vec<SearchResult> {
+        let terms = query.positive_terms();
+        hits.into_iter()
+            .filter_map(|(thread_id, score)| {
+                let thread = store.get_thread(&thread_id).ok().flatten()?;
+                let highlights = [("subject", &thread.subject), ("snippet", &thread.snippet)]
+                    .into_iter()
+                    .filter_map(|(field, text)| {
+                        let highlights = find_term_spans(text, &terms);
+                        (!highlights.is_empty()).then(|| FieldHighlight {
+                            field: field.to_string(),
+                            text: text.clone(),
+                            highlights,
+                        })
+                    })
+                    .collect();
+
+                Some(SearchResult {
+                    thread_id,
+                    subject: thread.subject,
+                    snippet: thread.snippet,
+                    last_message_at: thread.last_message_at,
+                    message_count: thread.message_count,
+                    sender_name: thread.sender_name,
+                    sender_email: thread.sender_email,
+                    is_unread: thread.is_unread,
+                    highlights,
+                    score,
+                })
+            })
+            .collect()
+    }
+}
+
+impl SearchBackend for FtsSearchIndex {
+    fn index_message(&self, message: &Message, thread: &Thread) -> Result<()> {
+        FtsSearchIndex::index_message(self, message, thread)
+    }
+
+    fn delete_thread(&self, thread_id: &ThreadId) -> Result<()> {
+        FtsSearchIndex::delete_thread(self, thread_id)
+    }
+
+    fn delete_message(&self, message_id: &MessageId) -> Result<()> {
+        FtsSearchIndex::delete_message(self, message_id)
+    }
+
+    fn update_labels(
+        &self,
+        thread_id: &ThreadId,
+        labels: &HashMap<MessageId, Vec<String>>,
+    ) -> Result<usize> {
+        FtsSearchIndex::update_labels(self, thread_id, labels)
+    }
+
+    fn commit(&self) -> Result<()> {
+        FtsSearchIndex::commit(self)
+    }
+
+    fn clear(&self) -> Result<()> {
+        FtsSearchIndex::clear(self)
+    }
+
+    fn indexed_message_ids(&self) -> Result<HashSet<MessageId>> {
+        FtsSearchIndex::indexed_message_ids(self)
+    }
+
+    fn search(
+        &self,
+        query: &ParsedQuery,
+        limit: usize,
+        store: &dyn MailStore,
+        account_id: Option<i64>,
+    ) -> Result<Vec<SearchResult>> {
+        FtsSearchIndex::search(self, query, limit, store, account_id)
+    }
+
+    fn search_page(
+        &self,
+        query: &ParsedQuery,
+        offset: usize,
+        limit: usize,
+        store: &dyn MailStore,
+        account_id: Option<i64>,
+    ) -> Result<SearchPage> {
+        FtsSearchIndex::search_page(self, query, offset, limit, store, account_id)
+    }
+
+    fn count_threads(&self, query: &ParsedQuery, account_id: Option<i64>) -> Result<usize> {
+        FtsSearchIndex::count_threads(self, query, account_id)
+    }
+}
+
+/// Delete the rows of both tables whose `fts_messages` row matches `filter`
+fn delete_rows(conn: &Connection, filter: &str, value: &str) -> Result<()> {
+    conn.execute(
+        &format!(
+            "DELETE FROM messages_fts WHERE rowid IN (SELECT id FROM fts_messages WHERE {})",
+            filter
+        ),
+        [value],
+    )?;
+    conn.execute(
+        &format!("DELETE FROM fts_messages WHERE {}", filter),
+        [value],
+    )?;
+    Ok(())
+}
+
+/// Labels as `|A|B|`, so a label can be matched with `instr(labels, '|A|')`
+fn encode_labels(labels: &[String]) -> String {
+    let mut encoded = String::from("|");
+    for label in labels {
+        encoded.push_str(label);
+        encoded.push('|');
+    }
+    encoded
+}
+
+/// Quote a value as an FTS5 string, which matches its tokens as a phrase
+///
+/// Returns None when the value has no indexable characters.
+fn fts_phrase(value: &str) -> Option<String> {
+    value
+        .chars()
+        .any(char::is_alphanumeric)
+        .then(|| format!("\"{}\"", value.replace('"', "\"\"")))
+}
+
+/// FTS5 expression matching any of the free-text terms in the text columns
+fn terms_expression(terms: &[String]) -> Option<String> {
+    let phrases: Vec<String> = terms.iter().filter_map(|t| fts_phrase(t)).collect();
+    if phrases.is_empty() {
+        return None;
+    }
+    Some(format!("{} : ({})", TEXT_COLUMNS, phrases.join(" OR ")))
+}
+
+/// `condition` restricted to `account_id` when one is selected
+fn scoped_condition(
+    query: &ParsedQuery,
+    account_id: Option<i64>,
+    terms_ranked: bool,
+    params: &mut Vec<Value>,
+) -> String {
+    let condition = condition(query, terms_ranked, params);
+    match account_id {
+        Some(id) => {
+            params.push(Value::Integer(id));
+            format!("({}) AND m.account_id = ?", condition)
+        }
+        None => condition,
+    }
+}
+
+/// Translate a parsed query to an SQL condition over `fts_messages m`
+///
+/// Parameters are appended to `params` in placeholder order. When
+/// `terms_ranked` is set, the top-level free-text terms are left out because
+/// the caller already matches them to compute scores.
+fn condition(query: &ParsedQuery, terms_ranked: bool, params: &mut Vec<Value>) -> String {
+    let mut parts: Vec<String> = Vec::new();
+
+    // Text filters share a single MATCH, ANDed together
+    let mut matches: Vec<String> = Vec::new();
+    if !terms_ranked {
+        matches.extend(terms_expression(&query.terms));
+    }
+    let column_filters = [
+        ("{from_name from_email}", &query.from),
+        ("to_addrs", &query.to),
+        ("subject", &query.subject),
+        ("filename", &query.filename),
+    ];
+    for (columns, values) in column_filters {
+        for value in values {
+            if let Some(phrase) = fts_phrase(value) {
+                matches.push(format!("{} : {}", columns, phrase));
+            }
+        }
+    }
+    if !matches.is_empty() {
+        parts.push("m.id IN (SELECT rowid FROM messages_fts WHERE messages_fts MATCH ?)".into());
+        params.push(Value::Text(matches.join(" AND ")));
+    }
+
+    // account: values are account IDs once resolved against the store;
+    // anything else names an unknown account
+    for account_val in &query.account {
+        match account_val.parse::<i64>() {
+            Ok(id) => {
+                parts.push("m.account_id = ?".into());
+                params.push(Value::Integer(id));
+            }
+            Err(_) => parts.push("0".into()),
+        }
+    }
+
+    if let Some(ref label) = query.in_label {
+        parts.push("instr(m.labels, ?) > 0".into());
+        params.push(Value::Text(format!("|{}|", label)));
+    }
+
+    let flags = [
+        ("m.is_unread", query.is_unread),
+        ("m.is_starred", query.is_starred),
+        ("m.has_attachment", query.has_attachment),
+    ];
+    for (column, flag) in flags {
+        if let Some(flag) = flag {
+            parts.push(format!("{} = ?", column));
+            params.push(Value::Integer(i64::from(flag)));
+        }
+    }
+
+    // Size and date bounds match the Tantivy backend: sizes and before: are
+    // exclusive, after: is inclusive
+    if let Some(larger) = query.larger {
+        parts.push("m.size_bytes > ?".into());
+        params.push(Value::Integer(i64::try_from(larger).unwrap_or(i64::MAX)));
+    }
+    if let Some(smaller) = query.smaller {
+        parts.push("m.size_bytes < ?".into());
+        params.push(Value::Integer(i64::try_from(smaller).unwrap_or(i64::MAX)));
+    }
+    if let Some(ref before) = query.before {
+        parts.push("m.received_at_ms < ?".into());
+        params.push(Value::Integer(before.timestamp_millis()));
+    }
+    if let Some(ref after) = query.after {
+        parts.push("m.received_at_ms >= ?".into());
+        params.push(Value::Integer(after.timestamp_millis()));
+    }
+
+    for group in &query.any_of {
+        if group.is_empty() {
+            continue;
+        }
+        let alternatives: Vec<String> = group
+            .iter()
+            .map(|alt| format!("({})", condition(alt, false, params)))
+            .collect();
+        parts.push(format!("({})", alternatives.join(" OR ")));
+    }
+
+    for excluded in &query.excluded {
+        parts.push(format!("NOT ({})", condition(excluded, false, params)));
+    }
+
+    if parts.is_empty() {
+        "1".into()
+    } else {
+        parts.join(" AND ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Account, EmailAddress};
+    use crate::search::parse_query;
+    use crate::storage::InMemoryMailStore;
+    use chrono::Duration;
+
+    fn message(id: &str, thread_id: &str, subject: &str, body: &str) -> Message {
+        Message::builder(MessageId::new(id), ThreadId::new(thread_id))
+            .from(EmailAddress::new("sender@example.com"))
+            .subject(subject)
+            .body_preview(body)
+            .body_text(Some(body.to_string()))
+            .received_at(Utc::now())
+            .label_ids(vec!["INBOX".to_string()])
+            .build()
+    }
+
+    fn thread(id: &str, subject: &str) -> Thread {
+        Thread {
+            id: ThreadId::new(id),
+            account_id: 1,
+            subject: subject.to_string(),
+            snippet: String::new(),
+            last_message_at: Utc::now(),
+            message_count: 1,
+            sender_name: None,
+            sender_email: "sender@example.com".to_string(),
+            is_unread: false,
+            participants: Vec::new(),
+        }
+    }
+
+    /// Index each message in its own thread, registering threads in the store
+    fn index_all(index: &FtsSearchIndex, store: &InMemoryMailStore, messages: &[Message]) {
+        for message in messages {
+            let thread = thread(message.thread_id.as_str(), &message.subject);
+            store.upsert_thread(thread.clone()).unwrap();
+            store.upsert_message(message.clone()).unwrap();
+            index.index_message(message, &thread).unwrap();
+        }
+        index.commit().unwrap();
+    }
+
+    fn ids(index: &FtsSearchIndex, store: &InMemoryMailStore, query: &str) -> Vec<String> {
+        index
+            .search(&parse_query(query), 10, store, None)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.thread_id.as_str().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_index_and_search() -> Result<()> {
+        let index = FtsSearchIndex::in_memory()?;
+        let store = InMemoryMailStore::new();
+        assert!(index.needs_rebuild());
+
+        let mut unread = message("m2", "t2", "Lunch", "Café on Friday?");
+        unread.label_ids.push("UNREAD".to_string());
+        unread.has_attachments = true;
+        unread.attachment_names = vec!["menu.pdf".to_string()];
+        index_all(
+            &index,
+            &store,
+            &[
+                message("m1", "t1", "Meeting tomorrow", "Discuss the project"),
+                unread,
+            ],
+        );
+
+        assert_eq!(ids(&index, &store, "meeting"), vec!["t1"]);
+        assert_eq!(ids(&index, &store, "PROJECT"), vec!["t1"]);
+        // Diacritics are folded
+        assert_eq!(ids(&index, &store, "cafe"), vec!["t2"]);
+        assert_eq!(ids(&index, &store, "is:unread"), vec!["t2"]);
+        assert_eq!(ids(&index, &store, "in:inbox has:attachment"), vec!["t2"]);
+        assert_eq!(ids(&index, &store, "filename:menu.pdf"), vec!["t2"]);
+        assert_eq!(
+            ids(&index, &store, "from:sender@example.com -lunch"),
+            vec!["t1"]
+        );
+        assert_eq!(
+            ids(&index, &store, "subject:meeting OR subject:lunch").len(),
+            2
+        );
+        assert!(ids(&index, &store, "missing").is_empty());
+
+        let results = index.search(&parse_query("meeting"), 10, &store, None)?;
+        assert_eq!(results[0].highlights[0].field, "subject");
+        Ok(())
+    }
+
+    #[test]
+    fn test_ranking_uses_boosts_and_recency() -> Result<()> {
+        let index = FtsSearchIndex::in_memory()?;
+        let store = InMemoryMailStore::new();
+
+        let mut ancient = message("m_ancient", "ancient", "Offsite", "");
+        ancient.received_at = Utc::now() - Duration::days(3650);
+        index_all(
+            &index,
+            &store,
+            &[
+                message("m_body", "body_hit", "Plans", "The roadmap is attached"),
+                message("m_subject", "subject_hit", "Roadmap", "Plans are attached"),
+                ancient,
+                message("m_recent", "recent", "Offsite", ""),
+            ],
+        );
+
+        assert_eq!(
+            ids(&index, &store, "roadmap"),
+            vec!["subject_hit", "body_hit"]
+        );
+        assert_eq!(ids(&index, &store, "offsite"), vec!["recent", "ancient"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_labels_and_delete() -> Result<()> {
+        let index = FtsSearchIndex::in_memory()?;
+        let store = InMemoryMailStore::new();
+        index_all(
+            &index,
+            &store,
+            &[
+                message("m1", "t1", "Report", ""),
+                message("m2", "t2", "Report", ""),
+            ],
+        );
+
+        let labels = HashMap::from([(MessageId::new("m1"), vec!["STARRED".to_string()])]);
+        assert_eq!(index.update_labels(&ThreadId::new("t1"), &labels)?, 1);
+        index.commit()?;
+        assert_eq!(ids(&index, &store, "is:starred"), vec!["t1"]);
+        assert_eq!(ids(&index, &store, "report in:inbox"), vec!["t2"]);
+
+        index.delete_message(&MessageId::new("m2"))?;
+        index.commit()?;
+        assert_eq!(index.count_threads(&parse_query("report"), None)?, 1);
+        assert_eq!(index.indexed_message_ids()?, HashSet::from([MessageId::new("m1")]));
+
+        // Re-indexing replaces rather than duplicates
+        index_all(&index, &store, &[message("m1", "t1", "Report", "")]);
+        assert_eq!(index.count_threads(&parse_query("report"), None)?, 1);
+
+        index.delete_thread(&ThreadId::new("t1"))?;
+        index.commit()?;
+        assert!(ids(&index, &store, "report").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_account_scoping_and_pages() -> Result<()> {
+        let index = FtsSearchIndex::in_memory()?;
+        let store = InMemoryMailStore::new();
+        let work = store.register_account(Account::new("me@work.example"))?;
+
+        for i in 0..5 {
+            let id = format!("t{}", i);
+            let mut thread = thread(&id, "Status update");
+            thread.account_id = if i == 0 { work.id } else { work.id + 1 };
+            store.upsert_thread(thread.clone())?;
+            index.index_message(
+                &message(&format!("m{}", i), &id, "Status update", ""),
+                &thread,
+            )?;
+        }
+        index.commit()?;
+
+        assert_eq!(
+            ids(&index, &store, "status account:me@work.example"),
+            vec!["t0"]
+        );
+        assert!(ids(&index, &store, "status account:nobody@example.com").is_empty());
+        let scoped = index.search(&parse_query("status"), 10, &store, Some(work.id))?;
+        assert_eq!(scoped.len(), 1);
+
+        let query = parse_query("status");
+        let first = index.search_page(&query, 0, 3, &store, None)?;
+        assert_eq!((first.results.len(), first.total), (3, 5));
+        assert_eq!(first.next_offset, Some(3));
+        let second = index.search_page(&query, 3, 3, &store, None)?;
+        assert_eq!(second.results.len(), 2);
+        assert_eq!(second.next_offset, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_and_rebuild() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("search.db");
+        let store = InMemoryMailStore::new();
+        for i in 0..3 {
+            let id = format!("t{}", i);
+            store.upsert_thread(thread(&id, "Invoice"))?;
+            store.upsert_message(message(&format!("m{}", i), &id, "Invoice", "Due soon"))?;
+        }
+
+        let index = FtsSearchIndex::open(&path)?;
+        assert!(index.needs_rebuild());
+        assert_eq!(index.rebuild(&store)?, 3);
+        assert!(!index.needs_rebuild());
+        drop(index);
+
+        let reopened = FtsSearchIndex::open(&path)?;
+        assert!(!reopened.needs_rebuild());
+        assert_eq!(reopened.count_threads(&parse_query("invoice"), None)?, 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,274 @@
+//! Highlighting of search terms in message bodies
+//!
+//! Search results only carry highlights for the subject and snippet. When a
+//! thread is opened from search, the full body is highlighted here instead:
+//! as byte spans over plain text, or by wrapping matches in `<mark>` elements
+//! inside the text nodes of an HTML body.
+
+use super::{FieldHighlight, HighlightSpan, ParsedQuery};
+use crate::models::Message;
+
+/// CSS class of the `<mark>` elements inserted by [`highlight_html`]
+pub const HIGHLIGHT_CLASS: &str = "cosmos-search-hit";
+
+/// Elements whose content is never shown as text
+const RAW_TEXT_ELEMENTS: [&str; 4] = ["script", "style", "title", "head"];
+
+/// Find every occurrence of the terms in `text`
+///
+/// Matching is case-insensitive and anchored at word starts, mirroring how
+/// the index tokenizes, so `art` highlights "Art show" but not "start".
+/// Overlapping and adjacent matches are merged; spans are byte offsets into
+/// `text`, sorted by position.
+pub fn find_term_spans(text: &str, terms: &[String]) -> Vec<HighlightSpan> {
+    // Lowercase while remembering where each lowercase byte came from, since
+    // lowercasing can change the byte length of non-ASCII characters
+    let mut lower = String::with_capacity(text.len());
+    let mut origin = Vec::with_capacity(text.len() + 1);
+    for (offset, ch) in text.char_indices() {
+        for lower_ch in ch.to_lowercase() {
+            for _ in 0..lower_ch.len_utf8() {
+                origin.push(offset);
+            }
+            lower.push(lower_ch);
+        }
+    }
+    origin.push(text.len());
+
+    let mut spans: Vec<HighlightSpan> = Vec::new();
+    for term in terms {
+        let needle = term.to_lowercase();
+        if needle.is_empty() {
+            continue;
+        }
+        for (pos, _) in lower.match_indices(&needle) {
+            let start = origin[pos];
+            let at_word_start = text[..start]
+                .chars()
+                .next_back()
+                .is_none_or(|prev| !prev.is_alphanumeric());
+            if !at_word_start {
+                continue;
+            }
+            // Extend to the end of the original character containing the
+            // last matched byte
+            let end_lower = pos + needle.len();
+            let end = (end_lower..origin.len())
+                .map(|i| origin[i])
+                .find(|&offset| offset > origin[end_lower - 1])
+                .unwrap_or(text.len());
+            spans.push(HighlightSpan { start, end });
+        }
+    }
+
+    spans.sort_by_key(|span| (span.start, span.end));
+    let mut merged: Vec<HighlightSpan> = Vec::with_capacity(spans.len());
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if span.start <= last.end => last.end = last.end.max(span.end),
+            _ => merged.push(span),
+        }
+    }
+    merged
+}
+
+/// Highlights for the plain-text body of a message
+///
+/// Returns a `body_text` field highlight covering every match of the query's
+/// positive terms, or None when the body has no text or nothing matches.
+pub fn body_highlights(message: &Message, query: &ParsedQuery) -> Option<FieldHighlight> {
+    let text = message.body_text.as_deref().filter(|t| !t.is_empty())?;
+    let highlights = find_term_spans(text, &query.positive_terms());
+    if highlights.is_empty() {
+        return None;
+    }
+    Some(FieldHighlight {
+        field: "body_text".to_string(),
+        text: text.to_string(),
+        highlights,
+    })
+}
+
+/// Wrap matches of the terms in an HTML document with `<mark>` elements
+///
+/// Only text between tags is touched: markup, comments, character
+/// references, and the content of script, style, title, and head elements are
+/// left alone. Each mark carries [`HIGHLIGHT_CLASS`].
+pub fn highlight_html(html: &str, terms: &[String]) -> String {
+    if terms.iter().all(|t| t.is_empty()) {
+        return html.to_string();
+    }
+
+    let mut out = String::with_capacity(html.len() + 64);
+    let mut rest = html;
+    let mut raw_until: Option<&str> = None;
+
+    while !rest.is_empty() {
+        let Some(tag_start) = rest.find('<') else {
+            push_text(&mut out, rest, terms, raw_until.is_some());
+            break;
+        };
+        push_text(&mut out, &rest[..tag_start], terms, raw_until.is_some());
+        rest = &rest[tag_start..];
+
+        // Comments may contain '>' so they end at "-->"
+        let tag_end = if rest.starts_with("<!--") {
+            rest.find("-->").map(|i| i + 3)
+        } else {
+            rest.find('>').map(|i| i + 1)
+        }
+        .unwrap_or(rest.len());
+        let tag = &rest[..tag_end];
+        out.push_str(tag);
+        rest = &rest[tag_end..];
+
+        let name = tag_name(tag);
+        match raw_until {
+            Some(open) if tag.starts_with("</") && name.eq_ignore_ascii_case(open) => {
+                raw_until = None;
+            }
+            None if !tag.starts_with("</") && !tag.ends_with("/>") => {
+                raw_until = RAW_TEXT_ELEMENTS
+                    .iter()
+                    .find(|raw| name.eq_ignore_ascii_case(raw))
+                    .copied();
+            }
+            _ => {}
+        }
+    }
+
+    out
+}
+
+/// Append a text node, marking matches unless it is raw element content
+fn push_text(out: &mut String, text: &str, terms: &[String], raw: bool) {
+    if raw {
+        out.push_str(text);
+        return;
+    }
+
+    let entities = entity_ranges(text);
+    let mut last = 0;
+    for span in find_term_spans(text, terms) {
+        let inside_entity = entities
+            .iter()
+            .any(|&(start, end)| span.start < end && start < span.end);
+        if inside_entity {
+            continue;
+        }
+        out.push_str(&text[last..span.start]);
+        out.push_str("<mark class=\"");
+        out.push_str(HIGHLIGHT_CLASS);
+        out.push_str("\">");
+        out.push_str(&text[span.start..span.end]);
+        out.push_str("</mark>");
+        last = span.end;
+    }
+    out.push_str(&text[last..]);
+}
+
+/// Byte ranges of character references such as `&amp;` or `&#39;`
+fn entity_ranges(text: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    for (start, _) in text.match_indices('&') {
+        let tail = &text[start + 1..];
+        let Some(len) = tail.find(';') else { continue };
+        let name = &tail[..len];
+        if !name.is_empty()
+            && name.len() <= 32
+            && name.chars().all(|c| c.is_alphanumeric() || c == '#')
+        {
+            ranges.push((start, start + len + 2));
+        }
+    }
+    ranges
+}
+
+/// Element name of a tag like `<div class="x">` or `</div>`
+fn tag_name(tag: &str) -> &str {
+    let name = tag.trim_start_matches('<').trim_start_matches('/');
+    let end = name
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(name.len());
+    &name[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{MessageId, ThreadId};
+    use crate::search::parse_query;
+
+    fn terms(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    fn spans(text: &str, words: &[&str]) -> Vec<&str> {
+        find_term_spans(text, &terms(words))
+            .iter()
+            .map(|span| &text[span.start..span.end])
+            .collect()
+    }
+
+    #[test]
+    fn test_find_term_spans() {
+        assert_eq!(
+            spans("Art at the start. ART!", &["art"]),
+            vec!["Art", "ART"]
+        );
+        // Prefix matches within a word still highlight from its start
+        assert_eq!(
+            spans("Budgeting for budget", &["budget"]),
+            vec!["Budget", "budget"]
+        );
+        // Overlapping terms merge
+        assert_eq!(
+            spans("quarterly report", &["quarter", "quarterly"]),
+            vec!["quarterly"]
+        );
+        assert!(spans("nothing here", &["missing"]).is_empty());
+    }
+
+    #[test]
+    fn test_find_term_spans_non_ascii() {
+        // 'İ' lowercases to two characters, shifting byte offsets
+        let text = "İstanbul café Café";
+        assert_eq!(spans(text, &["café"]), vec!["café", "Café"]);
+        assert_eq!(spans(text, &["i̇stanbul"]), vec!["İstanbul"]);
+    }
+
+    #[test]
+    fn test_body_highlights() {
+        let message = Message::builder(MessageId::new("m1"), ThreadId::new("t1"))
+            .body_text(Some(
+                "Invoice attached. Pay the invoice by Friday.".to_string(),
+            ))
+            .build();
+
+        let highlight = body_highlights(&message, &parse_query("invoice -friday")).unwrap();
+        assert_eq!(highlight.field, "body_text");
+        assert_eq!(highlight.highlights.len(), 2);
+
+        assert!(body_highlights(&message, &parse_query("receipt")).is_none());
+    }
+
+    #[test]
+    fn test_highlight_html() {
+        let html = concat!(
+            "<html><head><title>Report</title><style>.report{}</style></head>",
+            r#"<body><p class="report">Q3 report &amp; notes</p><!-- report -->"#,
+            r#"<a href="/report">Report</a></body></html>"#,
+        );
+        let out = highlight_html(html, &terms(&["report", "amp"]));
+
+        let mark = |text: &str| format!("<mark class=\"{}\">{}</mark>", HIGHLIGHT_CLASS, text);
+        assert!(out.contains("<title>Report</title>"));
+        assert!(out.contains("<style>.report{}</style>"));
+        assert!(out.contains(&format!(
+            "<p class=\"report\">Q3 {} &amp; notes</p>",
+            mark("report")
+        )));
+        assert!(out.contains("<!-- report -->"));
+        assert!(out.contains(&format!("<a href=\"/report\">{}</a>", mark("Report"))));
+    }
+}
@@ -1,27 +1,63 @@
 //! Search index implementation using Tantivy
 
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::ops::Bound;
 use std::path::Path;
-use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::Instant;
 
 use anyhow::{Context, Result};
-use tantivy::collector::TopDocs;
+use tantivy::collector::{Count, DocSetCollector, TopDocs};
 use tantivy::directory::MmapDirectory;
-use tantivy::query::{BooleanQuery, Occur, Query, QueryParser, RangeQuery, TermQuery};
-use tantivy::schema::{IndexRecordOption, Schema, Term, Value};
-use tantivy::{Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument};
-
-use crate::models::{Message, Thread, ThreadId};
-use crate::storage::MailStore;
+use tantivy::indexer::{IndexWriterOptions, LogMergePolicy};
+use tantivy::query::{
+    BooleanQuery, BoostQuery, EmptyQuery, FuzzyTermQuery, Occur, Query, QueryParser, RangeQuery,
+    TermQuery,
+};
+use tantivy::schema::{Field, IndexRecordOption, Schema, Term, Value};
+use tantivy::tokenizer::TokenStream;
+use tantivy::{
+    DocAddress, DocId, Index, IndexReader, IndexWriter, ReloadPolicy, Score, Searcher,
+    SegmentReader, TantivyDocument, TantivyError,
+};
+
+use crate::models::{EmailAddress, Message, MessageId, Thread, ThreadId};
+use crate::storage::{MailStore, ThreadCursor};
 
 use super::query_parser::ParsedQuery;
-use super::schema::{build_schema, SchemaFields};
-use super::{FieldHighlight, HighlightSpan, SearchResult};
+use super::schema::{build_schema, SchemaFields, SCHEMA_VERSION};
+use super::scoring::SearchConfig;
+use super::{
+    FieldHighlight, HighlightSpan, IndexVerification, InstantSearchOptions, InstantSearchResults,
+    SearchPage, SearchResult,
+};
 
 /// Default heap size for index writer (50MB)
 const DEFAULT_HEAP_SIZE: usize = 50_000_000;
 
+/// Smallest heap Tantivy accepts per indexing thread (15MB)
+const MIN_THREAD_HEAP_SIZE: usize = 15_000_000;
+
+/// Shortest term that instant search matches with a one-character typo
+const FUZZY_MIN_TERM_LEN: usize = 5;
+
+/// Number of matching messages scanned when building suggestions
+const SUGGEST_SCAN_LIMIT: usize = 200;
+
+/// File in the index directory recording the schema version it was built with
+const SCHEMA_VERSION_FILE: &str = "cosmos_schema_version";
+
+/// Matching messages up to which a page's total is counted exactly
+///
+/// Above this, the message count is reported as an upper-bound estimate to
+/// avoid loading every matching document.
+const EXACT_TOTAL_LIMIT: usize = 10_000;
+
+/// Threads indexed between commits while rebuilding
+const REBUILD_BATCH_SIZE: usize = 200;
+
 /// Thread-safe search index wrapper
 pub struct SearchIndex {
     index: Index,
@@ -31,6 +67,14 @@ pub struct SearchIndex {
     fields: SchemaFields,
     /// Writer is wrapped in RwLock for thread-safe access
     writer: RwLock<Option<IndexWriter>>,
+    /// Set when the index was created, reset, or found outdated on open
+    needs_rebuild: AtomicBool,
+    /// Field boosts and recency decay for ranking, and the commit policy
+    config: SearchConfig,
+    /// Documents added since the last commit
+    pending_docs: AtomicUsize,
+    /// When the index was last committed
+    last_commit: Mutex<Instant>,
 }
 
 impl std::fmt::Debug for SearchIndex {
@@ -43,15 +87,32 @@ impl std::fmt::Debug for SearchIndex {
 
 impl SearchIndex {
     /// Open or create index at the given path
+    ///
+    /// An index written with a different `SCHEMA_VERSION`, or one Tantivy
+    /// reports as corrupt, is discarded and recreated empty. Check
+    /// [`SearchIndex::needs_rebuild`] afterwards and repopulate it with
+    /// [`SearchIndex::rebuild_from_store`].
     pub fn open(path: impl AsRef<Path>) -> Result<Self> {
         let path = path.as_ref();
         std::fs::create_dir_all(path).context("Failed to create index directory")?;
 
         let schema = build_schema();
         let dir = MmapDirectory::open(path).context("Failed to open index directory")?;
+        let existed = Index::exists(&dir).context("Failed to inspect index directory")?;
+        let current = read_schema_version(path) == Some(SCHEMA_VERSION);
 
-        let index =
-            Index::open_or_create(dir, schema.clone()).context("Failed to open or create index")?;
+        let (index, needs_rebuild) = if existed && !current {
+            (recreate_index(path, &schema)?, true)
+        } else {
+            match Index::open_or_create(dir, schema.clone()) {
+                Ok(index) => (index, !existed),
+                Err(TantivyError::SchemaError(_) | TantivyError::DataCorruption(_)) => {
+                    (recreate_index(path, &schema)?, true)
+                }
+                Err(e) => return Err(e).context("Failed to open or create index"),
+            }
+        };
+        write_schema_version(path)?;
 
         let reader = index
             .reader_builder()
@@ -67,6 +128,10 @@ impl SearchIndex {
             schema,
             fields,
             writer: RwLock::new(None),
+            needs_rebuild: AtomicBool::new(needs_rebuild),
+            config: SearchConfig::default(),
+            pending_docs: AtomicUsize::new(0),
+            last_commit: Mutex::new(Instant::now()),
         })
     }
 
@@ -88,14 +153,54 @@ impl SearchIndex {
             schema,
             fields,
             writer: RwLock::new(None),
+            needs_rebuild: AtomicBool::new(false),
+            config: SearchConfig::default(),
+            pending_docs: AtomicUsize::new(0),
+            last_commit: Mutex::new(Instant::now()),
         })
     }
 
+    /// Rank, commit and merge with the given settings instead of the defaults
+    pub fn with_config(mut self, config: SearchConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Settings used for ranking, commits and merges
+    pub fn config(&self) -> &SearchConfig {
+        &self.config
+    }
+
+    /// Whether the index must be repopulated from the store
+    ///
+    /// True after `open` created a new index or discarded an outdated or
+    /// corrupt one, until `rebuild_from_store` completes.
+    pub fn needs_rebuild(&self) -> bool {
+        self.needs_rebuild.load(Ordering::SeqCst)
+    }
+
     /// Get or create a writer with the given heap size
+    ///
+    /// Tantivy merges segments on the writer's merge threads after each
+    /// commit, as the merge policy from the config decides.
     fn get_writer(&self) -> Result<std::sync::RwLockWriteGuard<'_, Option<IndexWriter>>> {
         let mut guard = self.writer.write().map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
         if guard.is_none() {
-            *guard = Some(self.index.writer(DEFAULT_HEAP_SIZE)?);
+            let threads = std::thread::available_parallelism()
+                .map_or(1, |n| n.get())
+                .clamp(1, DEFAULT_HEAP_SIZE / MIN_THREAD_HEAP_SIZE);
+            let options = IndexWriterOptions::builder()
+                .num_worker_threads(threads)
+                .memory_budget_per_thread(DEFAULT_HEAP_SIZE / threads)
+                .num_merge_threads(self.config.merge_threads.max(1))
+                .build();
+            let writer = self.index.writer_with_options(options)?;
+
+            let mut merge_policy = LogMergePolicy::default();
+            merge_policy.set_min_num_segments(self.config.merge_min_segments.max(2));
+            writer.set_merge_policy(Box::new(merge_policy));
+
+            *guard = Some(writer);
         }
         Ok(guard)
     }
@@ -147,9 +252,15 @@ impl SearchIndex {
             doc.add_text(self.fields.cc, &display);
         }
 
-        // Labels (each label as separate field value)
-        for label in &message.label_ids {
-            doc.add_text(self.fields.labels, label);
+        // Labels and the flags derived from them
+        self.add_label_fields(&mut doc, &message.label_ids);
+
+        // Attachment names and extracted text
+        for name in &message.attachment_names {
+            doc.add_text(self.fields.filename, name);
+        }
+        if let Some(ref text) = message.attachment_text {
+            doc.add_text(self.fields.attachment_text, text);
         }
 
         // Numeric fields
@@ -157,27 +268,61 @@ impl SearchIndex {
             self.fields.received_at_ms,
             message.received_at.timestamp_millis(),
         );
+        doc.add_i64(self.fields.size_bytes, message.size_bytes);
+        doc.add_u64(
+            self.fields.has_attachment,
+            if message.has_attachments { 1 } else { 0 },
+        );
+
+        writer.add_document(doc)?;
+        self.pending_docs.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Index a thread's note, replacing any previous one
+    ///
+    /// The note gets its own document, keyed by [`note_document_id`], so
+    /// searching for words in the note finds the thread. Terms are matched
+    /// per document: a query mixing note and message words won't match.
+    /// `None` or a blank note removes it.
+    pub fn index_note(&self, thread: &Thread, note: Option<&str>) -> Result<()> {
+        let mut writer_guard = self.get_writer()?;
+        let writer = writer_guard.as_mut().unwrap();
+
+        let doc_id = note_document_id(&thread.id);
+        writer.delete_term(Term::from_field_text(self.fields.message_id, &doc_id));
+
+        let Some(note) = note.filter(|n| !n.trim().is_empty()) else {
+            return Ok(());
+        };
+        let mut doc = TantivyDocument::new();
+        doc.add_text(self.fields.thread_id, thread.id.as_str());
+        doc.add_text(self.fields.message_id, &doc_id);
+        doc.add_i64(self.fields.account_id, thread.account_id);
+        doc.add_text(self.fields.note, note);
+        doc.add_i64(
+            self.fields.received_at_ms,
+            thread.last_message_at.timestamp_millis(),
+        );
+        writer.add_document(doc)?;
+        self.pending_docs.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Add label values plus the unread/starred flags derived from them
+    fn add_label_fields(&self, doc: &mut TantivyDocument, labels: &[String]) {
+        // Each label as separate field value
+        for label in labels {
+            doc.add_text(self.fields.labels, label);
+        }
         doc.add_u64(
             self.fields.is_unread,
-            if message.label_ids.iter().any(|l| l == "UNREAD") {
-                1
-            } else {
-                0
-            },
+            if labels.iter().any(|l| l == "UNREAD") { 1 } else { 0 },
         );
         doc.add_u64(
             self.fields.is_starred,
-            if message.label_ids.iter().any(|l| l == "STARRED") {
-                1
-            } else {
-                0
-            },
+            if labels.iter().any(|l| l == "STARRED") { 1 } else { 0 },
         );
-        // TODO: Detect attachments from message headers/parts
-        doc.add_u64(self.fields.has_attachment, 0);
-
-        writer.add_document(doc)?;
-        Ok(())
     }
 
     /// Delete all documents for a thread
@@ -192,22 +337,158 @@ impl SearchIndex {
         Ok(())
     }
 
+    /// Delete the document for a single message
+    pub fn delete_message(&self, message_id: &MessageId) -> Result<()> {
+        let mut writer_guard = self.get_writer()?;
+        let writer = writer_guard.as_mut().unwrap();
+
+        writer.delete_term(Term::from_field_text(
+            self.fields.message_id,
+            message_id.as_str(),
+        ));
+        Ok(())
+    }
+
+    /// Replace the labels of indexed messages in a thread
+    ///
+    /// Each message in `labels` has its document rewritten from the stored
+    /// field values with the new label set, so `in:`, `label:`, `is:unread`
+    /// and `is:starred` reflect the change without re-reading the message
+    /// body. Messages of the thread missing from `labels` are left as-is.
+    ///
+    /// Only committed documents are visible here; a message indexed since the
+    /// last commit should be re-indexed with `index_message` instead.
+    ///
+    /// Returns the number of documents rewritten. Changes become visible
+    /// after `commit`.
+    pub fn update_labels(
+        &self,
+        thread_id: &ThreadId,
+        labels: &HashMap<MessageId, Vec<String>>,
+    ) -> Result<usize> {
+        if labels.is_empty() {
+            return Ok(0);
+        }
+
+        let searcher = self.reader.searcher();
+        let thread_query = TermQuery::new(
+            Term::from_field_text(self.fields.thread_id, thread_id.as_str()),
+            IndexRecordOption::Basic,
+        );
+        let doc_addresses = searcher.search(&thread_query, &DocSetCollector)?;
+
+        let mut writer_guard = self.get_writer()?;
+        let writer = writer_guard.as_mut().unwrap();
+
+        let mut updated = 0;
+        for doc_address in doc_addresses {
+            let stored: TantivyDocument = searcher.doc(doc_address)?;
+            let Some(message_id) = stored
+                .get_first(self.fields.message_id)
+                .and_then(|v| v.as_str())
+                .map(MessageId::new)
+            else {
+                continue;
+            };
+            let Some(new_labels) = labels.get(&message_id) else {
+                continue;
+            };
+
+            writer.delete_term(Term::from_field_text(
+                self.fields.message_id,
+                message_id.as_str(),
+            ));
+            writer.add_document(self.relabel_document(&stored, new_labels))?;
+            updated += 1;
+        }
+        self.pending_docs.fetch_add(updated, Ordering::Relaxed);
+
+        Ok(updated)
+    }
+
+    /// Copy a stored document, replacing its labels and derived flags
+    fn relabel_document(&self, stored: &TantivyDocument, labels: &[String]) -> TantivyDocument {
+        let mut doc = TantivyDocument::new();
+
+        let text_fields = [
+            self.fields.thread_id,
+            self.fields.message_id,
+            self.fields.subject,
+            self.fields.body_text,
+            self.fields.snippet,
+            self.fields.from,
+            self.fields.from_email,
+            self.fields.to,
+            self.fields.cc,
+            self.fields.filename,
+            self.fields.attachment_text,
+            self.fields.note,
+        ];
+        for field in text_fields {
+            for value in stored.get_all(field).filter_map(|v| v.as_str()) {
+                doc.add_text(field, value);
+            }
+        }
+
+        for field in [
+            self.fields.account_id,
+            self.fields.received_at_ms,
+            self.fields.size_bytes,
+        ] {
+            if let Some(value) = stored.get_first(field).and_then(|v| v.as_i64()) {
+                doc.add_i64(field, value);
+            }
+        }
+        if let Some(value) = stored
+            .get_first(self.fields.has_attachment)
+            .and_then(|v| v.as_u64())
+        {
+            doc.add_u64(self.fields.has_attachment, value);
+        }
+
+        self.add_label_fields(&mut doc, labels);
+        doc
+    }
+
     /// Commit pending changes
     pub fn commit(&self) -> Result<()> {
         let mut writer_guard = self.writer.write().map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
         if let Some(ref mut writer) = *writer_guard {
             writer.commit()?;
         }
+        self.committed();
         self.reader.reload()?;
         Ok(())
     }
 
+    /// Commit if the config's commit policy says enough has been indexed
+    ///
+    /// Sync calls this after each batch so a large initial sync doesn't
+    /// write a segment per batch, then calls `commit` once it's done.
+    /// Returns whether it committed.
+    pub fn commit_if_due(&self) -> Result<bool> {
+        let pending = self.pending_docs.load(Ordering::Relaxed);
+        let since_commit = self.last_commit.lock().unwrap().elapsed();
+        if !self.config.commit_due(pending, since_commit) {
+            return Ok(false);
+        }
+        self.commit()?;
+        Ok(true)
+    }
+
+    /// Restart the commit policy's count and clock
+    fn committed(&self) {
+        self.pending_docs.store(0, Ordering::Relaxed);
+        *self.last_commit.lock().unwrap() = Instant::now();
+    }
+
     /// Clear all documents from the index
     pub fn clear(&self) -> Result<()> {
         let mut writer_guard = self.get_writer()?;
         let writer = writer_guard.as_mut().unwrap();
         writer.delete_all_documents()?;
         writer.commit()?;
+        self.committed();
         self.reader.reload()?;
         Ok(())
     }
@@ -223,19 +504,196 @@ impl SearchIndex {
         store: &dyn MailStore,
         account_id: Option<i64>,
     ) -> Result<Vec<SearchResult>> {
+        let query = &*with_account_ids(query, store)?;
         let searcher = self.reader.searcher();
 
         // Build Tantivy query from ParsedQuery
         let tantivy_query = self.build_query(query, account_id)?;
 
         // Execute search - fetch extra to account for deduplication
-        let top_docs = searcher.search(&tantivy_query, &TopDocs::with_limit(limit * 3))?;
+        let top_docs = self.top_docs(&searcher, tantivy_query.as_ref(), limit * 3)?;
+
+        let (results, _) = self.collect_results(&searcher, top_docs, query, limit, store, None)?;
+        Ok(results)
+    }
+
+    /// Fetch one page of distinct matching threads, plus the total hit count
+    ///
+    /// Threads are ranked by their best-scoring message, and `offset` counts
+    /// threads rather than messages. The total is exact while at most
+    /// `EXACT_TOTAL_LIMIT` messages match; beyond that the number of matching
+    /// messages is returned as an estimate, since a thread can match more
+    /// than once.
+    pub fn search_page(
+        &self,
+        query: &ParsedQuery,
+        offset: usize,
+        limit: usize,
+        store: &dyn MailStore,
+        account_id: Option<i64>,
+    ) -> Result<SearchPage> {
+        let query = &*with_account_ids(query, store)?;
+        let searcher = self.reader.searcher();
+        let tantivy_query = self.build_query(query, account_id)?;
+
+        let matching_messages = searcher.search(&tantivy_query, &Count)?;
+        let (total, total_is_estimate) = if matching_messages <= EXACT_TOTAL_LIMIT {
+            (self.count_threads(query, account_id)?, false)
+        } else {
+            (matching_messages, true)
+        };
+
+        // Widen the window until it covers enough distinct threads (one more
+        // than the page needs, to tell whether another page follows); threads
+        // with several matching messages use up more than one slot
+        let wanted = offset + limit;
+        let mut window = (wanted + 1) * 3;
+        let thread_hits = loop {
+            let top_docs = self.top_docs(&searcher, tantivy_query.as_ref(), window)?;
+            let exhausted = top_docs.len() < window;
+
+            let mut seen_threads = HashSet::new();
+            let mut thread_hits = Vec::new();
+            for (score, doc_address) in top_docs {
+                let doc: TantivyDocument = searcher.doc(doc_address)?;
+                let thread_id = doc
+                    .get_first(self.fields.thread_id)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string();
+                if seen_threads.insert(thread_id) {
+                    thread_hits.push((score, doc_address));
+                }
+            }
+
+            if exhausted || thread_hits.len() > wanted {
+                break thread_hits;
+            }
+            window *= 2;
+        };
+
+        let page_hits: Vec<_> = thread_hits.iter().skip(offset).take(limit).copied().collect();
+        let next_offset = (thread_hits.len() > wanted).then_some(wanted);
+
+        let (results, _) = self.collect_results(&searcher, page_hits, query, limit, store, None)?;
+        Ok(SearchPage {
+            results,
+            total,
+            total_is_estimate,
+            next_offset,
+        })
+    }
 
+    /// Search-as-you-type variant of [`SearchIndex::search`]
+    ///
+    /// Free-text terms match as prefixes, so results appear before a word is
+    /// finished. With `options.fuzzy`, terms of five or more characters also
+    /// tolerate one typo. Operators (`from:`, `is:`, ...) keep their exact
+    /// semantics. Once `options.budget` has elapsed, the results gathered so
+    /// far are returned and marked truncated.
+    pub fn instant_search(
+        &self,
+        query: &ParsedQuery,
+        limit: usize,
+        store: &dyn MailStore,
+        account_id: Option<i64>,
+        options: &InstantSearchOptions,
+    ) -> Result<InstantSearchResults> {
+        let started = Instant::now();
+        let query = &*with_account_ids(query, store)?;
+        let searcher = self.reader.searcher();
+
+        // Free text is expanded here; everything else goes through build_clauses
+        let operators = ParsedQuery {
+            terms: Vec::new(),
+            ..query.clone()
+        };
+        let mut clauses = self.build_clauses(&operators);
+        clauses.extend(self.instant_term_clauses(&query.terms, options.fuzzy)?);
+        if let Some(id) = account_id {
+            clauses.push(self.account_clause(id));
+        }
+        let tantivy_query = combine_clauses(clauses);
+
+        let top_docs = self.top_docs(&searcher, tantivy_query.as_ref(), limit * 3)?;
+        let deadline = started + options.budget;
+        let (results, complete) =
+            self.collect_results(&searcher, top_docs, query, limit, store, Some(deadline))?;
+
+        Ok(InstantSearchResults {
+            results,
+            truncated: !complete,
+            elapsed: started.elapsed(),
+        })
+    }
+
+    /// Build one required clause per free-text token for instant search
+    ///
+    /// The final token is matched as a prefix since it may still be typed.
+    fn instant_term_clauses(
+        &self,
+        terms: &[String],
+        fuzzy: bool,
+    ) -> Result<Vec<(Occur, Box<dyn Query>)>> {
+        // Tokenize like the indexed text so case and punctuation line up
+        let mut tokens = Vec::new();
+        for term in terms {
+            tokens.extend(self.tokenize(term)?);
+        }
+
+        let last = tokens.len().saturating_sub(1);
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        for (i, token) in tokens.iter().enumerate() {
+            let distance = if fuzzy && token.chars().count() >= FUZZY_MIN_TERM_LEN {
+                1
+            } else {
+                0
+            };
+
+            let mut alternatives: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+            for field in self.text_fields() {
+                let term = Term::from_field_text(field, token);
+                let boost = self.field_boost(field);
+                let mut push = |query: Box<dyn Query>| {
+                    alternatives.push((Occur::Should, Box::new(BoostQuery::new(query, boost))));
+                };
+                if i == last {
+                    push(Box::new(FuzzyTermQuery::new_prefix(term, distance, true)));
+                } else {
+                    push(Box::new(TermQuery::new(term.clone(), IndexRecordOption::WithFreqs)));
+                    if distance > 0 {
+                        push(Box::new(FuzzyTermQuery::new(term, distance, true)));
+                    }
+                }
+            }
+            clauses.push((Occur::Must, Box::new(BooleanQuery::new(alternatives))));
+        }
+
+        Ok(clauses)
+    }
+
+    /// Load thread metadata for top docs, deduplicating by thread
+    ///
+    /// Returns the results and whether collection finished before `deadline`.
+    /// At least one result is always collected when any doc matched.
+    fn collect_results(
+        &self,
+        searcher: &Searcher,
+        top_docs: Vec<(Score, DocAddress)>,
+        query: &ParsedQuery,
+        limit: usize,
+        store: &dyn MailStore,
+        deadline: Option<Instant>,
+    ) -> Result<(Vec<SearchResult>, bool)> {
         // Deduplicate by thread_id and build results
         let mut seen_threads = HashSet::new();
         let mut results = Vec::with_capacity(limit);
 
         for (score, doc_address) in top_docs {
+            if !results.is_empty() && deadline.is_some_and(|d| Instant::now() >= d) {
+                return Ok((results, false));
+            }
+
             let doc: TantivyDocument = searcher.doc(doc_address)?;
 
             // Extract thread_id
@@ -275,35 +733,217 @@ impl SearchIndex {
             }
         }
 
-        Ok(results)
+        Ok((results, true))
+    }
+
+    /// Count distinct threads matching the query
+    ///
+    /// If `account_id` is Some, only counts threads from that account.
+    pub fn count_threads(&self, query: &ParsedQuery, account_id: Option<i64>) -> Result<usize> {
+        let searcher = self.reader.searcher();
+        let tantivy_query = self.build_query(query, account_id)?;
+
+        // Matches are per message, so dedupe by thread_id
+        let doc_addresses = searcher.search(&tantivy_query, &DocSetCollector)?;
+        let mut threads = HashSet::new();
+        for doc_address in doc_addresses {
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+            if let Some(thread_id) = doc.get_first(self.fields.thread_id).and_then(|v| v.as_str()) {
+                threads.insert(thread_id.to_string());
+            }
+        }
+
+        Ok(threads.len())
+    }
+
+    /// Contacts whose name or address starts with `prefix`, most frequent first
+    ///
+    /// Senders and recipients of indexed messages are both considered. Every
+    /// word of `prefix` must start a word of the contact's name or address.
+    pub fn suggest_contacts(&self, prefix: &str, limit: usize) -> Result<Vec<EmailAddress>> {
+        let tokens = self.tokenize(prefix)?;
+        if tokens.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let fields = [
+            self.fields.from,
+            self.fields.from_email,
+            self.fields.to,
+            self.fields.cc,
+        ];
+        let searcher = self.reader.searcher();
+        let query = prefix_query(&fields, &tokens);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(SUGGEST_SCAN_LIMIT))?;
+
+        // Lowercased email -> (address, occurrences, first seen)
+        let mut contacts: HashMap<String, (EmailAddress, usize, usize)> = HashMap::new();
+        for (rank, (_, doc_address)) in top_docs.into_iter().enumerate() {
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+
+            let mut candidates = Vec::new();
+            if let Some(email) = doc.get_first(self.fields.from_email).and_then(|v| v.as_str()) {
+                let name = doc.get_first(self.fields.from).and_then(|v| v.as_str());
+                candidates.push(EmailAddress {
+                    name: name.map(String::from),
+                    email: email.to_string(),
+                });
+            }
+            for field in [self.fields.to, self.fields.cc] {
+                for value in doc.get_all(field) {
+                    if let Some(display) = value.as_str() {
+                        candidates.push(EmailAddress::parse(display));
+                    }
+                }
+            }
+
+            for address in candidates {
+                if !contact_matches(&address, &tokens) {
+                    continue;
+                }
+                let entry = contacts
+                    .entry(address.email.to_lowercase())
+                    .or_insert_with(|| (address.clone(), 0, rank));
+                entry.1 += 1;
+                if entry.0.name.is_none() {
+                    entry.0.name = address.name;
+                }
+            }
+        }
+
+        let mut ranked: Vec<_> = contacts.into_values().collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.2.cmp(&b.2)));
+        Ok(ranked
+            .into_iter()
+            .take(limit)
+            .map(|(address, _, _)| address)
+            .collect())
+    }
+
+    /// Distinct subjects containing words that start with `prefix`, best match first
+    ///
+    /// Reply and forward prefixes are stripped so a thread's subject is
+    /// suggested once.
+    pub fn suggest_subjects(&self, prefix: &str, limit: usize) -> Result<Vec<String>> {
+        let tokens = self.tokenize(prefix)?;
+        if tokens.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let searcher = self.reader.searcher();
+        let query = prefix_query(&[self.fields.subject], &tokens);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(SUGGEST_SCAN_LIMIT))?;
+
+        let mut seen = HashSet::new();
+        let mut subjects = Vec::new();
+        for (_, doc_address) in top_docs {
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+            let Some(subject) = doc.get_first(self.fields.subject).and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let subject = strip_reply_prefixes(subject);
+            if subject.is_empty() || !seen.insert(subject.to_lowercase()) {
+                continue;
+            }
+            subjects.push(subject.to_string());
+            if subjects.len() >= limit {
+                break;
+            }
+        }
+
+        Ok(subjects)
+    }
+
+    /// Split text into index tokens (lowercased words)
+    fn tokenize(&self, text: &str) -> Result<Vec<String>> {
+        let mut tokenizer = self.index.tokenizer_for_field(self.fields.subject)?;
+        let mut stream = tokenizer.token_stream(text);
+        let mut tokens = Vec::new();
+        while stream.advance() {
+            tokens.push(stream.token().text.clone());
+        }
+        Ok(tokens)
     }
 
     /// Build a Tantivy query from ParsedQuery
     fn build_query(&self, query: &ParsedQuery, account_id: Option<i64>) -> Result<Box<dyn Query>> {
-        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        let mut clauses = self.build_clauses(query);
 
         // Account filter
         if let Some(id) = account_id {
-            let term = Term::from_field_i64(self.fields.account_id, id);
-            clauses.push((
-                Occur::Must,
-                Box::new(TermQuery::new(term, IndexRecordOption::Basic)),
-            ));
+            clauses.push(self.account_clause(id));
+        }
+
+        Ok(combine_clauses(clauses))
+    }
+
+    /// Clause restricting matches to one account
+    fn account_clause(&self, account_id: i64) -> (Occur, Box<dyn Query>) {
+        let term = Term::from_field_i64(self.fields.account_id, account_id);
+        (
+            Occur::Must,
+            Box::new(TermQuery::new(term, IndexRecordOption::Basic)),
+        )
+    }
+
+    /// Top `limit` matches, with scores scaled by the configured recency decay
+    fn top_docs(
+        &self,
+        searcher: &Searcher,
+        query: &dyn Query,
+        limit: usize,
+    ) -> Result<Vec<(Score, DocAddress)>> {
+        let config = self.config;
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let collector =
+            TopDocs::with_limit(limit).tweak_score(move |segment_reader: &SegmentReader| {
+                let received_at = segment_reader.fast_fields().i64("received_at_ms").ok();
+                move |doc: DocId, score: Score| {
+                    let received_ms = received_at
+                        .as_ref()
+                        .and_then(|column| column.first(doc))
+                        .unwrap_or(now_ms);
+                    score * config.recency_multiplier(now_ms - received_ms)
+                }
+            });
+        Ok(searcher.search(query, &collector)?)
+    }
+
+    /// Configured boost for matches in a free-text field
+    fn field_boost(&self, field: Field) -> Score {
+        if field == self.fields.subject {
+            self.config.subject_boost
+        } else if field == self.fields.from || field == self.fields.from_email {
+            self.config.from_boost
+        } else {
+            self.config.body_boost
         }
+    }
+
+    /// Fields searched by free-text terms
+    fn text_fields(&self) -> Vec<Field> {
+        vec![
+            self.fields.subject,
+            self.fields.body_text,
+            self.fields.snippet,
+            self.fields.from,
+            self.fields.from_email,
+            self.fields.attachment_text,
+            self.fields.note,
+        ]
+    }
+
+    /// Translate a parsed query (including OR groups and negations) to clauses
+    fn build_clauses(&self, query: &ParsedQuery) -> Vec<(Occur, Box<dyn Query>)> {
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
 
         // Free-text terms - search across multiple fields
         if !query.terms.is_empty() {
             let query_text = query.terms.join(" ");
-            let parser = QueryParser::for_index(
-                &self.index,
-                vec![
-                    self.fields.subject,
-                    self.fields.body_text,
-                    self.fields.snippet,
-                    self.fields.from,
-                    self.fields.from_email,
-                ],
-            );
+            let mut parser = QueryParser::for_index(&self.index, self.text_fields());
+            for field in self.text_fields() {
+                parser.set_field_boost(field, self.field_boost(field));
+            }
             if let Ok(text_query) = parser.parse_query(&query_text) {
                 clauses.push((Occur::Must, text_query));
             }
@@ -338,6 +978,16 @@ impl SearchIndex {
             }
         }
 
+        // account: filter - values are account IDs once resolved against
+        // the store; anything else names an unknown account
+        for account_val in &query.account {
+            let clause: Box<dyn Query> = match account_val.parse::<i64>() {
+                Ok(id) => self.account_clause(id).1,
+                Err(_) => Box::new(EmptyQuery),
+            };
+            clauses.push((Occur::Must, clause));
+        }
+
         // in:label filter
         if let Some(ref label) = query.in_label {
             let term = Term::from_field_text(self.fields.labels, label);
@@ -377,6 +1027,29 @@ impl SearchIndex {
             ));
         }
 
+        // filename: filter
+        for filename_val in &query.filename {
+            // Quoted so "report.pdf" is a phrase over its tokens
+            let phrase = format!("\"{}\"", filename_val.to_lowercase());
+            let parser = QueryParser::for_index(&self.index, vec![self.fields.filename]);
+            if let Ok(filename_query) = parser.parse_query(&phrase) {
+                clauses.push((Occur::Must, filename_query));
+            }
+        }
+
+        // Size range filters (larger:/smaller:)
+        if let Some(larger) = query.larger {
+            let lower_term = Term::from_field_i64(self.fields.size_bytes, size_to_i64(larger));
+            let range = RangeQuery::new(Bound::Excluded(lower_term), Bound::Unbounded);
+            clauses.push((Occur::Must, Box::new(range)));
+        }
+
+        if let Some(smaller) = query.smaller {
+            let upper_term = Term::from_field_i64(self.fields.size_bytes, size_to_i64(smaller));
+            let range = RangeQuery::new(Bound::Unbounded, Bound::Excluded(upper_term));
+            clauses.push((Occur::Must, Box::new(range)));
+        }
+
         // Date range filters (before:/after:)
         if let Some(ref before) = query.before {
             let before_ms = before.timestamp_millis();
@@ -392,13 +1065,23 @@ impl SearchIndex {
             clauses.push((Occur::Must, Box::new(range)));
         }
 
-        // Combine all clauses
-        if clauses.is_empty() {
-            // Match all if no constraints
-            Ok(Box::new(tantivy::query::AllQuery))
-        } else {
-            Ok(Box::new(BooleanQuery::new(clauses)))
+        // OR groups - each group must match at least one alternative
+        for group in &query.any_of {
+            let alternatives: Vec<(Occur, Box<dyn Query>)> = group
+                .iter()
+                .map(|alt| (Occur::Should, combine_clauses(self.build_clauses(alt))))
+                .collect();
+            if !alternatives.is_empty() {
+                clauses.push((Occur::Must, Box::new(BooleanQuery::new(alternatives))));
+            }
+        }
+
+        // Negated clauses
+        for excluded in &query.excluded {
+            clauses.push((Occur::MustNot, combine_clauses(self.build_clauses(excluded))));
         }
+
+        clauses
     }
 
     /// Generate highlights for search results
@@ -420,7 +1103,7 @@ impl SearchIndex {
             .unwrap_or_default();
 
         // Find matches for each query term
-        for term in &query.terms {
+        for term in &query.positive_terms() {
             let term_lower = term.to_lowercase();
 
             // Check subject
@@ -458,34 +1141,251 @@ impl SearchIndex {
     /// Clears the existing index and re-indexes all messages from the store.
     /// Returns the number of messages indexed.
     pub fn rebuild(&self, store: &dyn MailStore) -> Result<usize> {
-        // Clear existing index
-        {
-            let mut writer_guard = self.get_writer()?;
-            let writer = writer_guard.as_mut().unwrap();
-            writer.delete_all_documents()?;
-            writer.commit()?;
-        }
+        self.rebuild_from_store(store, |_, _| {})
+    }
 
+    /// Rebuild the index from storage, reporting progress
+    ///
+    /// Threads are indexed in batches with a commit after each, so searches
+    /// see a growing partial index while the rebuild runs. Intended to run on
+    /// a background thread; sync may keep indexing concurrently.
+    ///
+    /// # Arguments
+    /// * `store` - The storage backend to read messages from
+    /// * `on_progress` - Called with (threads_done, threads_total) after each batch
+    ///
+    /// Returns the number of messages indexed.
+    pub fn rebuild_from_store<F>(&self, store: &dyn MailStore, mut on_progress: F) -> Result<usize>
+    where
+        F: FnMut(usize, usize),
+    {
+        self.clear()?;
+
+        let total = store.count_threads()?;
+        let mut threads_done = 0;
         let mut count = 0;
-        let threads = store.list_threads(100_000, 0)?;
+        let mut cursor: Option<ThreadCursor> = None;
+
+        loop {
+            let batch = store.list_threads_after(None, None, cursor.as_ref(), REBUILD_BATCH_SIZE)?;
+            let exhausted = batch.len() < REBUILD_BATCH_SIZE;
+
+            for thread in &batch {
+                let messages = store.list_messages_for_thread_with_bodies(&thread.id)?;
+                for message in &messages {
+                    self.index_message(message, thread)?;
+                    count += 1;
+                }
+                if let Some(note) = store.get_note(&thread.id)? {
+                    self.index_note(thread, Some(&note.markdown))?;
+                }
+            }
+
+            self.commit()?;
+            threads_done += batch.len();
+            on_progress(threads_done, total.max(threads_done));
 
-        for thread in threads {
-            let messages = store.list_messages_for_thread_with_bodies(&thread.id)?;
-            for message in &messages {
-                self.index_message(message, &thread)?;
-                count += 1;
+            cursor = batch.last().map(ThreadCursor::for_thread);
+            if exhausted || cursor.is_none() {
+                break;
             }
         }
 
-        self.commit()?;
+        self.needs_rebuild.store(false, Ordering::SeqCst);
         Ok(count)
     }
+
+    /// IDs of all indexed messages
+    ///
+    /// A message indexed twice mid-upsert is listed once. Note documents are
+    /// not messages and are left out.
+    pub fn indexed_message_ids(&self) -> Result<HashSet<MessageId>> {
+        let searcher = self.reader.searcher();
+        let doc_addresses = searcher.search(&tantivy::query::AllQuery, &DocSetCollector)?;
+        let mut indexed = HashSet::new();
+        for doc_address in doc_addresses {
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+            if let Some(id) = doc.get_first(self.fields.message_id).and_then(|v| v.as_str())
+                && !id.starts_with(NOTE_DOCUMENT_PREFIX)
+            {
+                indexed.insert(MessageId::new(id));
+            }
+        }
+        Ok(indexed)
+    }
+
+    /// Check the index for corruption and drift from the store
+    ///
+    /// Validates Tantivy's per-file checksums, then compares an
+    /// order-independent checksum of indexed message IDs with the same
+    /// checksum over the store's messages. Reads every stored document, so
+    /// run it off the UI thread.
+    pub fn verify(&self, store: &dyn MailStore) -> Result<IndexVerification> {
+        let mut corrupted_files: Vec<_> = self
+            .index
+            .validate_checksum()
+            .context("Failed to validate index checksums")?
+            .into_iter()
+            .collect();
+        corrupted_files.sort();
+
+        let indexed = self.indexed_message_ids()?;
+        let index_checksum = indexed
+            .iter()
+            .fold(0u64, |acc, id| acc.wrapping_add(fnv1a(id.as_str())));
+
+        // Stored message IDs
+        let mut stored_messages = 0;
+        let mut store_checksum = 0u64;
+        let mut cursor: Option<ThreadCursor> = None;
+        loop {
+            let batch = store.list_threads_after(None, None, cursor.as_ref(), REBUILD_BATCH_SIZE)?;
+            let exhausted = batch.len() < REBUILD_BATCH_SIZE;
+
+            for thread in &batch {
+                for id in store.get_message_ids_for_thread(&thread.id)? {
+                    stored_messages += 1;
+                    store_checksum = store_checksum.wrapping_add(fnv1a(id.as_str()));
+                }
+            }
+
+            cursor = batch.last().map(ThreadCursor::for_thread);
+            if exhausted || cursor.is_none() {
+                break;
+            }
+        }
+
+        Ok(IndexVerification {
+            corrupted_files,
+            indexed_messages: indexed.len(),
+            stored_messages,
+            index_checksum,
+            store_checksum,
+        })
+    }
+}
+
+/// Resolve `account:` values to account IDs, if the query uses them
+pub(super) fn with_account_ids<'a>(
+    query: &'a ParsedQuery,
+    store: &dyn MailStore,
+) -> Result<Cow<'a, ParsedQuery>> {
+    if !query.mentions_account() {
+        return Ok(Cow::Borrowed(query));
+    }
+    Ok(Cow::Owned(query.resolve_accounts(&store.list_accounts()?)))
+}
+
+/// Discard the index at `path` and create an empty one
+/// Prefix of the `message_id` of note documents; Gmail IDs are hex
+const NOTE_DOCUMENT_PREFIX: &str = "note:";
+
+/// The `message_id` of a thread's note document
+fn note_document_id(thread_id: &ThreadId) -> String {
+    format!("{}{}", NOTE_DOCUMENT_PREFIX, thread_id.as_str())
+}
+
+fn recreate_index(path: &Path, schema: &Schema) -> Result<Index> {
+    std::fs::remove_dir_all(path).context("Failed to remove outdated index")?;
+    std::fs::create_dir_all(path).context("Failed to create index directory")?;
+    Index::create_in_dir(path, schema.clone()).context("Failed to create index")
+}
+
+/// Read the schema version recorded in an index directory
+fn read_schema_version(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path.join(SCHEMA_VERSION_FILE))
+        .ok()
+        .and_then(|content| content.trim().parse().ok())
+}
+
+/// Record the current schema version in an index directory
+fn write_schema_version(path: &Path) -> Result<()> {
+    std::fs::write(path.join(SCHEMA_VERSION_FILE), SCHEMA_VERSION.to_string())
+        .context("Failed to write index schema version")
+}
+
+/// 64-bit FNV-1a hash (stable across runs, unlike `DefaultHasher`)
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Query requiring every token to start a word in one of `fields`
+fn prefix_query(fields: &[Field], tokens: &[String]) -> BooleanQuery {
+    let clauses = tokens
+        .iter()
+        .map(|token| {
+            let alternatives: Vec<(Occur, Box<dyn Query>)> = fields
+                .iter()
+                .map(|&field| {
+                    let term = Term::from_field_text(field, token);
+                    let query: Box<dyn Query> =
+                        Box::new(FuzzyTermQuery::new_prefix(term, 0, false));
+                    (Occur::Should, query)
+                })
+                .collect();
+            let query: Box<dyn Query> = Box::new(BooleanQuery::new(alternatives));
+            (Occur::Must, query)
+        })
+        .collect();
+    BooleanQuery::new(clauses)
+}
+
+/// Check that every token starts a word of the contact's name or address
+fn contact_matches(address: &EmailAddress, tokens: &[String]) -> bool {
+    let haystack = format!(
+        "{} {}",
+        address.name.as_deref().unwrap_or_default(),
+        address.email
+    )
+    .to_lowercase();
+    let words: Vec<&str> = haystack
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    tokens
+        .iter()
+        .all(|token| words.iter().any(|word| word.starts_with(token.as_str())))
+}
+
+/// Strip leading "Re:", "Fwd:", and "Fw:" markers from a subject
+fn strip_reply_prefixes(subject: &str) -> &str {
+    let mut rest = subject.trim();
+    while let Some(marker) = ["re:", "fwd:", "fw:"].iter().find(|marker| {
+        rest.get(..marker.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(marker))
+    }) {
+        rest = rest[marker.len()..].trim_start();
+    }
+    rest
+}
+
+/// Convert a parsed size to the indexed i64, saturating on overflow
+fn size_to_i64(size: u64) -> i64 {
+    i64::try_from(size).unwrap_or(i64::MAX)
+}
+
+/// Combine clauses into a single query
+///
+/// Empty clauses match everything. A purely negative clause list needs an
+/// explicit match-all, since Tantivy matches nothing without a positive clause.
+fn combine_clauses(mut clauses: Vec<(Occur, Box<dyn Query>)>) -> Box<dyn Query> {
+    if clauses.is_empty() {
+        // Match all if no constraints
+        return Box::new(tantivy::query::AllQuery);
+    }
+    if clauses.iter().all(|(occur, _)| *occur == Occur::MustNot) {
+        clauses.push((Occur::Must, Box::new(tantivy::query::AllQuery)));
+    }
+    Box::new(BooleanQuery::new(clauses))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{EmailAddress, Message, MessageId};
+    use crate::models::{Account, EmailAddress, Message, MessageId};
+    use crate::search::InstantSearchOptions;
     use crate::storage::InMemoryMailStore;
     use chrono::Utc;
 
@@ -512,6 +1412,7 @@ mod tests {
             sender_name: Some("Sender".to_string()),
             sender_email: "sender@example.com".to_string(),
             is_unread: false,
+            participants: Vec::new(),
         }
     }
 
@@ -542,6 +1443,30 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_commit_if_due_coalesces_writes() -> Result<()> {
+        let index = SearchIndex::in_memory()?.with_config(SearchConfig {
+            commit_interval_secs: 3600,
+            commit_max_docs: 2,
+            ..SearchConfig::default()
+        });
+        let thread = create_test_thread("thread1", "Quarterly report");
+
+        let first = create_test_message("msg1", "thread1", "Quarterly report", "Draft");
+        index.index_message(&first, &thread)?;
+        assert!(!index.commit_if_due()?);
+        assert!(index.indexed_message_ids()?.is_empty());
+
+        let second = create_test_message("msg2", "thread1", "Re: Quarterly report", "Final");
+        index.index_message(&second, &thread)?;
+        assert!(index.commit_if_due()?);
+        assert_eq!(index.indexed_message_ids()?.len(), 2);
+
+        // Nothing new to commit
+        assert!(!index.commit_if_due()?);
+        Ok(())
+    }
+
     #[test]
     fn test_search_with_from_filter() -> Result<()> {
         let index = SearchIndex::in_memory()?;
@@ -596,6 +1521,247 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_search_boolean_composition() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+        let store = InMemoryMailStore::new();
+
+        for (id, from, labels) in [
+            ("t1", "alice@example.com", vec!["INBOX"]),
+            ("t2", "bob@example.com", vec!["INBOX", "CATEGORY_PROMOTIONS"]),
+            ("t3", "carol@example.com", vec!["INBOX"]),
+        ] {
+            let thread = create_test_thread(id, "Update");
+            let mut message = create_test_message(&format!("m_{}", id), id, "Update", "Body");
+            message.from = EmailAddress::new(from);
+            message.label_ids = labels.into_iter().map(String::from).collect();
+            store.upsert_thread(thread.clone())?;
+            store.upsert_message(message.clone())?;
+            index.index_message(&message, &thread)?;
+        }
+        index.commit()?;
+
+        let ids = |q: &str| -> Result<Vec<String>> {
+            let mut ids: Vec<_> = index
+                .search(&super::super::parse_query(q), 10, &store, None)?
+                .into_iter()
+                .map(|r| r.thread_id.as_str().to_string())
+                .collect();
+            ids.sort();
+            Ok(ids)
+        };
+
+        assert_eq!(ids("from:alice OR from:bob")?, vec!["t1", "t2"]);
+        assert_eq!(ids("-label:promotions")?, vec!["t1", "t3"]);
+        assert_eq!(ids("(from:alice OR from:bob) -label:promotions")?, vec!["t1"]);
+        assert_eq!(ids("-(from:alice OR from:bob)")?, vec!["t3"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_attachment_and_size() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+        let store = InMemoryMailStore::new();
+
+        for (id, names, size) in [
+            ("t1", vec!["Q3 report.pdf"], 6 * 1024 * 1024),
+            ("t2", vec!["photo.jpg", "notes.txt"], 300 * 1024),
+            ("t3", vec![], 4 * 1024),
+        ] {
+            let thread = create_test_thread(id, "Files");
+            let mut message = create_test_message(&format!("m_{}", id), id, "Files", "Body");
+            message.has_attachments = !names.is_empty();
+            message.attachment_names = names.into_iter().map(String::from).collect();
+            message.size_bytes = size;
+            store.upsert_thread(thread.clone())?;
+            store.upsert_message(message.clone())?;
+            index.index_message(&message, &thread)?;
+        }
+        index.commit()?;
+
+        let ids = |q: &str| -> Result<Vec<String>> {
+            let mut ids: Vec<_> = index
+                .search(&super::super::parse_query(q), 10, &store, None)?
+                .into_iter()
+                .map(|r| r.thread_id.as_str().to_string())
+                .collect();
+            ids.sort();
+            Ok(ids)
+        };
+
+        assert_eq!(ids("has:attachment")?, vec!["t1", "t2"]);
+        assert_eq!(ids("filename:pdf")?, vec!["t1"]);
+        assert_eq!(ids("filename:\"report.pdf\"")?, vec!["t1"]);
+        assert_eq!(ids("filename:txt OR filename:pdf")?, vec!["t1", "t2"]);
+        assert_eq!(ids("larger:5M")?, vec!["t1"]);
+        assert_eq!(ids("smaller:1M")?, vec!["t2", "t3"]);
+        assert_eq!(ids("larger:100K smaller:1M")?, vec!["t2"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_attachment_text() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+        let store = InMemoryMailStore::new();
+
+        let thread = create_test_thread("t1", "Files");
+        let mut message = create_test_message("m1", "t1", "Files", "See attached");
+        message.attachment_text = Some("Invoice 4821 due on receipt".to_string());
+        store.upsert_thread(thread.clone())?;
+        store.upsert_message(message.clone())?;
+        index.index_message(&message, &thread)?;
+        index.commit()?;
+
+        let results = |q: &str| index.search(&super::super::parse_query(q), 10, &store, None);
+        assert_eq!(results("invoice 4821")?.len(), 1);
+        assert_eq!(results("receipt")?.len(), 1);
+
+        // Relabeling rewrites the document from stored fields
+        let labels = HashMap::from([(message.id.clone(), vec!["STARRED".to_string()])]);
+        index.update_labels(&thread.id, &labels)?;
+        index.commit()?;
+        assert_eq!(results("invoice 4821")?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_notes() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+        let store = InMemoryMailStore::new();
+
+        let thread = create_test_thread("t1", "Contract");
+        let message = create_test_message("m1", "t1", "Contract", "Draft attached");
+        store.upsert_thread(thread.clone())?;
+        store.upsert_message(message.clone())?;
+        index.index_message(&message, &thread)?;
+        index.index_note(&thread, Some("Waiting on legal review"))?;
+        index.commit()?;
+
+        let results = |q: &str| index.search(&super::super::parse_query(q), 10, &store, None);
+        assert_eq!(results("legal")?.len(), 1);
+        assert_eq!(
+            index.indexed_message_ids()?,
+            HashSet::from([MessageId::new("m1")])
+        );
+
+        index.index_note(&thread, Some("Signed"))?;
+        index.commit()?;
+        assert!(results("legal")?.is_empty());
+        assert_eq!(results("signed")?.len(), 1);
+
+        // Rebuilding picks notes up from the store
+        store.set_note(&thread.id, "Ask finance")?;
+        index.rebuild(&store)?;
+        assert_eq!(results("finance")?.len(), 1);
+
+        index.index_note(&thread, None)?;
+        index.commit()?;
+        assert!(results("finance")?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_instant_search_prefix_and_fuzzy() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+        let store = InMemoryMailStore::new();
+
+        for (id, subject, from) in [
+            ("t1", "Quarterly planning", "alice@example.com"),
+            ("t2", "Quartz countertop quote", "bob@example.com"),
+            ("t3", "Lunch", "alice@example.com"),
+        ] {
+            let thread = create_test_thread(id, subject);
+            let mut message = create_test_message(&format!("m_{}", id), id, subject, "Body");
+            message.from = EmailAddress::new(from);
+            store.upsert_thread(thread.clone())?;
+            store.upsert_message(message.clone())?;
+            index.index_message(&message, &thread)?;
+        }
+        index.commit()?;
+
+        let ids = |q: &str, fuzzy: bool| -> Result<Vec<String>> {
+            let options = InstantSearchOptions {
+                fuzzy,
+                ..Default::default()
+            };
+            let mut ids: Vec<_> = index
+                .instant_search(&super::super::parse_query(q), 10, &store, None, &options)?
+                .results
+                .into_iter()
+                .map(|r| r.thread_id.as_str().to_string())
+                .collect();
+            ids.sort();
+            Ok(ids)
+        };
+
+        // The word being typed matches as a prefix
+        assert_eq!(ids("quar", false)?, vec!["t1", "t2"]);
+        assert_eq!(ids("quarte", false)?, vec!["t1"]);
+        // Earlier words must match in full
+        assert_eq!(ids("quar plan", false)?, Vec::<String>::new());
+        assert_eq!(ids("quarterly plan", false)?, vec!["t1"]);
+        // One typo is tolerated for longer words only when fuzzy
+        assert_eq!(ids("quartelry", false)?, Vec::<String>::new());
+        assert_eq!(ids("quartelry", true)?, vec!["t1"]);
+        assert_eq!(ids("quarterlx planning", true)?, vec!["t1"]);
+        assert_eq!(ids("lunc", true)?, vec!["t3"]);
+        // Operators keep exact semantics
+        assert_eq!(ids("from:alice qu", true)?, vec!["t1"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_instant_search_budget() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+        let store = InMemoryMailStore::new();
+
+        for i in 0..5 {
+            let id = format!("t{}", i);
+            let thread = create_test_thread(&id, "Status report");
+            let message = create_test_message(&format!("m{}", i), &id, "Status report", "Body");
+            store.upsert_thread(thread.clone())?;
+            store.upsert_message(message.clone())?;
+            index.index_message(&message, &thread)?;
+        }
+        index.commit()?;
+
+        // An exhausted budget still returns the best match
+        let options = InstantSearchOptions {
+            fuzzy: false,
+            budget: std::time::Duration::ZERO,
+        };
+        let instant = index.instant_search(
+            &super::super::parse_query("stat"),
+            10,
+            &store,
+            None,
+            &options,
+        )?;
+        assert_eq!(instant.results.len(), 1);
+        assert!(instant.truncated);
+
+        let options = InstantSearchOptions {
+            fuzzy: false,
+            budget: std::time::Duration::from_secs(10),
+        };
+        let relaxed = index.instant_search(
+            &super::super::parse_query("stat"),
+            10,
+            &store,
+            None,
+            &options,
+        )?;
+        assert_eq!(relaxed.results.len(), 5);
+        assert!(!relaxed.truncated);
+
+        Ok(())
+    }
+
     #[test]
     fn test_search_deduplication() -> Result<()> {
         let index = SearchIndex::in_memory()?;
@@ -651,6 +1817,179 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_field_boosts_and_recency() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+        let store = InMemoryMailStore::new();
+
+        let add = |id: &str, subject: &str, body: &str, age_days: i64| -> Result<()> {
+            let thread = create_test_thread(id, subject);
+            store.upsert_thread(thread.clone())?;
+            let mut message = create_test_message(&format!("m_{}", id), id, subject, body);
+            message.received_at = Utc::now() - chrono::Duration::days(age_days);
+            index.index_message(&message, &thread)?;
+            Ok(())
+        };
+        add("subject_hit", "Roadmap", "Agenda attached", 1)?;
+        add("body_hit", "Agenda", "Draft roadmap attached", 1)?;
+        add("recent", "Kickoff", "Offsite planning notes", 7)?;
+        add("ancient", "Kickoff", "Offsite planning notes", 3650)?;
+        index.commit()?;
+
+        let ids = |query: &str| -> Result<Vec<String>> {
+            let results = index.search(&super::super::parse_query(query), 10, &store, None)?;
+            Ok(results.into_iter().map(|r| r.thread_id.as_str().to_string()).collect())
+        };
+        assert_eq!(ids("roadmap")?, vec!["subject_hit", "body_hit"]);
+        assert_eq!(ids("offsite")?, vec!["recent", "ancient"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_account_scoping() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+        let store = InMemoryMailStore::new();
+        let personal = store.register_account(Account::new("me@example.com"))?;
+        let work = store.register_account(Account::new("me@work.example"))?;
+
+        for (id, account_id) in [("t_personal", personal.id), ("t_work", work.id)] {
+            let mut thread = create_test_thread(id, "Status update");
+            thread.account_id = account_id;
+            store.upsert_thread(thread.clone())?;
+            let mut message = create_test_message(&format!("m_{}", id), id, "Status update", "");
+            message.account_id = account_id;
+            index.index_message(&message, &thread)?;
+        }
+        index.commit()?;
+
+        let ids = |query: &str, account_id: Option<i64>| -> Result<Vec<String>> {
+            let query = super::super::parse_query(query);
+            let mut ids: Vec<String> = index
+                .search(&query, 10, &store, account_id)?
+                .into_iter()
+                .map(|r| r.thread_id.as_str().to_string())
+                .collect();
+            ids.sort();
+            Ok(ids)
+        };
+
+        // Unified view sees both; a selected account scopes implicitly
+        assert_eq!(ids("status", None)?, vec!["t_personal", "t_work"]);
+        assert_eq!(ids("status", Some(work.id))?, vec!["t_work"]);
+
+        // account: resolves addresses and IDs, and composes with the UI scope
+        assert_eq!(ids("status account:ME@WORK.example", None)?, vec!["t_work"]);
+        assert_eq!(ids(&format!("status account:{}", personal.id), None)?, vec!["t_personal"]);
+        assert_eq!(ids("status -account:me@work.example", None)?, vec!["t_personal"]);
+        assert!(ids("status account:me@example.com", Some(work.id))?.is_empty());
+        assert!(ids("status account:nobody@example.com", None)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_page() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+        let store = InMemoryMailStore::new();
+
+        // Five threads, the first with two matching messages
+        for i in 0..5 {
+            let thread_id = format!("thread{}", i);
+            let thread = create_test_thread(&thread_id, "Invoice");
+            store.upsert_thread(thread.clone())?;
+            let message = create_test_message(&format!("msg{}", i), &thread_id, "Invoice", "Due");
+            index.index_message(&message, &thread)?;
+        }
+        let extra = create_test_message("msg0b", "thread0", "Re: Invoice", "Paid");
+        index.index_message(&extra, &create_test_thread("thread0", "Invoice"))?;
+        index.commit()?;
+
+        let query = super::super::parse_query("invoice");
+        let first = index.search_page(&query, 0, 2, &store, None)?;
+        assert_eq!(first.total, 5);
+        assert!(!first.total_is_estimate);
+        assert_eq!(first.results.len(), 2);
+        assert_eq!(first.next_offset, Some(2));
+
+        let mut seen: Vec<_> = first.results.iter().map(|r| r.thread_id.clone()).collect();
+        let mut offset = first.next_offset;
+        while let Some(next) = offset {
+            let page = index.search_page(&query, next, 2, &store, None)?;
+            seen.extend(page.results.iter().map(|r| r.thread_id.clone()));
+            offset = page.next_offset;
+        }
+
+        // Every thread appears exactly once across pages
+        assert_eq!(seen.len(), 5);
+        let distinct: HashSet<_> = seen.iter().collect();
+        assert_eq!(distinct.len(), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_labels() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+        let store = InMemoryMailStore::new();
+
+        let thread = create_test_thread("thread1", "Quarterly report");
+        let msg1 = create_test_message("msg1", "thread1", "Quarterly report", "Numbers");
+        let msg2 = create_test_message("msg2", "thread1", "Re: Quarterly report", "Thanks");
+        store.upsert_thread(thread.clone())?;
+        index.index_message(&msg1, &thread)?;
+        index.index_message(&msg2, &thread)?;
+        index.commit()?;
+
+        let count = |query: &str| -> Result<usize> {
+            index.count_threads(&super::super::parse_query(query), None)
+        };
+        assert_eq!(count("in:inbox")?, 1);
+
+        // Archive the thread and star one message
+        let labels = HashMap::from([
+            (msg1.id.clone(), vec!["STARRED".to_string()]),
+            (msg2.id.clone(), Vec::new()),
+        ]);
+        assert_eq!(index.update_labels(&thread.id, &labels)?, 2);
+        index.commit()?;
+
+        assert_eq!(count("in:inbox")?, 0);
+        assert_eq!(count("is:starred")?, 1);
+        // Text fields survive the rewrite
+        assert_eq!(count("quarterly numbers")?, 1);
+        assert_eq!(count("from:sender")?, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_message_and_thread() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+
+        let thread = create_test_thread("thread1", "Cleanup");
+        let msg1 = create_test_message("msg1", "thread1", "Cleanup", "alpha");
+        let msg2 = create_test_message("msg2", "thread1", "Cleanup", "beta");
+        index.index_message(&msg1, &thread)?;
+        index.index_message(&msg2, &thread)?;
+        index.commit()?;
+
+        let count = |query: &str| -> Result<usize> {
+            index.count_threads(&super::super::parse_query(query), None)
+        };
+
+        index.delete_message(&msg1.id)?;
+        index.commit()?;
+        assert_eq!(count("alpha")?, 0);
+        assert_eq!(count("beta")?, 1);
+
+        index.delete_thread(&thread.id)?;
+        index.commit()?;
+        assert_eq!(count("cleanup")?, 0);
+
+        Ok(())
+    }
+
     #[test]
     fn test_rebuild() -> Result<()> {
         let index = SearchIndex::in_memory()?;
@@ -674,4 +2013,107 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_open_recreates_outdated_schema() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        // An index written by an older build with fewer fields
+        let mut old_schema = Schema::builder();
+        old_schema.add_text_field("thread_id", tantivy::schema::STRING);
+        Index::create_in_dir(dir.path(), old_schema.build())?;
+
+        let index = SearchIndex::open(dir.path())?;
+        assert_eq!(index.index.schema(), build_schema());
+        assert!(index.needs_rebuild());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_checks_schema_version() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = InMemoryMailStore::new();
+        store.upsert_thread(create_test_thread("thread1", "Versioned"))?;
+        store.upsert_message(create_test_message("msg1", "thread1", "Versioned", "Body"))?;
+
+        {
+            let index = SearchIndex::open(dir.path())?;
+            assert!(index.needs_rebuild());
+            index.rebuild_from_store(&store, |_, _| {})?;
+            assert!(!index.needs_rebuild());
+        }
+
+        // Reopening at the same version keeps the documents
+        {
+            let index = SearchIndex::open(dir.path())?;
+            assert!(!index.needs_rebuild());
+            let results = index.search(&super::super::parse_query("versioned"), 10, &store, None)?;
+            assert_eq!(results.len(), 1);
+        }
+
+        // A different recorded version discards the index
+        std::fs::write(dir.path().join(SCHEMA_VERSION_FILE), "1")?;
+        let index = SearchIndex::open(dir.path())?;
+        assert!(index.needs_rebuild());
+        let results = index.search(&super::super::parse_query("versioned"), 10, &store, None)?;
+        assert!(results.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rebuild_from_store_reports_progress() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+        let store = InMemoryMailStore::new();
+
+        let thread_count = REBUILD_BATCH_SIZE + 5;
+        for i in 0..thread_count {
+            let thread_id = format!("thread{}", i);
+            store.upsert_thread(create_test_thread(&thread_id, "Progress"))?;
+            let message = create_test_message(&format!("msg{}", i), &thread_id, "Progress", "Body");
+            store.upsert_message(message)?;
+        }
+
+        let mut updates = Vec::new();
+        let count = index.rebuild_from_store(&store, |done, total| updates.push((done, total)))?;
+
+        assert_eq!(count, thread_count);
+        assert_eq!(
+            updates,
+            vec![(REBUILD_BATCH_SIZE, thread_count), (thread_count, thread_count)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_detects_missing_messages() -> Result<()> {
+        let index = SearchIndex::in_memory()?;
+        let store = InMemoryMailStore::new();
+
+        let thread = create_test_thread("thread1", "Verify");
+        let msg1 = create_test_message("msg1", "thread1", "Verify", "First");
+        let msg2 = create_test_message("msg2", "thread1", "Verify", "Second");
+        store.upsert_thread(thread.clone())?;
+        store.upsert_message(msg1.clone())?;
+        store.upsert_message(msg2)?;
+
+        // Only one of the two stored messages is indexed
+        index.index_message(&msg1, &thread)?;
+        index.commit()?;
+
+        let report = index.verify(&store)?;
+        assert!(!report.is_healthy());
+        assert!(report.corrupted_files.is_empty());
+        assert_eq!(report.indexed_messages, 1);
+        assert_eq!(report.stored_messages, 2);
+
+        index.rebuild(&store)?;
+        let report = index.verify(&store)?;
+        assert!(report.is_healthy());
+        assert_eq!(report.index_checksum, report.store_checksum);
+
+        Ok(())
+    }
 }
@@ -2,18 +2,38 @@
 //!
 //! Provides Gmail-style search with operators like `from:`, `to:`, `subject:`,
 //! `is:unread`, `in:inbox`, `before:`, `after:`, etc.
+//!
+//! With the `fts5` feature, `FtsSearchIndex` provides the same operations on
+//! SQLite FTS5 instead; both implement [`SearchBackend`].
 
+mod backend;
+#[cfg(feature = "fts5")]
+mod fts;
+mod highlight;
 mod index;
 mod query_parser;
 mod schema;
+mod scoring;
+mod suggest;
 
+pub use backend::SearchBackend;
+#[cfg(feature = "fts5")]
+pub use fts::FtsSearchIndex;
+pub use highlight::{body_highlights, find_term_spans, highlight_html, HIGHLIGHT_CLASS};
 pub use index::SearchIndex;
 pub use query_parser::{parse_query, ParsedQuery};
+pub use scoring::{SearchConfig, SEARCH_CONFIG_FILE};
+pub use suggest::{suggest, SearchSuggestion, SuggestionKind};
+
+use std::time::Duration;
 
 use crate::models::ThreadId;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+/// Default time allowed for an instant search before partial results are returned
+pub const DEFAULT_INSTANT_SEARCH_BUDGET: Duration = Duration::from_millis(50);
+
 /// A highlighted text span within a field
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HighlightSpan {
@@ -59,6 +79,72 @@ pub struct SearchResult {
     pub score: f32,
 }
 
+/// One page of search results plus the overall hit count
+#[derive(Debug, Clone)]
+pub struct SearchPage {
+    /// Matching threads on this page, best first
+    pub results: Vec<SearchResult>,
+    /// Number of matching threads across all pages
+    pub total: usize,
+    /// Whether `total` is an upper-bound estimate rather than an exact count
+    pub total_is_estimate: bool,
+    /// Offset to pass for the next page, or None if this is the last one
+    pub next_offset: Option<usize>,
+}
+
+/// Result of checking a search index against the mail store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexVerification {
+    /// Index files whose contents no longer match their stored checksum
+    pub corrupted_files: Vec<std::path::PathBuf>,
+    /// Distinct messages in the index
+    pub indexed_messages: usize,
+    /// Messages in the store
+    pub stored_messages: usize,
+    /// Order-independent checksum of indexed message IDs
+    pub index_checksum: u64,
+    /// Order-independent checksum of stored message IDs
+    pub store_checksum: u64,
+}
+
+impl IndexVerification {
+    /// Whether the index is intact and covers exactly the stored messages
+    pub fn is_healthy(&self) -> bool {
+        self.corrupted_files.is_empty()
+            && self.indexed_messages == self.stored_messages
+            && self.index_checksum == self.store_checksum
+    }
+}
+
+/// Options for search-as-you-type queries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstantSearchOptions {
+    /// Also match terms of five or more characters within one edit
+    pub fuzzy: bool,
+    /// Time allowed before returning the results gathered so far
+    pub budget: Duration,
+}
+
+impl Default for InstantSearchOptions {
+    fn default() -> Self {
+        Self {
+            fuzzy: true,
+            budget: DEFAULT_INSTANT_SEARCH_BUDGET,
+        }
+    }
+}
+
+/// Results of an instant search
+#[derive(Debug, Clone)]
+pub struct InstantSearchResults {
+    /// Matching threads, best first
+    pub results: Vec<SearchResult>,
+    /// Whether the latency budget cut collection short
+    pub truncated: bool,
+    /// Time spent searching
+    pub elapsed: Duration,
+}
+
 /// Search threads by query string
 ///
 /// This is the main entry point for searching. It parses the query string,
@@ -104,6 +190,55 @@ pub fn search_threads_for_account(
     index.search(&parsed, limit, store, account_id)
 }
 
+/// Fetch one page of search results with the total hit count
+///
+/// Pages are ordered by relevance and contain distinct threads, so passing
+/// `next_offset` back in continues where the previous page ended.
+///
+/// # Arguments
+/// * `index` - The search index to query
+/// * `store` - Mail store for fetching thread metadata
+/// * `query` - Search query string (supports Gmail-style operators)
+/// * `offset` - Number of matching threads to skip
+/// * `limit` - Maximum number of results on this page
+/// * `account_id` - Optional account ID to filter results (None = all accounts)
+pub fn search_threads_page(
+    index: &SearchIndex,
+    store: &dyn crate::storage::MailStore,
+    query: &str,
+    offset: usize,
+    limit: usize,
+    account_id: Option<i64>,
+) -> anyhow::Result<SearchPage> {
+    let parsed = parse_query(query);
+    index.search_page(&parsed, offset, limit, store, account_id)
+}
+
+/// Search threads as the user types
+///
+/// Like `search_threads_for_account`, but the last word matches as a prefix
+/// and, optionally, longer words tolerate a typo. Intended for updating
+/// results on every keystroke; run the full search on submit.
+///
+/// # Arguments
+/// * `index` - The search index to query
+/// * `store` - Mail store for fetching thread metadata
+/// * `query` - Partial search query string
+/// * `limit` - Maximum number of results to return
+/// * `account_id` - Optional account ID to filter results (None = all accounts)
+/// * `options` - Fuzzy matching and latency budget
+pub fn instant_search(
+    index: &SearchIndex,
+    store: &dyn crate::storage::MailStore,
+    query: &str,
+    limit: usize,
+    account_id: Option<i64>,
+    options: &InstantSearchOptions,
+) -> anyhow::Result<InstantSearchResults> {
+    let parsed = parse_query(query);
+    index.instant_search(&parsed, limit, store, account_id, options)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -4,14 +4,27 @@
 //! - `from:john@example.com` - sender filter
 //! - `to:team@company.com` - recipient filter
 //! - `subject:meeting` - subject filter
-//! - `in:inbox` - label filter
+//! - `in:inbox`, `label:work` - label filter
 //! - `is:unread`, `is:read`, `is:starred` - boolean filters
 //! - `has:attachment` - attachment filter
+//! - `filename:pdf` - attachment name or extension filter
+//! - `larger:5M`, `smaller:100K` - message size filters
 //! - `before:2024/12/01`, `after:2024/01/01` - date filters
+//! - `account:work@example.com` - account filter (address, name, or ID)
+//!
+//! Clauses can be combined with `OR`, negated with a leading `-`, and
+//! grouped with parentheses. As in Gmail, `OR` binds tighter than the
+//! implicit AND between adjacent clauses, so `a b OR c` means `a AND (b OR c)`.
 
 use chrono::{DateTime, NaiveDate, TimeZone, Utc};
 
+use crate::models::Account;
+
 /// Parsed query with structured components
+///
+/// All populated fields must match (AND). `any_of` and `excluded` carry the
+/// boolean structure: each `any_of` group needs one matching alternative,
+/// and no `excluded` clause may match.
 #[derive(Debug, Clone, Default, PartialEq)]
 pub struct ParsedQuery {
     /// Free-text search terms
@@ -30,10 +43,22 @@ pub struct ParsedQuery {
     pub is_starred: Option<bool>,
     /// has:attachment
     pub has_attachment: Option<bool>,
+    /// filename: attachment name filter values
+    pub filename: Vec<String>,
+    /// larger: minimum message size in bytes (exclusive)
+    pub larger: Option<u64>,
+    /// smaller: maximum message size in bytes (exclusive)
+    pub smaller: Option<u64>,
     /// before: date filter
     pub before: Option<DateTime<Utc>>,
     /// after: date filter
     pub after: Option<DateTime<Utc>>,
+    /// account: filter values (account ID once resolved, see `resolve_accounts`)
+    pub account: Vec<String>,
+    /// OR groups: at least one alternative in each group must match
+    pub any_of: Vec<Vec<ParsedQuery>>,
+    /// Negated clauses: none may match
+    pub excluded: Vec<ParsedQuery>,
 }
 
 impl ParsedQuery {
@@ -47,27 +72,220 @@ impl ParsedQuery {
             && self.is_unread.is_none()
             && self.is_starred.is_none()
             && self.has_attachment.is_none()
+            && self.filename.is_empty()
+            && self.larger.is_none()
+            && self.smaller.is_none()
             && self.before.is_none()
             && self.after.is_none()
+            && self.account.is_empty()
+            && self.any_of.is_empty()
+            && self.excluded.is_empty()
+    }
+
+    /// Free-text terms that can contribute to a match, for highlighting
+    ///
+    /// Includes terms inside OR alternatives but not negated terms.
+    pub fn positive_terms(&self) -> Vec<String> {
+        let mut terms = self.terms.clone();
+        for group in &self.any_of {
+            for alternative in group {
+                terms.extend(alternative.positive_terms());
+            }
+        }
+        terms
+    }
+
+    /// Whether `account:` appears anywhere in the query
+    pub fn mentions_account(&self) -> bool {
+        !self.account.is_empty()
+            || self.any_of.iter().flatten().any(ParsedQuery::mentions_account)
+            || self.excluded.iter().any(ParsedQuery::mentions_account)
+    }
+
+    /// Replace `account:` values with the matching account IDs
+    ///
+    /// A value matches an account by email address or display name
+    /// (case-insensitive) or by numeric ID. Values matching no account are
+    /// kept as-is and match no messages.
+    pub fn resolve_accounts(&self, accounts: &[Account]) -> ParsedQuery {
+        let mut resolved = self.clone();
+        for value in &mut resolved.account {
+            let matched = accounts.iter().find(|account| {
+                account.email.eq_ignore_ascii_case(value)
+                    || account.id.to_string() == *value
+                    || account
+                        .display_name
+                        .as_deref()
+                        .is_some_and(|name| name.eq_ignore_ascii_case(value))
+            });
+            if let Some(account) = matched {
+                *value = account.id.to_string();
+            }
+        }
+        for group in &mut resolved.any_of {
+            for alternative in group.iter_mut() {
+                *alternative = alternative.resolve_accounts(accounts);
+            }
+        }
+        for excluded in &mut resolved.excluded {
+            *excluded = excluded.resolve_accounts(accounts);
+        }
+        resolved
+    }
+
+    /// AND another query into this one
+    fn merge(&mut self, other: ParsedQuery) {
+        self.terms.extend(other.terms);
+        self.from.extend(other.from);
+        self.to.extend(other.to);
+        self.subject.extend(other.subject);
+        self.in_label = other.in_label.or(self.in_label.take());
+        self.is_unread = other.is_unread.or(self.is_unread);
+        self.is_starred = other.is_starred.or(self.is_starred);
+        self.has_attachment = other.has_attachment.or(self.has_attachment);
+        self.filename.extend(other.filename);
+        self.larger = other.larger.or(self.larger);
+        self.smaller = other.smaller.or(self.smaller);
+        self.before = other.before.or(self.before);
+        self.after = other.after.or(self.after);
+        self.account.extend(other.account);
+        self.any_of.extend(other.any_of);
+        self.excluded.extend(other.excluded);
     }
 }
 
+/// Lexical token in a query string
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// `(`
+    Open,
+    /// `)`
+    Close,
+    /// `OR` (uppercase, as in Gmail)
+    Or,
+    /// `AND` (uppercase; same as adjacency)
+    And,
+    /// Leading `-` on a clause
+    Not,
+    /// A single operator or free-text clause
+    Clause(ParsedQuery),
+}
+
 /// Parse a search query string into structured components
 ///
 /// Supports Gmail-style operators:
 /// - `from:value` or `from:"quoted value"`
 /// - `to:value`
 /// - `subject:value`
-/// - `in:label`
+/// - `in:label` or `label:label`
 /// - `is:unread`, `is:read`, `is:starred`
 /// - `has:attachment`
+/// - `filename:value` (name or extension, e.g. `filename:pdf`)
+/// - `larger:SIZE`, `smaller:SIZE` (bytes, or with a `K`/`M`/`G` suffix)
 /// - `before:YYYY/MM/DD` or `before:YYYY-MM-DD`
 /// - `after:YYYY/MM/DD` or `after:YYYY-MM-DD`
+/// - `account:value` (email address, display name, or account ID)
+///
+/// Boolean composition:
+/// - `a OR b` - either clause matches
+/// - `-clause` - clause must not match
+/// - `( ... )` - grouping
 ///
 /// Everything else is treated as free-text search terms.
 pub fn parse_query(input: &str) -> ParsedQuery {
+    let tokens = tokenize(input);
+    let mut pos = 0;
     let mut query = ParsedQuery::default();
 
+    // Parse until all tokens are consumed; stray `)` are skipped
+    while pos < tokens.len() {
+        query.merge(parse_and(&tokens, &mut pos));
+        if tokens.get(pos) == Some(&Token::Close) {
+            pos += 1;
+        }
+    }
+
+    query
+}
+
+/// Parse adjacent clauses (implicit AND) until `)` or end of input
+fn parse_and(tokens: &[Token], pos: &mut usize) -> ParsedQuery {
+    let mut query = ParsedQuery::default();
+
+    while let Some(token) = tokens.get(*pos) {
+        match token {
+            Token::Close => break,
+            Token::And | Token::Or => {
+                // Dangling connective (e.g. leading `OR`) - ignore
+                *pos += 1;
+            }
+            _ => query.merge(parse_or(tokens, pos)),
+        }
+    }
+
+    query
+}
+
+/// Parse `unary (OR unary)*`
+fn parse_or(tokens: &[Token], pos: &mut usize) -> ParsedQuery {
+    let mut alternatives = vec![parse_unary(tokens, pos)];
+
+    while tokens.get(*pos) == Some(&Token::Or) {
+        // `OR` must be followed by a clause to count as a connective
+        match tokens.get(*pos + 1) {
+            Some(Token::Close) | Some(Token::Or) | Some(Token::And) | None => break,
+            _ => {}
+        }
+        *pos += 1;
+        alternatives.push(parse_unary(tokens, pos));
+    }
+
+    if alternatives.len() == 1 {
+        alternatives.pop().unwrap_or_default()
+    } else {
+        ParsedQuery {
+            any_of: vec![alternatives],
+            ..Default::default()
+        }
+    }
+}
+
+/// Parse `-unary` or an atom
+fn parse_unary(tokens: &[Token], pos: &mut usize) -> ParsedQuery {
+    match tokens.get(*pos) {
+        Some(Token::Not) => {
+            *pos += 1;
+            let inner = parse_unary(tokens, pos);
+            if inner.is_empty() {
+                return inner;
+            }
+            ParsedQuery {
+                excluded: vec![inner],
+                ..Default::default()
+            }
+        }
+        Some(Token::Open) => {
+            *pos += 1;
+            let inner = parse_and(tokens, pos);
+            if tokens.get(*pos) == Some(&Token::Close) {
+                *pos += 1;
+            }
+            inner
+        }
+        Some(Token::Clause(clause)) => {
+            *pos += 1;
+            clause.clone()
+        }
+        _ => {
+            *pos += 1;
+            ParsedQuery::default()
+        }
+    }
+}
+
+/// Split a query string into tokens
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
     let mut i = 0;
     let chars: Vec<char> = input.chars().collect();
 
@@ -80,15 +298,36 @@ pub fn parse_query(input: &str) -> ParsedQuery {
             break;
         }
 
+        match chars[i] {
+            '(' => {
+                tokens.push(Token::Open);
+                i += 1;
+                continue;
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                i += 1;
+                continue;
+            }
+            // A leading `-` negates the following clause
+            '-' if chars.get(i + 1).is_some_and(|c| !c.is_whitespace() && *c != '-') => {
+                tokens.push(Token::Not);
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+
         // Check for operator patterns
         let rest: String = chars[i..].iter().collect();
 
         if let Some((key, value, consumed)) = parse_operator(&rest) {
+            let mut query = ParsedQuery::default();
             match key.to_lowercase().as_str() {
                 "from" => query.from.push(value),
                 "to" => query.to.push(value),
                 "subject" => query.subject.push(value),
-                "in" => query.in_label = Some(value.to_uppercase()),
+                "in" | "label" => query.in_label = Some(normalize_label(&value)),
                 "is" => match value.to_lowercase().as_str() {
                     "unread" => query.is_unread = Some(true),
                     "read" => query.is_unread = Some(false),
@@ -100,6 +339,10 @@ pub fn parse_query(input: &str) -> ParsedQuery {
                         query.has_attachment = Some(true);
                     }
                 }
+                "filename" => query.filename.push(value),
+                "account" => query.account.push(value),
+                "larger" => query.larger = parse_size(&value),
+                "smaller" => query.smaller = parse_size(&value),
                 "before" => {
                     if let Some(date) = parse_date(&value) {
                         query.before = Some(date);
@@ -112,18 +355,42 @@ pub fn parse_query(input: &str) -> ParsedQuery {
                 }
                 _ => {}
             }
+            tokens.push(Token::Clause(query));
             i += consumed;
         } else {
             // Regular word or quoted string
+            let quoted = chars[i] == '"';
             let (word, consumed) = parse_word(&rest);
-            if !word.is_empty() {
-                query.terms.push(word);
-            }
             i += consumed;
+            if word.is_empty() {
+                continue;
+            }
+            match word.as_str() {
+                "OR" if !quoted => tokens.push(Token::Or),
+                "AND" if !quoted => tokens.push(Token::And),
+                _ => tokens.push(Token::Clause(ParsedQuery {
+                    terms: vec![word],
+                    ..Default::default()
+                })),
+            }
         }
     }
 
-    query
+    tokens
+}
+
+/// Map a label name to its Gmail label ID
+///
+/// Gmail inbox categories are stored as `CATEGORY_*` labels, so
+/// `label:promotions` matches `CATEGORY_PROMOTIONS`.
+fn normalize_label(value: &str) -> String {
+    let upper = value.to_uppercase();
+    match upper.as_str() {
+        "SOCIAL" | "PROMOTIONS" | "UPDATES" | "FORUMS" | "PERSONAL" => {
+            format!("CATEGORY_{}", upper)
+        }
+        _ => upper,
+    }
 }
 
 /// Parse an operator like "from:value" or "from:\"quoted value\""
@@ -133,7 +400,8 @@ fn parse_operator(input: &str) -> Option<(String, String, usize)> {
 
     // Validate key is a known operator
     let valid_ops = [
-        "from", "to", "subject", "in", "is", "has", "before", "after",
+        "from", "to", "subject", "in", "label", "is", "has", "filename", "larger", "smaller",
+        "before", "after", "account",
     ];
     if !valid_ops.contains(&key.to_lowercase().as_str()) {
         return None;
@@ -175,10 +443,10 @@ fn parse_value(input: &str) -> (String, usize) {
         return (value, consumed);
     }
 
-    // Unquoted value (until whitespace)
+    // Unquoted value (until whitespace or a closing parenthesis)
     let mut value = String::new();
     let mut i = 0;
-    while i < chars.len() && !chars[i].is_whitespace() {
+    while i < chars.len() && !chars[i].is_whitespace() && chars[i] != ')' {
         value.push(chars[i]);
         i += 1;
     }
@@ -206,10 +474,10 @@ fn parse_word(input: &str) -> (String, usize) {
         return (word, consumed);
     }
 
-    // Unquoted word
+    // Unquoted word (until whitespace or a parenthesis)
     let mut word = String::new();
     let mut i = 0;
-    while i < chars.len() && !chars[i].is_whitespace() {
+    while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '(' && chars[i] != ')' {
         word.push(chars[i]);
         i += 1;
     }
@@ -217,6 +485,27 @@ fn parse_word(input: &str) -> (String, usize) {
     (word, i)
 }
 
+/// Parse a size like "5M", "100k", "2.5MB", or "1024" into bytes
+///
+/// Suffixes are binary multiples, matching Gmail (`1K` = 1024 bytes).
+fn parse_size(input: &str) -> Option<u64> {
+    let upper = input.trim().to_uppercase();
+    let digits = upper.strip_suffix('B').unwrap_or(&upper);
+
+    let (number, multiplier) = match digits.chars().last()? {
+        'K' => (&digits[..digits.len() - 1], 1u64 << 10),
+        'M' => (&digits[..digits.len() - 1], 1u64 << 20),
+        'G' => (&digits[..digits.len() - 1], 1u64 << 30),
+        _ => (digits, 1),
+    };
+
+    let value: f64 = number.parse().ok()?;
+    if !value.is_finite() || value < 0.0 {
+        return None;
+    }
+    Some((value * multiplier as f64) as u64)
+}
+
 /// Parse a date string (YYYY/MM/DD or YYYY-MM-DD)
 fn parse_date(input: &str) -> Option<DateTime<Utc>> {
     // Try YYYY/MM/DD format
@@ -305,6 +594,62 @@ mod tests {
         assert_eq!(query.has_attachment, Some(true));
     }
 
+    #[test]
+    fn test_parse_filename() {
+        let query = parse_query("filename:pdf invoice");
+        assert_eq!(query.filename, vec!["pdf"]);
+        assert_eq!(query.terms, vec!["invoice"]);
+
+        let query = parse_query("filename:\"Q3 report.xlsx\"");
+        assert_eq!(query.filename, vec!["Q3 report.xlsx"]);
+    }
+
+    #[test]
+    fn test_parse_account() {
+        let query = parse_query("account:work@example.com report");
+        assert_eq!(query.account, vec!["work@example.com"]);
+        assert_eq!(query.terms, vec!["report"]);
+        assert!(query.mentions_account());
+
+        let query = parse_query("report (account:a OR account:b)");
+        assert!(query.account.is_empty());
+        assert!(query.mentions_account());
+    }
+
+    #[test]
+    fn test_resolve_accounts() {
+        let mut work = Account::new("Work@Example.com");
+        work.id = 2;
+        work.display_name = Some("Work".to_string());
+        let accounts = vec![work];
+
+        let resolve = |input: &str| parse_query(input).resolve_accounts(&accounts).account;
+        assert_eq!(resolve("account:work@example.com"), vec!["2"]);
+        assert_eq!(resolve("account:work"), vec!["2"]);
+        assert_eq!(resolve("account:2"), vec!["2"]);
+        assert_eq!(resolve("account:other@example.com"), vec!["other@example.com"]);
+
+        let nested = parse_query("-account:work").resolve_accounts(&accounts);
+        assert_eq!(nested.excluded[0].account, vec!["2"]);
+    }
+
+    #[test]
+    fn test_parse_size_filters() {
+        let query = parse_query("larger:5M smaller:10m");
+        assert_eq!(query.larger, Some(5 * 1024 * 1024));
+        assert_eq!(query.smaller, Some(10 * 1024 * 1024));
+
+        assert_eq!(parse_query("larger:100K").larger, Some(100 * 1024));
+        assert_eq!(parse_query("larger:2048").larger, Some(2048));
+        assert_eq!(parse_query("larger:1.5MB").larger, Some(1_572_864));
+        assert_eq!(parse_query("smaller:1G").smaller, Some(1 << 30));
+
+        // Unparseable sizes are dropped rather than matching everything
+        let query = parse_query("larger:huge");
+        assert_eq!(query.larger, None);
+        assert!(query.is_empty());
+    }
+
     #[test]
     fn test_parse_in_label() {
         let query = parse_query("in:inbox");
@@ -380,4 +725,120 @@ mod tests {
         with_from.from.push("alice".to_string());
         assert!(!with_from.is_empty());
     }
+
+    fn from_clause(value: &str) -> ParsedQuery {
+        ParsedQuery {
+            from: vec![value.to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn term_clause(value: &str) -> ParsedQuery {
+        ParsedQuery {
+            terms: vec![value.to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_or() {
+        let query = parse_query("from:a OR from:b");
+        assert!(query.from.is_empty());
+        assert_eq!(query.any_of, vec![vec![from_clause("a"), from_clause("b")]]);
+    }
+
+    #[test]
+    fn test_lowercase_or_is_a_term() {
+        let query = parse_query("cats or dogs");
+        assert_eq!(query.terms, vec!["cats", "or", "dogs"]);
+        assert!(query.any_of.is_empty());
+    }
+
+    #[test]
+    fn test_parse_negation() {
+        let query = parse_query("-label:promotions -is:unread");
+        assert!(query.in_label.is_none());
+        assert_eq!(query.excluded.len(), 2);
+        assert_eq!(
+            query.excluded[0].in_label,
+            Some("CATEGORY_PROMOTIONS".to_string())
+        );
+        assert_eq!(query.excluded[1].is_unread, Some(true));
+    }
+
+    #[test]
+    fn test_hyphen_inside_word_is_not_negation() {
+        let query = parse_query("e-mail - test");
+        assert_eq!(query.terms, vec!["e-mail", "-", "test"]);
+        assert!(query.excluded.is_empty());
+    }
+
+    #[test]
+    fn test_or_binds_tighter_than_and() {
+        // a b OR c == a AND (b OR c)
+        let query = parse_query("report from:a OR from:b");
+        assert_eq!(query.terms, vec!["report"]);
+        assert_eq!(query.any_of, vec![vec![from_clause("a"), from_clause("b")]]);
+    }
+
+    #[test]
+    fn test_negation_binds_tighter_than_or() {
+        // -a OR b == (NOT a) OR b
+        let query = parse_query("-cats OR dogs");
+        let negated = ParsedQuery {
+            excluded: vec![term_clause("cats")],
+            ..Default::default()
+        };
+        assert_eq!(query.any_of, vec![vec![negated, term_clause("dogs")]]);
+    }
+
+    #[test]
+    fn test_parentheses_group() {
+        let query = parse_query("(from:a subject:x) OR from:b");
+        let grouped = ParsedQuery {
+            from: vec!["a".to_string()],
+            subject: vec!["x".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(query.any_of, vec![vec![grouped, from_clause("b")]]);
+    }
+
+    #[test]
+    fn test_negated_group() {
+        let query = parse_query("is:starred -(from:a OR from:b)");
+        assert_eq!(query.is_starred, Some(true));
+        assert_eq!(query.excluded.len(), 1);
+        assert_eq!(
+            query.excluded[0].any_of,
+            vec![vec![from_clause("a"), from_clause("b")]]
+        );
+    }
+
+    #[test]
+    fn test_operator_value_stops_at_paren() {
+        let query = parse_query("(from:alice)");
+        assert_eq!(query.from, vec!["alice"]);
+    }
+
+    #[test]
+    fn test_unbalanced_parentheses() {
+        let query = parse_query("(from:a OR from:b");
+        assert_eq!(query.any_of, vec![vec![from_clause("a"), from_clause("b")]]);
+
+        let query = parse_query("hello) world");
+        assert_eq!(query.terms, vec!["hello", "world"]);
+    }
+
+    #[test]
+    fn test_dangling_or_ignored() {
+        let query = parse_query("OR hello OR");
+        assert_eq!(query.terms, vec!["hello"]);
+        assert!(query.any_of.is_empty());
+    }
+
+    #[test]
+    fn test_positive_terms() {
+        let query = parse_query("budget (report OR summary) -draft");
+        assert_eq!(query.positive_terms(), vec!["budget", "report", "summary"]);
+    }
 }
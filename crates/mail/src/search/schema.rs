@@ -1,9 +1,16 @@
 //! Tantivy schema definition for email indexing
 
 use tantivy::schema::{
-    Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, FAST, STORED, STRING,
+    Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, FAST, INDEXED, STORED,
+    STRING,
 };
 
+/// Version of the index layout produced by `build_schema`
+///
+/// Bump this whenever fields are added, removed, or indexed differently so
+/// existing on-disk indexes are detected as outdated and rebuilt.
+pub const SCHEMA_VERSION: u32 = 6;
+
 /// Build the Tantivy schema for email indexing
 ///
 /// Fields indexed:
@@ -12,7 +19,11 @@ use tantivy::schema::{
 /// - subject, body_text, snippet: Full-text searchable content
 /// - from, from_email, to, cc: Sender/recipient search
 /// - labels: Exact match label filtering
+/// - filename: Attachment names (filename: operator)
+/// - attachment_text: Text extracted from attachments (free-text search)
+/// - note: The user's thread note, on a separate per-thread document
 /// - received_at_ms: Date range queries
+/// - size_bytes: Size range queries (larger:/smaller:)
 /// - is_unread, is_starred, has_attachment: Boolean filters
 pub fn build_schema() -> Schema {
     let mut builder = Schema::builder();
@@ -21,8 +32,9 @@ pub fn build_schema() -> Schema {
     builder.add_text_field("thread_id", STRING | STORED);
     builder.add_text_field("message_id", STRING | STORED);
 
-    // Account ID for multi-account filtering (FAST for filtering, STORED for retrieval)
-    builder.add_i64_field("account_id", FAST | STORED);
+    // Account ID for multi-account filtering (INDEXED for term lookups,
+    // FAST for filtering, STORED for retrieval)
+    builder.add_i64_field("account_id", INDEXED | FAST | STORED);
 
     // Full-text fields with positions for phrase queries and highlighting
     let text_opts = TextOptions::default()
@@ -41,16 +53,28 @@ pub fn build_schema() -> Schema {
     builder.add_text_field("from", text_opts.clone());
     builder.add_text_field("from_email", text_opts.clone());
     builder.add_text_field("to", text_opts.clone());
-    builder.add_text_field("cc", text_opts);
+    builder.add_text_field("cc", text_opts.clone());
+
+    // Attachment names, one value per attachment; tokenized so that
+    // filename:pdf matches "report.pdf"
+    builder.add_text_field("filename", text_opts.clone());
+
+    // Text extracted from PDF/office attachments, searched like the body
+    builder.add_text_field("attachment_text", text_opts.clone());
+
+    // Thread notes, indexed as one extra document per thread
+    builder.add_text_field("note", text_opts);
 
-    // Exact match fields for label filtering (multi-valued via multiple additions)
-    builder.add_text_field("labels", STRING);
+    // Exact match fields for label filtering (multi-valued via multiple additions).
+    // Stored so label updates can rewrite a document without the source message.
+    builder.add_text_field("labels", STRING | STORED);
 
     // Numeric fields for filtering (FAST for range queries)
     builder.add_i64_field("received_at_ms", FAST | STORED);
+    builder.add_i64_field("size_bytes", FAST | STORED);
     builder.add_u64_field("is_unread", FAST);
     builder.add_u64_field("is_starred", FAST);
-    builder.add_u64_field("has_attachment", FAST);
+    builder.add_u64_field("has_attachment", FAST | STORED);
 
     builder.build()
 }
@@ -68,7 +92,11 @@ pub struct SchemaFields {
     pub to: Field,
     pub cc: Field,
     pub labels: Field,
+    pub filename: Field,
+    pub attachment_text: Field,
+    pub note: Field,
     pub received_at_ms: Field,
+    pub size_bytes: Field,
     pub is_unread: Field,
     pub is_starred: Field,
     pub has_attachment: Field,
@@ -89,7 +117,13 @@ impl SchemaFields {
             to: schema.get_field("to").expect("to field"),
             cc: schema.get_field("cc").expect("cc field"),
             labels: schema.get_field("labels").expect("labels field"),
+            filename: schema.get_field("filename").expect("filename field"),
+            attachment_text: schema
+                .get_field("attachment_text")
+                .expect("attachment_text field"),
+            note: schema.get_field("note").expect("note field"),
             received_at_ms: schema.get_field("received_at_ms").expect("received_at_ms field"),
+            size_bytes: schema.get_field("size_bytes").expect("size_bytes field"),
             is_unread: schema.get_field("is_unread").expect("is_unread field"),
             is_starred: schema.get_field("is_starred").expect("is_starred field"),
             has_attachment: schema.get_field("has_attachment").expect("has_attachment field"),
@@ -118,7 +152,11 @@ mod tests {
         assert!(schema.get_field("to").is_ok());
         assert!(schema.get_field("cc").is_ok());
         assert!(schema.get_field("labels").is_ok());
+        assert!(schema.get_field("filename").is_ok());
+        assert!(schema.get_field("attachment_text").is_ok());
+        assert!(schema.get_field("note").is_ok());
         assert!(schema.get_field("received_at_ms").is_ok());
+        assert!(schema.get_field("size_bytes").is_ok());
         assert!(schema.get_field("is_unread").is_ok());
         assert!(schema.get_field("is_starred").is_ok());
         assert!(schema.get_field("has_attachment").is_ok());
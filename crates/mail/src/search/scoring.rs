@@ -0,0 +1,154 @@
+//! Relevance tuning and indexing policy for search
+//!
+//! Free-text matches are weighted per field (a subject hit counts for more
+//! than a body hit), then scaled by a recency factor so recent mail outranks
+//! equally relevant old mail. The same config sets how often indexed mail is
+//! committed and how segments are merged. Settings are read from
+//! `mail.search.json` in the Cosmos config directory; missing keys keep their
+//! defaults.
+
+use std::time::Duration;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// Config file holding search relevance weights
+pub const SEARCH_CONFIG_FILE: &str = "mail.search.json";
+
+/// Milliseconds in a day
+const MS_PER_DAY: f64 = 86_400_000.0;
+
+/// Ranking weights and indexing policy for search
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// Weight of subject matches
+    pub subject_boost: f32,
+    /// Weight of sender name and address matches
+    pub from_boost: f32,
+    /// Weight of body and snippet matches
+    pub body_boost: f32,
+    /// Age in days at which the decaying part of the score has halved
+    pub recency_half_life_days: f32,
+    /// Share of the score that decays with age (0 disables recency)
+    pub recency_weight: f32,
+    /// Seconds indexed mail may wait for a commit during sync
+    pub commit_interval_secs: u64,
+    /// Indexed documents that trigger a commit before the interval is up
+    pub commit_max_docs: usize,
+    /// Background threads merging index segments
+    pub merge_threads: usize,
+    /// Segments of similar size that are merged into one
+    pub merge_min_segments: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            subject_boost: 3.0,
+            from_boost: 2.0,
+            body_boost: 1.0,
+            recency_half_life_days: 30.0,
+            recency_weight: 0.5,
+            commit_interval_secs: 10,
+            commit_max_docs: 5_000,
+            merge_threads: 1,
+            merge_min_segments: 8,
+        }
+    }
+}
+
+impl SearchConfig {
+    /// Load weights from the config directory, falling back to defaults
+    ///
+    /// An unreadable or malformed file is logged and ignored.
+    pub fn load() -> Self {
+        if !config::config_exists(SEARCH_CONFIG_FILE) {
+            return Self::default();
+        }
+        match config::load_json(SEARCH_CONFIG_FILE) {
+            Ok(search_config) => search_config,
+            Err(e) => {
+                warn!("Ignoring invalid search config: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Score multiplier for a message received `age_ms` milliseconds ago
+    ///
+    /// Ranges from 1.0 for brand-new mail down towards
+    /// `1.0 - recency_weight` for very old mail. Future timestamps count as
+    /// new.
+    pub fn recency_multiplier(&self, age_ms: i64) -> f32 {
+        let weight = f64::from(self.recency_weight.clamp(0.0, 1.0));
+        if weight <= 0.0 || self.recency_half_life_days <= 0.0 {
+            return 1.0;
+        }
+
+        let age_days = age_ms.max(0) as f64 / MS_PER_DAY;
+        let decay = 0.5f64.powf(age_days / f64::from(self.recency_half_life_days));
+        (1.0 - weight + weight * decay) as f32
+    }
+
+    /// Whether a sync should commit `pending_docs` documents indexed since the
+    /// last commit `since_commit` ago
+    pub fn commit_due(&self, pending_docs: usize, since_commit: Duration) -> bool {
+        pending_docs > 0
+            && (pending_docs >= self.commit_max_docs
+                || since_commit >= Duration::from_secs(self.commit_interval_secs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_MS: i64 = 86_400_000;
+
+    #[test]
+    fn test_recency_multiplier_decays() {
+        let config = SearchConfig::default();
+
+        assert!((config.recency_multiplier(0) - 1.0).abs() < 1e-6);
+        // One half-life removes half of the decaying share
+        assert!((config.recency_multiplier(30 * DAY_MS) - 0.75).abs() < 1e-6);
+
+        let week = config.recency_multiplier(7 * DAY_MS);
+        let decade = config.recency_multiplier(3650 * DAY_MS);
+        assert!(week > decade);
+        assert!(decade >= 0.5);
+
+        // Clock skew doesn't push scores above 1
+        assert!((config.recency_multiplier(-DAY_MS) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_recency_disabled() {
+        let config = SearchConfig {
+            recency_weight: 0.0,
+            ..SearchConfig::default()
+        };
+        assert_eq!(config.recency_multiplier(3650 * DAY_MS), 1.0);
+    }
+
+    #[test]
+    fn test_commit_due() {
+        let config = SearchConfig {
+            commit_interval_secs: 10,
+            commit_max_docs: 100,
+            ..SearchConfig::default()
+        };
+        assert!(!config.commit_due(0, Duration::from_secs(60)));
+        assert!(!config.commit_due(99, Duration::from_secs(9)));
+        assert!(config.commit_due(100, Duration::ZERO));
+        assert!(config.commit_due(1, Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_partial_config_keeps_defaults() {
+        let config: SearchConfig = serde_json::from_str(r#"{"subject_boost": 5.0}"#).unwrap();
+        assert_eq!(config.subject_boost, 5.0);
+        assert_eq!(config.from_boost, SearchConfig::default().from_boost);
+    }
+}
@@ -0,0 +1,262 @@
+//! Search typeahead suggestions
+//!
+//! Offers completions for a partially typed query from three sources:
+//! recently submitted queries, contacts seen in indexed mail, and subjects
+//! of indexed messages. Each suggestion carries the query to run if chosen.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::index::SearchIndex;
+use super::query_parser::parse_query;
+use crate::storage::{MAX_RECENT_SEARCHES, MailStore};
+
+/// Where a suggestion came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    /// A previously submitted query
+    RecentQuery,
+    /// A sender or recipient
+    Contact,
+    /// A message subject
+    Subject,
+}
+
+/// A single typeahead suggestion
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchSuggestion {
+    /// Suggestion source
+    pub kind: SuggestionKind,
+    /// Text shown in the dropdown
+    pub label: String,
+    /// Query to run when the suggestion is chosen
+    pub query: String,
+}
+
+/// Suggest completions for a partially typed query
+///
+/// Recent queries match when they start with `prefix` (case-insensitive);
+/// an empty prefix lists only recent queries. Contacts and subjects are
+/// matched against the free-text words of `prefix`, with `from:` values
+/// also used for contacts and `subject:` values for subjects.
+///
+/// Results are grouped by kind (recent, contacts, subjects). Each kind gets
+/// an even share of `limit` first; leftover slots go to kinds with more
+/// matches.
+///
+/// # Arguments
+/// * `index` - The search index to read contacts and subjects from
+/// * `store` - Mail store holding recent queries
+/// * `prefix` - What the user has typed so far
+/// * `limit` - Maximum number of suggestions to return
+pub fn suggest(
+    index: &SearchIndex,
+    store: &dyn MailStore,
+    prefix: &str,
+    limit: usize,
+) -> Result<Vec<SearchSuggestion>> {
+    if limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    let typed = prefix.trim();
+    let typed_lower = typed.to_lowercase();
+
+    let recent: Vec<SearchSuggestion> = store
+        .list_recent_searches(MAX_RECENT_SEARCHES)?
+        .into_iter()
+        .filter(|q| q.to_lowercase().starts_with(&typed_lower) && q.trim() != typed)
+        .map(|q| SearchSuggestion {
+            kind: SuggestionKind::RecentQuery,
+            label: q.clone(),
+            query: q,
+        })
+        .collect();
+
+    let parsed = parse_query(typed);
+    let words = parsed.terms.join(" ");
+
+    let contact_prefix = parsed.from.last().cloned().unwrap_or_else(|| words.clone());
+    let contacts: Vec<SearchSuggestion> = index
+        .suggest_contacts(&contact_prefix, limit)?
+        .into_iter()
+        .map(|address| SearchSuggestion {
+            kind: SuggestionKind::Contact,
+            label: address.display(),
+            query: format!("from:{}", address.email),
+        })
+        .collect();
+
+    let subject_prefix = parsed.subject.last().cloned().unwrap_or(words);
+    let subjects: Vec<SearchSuggestion> = index
+        .suggest_subjects(&subject_prefix, limit)?
+        .into_iter()
+        .map(|subject| SearchSuggestion {
+            kind: SuggestionKind::Subject,
+            query: format!("subject:\"{}\"", subject.replace('"', "")),
+            label: subject,
+        })
+        .collect();
+
+    Ok(merge_groups(vec![recent, contacts, subjects], limit))
+}
+
+/// Combine suggestion groups, giving each an even share of `limit` first
+fn merge_groups(groups: Vec<Vec<SearchSuggestion>>, limit: usize) -> Vec<SearchSuggestion> {
+    let share = limit.div_ceil(groups.len().max(1));
+    let mut taken: Vec<usize> = groups.iter().map(|g| g.len().min(share)).collect();
+
+    // Hand leftover slots to groups that have more, in group order
+    let mut remaining = limit.saturating_sub(taken.iter().sum());
+    for (group, count) in groups.iter().zip(taken.iter_mut()) {
+        let extra = (group.len() - *count).min(remaining);
+        *count += extra;
+        remaining -= extra;
+    }
+
+    let mut merged = Vec::with_capacity(limit);
+    for (group, count) in groups.into_iter().zip(taken) {
+        merged.extend(group.into_iter().take(count));
+    }
+    merged.truncate(limit);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EmailAddress, Message, MessageId, Thread, ThreadId};
+    use crate::storage::InMemoryMailStore;
+    use chrono::Utc;
+
+    fn add_message(
+        store: &InMemoryMailStore,
+        index: &SearchIndex,
+        id: &str,
+        subject: &str,
+        from: EmailAddress,
+    ) {
+        let thread = Thread::new(
+            ThreadId::new(id),
+            1,
+            subject.to_string(),
+            String::new(),
+            Utc::now(),
+            1,
+            from.name.clone(),
+            from.email.clone(),
+            false,
+        );
+        let message = Message::builder(MessageId::new(format!("m_{}", id)), ThreadId::new(id))
+            .account_id(1)
+            .from(from)
+            .to(vec![EmailAddress::with_name("Me", "me@example.com")])
+            .subject(subject)
+            .received_at(Utc::now())
+            .build();
+        store.upsert_thread(thread.clone()).unwrap();
+        store.upsert_message(message.clone()).unwrap();
+        index.index_message(&message, &thread).unwrap();
+    }
+
+    fn setup() -> (InMemoryMailStore, SearchIndex) {
+        let store = InMemoryMailStore::new();
+        let index = SearchIndex::in_memory().unwrap();
+        let alice = EmailAddress::with_name("Alice Martin", "alice@example.com");
+        add_message(&store, &index, "t1", "Budget review", alice.clone());
+        add_message(&store, &index, "t2", "Re: Budget review", alice);
+        add_message(
+            &store,
+            &index,
+            "t3",
+            "Alps trip",
+            EmailAddress::new("albert@travel.example"),
+        );
+        index.commit().unwrap();
+        (store, index)
+    }
+
+    #[test]
+    fn test_suggests_all_kinds() {
+        let (store, index) = setup();
+        store.record_recent_search("budget is:unread").unwrap();
+        store.record_recent_search("from:bob").unwrap();
+
+        let suggestions = suggest(&index, &store, "bud", 10).unwrap();
+        let kinds: Vec<_> = suggestions.iter().map(|s| s.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![SuggestionKind::RecentQuery, SuggestionKind::Subject]
+        );
+        assert_eq!(suggestions[0].query, "budget is:unread");
+        // Reply prefixes are stripped, so the subject appears once
+        assert_eq!(suggestions[1].label, "Budget review");
+        assert_eq!(suggestions[1].query, "subject:\"Budget review\"");
+    }
+
+    #[test]
+    fn test_contacts_ranked_by_frequency() {
+        let (store, index) = setup();
+
+        let suggestions = suggest(&index, &store, "al", 10).unwrap();
+        let contacts: Vec<_> = suggestions
+            .iter()
+            .filter(|s| s.kind == SuggestionKind::Contact)
+            .map(|s| s.query.as_str())
+            .collect();
+        assert_eq!(
+            contacts,
+            vec!["from:alice@example.com", "from:albert@travel.example"]
+        );
+
+        // Matches on display name and via from:
+        let by_name = suggest(&index, &store, "from:mart", 10).unwrap();
+        assert_eq!(by_name[0].label, "Alice Martin <alice@example.com>");
+    }
+
+    #[test]
+    fn test_empty_prefix_lists_recent() {
+        let (store, index) = setup();
+        store.record_recent_search("older").unwrap();
+        store.record_recent_search("newer").unwrap();
+
+        let suggestions = suggest(&index, &store, "", 10).unwrap();
+        let queries: Vec<_> = suggestions.iter().map(|s| s.query.as_str()).collect();
+        assert_eq!(queries, vec!["newer", "older"]);
+    }
+
+    #[test]
+    fn test_merge_groups_shares_limit() {
+        let group = |kind: SuggestionKind, n: usize| -> Vec<SearchSuggestion> {
+            (0..n)
+                .map(|i| SearchSuggestion {
+                    kind,
+                    label: i.to_string(),
+                    query: i.to_string(),
+                })
+                .collect()
+        };
+
+        let merged = merge_groups(
+            vec![
+                group(SuggestionKind::RecentQuery, 5),
+                group(SuggestionKind::Contact, 1),
+                group(SuggestionKind::Subject, 5),
+            ],
+            6,
+        );
+        let kinds: Vec<_> = merged.iter().map(|s| s.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                SuggestionKind::RecentQuery,
+                SuggestionKind::RecentQuery,
+                SuggestionKind::RecentQuery,
+                SuggestionKind::Contact,
+                SuggestionKind::Subject,
+                SuggestionKind::Subject,
+            ]
+        );
+    }
+}
@@ -0,0 +1,450 @@
+//! Local API server for third-party tools (`server` feature)
+//!
+//! Scripts and launcher extensions (Raycast, Alfred) talk to the mail store
+//! through a Unix socket instead of opening the database themselves. Each
+//! request is one line of JSON and gets one line back, loosely following
+//! JSON-RPC 2.0:
+//!
+//! ```text
+//! > {"id": 1, "method": "search", "params": {"query": "from:ana", "limit": 5}}
+//! < {"id": 1, "schema_version": 1, "result": [...]}
+//! ```
+//!
+//! Every response carries [`API_SCHEMA_VERSION`], which only changes when a
+//! method's params or result change shape. Requests may send the
+//! `schema_version` they were written against; newer ones are refused
+//! rather than half-understood. Mutating methods only work for accounts the
+//! host registered an [`ActionHandler`] for, and the socket is only
+//! accessible to the current user.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::actions::ActionHandler;
+use crate::models::ThreadId;
+use crate::query::{ThreadSummary, get_thread_detail};
+use crate::search::{SearchIndex, search_threads_for_account};
+use crate::storage::MailStore;
+
+/// Socket file the API listens on, in the Cosmos config directory
+pub const API_SOCKET_FILE: &str = "cosmos.api.sock";
+
+/// Version of the request and response shapes
+pub const API_SCHEMA_VERSION: u32 = 1;
+
+/// Results returned when a request doesn't set `limit`
+const DEFAULT_LIMIT: usize = 50;
+
+/// Upper bound on `limit`, so one request can't dump the whole mailbox
+const MAX_LIMIT: usize = 500;
+
+/// Path of the API socket
+pub fn api_socket_path() -> Option<PathBuf> {
+    config::config_path(API_SOCKET_FILE)
+}
+
+/// A method call from a client
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiRequest {
+    /// Echoed back in the response so clients can match replies
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+    /// Schema the client was written against, if it says
+    #[serde(default)]
+    pub schema_version: Option<u32>,
+}
+
+/// The reply to one request; exactly one of `result` and `error` is set
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiResponse {
+    pub id: Value,
+    pub schema_version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ApiError>,
+}
+
+/// Why a request failed, with JSON-RPC style codes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ApiError {
+    pub code: i32,
+    pub message: String,
+}
+
+impl ApiError {
+    /// The request line wasn't valid JSON
+    pub const PARSE_ERROR: i32 = -32700;
+    /// No method with that name
+    pub const METHOD_NOT_FOUND: i32 = -32601;
+    /// Params were missing or had the wrong shape
+    pub const INVALID_PARAMS: i32 = -32602;
+    /// The method failed while running
+    pub const INTERNAL_ERROR: i32 = -32000;
+    /// The method changes mail but the account has no action handler
+    pub const ACTIONS_DISABLED: i32 = -32001;
+    /// The client needs a newer schema than this server speaks
+    pub const UNSUPPORTED_SCHEMA: i32 = -32002;
+
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ListParams {
+    #[serde(default = "inbox")]
+    label: String,
+    account_id: Option<i64>,
+    limit: Option<usize>,
+}
+
+fn inbox() -> String {
+    crate::models::LabelId::INBOX.to_string()
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    query: String,
+    account_id: Option<i64>,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct ThreadParams {
+    thread_id: String,
+}
+
+#[derive(Deserialize)]
+struct SetReadParams {
+    thread_id: String,
+    read: bool,
+}
+
+/// Answers API requests against a store
+pub struct ApiService {
+    store: Arc<dyn MailStore>,
+    search_index: Option<Arc<SearchIndex>>,
+    handlers: HashMap<i64, Arc<ActionHandler>>,
+}
+
+impl ApiService {
+    /// Create a read-only service
+    pub fn new(store: Arc<dyn MailStore>, search_index: Option<Arc<SearchIndex>>) -> Self {
+        Self {
+            store,
+            search_index,
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Allow mutating methods on threads of `account_id`
+    pub fn with_actions(mut self, account_id: i64, handler: Arc<ActionHandler>) -> Self {
+        self.handlers.insert(account_id, handler);
+        self
+    }
+
+    /// Answer one request line with one response line (no trailing newline)
+    pub fn handle_line(&self, line: &str) -> String {
+        let response = match serde_json::from_str::<ApiRequest>(line) {
+            Ok(request) => self.handle(request),
+            Err(e) => ApiResponse {
+                id: Value::Null,
+                schema_version: API_SCHEMA_VERSION,
+                result: None,
+                error: Some(ApiError::new(ApiError::PARSE_ERROR, e.to_string())),
+            },
+        };
+        serde_json::to_string(&response).unwrap_or_else(|e| {
+            format!(
+                r#"{{"id":null,"schema_version":{},"error":{{"code":{},"message":"{}"}}}}"#,
+                API_SCHEMA_VERSION,
+                ApiError::INTERNAL_ERROR,
+                e
+            )
+        })
+    }
+
+    /// Answer a request
+    pub fn handle(&self, request: ApiRequest) -> ApiResponse {
+        let outcome = match request.schema_version {
+            Some(version) if version > API_SCHEMA_VERSION => Err(ApiError::new(
+                ApiError::UNSUPPORTED_SCHEMA,
+                format!(
+                    "Schema {} requested, this server speaks {}",
+                    version, API_SCHEMA_VERSION
+                ),
+            )),
+            _ => self.dispatch(&request.method, request.params),
+        };
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        ApiResponse {
+            id: request.id,
+            schema_version: API_SCHEMA_VERSION,
+            result,
+            error,
+        }
+    }
+
+    fn dispatch(&self, method: &str, params: Value) -> Result<Value, ApiError> {
+        let store = self.store.as_ref();
+        match method {
+            "version" => Ok(json!({ "schema_version": API_SCHEMA_VERSION })),
+            "accounts.list" => {
+                let accounts = store.list_accounts().map_err(internal)?;
+                Ok(accounts
+                    .into_iter()
+                    .map(|a| json!({ "id": a.id, "email": a.email, "is_primary": a.is_primary }))
+                    .collect())
+            }
+            "threads.list" => {
+                let p: ListParams = parse_params(params)?;
+                let threads: Vec<ThreadSummary> = store
+                    .list_threads_after(Some(&p.label), p.account_id, None, clamp(p.limit))
+                    .map_err(internal)?
+                    .into_iter()
+                    .map(ThreadSummary::from)
+                    .collect();
+                to_value(&threads)
+            }
+            "threads.get" => {
+                let p: ThreadParams = parse_params(params)?;
+                let detail =
+                    get_thread_detail(store, &ThreadId::new(p.thread_id)).map_err(internal)?;
+                to_value(&detail)
+            }
+            "search" => {
+                let p: SearchParams = parse_params(params)?;
+                let index = self.search_index.as_ref().ok_or_else(|| {
+                    ApiError::new(ApiError::INTERNAL_ERROR, "Search index unavailable")
+                })?;
+                let results = search_threads_for_account(
+                    index,
+                    store,
+                    &p.query,
+                    clamp(p.limit),
+                    p.account_id,
+                )
+                .map_err(internal)?;
+                to_value(&results)
+            }
+            "threads.archive" => {
+                let p: ThreadParams = parse_params(params)?;
+                let thread_id = ThreadId::new(p.thread_id);
+                self.handler_for(&thread_id)?
+                    .archive_thread(&thread_id)
+                    .map_err(internal)?;
+                Ok(Value::Null)
+            }
+            "threads.trash" => {
+                let p: ThreadParams = parse_params(params)?;
+                let thread_id = ThreadId::new(p.thread_id);
+                self.handler_for(&thread_id)?
+                    .trash_thread(&thread_id)
+                    .map_err(internal)?;
+                Ok(Value::Null)
+            }
+            "threads.set_read" => {
+                let p: SetReadParams = parse_params(params)?;
+                let thread_id = ThreadId::new(p.thread_id);
+                self.handler_for(&thread_id)?
+                    .set_read(&thread_id, p.read)
+                    .map_err(internal)?;
+                Ok(Value::Null)
+            }
+            "threads.toggle_star" => {
+                let p: ThreadParams = parse_params(params)?;
+                let thread_id = ThreadId::new(p.thread_id);
+                let starred = self
+                    .handler_for(&thread_id)?
+                    .toggle_star(&thread_id)
+                    .map_err(internal)?;
+                Ok(json!({ "starred": starred }))
+            }
+            _ => Err(ApiError::new(
+                ApiError::METHOD_NOT_FOUND,
+                format!("Unknown method '{}'", method),
+            )),
+        }
+    }
+
+    /// Action handler for the account owning a thread
+    fn handler_for(&self, thread_id: &ThreadId) -> Result<&ActionHandler, ApiError> {
+        let thread = self
+            .store
+            .get_thread(thread_id)
+            .map_err(internal)?
+            .ok_or_else(|| {
+                ApiError::new(
+                    ApiError::INVALID_PARAMS,
+                    format!("Thread {} not found", thread_id.as_str()),
+                )
+            })?;
+        self.handlers
+            .get(&thread.account_id)
+            .map(|handler| handler.as_ref())
+            .ok_or_else(|| {
+                ApiError::new(
+                    ApiError::ACTIONS_DISABLED,
+                    format!("Actions are disabled for account {}", thread.account_id),
+                )
+            })
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, ApiError> {
+    // Methods without required params accept a missing params object
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| ApiError::new(ApiError::INVALID_PARAMS, e.to_string()))
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, ApiError> {
+    serde_json::to_value(value).map_err(|e| ApiError::new(ApiError::INTERNAL_ERROR, e.to_string()))
+}
+
+fn internal(error: anyhow::Error) -> ApiError {
+    ApiError::new(ApiError::INTERNAL_ERROR, format!("{:#}", error))
+}
+
+fn clamp(limit: Option<usize>) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT)
+}
+
+/// Serve `service` on a Unix socket at `path` from background threads
+///
+/// A stale socket file from an earlier run is replaced. The socket is made
+/// readable and writable by the current user only.
+pub fn serve(service: Arc<ApiService>, path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            anyhow::bail!("Another API server is listening on {}", path.display());
+        }
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove stale socket {}", path.display()))?;
+    }
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Failed to listen on {}", path.display()))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let service = service.clone();
+                    std::thread::spawn(move || {
+                        if let Err(e) = serve_connection(&service, stream) {
+                            debug!("API client disconnected: {}", e);
+                        }
+                    });
+                }
+                Err(e) => warn!("Failed to accept API client: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+fn serve_connection(service: &ApiService, stream: UnixStream) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = service.handle_line(&line);
+        writer.write_all(reply.as_bytes())?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EmailAddress, LabelId, Message, MessageId, Thread};
+    use crate::storage::InMemoryMailStore;
+    use chrono::Utc;
+
+    fn service() -> ApiService {
+        let store = InMemoryMailStore::new();
+        store
+            .upsert_thread(Thread {
+                id: ThreadId::new("t1"),
+                account_id: 1,
+                subject: "Lunch".to_string(),
+                snippet: String::new(),
+                last_message_at: Utc::now(),
+                message_count: 1,
+                sender_name: None,
+                sender_email: "ana@example.com".to_string(),
+                is_unread: true,
+                participants: Vec::new(),
+            })
+            .unwrap();
+        let message = Message::builder(MessageId::new("m1"), ThreadId::new("t1"))
+            .account_id(1)
+            .from(EmailAddress::new("ana@example.com"))
+            .label_ids(vec![LabelId::INBOX.to_string()])
+            .build();
+        store.upsert_message(message).unwrap();
+        ApiService::new(Arc::new(store), None)
+    }
+
+    fn call(service: &ApiService, line: &str) -> Value {
+        serde_json::from_str(&service.handle_line(line)).unwrap()
+    }
+
+    #[test]
+    fn test_threads_list_echoes_id_and_schema() {
+        let reply = call(&service(), r#"{"id": 7, "method": "threads.list"}"#);
+        assert_eq!(reply["id"], 7);
+        assert_eq!(reply["schema_version"], API_SCHEMA_VERSION);
+        assert_eq!(reply["result"][0]["subject"], "Lunch");
+        assert!(reply.get("error").is_none());
+    }
+
+    #[test]
+    fn test_errors_carry_codes() {
+        let service = service();
+        let reply = call(&service, "not json");
+        assert_eq!(reply["error"]["code"], ApiError::PARSE_ERROR);
+
+        let reply = call(&service, r#"{"id": 1, "method": "threads.delete_all"}"#);
+        assert_eq!(reply["error"]["code"], ApiError::METHOD_NOT_FOUND);
+
+        let reply = call(&service, r#"{"id": 1, "method": "threads.get"}"#);
+        assert_eq!(reply["error"]["code"], ApiError::INVALID_PARAMS);
+
+        let reply = call(&service, r#"{"id": 1, "method": "version", "schema_version": 99}"#);
+        assert_eq!(reply["error"]["code"], ApiError::UNSUPPORTED_SCHEMA);
+    }
+
+    #[test]
+    fn test_actions_need_a_handler() {
+        let reply = call(
+            &service(),
+            r#"{"id": 1, "method": "threads.archive", "params": {"thread_id": "t1"}}"#,
+        );
+        assert_eq!(reply["error"]["code"], ApiError::ACTIONS_DISABLED);
+    }
+}
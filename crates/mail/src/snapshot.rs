@@ -0,0 +1,340 @@
+//! Mailbox snapshots for moving to another machine
+//!
+//! [`export_snapshot`] packs the mail database (accounts, messages and sync
+//! state), the message body blobs and the search index into one zip
+//! archive, with a `manifest.json` recording the format and schema version.
+//! [`import_snapshot`] unpacks it into an empty data directory, and sync
+//! carries on incrementally from the stored history rather than fetching
+//! the whole mailbox again.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Seek, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use config::StorageSettings;
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::storage::{copy_database, database_info, schema_version};
+
+/// Archive layout written by this build
+pub const SNAPSHOT_FORMAT: u32 = 1;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const DB_ENTRY: &str = "mail.db";
+const BLOBS_ENTRY: &str = "blobs";
+const INDEX_ENTRY: &str = "search-index";
+
+/// Directory in the data directory an import unpacks into first
+const STAGING_DIR: &str = ".snapshot-import";
+
+/// What a snapshot holds, stored in it as `manifest.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    /// Archive layout, see [`SNAPSHOT_FORMAT`]
+    pub format: u32,
+    /// Database schema version (migrations applied)
+    pub schema_version: usize,
+    /// Cosmos version that wrote the snapshot
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    /// Emails of the accounts in the snapshot
+    pub accounts: Vec<String>,
+    /// Whether the search index is included
+    pub search_index: bool,
+}
+
+/// Write a snapshot of the mailbox in `storage` to `path`
+///
+/// Safe while the apps run: the database is copied in one consistent read.
+/// The search index is copied as it stands; one that turns out stale is
+/// rebuilt after import like any other.
+pub fn export_snapshot(storage: &StorageSettings, path: &Path) -> Result<SnapshotManifest> {
+    let db_path = storage.db_path()?;
+    if !db_path.exists() {
+        bail!("No mailbox at {}", db_path.display());
+    }
+
+    // VACUUM INTO won't overwrite a file, so clear any leftover copy
+    let db_copy = path.with_extension("db-tmp");
+    let _ = std::fs::remove_file(&db_copy);
+    copy_database(&db_path, &db_copy)?;
+    let result = write_archive(storage, &db_copy, path);
+    let _ = std::fs::remove_file(&db_copy);
+    result
+}
+
+fn write_archive(
+    storage: &StorageSettings,
+    db_copy: &Path,
+    path: &Path,
+) -> Result<SnapshotManifest> {
+    let (schema_version, accounts) = database_info(db_copy)?;
+    let blob_path = storage.blob_path()?;
+    let index_path = storage.index_path()?;
+    let manifest = SnapshotManifest {
+        format: SNAPSHOT_FORMAT,
+        schema_version,
+        created_by: format!("cosmos {}", env!("CARGO_PKG_VERSION")),
+        created_at: Utc::now(),
+        accounts,
+        search_index: index_path.exists(),
+    };
+
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    let deflated = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+    // Bodies are already zstd-compressed
+    let stored = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .large_file(true);
+
+    zip.start_file(MANIFEST_ENTRY, deflated)?;
+    serde_json::to_writer_pretty(&mut zip, &manifest)?;
+    add_path(&mut zip, db_copy, DB_ENTRY, deflated, &|_| true)?;
+    if blob_path.exists() {
+        // Skips the quarantine and the recovery marker
+        add_path(&mut zip, &blob_path, BLOBS_ENTRY, stored, &|name| {
+            !name.starts_with('.')
+        })?;
+    }
+    if manifest.search_index {
+        add_path(&mut zip, &index_path, INDEX_ENTRY, deflated, &|name| {
+            !name.ends_with(".lock")
+        })?;
+    }
+    zip.finish()?
+        .flush()
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(manifest)
+}
+
+/// Add the file or directory at `path` as entry `name`, keeping only the
+/// directory entries whose file names pass `keep`
+fn add_path<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    path: &Path,
+    name: &str,
+    options: SimpleFileOptions,
+    keep: &dyn Fn(&str) -> bool,
+) -> Result<()> {
+    if path.is_dir() {
+        zip.add_directory(name, options)?;
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            if keep(&file_name) {
+                let entry_name = format!("{}/{}", name, file_name);
+                add_path(zip, &entry.path(), &entry_name, options, keep)?;
+            }
+        }
+        return Ok(());
+    }
+
+    zip.start_file(name, options)?;
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    io::copy(&mut file, zip)?;
+    Ok(())
+}
+
+/// Read the manifest of the snapshot at `path` without unpacking it
+pub fn read_snapshot_manifest(path: &Path) -> Result<SnapshotManifest> {
+    read_manifest(&mut open_archive(path)?)
+}
+
+/// Unpack the snapshot at `path` into `storage`
+///
+/// The data directory must not have a mailbox yet: import into a new
+/// profile, or move the old database away first. Snapshots from a newer
+/// build are refused; older ones are migrated when the store next opens.
+pub fn import_snapshot(storage: &StorageSettings, path: &Path) -> Result<SnapshotManifest> {
+    let mut archive = open_archive(path)?;
+    let manifest = read_manifest(&mut archive)?;
+    if manifest.format > SNAPSHOT_FORMAT {
+        bail!(
+            "Snapshot format {} is newer than this build reads ({}); update Cosmos first",
+            manifest.format,
+            SNAPSHOT_FORMAT
+        );
+    }
+    if manifest.schema_version > schema_version() {
+        bail!(
+            "Snapshot database schema {} is newer than this build's ({}); update Cosmos first",
+            manifest.schema_version,
+            schema_version()
+        );
+    }
+
+    let dir = storage.dir()?;
+    let db_path = storage.db_path()?;
+    if db_path.exists() {
+        bail!(
+            "{} already has a mailbox; import into a new profile or move it away first",
+            dir.display()
+        );
+    }
+
+    let staging = dir.join(STAGING_DIR);
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    std::fs::create_dir_all(&staging)
+        .with_context(|| format!("Failed to create {}", staging.display()))?;
+    extract(&mut archive, &staging)?;
+
+    // With no database, anything left here belongs to no mailbox; a stray
+    // WAL would even be replayed into the imported one
+    for name in ["mail.db-wal", "mail.db-shm"] {
+        remove_path(&db_path.with_file_name(name))?;
+    }
+    // Database last, so an interrupted import still looks like no mailbox
+    let targets = [
+        (BLOBS_ENTRY, storage.blob_path()?),
+        (INDEX_ENTRY, storage.index_path()?),
+        (DB_ENTRY, db_path),
+    ];
+    for (entry, target) in targets {
+        let source = staging.join(entry);
+        if source.exists() {
+            remove_path(&target)?;
+            std::fs::rename(&source, &target)
+                .with_context(|| format!("Failed to move snapshot data to {}", target.display()))?;
+        }
+    }
+    std::fs::remove_dir_all(&staging)?;
+    Ok(manifest)
+}
+
+fn open_archive(path: &Path) -> Result<ZipArchive<BufReader<File>>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    ZipArchive::new(BufReader::new(file))
+        .with_context(|| format!("{} is not a Cosmos snapshot", path.display()))
+}
+
+fn read_manifest(archive: &mut ZipArchive<BufReader<File>>) -> Result<SnapshotManifest> {
+    let entry = archive
+        .by_name(MANIFEST_ENTRY)
+        .context("Snapshot has no manifest")?;
+    serde_json::from_reader(entry).context("Failed to parse snapshot manifest")
+}
+
+fn extract(archive: &mut ZipArchive<BufReader<File>>, dir: &Path) -> Result<()> {
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        // Refuse names that would land outside `dir`
+        let Some(relative) = entry.enclosed_name() else {
+            bail!("Snapshot has an invalid entry: {}", entry.name());
+        };
+        let target = dir.join(relative);
+        if entry.is_dir() {
+            std::fs::create_dir_all(&target)?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = File::create(&target)
+            .with_context(|| format!("Failed to create {}", target.display()))?;
+        io::copy(&mut entry, &mut file)?;
+    }
+    Ok(())
+}
+
+fn remove_path(path: &Path) -> Result<()> {
+    let result = if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+    match result {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("Failed to remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Account;
+    use crate::storage::{FileBlobStore, MailStore, SqliteMailStore};
+    use std::path::PathBuf;
+
+    fn storage_in(dir: &Path) -> StorageSettings {
+        StorageSettings {
+            data_dir: Some(dir.to_path_buf()),
+        }
+    }
+
+    fn open_store(storage: &StorageSettings) -> SqliteMailStore {
+        let blobs = Box::new(FileBlobStore::new(storage.blob_path().unwrap()).unwrap());
+        SqliteMailStore::new(storage.db_path().unwrap(), blobs).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = storage_in(&dir.path().join("old"));
+        let store = open_store(&source);
+        store
+            .register_account(Account::new("me@example.com"))
+            .unwrap();
+        let blob: PathBuf = source.blob_path().unwrap().join("ab/abc.txt.zst");
+        std::fs::create_dir_all(blob.parent().unwrap()).unwrap();
+        std::fs::write(&blob, "body").unwrap();
+
+        // Exported while the store is open
+        let archive = dir.path().join("mailbox.zip");
+        let exported = export_snapshot(&source, &archive).unwrap();
+        assert_eq!(exported.accounts, vec!["me@example.com"]);
+        assert_eq!(exported.schema_version, schema_version());
+        assert_eq!(read_snapshot_manifest(&archive).unwrap(), exported);
+
+        let target = storage_in(&dir.path().join("new"));
+        import_snapshot(&target, &archive).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(target.blob_path().unwrap().join("ab/abc.txt.zst")).unwrap(),
+            "body"
+        );
+        let imported = open_store(&target);
+        let accounts = imported.list_accounts().unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].email, "me@example.com");
+        assert!(!dir.path().join("new").join(STAGING_DIR).exists());
+
+        // Never over an existing mailbox
+        assert!(import_snapshot(&target, &archive).is_err());
+    }
+
+    #[test]
+    fn test_import_refuses_newer_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("future.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        zip.start_file(MANIFEST_ENTRY, SimpleFileOptions::default())
+            .unwrap();
+        let manifest = SnapshotManifest {
+            format: SNAPSHOT_FORMAT,
+            schema_version: schema_version() + 1,
+            created_by: "cosmos 99.0.0".to_string(),
+            created_at: Utc::now(),
+            accounts: Vec::new(),
+            search_index: false,
+        };
+        serde_json::to_writer(&mut zip, &manifest).unwrap();
+        zip.finish().unwrap();
+
+        let target = storage_in(&dir.path().join("new"));
+        let err = import_snapshot(&target, &archive).unwrap_err();
+        assert!(err.to_string().contains("newer"));
+        assert!(!target.db_path().unwrap().exists());
+    }
+}
@@ -11,6 +11,8 @@ pub enum ContentType {
     BodyHtml,
     /// Attachment (future)
     Attachment,
+    /// Machine-translated body, cached per target language
+    Translation,
 }
 
 impl ContentType {
@@ -20,6 +22,7 @@ impl ContentType {
             ContentType::BodyText => "txt",
             ContentType::BodyHtml => "html",
             ContentType::Attachment => "bin",
+            ContentType::Translation => "tr",
         }
     }
 }
@@ -54,6 +57,15 @@ impl BlobKey {
         }
     }
 
+    /// Create a key for a body translated into `lang`
+    pub fn translation(message_id: &str, lang: &str) -> Self {
+        Self {
+            message_id: message_id.to_string(),
+            content_type: ContentType::Translation,
+            part_id: Some(lang.to_string()),
+        }
+    }
+
     /// Create a key for an attachment
     pub fn attachment(message_id: &str, part_id: &str) -> Self {
         Self {
@@ -64,6 +76,15 @@ impl BlobKey {
     }
 }
 
+/// A stored blob and its size on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobEntry {
+    /// Key the blob is stored under
+    pub key: BlobKey,
+    /// Stored (compressed) size in bytes
+    pub stored_bytes: u64,
+}
+
 /// Trait for blob storage operations
 ///
 /// Implementations handle compression/decompression internally.
@@ -88,6 +109,16 @@ pub trait BlobStore: Send + Sync {
     /// Delete all blobs for a message
     fn delete_all_for_message(&self, message_id: &str) -> Result<()>;
 
+    /// List every stored blob
+    fn list(&self) -> Result<Vec<BlobEntry>>;
+
+    /// Check blobs written since the last check and set aside unreadable ones
+    ///
+    /// Call at startup, before serving reads: a crash or a failing disk can
+    /// leave truncated or corrupt blobs behind. Returns the keys of the blobs
+    /// set aside, so their content can be fetched again.
+    fn recover(&self) -> Result<Vec<BlobKey>>;
+
     /// Clear all blobs (for testing/reset)
     fn clear(&self) -> Result<()>;
 }
@@ -1,12 +1,30 @@
 //! File-based blob storage with zstd compression
+//!
+//! Writes go to a temp file that is flushed to disk and then renamed over the
+//! blob, so a crash leaves either the old blob or the new one. Every blob
+//! carries zstd's content checksum, and [`BlobStore::recover`] moves blobs
+//! that fail it (or are truncated) aside at startup.
 
 use std::fs;
-use std::io::Read;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result};
+use log::warn;
 
-use super::blob::{BlobKey, BlobStore, ContentType};
+use super::blob::{BlobEntry, BlobKey, BlobStore, ContentType};
+use crate::error::MailError;
+
+/// Directory under the root that unreadable blobs are moved to
+const QUARANTINE_DIR: &str = ".quarantine";
+
+/// File under the root holding when `recover` last ran (seconds since the epoch)
+const VERIFIED_MARKER: &str = ".verified";
+
+/// Tells apart the temp files of concurrent writes to the same blob
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
 
 /// File-based blob storage with zstd compression
 ///
@@ -17,8 +35,11 @@ use super::blob::{BlobKey, BlobStore, ContentType};
 ///     ab12cd34ef56.txt.zst     # body_text for message ab12cd34ef56
 ///     ab12cd34ef56.html.zst    # body_html for message ab12cd34ef56
 ///     ab12cd34ef56.att.0.zst   # attachment 0
+///     ab12cd34ef56.tr.fra.zst  # body translated to French
 ///   cd/
 ///     cd78ef90ab12.txt.zst
+///   .quarantine/             # blobs `recover` found unreadable
+///   .verified                # when `recover` last ran
 /// ```
 pub struct FileBlobStore {
     root: PathBuf,
@@ -56,6 +77,11 @@ impl FileBlobStore {
                 format!("{}.att.{}.zst", key.message_id, part)
             }
             (ContentType::Attachment, None) => format!("{}.att.zst", key.message_id),
+            (ContentType::Translation, lang) => format!(
+                "{}.tr.{}.zst",
+                key.message_id,
+                lang.as_deref().unwrap_or_default()
+            ),
         };
 
         self.root.join(shard).join(filename)
@@ -85,27 +111,132 @@ impl FileBlobStore {
 
         Ok(paths)
     }
+
+    /// Shard directories, skipping the store's own bookkeeping
+    fn shard_dirs(&self) -> Result<Vec<PathBuf>> {
+        let mut dirs = Vec::new();
+        for entry in fs::read_dir(&self.root)? {
+            let entry = entry?;
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if entry.file_type()?.is_dir() && !hidden {
+                dirs.push(entry.path());
+            }
+        }
+        Ok(dirs)
+    }
+
+    /// Compress `data`, with a checksum of the original for `decompress` to verify
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut encoder = zstd::Encoder::new(Vec::new(), self.compression_level)?;
+        encoder.include_checksum(true)?;
+        encoder.write_all(data)?;
+        Ok(encoder.finish()?)
+    }
+
+    /// When `recover` last ran, if it ever did
+    fn verified_at(&self) -> Option<SystemTime> {
+        let marker = fs::read_to_string(self.root.join(VERIFIED_MARKER)).ok()?;
+        let secs = marker.trim().parse().ok()?;
+        Some(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    /// Move an unreadable blob out of the store
+    fn quarantine(&self, path: &Path) -> Result<()> {
+        let dir = self.root.join(QUARANTINE_DIR);
+        fs::create_dir_all(&dir)?;
+        if let Some(name) = path.file_name() {
+            fs::rename(path, dir.join(name))?;
+        }
+        Ok(())
+    }
+}
+
+/// Decompress a blob, failing if it is truncated or fails its checksum
+///
+/// Blobs written before checksums were added have none and are only checked
+/// for truncation.
+fn decompress(compressed: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut decoder = zstd::Decoder::new(compressed)?;
+    let mut decompressed = Vec::new();
+    decoder.read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// Unique temp file next to `path`, e.g. `ab12.txt.zst.4711-3.tmp`
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    path.with_file_name(name)
+}
+
+/// Write `data` to a new file and flush it to disk
+fn write_synced(path: &Path, data: &[u8]) -> Result<()> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Flush a directory's entries, making a rename into it durable
+///
+/// Windows can't open directories as files; its renames are journaled by NTFS.
+fn sync_dir(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    fs::File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// Recover the key from a blob file name (the inverse of `blob_path`)
+fn parse_blob_name(name: &str) -> Option<BlobKey> {
+    let stem = name.strip_suffix(".zst")?;
+    if let Some(id) = stem.strip_suffix(".txt") {
+        return Some(BlobKey::body_text(id));
+    }
+    if let Some(id) = stem.strip_suffix(".html") {
+        return Some(BlobKey::body_html(id));
+    }
+    if let Some(id) = stem.strip_suffix(".att") {
+        return Some(BlobKey {
+            message_id: id.to_string(),
+            content_type: ContentType::Attachment,
+            part_id: None,
+        });
+    }
+    if let Some((id, lang)) = stem.rsplit_once(".tr.")
+        && !lang.contains('.')
+    {
+        return Some(BlobKey::translation(id, lang));
+    }
+    let (id, part) = stem.rsplit_once(".att.")?;
+    Some(BlobKey::attachment(id, part))
 }
 
 impl BlobStore for FileBlobStore {
     fn put(&self, key: &BlobKey, data: &[u8]) -> Result<()> {
         let path = self.blob_path(key);
+        let parent = path.parent().context("Blob path has no parent")?;
 
         // Ensure parent directory exists
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
+        fs::create_dir_all(parent)?;
 
         // Compress with zstd
-        let compressed =
-            zstd::encode_all(data, self.compression_level).context("Failed to compress blob")?;
-
-        // Write atomically (write to temp, then rename)
-        let temp_path = path.with_extension("tmp");
-        fs::write(&temp_path, &compressed)?;
-        fs::rename(&temp_path, &path)?;
-
-        Ok(())
+        let compressed = self.compress(data).context("Failed to compress blob")?;
+
+        // Write atomically: the temp file is on disk before it replaces the blob
+        let temp_path = temp_path(&path);
+        let written = write_synced(&temp_path, &compressed)
+            .and_then(|()| fs::rename(&temp_path, &path).map_err(anyhow::Error::from));
+        if let Err(e) = written {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e.context(format!("Failed to write blob {}", path.display())));
+        }
+        sync_dir(parent)
     }
 
     fn get(&self, key: &BlobKey) -> Result<Option<Vec<u8>>> {
@@ -116,11 +247,8 @@ impl BlobStore for FileBlobStore {
         }
 
         let compressed = fs::read(&path)?;
-        let mut decoder = zstd::Decoder::new(compressed.as_slice())?;
-        let mut decompressed = Vec::new();
-        decoder
-            .read_to_end(&mut decompressed)
-            .context("Failed to decompress blob")?;
+        let decompressed = decompress(&compressed)
+            .map_err(|e| MailError::Corruption(format!("Blob {}: {}", path.display(), e)))?;
 
         Ok(Some(decompressed))
     }
@@ -144,6 +272,70 @@ impl BlobStore for FileBlobStore {
         Ok(())
     }
 
+    fn list(&self) -> Result<Vec<BlobEntry>> {
+        let mut entries = Vec::new();
+
+        for shard in self.shard_dirs()? {
+            for entry in fs::read_dir(shard)? {
+                let entry = entry?;
+                // Skips temp files left by interrupted writes
+                let Some(key) = entry.file_name().to_str().and_then(parse_blob_name) else {
+                    continue;
+                };
+                entries.push(BlobEntry {
+                    key,
+                    stored_bytes: entry.metadata()?.len(),
+                });
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn recover(&self) -> Result<Vec<BlobKey>> {
+        let verified_at = self.verified_at();
+        let started_at = SystemTime::now();
+        let mut quarantined = Vec::new();
+
+        for shard in self.shard_dirs()? {
+            for entry in fs::read_dir(shard)? {
+                let entry = entry?;
+                let path = entry.path();
+                let name = entry.file_name();
+                let Some(name) = name.to_str() else {
+                    continue;
+                };
+
+                // A write cut short before its rename; the blob it was
+                // replacing, if any, is untouched
+                if name.ends_with(".tmp") {
+                    fs::remove_file(&path)?;
+                    continue;
+                }
+                let Some(key) = parse_blob_name(name) else {
+                    continue;
+                };
+                // Blobs checked by an earlier run haven't changed since
+                let modified = entry.metadata()?.modified()?;
+                if verified_at.is_some_and(|at| modified < at) {
+                    continue;
+                }
+
+                let compressed = fs::read(&path)?;
+                if let Err(e) = decompress(&compressed) {
+                    warn!("Quarantining unreadable blob {}: {}", path.display(), e);
+                    self.quarantine(&path)?;
+                    quarantined.push(key);
+                }
+            }
+        }
+
+        let secs = started_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        fs::write(self.root.join(VERIFIED_MARKER), secs.to_string())?;
+
+        Ok(quarantined)
+    }
+
     fn clear(&self) -> Result<()> {
         if self.root.exists() {
             fs::remove_dir_all(&self.root)?;
@@ -272,4 +464,89 @@ mod tests {
         let retrieved = store.get(&key).unwrap().unwrap();
         assert_eq!(retrieved, data.as_bytes());
     }
+
+    #[test]
+    fn test_list() {
+        let dir = tempdir().unwrap();
+        let store = FileBlobStore::new(dir.path().join("blobs")).unwrap();
+
+        store.put(&BlobKey::body_text("abc123"), b"text").unwrap();
+        store.put(&BlobKey::body_html("abc123"), b"html").unwrap();
+        store
+            .put(&BlobKey::attachment("cd4.5", "1"), b"attachment")
+            .unwrap();
+        fs::write(dir.path().join("blobs/ab/abc123.txt.tmp"), b"partial").unwrap();
+
+        let mut entries = store.list().unwrap();
+        entries.sort_by(|a, b| a.key.message_id.cmp(&b.key.message_id));
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[2].key, BlobKey::attachment("cd4.5", "1"));
+        assert_eq!(
+            entries[2].stored_bytes,
+            fs::metadata(store.blob_path(&entries[2].key)).unwrap().len()
+        );
+
+        let keys: Vec<_> = entries.iter().map(|e| e.key.content_type).collect();
+        assert!(keys.contains(&ContentType::BodyText));
+        assert!(keys.contains(&ContentType::BodyHtml));
+    }
+
+    #[test]
+    fn test_translation_blobs() {
+        let dir = tempdir().unwrap();
+        let store = FileBlobStore::new(dir.path().join("blobs")).unwrap();
+
+        let key = BlobKey::translation("abc123", "fra");
+        store.put(&key, b"Bonjour").unwrap();
+        store
+            .put(&BlobKey::attachment("x.tr.y", "1"), b"attachment")
+            .unwrap();
+        assert_eq!(store.get(&key).unwrap().unwrap(), b"Bonjour");
+
+        let mut keys: Vec<_> = store.list().unwrap().into_iter().map(|e| e.key).collect();
+        keys.sort_by(|a, b| a.message_id.cmp(&b.message_id));
+        assert_eq!(keys, vec![key, BlobKey::attachment("x.tr.y", "1")]);
+
+        store.delete_all_for_message("abc123").unwrap();
+        assert!(store.get(&BlobKey::translation("abc123", "fra")).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_recover_quarantines_unreadable_blobs() {
+        let dir = tempdir().unwrap();
+        let store = FileBlobStore::new(dir.path().join("blobs")).unwrap();
+
+        let intact = BlobKey::body_text("abc123");
+        let truncated = BlobKey::body_html("abc123");
+        let flipped = BlobKey::body_text("cd4567");
+        store.put(&intact, b"intact").unwrap();
+        store.put(&truncated, "truncated ".repeat(100).as_bytes()).unwrap();
+        store.put(&flipped, b"checksummed").unwrap();
+
+        // A crash mid-write, a torn blob, and a flipped bit in the checksum
+        let temp = dir.path().join("blobs/ab/abc123.txt.zst.1-0.tmp");
+        fs::write(&temp, b"partial").unwrap();
+        let path = store.blob_path(&truncated);
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+        let path = store.blob_path(&flipped);
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        fs::write(&path, &bytes).unwrap();
+        assert!(store.get(&flipped).is_err());
+
+        let mut quarantined = store.recover().unwrap();
+        quarantined.sort_by_key(|key| key.message_id.clone());
+        assert_eq!(quarantined, vec![truncated.clone(), flipped.clone()]);
+
+        assert!(!temp.exists());
+        assert!(dir.path().join("blobs/.quarantine/abc123.html.zst").exists());
+        assert_eq!(store.get(&intact).unwrap().unwrap(), b"intact");
+        assert!(store.get(&truncated).unwrap().is_none());
+        let keys: Vec<_> = store.list().unwrap().into_iter().map(|e| e.key).collect();
+        assert_eq!(keys, vec![intact]);
+
+        // Nothing new to check
+        assert!(store.recover().unwrap().is_empty());
+    }
 }
@@ -0,0 +1,116 @@
+//! Store change notifications
+//!
+//! Storage implementations publish a `StoreEvent` after each committed
+//! mutation so views can update incrementally instead of reloading.
+
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, Sender, channel};
+
+use crate::models::{MessageId, ThreadId};
+
+/// A change committed to the mail store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreEvent {
+    /// A thread was inserted or its metadata changed
+    ThreadUpserted {
+        thread_id: ThreadId,
+        account_id: i64,
+    },
+    /// A thread was removed (its last message was deleted)
+    ThreadRemoved { thread_id: ThreadId },
+    /// Labels on a message changed
+    LabelChanged {
+        thread_id: ThreadId,
+        message_id: MessageId,
+        /// Label IDs added to the message
+        added: Vec<String>,
+        /// Label IDs removed from the message
+        removed: Vec<String>,
+    },
+    /// Mail data was cleared in bulk
+    ///
+    /// `account_id` is None when every account was cleared.
+    Cleared { account_id: Option<i64> },
+}
+
+/// Fan-out of store events to subscribers
+///
+/// Subscribers whose receiver has been dropped are pruned on the next publish.
+#[derive(Default)]
+pub(crate) struct EventBus {
+    subscribers: Mutex<Vec<Sender<StoreEvent>>>,
+}
+
+impl EventBus {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new subscriber
+    pub(crate) fn subscribe(&self) -> Receiver<StoreEvent> {
+        let (tx, rx) = channel();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    /// Send an event to every live subscriber
+    pub(crate) fn publish(&self, event: StoreEvent) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        if subscribers.is_empty() {
+            return;
+        }
+        subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+    }
+}
+
+/// Compute labels added and removed between two label sets
+pub(crate) fn label_diff(old: &[String], new: &[String]) -> (Vec<String>, Vec<String>) {
+    let added = new.iter().filter(|l| !old.contains(l)).cloned().collect();
+    let removed = old.iter().filter(|l| !new.contains(l)).cloned().collect();
+    (added, removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish_reaches_all_subscribers() {
+        let bus = EventBus::new();
+        let rx1 = bus.subscribe();
+        let rx2 = bus.subscribe();
+
+        bus.publish(StoreEvent::Cleared { account_id: None });
+
+        assert_eq!(
+            rx1.try_recv().unwrap(),
+            StoreEvent::Cleared { account_id: None }
+        );
+        assert_eq!(
+            rx2.try_recv().unwrap(),
+            StoreEvent::Cleared { account_id: None }
+        );
+    }
+
+    #[test]
+    fn test_dropped_subscribers_are_pruned() {
+        let bus = EventBus::new();
+        let rx = bus.subscribe();
+        drop(bus.subscribe());
+
+        bus.publish(StoreEvent::Cleared {
+            account_id: Some(1),
+        });
+        assert_eq!(bus.subscribers.lock().unwrap().len(), 1);
+        assert!(rx.try_recv().is_ok());
+    }
+
+    #[test]
+    fn test_label_diff() {
+        let old = vec!["INBOX".to_string(), "UNREAD".to_string()];
+        let new = vec!["INBOX".to_string(), "STARRED".to_string()];
+        let (added, removed) = label_diff(&old, &new);
+        assert_eq!(added, vec!["STARRED".to_string()]);
+        assert_eq!(removed, vec!["UNREAD".to_string()]);
+    }
+}
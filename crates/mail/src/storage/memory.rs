@@ -4,12 +4,27 @@
 //! the real cosmos-storage integration is available.
 
 use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
 use std::cmp::Reverse;
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::Bound;
 use std::sync::RwLock;
-
-use super::traits::{MailStore, MessageBody, MessageMetadata, PendingMessage};
-use crate::models::{Account, Message, MessageId, SyncState, Thread, ThreadId};
+use std::sync::mpsc::Receiver;
+
+use super::blob::ContentType;
+use super::events::{EventBus, StoreEvent, label_diff};
+use super::traits::{
+    CompactionReport, ContentUsage, IntegrityReport, MAX_RECENT_SEARCHES, MailStore, MessageBody,
+    MessageMetadata, PendingMessage, StorageStats, ThreadCursor, rebuild_threads,
+};
+use crate::analytics::{DailyVolume, LabelStorage, SenderRanking, SenderStats};
+use crate::error::MailError;
+use crate::models::{
+    Account, AccountSettings, FollowupState, Label, LabelColor, LabelId, Message, MessageId, OpenStatus,
+    OutboxMessage, QueuedAction, Rule, SavedSearch, SendAsAlias, SyncState, Thread, ThreadId,
+    ThreadNote,
+};
 use std::sync::atomic::{AtomicI64, Ordering};
 
 /// In-memory implementation of MailStore
@@ -18,6 +33,7 @@ use std::sync::atomic::{AtomicI64, Ordering};
 /// This is a stub implementation for Phase 1, extended for Phase 2.
 /// Internal storage for pending messages
 struct PendingMessageData {
+    account_id: i64,
     data: Vec<u8>,
     label_ids: Vec<String>,
 }
@@ -36,10 +52,50 @@ pub struct InMemoryMailStore {
     thread_label_ts: RwLock<HashMap<(String, String), i64>>,
     /// Pending messages for deferred processing (Phase 4)
     pending_messages: RwLock<HashMap<String, PendingMessageData>>,
+    /// Messages queued to be fetched again
+    refetch_messages: RwLock<BTreeSet<String>>,
     /// Registered accounts (Multi-Account Support)
     accounts: RwLock<HashMap<i64, Account>>,
     /// Auto-increment counter for account IDs
     next_account_id: AtomicI64,
+    /// Send-as aliases by account ID
+    send_as_aliases: RwLock<HashMap<i64, Vec<SendAsAlias>>>,
+    /// User labels by account ID
+    labels: RwLock<HashMap<i64, Vec<Label>>>,
+    /// Local label icons and colors by account and label ID
+    label_overrides: RwLock<HashMap<(i64, String), (Option<String>, Option<LabelColor>)>>,
+    /// Saved searches by ID
+    saved_searches: RwLock<HashMap<i64, SavedSearch>>,
+    /// Auto-increment counter for saved search IDs
+    next_saved_search_id: AtomicI64,
+    /// Recent search queries, newest first
+    recent_searches: RwLock<Vec<String>>,
+    /// Mail rules by ID
+    rules: RwLock<BTreeMap<i64, Rule>>,
+    /// Auto-increment counter for rule IDs
+    next_rule_id: AtomicI64,
+    /// Offline label changes by ID, oldest first
+    queued_actions: RwLock<BTreeMap<i64, QueuedAction>>,
+    /// Auto-increment counter for queued action IDs
+    next_queued_action_id: AtomicI64,
+    /// Outgoing mail by ID, oldest first
+    outbox: RwLock<BTreeMap<i64, OutboxMessage>>,
+    /// Auto-increment counter for outbox IDs
+    next_outbox_id: AtomicI64,
+    /// Cached body translations by (message_id, lang)
+    translations: RwLock<HashMap<(String, String), String>>,
+    /// Cached thread summaries: thread_id -> (source_key, summary)
+    thread_summaries: RwLock<HashMap<String, (String, String)>>,
+    /// Beacon tokens: token -> message_id
+    tracking_tokens: RwLock<HashMap<String, String>>,
+    /// Recorded opens by message_id
+    message_opens: RwLock<HashMap<String, BTreeSet<DateTime<Utc>>>>,
+    /// Follow-up reminder state by thread_id
+    followup_states: RwLock<HashMap<String, FollowupState>>,
+    /// User notes by thread_id
+    thread_notes: RwLock<HashMap<String, ThreadNote>>,
+    /// Change notification subscribers
+    events: EventBus,
 }
 
 impl InMemoryMailStore {
@@ -53,8 +109,28 @@ impl InMemoryMailStore {
             label_thread_index: RwLock::new(HashMap::new()),
             thread_label_ts: RwLock::new(HashMap::new()),
             pending_messages: RwLock::new(HashMap::new()),
+            refetch_messages: RwLock::new(BTreeSet::new()),
             accounts: RwLock::new(HashMap::new()),
             next_account_id: AtomicI64::new(1),
+            send_as_aliases: RwLock::new(HashMap::new()),
+            labels: RwLock::new(HashMap::new()),
+            label_overrides: RwLock::new(HashMap::new()),
+            saved_searches: RwLock::new(HashMap::new()),
+            next_saved_search_id: AtomicI64::new(1),
+            recent_searches: RwLock::new(Vec::new()),
+            rules: RwLock::new(BTreeMap::new()),
+            next_rule_id: AtomicI64::new(1),
+            queued_actions: RwLock::new(BTreeMap::new()),
+            next_queued_action_id: AtomicI64::new(1),
+            outbox: RwLock::new(BTreeMap::new()),
+            next_outbox_id: AtomicI64::new(1),
+            translations: RwLock::new(HashMap::new()),
+            thread_summaries: RwLock::new(HashMap::new()),
+            tracking_tokens: RwLock::new(HashMap::new()),
+            message_opens: RwLock::new(HashMap::new()),
+            followup_states: RwLock::new(HashMap::new()),
+            thread_notes: RwLock::new(HashMap::new()),
+            events: EventBus::new(),
         }
     }
 
@@ -94,8 +170,16 @@ impl Default for InMemoryMailStore {
 
 impl MailStore for InMemoryMailStore {
     fn upsert_thread(&self, thread: Thread) -> Result<()> {
+        let event = StoreEvent::ThreadUpserted {
+            thread_id: thread.id.clone(),
+            account_id: thread.account_id,
+        };
+
         let mut threads = self.threads.write().unwrap();
         threads.insert(thread.id.0.clone(), thread);
+        drop(threads);
+
+        self.events.publish(event);
         Ok(())
     }
 
@@ -160,9 +244,121 @@ impl MailStore for InMemoryMailStore {
         Ok(messages.get(&id.0).map(|m| MessageBody {
             text: m.body_text.clone(),
             html: m.body_html.clone(),
+            attachment_text: m.attachment_text.clone(),
+        }))
+    }
+
+    fn get_translation(&self, id: &MessageId, lang: &str) -> Result<Option<String>> {
+        let translations = self.translations.read().unwrap();
+        Ok(translations.get(&(id.0.clone(), lang.to_string())).cloned())
+    }
+
+    fn put_translation(&self, id: &MessageId, lang: &str, text: &str) -> Result<()> {
+        self.translations
+            .write()
+            .unwrap()
+            .insert((id.0.clone(), lang.to_string()), text.to_string());
+        Ok(())
+    }
+
+    fn get_cached_summary(
+        &self,
+        thread_id: &ThreadId,
+        source_key: &str,
+    ) -> Result<Option<String>> {
+        let summaries = self.thread_summaries.read().unwrap();
+        Ok(summaries
+            .get(&thread_id.0)
+            .filter(|(key, _)| key == source_key)
+            .map(|(_, summary)| summary.clone()))
+    }
+
+    fn cache_summary(&self, thread_id: &ThreadId, source_key: &str, summary: &str) -> Result<()> {
+        self.thread_summaries.write().unwrap().insert(
+            thread_id.0.clone(),
+            (source_key.to_string(), summary.to_string()),
+        );
+        Ok(())
+    }
+
+    fn save_tracking_token(&self, token: &str, message_id: &MessageId) -> Result<()> {
+        self.tracking_tokens
+            .write()
+            .unwrap()
+            .insert(token.to_string(), message_id.0.clone());
+        Ok(())
+    }
+
+    fn record_message_open(&self, token: &str, opened_at: DateTime<Utc>) -> Result<bool> {
+        let Some(message_id) = self.tracking_tokens.read().unwrap().get(token).cloned() else {
+            return Ok(false);
+        };
+        let mut opens = self.message_opens.write().unwrap();
+        Ok(opens.entry(message_id).or_default().insert(opened_at))
+    }
+
+    fn get_open_status(&self, message_id: &MessageId) -> Result<Option<OpenStatus>> {
+        let tracked = self
+            .tracking_tokens
+            .read()
+            .unwrap()
+            .values()
+            .any(|id| id == &message_id.0);
+        if !tracked {
+            return Ok(None);
+        }
+        let opens = self.message_opens.read().unwrap();
+        let times = opens.get(&message_id.0);
+        Ok(Some(OpenStatus {
+            open_count: times.map_or(0, |t| t.len() as u32),
+            first_opened_at: times.and_then(|t| t.first().copied()),
+            last_opened_at: times.and_then(|t| t.last().copied()),
         }))
     }
 
+    fn latest_message_open(&self) -> Result<Option<DateTime<Utc>>> {
+        let opens = self.message_opens.read().unwrap();
+        Ok(opens.values().filter_map(|t| t.last().copied()).max())
+    }
+
+    fn get_followup_state(&self, thread_id: &ThreadId) -> Result<Option<FollowupState>> {
+        Ok(self
+            .followup_states
+            .read()
+            .unwrap()
+            .get(&thread_id.0)
+            .cloned())
+    }
+
+    fn save_followup_state(&self, state: &FollowupState) -> Result<()> {
+        self.followup_states
+            .write()
+            .unwrap()
+            .insert(state.thread_id.0.clone(), state.clone());
+        Ok(())
+    }
+
+    fn get_note(&self, thread_id: &ThreadId) -> Result<Option<ThreadNote>> {
+        Ok(self.thread_notes.read().unwrap().get(&thread_id.0).cloned())
+    }
+
+    fn set_note(&self, thread_id: &ThreadId, markdown: &str) -> Result<()> {
+        let mut notes = self.thread_notes.write().unwrap();
+        if markdown.trim().is_empty() {
+            notes.remove(&thread_id.0);
+        } else {
+            notes.insert(
+                thread_id.0.clone(),
+                ThreadNote {
+                    thread_id: thread_id.clone(),
+                    markdown: markdown.to_string(),
+                    updated_at: Utc::now(),
+                },
+            );
+        }
+        Ok(())
+    }
+
     fn list_threads(&self, limit: usize, offset: usize) -> Result<Vec<Thread>> {
         let threads = self.threads.read().unwrap();
         let mut thread_list: Vec<_> = threads.values().cloned().collect();
@@ -221,6 +417,49 @@ impl MailStore for InMemoryMailStore {
         Ok(result)
     }
 
+    fn list_unsubscribable_messages(
+        &self,
+        account_id: Option<i64>,
+    ) -> Result<Vec<MessageMetadata>> {
+        let messages = self.messages.read().unwrap();
+
+        let mut result: Vec<MessageMetadata> = messages
+            .values()
+            .filter(|m| m.unsubscribe.is_some())
+            .filter(|m| account_id.is_none() || Some(m.account_id) == account_id)
+            .map(MessageMetadata::from)
+            .collect();
+
+        result.sort_by(|a, b| b.received_at.cmp(&a.received_at));
+
+        Ok(result)
+    }
+
+    fn list_messages_for_account(
+        &self,
+        label: Option<&str>,
+        account_id: Option<i64>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<MessageMetadata>> {
+        let messages = self.messages.read().unwrap();
+
+        let mut result: Vec<MessageMetadata> = messages
+            .values()
+            .filter(|m| label.is_none_or(|label| m.label_ids.iter().any(|l| l == label)))
+            .filter(|m| account_id.is_none() || Some(m.account_id) == account_id)
+            .map(MessageMetadata::from)
+            .collect();
+
+        result.sort_by(|a, b| {
+            b.received_at
+                .cmp(&a.received_at)
+                .then_with(|| a.id.as_str().cmp(b.id.as_str()))
+        });
+
+        Ok(result.into_iter().skip(offset).take(limit).collect())
+    }
+
     fn list_messages_for_thread_with_bodies(
         &self,
         thread_id: &ThreadId,
@@ -288,6 +527,26 @@ impl MailStore for InMemoryMailStore {
             .unwrap_or(0))
     }
 
+    fn first_message_at(&self, thread_id: &ThreadId) -> Result<Option<DateTime<Utc>>> {
+        let thread_messages = self.thread_messages.read().unwrap();
+        let messages = self.messages.read().unwrap();
+        Ok(thread_messages.get(&thread_id.0).and_then(|ids| {
+            ids.iter()
+                .filter_map(|id| messages.get(id))
+                .map(|m| m.received_at)
+                .min()
+        }))
+    }
+
+    fn latest_internal_date(&self, account_id: i64) -> Result<Option<i64>> {
+        let messages = self.messages.read().unwrap();
+        Ok(messages
+            .values()
+            .filter(|m| m.account_id == account_id)
+            .map(|m| m.internal_date)
+            .max())
+    }
+
     fn clear(&self) -> Result<()> {
         self.threads.write().unwrap().clear();
         self.messages.write().unwrap().clear();
@@ -296,7 +555,13 @@ impl MailStore for InMemoryMailStore {
         self.label_thread_index.write().unwrap().clear();
         self.thread_label_ts.write().unwrap().clear();
         self.pending_messages.write().unwrap().clear();
+        self.refetch_messages.write().unwrap().clear();
         self.accounts.write().unwrap().clear();
+        self.translations.write().unwrap().clear();
+        self.thread_summaries.write().unwrap().clear();
+        self.followup_states.write().unwrap().clear();
+        self.thread_notes.write().unwrap().clear();
+        self.events.publish(StoreEvent::Cleared { account_id: None });
         Ok(())
     }
 
@@ -330,7 +595,9 @@ impl MailStore for InMemoryMailStore {
         self.thread_messages.write().unwrap().clear();
         self.label_thread_index.write().unwrap().clear();
         self.thread_label_ts.write().unwrap().clear();
+        self.refetch_messages.write().unwrap().clear();
         // Note: sync_states is NOT cleared
+        self.events.publish(StoreEvent::Cleared { account_id: None });
         Ok(())
     }
 
@@ -345,6 +612,49 @@ impl MailStore for InMemoryMailStore {
         Ok(ids)
     }
 
+    fn list_duplicate_messages(
+        &self,
+        thread_id: &ThreadId,
+    ) -> Result<Vec<(MessageId, MessageId)>> {
+        let ids = self.get_message_ids_for_thread(thread_id)?;
+        let messages = self.messages.read().unwrap();
+
+        let mut pairs = Vec::new();
+        for id in ids {
+            let Some(message) = messages.get(&id.0) else {
+                continue;
+            };
+            let Some(rfc_message_id) = &message.rfc_message_id else {
+                continue;
+            };
+            // Earliest-received copy, then lowest ID, as in the SQLite store
+            let canonical = messages
+                .values()
+                .filter(|m| {
+                    m.account_id == message.account_id
+                        && m.rfc_message_id.as_ref() == Some(rfc_message_id)
+                })
+                .min_by(|a, b| (a.internal_date, &a.id.0).cmp(&(b.internal_date, &b.id.0)))
+                .map(|m| m.id.clone());
+            if let Some(canonical) = canonical
+                && canonical != id
+            {
+                pairs.push((id, canonical));
+            }
+        }
+        pairs.sort_by(|a, b| a.0.0.cmp(&b.0.0));
+        Ok(pairs)
+    }
+
+    fn list_message_ids(&self, account_id: i64) -> Result<Vec<MessageId>> {
+        let messages = self.messages.read().unwrap();
+        Ok(messages
+            .values()
+            .filter(|m| m.account_id == account_id)
+            .map(|m| m.id.clone())
+            .collect())
+    }
+
     fn update_message_labels(&self, message_id: &MessageId, label_ids: Vec<String>) -> Result<()> {
         let mut messages = self.messages.write().unwrap();
 
@@ -415,6 +725,16 @@ impl MailStore for InMemoryMailStore {
                     thread.is_unread = any_unread;
                 }
             }
+
+            let (added, removed) = label_diff(&old_labels, &label_ids);
+            if !added.is_empty() || !removed.is_empty() {
+                self.events.publish(StoreEvent::LabelChanged {
+                    thread_id: ThreadId::new(thread_id),
+                    message_id: message_id.clone(),
+                    added,
+                    removed,
+                });
+            }
         }
 
         Ok(())
@@ -431,6 +751,12 @@ impl MailStore for InMemoryMailStore {
 
         let thread_id = message.thread_id.0.clone();
 
+        self.refetch_messages.write().unwrap().remove(&message_id.0);
+        self.translations
+            .write()
+            .unwrap()
+            .retain(|(id, _), _| *id != message_id.0);
+
         // Remove from thread_messages index
         {
             let mut thread_messages = self.thread_messages.write().unwrap();
@@ -466,14 +792,117 @@ impl MailStore for InMemoryMailStore {
             .map(|s| s.len())
             .unwrap_or(0);
 
-        if remaining_count == 0 {
+        let event = if remaining_count == 0 {
             // Delete the thread entirely
-            threads.remove(&thread_id);
+            threads
+                .remove(&thread_id)
+                .map(|_| StoreEvent::ThreadRemoved {
+                    thread_id: ThreadId::new(&thread_id),
+                })
         } else if let Some(thread) = threads.get_mut(&thread_id) {
             // Update message count
             thread.message_count = remaining_count;
+            Some(StoreEvent::ThreadUpserted {
+                thread_id: thread.id.clone(),
+                account_id: thread.account_id,
+            })
+        } else {
+            None
+        };
+
+        drop(thread_messages);
+        drop(threads);
+
+        if let Some(event) = event {
+            self.events.publish(event);
+        }
+
+        Ok(())
+    }
+
+    fn move_message(&self, message_id: &MessageId, thread_id: &ThreadId) -> Result<()> {
+        let mut messages = self.messages.write().unwrap();
+        let Some(message) = messages.get_mut(&message_id.0) else {
+            return Ok(());
+        };
+        if message.thread_id == *thread_id {
+            return Ok(());
+        }
+        let old_thread_id = std::mem::replace(&mut message.thread_id, thread_id.clone()).0;
+        let labels = message.label_ids.clone();
+        drop(messages);
+
+        {
+            let mut thread_messages = self.thread_messages.write().unwrap();
+            if let Some(set) = thread_messages.get_mut(&old_thread_id) {
+                set.remove(&message_id.0);
+            }
+            thread_messages
+                .entry(thread_id.0.clone())
+                .or_default()
+                .insert(message_id.0.clone());
+        }
+
+        // Drop the old thread's entries for the moved labels; those its
+        // other messages still carry are added back below
+        {
+            let mut index = self.label_thread_index.write().unwrap();
+            let mut reverse = self.thread_label_ts.write().unwrap();
+            for label in &labels {
+                let key = (old_thread_id.clone(), label.clone());
+                if let Some(ts) = reverse.remove(&key)
+                    && let Some(set) = index.get_mut(label)
+                {
+                    set.remove(&(Reverse(ts), old_thread_id.clone()));
+                }
+            }
+        }
+        let timestamp_millis = self
+            .threads
+            .read()
+            .unwrap()
+            .get(&thread_id.0)
+            .map(|t| t.last_message_at.timestamp_millis())
+            .unwrap_or_default();
+        self.update_label_index(&thread_id.0, &labels, timestamp_millis);
+
+        let remaining = self.get_message_ids_for_thread(&ThreadId::new(&old_thread_id))?;
+        let remaining_labels: Vec<String> = {
+            let messages = self.messages.read().unwrap();
+            remaining
+                .iter()
+                .filter_map(|id| messages.get(&id.0))
+                .flat_map(|m| m.label_ids.iter().cloned())
+                .collect()
+        };
+        if let Some(thread) = self.threads.read().unwrap().get(&old_thread_id) {
+            let timestamp_millis = thread.last_message_at.timestamp_millis();
+            self.update_label_index(&old_thread_id, &remaining_labels, timestamp_millis);
         }
 
+        // Recount the old thread, or delete it if empty
+        let remaining_count = remaining.len();
+        let mut threads = self.threads.write().unwrap();
+        let event = if remaining_count == 0 {
+            threads
+                .remove(&old_thread_id)
+                .map(|_| StoreEvent::ThreadRemoved {
+                    thread_id: ThreadId::new(&old_thread_id),
+                })
+        } else if let Some(thread) = threads.get_mut(&old_thread_id) {
+            thread.message_count = remaining_count;
+            Some(StoreEvent::ThreadUpserted {
+                thread_id: thread.id.clone(),
+                account_id: thread.account_id,
+            })
+        } else {
+            None
+        };
+        drop(threads);
+
+        if let Some(event) = event {
+            self.events.publish(event);
+        }
         Ok(())
     }
 
@@ -482,11 +911,12 @@ impl MailStore for InMemoryMailStore {
     fn store_pending_message(
         &self,
         id: &MessageId,
-        _account_id: i64,
+        account_id: i64,
         data: &[u8],
         label_ids: Vec<String>,
     ) -> Result<()> {
         let pending_data = PendingMessageData {
+            account_id,
             data: data.to_vec(),
             label_ids,
         };
@@ -574,6 +1004,34 @@ impl MailStore for InMemoryMailStore {
         Ok(())
     }
 
+    // === Re-fetch Queue ===
+
+    fn mark_for_refetch(&self, ids: &[MessageId]) -> Result<()> {
+        let messages = self.messages.read().unwrap();
+        let mut refetch = self.refetch_messages.write().unwrap();
+        for id in ids {
+            if messages.contains_key(&id.0) {
+                refetch.insert(id.0.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn list_refetch_messages(&self, account_id: i64) -> Result<Vec<MessageId>> {
+        let messages = self.messages.read().unwrap();
+        let refetch = self.refetch_messages.read().unwrap();
+        Ok(refetch
+            .iter()
+            .filter(|id| messages.get(*id).is_some_and(|m| m.account_id == account_id))
+            .map(|id| MessageId::new(id))
+            .collect())
+    }
+
+    fn clear_refetch(&self, id: &MessageId) -> Result<()> {
+        self.refetch_messages.write().unwrap().remove(&id.0);
+        Ok(())
+    }
+
     // === Multi-Account Support Methods ===
 
     fn register_account(&self, account: Account) -> Result<Account> {
@@ -586,6 +1044,8 @@ impl MailStore for InMemoryMailStore {
             is_primary: account.is_primary,
             added_at: account.added_at,
             token_data: account.token_data,
+            signature: account.signature,
+            sync_enabled: account.sync_enabled,
         };
         self.accounts
             .write()
@@ -620,8 +1080,26 @@ impl MailStore for InMemoryMailStore {
         // Clear account data first
         self.clear_account_data(account_id)?;
 
-        // Then remove the account itself
+        // Then remove the account itself, its aliases and its rules
         self.accounts.write().unwrap().remove(&account_id);
+        self.send_as_aliases.write().unwrap().remove(&account_id);
+        self.labels.write().unwrap().remove(&account_id);
+        self.label_overrides
+            .write()
+            .unwrap()
+            .retain(|(id, _), _| *id != account_id);
+        self.rules
+            .write()
+            .unwrap()
+            .retain(|_, rule| rule.account_id != Some(account_id));
+        self.queued_actions
+            .write()
+            .unwrap()
+            .retain(|_, action| action.account_id != account_id);
+        self.outbox
+            .write()
+            .unwrap()
+            .retain(|_, message| message.account_id != account_id);
         Ok(())
     }
 
@@ -633,6 +1111,138 @@ impl MailStore for InMemoryMailStore {
         Ok(())
     }
 
+    fn update_account_settings(&self, account_id: i64, settings: AccountSettings) -> Result<()> {
+        let mut accounts = self.accounts.write().unwrap();
+        if let Some(account) = accounts.get_mut(&account_id) {
+            account.apply_settings(settings);
+        }
+        Ok(())
+    }
+
+    fn set_account_sync_enabled(&self, account_id: i64, enabled: bool) -> Result<()> {
+        let mut accounts = self.accounts.write().unwrap();
+        if let Some(account) = accounts.get_mut(&account_id) {
+            account.sync_enabled = enabled;
+        }
+        Ok(())
+    }
+
+    fn replace_send_as_aliases(&self, account_id: i64, aliases: Vec<SendAsAlias>) -> Result<()> {
+        let aliases = aliases
+            .into_iter()
+            .map(|alias| SendAsAlias {
+                account_id,
+                ..alias
+            })
+            .collect();
+        self.send_as_aliases
+            .write()
+            .unwrap()
+            .insert(account_id, aliases);
+        Ok(())
+    }
+
+    fn list_send_as_aliases(&self, account_id: i64) -> Result<Vec<SendAsAlias>> {
+        let mut aliases = self
+            .send_as_aliases
+            .read()
+            .unwrap()
+            .get(&account_id)
+            .cloned()
+            .unwrap_or_default();
+        aliases.sort_by(|a, b| {
+            b.is_default
+                .cmp(&a.is_default)
+                .then_with(|| a.email.cmp(&b.email))
+        });
+        Ok(aliases)
+    }
+
+    fn replace_labels(&self, account_id: i64, labels: Vec<Label>) -> Result<()> {
+        let labels = labels
+            .into_iter()
+            .map(|label| Label::new(label.id, label.name).with_color(label.color))
+            .collect();
+        self.labels.write().unwrap().insert(account_id, labels);
+        Ok(())
+    }
+
+    fn list_labels(&self, account_id: i64) -> Result<Vec<Label>> {
+        let mut labels = self
+            .labels
+            .read()
+            .unwrap()
+            .get(&account_id)
+            .cloned()
+            .unwrap_or_default();
+        let overrides = self.label_overrides.read().unwrap();
+        for label in &mut labels {
+            if let Some((icon, color)) = overrides.get(&(account_id, label.id.0.clone())) {
+                label.icon = icon.clone();
+                if color.is_some() {
+                    label.color = color.clone();
+                }
+            }
+        }
+        labels.sort_by(|a, b| {
+            a.name
+                .to_lowercase()
+                .cmp(&b.name.to_lowercase())
+                .then_with(|| a.id.0.cmp(&b.id.0))
+        });
+        Ok(labels)
+    }
+
+    fn list_user_labels_for_threads(
+        &self,
+        thread_ids: &[ThreadId],
+    ) -> Result<HashMap<ThreadId, Vec<Label>>> {
+        let threads = self.threads.read().unwrap();
+        let reverse = self.thread_label_ts.read().unwrap();
+        let mut account_labels: HashMap<i64, Vec<Label>> = HashMap::new();
+        let mut labels = HashMap::new();
+
+        for thread_id in thread_ids {
+            let Some(thread) = threads.get(thread_id.as_str()) else {
+                continue;
+            };
+            let account_id = thread.account_id;
+            if let Entry::Vacant(entry) = account_labels.entry(account_id) {
+                entry.insert(self.list_labels(account_id)?);
+            }
+
+            let on_thread: Vec<Label> = account_labels[&account_id]
+                .iter()
+                .filter(|label| {
+                    reverse.contains_key(&(thread_id.0.clone(), label.id.0.clone()))
+                })
+                .cloned()
+                .collect();
+            if !on_thread.is_empty() {
+                labels.insert(thread_id.clone(), on_thread);
+            }
+        }
+
+        Ok(labels)
+    }
+
+    fn set_label_override(
+        &self,
+        account_id: i64,
+        label_id: &str,
+        icon: Option<String>,
+        color: Option<LabelColor>,
+    ) -> Result<()> {
+        let mut overrides = self.label_overrides.write().unwrap();
+        let key = (account_id, label_id.to_string());
+        if icon.is_none() && color.is_none() {
+            overrides.remove(&key);
+        } else {
+            overrides.insert(key, (icon, color));
+        }
+        Ok(())
+    }
+
     fn list_threads_for_account(
         &self,
         account_id: Option<i64>,
@@ -681,26 +1291,139 @@ impl MailStore for InMemoryMailStore {
         Ok(result)
     }
 
-    fn count_threads_for_account(&self, account_id: Option<i64>) -> Result<usize> {
-        let threads = self.threads.read().unwrap();
-        let count = if let Some(id) = account_id {
-            threads.values().filter(|t| t.account_id == id).count()
-        } else {
-            threads.len()
-        };
-        Ok(count)
-    }
-
-    fn count_threads_by_label_for_account(
+    fn list_threads_with_labels_after(
         &self,
-        label: &str,
+        labels: &[&str],
+        excluded_labels: &[&str],
         account_id: Option<i64>,
-    ) -> Result<usize> {
+        after: Option<&ThreadCursor>,
+        limit: usize,
+    ) -> Result<Vec<Thread>> {
+        let Some((first, _)) = labels.split_first() else {
+            anyhow::bail!("At least one label is required");
+        };
+
         let index = self.label_thread_index.read().unwrap();
+        let threads = self.threads.read().unwrap();
+        let reverse = self.thread_label_ts.read().unwrap();
 
-        if let Some(id) = account_id {
-            let threads = self.threads.read().unwrap();
-            let Some(label_set) = index.get(label) else {
+        let Some(label_set) = index.get(*first) else {
+            return Ok(Vec::new());
+        };
+
+        let start = match after {
+            Some(cursor) => {
+                let ts = reverse
+                    .get(&(cursor.thread_id.0.clone(), first.to_string()))
+                    .copied()
+                    .unwrap_or_else(|| cursor.last_message_at.timestamp_millis());
+                Bound::Excluded((Reverse(ts), cursor.thread_id.0.clone()))
+            }
+            None => Bound::Unbounded,
+        };
+
+        let result = label_set
+            .range((start, Bound::Unbounded))
+            .filter(|(_, thread_id)| {
+                has_label_set(&reverse, thread_id, labels, excluded_labels)
+            })
+            .filter_map(|(_, thread_id)| threads.get(thread_id).cloned())
+            .filter(|t| account_id.is_none() || Some(t.account_id) == account_id)
+            .take(limit)
+            .collect();
+
+        Ok(result)
+    }
+
+    fn list_threads_after(
+        &self,
+        label: Option<&str>,
+        account_id: Option<i64>,
+        after: Option<&ThreadCursor>,
+        limit: usize,
+    ) -> Result<Vec<Thread>> {
+        let matches_account =
+            |t: &Thread| account_id.is_none() || Some(t.account_id) == account_id;
+
+        if let Some(label) = label {
+            let index = self.label_thread_index.read().unwrap();
+            let threads = self.threads.read().unwrap();
+
+            let Some(label_set) = index.get(label) else {
+                return Ok(Vec::new());
+            };
+
+            // Seek directly to the cursor position in the sorted index. Prefer the
+            // timestamp the index actually holds for the cursor thread, since
+            // label entries can be keyed by message time rather than thread time.
+            let start = match after {
+                Some(cursor) => {
+                    let reverse = self.thread_label_ts.read().unwrap();
+                    let ts = reverse
+                        .get(&(cursor.thread_id.0.clone(), label.to_string()))
+                        .copied()
+                        .unwrap_or_else(|| cursor.last_message_at.timestamp_millis());
+                    Bound::Excluded((Reverse(ts), cursor.thread_id.0.clone()))
+                }
+                None => Bound::Unbounded,
+            };
+
+            let result = label_set
+                .range((start, Bound::Unbounded))
+                .filter_map(|(_, thread_id)| threads.get(thread_id).cloned())
+                .filter(|t| matches_account(t))
+                .take(limit)
+                .collect();
+
+            return Ok(result);
+        }
+
+        let threads = self.threads.read().unwrap();
+        let mut thread_list: Vec<_> = threads
+            .values()
+            .filter(|t| matches_account(t))
+            .filter(|t| match after {
+                Some(cursor) => {
+                    t.last_message_at < cursor.last_message_at
+                        || (t.last_message_at == cursor.last_message_at
+                            && t.id.0 > cursor.thread_id.0)
+                }
+                None => true,
+            })
+            .cloned()
+            .collect();
+
+        // Sort by last_message_at descending, thread_id ascending
+        thread_list.sort_by(|a, b| {
+            b.last_message_at
+                .cmp(&a.last_message_at)
+                .then_with(|| a.id.0.cmp(&b.id.0))
+        });
+        thread_list.truncate(limit);
+
+        Ok(thread_list)
+    }
+
+    fn count_threads_for_account(&self, account_id: Option<i64>) -> Result<usize> {
+        let threads = self.threads.read().unwrap();
+        let count = if let Some(id) = account_id {
+            threads.values().filter(|t| t.account_id == id).count()
+        } else {
+            threads.len()
+        };
+        Ok(count)
+    }
+
+    fn count_threads_by_label_for_account(
+        &self,
+        label: &str,
+        account_id: Option<i64>,
+    ) -> Result<usize> {
+        let index = self.label_thread_index.read().unwrap();
+
+        if let Some(id) = account_id {
+            let threads = self.threads.read().unwrap();
+            let Some(label_set) = index.get(label) else {
                 return Ok(0);
             };
             let count = label_set
@@ -745,6 +1468,96 @@ impl MailStore for InMemoryMailStore {
         Ok(count)
     }
 
+    fn count_unread_threads_per_label(
+        &self,
+        account_id: Option<i64>,
+    ) -> Result<HashMap<String, usize>> {
+        let index = self.label_thread_index.read().unwrap();
+        let threads = self.threads.read().unwrap();
+
+        let mut counts = HashMap::new();
+        for (label, label_set) in index.iter() {
+            let count = label_set
+                .iter()
+                .filter(|(_, thread_id)| {
+                    threads.get(thread_id).is_some_and(|t| {
+                        t.is_unread && (account_id.is_none() || Some(t.account_id) == account_id)
+                    })
+                })
+                .count();
+            if count > 0 {
+                counts.insert(label.clone(), count);
+            }
+        }
+        Ok(counts)
+    }
+
+    fn count_threads_with_labels(
+        &self,
+        labels: &[&str],
+        excluded_labels: &[&str],
+        account_id: Option<i64>,
+    ) -> Result<usize> {
+        let Some((first, _)) = labels.split_first() else {
+            anyhow::bail!("At least one label is required");
+        };
+
+        let index = self.label_thread_index.read().unwrap();
+        let threads = self.threads.read().unwrap();
+        let reverse = self.thread_label_ts.read().unwrap();
+
+        let Some(label_set) = index.get(*first) else {
+            return Ok(0);
+        };
+
+        let count = label_set
+            .iter()
+            .filter(|(_, thread_id)| {
+                has_label_set(&reverse, thread_id, labels, excluded_labels)
+            })
+            .filter(|(_, thread_id)| {
+                threads.get(thread_id).is_some_and(|t| {
+                    account_id.is_none() || Some(t.account_id) == account_id
+                })
+            })
+            .count();
+
+        Ok(count)
+    }
+
+    fn count_unread_threads_with_labels(
+        &self,
+        labels: &[&str],
+        excluded_labels: &[&str],
+        account_id: Option<i64>,
+    ) -> Result<usize> {
+        let Some((first, _)) = labels.split_first() else {
+            anyhow::bail!("At least one label is required");
+        };
+
+        let index = self.label_thread_index.read().unwrap();
+        let threads = self.threads.read().unwrap();
+        let reverse = self.thread_label_ts.read().unwrap();
+
+        let Some(label_set) = index.get(*first) else {
+            return Ok(0);
+        };
+
+        let count = label_set
+            .iter()
+            .filter(|(_, thread_id)| {
+                has_label_set(&reverse, thread_id, labels, excluded_labels)
+            })
+            .filter(|(_, thread_id)| {
+                threads.get(thread_id).is_some_and(|t| {
+                    t.is_unread && (account_id.is_none() || Some(t.account_id) == account_id)
+                })
+            })
+            .count();
+
+        Ok(count)
+    }
+
     fn clear_account_data(&self, account_id: i64) -> Result<()> {
         // Collect IDs to delete
         let thread_ids_to_delete: Vec<String> = {
@@ -780,11 +1593,17 @@ impl MailStore for InMemoryMailStore {
             }
         }
 
-        // Delete threads
+        // Delete threads, with the state SQLite removes by cascade
         {
             let mut threads = self.threads.write().unwrap();
+            let mut summaries = self.thread_summaries.write().unwrap();
+            let mut followups = self.followup_states.write().unwrap();
+            let mut notes = self.thread_notes.write().unwrap();
             for tid in &thread_ids_to_delete {
                 threads.remove(tid);
+                summaries.remove(tid);
+                followups.remove(tid);
+                notes.remove(tid);
             }
         }
 
@@ -813,15 +1632,435 @@ impl MailStore for InMemoryMailStore {
             }
         }
 
-        // Note: In-memory store doesn't track account_id on pending messages
-        // For a complete implementation, we'd need to add account_id to PendingMessageData
-        // For now, pending messages are not cleared per-account
+        {
+            let mut refetch = self.refetch_messages.write().unwrap();
+            let mut translations = self.translations.write().unwrap();
+            for id in &message_ids_to_delete {
+                refetch.remove(id);
+            }
+            translations.retain(|(message_id, _), _| !message_ids_to_delete.contains(message_id));
+        }
+
+        self.pending_messages
+            .write()
+            .unwrap()
+            .retain(|_, data| data.account_id != account_id);
 
         // Delete sync state for this account
         self.sync_states.write().unwrap().remove(&account_id);
 
+        self.events.publish(StoreEvent::Cleared {
+            account_id: Some(account_id),
+        });
+
         Ok(())
     }
+
+    fn save_search(&self, search: SavedSearch) -> Result<SavedSearch> {
+        let mut searches = self.saved_searches.write().unwrap();
+
+        if searches
+            .values()
+            .any(|s| s.name == search.name && s.id != search.id)
+        {
+            anyhow::bail!("Saved search named {:?} already exists", search.name);
+        }
+
+        let search = if search.id == 0 {
+            let id = self.next_saved_search_id.fetch_add(1, Ordering::SeqCst);
+            SavedSearch { id, ..search }
+        } else if searches.contains_key(&search.id) {
+            search
+        } else {
+            return Err(MailError::NotFound(format!("Saved search {}", search.id)).into());
+        };
+
+        searches.insert(search.id, search.clone());
+        Ok(search)
+    }
+
+    fn list_saved_searches(&self) -> Result<Vec<SavedSearch>> {
+        let searches = self.saved_searches.read().unwrap();
+        let mut list: Vec<_> = searches.values().cloned().collect();
+        // Sort: pinned first, then by name
+        list.sort_by(|a, b| {
+            b.pinned
+                .cmp(&a.pinned)
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        });
+        Ok(list)
+    }
+
+    fn delete_saved_search(&self, id: i64) -> Result<()> {
+        self.saved_searches.write().unwrap().remove(&id);
+        Ok(())
+    }
+
+    fn save_rule(&self, rule: Rule) -> Result<Rule> {
+        let mut rules = self.rules.write().unwrap();
+
+        let rule = if rule.id == 0 {
+            let id = self.next_rule_id.fetch_add(1, Ordering::SeqCst);
+            Rule { id, ..rule }
+        } else if rules.contains_key(&rule.id) {
+            rule
+        } else {
+            return Err(MailError::NotFound(format!("Rule {}", rule.id)).into());
+        };
+
+        rules.insert(rule.id, rule.clone());
+        Ok(rule)
+    }
+
+    fn list_rules(&self) -> Result<Vec<Rule>> {
+        Ok(self.rules.read().unwrap().values().cloned().collect())
+    }
+
+    fn delete_rule(&self, id: i64) -> Result<()> {
+        self.rules.write().unwrap().remove(&id);
+        Ok(())
+    }
+
+    fn queue_action(&self, action: QueuedAction) -> Result<QueuedAction> {
+        let id = self.next_queued_action_id.fetch_add(1, Ordering::SeqCst);
+        let action = QueuedAction { id, ..action };
+        self.queued_actions
+            .write()
+            .unwrap()
+            .insert(id, action.clone());
+        Ok(action)
+    }
+
+    fn list_queued_actions(&self, account_id: i64) -> Result<Vec<QueuedAction>> {
+        Ok(self
+            .queued_actions
+            .read()
+            .unwrap()
+            .values()
+            .filter(|action| action.account_id == account_id)
+            .cloned()
+            .collect())
+    }
+
+    fn delete_queued_action(&self, id: i64) -> Result<()> {
+        self.queued_actions.write().unwrap().remove(&id);
+        Ok(())
+    }
+
+    fn add_outbox_message(&self, message: OutboxMessage) -> Result<OutboxMessage> {
+        let id = self.next_outbox_id.fetch_add(1, Ordering::SeqCst);
+        let message = OutboxMessage { id, ..message };
+        self.outbox.write().unwrap().insert(id, message.clone());
+        Ok(message)
+    }
+
+    fn update_outbox_message(&self, message: &OutboxMessage) -> Result<()> {
+        let mut outbox = self.outbox.write().unwrap();
+        let stored = outbox
+            .get_mut(&message.id)
+            .ok_or_else(|| MailError::NotFound(format!("Outbox message {}", message.id)))?;
+        stored.state = message.state;
+        stored.attempts = message.attempts;
+        stored.last_error = message.last_error.clone();
+        stored.next_attempt_at = message.next_attempt_at;
+        stored.sent_message_id = message.sent_message_id.clone();
+        Ok(())
+    }
+
+    fn get_outbox_message(&self, id: i64) -> Result<Option<OutboxMessage>> {
+        Ok(self.outbox.read().unwrap().get(&id).cloned())
+    }
+
+    fn list_outbox_messages(&self, account_id: Option<i64>) -> Result<Vec<OutboxMessage>> {
+        Ok(self
+            .outbox
+            .read()
+            .unwrap()
+            .values()
+            .filter(|message| account_id.is_none_or(|id| message.account_id == id))
+            .cloned()
+            .collect())
+    }
+
+    fn delete_outbox_message(&self, id: i64) -> Result<()> {
+        self.outbox.write().unwrap().remove(&id);
+        Ok(())
+    }
+
+    fn sender_stats(
+        &self,
+        account_id: Option<i64>,
+        ranking: SenderRanking,
+        limit: usize,
+    ) -> Result<Vec<SenderStats>> {
+        let messages = self.messages.read().unwrap();
+
+        // Stats plus the internal date of the message they took the name from
+        let mut senders: HashMap<String, (SenderStats, i64)> = HashMap::new();
+        for message in received_messages(&messages, account_id) {
+            let email = message.from.email.to_lowercase();
+            let (stats, newest) = senders.entry(email.clone()).or_insert_with(|| {
+                let stats = SenderStats {
+                    email,
+                    name: None,
+                    message_count: 0,
+                    total_bytes: 0,
+                    last_received_at: message.received_at,
+                };
+                (stats, i64::MIN)
+            });
+            stats.message_count += 1;
+            stats.total_bytes += message.size_bytes;
+            if message.internal_date > *newest {
+                *newest = message.internal_date;
+                stats.name = message.from.name.clone();
+                stats.last_received_at = DateTime::from_timestamp_millis(message.internal_date)
+                    .unwrap_or(message.received_at);
+            }
+        }
+
+        let key = |s: &SenderStats| match ranking {
+            SenderRanking::Volume => (s.message_count as i64, s.total_bytes),
+            SenderRanking::Size => (s.total_bytes, s.message_count as i64),
+        };
+        let mut result: Vec<SenderStats> = senders.into_values().map(|(stats, _)| stats).collect();
+        result.sort_by(|a, b| key(b).cmp(&key(a)).then_with(|| a.email.cmp(&b.email)));
+        result.truncate(limit);
+
+        Ok(result)
+    }
+
+    fn daily_message_counts(
+        &self,
+        account_id: Option<i64>,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<DailyVolume>> {
+        let messages = self.messages.read().unwrap();
+
+        let mut days: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+        for message in received_messages(&messages, account_id) {
+            if message.internal_date < since.timestamp_millis() {
+                continue;
+            }
+            if let Some(at) = DateTime::from_timestamp_millis(message.internal_date) {
+                *days.entry(at.date_naive()).or_default() += 1;
+            }
+        }
+
+        Ok(days
+            .into_iter()
+            .map(|(date, message_count)| DailyVolume {
+                date,
+                message_count,
+            })
+            .collect())
+    }
+
+    fn reply_delays_ms(&self, account_id: Option<i64>, since: DateTime<Utc>) -> Result<Vec<i64>> {
+        let thread_messages = self.thread_messages.read().unwrap();
+        let messages = self.messages.read().unwrap();
+
+        let is_sent = |m: &Message| m.label_ids.iter().any(|l| l == LabelId::SENT);
+        let mut delays = Vec::new();
+        for msg_ids in thread_messages.values() {
+            let mut thread: Vec<&Message> =
+                msg_ids.iter().filter_map(|id| messages.get(id)).collect();
+            thread.sort_by_key(|m| m.internal_date);
+
+            for pair in thread.windows(2) {
+                let (previous, reply) = (pair[0], pair[1]);
+                if is_sent(reply)
+                    && !is_sent(previous)
+                    && previous.internal_date < reply.internal_date
+                    && reply.internal_date >= since.timestamp_millis()
+                    && account_id.is_none_or(|id| id == reply.account_id)
+                {
+                    delays.push(reply.internal_date - previous.internal_date);
+                }
+            }
+        }
+
+        Ok(delays)
+    }
+
+    fn label_storage(&self, account_id: Option<i64>) -> Result<Vec<LabelStorage>> {
+        let messages = self.messages.read().unwrap();
+
+        let mut labels: HashMap<&str, LabelStorage> = HashMap::new();
+        for message in messages.values() {
+            if account_id.is_some_and(|id| id != message.account_id) {
+                continue;
+            }
+            for label in &message.label_ids {
+                let storage = labels.entry(label.as_str()).or_insert_with(|| LabelStorage {
+                    label_id: label.clone(),
+                    message_count: 0,
+                    total_bytes: 0,
+                });
+                storage.message_count += 1;
+                storage.total_bytes += message.size_bytes;
+            }
+        }
+
+        let mut result: Vec<LabelStorage> = labels.into_values().collect();
+        result.sort_by(|a, b| {
+            b.total_bytes
+                .cmp(&a.total_bytes)
+                .then_with(|| a.label_id.cmp(&b.label_id))
+        });
+
+        Ok(result)
+    }
+
+    fn largest_threads(
+        &self,
+        account_id: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<(ThreadId, i64)>> {
+        let messages = self.messages.read().unwrap();
+
+        let mut totals: HashMap<&ThreadId, i64> = HashMap::new();
+        for message in messages.values() {
+            if account_id.is_some_and(|id| id != message.account_id) {
+                continue;
+            }
+            *totals.entry(&message.thread_id).or_default() += message.size_bytes;
+        }
+
+        let mut result: Vec<(ThreadId, i64)> = totals
+            .into_iter()
+            .map(|(thread_id, total)| (thread_id.clone(), total))
+            .collect();
+        result.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.as_str().cmp(b.0.as_str())));
+        result.truncate(limit);
+
+        Ok(result)
+    }
+
+    fn storage_stats(&self) -> Result<StorageStats> {
+        let messages = self.messages.read().unwrap();
+
+        // Bodies are held uncompressed
+        let mut stats = StorageStats::default();
+        for message in messages.values() {
+            let bodies = [
+                (ContentType::BodyText, &message.body_text),
+                (ContentType::BodyHtml, &message.body_html),
+            ];
+            for (content_type, body) in bodies {
+                let Some(body) = body else { continue };
+                let bytes = body.len() as u64;
+                stats.content.entry(content_type).or_default().add(ContentUsage {
+                    count: 1,
+                    stored_bytes: bytes,
+                    original_bytes: bytes,
+                    measured_bytes: bytes,
+                });
+            }
+        }
+
+        Ok(stats)
+    }
+
+    fn compact(&self, _older_than: DateTime<Utc>) -> Result<CompactionReport> {
+        // Nothing is compressed or stored outside the maps
+        Ok(CompactionReport::default())
+    }
+
+    fn check_integrity(&self) -> Result<IntegrityReport> {
+        let threads = self.threads.read().unwrap();
+        let messages = self.messages.read().unwrap();
+        let reverse = self.thread_label_ts.read().unwrap();
+
+        let mut messages_without_threads: Vec<MessageId> = messages
+            .values()
+            .filter(|m| !threads.contains_key(m.thread_id.as_str()))
+            .map(|m| m.id.clone())
+            .collect();
+        messages_without_threads.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+        let stale: BTreeSet<&str> = reverse
+            .keys()
+            .map(|(thread_id, _)| thread_id.as_str())
+            .filter(|thread_id| !threads.contains_key(*thread_id))
+            .collect();
+
+        Ok(IntegrityReport {
+            messages_without_threads,
+            thread_labels_without_threads: stale.into_iter().map(ThreadId::new).collect(),
+            ..Default::default()
+        })
+    }
+
+    fn repair(&self) -> Result<IntegrityReport> {
+        let mut report = self.check_integrity()?;
+
+        let rebuilt = rebuild_threads(self, &report.messages_without_threads)?;
+        report
+            .thread_labels_without_threads
+            .retain(|thread_id| !rebuilt.contains(thread_id));
+
+        let mut index = self.label_thread_index.write().unwrap();
+        let mut reverse = self.thread_label_ts.write().unwrap();
+        for thread_id in &report.thread_labels_without_threads {
+            reverse.retain(|(id, label), ts| {
+                let keep = id != thread_id.as_str();
+                if !keep && let Some(set) = index.get_mut(label) {
+                    set.remove(&(Reverse(*ts), id.clone()));
+                }
+                keep
+            });
+        }
+
+        Ok(report)
+    }
+
+    fn record_recent_search(&self, query: &str) -> Result<()> {
+        let mut recent = self.recent_searches.write().unwrap();
+        recent.retain(|q| q != query);
+        recent.insert(0, query.to_string());
+        recent.truncate(MAX_RECENT_SEARCHES);
+        Ok(())
+    }
+
+    fn list_recent_searches(&self, limit: usize) -> Result<Vec<String>> {
+        let recent = self.recent_searches.read().unwrap();
+        Ok(recent.iter().take(limit).cloned().collect())
+    }
+
+    fn clear_recent_searches(&self) -> Result<()> {
+        self.recent_searches.write().unwrap().clear();
+        Ok(())
+    }
+
+    fn subscribe(&self) -> Receiver<StoreEvent> {
+        self.events.subscribe()
+    }
+}
+
+/// Messages matching an account filter, without the account's own sent mail
+fn received_messages(
+    messages: &HashMap<String, Message>,
+    account_id: Option<i64>,
+) -> impl Iterator<Item = &Message> {
+    messages.values().filter(move |m| {
+        account_id.is_none_or(|id| id == m.account_id)
+            && !m.label_ids.iter().any(|l| l == LabelId::SENT)
+    })
+}
+
+/// Whether a thread has all of `labels` and none of `excluded_labels`,
+/// according to the (thread, label) timestamp map
+fn has_label_set(
+    thread_labels: &HashMap<(String, String), i64>,
+    thread_id: &str,
+    labels: &[&str],
+    excluded_labels: &[&str],
+) -> bool {
+    let has = |label: &&str| {
+        thread_labels.contains_key(&(thread_id.to_string(), label.to_string()))
+    };
+    labels.iter().all(has) && !excluded_labels.iter().any(has)
 }
 
 #[cfg(test)]
@@ -1028,4 +2267,39 @@ mod tests {
         // But sync state is preserved
         assert!(store.get_sync_state(1).unwrap().is_some());
     }
+
+    #[test]
+    fn test_subscribe_receives_events() {
+        let store = InMemoryMailStore::new();
+        let rx = store.subscribe();
+
+        store.upsert_thread(make_test_thread("t1", "Test")).unwrap();
+        let mut msg = make_test_message("m1", "t1");
+        msg.label_ids = vec!["INBOX".to_string()];
+        store.upsert_message(msg).unwrap();
+        store
+            .update_message_labels(&MessageId::new("m1"), vec!["STARRED".to_string()])
+            .unwrap();
+        store.delete_message(&MessageId::new("m1")).unwrap();
+
+        let events: Vec<_> = rx.try_iter().collect();
+        assert_eq!(
+            events,
+            vec![
+                StoreEvent::ThreadUpserted {
+                    thread_id: ThreadId::new("t1"),
+                    account_id: 1,
+                },
+                StoreEvent::LabelChanged {
+                    thread_id: ThreadId::new("t1"),
+                    message_id: MessageId::new("m1"),
+                    added: vec!["STARRED".to_string()],
+                    removed: vec!["INBOX".to_string()],
+                },
+                StoreEvent::ThreadRemoved {
+                    thread_id: ThreadId::new("t1"),
+                },
+            ]
+        );
+    }
 }
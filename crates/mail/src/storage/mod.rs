@@ -12,12 +12,20 @@
 
 mod blob;
 mod blob_file;
+mod events;
 mod memory;
+mod pool;
 mod sqlite;
 mod traits;
 
-pub use blob::{BlobKey, BlobStore, ContentType};
+pub use blob::{BlobEntry, BlobKey, BlobStore, ContentType};
 pub use blob_file::FileBlobStore;
+pub use events::StoreEvent;
 pub use memory::InMemoryMailStore;
-pub use sqlite::SqliteMailStore;
-pub use traits::{MailStore, MessageBody, MessageMetadata, PendingMessage};
+pub use sqlite::{SqliteMailStore, StoreConfig};
+pub(crate) use sqlite::{copy_database, database_info, schema_version};
+pub(crate) use traits::summarize_thread;
+pub use traits::{
+    CompactionReport, ContentUsage, IntegrityReport, MAX_RECENT_SEARCHES, MailStore, MessageBody,
+    MessageMetadata, PendingMessage, StorageStats, ThreadCursor,
+};
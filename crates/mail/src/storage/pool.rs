@@ -0,0 +1,87 @@
+//! Read-only SQLite connections shared by [`SqliteMailStore`]
+//!
+//! In WAL mode readers see the last committed state and never wait on the
+//! writer, so thread lists stay responsive while sync holds a long write
+//! transaction.
+//!
+//! [`SqliteMailStore`]: super::SqliteMailStore
+
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::{Condvar, Mutex};
+
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OpenFlags};
+
+/// Fixed set of read-only connections to one database
+pub(super) struct ReadPool {
+    idle: Mutex<Vec<Connection>>,
+    returned: Condvar,
+}
+
+impl ReadPool {
+    /// Open `size` read-only connections to the database at `path`
+    ///
+    /// The database must already exist and be in WAL mode.
+    pub(super) fn open(path: &Path, size: usize) -> Result<Self> {
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX
+            | OpenFlags::SQLITE_OPEN_URI;
+        let mut idle = Vec::with_capacity(size);
+        for _ in 0..size {
+            let conn = Connection::open_with_flags(path, flags)
+                .with_context(|| format!("Failed to open read connection to {:?}", path))?;
+            // Smaller cache than the writer's: readers share the OS page cache
+            // through mmap
+            conn.execute_batch(
+                r#"
+                PRAGMA cache_size = -16000;
+                PRAGMA temp_store = MEMORY;
+                PRAGMA mmap_size = 268435456;
+                "#,
+            )?;
+            idle.push(conn);
+        }
+        Ok(Self {
+            idle: Mutex::new(idle),
+            returned: Condvar::new(),
+        })
+    }
+
+    /// Borrow a connection, waiting for one if all are in use
+    pub(super) fn get(&self) -> PooledConnection<'_> {
+        let mut idle = self.idle.lock().unwrap();
+        loop {
+            if let Some(conn) = idle.pop() {
+                return PooledConnection {
+                    pool: self,
+                    conn: Some(conn),
+                };
+            }
+            idle = self.returned.wait(idle).unwrap();
+        }
+    }
+}
+
+/// Connection borrowed from a [`ReadPool`], returned when dropped
+pub(super) struct PooledConnection<'a> {
+    pool: &'a ReadPool,
+    conn: Option<Connection>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection is present until drop")
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.idle.lock().unwrap().push(conn);
+            self.pool.returned.notify_one();
+        }
+    }
+}
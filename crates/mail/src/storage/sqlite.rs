@@ -1,23 +1,91 @@
 //! SQLite-based mail storage with blob storage for message bodies
 
+use std::collections::HashMap;
+use std::ops::Deref;
 use std::path::Path;
-use std::sync::Mutex;
+use std::sync::mpsc::Receiver;
+use std::sync::{Mutex, MutexGuard};
 
 use anyhow::{Context, Result};
+use log::warn;
 use rusqlite::{Connection, OptionalExtension, params};
 use rusqlite_migration::{M, Migrations};
 
-use super::blob::BlobStore;
-use super::traits::{MailStore, MessageBody, MessageMetadata, PendingMessage};
-use crate::models::{Account, EmailAddress, Message, MessageId, SyncState, Thread, ThreadId};
+use super::blob::{BlobKey, BlobStore, ContentType};
+use super::events::{EventBus, StoreEvent, label_diff};
+use super::pool::{PooledConnection, ReadPool};
+use super::traits::{
+    CompactionReport, ContentUsage, IntegrityReport, MAX_RECENT_SEARCHES, MailStore, MessageBody,
+    MessageMetadata, PendingMessage, StorageStats, ThreadCursor, rebuild_threads,
+};
+use crate::analytics::{DailyVolume, LabelStorage, SenderRanking, SenderStats};
+use crate::error::MailError;
+use crate::models::{
+    Account, AccountSettings, EmailAddress, FollowupState, Label, LabelColor, Message, MessageId,
+    OpenStatus, OutboxMessage, OutboxState, QueuedAction, Rule, SavedSearch, SendAsAlias, Signature,
+    SyncState, Thread, ThreadId, ThreadNote,
+};
+
+/// Columns read by `message_metadata_from_row`, in order
+const MESSAGE_COLUMNS: &str = "id, thread_id, account_id, from_name, from_email, subject, \
+                               body_preview, received_at, internal_date, has_body_text, \
+                               has_body_html, has_attachments, attachment_names, size_bytes, \
+                               unsubscribe, auth_results, invite, lang, rfc_message_id";
+
+/// Messages `load_messages_metadata` reads per query, well under SQLite's
+/// limit on bound parameters
+const METADATA_BATCH_SIZE: usize = 500;
+
+/// Condition excluding the account's own sent mail from `messages m`
+const NOT_SENT: &str = "NOT EXISTS (SELECT 1 FROM message_labels sl
+                        WHERE sl.message_id = m.id AND sl.label_id = 'SENT')";
+
+/// zstd level for newly written bodies (good balance of speed vs compression)
+const BODY_COMPRESSION_LEVEL: i32 = 3;
+
+/// zstd level `compact` recompresses old bodies at
+const ARCHIVE_COMPRESSION_LEVEL: i32 = 19;
+
+/// Messages recompressed per transaction during `compact`
+const COMPACT_BATCH_SIZE: i64 = 200;
+
+/// Columns read by `account_from_row`, in order
+const ACCOUNT_COLUMNS: &str = "id, email, display_name, avatar_color, is_primary, added_at, \
+                               token_data, signature_text, signature_html, sync_enabled";
+
+/// Columns read by `thread_from_row`, in order
+///
+/// Label listings read the same columns from the copy on `thread_labels`.
+const THREAD_COLUMNS: &str = "id, account_id, subject, snippet, last_message_at, message_count, \
+                              sender_name, sender_email, is_unread, participants";
+
+/// Columns read by `label_from_row`, in order, from `labels l` left-joined
+/// to `label_overrides o`
+///
+/// An override's colors replace Gmail's as a pair.
+const LABEL_COLUMNS: &str = "l.id, l.name, o.icon, \
+                             COALESCE(o.background_color, l.background_color), \
+                             CASE WHEN o.background_color IS NULL THEN l.text_color \
+                                  ELSE o.text_color END";
+
+/// Join from `labels l` to its local overrides
+const LABEL_OVERRIDES_JOIN: &str = "LEFT JOIN label_overrides o \
+                                    ON o.account_id = l.account_id AND o.label_id = l.id";
+
+/// Columns read by `outbox_message_from_row`, in order
+const OUTBOX_COLUMNS: &str = "id, account_id, thread_id, draft_id, subject, recipients, raw, \
+                              tracking_token, state, attempts, last_error, next_attempt_at, \
+                              sent_message_id, created_at";
 
 /// Database migrations
 ///
-/// Single consolidated schema for multi-account support.
+/// Single consolidated schema for multi-account support, followed by
+/// additive migrations for columns introduced since.
 /// No backwards compatibility - database will be cleared before running.
-fn migrations() -> Migrations<'static> {
-    Migrations::new(vec![M::up(
-        r#"
+fn migration_steps() -> Vec<M<'static>> {
+    vec![
+        M::up(
+            r#"
             -- Accounts registry (must be created first for FK references)
             CREATE TABLE accounts (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -128,24 +196,415 @@ fn migrations() -> Migrations<'static> {
 
             CREATE INDEX idx_pending_labels ON pending_message_labels(label_id);
             "#,
-    )])
+        ),
+        // Attachment flag for quick filters and has:attachment search
+        M::up("ALTER TABLE messages ADD COLUMN has_attachments INTEGER NOT NULL DEFAULT 0;"),
+        // Saved searches (smart folders)
+        M::up(
+            r#"
+            CREATE TABLE saved_searches (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                query TEXT NOT NULL,
+                pinned INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL
+            );
+            "#,
+        ),
+        // Attachment filenames (JSON array) and message size for filename:/larger:/smaller: search
+        M::up(
+            r#"
+            ALTER TABLE messages ADD COLUMN attachment_names TEXT NOT NULL DEFAULT '[]';
+            ALTER TABLE messages ADD COLUMN size_bytes INTEGER NOT NULL DEFAULT 0;
+            "#,
+        ),
+        // Recent search queries for typeahead suggestions
+        M::up(
+            r#"
+            CREATE TABLE recent_searches (
+                query TEXT PRIMARY KEY,
+                searched_at TEXT NOT NULL
+            );
+
+            CREATE INDEX idx_recent_searches_at ON recent_searches(searched_at DESC);
+            "#,
+        ),
+        // Mail rules (predicates and actions as JSON arrays)
+        M::up(
+            r#"
+            CREATE TABLE rules (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                account_id INTEGER REFERENCES accounts(id) ON DELETE CASCADE,
+                name TEXT NOT NULL,
+                predicates TEXT NOT NULL,
+                actions TEXT NOT NULL,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL
+            );
+            "#,
+        ),
+        // List-Unsubscribe targets (JSON object, NULL when the message has none)
+        M::up(
+            r#"
+            ALTER TABLE messages ADD COLUMN unsubscribe TEXT;
+
+            CREATE INDEX idx_messages_unsubscribe ON messages(received_at DESC)
+                WHERE unsubscribe IS NOT NULL;
+            "#,
+        ),
+        // Uncompressed body sizes (NULL for bodies stored before this) and the
+        // zstd level the bodies were compressed at
+        M::up(
+            r#"
+            ALTER TABLE messages ADD COLUMN body_text_size INTEGER;
+            ALTER TABLE messages ADD COLUMN body_html_size INTEGER;
+            ALTER TABLE messages ADD COLUMN body_compression_level INTEGER NOT NULL DEFAULT 3;
+            "#,
+        ),
+        // Account signatures (text NULL when the account has none)
+        M::up(
+            r#"
+            ALTER TABLE accounts ADD COLUMN signature_text TEXT;
+            ALTER TABLE accounts ADD COLUMN signature_html TEXT;
+            "#,
+        ),
+        // Gmail send-as aliases (alternative From addresses)
+        M::up(
+            r#"
+            CREATE TABLE send_as_aliases (
+                account_id INTEGER NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+                email TEXT NOT NULL,
+                display_name TEXT,
+                is_primary INTEGER NOT NULL DEFAULT 0,
+                is_default INTEGER NOT NULL DEFAULT 0,
+                verified INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (account_id, email)
+            );
+            "#,
+        ),
+        // Rules imported from Gmail filters
+        M::up(
+            r#"
+            ALTER TABLE rules ADD COLUMN gmail_filter_id TEXT;
+            "#,
+        ),
+        // SPF/DKIM/DMARC results (JSON)
+        M::up(
+            r#"
+            ALTER TABLE messages ADD COLUMN auth_results TEXT;
+            "#,
+        ),
+        // Calendar invites (JSON)
+        M::up(
+            r#"
+            ALTER TABLE messages ADD COLUMN invite TEXT;
+            "#,
+        ),
+        // Text extracted from attachments (zstd-compressed)
+        M::up(
+            r#"
+            ALTER TABLE messages ADD COLUMN attachment_text BLOB;
+            "#,
+        ),
+        // Detected body language (ISO 639-3)
+        M::up(
+            r#"
+            ALTER TABLE messages ADD COLUMN lang TEXT;
+            "#,
+        ),
+        // Cached thread summaries, keyed by the messages they cover
+        M::up(
+            r#"
+            CREATE TABLE thread_summaries (
+                thread_id TEXT PRIMARY KEY REFERENCES threads(id) ON DELETE CASCADE,
+                source_key TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            "#,
+        ),
+        // Open tracking for sent mail. No foreign keys: the sent copy may not
+        // have synced yet when its beacon is saved or first opened.
+        M::up(
+            r#"
+            CREATE TABLE tracking_beacons (
+                token TEXT PRIMARY KEY,
+                message_id TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            CREATE INDEX idx_tracking_beacons_message ON tracking_beacons(message_id);
+            CREATE TABLE message_opens (
+                id INTEGER PRIMARY KEY,
+                message_id TEXT NOT NULL,
+                opened_at TEXT NOT NULL,
+                UNIQUE(message_id, opened_at)
+            );
+            "#,
+        ),
+        // Dismissed and snoozed follow-up reminders
+        M::up(
+            r#"
+            CREATE TABLE followup_reminders (
+                thread_id TEXT PRIMARY KEY REFERENCES threads(id) ON DELETE CASCADE,
+                dismissed_at TEXT,
+                snoozed_until TEXT
+            );
+            "#,
+        ),
+        // Private per-thread notes
+        M::up(
+            r#"
+            CREATE TABLE thread_notes (
+                thread_id TEXT PRIMARY KEY REFERENCES threads(id) ON DELETE CASCADE,
+                markdown TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            "#,
+        ),
+        // Label changes made offline, replayed to Gmail in order. No thread
+        // foreign key: a sync may drop the thread before the change is sent.
+        M::up(
+            r#"
+            CREATE TABLE queued_actions (
+                id INTEGER PRIMARY KEY,
+                account_id INTEGER NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+                thread_id TEXT NOT NULL,
+                add_labels TEXT NOT NULL,
+                remove_labels TEXT NOT NULL,
+                queued_at TEXT NOT NULL
+            );
+            CREATE INDEX idx_queued_actions_account ON queued_actions(account_id, id);
+            "#,
+        ),
+        // Messages whose stored content was lost, fetched again by the next sync
+        M::up(
+            r#"
+            CREATE TABLE refetch_messages (
+                message_id TEXT PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE
+            );
+            "#,
+        ),
+        // Thread summaries copied onto thread_labels, so label listings read
+        // one table in index order instead of joining threads
+        M::up(
+            r#"
+            ALTER TABLE thread_labels ADD COLUMN subject TEXT NOT NULL DEFAULT '';
+            ALTER TABLE thread_labels ADD COLUMN snippet TEXT NOT NULL DEFAULT '';
+            ALTER TABLE thread_labels ADD COLUMN message_count INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE thread_labels ADD COLUMN sender_name TEXT;
+            ALTER TABLE thread_labels ADD COLUMN sender_email TEXT NOT NULL DEFAULT '';
+            ALTER TABLE thread_labels ADD COLUMN is_unread INTEGER NOT NULL DEFAULT 0;
+
+            UPDATE thread_labels SET
+                (subject, snippet, message_count, sender_name, sender_email, is_unread) =
+                (SELECT subject, snippet, message_count, sender_name, sender_email, is_unread
+                 FROM threads WHERE threads.id = thread_labels.thread_id);
+
+            DROP INDEX idx_thread_labels_account_query;
+            CREATE INDEX idx_thread_labels_account_query
+                ON thread_labels(account_id, label_id, last_message_at DESC, thread_id);
+            CREATE INDEX idx_thread_labels_label_query
+                ON thread_labels(label_id, last_message_at DESC, thread_id);
+            "#,
+        ),
+        // Pending payloads are zstd-compressed; rows queued before stay plain
+        M::up(
+            r#"
+            ALTER TABLE pending_messages ADD COLUMN compressed INTEGER NOT NULL DEFAULT 0;
+            "#,
+        ),
+        // A thread's earliest message is found from the index alone
+        M::up(
+            r#"
+            CREATE INDEX idx_messages_thread_received ON messages(thread_id, received_at);
+            "#,
+        ),
+        // Accounts the user paused stay registered but aren't synced
+        M::up(
+            r#"
+            ALTER TABLE accounts ADD COLUMN sync_enabled INTEGER NOT NULL DEFAULT 1;
+            "#,
+        ),
+        // Sent mail waiting for delivery or retry. No thread foreign key: a
+        // new message's thread doesn't exist until it's sent and synced.
+        M::up(
+            r#"
+            CREATE TABLE outbox (
+                id INTEGER PRIMARY KEY,
+                account_id INTEGER NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+                thread_id TEXT,
+                draft_id TEXT,
+                subject TEXT NOT NULL,
+                recipients TEXT NOT NULL,
+                raw TEXT NOT NULL,
+                tracking_token TEXT,
+                state TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT,
+                next_attempt_at TEXT NOT NULL,
+                sent_message_id TEXT,
+                created_at TEXT NOT NULL
+            );
+            CREATE INDEX idx_outbox_account ON outbox(account_id, id);
+            "#,
+        ),
+        // RFC Message-ID, and repeat deliveries of one message (a mailing
+        // list copy and a direct CC) mapped to the copy shown for them
+        M::up(
+            r#"
+            ALTER TABLE messages ADD COLUMN rfc_message_id TEXT;
+            CREATE INDEX idx_messages_rfc_message_id ON messages(account_id, rfc_message_id);
+            CREATE TABLE message_duplicates (
+                message_id TEXT PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+                canonical_id TEXT NOT NULL REFERENCES messages(id) ON DELETE CASCADE
+            );
+            CREATE INDEX idx_message_duplicates_canonical ON message_duplicates(canonical_id);
+            "#,
+        ),
+        // Gmail user labels, for the nested label tree
+        M::up(
+            r#"
+            CREATE TABLE labels (
+                account_id INTEGER NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+                id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                PRIMARY KEY (account_id, id)
+            );
+            "#,
+        ),
+        // Gmail label colors, and icons and colors chosen on this machine
+        M::up(
+            r#"
+            ALTER TABLE labels ADD COLUMN background_color TEXT;
+            ALTER TABLE labels ADD COLUMN text_color TEXT;
+            CREATE TABLE label_overrides (
+                account_id INTEGER NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+                label_id TEXT NOT NULL,
+                icon TEXT,
+                background_color TEXT,
+                text_color TEXT,
+                PRIMARY KEY (account_id, label_id)
+            );
+            "#,
+        ),
+        // Distinct senders of each thread, for "Alice, Bob, Carol" list rows,
+        // filled in from the stored messages as `summarize_thread` would
+        M::up(
+            r#"
+            ALTER TABLE threads ADD COLUMN participants TEXT NOT NULL DEFAULT '[]';
+            ALTER TABLE thread_labels ADD COLUMN participants TEXT NOT NULL DEFAULT '[]';
+
+            UPDATE threads SET participants = (
+                SELECT json_group_array(json_object('name', from_name, 'email', from_email))
+                FROM (
+                    SELECT from_name, from_email, MIN(received_at || ' ' || id) AS first_at
+                    FROM messages
+                    WHERE messages.thread_id = threads.id
+                    GROUP BY lower(from_email)
+                    ORDER BY first_at
+                    LIMIT 4
+                )
+            );
+            UPDATE thread_labels SET participants =
+                (SELECT participants FROM threads WHERE threads.id = thread_labels.thread_id);
+            "#,
+        ),
+    ]
+}
+
+fn migrations() -> Migrations<'static> {
+    Migrations::new(migration_steps())
+}
+
+/// Schema version of an up-to-date database, as kept in its `user_version`
+pub(crate) fn schema_version() -> usize {
+    migration_steps().len()
+}
+
+/// Copy the database at `db_path` to a new file at `dest`
+///
+/// The copy is one consistent read, so other connections can keep writing,
+/// and holds everything in a single file with no WAL.
+pub(crate) fn copy_database(db_path: &Path, dest: &Path) -> Result<()> {
+    let conn = Connection::open(db_path)
+        .with_context(|| format!("Failed to open database at {:?}", db_path))?;
+    conn.execute("VACUUM INTO ?1", params![dest.to_string_lossy()])
+        .with_context(|| format!("Failed to copy database to {:?}", dest))?;
+    Ok(())
+}
+
+/// Schema version and account emails of the database at `path`, read
+/// without migrating it
+pub(crate) fn database_info(path: &Path) -> Result<(usize, Vec<String>)> {
+    let conn = Connection::open(path)
+        .with_context(|| format!("Failed to open database at {:?}", path))?;
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    let mut stmt = conn.prepare("SELECT email FROM accounts ORDER BY email")?;
+    let emails = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok((usize::try_from(version)?, emails))
+}
+
+/// Tuning for [`SqliteMailStore`]
+#[derive(Debug, Clone)]
+pub struct StoreConfig {
+    /// Read-only connections for thread lists and lookups, so they don't wait
+    /// on sync's writes. 0 runs every query on the single writer connection.
+    pub read_connections: usize,
+}
+
+impl Default for StoreConfig {
+    fn default() -> Self {
+        Self { read_connections: 4 }
+    }
 }
 
 /// SQLite-based mail storage
 ///
 /// Uses SQLite for queryable metadata and a BlobStore for large content
-/// (message bodies, attachments).
+/// (message bodies, attachments). Writes go through one connection; reads
+/// that the UI waits on use a pool of read-only connections.
 pub struct SqliteMailStore {
     conn: Mutex<Connection>,
+    readers: Option<ReadPool>,
     blob_store: Box<dyn BlobStore>,
+    events: EventBus,
+}
+
+/// Connection a read runs on
+enum ReadConnection<'a> {
+    Pooled(PooledConnection<'a>),
+    /// The store has no read pool
+    Writer(MutexGuard<'a, Connection>),
+}
+
+impl Deref for ReadConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        match self {
+            ReadConnection::Pooled(conn) => conn,
+            ReadConnection::Writer(conn) => conn,
+        }
+    }
 }
 
 impl SqliteMailStore {
-    /// Create a new SQLite mail store
+    /// Create a new SQLite mail store with the default [`StoreConfig`]
     ///
     /// - `db_path`: Path to the SQLite database file
     /// - `blob_store`: Storage for message bodies
     pub fn new(db_path: impl AsRef<Path>, blob_store: Box<dyn BlobStore>) -> Result<Self> {
+        Self::with_config(db_path, blob_store, StoreConfig::default())
+    }
+
+    /// Create a new SQLite mail store
+    pub fn with_config(
+        db_path: impl AsRef<Path>,
+        blob_store: Box<dyn BlobStore>,
+        config: StoreConfig,
+    ) -> Result<Self> {
         let mut conn = Connection::open(db_path.as_ref())
             .with_context(|| format!("Failed to open database at {:?}", db_path.as_ref()))?;
 
@@ -178,8 +637,14 @@ impl SqliteMailStore {
         // foreign_keys = ON:
         //   - Enforces referential integrity
         //   - Required for ON DELETE CASCADE to work
+        //
+        // auto_vacuum = INCREMENTAL:
+        //   - Lets clearing an account hand its pages back to the OS
+        //   - Only takes effect on a new database; `compact` converts
+        //     existing ones when it vacuums
         conn.execute_batch(
             r#"
+            PRAGMA auto_vacuum = INCREMENTAL;
             PRAGMA journal_mode = WAL;
             PRAGMA synchronous = NORMAL;
             PRAGMA cache_size = -64000;
@@ -194,26 +659,106 @@ impl SqliteMailStore {
             .to_latest(&mut conn)
             .context("Failed to run database migrations")?;
 
-        Ok(Self {
+        // Opened after migrations so readers never see a partial schema
+        let readers = match config.read_connections {
+            0 => None,
+            size => Some(ReadPool::open(db_path.as_ref(), size)?),
+        };
+
+        let store = Self {
             conn: Mutex::new(conn),
+            readers,
             blob_store,
-        })
+            events: EventBus::new(),
+        };
+        // Damaged blobs shouldn't keep the mailbox from opening
+        if let Err(e) = store.recover_blobs() {
+            warn!("Failed to check blob storage: {:#}", e);
+        }
+        Ok(store)
+    }
+
+    /// Set aside blobs left unreadable by a crash and queue their messages
+    /// for re-fetch
+    ///
+    /// Translations aren't queued; they're recreated when next requested.
+    fn recover_blobs(&self) -> Result<()> {
+        let lost: Vec<MessageId> = self
+            .blob_store
+            .recover()?
+            .into_iter()
+            .filter(|key| key.content_type != ContentType::Translation)
+            .map(|key| MessageId::new(key.message_id))
+            .collect();
+        if !lost.is_empty() {
+            self.mark_for_refetch(&lost)?;
+        }
+        Ok(())
+    }
+
+    /// Connection for a read-only query
+    ///
+    /// Sees everything committed before the call, but not the writer's open
+    /// transaction.
+    fn reader(&self) -> ReadConnection<'_> {
+        match &self.readers {
+            Some(pool) => ReadConnection::Pooled(pool.get()),
+            None => ReadConnection::Writer(self.conn.lock().unwrap()),
+        }
+    }
+
+    /// Blobs whose message is no longer in the database
+    fn orphaned_blobs(&self) -> Result<Vec<BlobKey>> {
+        let entries = self.blob_store.list()?;
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare_cached("SELECT EXISTS (SELECT 1 FROM messages WHERE id = ?)")?;
+        let mut orphans = Vec::new();
+        for entry in entries {
+            let exists: bool = stmt.query_row([&entry.key.message_id], |row| row.get(0))?;
+            if !exists {
+                orphans.push(entry.key);
+            }
+        }
+
+        Ok(orphans)
+    }
+
+    /// Remove the blobs of an account's deleted messages, release the freed
+    /// pages and announce the clear
+    fn finish_account_clear(&self, account_id: i64, message_ids: &[String]) -> Result<()> {
+        for message_id in message_ids {
+            self.blob_store.delete_all_for_message(message_id)?;
+        }
+
+        // The rows are gone either way; a failed vacuum only leaves the file
+        // larger until the next compaction
+        let conn = self.conn.lock().unwrap();
+        if let Err(e) = conn.execute_batch("PRAGMA incremental_vacuum;") {
+            warn!("Failed to reclaim space after clearing account {}: {}", account_id, e);
+        }
+        drop(conn);
+
+        self.events.publish(StoreEvent::Cleared {
+            account_id: Some(account_id),
+        });
+        Ok(())
     }
 
     /// Update the thread_labels denormalized index for a thread
+    ///
+    /// Each row carries a copy of the thread's summary; `upsert_thread` keeps
+    /// the copies current between label changes.
     fn update_thread_labels(&self, conn: &Connection, thread_id: &str) -> Result<()> {
-        // Get thread's last_message_at and account_id
-        let thread_info: Option<(String, i64)> = conn
-            .query_row(
-                "SELECT last_message_at, account_id FROM threads WHERE id = ?",
-                [thread_id],
-                |row| Ok((row.get(0)?, row.get(1)?)),
-            )
-            .optional()?;
-
-        let Some((last_message_at, account_id)) = thread_info else {
+        let thread_exists: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM threads WHERE id = ?)",
+            [thread_id],
+            |row| row.get(0),
+        )?;
+        if !thread_exists {
             return Ok(());
-        };
+        }
 
         // Get all unique labels for messages in this thread
         let mut stmt = conn.prepare(
@@ -228,43 +773,23 @@ impl SqliteMailStore {
         // Clear existing thread_labels for this thread
         conn.execute("DELETE FROM thread_labels WHERE thread_id = ?", [thread_id])?;
 
-        // Insert new entries
+        // Insert new entries, each carrying the thread's summary
         let mut insert_stmt = conn.prepare(
-            "INSERT INTO thread_labels (thread_id, account_id, label_id, last_message_at) VALUES (?, ?, ?, ?)",
+            "INSERT INTO thread_labels
+             (thread_id, account_id, label_id, last_message_at, subject, snippet, message_count,
+              sender_name, sender_email, is_unread, participants)
+             SELECT id, account_id, ?, last_message_at, subject, snippet, message_count,
+                    sender_name, sender_email, is_unread, participants
+             FROM threads WHERE id = ?",
         )?;
 
         for label in labels {
-            insert_stmt.execute(params![thread_id, account_id, label, last_message_at])?;
+            insert_stmt.execute(params![label, thread_id])?;
         }
 
         Ok(())
     }
 
-    /// Load recipients for a message
-    fn load_recipients(
-        &self,
-        conn: &Connection,
-        message_id: &str,
-        recipient_type: &str,
-    ) -> Result<Vec<EmailAddress>> {
-        let mut stmt = conn.prepare(
-            "SELECT name, email FROM message_recipients
-             WHERE message_id = ? AND recipient_type = ?
-             ORDER BY position",
-        )?;
-
-        let recipients = stmt
-            .query_map(params![message_id, recipient_type], |row| {
-                Ok(EmailAddress {
-                    name: row.get(0)?,
-                    email: row.get(1)?,
-                })
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
-
-        Ok(recipients)
-    }
-
     /// Load labels for a message
     fn load_labels(&self, conn: &Connection, message_id: &str) -> Result<Vec<String>> {
         let mut stmt = conn.prepare("SELECT label_id FROM message_labels WHERE message_id = ?")?;
@@ -303,6 +828,89 @@ impl SqliteMailStore {
     }
 
     /// Save labels for a message
+    /// Record every delivery of an RFC Message-ID but one as a duplicate of
+    /// that one
+    ///
+    /// The earliest-received copy (then lowest ID) is canonical, so the
+    /// mapping doesn't depend on which copy synced first.
+    fn link_duplicates(
+        &self,
+        conn: &Connection,
+        account_id: i64,
+        rfc_message_id: &str,
+    ) -> Result<()> {
+        let mut stmt = conn.prepare(
+            "SELECT id FROM messages WHERE account_id = ? AND rfc_message_id = ?
+             ORDER BY internal_date, id",
+        )?;
+        let ids = stmt
+            .query_map(params![account_id, rfc_message_id], |row| {
+                row.get::<_, String>(0)
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        conn.execute(
+            "DELETE FROM message_duplicates WHERE message_id IN
+             (SELECT id FROM messages WHERE account_id = ? AND rfc_message_id = ?)",
+            params![account_id, rfc_message_id],
+        )?;
+        if let Some((canonical, duplicates)) = ids.split_first() {
+            for id in duplicates {
+                conn.execute(
+                    "INSERT INTO message_duplicates (message_id, canonical_id) VALUES (?, ?)",
+                    params![id, canonical],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Update a thread after one of its messages was deleted or moved away
+    ///
+    /// Recounts its messages and refreshes its label index, or deletes the
+    /// thread if it has none left. Returns the event to publish once the
+    /// transaction commits.
+    fn message_left_thread(
+        &self,
+        conn: &Connection,
+        thread_id: String,
+    ) -> Result<Option<StoreEvent>> {
+        let remaining: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM messages WHERE thread_id = ?",
+            [&thread_id],
+            |row| row.get(0),
+        )?;
+
+        if remaining == 0 {
+            // Delete thread entirely
+            let deleted = conn.execute("DELETE FROM threads WHERE id = ?", [&thread_id])?;
+            return Ok((deleted > 0).then(|| StoreEvent::ThreadRemoved {
+                thread_id: ThreadId::new(thread_id),
+            }));
+        }
+
+        // Update message count
+        conn.execute(
+            "UPDATE threads SET message_count = ? WHERE id = ?",
+            params![remaining, thread_id],
+        )?;
+
+        // Update thread_labels index
+        self.update_thread_labels(conn, &thread_id)?;
+
+        let account_id: Option<i64> = conn
+            .query_row(
+                "SELECT account_id FROM threads WHERE id = ?",
+                [&thread_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(account_id.map(|account_id| StoreEvent::ThreadUpserted {
+            thread_id: ThreadId::new(thread_id),
+            account_id,
+        }))
+    }
+
     fn save_labels(&self, conn: &Connection, message_id: &str, labels: &[String]) -> Result<()> {
         let mut stmt =
             conn.prepare("INSERT INTO message_labels (message_id, label_id) VALUES (?, ?)")?;
@@ -314,105 +922,101 @@ impl SqliteMailStore {
         Ok(())
     }
 
-    /// Load a MessageMetadata from a row
+    /// Load a message's metadata
     fn load_message_metadata(
         &self,
         conn: &Connection,
         message_id: &str,
     ) -> Result<Option<MessageMetadata>> {
-        let row: Option<(
-            String,
-            String,
-            i64,
-            Option<String>,
-            String,
-            String,
-            String,
-            String,
-            i64,
-            bool,
-            bool,
-        )> = conn
-            .query_row(
-                "SELECT id, thread_id, account_id, from_name, from_email, subject, body_preview,
-                        received_at, internal_date, has_body_text, has_body_html
-                 FROM messages WHERE id = ?",
-                [message_id],
-                |row| {
-                    Ok((
-                        row.get(0)?,
-                        row.get(1)?,
-                        row.get(2)?,
-                        row.get(3)?,
-                        row.get(4)?,
-                        row.get(5)?,
-                        row.get(6)?,
-                        row.get(7)?,
-                        row.get(8)?,
-                        row.get(9)?,
-                        row.get(10)?,
-                    ))
-                },
-            )
-            .optional()?;
+        let mut loaded = self.load_messages_metadata(conn, &[message_id.to_string()])?;
+        Ok(loaded.pop())
+    }
 
-        let Some((
-            id,
-            thread_id,
-            account_id,
-            from_name,
-            from_email,
-            subject,
-            body_preview,
-            received_at_str,
-            internal_date,
-            has_body_text,
-            has_body_html,
-        )) = row
-        else {
-            return Ok(None);
-        };
+    /// Load metadata for messages, in the order of `ids`
+    ///
+    /// Reads messages, recipients and labels with one query each per batch
+    /// of ids, so a long thread costs the same few round-trips as a short
+    /// one. Ids that aren't stored are skipped.
+    fn load_messages_metadata(
+        &self,
+        conn: &Connection,
+        ids: &[String],
+    ) -> Result<Vec<MessageMetadata>> {
+        let mut loaded: HashMap<String, MessageMetadata> = HashMap::with_capacity(ids.len());
+
+        for batch in ids.chunks(METADATA_BATCH_SIZE) {
+            let placeholders = vec!["?"; batch.len()].join(", ");
+
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM messages WHERE id IN ({})",
+                MESSAGE_COLUMNS, placeholders
+            ))?;
+            let rows = stmt.query_map(
+                rusqlite::params_from_iter(batch),
+                message_metadata_from_row,
+            )?;
+            for metadata in rows {
+                let metadata = metadata?;
+                loaded.insert(metadata.id.as_str().to_string(), metadata);
+            }
 
-        let to = self.load_recipients(conn, &id, "to")?;
-        let cc = self.load_recipients(conn, &id, "cc")?;
-        let label_ids = self.load_labels(conn, &id)?;
+            let mut stmt = conn.prepare(&format!(
+                "SELECT message_id, recipient_type, name, email FROM message_recipients
+                 WHERE message_id IN ({})
+                 ORDER BY message_id, recipient_type, position",
+                placeholders
+            ))?;
+            let mut rows = stmt.query(rusqlite::params_from_iter(batch))?;
+            while let Some(row) = rows.next()? {
+                let message_id: String = row.get(0)?;
+                let recipient_type: String = row.get(1)?;
+                let Some(metadata) = loaded.get_mut(&message_id) else {
+                    continue;
+                };
+                let address = EmailAddress {
+                    name: row.get(2)?,
+                    email: row.get(3)?,
+                };
+                match recipient_type.as_str() {
+                    "to" => metadata.to.push(address),
+                    "cc" => metadata.cc.push(address),
+                    _ => {}
+                }
+            }
 
-        let received_at = chrono::DateTime::parse_from_rfc3339(&received_at_str)
-            .map(|dt| dt.with_timezone(&chrono::Utc))
-            .unwrap_or_else(|_| chrono::Utc::now());
+            let mut stmt = conn.prepare(&format!(
+                "SELECT message_id, label_id FROM message_labels
+                 WHERE message_id IN ({})
+                 ORDER BY message_id, label_id",
+                placeholders
+            ))?;
+            let mut rows = stmt.query(rusqlite::params_from_iter(batch))?;
+            while let Some(row) = rows.next()? {
+                let message_id: String = row.get(0)?;
+                if let Some(metadata) = loaded.get_mut(&message_id) {
+                    metadata.label_ids.push(row.get(1)?);
+                }
+            }
+        }
 
-        Ok(Some(MessageMetadata {
-            id: MessageId::new(id),
-            thread_id: ThreadId::new(thread_id),
-            account_id,
-            from: EmailAddress {
-                name: from_name,
-                email: from_email,
-            },
-            to,
-            cc,
-            subject,
-            body_preview,
-            received_at,
-            internal_date,
-            label_ids,
-            has_body_text,
-            has_body_html,
-        }))
+        Ok(ids.iter().filter_map(|id| loaded.remove(id)).collect())
     }
 }
 
 impl MailStore for SqliteMailStore {
     fn upsert_thread(&self, thread: Thread) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
 
         // Use ON CONFLICT DO UPDATE instead of INSERT OR REPLACE
         // INSERT OR REPLACE deletes the old row first, which triggers CASCADE
         // and deletes all messages referencing the thread!
-        conn.execute(
+        let participants = serde_json::to_string(&thread.participants)?;
+        tx.execute(
             "INSERT INTO threads
-             (id, account_id, subject, snippet, last_message_at, message_count, sender_name, sender_email, is_unread)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+             (id, account_id, subject, snippet, last_message_at, message_count, sender_name, sender_email, is_unread,
+              participants)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
              ON CONFLICT(id) DO UPDATE SET
                 account_id = excluded.account_id,
                 subject = excluded.subject,
@@ -421,7 +1025,8 @@ impl MailStore for SqliteMailStore {
                 message_count = excluded.message_count,
                 sender_name = excluded.sender_name,
                 sender_email = excluded.sender_email,
-                is_unread = excluded.is_unread",
+                is_unread = excluded.is_unread,
+                participants = excluded.participants",
             params![
                 thread.id.as_str(),
                 thread.account_id,
@@ -432,30 +1037,77 @@ impl MailStore for SqliteMailStore {
                 thread.sender_name,
                 thread.sender_email,
                 thread.is_unread,
+                participants,
             ],
         )?;
-
-        Ok(())
-    }
+        // Keep the summaries copied onto thread_labels current
+        tx.execute(
+            "UPDATE thread_labels SET
+                last_message_at = ?, subject = ?, snippet = ?, message_count = ?,
+                sender_name = ?, sender_email = ?, is_unread = ?, participants = ?
+             WHERE thread_id = ?",
+            params![
+                thread.last_message_at.to_rfc3339(),
+                thread.subject,
+                thread.snippet,
+                thread.message_count as i64,
+                thread.sender_name,
+                thread.sender_email,
+                thread.is_unread,
+                participants,
+                thread.id.as_str(),
+            ],
+        )?;
+        tx.commit()?;
+        drop(conn);
+
+        self.events.publish(StoreEvent::ThreadUpserted {
+            thread_id: thread.id,
+            account_id: thread.account_id,
+        });
+        Ok(())
+    }
 
     fn upsert_message(&self, message: Message) -> Result<()> {
-        // Compress bodies with zstd (level 3 = good balance of speed vs compression)
         let body_text_compressed = message
             .body_text
             .as_ref()
-            .map(|text| zstd::encode_all(text.as_bytes(), 3))
+            .map(|text| zstd::encode_all(text.as_bytes(), BODY_COMPRESSION_LEVEL))
             .transpose()
             .context("Failed to compress body_text")?;
 
         let body_html_compressed = message
             .body_html
             .as_ref()
-            .map(|html| zstd::encode_all(html.as_bytes(), 3))
+            .map(|html| zstd::encode_all(html.as_bytes(), BODY_COMPRESSION_LEVEL))
             .transpose()
             .context("Failed to compress body_html")?;
 
+        let attachment_text_compressed = message
+            .attachment_text
+            .as_ref()
+            .map(|text| zstd::encode_all(text.as_bytes(), BODY_COMPRESSION_LEVEL))
+            .transpose()
+            .context("Failed to compress attachment_text")?;
+
         let has_body_text = body_text_compressed.is_some();
         let has_body_html = body_html_compressed.is_some();
+        let attachment_names_json = serde_json::to_string(&message.attachment_names)?;
+        let unsubscribe_json = message
+            .unsubscribe
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+        let auth_results_json = message
+            .auth_results
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+        let invite_json = message
+            .invite
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
 
         // Update SQLite in a transaction
         let mut conn = self.conn.lock().unwrap();
@@ -477,8 +1129,10 @@ impl MailStore for SqliteMailStore {
             "INSERT INTO messages
              (id, thread_id, account_id, from_name, from_email, subject, body_preview,
               received_at, internal_date, has_body_text, has_body_html,
-              body_text, body_html)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+              body_text, body_html, body_text_size, body_html_size, body_compression_level,
+              has_attachments, attachment_names, size_bytes, unsubscribe, auth_results, invite,
+              attachment_text, lang, rfc_message_id)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
              ON CONFLICT(id) DO UPDATE SET
                 thread_id = excluded.thread_id,
                 account_id = excluded.account_id,
@@ -491,7 +1145,19 @@ impl MailStore for SqliteMailStore {
                 has_body_text = excluded.has_body_text,
                 has_body_html = excluded.has_body_html,
                 body_text = excluded.body_text,
-                body_html = excluded.body_html",
+                body_html = excluded.body_html,
+                body_text_size = excluded.body_text_size,
+                body_html_size = excluded.body_html_size,
+                body_compression_level = excluded.body_compression_level,
+                has_attachments = excluded.has_attachments,
+                attachment_names = excluded.attachment_names,
+                size_bytes = excluded.size_bytes,
+                unsubscribe = excluded.unsubscribe,
+                auth_results = excluded.auth_results,
+                invite = excluded.invite,
+                attachment_text = excluded.attachment_text,
+                lang = excluded.lang,
+                rfc_message_id = excluded.rfc_message_id",
             params![
                 message.id.as_str(),
                 message.thread_id.as_str(),
@@ -506,6 +1172,18 @@ impl MailStore for SqliteMailStore {
                 has_body_html,
                 body_text_compressed,
                 body_html_compressed,
+                message.body_text.as_ref().map(|text| text.len() as i64),
+                message.body_html.as_ref().map(|html| html.len() as i64),
+                BODY_COMPRESSION_LEVEL,
+                message.has_attachments,
+                attachment_names_json,
+                message.size_bytes,
+                unsubscribe_json,
+                auth_results_json,
+                invite_json,
+                attachment_text_compressed,
+                message.lang,
+                message.rfc_message_id,
             ],
         )?;
 
@@ -516,6 +1194,10 @@ impl MailStore for SqliteMailStore {
         // Save labels
         self.save_labels(&tx, message.id.as_str(), &message.label_ids)?;
 
+        if let Some(rfc_message_id) = &message.rfc_message_id {
+            self.link_duplicates(&tx, message.account_id, rfc_message_id)?;
+        }
+
         // Update thread_labels index
         self.update_thread_labels(&tx, message.thread_id.as_str())?;
 
@@ -530,75 +1212,22 @@ impl MailStore for SqliteMailStore {
     }
 
     fn get_thread(&self, id: &ThreadId) -> Result<Option<Thread>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader();
 
-        let row: Option<(
-            String,
-            i64,
-            String,
-            String,
-            String,
-            i64,
-            Option<String>,
-            String,
-            bool,
-        )> = conn
+        let thread = conn
             .query_row(
-                "SELECT id, account_id, subject, snippet, last_message_at, message_count,
-                        sender_name, sender_email, is_unread
-                 FROM threads WHERE id = ?",
+                &format!("SELECT {} FROM threads WHERE id = ?", THREAD_COLUMNS),
                 [id.as_str()],
-                |row| {
-                    Ok((
-                        row.get(0)?,
-                        row.get(1)?,
-                        row.get(2)?,
-                        row.get(3)?,
-                        row.get(4)?,
-                        row.get(5)?,
-                        row.get(6)?,
-                        row.get(7)?,
-                        row.get(8)?,
-                    ))
-                },
+                thread_from_row,
             )
             .optional()?;
 
-        let Some((
-            id,
-            account_id,
-            subject,
-            snippet,
-            last_message_at_str,
-            message_count,
-            sender_name,
-            sender_email,
-            is_unread,
-        )) = row
-        else {
-            return Ok(None);
-        };
-
-        let last_message_at = chrono::DateTime::parse_from_rfc3339(&last_message_at_str)
-            .map(|dt| dt.with_timezone(&chrono::Utc))
-            .unwrap_or_else(|_| chrono::Utc::now());
-
-        Ok(Some(Thread {
-            id: ThreadId::new(id),
-            account_id,
-            subject,
-            snippet,
-            last_message_at,
-            message_count: message_count as usize,
-            sender_name,
-            sender_email,
-            is_unread,
-        }))
+        Ok(thread)
     }
 
     fn get_message(&self, id: &MessageId) -> Result<Option<Message>> {
         let metadata = {
-            let conn = self.conn.lock().unwrap();
+            let conn = self.reader();
             self.load_message_metadata(&conn, id.as_str())?
         };
 
@@ -612,22 +1241,24 @@ impl MailStore for SqliteMailStore {
     }
 
     fn get_message_metadata(&self, id: &MessageId) -> Result<Option<MessageMetadata>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader();
         self.load_message_metadata(&conn, id.as_str())
     }
 
     fn get_message_body(&self, id: &MessageId) -> Result<Option<MessageBody>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader();
 
-        let row: Option<(Option<Vec<u8>>, Option<Vec<u8>>)> = conn
+        type BodyRow = (Option<Vec<u8>>, Option<Vec<u8>>, Option<Vec<u8>>);
+        let row: Option<BodyRow> = conn
             .query_row(
-                "SELECT body_text, body_html FROM messages WHERE id = ?",
+                "SELECT body_text, body_html, attachment_text FROM messages WHERE id = ?",
                 [id.as_str()],
-                |row| Ok((row.get(0)?, row.get(1)?)),
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
             )
             .optional()?;
 
-        let Some((body_text_compressed, body_html_compressed)) = row else {
+        let Some((body_text_compressed, body_html_compressed, attachment_text_compressed)) = row
+        else {
             return Ok(None);
         };
 
@@ -648,43 +1279,223 @@ impl MailStore for SqliteMailStore {
             })
             .transpose()?;
 
-        if text.is_none() && html.is_none() {
+        let attachment_text = attachment_text_compressed
+            .map(|data| {
+                zstd::decode_all(data.as_slice())
+                    .context("Failed to decompress attachment_text")
+                    .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            })
+            .transpose()?;
+
+        if text.is_none() && html.is_none() && attachment_text.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(MessageBody {
+            text,
+            html,
+            attachment_text,
+        }))
+    }
+
+    fn get_translation(&self, id: &MessageId, lang: &str) -> Result<Option<String>> {
+        let data = self
+            .blob_store
+            .get(&BlobKey::translation(id.as_str(), lang))?;
+        Ok(data.map(|bytes| String::from_utf8_lossy(&bytes).into_owned()))
+    }
+
+    fn put_translation(&self, id: &MessageId, lang: &str, text: &str) -> Result<()> {
+        self.blob_store
+            .put(&BlobKey::translation(id.as_str(), lang), text.as_bytes())
+    }
+
+    fn get_cached_summary(
+        &self,
+        thread_id: &ThreadId,
+        source_key: &str,
+    ) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let summary = conn
+            .query_row(
+                "SELECT summary FROM thread_summaries WHERE thread_id = ? AND source_key = ?",
+                params![thread_id.as_str(), source_key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(summary)
+    }
+
+    fn cache_summary(&self, thread_id: &ThreadId, source_key: &str, summary: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO thread_summaries (thread_id, source_key, summary, created_at)
+             VALUES (?, ?, ?, ?)
+             ON CONFLICT(thread_id) DO UPDATE SET
+                source_key = excluded.source_key,
+                summary = excluded.summary,
+                created_at = excluded.created_at",
+            params![
+                thread_id.as_str(),
+                source_key,
+                summary,
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn save_tracking_token(&self, token: &str, message_id: &MessageId) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO tracking_beacons (token, message_id, created_at)
+             VALUES (?, ?, ?)",
+            params![token, message_id.as_str(), chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    fn record_message_open(
+        &self,
+        token: &str,
+        opened_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO message_opens (message_id, opened_at)
+             SELECT message_id, ? FROM tracking_beacons WHERE token = ?",
+            params![opened_at.to_rfc3339(), token],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    fn get_open_status(&self, message_id: &MessageId) -> Result<Option<OpenStatus>> {
+        let conn = self.conn.lock().unwrap();
+        let tracked = conn
+            .query_row(
+                "SELECT 1 FROM tracking_beacons WHERE message_id = ? LIMIT 1",
+                params![message_id.as_str()],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        if !tracked {
             return Ok(None);
         }
 
-        Ok(Some(MessageBody { text, html }))
+        let mut stmt = conn.prepare("SELECT opened_at FROM message_opens WHERE message_id = ?")?;
+        let mut times = stmt
+            .query_map(params![message_id.as_str()], |row| row.get::<_, String>(0))?
+            .filter_map(|r| r.ok())
+            .filter_map(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .collect::<Vec<_>>();
+        times.sort();
+
+        Ok(Some(OpenStatus {
+            open_count: times.len() as u32,
+            first_opened_at: times.first().copied(),
+            last_opened_at: times.last().copied(),
+        }))
     }
 
-    fn list_threads(&self, limit: usize, offset: usize) -> Result<Vec<Thread>> {
+    fn latest_message_open(&self) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT opened_at FROM message_opens")?;
+        let latest = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .filter_map(|r| r.ok())
+            .filter_map(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .max();
+        Ok(latest)
+    }
+
+    fn get_followup_state(&self, thread_id: &ThreadId) -> Result<Option<FollowupState>> {
+        let conn = self.conn.lock().unwrap();
+        let row: Option<(Option<String>, Option<String>)> = conn
+            .query_row(
+                "SELECT dismissed_at, snoozed_until FROM followup_reminders WHERE thread_id = ?",
+                params![thread_id.as_str()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+
+        let parse = |s: Option<String>| {
+            s.and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                .map(|dt| dt.with_timezone(&chrono::Utc))
+        };
+        Ok(row.map(|(dismissed_at, snoozed_until)| FollowupState {
+            thread_id: thread_id.clone(),
+            dismissed_at: parse(dismissed_at),
+            snoozed_until: parse(snoozed_until),
+        }))
+    }
+
+    fn save_followup_state(&self, state: &FollowupState) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO followup_reminders (thread_id, dismissed_at, snoozed_until)
+             VALUES (?, ?, ?)",
+            params![
+                state.thread_id.as_str(),
+                state.dismissed_at.map(|t| t.to_rfc3339()),
+                state.snoozed_until.map(|t| t.to_rfc3339())
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn get_note(&self, thread_id: &ThreadId) -> Result<Option<ThreadNote>> {
+        let conn = self.conn.lock().unwrap();
+        let row: Option<(String, String)> = conn
+            .query_row(
+                "SELECT markdown, updated_at FROM thread_notes WHERE thread_id = ?",
+                params![thread_id.as_str()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+
+        Ok(row.map(|(markdown, updated_at)| ThreadNote {
+            thread_id: thread_id.clone(),
+            markdown,
+            updated_at: chrono::DateTime::parse_from_rfc3339(&updated_at)
+                .map(|dt| dt.with_timezone(&chrono::Utc))
+                .unwrap_or_else(|_| chrono::Utc::now()),
+        }))
+    }
+
+    fn set_note(&self, thread_id: &ThreadId, markdown: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
+        if markdown.trim().is_empty() {
+            conn.execute(
+                "DELETE FROM thread_notes WHERE thread_id = ?",
+                params![thread_id.as_str()],
+            )?;
+        } else {
+            conn.execute(
+                "INSERT OR REPLACE INTO thread_notes (thread_id, markdown, updated_at)
+                 VALUES (?, ?, ?)",
+                params![thread_id.as_str(), markdown, chrono::Utc::now().to_rfc3339()],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn list_threads(&self, limit: usize, offset: usize) -> Result<Vec<Thread>> {
+        let conn = self.reader();
 
         let mut stmt = conn.prepare(
-            "SELECT id, account_id, subject, snippet, last_message_at, message_count,
-                    sender_name, sender_email, is_unread
-             FROM threads
-             ORDER BY last_message_at DESC
-             LIMIT ? OFFSET ?",
+            &format!(
+                "SELECT {} FROM threads
+                 ORDER BY last_message_at DESC
+                 LIMIT ? OFFSET ?",
+                THREAD_COLUMNS
+            ),
         )?;
 
         let threads = stmt
-            .query_map(params![limit as i64, offset as i64], |row| {
-                let last_message_at_str: String = row.get(4)?;
-                let last_message_at = chrono::DateTime::parse_from_rfc3339(&last_message_at_str)
-                    .map(|dt| dt.with_timezone(&chrono::Utc))
-                    .unwrap_or_else(|_| chrono::Utc::now());
-
-                Ok(Thread {
-                    id: ThreadId::new(row.get::<_, String>(0)?),
-                    account_id: row.get(1)?,
-                    subject: row.get(2)?,
-                    snippet: row.get(3)?,
-                    last_message_at,
-                    message_count: row.get::<_, i64>(5)? as usize,
-                    sender_name: row.get(6)?,
-                    sender_email: row.get(7)?,
-                    is_unread: row.get(8)?,
-                })
-            })?
+            .query_map(params![limit as i64, offset as i64], thread_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(threads)
@@ -696,44 +1507,20 @@ impl MailStore for SqliteMailStore {
         limit: usize,
         offset: usize,
     ) -> Result<Vec<Thread>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader();
 
-        let mut stmt = conn.prepare(
-            "SELECT t.id, t.account_id, t.subject, t.snippet, t.last_message_at, t.message_count,
-                    t.sender_name, t.sender_email, t.is_unread
-             FROM threads t
-             INNER JOIN thread_labels tl ON t.id = tl.thread_id
-             WHERE tl.label_id = ?
-             ORDER BY tl.last_message_at DESC
-             LIMIT ? OFFSET ?",
-        )?;
+        let query = format!("{} OFFSET ?", label_threads_query(false, false));
+        let mut stmt = conn.prepare(&query)?;
 
         let threads = stmt
-            .query_map(params![label, limit as i64, offset as i64], |row| {
-                let last_message_at_str: String = row.get(4)?;
-                let last_message_at = chrono::DateTime::parse_from_rfc3339(&last_message_at_str)
-                    .map(|dt| dt.with_timezone(&chrono::Utc))
-                    .unwrap_or_else(|_| chrono::Utc::now());
-
-                Ok(Thread {
-                    id: ThreadId::new(row.get::<_, String>(0)?),
-                    account_id: row.get(1)?,
-                    subject: row.get(2)?,
-                    snippet: row.get(3)?,
-                    last_message_at,
-                    message_count: row.get::<_, i64>(5)? as usize,
-                    sender_name: row.get(6)?,
-                    sender_email: row.get(7)?,
-                    is_unread: row.get(8)?,
-                })
-            })?
+            .query_map(params![label, limit as i64, offset as i64], thread_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(threads)
     }
 
     fn list_messages_for_thread(&self, thread_id: &ThreadId) -> Result<Vec<MessageMetadata>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader();
 
         let mut stmt =
             conn.prepare("SELECT id FROM messages WHERE thread_id = ? ORDER BY received_at ASC")?;
@@ -742,16 +1529,62 @@ impl MailStore for SqliteMailStore {
             .query_map([thread_id.as_str()], |row| row.get(0))?
             .collect::<Result<Vec<_>, _>>()?;
 
-        let mut messages = Vec::new();
-        for id in &message_ids {
-            if let Some(metadata) = self.load_message_metadata(&conn, id)? {
-                messages.push(metadata);
-            } else {
-                log::warn!("[STORE] Failed to load metadata for message {}", id);
-            }
+        self.load_messages_metadata(&conn, &message_ids)
+    }
+
+    fn list_unsubscribable_messages(
+        &self,
+        account_id: Option<i64>,
+    ) -> Result<Vec<MessageMetadata>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut query = String::from("SELECT id FROM messages WHERE unsubscribe IS NOT NULL");
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        if let Some(id) = account_id {
+            query.push_str(" AND account_id = ?");
+            params.push(Box::new(id));
         }
+        query.push_str(" ORDER BY received_at DESC");
 
-        Ok(messages)
+        let mut stmt = conn.prepare(&query)?;
+        let message_ids: Vec<String> = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.load_messages_metadata(&conn, &message_ids)
+    }
+
+    fn list_messages_for_account(
+        &self,
+        label: Option<&str>,
+        account_id: Option<i64>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<MessageMetadata>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut query = String::from("SELECT m.id FROM messages m");
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        if let Some(label) = label {
+            query.push_str(
+                " INNER JOIN message_labels ml ON ml.message_id = m.id AND ml.label_id = ?",
+            );
+            params.push(Box::new(label.to_string()));
+        }
+        if let Some(id) = account_id {
+            query.push_str(" WHERE m.account_id = ?");
+            params.push(Box::new(id));
+        }
+        query.push_str(" ORDER BY m.received_at DESC, m.id ASC LIMIT ? OFFSET ?");
+        params.push(Box::new(limit as i64));
+        params.push(Box::new(offset as i64));
+
+        let mut stmt = conn.prepare(&query)?;
+        let message_ids: Vec<String> = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.load_messages_metadata(&conn, &message_ids)
     }
 
     fn list_messages_for_thread_with_bodies(&self, thread_id: &ThreadId) -> Result<Vec<Message>> {
@@ -767,7 +1600,7 @@ impl MailStore for SqliteMailStore {
     }
 
     fn has_message(&self, id: &MessageId) -> Result<bool> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader();
 
         let count: i64 = conn.query_row(
             "SELECT COUNT(*) FROM messages WHERE id = ?",
@@ -779,7 +1612,7 @@ impl MailStore for SqliteMailStore {
     }
 
     fn count_threads(&self) -> Result<usize> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader();
 
         let count: i64 = conn.query_row("SELECT COUNT(*) FROM threads", [], |row| row.get(0))?;
 
@@ -787,7 +1620,7 @@ impl MailStore for SqliteMailStore {
     }
 
     fn count_threads_by_label(&self, label: &str) -> Result<usize> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader();
 
         let count: i64 = conn.query_row(
             "SELECT COUNT(*) FROM thread_labels WHERE label_id = ?",
@@ -799,7 +1632,7 @@ impl MailStore for SqliteMailStore {
     }
 
     fn count_unread_threads_by_label(&self, label: &str) -> Result<usize> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader();
 
         let count: i64 = conn.query_row(
             "SELECT COUNT(*) FROM thread_labels tl
@@ -813,7 +1646,7 @@ impl MailStore for SqliteMailStore {
     }
 
     fn count_messages_in_thread(&self, thread_id: &ThreadId) -> Result<usize> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader();
 
         let count: i64 = conn.query_row(
             "SELECT COUNT(*) FROM messages WHERE thread_id = ?",
@@ -824,6 +1657,39 @@ impl MailStore for SqliteMailStore {
         Ok(count as usize)
     }
 
+    fn first_message_at(
+        &self,
+        thread_id: &ThreadId,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        let conn = self.reader();
+
+        let first: Option<String> = conn.query_row(
+            "SELECT MIN(received_at) FROM messages WHERE thread_id = ?",
+            [thread_id.as_str()],
+            |row| row.get(0),
+        )?;
+
+        first
+            .map(|s| {
+                chrono::DateTime::parse_from_rfc3339(&s)
+                    .map(|dt| dt.with_timezone(&chrono::Utc))
+                    .context("Invalid received_at")
+            })
+            .transpose()
+    }
+
+    fn latest_internal_date(&self, account_id: i64) -> Result<Option<i64>> {
+        let conn = self.reader();
+
+        let latest = conn.query_row(
+            "SELECT MAX(internal_date) FROM messages WHERE account_id = ?",
+            [account_id],
+            |row| row.get(0),
+        )?;
+
+        Ok(latest)
+    }
+
     fn clear(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
 
@@ -837,9 +1703,11 @@ impl MailStore for SqliteMailStore {
              DELETE FROM threads;
              DELETE FROM sync_state;",
         )?;
+        drop(conn);
 
         self.blob_store.clear()?;
 
+        self.events.publish(StoreEvent::Cleared { account_id: None });
         Ok(())
     }
 
@@ -934,7 +1802,7 @@ impl MailStore for SqliteMailStore {
     }
 
     fn has_thread(&self, id: &ThreadId) -> Result<bool> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader();
 
         let count: i64 = conn.query_row(
             "SELECT COUNT(*) FROM threads WHERE id = ?",
@@ -955,14 +1823,16 @@ impl MailStore for SqliteMailStore {
              DELETE FROM messages;
              DELETE FROM threads;",
         )?;
+        drop(conn);
 
         self.blob_store.clear()?;
 
+        self.events.publish(StoreEvent::Cleared { account_id: None });
         Ok(())
     }
 
     fn get_message_ids_for_thread(&self, thread_id: &ThreadId) -> Result<Vec<MessageId>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader();
 
         let mut stmt = conn.prepare("SELECT id FROM messages WHERE thread_id = ?")?;
 
@@ -975,6 +1845,43 @@ impl MailStore for SqliteMailStore {
         Ok(ids)
     }
 
+    fn list_duplicate_messages(
+        &self,
+        thread_id: &ThreadId,
+    ) -> Result<Vec<(MessageId, MessageId)>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(
+            "SELECT d.message_id, d.canonical_id FROM message_duplicates d
+             JOIN messages m ON m.id = d.message_id
+             WHERE m.thread_id = ?
+             ORDER BY d.message_id",
+        )?;
+
+        let pairs = stmt
+            .query_map([thread_id.as_str()], |row| {
+                Ok((
+                    MessageId::new(row.get::<_, String>(0)?),
+                    MessageId::new(row.get::<_, String>(1)?),
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(pairs)
+    }
+
+    fn list_message_ids(&self, account_id: i64) -> Result<Vec<MessageId>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare("SELECT id FROM messages WHERE account_id = ?")?;
+
+        let ids = stmt
+            .query_map([account_id], |row| Ok(MessageId::new(row.get::<_, String>(0)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ids)
+    }
+
     fn update_message_labels(&self, message_id: &MessageId, label_ids: Vec<String>) -> Result<()> {
         let mut conn = self.conn.lock().unwrap();
         let tx = conn.transaction()?;
@@ -992,6 +1899,9 @@ impl MailStore for SqliteMailStore {
             return Ok(()); // Message not found
         };
 
+        // Capture old labels for the change notification
+        let old_labels = self.load_labels(&tx, message_id.as_str())?;
+
         // Delete old labels
         tx.execute(
             "DELETE FROM message_labels WHERE message_id = ?",
@@ -1028,6 +1938,17 @@ impl MailStore for SqliteMailStore {
         self.update_thread_labels(&tx, &thread_id)?;
 
         tx.commit()?;
+        drop(conn);
+
+        let (added, removed) = label_diff(&old_labels, &label_ids);
+        if !added.is_empty() || !removed.is_empty() {
+            self.events.publish(StoreEvent::LabelChanged {
+                thread_id: ThreadId::new(thread_id),
+                message_id: message_id.clone(),
+                added,
+                removed,
+            });
+        }
         Ok(())
     }
 
@@ -1040,41 +1961,66 @@ impl MailStore for SqliteMailStore {
         let tx = conn.transaction()?;
 
         // Get thread_id before deleting
-        let thread_id: Option<String> = tx
+        let deleted: Option<(String, i64, Option<String>)> = tx
             .query_row(
-                "SELECT thread_id FROM messages WHERE id = ?",
+                "SELECT thread_id, account_id, rfc_message_id FROM messages WHERE id = ?",
                 [message_id.as_str()],
-                |row| row.get(0),
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
             )
             .optional()?;
 
-        // Delete message (cascades to recipients and labels)
+        // Delete message (cascades to recipients, labels and duplicates)
         tx.execute("DELETE FROM messages WHERE id = ?", [message_id.as_str()])?;
 
+        // Another delivery takes over if this was the canonical copy
+        if let Some((_, account_id, Some(rfc_message_id))) = &deleted {
+            self.link_duplicates(&tx, *account_id, rfc_message_id)?;
+        }
+        let thread_id = deleted.map(|(thread_id, _, _)| thread_id);
+
         // Update thread if it still exists
-        if let Some(thread_id) = thread_id {
-            let remaining: i64 = tx.query_row(
-                "SELECT COUNT(*) FROM messages WHERE thread_id = ?",
-                [&thread_id],
-                |row| row.get(0),
-            )?;
+        let event = match thread_id {
+            Some(thread_id) => self.message_left_thread(&tx, thread_id)?,
+            None => None,
+        };
 
-            if remaining == 0 {
-                // Delete thread entirely
-                tx.execute("DELETE FROM threads WHERE id = ?", [&thread_id])?;
-            } else {
-                // Update message count
-                tx.execute(
-                    "UPDATE threads SET message_count = ? WHERE id = ?",
-                    params![remaining, thread_id],
-                )?;
+        tx.commit()?;
+        drop(conn);
 
-                // Update thread_labels index
-                self.update_thread_labels(&tx, &thread_id)?;
-            }
+        if let Some(event) = event {
+            self.events.publish(event);
         }
+        Ok(())
+    }
+
+    fn move_message(&self, message_id: &MessageId, thread_id: &ThreadId) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let old_thread_id: Option<String> = tx
+            .query_row(
+                "SELECT thread_id FROM messages WHERE id = ?",
+                [message_id.as_str()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(old_thread_id) = old_thread_id.filter(|old| old != thread_id.as_str()) else {
+            return Ok(());
+        };
+
+        tx.execute(
+            "UPDATE messages SET thread_id = ? WHERE id = ?",
+            params![thread_id.as_str(), message_id.as_str()],
+        )?;
+        self.update_thread_labels(&tx, thread_id.as_str())?;
+        let event = self.message_left_thread(&tx, old_thread_id)?;
 
         tx.commit()?;
+        drop(conn);
+
+        if let Some(event) = event {
+            self.events.publish(event);
+        }
         Ok(())
     }
 
@@ -1085,11 +2031,17 @@ impl MailStore for SqliteMailStore {
         data: &[u8],
         label_ids: Vec<String>,
     ) -> Result<()> {
+        // Raw Gmail JSON compresses several times over; an initial sync can
+        // queue hundreds of thousands of these
+        let data = zstd::encode_all(data, BODY_COMPRESSION_LEVEL)
+            .context("Failed to compress pending message")?;
+
         let mut conn = self.conn.lock().unwrap();
         let tx = conn.transaction()?;
 
         tx.execute(
-            "INSERT OR REPLACE INTO pending_messages (id, account_id, data) VALUES (?, ?, ?)",
+            "INSERT OR REPLACE INTO pending_messages (id, account_id, data, compressed)
+             VALUES (?, ?, ?, 1)",
             params![id.as_str(), account_id, data],
         )?;
 
@@ -1131,31 +2083,31 @@ impl MailStore for SqliteMailStore {
     ) -> Result<Vec<PendingMessage>> {
         let conn = self.conn.lock().unwrap();
 
-        let messages: Vec<(String, Vec<u8>)> = if let Some(label) = label {
+        let messages: Vec<(String, Vec<u8>, bool)> = if let Some(label) = label {
             // Get messages with specific label
             let mut stmt = conn.prepare(
-                "SELECT p.id, p.data FROM pending_messages p
+                "SELECT p.id, p.data, p.compressed FROM pending_messages p
                  INNER JOIN pending_message_labels pl ON p.id = pl.message_id
                  WHERE p.account_id = ? AND pl.label_id = ?
                  LIMIT ?",
             )?;
 
             stmt.query_map(params![account_id, label, limit as i64], |row| {
-                Ok((row.get(0)?, row.get(1)?))
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
             })?
             .collect::<Result<Vec<_>, _>>()?
         } else {
             // Get INBOX messages first, then others
             let mut inbox_stmt = conn.prepare(
-                "SELECT p.id, p.data FROM pending_messages p
+                "SELECT p.id, p.data, p.compressed FROM pending_messages p
                  INNER JOIN pending_message_labels pl ON p.id = pl.message_id
                  WHERE p.account_id = ? AND pl.label_id = 'INBOX'
                  LIMIT ?",
             )?;
 
-            let inbox: Vec<(String, Vec<u8>)> = inbox_stmt
+            let inbox: Vec<(String, Vec<u8>, bool)> = inbox_stmt
                 .query_map(params![account_id, limit as i64], |row| {
-                    Ok((row.get(0)?, row.get(1)?))
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
                 })?
                 .collect::<Result<Vec<_>, _>>()?;
 
@@ -1163,17 +2115,17 @@ impl MailStore for SqliteMailStore {
                 inbox
             } else {
                 let remaining = limit - inbox.len();
-                let inbox_ids: Vec<String> = inbox.iter().map(|(id, _)| id.clone()).collect();
+                let inbox_ids: Vec<String> = inbox.iter().map(|(id, _, _)| id.clone()).collect();
 
                 let mut other_stmt = conn.prepare(
-                    "SELECT id, data FROM pending_messages
+                    "SELECT id, data, compressed FROM pending_messages
                      WHERE account_id = ? AND id NOT IN (SELECT message_id FROM pending_message_labels WHERE label_id = 'INBOX')
                      LIMIT ?",
                 )?;
 
-                let others: Vec<(String, Vec<u8>)> = other_stmt
+                let others: Vec<(String, Vec<u8>, bool)> = other_stmt
                     .query_map(params![account_id, remaining as i64], |row| {
-                        Ok((row.get(0)?, row.get(1)?))
+                        Ok((row.get(0)?, row.get(1)?, row.get(2)?))
                     })?
                     .collect::<Result<Vec<_>, _>>()?;
 
@@ -1189,7 +2141,13 @@ impl MailStore for SqliteMailStore {
 
         // Load labels for each message
         let mut result = Vec::new();
-        for (id, data) in messages {
+        for (id, data, compressed) in messages {
+            let data = if compressed {
+                zstd::decode_all(data.as_slice())
+                    .with_context(|| format!("Failed to decompress pending message {}", id))?
+            } else {
+                data
+            };
             let mut label_stmt =
                 conn.prepare("SELECT label_id FROM pending_message_labels WHERE message_id = ?")?;
             let label_ids: Vec<String> = label_stmt
@@ -1242,14 +2200,58 @@ impl MailStore for SqliteMailStore {
         Ok(())
     }
 
+    // === Re-fetch Queue ===
+
+    fn mark_for_refetch(&self, ids: &[MessageId]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT OR IGNORE INTO refetch_messages (message_id)
+                 SELECT id FROM messages WHERE id = ?",
+            )?;
+            for id in ids {
+                stmt.execute([id.as_str()])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn list_refetch_messages(&self, account_id: i64) -> Result<Vec<MessageId>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT r.message_id FROM refetch_messages r
+             INNER JOIN messages m ON m.id = r.message_id
+             WHERE m.account_id = ?
+             ORDER BY r.message_id",
+        )?;
+        let ids = stmt
+            .query_map([account_id], |row| row.get::<_, String>(0))?
+            .map(|id| id.map(MessageId::new))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ids)
+    }
+
+    fn clear_refetch(&self, id: &MessageId) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM refetch_messages WHERE message_id = ?",
+            [id.as_str()],
+        )?;
+        Ok(())
+    }
+
     // === Multi-Account Support Methods ===
 
     fn register_account(&self, account: Account) -> Result<Account> {
         let conn = self.conn.lock().unwrap();
 
+        let signature = account.signature.as_ref();
         conn.execute(
-            "INSERT INTO accounts (email, display_name, avatar_color, is_primary, added_at, token_data)
-             VALUES (?, ?, ?, ?, ?, ?)",
+            "INSERT INTO accounts (email, display_name, avatar_color, is_primary, added_at,
+                                   token_data, signature_text, signature_html, sync_enabled)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 account.email,
                 account.display_name,
@@ -1257,129 +2259,53 @@ impl MailStore for SqliteMailStore {
                 account.is_primary,
                 account.added_at.to_rfc3339(),
                 account.token_data,
+                signature.map(|s| &s.text),
+                signature.and_then(|s| s.html.as_ref()),
+                account.sync_enabled,
             ],
         )?;
 
         let id = conn.last_insert_rowid();
 
-        Ok(Account {
-            id,
-            email: account.email,
-            display_name: account.display_name,
-            avatar_color: account.avatar_color,
-            is_primary: account.is_primary,
-            added_at: account.added_at,
-            token_data: account.token_data,
-        })
+        Ok(Account { id, ..account })
     }
 
     fn get_account(&self, account_id: i64) -> Result<Option<Account>> {
         let conn = self.conn.lock().unwrap();
 
-        let row: Option<(i64, String, Option<String>, String, bool, String, Option<String>)> = conn
+        let account = conn
             .query_row(
-                "SELECT id, email, display_name, avatar_color, is_primary, added_at, token_data
-                 FROM accounts WHERE id = ?",
+                &format!("SELECT {} FROM accounts WHERE id = ?", ACCOUNT_COLUMNS),
                 [account_id],
-                |row| {
-                    Ok((
-                        row.get(0)?,
-                        row.get(1)?,
-                        row.get(2)?,
-                        row.get(3)?,
-                        row.get(4)?,
-                        row.get(5)?,
-                        row.get(6)?,
-                    ))
-                },
+                account_from_row,
             )
             .optional()?;
-
-        let Some((id, email, display_name, avatar_color, is_primary, added_at_str, token_data)) = row else {
-            return Ok(None);
-        };
-
-        let added_at = chrono::DateTime::parse_from_rfc3339(&added_at_str)
-            .map(|dt| dt.with_timezone(&chrono::Utc))
-            .unwrap_or_else(|_| chrono::Utc::now());
-
-        Ok(Some(Account {
-            id,
-            email,
-            display_name,
-            avatar_color,
-            is_primary,
-            added_at,
-            token_data,
-        }))
+        Ok(account)
     }
 
     fn get_account_by_email(&self, email: &str) -> Result<Option<Account>> {
         let conn = self.conn.lock().unwrap();
 
-        let row: Option<(i64, String, Option<String>, String, bool, String, Option<String>)> = conn
+        let account = conn
             .query_row(
-                "SELECT id, email, display_name, avatar_color, is_primary, added_at, token_data
-                 FROM accounts WHERE email = ?",
+                &format!("SELECT {} FROM accounts WHERE email = ?", ACCOUNT_COLUMNS),
                 [email],
-                |row| {
-                    Ok((
-                        row.get(0)?,
-                        row.get(1)?,
-                        row.get(2)?,
-                        row.get(3)?,
-                        row.get(4)?,
-                        row.get(5)?,
-                        row.get(6)?,
-                    ))
-                },
+                account_from_row,
             )
             .optional()?;
-
-        let Some((id, email, display_name, avatar_color, is_primary, added_at_str, token_data)) = row else {
-            return Ok(None);
-        };
-
-        let added_at = chrono::DateTime::parse_from_rfc3339(&added_at_str)
-            .map(|dt| dt.with_timezone(&chrono::Utc))
-            .unwrap_or_else(|_| chrono::Utc::now());
-
-        Ok(Some(Account {
-            id,
-            email,
-            display_name,
-            avatar_color,
-            is_primary,
-            added_at,
-            token_data,
-        }))
+        Ok(account)
     }
 
     fn list_accounts(&self) -> Result<Vec<Account>> {
         let conn = self.conn.lock().unwrap();
 
-        let mut stmt = conn.prepare(
-            "SELECT id, email, display_name, avatar_color, is_primary, added_at, token_data
-             FROM accounts ORDER BY is_primary DESC, added_at ASC",
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM accounts ORDER BY is_primary DESC, added_at ASC",
+            ACCOUNT_COLUMNS
+        ))?;
 
         let accounts = stmt
-            .query_map([], |row| {
-                let added_at_str: String = row.get(5)?;
-                let added_at = chrono::DateTime::parse_from_rfc3339(&added_at_str)
-                    .map(|dt| dt.with_timezone(&chrono::Utc))
-                    .unwrap_or_else(|_| chrono::Utc::now());
-
-                Ok(Account {
-                    id: row.get(0)?,
-                    email: row.get(1)?,
-                    display_name: row.get(2)?,
-                    avatar_color: row.get(3)?,
-                    is_primary: row.get(4)?,
-                    added_at,
-                    token_data: row.get(6)?,
-                })
-            })?
+            .query_map([], account_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(accounts)
@@ -1389,43 +2315,15 @@ impl MailStore for SqliteMailStore {
         let mut conn = self.conn.lock().unwrap();
         let tx = conn.transaction()?;
 
-        // Delete in dependency order (due to foreign keys)
-        // First clear data associated with the account
-        tx.execute(
-            "DELETE FROM pending_message_labels WHERE message_id IN
-             (SELECT id FROM pending_messages WHERE account_id = ?)",
-            [account_id],
-        )?;
-        tx.execute(
-            "DELETE FROM pending_messages WHERE account_id = ?",
-            [account_id],
-        )?;
-        tx.execute(
-            "DELETE FROM thread_labels WHERE account_id = ?",
-            [account_id],
-        )?;
-        tx.execute(
-            "DELETE FROM message_labels WHERE message_id IN
-             (SELECT id FROM messages WHERE account_id = ?)",
-            [account_id],
-        )?;
-        tx.execute(
-            "DELETE FROM message_recipients WHERE message_id IN
-             (SELECT id FROM messages WHERE account_id = ?)",
-            [account_id],
-        )?;
-        tx.execute("DELETE FROM messages WHERE account_id = ?", [account_id])?;
-        tx.execute("DELETE FROM threads WHERE account_id = ?", [account_id])?;
-        tx.execute("DELETE FROM sync_state WHERE account_id = ?", [account_id])?;
+        let message_ids = delete_account_rows(&tx, account_id)?;
 
         // Finally delete the account itself
         tx.execute("DELETE FROM accounts WHERE id = ?", [account_id])?;
 
         tx.commit()?;
+        drop(conn);
 
-        // Also clear blob store for this account (TODO: account-scoped blobs)
-        // For now, this is a best-effort - blobs are keyed by message ID
-        Ok(())
+        self.finish_account_clear(account_id, &message_ids)
     }
 
     fn update_account_token(&self, account_id: i64, token_data: Option<String>) -> Result<()> {
@@ -1437,22 +2335,196 @@ impl MailStore for SqliteMailStore {
         Ok(())
     }
 
+    fn update_account_settings(&self, account_id: i64, settings: AccountSettings) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let signature = settings.signature.as_ref();
+        conn.execute(
+            "UPDATE accounts
+             SET display_name = ?, avatar_color = ?, signature_text = ?, signature_html = ?
+             WHERE id = ?",
+            params![
+                settings.display_name,
+                settings.avatar_color,
+                signature.map(|s| &s.text),
+                signature.and_then(|s| s.html.as_ref()),
+                account_id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn set_account_sync_enabled(&self, account_id: i64, enabled: bool) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE accounts SET sync_enabled = ? WHERE id = ?",
+            params![enabled, account_id],
+        )?;
+        Ok(())
+    }
+
+    fn replace_send_as_aliases(&self, account_id: i64, aliases: Vec<SendAsAlias>) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute("DELETE FROM send_as_aliases WHERE account_id = ?", [account_id])?;
+        for alias in &aliases {
+            tx.execute(
+                "INSERT OR REPLACE INTO send_as_aliases
+                     (account_id, email, display_name, is_primary, is_default, verified)
+                 VALUES (?, ?, ?, ?, ?, ?)",
+                params![
+                    account_id,
+                    alias.email,
+                    alias.display_name,
+                    alias.is_primary,
+                    alias.is_default,
+                    alias.verified,
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn list_send_as_aliases(&self, account_id: i64) -> Result<Vec<SendAsAlias>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT email, display_name, is_primary, is_default, verified
+             FROM send_as_aliases WHERE account_id = ?
+             ORDER BY is_default DESC, email ASC",
+        )?;
+
+        let aliases = stmt
+            .query_map([account_id], |row| {
+                Ok(SendAsAlias {
+                    account_id,
+                    email: row.get(0)?,
+                    display_name: row.get(1)?,
+                    is_primary: row.get(2)?,
+                    is_default: row.get(3)?,
+                    verified: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(aliases)
+    }
+
+    fn replace_labels(&self, account_id: i64, labels: Vec<Label>) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute("DELETE FROM labels WHERE account_id = ?", [account_id])?;
+        for label in &labels {
+            let color = label.color.as_ref();
+            tx.execute(
+                "INSERT OR REPLACE INTO labels
+                 (account_id, id, name, background_color, text_color)
+                 VALUES (?, ?, ?, ?, ?)",
+                params![
+                    account_id,
+                    label.id.as_str(),
+                    label.name,
+                    color.map(|c| c.background.as_str()),
+                    color.map(|c| c.text.as_str()),
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn list_labels(&self, account_id: i64) -> Result<Vec<Label>> {
+        let conn = self.reader();
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM labels l {}
+             WHERE l.account_id = ?
+             ORDER BY l.name COLLATE NOCASE, l.id",
+            LABEL_COLUMNS, LABEL_OVERRIDES_JOIN
+        ))?;
+
+        let labels = stmt
+            .query_map([account_id], label_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(labels)
+    }
+
+    fn list_user_labels_for_threads(
+        &self,
+        thread_ids: &[ThreadId],
+    ) -> Result<HashMap<ThreadId, Vec<Label>>> {
+        let conn = self.reader();
+        let mut labels: HashMap<ThreadId, Vec<Label>> = HashMap::new();
+
+        for batch in thread_ids.chunks(METADATA_BATCH_SIZE) {
+            let placeholders = vec!["?"; batch.len()].join(", ");
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {}, tl.thread_id FROM thread_labels tl
+                 INNER JOIN labels l ON l.account_id = tl.account_id AND l.id = tl.label_id
+                 {}
+                 WHERE tl.thread_id IN ({})
+                 ORDER BY l.name COLLATE NOCASE, l.id",
+                LABEL_COLUMNS, LABEL_OVERRIDES_JOIN, placeholders
+            ))?;
+            let ids = batch.iter().map(ThreadId::as_str);
+            let mut rows = stmt.query(rusqlite::params_from_iter(ids))?;
+            while let Some(row) = rows.next()? {
+                let thread_id = ThreadId::new(row.get::<_, String>(5)?);
+                labels.entry(thread_id).or_default().push(label_from_row(row)?);
+            }
+        }
+
+        Ok(labels)
+    }
+
+    fn set_label_override(
+        &self,
+        account_id: i64,
+        label_id: &str,
+        icon: Option<String>,
+        color: Option<LabelColor>,
+    ) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        if icon.is_none() && color.is_none() {
+            conn.execute(
+                "DELETE FROM label_overrides WHERE account_id = ? AND label_id = ?",
+                params![account_id, label_id],
+            )?;
+            return Ok(());
+        }
+
+        conn.execute(
+            "INSERT OR REPLACE INTO label_overrides
+             (account_id, label_id, icon, background_color, text_color)
+             VALUES (?, ?, ?, ?, ?)",
+            params![
+                account_id,
+                label_id,
+                icon,
+                color.as_ref().map(|c| c.background.as_str()),
+                color.as_ref().map(|c| c.text.as_str()),
+            ],
+        )?;
+        Ok(())
+    }
+
     fn list_threads_for_account(
         &self,
         account_id: Option<i64>,
         limit: usize,
         offset: usize,
     ) -> Result<Vec<Thread>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader();
 
-        let (query, params): (&str, Vec<Box<dyn rusqlite::ToSql>>) = if let Some(id) = account_id {
+        let (filter, params): (&str, Vec<Box<dyn rusqlite::ToSql>>) = if let Some(id) = account_id {
             (
-                "SELECT id, account_id, subject, snippet, last_message_at, message_count,
-                        sender_name, sender_email, is_unread
-                 FROM threads
-                 WHERE account_id = ?
-                 ORDER BY last_message_at DESC
-                 LIMIT ? OFFSET ?",
+                "WHERE account_id = ?",
                 vec![
                     Box::new(id) as Box<dyn rusqlite::ToSql>,
                     Box::new(limit as i64),
@@ -1461,11 +2533,7 @@ impl MailStore for SqliteMailStore {
             )
         } else {
             (
-                "SELECT id, account_id, subject, snippet, last_message_at, message_count,
-                        sender_name, sender_email, is_unread
-                 FROM threads
-                 ORDER BY last_message_at DESC
-                 LIMIT ? OFFSET ?",
+                "",
                 vec![
                     Box::new(limit as i64) as Box<dyn rusqlite::ToSql>,
                     Box::new(offset as i64),
@@ -1473,27 +2541,15 @@ impl MailStore for SqliteMailStore {
             )
         };
 
-        let mut stmt = conn.prepare(query)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM threads {}
+             ORDER BY last_message_at DESC
+             LIMIT ? OFFSET ?",
+            THREAD_COLUMNS, filter
+        ))?;
 
         let threads = stmt
-            .query_map(rusqlite::params_from_iter(params.iter()), |row| {
-                let last_message_at_str: String = row.get(4)?;
-                let last_message_at = chrono::DateTime::parse_from_rfc3339(&last_message_at_str)
-                    .map(|dt| dt.with_timezone(&chrono::Utc))
-                    .unwrap_or_else(|_| chrono::Utc::now());
-
-                Ok(Thread {
-                    id: ThreadId::new(row.get::<_, String>(0)?),
-                    account_id: row.get(1)?,
-                    subject: row.get(2)?,
-                    snippet: row.get(3)?,
-                    last_message_at,
-                    message_count: row.get::<_, i64>(5)? as usize,
-                    sender_name: row.get(6)?,
-                    sender_email: row.get(7)?,
-                    is_unread: row.get(8)?,
-                })
-            })?
+            .query_map(rusqlite::params_from_iter(params.iter()), thread_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(threads)
@@ -1506,71 +2562,131 @@ impl MailStore for SqliteMailStore {
         limit: usize,
         offset: usize,
     ) -> Result<Vec<Thread>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader();
 
-        let (query, params): (&str, Vec<Box<dyn rusqlite::ToSql>>) = if let Some(id) = account_id {
-            (
-                "SELECT t.id, t.account_id, t.subject, t.snippet, t.last_message_at, t.message_count,
-                        t.sender_name, t.sender_email, t.is_unread
-                 FROM threads t
-                 INNER JOIN thread_labels tl ON t.id = tl.thread_id
-                 WHERE tl.label_id = ? AND t.account_id = ?
-                 ORDER BY tl.last_message_at DESC
-                 LIMIT ? OFFSET ?",
-                vec![
-                    Box::new(label.to_string()) as Box<dyn rusqlite::ToSql>,
-                    Box::new(id),
-                    Box::new(limit as i64),
-                    Box::new(offset as i64),
-                ],
-            )
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(label.to_string())];
+        if let Some(id) = account_id {
+            params.push(Box::new(id));
+        }
+        params.push(Box::new(limit as i64));
+        params.push(Box::new(offset as i64));
+
+        let query = format!(
+            "{} OFFSET ?",
+            label_threads_query(account_id.is_some(), false)
+        );
+        let mut stmt = conn.prepare(&query)?;
+
+        let threads = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), thread_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(threads)
+    }
+
+    fn list_threads_after(
+        &self,
+        label: Option<&str>,
+        account_id: Option<i64>,
+        after: Option<&ThreadCursor>,
+        limit: usize,
+    ) -> Result<Vec<Thread>> {
+        let conn = self.reader();
+
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        if let Some(label) = label {
+            params.push(Box::new(label.to_string()));
+        }
+        if let Some(id) = account_id {
+            params.push(Box::new(id));
+        }
+        if let Some(cursor) = after {
+            let ts = cursor.last_message_at.to_rfc3339();
+            params.push(Box::new(ts.clone()));
+            params.push(Box::new(ts));
+            params.push(Box::new(cursor.thread_id.as_str().to_string()));
+        }
+        params.push(Box::new(limit as i64));
+
+        // Label queries read the summaries on thread_labels
+        let query = if label.is_some() {
+            label_threads_query(account_id.is_some(), after.is_some())
         } else {
-            (
+            let mut query = String::from(
                 "SELECT t.id, t.account_id, t.subject, t.snippet, t.last_message_at, t.message_count,
-                        t.sender_name, t.sender_email, t.is_unread
+                        t.sender_name, t.sender_email, t.is_unread, t.participants
                  FROM threads t
-                 INNER JOIN thread_labels tl ON t.id = tl.thread_id
-                 WHERE tl.label_id = ?
-                 ORDER BY tl.last_message_at DESC
-                 LIMIT ? OFFSET ?",
-                vec![
-                    Box::new(label.to_string()) as Box<dyn rusqlite::ToSql>,
-                    Box::new(limit as i64),
-                    Box::new(offset as i64),
-                ],
-            )
+                 WHERE 1 = 1",
+            );
+            if account_id.is_some() {
+                query.push_str(" AND t.account_id = ?");
+            }
+            // Keyset condition: strictly after the cursor in (ts DESC, id ASC) order
+            if after.is_some() {
+                query.push_str(
+                    " AND (t.last_message_at < ? OR (t.last_message_at = ? AND t.id > ?))",
+                );
+            }
+            query.push_str(" ORDER BY t.last_message_at DESC, t.id ASC LIMIT ?");
+            query
         };
 
-        let mut stmt = conn.prepare(query)?;
+        let mut stmt = conn.prepare(&query)?;
 
         let threads = stmt
-            .query_map(rusqlite::params_from_iter(params.iter()), |row| {
-                let last_message_at_str: String = row.get(4)?;
-                let last_message_at = chrono::DateTime::parse_from_rfc3339(&last_message_at_str)
-                    .map(|dt| dt.with_timezone(&chrono::Utc))
-                    .unwrap_or_else(|_| chrono::Utc::now());
-
-                Ok(Thread {
-                    id: ThreadId::new(row.get::<_, String>(0)?),
-                    account_id: row.get(1)?,
-                    subject: row.get(2)?,
-                    snippet: row.get(3)?,
-                    last_message_at,
-                    message_count: row.get::<_, i64>(5)? as usize,
-                    sender_name: row.get(6)?,
-                    sender_email: row.get(7)?,
-                    is_unread: row.get(8)?,
-                })
-            })?
+            .query_map(rusqlite::params_from_iter(params.iter()), thread_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(threads)
     }
 
-    fn count_threads_for_account(&self, account_id: Option<i64>) -> Result<usize> {
-        let conn = self.conn.lock().unwrap();
-
-        let count: i64 = if let Some(id) = account_id {
+    fn list_threads_with_labels_after(
+        &self,
+        labels: &[&str],
+        excluded_labels: &[&str],
+        account_id: Option<i64>,
+        after: Option<&ThreadCursor>,
+        limit: usize,
+    ) -> Result<Vec<Thread>> {
+        let conn = self.reader();
+
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        let condition = label_set_condition(labels, excluded_labels, account_id, &mut params)?;
+        let mut query = format!(
+            "SELECT t.id, t.account_id, t.subject, t.snippet, t.last_message_at, t.message_count,
+                    t.sender_name, t.sender_email, t.is_unread, t.participants
+             FROM threads t
+             INNER JOIN thread_labels tl ON t.id = tl.thread_id
+             WHERE {}",
+            condition
+        );
+
+        if let Some(cursor) = after {
+            let ts = cursor.last_message_at.to_rfc3339();
+            query.push_str(
+                " AND (tl.last_message_at < ? OR (tl.last_message_at = ? AND tl.thread_id > ?))",
+            );
+            params.push(Box::new(ts.clone()));
+            params.push(Box::new(ts));
+            params.push(Box::new(cursor.thread_id.as_str().to_string()));
+        }
+
+        query.push_str(" ORDER BY tl.last_message_at DESC, tl.thread_id ASC LIMIT ?");
+        params.push(Box::new(limit as i64));
+
+        let mut stmt = conn.prepare(&query)?;
+
+        let threads = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), thread_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(threads)
+    }
+
+    fn count_threads_for_account(&self, account_id: Option<i64>) -> Result<usize> {
+        let conn = self.reader();
+
+        let count: i64 = if let Some(id) = account_id {
             conn.query_row(
                 "SELECT COUNT(*) FROM threads WHERE account_id = ?",
                 [id],
@@ -1588,7 +2704,7 @@ impl MailStore for SqliteMailStore {
         label: &str,
         account_id: Option<i64>,
     ) -> Result<usize> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader();
 
         let count: i64 = if let Some(id) = account_id {
             conn.query_row(
@@ -1614,7 +2730,7 @@ impl MailStore for SqliteMailStore {
         label: &str,
         account_id: Option<i64>,
     ) -> Result<usize> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader();
 
         let count: i64 = if let Some(id) = account_id {
             conn.query_row(
@@ -1637,331 +2753,2432 @@ impl MailStore for SqliteMailStore {
         Ok(count as usize)
     }
 
-    fn clear_account_data(&self, account_id: i64) -> Result<()> {
-        let mut conn = self.conn.lock().unwrap();
-        let tx = conn.transaction()?;
-
-        // Delete in dependency order (keep account record)
-        tx.execute(
-            "DELETE FROM pending_message_labels WHERE message_id IN
-             (SELECT id FROM pending_messages WHERE account_id = ?)",
-            [account_id],
-        )?;
-        tx.execute(
-            "DELETE FROM pending_messages WHERE account_id = ?",
-            [account_id],
-        )?;
-        tx.execute(
-            "DELETE FROM thread_labels WHERE account_id = ?",
-            [account_id],
-        )?;
-        tx.execute(
-            "DELETE FROM message_labels WHERE message_id IN
-             (SELECT id FROM messages WHERE account_id = ?)",
-            [account_id],
-        )?;
-        tx.execute(
-            "DELETE FROM message_recipients WHERE message_id IN
-             (SELECT id FROM messages WHERE account_id = ?)",
-            [account_id],
+    fn count_unread_threads_per_label(
+        &self,
+        account_id: Option<i64>,
+    ) -> Result<HashMap<String, usize>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(
+            "SELECT tl.label_id, COUNT(*) FROM thread_labels tl
+             INNER JOIN threads t ON tl.thread_id = t.id
+             WHERE t.is_unread = 1 AND (?1 IS NULL OR t.account_id = ?1)
+             GROUP BY tl.label_id",
         )?;
-        tx.execute("DELETE FROM messages WHERE account_id = ?", [account_id])?;
-        tx.execute("DELETE FROM threads WHERE account_id = ?", [account_id])?;
-        tx.execute("DELETE FROM sync_state WHERE account_id = ?", [account_id])?;
-
-        tx.commit()?;
-        Ok(())
+        let counts = stmt
+            .query_map([account_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(counts)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::storage::blob_file::FileBlobStore;
-    use chrono::Utc;
-    use tempfile::tempdir;
+    fn count_threads_with_labels(
+        &self,
+        labels: &[&str],
+        excluded_labels: &[&str],
+        account_id: Option<i64>,
+    ) -> Result<usize> {
+        let conn = self.reader();
 
-    fn create_test_store() -> (SqliteMailStore, tempfile::TempDir) {
-        let dir = tempdir().unwrap();
-        // Use .test.sqlite extension to clearly distinguish from production databases
-        let db_path = dir.path().join("mail.test.sqlite");
-        let blob_path = dir.path().join("blobs.test");
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        let condition = label_set_condition(labels, excluded_labels, account_id, &mut params)?;
+        let count: i64 = conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM thread_labels tl
+                 INNER JOIN threads t ON tl.thread_id = t.id
+                 WHERE {}",
+                condition
+            ),
+            rusqlite::params_from_iter(params.iter()),
+            |row| row.get(0),
+        )?;
 
-        let blob_store = Box::new(FileBlobStore::new(&blob_path).unwrap());
-        let store = SqliteMailStore::new(&db_path, blob_store).unwrap();
+        Ok(count as usize)
+    }
 
-        // Register a test account so threads/messages can reference it
-        let test_account = Account {
-            id: 0, // Will be assigned by database
-            email: "test@example.com".to_string(),
-            display_name: Some("Test User".to_string()),
-            avatar_color: "#3B82F6".to_string(),
-            is_primary: true,
-            added_at: Utc::now(),
-            token_data: None,
-        };
-        store.register_account(test_account).unwrap();
+    fn count_unread_threads_with_labels(
+        &self,
+        labels: &[&str],
+        excluded_labels: &[&str],
+        account_id: Option<i64>,
+    ) -> Result<usize> {
+        let conn = self.reader();
 
-        (store, dir)
-    }
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        let condition = label_set_condition(labels, excluded_labels, account_id, &mut params)?;
+        let count: i64 = conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM thread_labels tl
+                 INNER JOIN threads t ON tl.thread_id = t.id
+                 WHERE t.is_unread = 1 AND {}",
+                condition
+            ),
+            rusqlite::params_from_iter(params.iter()),
+            |row| row.get(0),
+        )?;
 
-    fn make_test_thread(id: &str, subject: &str) -> Thread {
-        Thread::new(
-            ThreadId::new(id),
-            1, // account_id
-            subject.to_string(),
-            "Test snippet".to_string(),
-            Utc::now(),
-            1,
-            Some("Test User".to_string()),
-            "test@example.com".to_string(),
-            false,
-        )
+        Ok(count as usize)
     }
 
-    fn make_test_message(id: &str, thread_id: &str) -> Message {
-        Message::builder(MessageId::new(id), ThreadId::new(thread_id))
-            .account_id(1)
-            .from(EmailAddress::new("test@example.com"))
-            .subject("Test")
-            .body_preview("Test preview")
-            .body_text(Some("Test body text".to_string()))
-            .body_html(Some("<p>Test body HTML</p>".to_string()))
-            .label_ids(vec!["INBOX".to_string(), "UNREAD".to_string()])
-            .build()
+    fn clear_account_data(&self, account_id: i64) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let message_ids = delete_account_rows(&tx, account_id)?;
+        tx.commit()?;
+        drop(conn);
+
+        self.finish_account_clear(account_id, &message_ids)
     }
 
-    #[test]
-    fn test_thread_crud() {
-        let (store, _dir) = create_test_store();
+    fn save_search(&self, search: SavedSearch) -> Result<SavedSearch> {
+        let conn = self.conn.lock().unwrap();
 
-        let thread = make_test_thread("t1", "Test Thread");
-        store.upsert_thread(thread.clone()).unwrap();
+        if search.id == 0 {
+            conn.execute(
+                "INSERT INTO saved_searches (name, query, pinned, created_at) VALUES (?, ?, ?, ?)",
+                params![
+                    search.name,
+                    search.query,
+                    search.pinned,
+                    search.created_at.to_rfc3339(),
+                ],
+            )?;
+            let id = conn.last_insert_rowid();
+            return Ok(SavedSearch { id, ..search });
+        }
 
-        let retrieved = store.get_thread(&ThreadId::new("t1")).unwrap().unwrap();
-        assert_eq!(retrieved.subject, "Test Thread");
-        assert!(store.has_thread(&ThreadId::new("t1")).unwrap());
-        assert!(!store.has_thread(&ThreadId::new("t2")).unwrap());
+        let updated = conn.execute(
+            "UPDATE saved_searches SET name = ?, query = ?, pinned = ? WHERE id = ?",
+            params![search.name, search.query, search.pinned, search.id],
+        )?;
+        if updated == 0 {
+            return Err(MailError::NotFound(format!("Saved search {}", search.id)).into());
+        }
+        Ok(search)
     }
 
-    #[test]
-    fn test_message_crud() {
-        let (store, _dir) = create_test_store();
-
-        let thread = make_test_thread("t1", "Test Thread");
-        store.upsert_thread(thread).unwrap();
-
-        let message = make_test_message("m1", "t1");
-        store.upsert_message(message).unwrap();
+    fn list_saved_searches(&self) -> Result<Vec<SavedSearch>> {
+        let conn = self.conn.lock().unwrap();
 
-        // Test get_message (with body)
-        let retrieved = store.get_message(&MessageId::new("m1")).unwrap().unwrap();
-        assert_eq!(retrieved.subject, "Test");
-        assert_eq!(retrieved.body_text, Some("Test body text".to_string()));
-        assert_eq!(
-            retrieved.body_html,
-            Some("<p>Test body HTML</p>".to_string())
-        );
+        let mut stmt = conn.prepare(
+            "SELECT id, name, query, pinned, created_at
+             FROM saved_searches ORDER BY pinned DESC, name COLLATE NOCASE ASC",
+        )?;
 
-        // Test get_message_metadata (without body)
-        let metadata = store
-            .get_message_metadata(&MessageId::new("m1"))
-            .unwrap()
-            .unwrap();
-        assert_eq!(metadata.subject, "Test");
-        assert!(metadata.has_body_text);
-        assert!(metadata.has_body_html);
+        let searches = stmt
+            .query_map([], |row| {
+                let created_at_str: String = row.get(4)?;
+                let created_at = chrono::DateTime::parse_from_rfc3339(&created_at_str)
+                    .map(|dt| dt.with_timezone(&chrono::Utc))
+                    .unwrap_or_else(|_| chrono::Utc::now());
 
-        // Test get_message_body
-        let body = store
-            .get_message_body(&MessageId::new("m1"))
-            .unwrap()
-            .unwrap();
-        assert_eq!(body.text, Some("Test body text".to_string()));
-        assert_eq!(body.html, Some("<p>Test body HTML</p>".to_string()));
+                Ok(SavedSearch {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    query: row.get(2)?,
+                    pinned: row.get(3)?,
+                    created_at,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
 
-        assert!(store.has_message(&MessageId::new("m1")).unwrap());
-        assert!(!store.has_message(&MessageId::new("m2")).unwrap());
+        Ok(searches)
     }
 
-    #[test]
-    fn test_list_threads() {
-        let (store, _dir) = create_test_store();
+    fn delete_saved_search(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM saved_searches WHERE id = ?", [id])?;
+        Ok(())
+    }
 
-        for i in 0..5 {
-            let thread = make_test_thread(&format!("t{}", i), &format!("Thread {}", i));
-            store.upsert_thread(thread).unwrap();
+    fn save_rule(&self, rule: Rule) -> Result<Rule> {
+        let conn = self.conn.lock().unwrap();
+        let predicates = serde_json::to_string(&rule.predicates)?;
+        let actions = serde_json::to_string(&rule.actions)?;
+
+        if rule.id == 0 {
+            conn.execute(
+                "INSERT INTO rules (account_id, name, predicates, actions, enabled, created_at,
+                                    gmail_filter_id)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    rule.account_id,
+                    rule.name,
+                    predicates,
+                    actions,
+                    rule.enabled,
+                    rule.created_at.to_rfc3339(),
+                    rule.gmail_filter_id,
+                ],
+            )?;
+            let id = conn.last_insert_rowid();
+            return Ok(Rule { id, ..rule });
         }
 
-        let threads = store.list_threads(10, 0).unwrap();
-        assert_eq!(threads.len(), 5);
+        let updated = conn.execute(
+            "UPDATE rules SET account_id = ?, name = ?, predicates = ?, actions = ?, enabled = ?,
+                              gmail_filter_id = ?
+             WHERE id = ?",
+            params![
+                rule.account_id,
+                rule.name,
+                predicates,
+                actions,
+                rule.enabled,
+                rule.gmail_filter_id,
+                rule.id
+            ],
+        )?;
+        if updated == 0 {
+            return Err(MailError::NotFound(format!("Rule {}", rule.id)).into());
+        }
+        Ok(rule)
+    }
 
-        let threads = store.list_threads(2, 0).unwrap();
-        assert_eq!(threads.len(), 2);
+    fn list_rules(&self) -> Result<Vec<Rule>> {
+        let conn = self.conn.lock().unwrap();
 
-        let threads = store.list_threads(10, 3).unwrap();
-        assert_eq!(threads.len(), 2);
-    }
+        let mut stmt = conn.prepare(
+            "SELECT id, account_id, name, predicates, actions, enabled, created_at, gmail_filter_id
+             FROM rules ORDER BY id ASC",
+        )?;
 
-    #[test]
-    fn test_list_threads_by_label() {
-        let (store, _dir) = create_test_store();
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, Option<i64>>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, bool>(5)?,
+                    row.get::<_, String>(6)?,
+                    row.get::<_, Option<String>>(7)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
 
-        let thread = make_test_thread("t1", "Test Thread");
-        store.upsert_thread(thread).unwrap();
+        let mut rules = Vec::with_capacity(rows.len());
+        for row in rows {
+            let (id, account_id, name, predicates, actions, enabled, created_at, gmail_filter_id) =
+                row;
+            let created_at = chrono::DateTime::parse_from_rfc3339(&created_at)
+                .map(|dt| dt.with_timezone(&chrono::Utc))
+                .unwrap_or_else(|_| chrono::Utc::now());
+            rules.push(Rule {
+                id,
+                account_id,
+                name,
+                predicates: serde_json::from_str(&predicates)
+                    .with_context(|| format!("Invalid predicates for rule {}", id))?,
+                actions: serde_json::from_str(&actions)
+                    .with_context(|| format!("Invalid actions for rule {}", id))?,
+                enabled,
+                created_at,
+                gmail_filter_id,
+            });
+        }
 
-        let message = make_test_message("m1", "t1");
-        store.upsert_message(message).unwrap();
+        Ok(rules)
+    }
 
-        let threads = store.list_threads_by_label("INBOX", 10, 0).unwrap();
-        assert_eq!(threads.len(), 1);
+    fn delete_rule(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM rules WHERE id = ?", [id])?;
+        Ok(())
+    }
 
-        let threads = store.list_threads_by_label("SENT", 10, 0).unwrap();
-        assert_eq!(threads.len(), 0);
+    fn queue_action(&self, action: QueuedAction) -> Result<QueuedAction> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO queued_actions (account_id, thread_id, add_labels, remove_labels, queued_at)
+             VALUES (?, ?, ?, ?, ?)",
+            params![
+                action.account_id,
+                action.thread_id.as_str(),
+                serde_json::to_string(&action.add_labels)?,
+                serde_json::to_string(&action.remove_labels)?,
+                action.queued_at.to_rfc3339(),
+            ],
+        )?;
+        let id = conn.last_insert_rowid();
+        Ok(QueuedAction { id, ..action })
     }
 
-    #[test]
-    fn test_sync_state() {
-        let (store, _dir) = create_test_store();
+    fn list_queued_actions(&self, account_id: i64) -> Result<Vec<QueuedAction>> {
+        let conn = self.conn.lock().unwrap();
 
-        assert!(store.get_sync_state(1).unwrap().is_none());
+        let mut stmt = conn.prepare(
+            "SELECT id, thread_id, add_labels, remove_labels, queued_at
+             FROM queued_actions WHERE account_id = ? ORDER BY id ASC",
+        )?;
 
-        let state = SyncState::new(1, "12345");
-        store.save_sync_state(state).unwrap();
+        let rows = stmt
+            .query_map([account_id], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
 
-        let retrieved = store.get_sync_state(1).unwrap().unwrap();
-        assert_eq!(retrieved.history_id, "12345");
+        let mut actions = Vec::with_capacity(rows.len());
+        for (id, thread_id, add_labels, remove_labels, queued_at) in rows {
+            let queued_at = chrono::DateTime::parse_from_rfc3339(&queued_at)
+                .map(|dt| dt.with_timezone(&chrono::Utc))
+                .unwrap_or_else(|_| chrono::Utc::now());
+            actions.push(QueuedAction {
+                id,
+                account_id,
+                thread_id: ThreadId::new(thread_id),
+                add_labels: serde_json::from_str(&add_labels)
+                    .with_context(|| format!("Invalid labels for queued action {}", id))?,
+                remove_labels: serde_json::from_str(&remove_labels)
+                    .with_context(|| format!("Invalid labels for queued action {}", id))?,
+                queued_at,
+            });
+        }
 
-        store.delete_sync_state(1).unwrap();
-        assert!(store.get_sync_state(1).unwrap().is_none());
+        Ok(actions)
     }
 
-    #[test]
+    fn delete_queued_action(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM queued_actions WHERE id = ?", [id])?;
+        Ok(())
+    }
+
+    fn add_outbox_message(&self, message: OutboxMessage) -> Result<OutboxMessage> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO outbox (account_id, thread_id, draft_id, subject, recipients, raw,
+                                 tracking_token, state, attempts, last_error, next_attempt_at,
+                                 sent_message_id, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                message.account_id,
+                message.thread_id.as_ref().map(|id| id.as_str()),
+                message.draft_id,
+                message.subject,
+                serde_json::to_string(&message.recipients)?,
+                message.raw,
+                message.tracking_token,
+                message.state.as_str(),
+                message.attempts,
+                message.last_error,
+                message.next_attempt_at.to_rfc3339(),
+                message.sent_message_id,
+                message.created_at.to_rfc3339(),
+            ],
+        )?;
+        let id = conn.last_insert_rowid();
+        Ok(OutboxMessage { id, ..message })
+    }
+
+    fn update_outbox_message(&self, message: &OutboxMessage) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE outbox SET state = ?, attempts = ?, last_error = ?, next_attempt_at = ?,
+                               sent_message_id = ?
+             WHERE id = ?",
+            params![
+                message.state.as_str(),
+                message.attempts,
+                message.last_error,
+                message.next_attempt_at.to_rfc3339(),
+                message.sent_message_id,
+                message.id,
+            ],
+        )?;
+        if updated == 0 {
+            return Err(MailError::NotFound(format!("Outbox message {}", message.id)).into());
+        }
+        Ok(())
+    }
+
+    fn get_outbox_message(&self, id: i64) -> Result<Option<OutboxMessage>> {
+        let conn = self.reader();
+        let message = conn
+            .query_row(
+                &format!("SELECT {} FROM outbox WHERE id = ?", OUTBOX_COLUMNS),
+                [id],
+                outbox_message_from_row,
+            )
+            .optional()?;
+        Ok(message)
+    }
+
+    fn list_outbox_messages(&self, account_id: Option<i64>) -> Result<Vec<OutboxMessage>> {
+        let conn = self.reader();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM outbox WHERE ?1 IS NULL OR account_id = ?1 ORDER BY id ASC",
+            OUTBOX_COLUMNS
+        ))?;
+        let messages = stmt
+            .query_map([account_id], outbox_message_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(messages)
+    }
+
+    fn delete_outbox_message(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM outbox WHERE id = ?", [id])?;
+        Ok(())
+    }
+
+    fn sender_stats(
+        &self,
+        account_id: Option<i64>,
+        ranking: SenderRanking,
+        limit: usize,
+    ) -> Result<Vec<SenderStats>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        // With a single MAX() aggregate, SQLite takes the bare from_name
+        // column from the sender's newest message
+        let mut query = format!(
+            "SELECT lower(m.from_email) AS email, m.from_name, COUNT(*) AS message_count,
+                    COALESCE(SUM(m.size_bytes), 0) AS total_bytes, MAX(m.internal_date)
+             FROM messages m
+             WHERE {}",
+            NOT_SENT
+        );
+        if let Some(id) = account_id {
+            query.push_str(" AND m.account_id = ?");
+            params.push(Box::new(id));
+        }
+        let order = match ranking {
+            SenderRanking::Volume => "message_count DESC, total_bytes DESC",
+            SenderRanking::Size => "total_bytes DESC, message_count DESC",
+        };
+        query.push_str(&format!(
+            " GROUP BY lower(m.from_email) ORDER BY {}, email ASC LIMIT ?",
+            order
+        ));
+        params.push(Box::new(limit as i64));
+
+        let mut stmt = conn.prepare(&query)?;
+        let senders = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |row| {
+                let message_count: i64 = row.get(2)?;
+                let newest: i64 = row.get(4)?;
+                Ok(SenderStats {
+                    email: row.get(0)?,
+                    name: row.get(1)?,
+                    message_count: message_count as usize,
+                    total_bytes: row.get(3)?,
+                    last_received_at: chrono::DateTime::from_timestamp_millis(newest)
+                        .unwrap_or_default(),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(senders)
+    }
+
+    fn daily_message_counts(
+        &self,
+        account_id: Option<i64>,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<DailyVolume>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(since.timestamp_millis())];
+        let mut query = format!(
+            "SELECT date(m.internal_date / 1000, 'unixepoch') AS day, COUNT(*)
+             FROM messages m
+             WHERE m.internal_date >= ? AND {}",
+            NOT_SENT
+        );
+        if let Some(id) = account_id {
+            query.push_str(" AND m.account_id = ?");
+            params.push(Box::new(id));
+        }
+        query.push_str(" GROUP BY day ORDER BY day ASC");
+
+        let mut stmt = conn.prepare(&query)?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        rows.into_iter()
+            .map(|(day, count)| -> Result<DailyVolume> {
+                Ok(DailyVolume {
+                    date: chrono::NaiveDate::parse_from_str(&day, "%Y-%m-%d")?,
+                    message_count: count as usize,
+                })
+            })
+            .collect()
+    }
+
+    fn reply_delays_ms(
+        &self,
+        account_id: Option<i64>,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<i64>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(since.timestamp_millis())];
+        let account_filter = match account_id {
+            Some(id) => {
+                params.push(Box::new(id));
+                " AND m.account_id = ?"
+            }
+            None => "",
+        };
+        // A sent message counts as a reply when the message right before it
+        // in the thread is also its newest preceding received message
+        let query = format!(
+            "WITH sent AS (
+                 SELECT m.thread_id, m.internal_date
+                 FROM messages m
+                 INNER JOIN message_labels l ON l.message_id = m.id AND l.label_id = 'SENT'
+                 WHERE m.internal_date >= ?{}
+             ),
+             replies AS (
+                 SELECT s.internal_date AS sent_at,
+                        (SELECT MAX(p.internal_date) FROM messages p
+                         WHERE p.thread_id = s.thread_id
+                           AND p.internal_date < s.internal_date) AS previous_at,
+                        (SELECT MAX(m.internal_date) FROM messages m
+                         WHERE m.thread_id = s.thread_id
+                           AND m.internal_date < s.internal_date
+                           AND {}) AS received_at
+                 FROM sent s
+             )
+             SELECT sent_at - received_at FROM replies
+             WHERE received_at IS NOT NULL AND received_at = previous_at",
+            account_filter, NOT_SENT
+        );
+
+        let mut stmt = conn.prepare(&query)?;
+        let delays = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
+
+        Ok(delays)
+    }
+
+    fn label_storage(&self, account_id: Option<i64>) -> Result<Vec<LabelStorage>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        let mut query = String::from(
+            "SELECT l.label_id, COUNT(*), COALESCE(SUM(m.size_bytes), 0) AS total_bytes
+             FROM message_labels l
+             INNER JOIN messages m ON m.id = l.message_id",
+        );
+        if let Some(id) = account_id {
+            query.push_str(" WHERE m.account_id = ?");
+            params.push(Box::new(id));
+        }
+        query.push_str(" GROUP BY l.label_id ORDER BY total_bytes DESC, l.label_id ASC");
+
+        let mut stmt = conn.prepare(&query)?;
+        let storage = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |row| {
+                let message_count: i64 = row.get(1)?;
+                Ok(LabelStorage {
+                    label_id: row.get(0)?,
+                    message_count: message_count as usize,
+                    total_bytes: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(storage)
+    }
+
+    fn largest_threads(
+        &self,
+        account_id: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<(ThreadId, i64)>> {
+        let conn = self.reader();
+
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        let mut query = String::from(
+            "SELECT thread_id, COALESCE(SUM(size_bytes), 0) AS total_bytes FROM messages",
+        );
+        if let Some(id) = account_id {
+            query.push_str(" WHERE account_id = ?");
+            params.push(Box::new(id));
+        }
+        query.push_str(" GROUP BY thread_id ORDER BY total_bytes DESC, thread_id ASC LIMIT ?");
+        params.push(Box::new(limit as i64));
+
+        let mut stmt = conn.prepare(&query)?;
+        let threads = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |row| {
+                Ok((ThreadId::new(row.get::<_, String>(0)?), row.get(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(threads)
+    }
+
+    fn storage_stats(&self) -> Result<StorageStats> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut content: HashMap<ContentType, ContentUsage> = HashMap::new();
+        for (content_type, column) in [
+            (ContentType::BodyText, "body_text"),
+            (ContentType::BodyHtml, "body_html"),
+        ] {
+            // Bodies stored before sizes were recorded only count towards stored bytes
+            let query = format!(
+                "SELECT COUNT(*), COALESCE(SUM(length({c})), 0), COALESCE(SUM({c}_size), 0),
+                        COALESCE(SUM(CASE WHEN {c}_size IS NOT NULL THEN length({c}) END), 0)
+                 FROM messages WHERE {c} IS NOT NULL",
+                c = column
+            );
+            let usage = conn.query_row(&query, [], |row| {
+                let count: i64 = row.get(0)?;
+                let stored_bytes: i64 = row.get(1)?;
+                let original_bytes: i64 = row.get(2)?;
+                let measured_bytes: i64 = row.get(3)?;
+                Ok(ContentUsage {
+                    count: count as usize,
+                    stored_bytes: stored_bytes as u64,
+                    original_bytes: original_bytes as u64,
+                    measured_bytes: measured_bytes as u64,
+                })
+            })?;
+            content.entry(content_type).or_default().add(usage);
+        }
+
+        let database_bytes = database_bytes(&conn)?;
+        drop(conn);
+
+        for entry in self.blob_store.list()? {
+            content.entry(entry.key.content_type).or_default().add(ContentUsage {
+                count: 1,
+                stored_bytes: entry.stored_bytes,
+                ..Default::default()
+            });
+        }
+
+        Ok(StorageStats {
+            database_bytes,
+            content,
+        })
+    }
+
+    fn compact(&self, older_than: chrono::DateTime<chrono::Utc>) -> Result<CompactionReport> {
+        let mut report = CompactionReport {
+            database_bytes_before: database_bytes(&self.conn.lock().unwrap())?,
+            ..Default::default()
+        };
+
+        // Recompress in batches so other callers can use the connection in between
+        loop {
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.transaction()?;
+
+            let rows = {
+                let mut stmt = tx.prepare(
+                    "SELECT id, body_text, body_html FROM messages
+                     WHERE internal_date < ? AND body_compression_level < ?
+                       AND (body_text IS NOT NULL OR body_html IS NOT NULL)
+                     LIMIT ?",
+                )?;
+                stmt.query_map(
+                    params![
+                        older_than.timestamp_millis(),
+                        ARCHIVE_COMPRESSION_LEVEL,
+                        COMPACT_BATCH_SIZE
+                    ],
+                    |row| {
+                        Ok((
+                            row.get::<_, String>(0)?,
+                            row.get::<_, Option<Vec<u8>>>(1)?,
+                            row.get::<_, Option<Vec<u8>>>(2)?,
+                        ))
+                    },
+                )?
+                .collect::<Result<Vec<_>, _>>()?
+            };
+            if rows.is_empty() {
+                break;
+            }
+
+            for (id, text, html) in rows {
+                let text = text.map(|data| recompress(&data)).transpose()?;
+                let html = html.map(|data| recompress(&data)).transpose()?;
+                report.recompressed += text.is_some() as usize + html.is_some() as usize;
+
+                let (text, text_size) = text.unzip();
+                let (html, html_size) = html.unzip();
+                tx.execute(
+                    "UPDATE messages SET body_text = ?, body_html = ?, body_text_size = ?,
+                        body_html_size = ?, body_compression_level = ?
+                     WHERE id = ?",
+                    params![text, html, text_size, html_size, ARCHIVE_COMPRESSION_LEVEL, id],
+                )?;
+            }
+
+            tx.commit()?;
+        }
+
+        // Remove blobs left behind by messages that no longer exist
+        for key in self.orphaned_blobs()? {
+            self.blob_store.delete(&key)?;
+            report.orphaned_blobs_removed += 1;
+        }
+
+        // VACUUM writes through the WAL; checkpoint so the file itself shrinks
+        let conn = self.conn.lock().unwrap();
+        conn.execute_batch(
+            "PRAGMA auto_vacuum = INCREMENTAL; VACUUM; PRAGMA wal_checkpoint(TRUNCATE);",
+        )?;
+        report.database_bytes_after = database_bytes(&conn)?;
+
+        Ok(report)
+    }
+
+    fn check_integrity(&self) -> Result<IntegrityReport> {
+        let blobs_without_messages = self.orphaned_blobs()?;
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT id FROM messages
+             WHERE thread_id NOT IN (SELECT id FROM threads)
+             ORDER BY id",
+        )?;
+        let messages_without_threads = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .map(|id| id.map(MessageId::new))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT thread_id FROM thread_labels
+             WHERE thread_id NOT IN (SELECT id FROM threads)
+             ORDER BY thread_id",
+        )?;
+        let thread_labels_without_threads = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .map(|id| id.map(ThreadId::new))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(IntegrityReport {
+            messages_without_threads,
+            thread_labels_without_threads,
+            blobs_without_messages,
+            ..Default::default()
+        })
+    }
+
+    fn repair(&self) -> Result<IntegrityReport> {
+        let mut report = self.check_integrity()?;
+
+        let rebuilt = rebuild_threads(self, &report.messages_without_threads)?;
+        report
+            .thread_labels_without_threads
+            .retain(|thread_id| !rebuilt.contains(thread_id));
+
+        {
+            let conn = self.conn.lock().unwrap();
+            for thread_id in &rebuilt {
+                self.update_thread_labels(&conn, thread_id.as_str())?;
+            }
+            for thread_id in &report.thread_labels_without_threads {
+                conn.execute(
+                    "DELETE FROM thread_labels WHERE thread_id = ?",
+                    [thread_id.as_str()],
+                )?;
+            }
+        }
+
+        for key in &report.blobs_without_messages {
+            self.blob_store.delete(key)?;
+        }
+
+        Ok(report)
+    }
+
+    fn record_recent_search(&self, query: &str) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "INSERT INTO recent_searches (query, searched_at) VALUES (?, ?)
+             ON CONFLICT(query) DO UPDATE SET searched_at = excluded.searched_at",
+            params![query, chrono::Utc::now().to_rfc3339()],
+        )?;
+
+        // Keep only the newest entries
+        tx.execute(
+            "DELETE FROM recent_searches WHERE query NOT IN
+             (SELECT query FROM recent_searches ORDER BY searched_at DESC LIMIT ?)",
+            [MAX_RECENT_SEARCHES as i64],
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn list_recent_searches(&self, limit: usize) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn
+            .prepare("SELECT query FROM recent_searches ORDER BY searched_at DESC LIMIT ?")?;
+        let queries = stmt
+            .query_map([limit as i64], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+
+        Ok(queries)
+    }
+
+    fn clear_recent_searches(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM recent_searches", [])?;
+        Ok(())
+    }
+
+    fn subscribe(&self) -> Receiver<StoreEvent> {
+        self.events.subscribe()
+    }
+}
+
+/// Build an Account from a row selecting `ACCOUNT_COLUMNS`
+fn account_from_row(row: &rusqlite::Row) -> rusqlite::Result<Account> {
+    let added_at_str: String = row.get(5)?;
+    let added_at = chrono::DateTime::parse_from_rfc3339(&added_at_str)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .unwrap_or_else(|_| chrono::Utc::now());
+    let signature_text: Option<String> = row.get(7)?;
+    let signature_html: Option<String> = row.get(8)?;
+
+    Ok(Account {
+        id: row.get(0)?,
+        email: row.get(1)?,
+        display_name: row.get(2)?,
+        avatar_color: row.get(3)?,
+        is_primary: row.get(4)?,
+        added_at,
+        token_data: row.get(6)?,
+        signature: signature_text.map(|text| Signature {
+            text,
+            html: signature_html,
+        }),
+        sync_enabled: row.get(9)?,
+    })
+}
+
+/// Build an OutboxMessage from a row selecting `OUTBOX_COLUMNS`
+fn outbox_message_from_row(row: &rusqlite::Row) -> rusqlite::Result<OutboxMessage> {
+    let parse = |s: String| {
+        chrono::DateTime::parse_from_rfc3339(&s)
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .unwrap_or_else(|_| chrono::Utc::now())
+    };
+    let recipients_json: String = row.get(5)?;
+    let state: String = row.get(8)?;
+
+    Ok(OutboxMessage {
+        id: row.get(0)?,
+        account_id: row.get(1)?,
+        thread_id: row.get::<_, Option<String>>(2)?.map(ThreadId::new),
+        draft_id: row.get(3)?,
+        subject: row.get(4)?,
+        recipients: serde_json::from_str(&recipients_json).unwrap_or_default(),
+        raw: row.get(6)?,
+        tracking_token: row.get(7)?,
+        state: OutboxState::parse(&state),
+        attempts: row.get(9)?,
+        last_error: row.get(10)?,
+        next_attempt_at: parse(row.get(11)?),
+        sent_message_id: row.get(12)?,
+        created_at: parse(row.get(13)?),
+    })
+}
+
+/// Build a Thread from a row selecting `THREAD_COLUMNS`
+fn thread_from_row(row: &rusqlite::Row) -> rusqlite::Result<Thread> {
+    let last_message_at_str: String = row.get(4)?;
+    let last_message_at = chrono::DateTime::parse_from_rfc3339(&last_message_at_str)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .unwrap_or_else(|_| chrono::Utc::now());
+    let participants: String = row.get(9)?;
+
+    Ok(Thread {
+        id: ThreadId::new(row.get::<_, String>(0)?),
+        account_id: row.get(1)?,
+        subject: row.get(2)?,
+        snippet: row.get(3)?,
+        last_message_at,
+        message_count: row.get::<_, i64>(5)? as usize,
+        sender_name: row.get(6)?,
+        sender_email: row.get(7)?,
+        is_unread: row.get(8)?,
+        participants: serde_json::from_str(&participants).unwrap_or_default(),
+    })
+}
+
+/// Build a Label from a row selecting `LABEL_COLUMNS`
+///
+/// Counts are left at zero.
+fn label_from_row(row: &rusqlite::Row) -> rusqlite::Result<Label> {
+    let background: Option<String> = row.get(3)?;
+    let text: Option<String> = row.get(4)?;
+    let mut label = Label::new(row.get::<_, String>(0)?, row.get::<_, String>(1)?)
+        .with_color(background.zip(text).map(|(bg, text)| LabelColor::new(bg, text)));
+    label.icon = row.get(2)?;
+    Ok(label)
+}
+
+/// Build MessageMetadata from a row selecting `MESSAGE_COLUMNS`
+///
+/// Recipients and labels live in their own tables and are left empty.
+fn message_metadata_from_row(row: &rusqlite::Row) -> rusqlite::Result<MessageMetadata> {
+    let received_at_str: String = row.get(7)?;
+    let received_at = chrono::DateTime::parse_from_rfc3339(&received_at_str)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .unwrap_or_else(|_| chrono::Utc::now());
+    let attachment_names_json: String = row.get(12)?;
+    let unsubscribe_json: Option<String> = row.get(14)?;
+    let auth_results_json: Option<String> = row.get(15)?;
+    let invite_json: Option<String> = row.get(16)?;
+
+    Ok(MessageMetadata {
+        id: MessageId::new(row.get::<_, String>(0)?),
+        thread_id: ThreadId::new(row.get::<_, String>(1)?),
+        account_id: row.get(2)?,
+        from: EmailAddress {
+            name: row.get(3)?,
+            email: row.get(4)?,
+        },
+        to: Vec::new(),
+        cc: Vec::new(),
+        subject: row.get(5)?,
+        body_preview: row.get(6)?,
+        received_at,
+        internal_date: row.get(8)?,
+        label_ids: Vec::new(),
+        has_body_text: row.get(9)?,
+        has_body_html: row.get(10)?,
+        has_attachments: row.get(11)?,
+        attachment_names: serde_json::from_str(&attachment_names_json).unwrap_or_default(),
+        size_bytes: row.get(13)?,
+        unsubscribe: unsubscribe_json.and_then(|json| serde_json::from_str(&json).ok()),
+        auth_results: auth_results_json.and_then(|json| serde_json::from_str(&json).ok()),
+        invite: invite_json.and_then(|json| serde_json::from_str(&json).ok()),
+        lang: row.get(17)?,
+        rfc_message_id: row.get(18)?,
+    })
+}
+
+/// Delete an account's mail, pending queue and sync state, keeping the
+/// account record
+///
+/// Notes, summaries and other per-thread or per-message state go with their
+/// rows by cascade. Returns the IDs of the deleted messages, whose blobs the
+/// caller removes once the deletes commit.
+fn delete_account_rows(conn: &Connection, account_id: i64) -> Result<Vec<String>> {
+    let message_ids = {
+        let mut stmt = conn.prepare("SELECT id FROM messages WHERE account_id = ?")?;
+        stmt.query_map([account_id], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    // Delete in dependency order (due to foreign keys)
+    conn.execute(
+        "DELETE FROM pending_message_labels WHERE message_id IN
+         (SELECT id FROM pending_messages WHERE account_id = ?)",
+        [account_id],
+    )?;
+    conn.execute(
+        "DELETE FROM pending_messages WHERE account_id = ?",
+        [account_id],
+    )?;
+    conn.execute(
+        "DELETE FROM thread_labels WHERE account_id = ?",
+        [account_id],
+    )?;
+    conn.execute(
+        "DELETE FROM message_labels WHERE message_id IN
+         (SELECT id FROM messages WHERE account_id = ?)",
+        [account_id],
+    )?;
+    conn.execute(
+        "DELETE FROM message_recipients WHERE message_id IN
+         (SELECT id FROM messages WHERE account_id = ?)",
+        [account_id],
+    )?;
+    conn.execute("DELETE FROM messages WHERE account_id = ?", [account_id])?;
+    conn.execute("DELETE FROM threads WHERE account_id = ?", [account_id])?;
+    conn.execute("DELETE FROM sync_state WHERE account_id = ?", [account_id])?;
+
+    Ok(message_ids)
+}
+
+/// Size of the database in bytes
+fn database_bytes(conn: &Connection) -> Result<u64> {
+    let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    Ok((page_count * page_size) as u64)
+}
+
+/// Recompress a zstd body at the archive level, returning it with its original size
+fn recompress(data: &[u8]) -> Result<(Vec<u8>, i64)> {
+    let original = zstd::decode_all(data).context("Failed to decompress body")?;
+    let compressed = zstd::encode_all(original.as_slice(), ARCHIVE_COMPRESSION_LEVEL)
+        .context("Failed to recompress body")?;
+    Ok((compressed, original.len() as i64))
+}
+
+/// Query for threads with a label, newest first
+///
+/// Reads the thread summaries copied onto `thread_labels` in index order,
+/// without touching `threads`. Binds the label, then the account id if
+/// `for_account`, then the cursor's timestamp twice and thread id if
+/// `after_cursor`, then the limit.
+fn label_threads_query(for_account: bool, after_cursor: bool) -> String {
+    let mut query = String::from(
+        "SELECT tl.thread_id, tl.account_id, tl.subject, tl.snippet, tl.last_message_at,
+                tl.message_count, tl.sender_name, tl.sender_email, tl.is_unread, tl.participants
+         FROM thread_labels tl
+         WHERE tl.label_id = ?",
+    );
+    if for_account {
+        query.push_str(" AND tl.account_id = ?");
+    }
+    // Keyset condition: strictly after the cursor in (ts DESC, id ASC) order
+    if after_cursor {
+        query.push_str(
+            " AND (tl.last_message_at < ? OR (tl.last_message_at = ? AND tl.thread_id > ?))",
+        );
+    }
+    query.push_str(" ORDER BY tl.last_message_at DESC, tl.thread_id ASC LIMIT ?");
+    query
+}
+
+/// Filter for threads joined to `thread_labels tl` on the first of `labels`
+///
+/// The remaining labels must also be present and none of `excluded_labels`
+/// may be. Parameters are appended in placeholder order.
+fn label_set_condition(
+    labels: &[&str],
+    excluded_labels: &[&str],
+    account_id: Option<i64>,
+    params: &mut Vec<Box<dyn rusqlite::ToSql>>,
+) -> Result<String> {
+    let Some((first, rest)) = labels.split_first() else {
+        anyhow::bail!("At least one label is required");
+    };
+
+    let mut condition = String::from("tl.label_id = ?");
+    params.push(Box::new(first.to_string()));
+
+    for label in rest {
+        condition.push_str(
+            " AND EXISTS (SELECT 1 FROM thread_labels x
+                          WHERE x.thread_id = t.id AND x.label_id = ?)",
+        );
+        params.push(Box::new(label.to_string()));
+    }
+
+    if !excluded_labels.is_empty() {
+        let placeholders = vec!["?"; excluded_labels.len()].join(", ");
+        condition.push_str(&format!(
+            " AND NOT EXISTS (SELECT 1 FROM thread_labels x
+                              WHERE x.thread_id = t.id AND x.label_id IN ({}))",
+            placeholders
+        ));
+        for label in excluded_labels {
+            params.push(Box::new(label.to_string()));
+        }
+    }
+
+    if let Some(id) = account_id {
+        condition.push_str(" AND t.account_id = ?");
+        params.push(Box::new(id));
+    }
+
+    Ok(condition)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AuthResults, AuthVerdict, RuleAction, RulePredicate, Unsubscribe};
+    use crate::storage::blob::BlobKey;
+    use crate::storage::blob_file::FileBlobStore;
+    use chrono::Utc;
+    use tempfile::tempdir;
+
+    fn create_test_store() -> (SqliteMailStore, tempfile::TempDir) {
+        let dir = tempdir().unwrap();
+        // Use .test.sqlite extension to clearly distinguish from production databases
+        let db_path = dir.path().join("mail.test.sqlite");
+        let blob_path = dir.path().join("blobs.test");
+
+        let blob_store = Box::new(FileBlobStore::new(&blob_path).unwrap());
+        let store = SqliteMailStore::new(&db_path, blob_store).unwrap();
+
+        // Register a test account so threads/messages can reference it
+        let test_account = Account {
+            id: 0, // Will be assigned by database
+            email: "test@example.com".to_string(),
+            display_name: Some("Test User".to_string()),
+            avatar_color: "#3B82F6".to_string(),
+            is_primary: true,
+            added_at: Utc::now(),
+            token_data: None,
+            signature: None,
+            sync_enabled: true,
+        };
+        store.register_account(test_account).unwrap();
+
+        (store, dir)
+    }
+
+    fn make_test_thread(id: &str, subject: &str) -> Thread {
+        Thread::new(
+            ThreadId::new(id),
+            1, // account_id
+            subject.to_string(),
+            "Test snippet".to_string(),
+            Utc::now(),
+            1,
+            Some("Test User".to_string()),
+            "test@example.com".to_string(),
+            false,
+        )
+    }
+
+    fn make_test_message(id: &str, thread_id: &str) -> Message {
+        Message::builder(MessageId::new(id), ThreadId::new(thread_id))
+            .account_id(1)
+            .from(EmailAddress::new("test@example.com"))
+            .subject("Test")
+            .body_preview("Test preview")
+            .body_text(Some("Test body text".to_string()))
+            .body_html(Some("<p>Test body HTML</p>".to_string()))
+            .label_ids(vec!["INBOX".to_string(), "UNREAD".to_string()])
+            .build()
+    }
+
+    #[test]
+    fn test_thread_crud() {
+        let (store, _dir) = create_test_store();
+
+        let thread = make_test_thread("t1", "Test Thread");
+        store.upsert_thread(thread.clone()).unwrap();
+
+        let retrieved = store.get_thread(&ThreadId::new("t1")).unwrap().unwrap();
+        assert_eq!(retrieved.subject, "Test Thread");
+        assert!(store.has_thread(&ThreadId::new("t1")).unwrap());
+        assert!(!store.has_thread(&ThreadId::new("t2")).unwrap());
+    }
+
+    #[test]
+    fn test_message_crud() {
+        let (store, _dir) = create_test_store();
+
+        let thread = make_test_thread("t1", "Test Thread");
+        store.upsert_thread(thread).unwrap();
+
+        let message = make_test_message("m1", "t1");
+        store.upsert_message(message).unwrap();
+
+        // Test get_message (with body)
+        let retrieved = store.get_message(&MessageId::new("m1")).unwrap().unwrap();
+        assert_eq!(retrieved.subject, "Test");
+        assert_eq!(retrieved.body_text, Some("Test body text".to_string()));
+        assert_eq!(
+            retrieved.body_html,
+            Some("<p>Test body HTML</p>".to_string())
+        );
+
+        // Test get_message_metadata (without body)
+        let metadata = store
+            .get_message_metadata(&MessageId::new("m1"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.subject, "Test");
+        assert!(metadata.has_body_text);
+        assert!(metadata.has_body_html);
+
+        // Test get_message_body
+        let body = store
+            .get_message_body(&MessageId::new("m1"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(body.text, Some("Test body text".to_string()));
+        assert_eq!(body.html, Some("<p>Test body HTML</p>".to_string()));
+
+        assert!(store.has_message(&MessageId::new("m1")).unwrap());
+        assert!(!store.has_message(&MessageId::new("m2")).unwrap());
+    }
+
+    #[test]
+    fn test_list_threads() {
+        let (store, _dir) = create_test_store();
+
+        for i in 0..5 {
+            let thread = make_test_thread(&format!("t{}", i), &format!("Thread {}", i));
+            store.upsert_thread(thread).unwrap();
+        }
+
+        let threads = store.list_threads(10, 0).unwrap();
+        assert_eq!(threads.len(), 5);
+
+        let threads = store.list_threads(2, 0).unwrap();
+        assert_eq!(threads.len(), 2);
+
+        let threads = store.list_threads(10, 3).unwrap();
+        assert_eq!(threads.len(), 2);
+    }
+
+    #[test]
+    fn test_list_threads_by_label() {
+        let (store, _dir) = create_test_store();
+
+        let thread = make_test_thread("t1", "Test Thread");
+        store.upsert_thread(thread).unwrap();
+
+        let message = make_test_message("m1", "t1");
+        store.upsert_message(message).unwrap();
+
+        let threads = store.list_threads_by_label("INBOX", 10, 0).unwrap();
+        assert_eq!(threads.len(), 1);
+
+        let threads = store.list_threads_by_label("SENT", 10, 0).unwrap();
+        assert_eq!(threads.len(), 0);
+    }
+
+    #[test]
+    fn test_threads_with_label_set() {
+        let (store, _dir) = create_test_store();
+
+        for (id, category) in [("t1", None), ("t2", Some("CATEGORY_PROMOTIONS"))] {
+            let mut thread = make_test_thread(id, "Test Thread");
+            thread.is_unread = true;
+            store.upsert_thread(thread).unwrap();
+            let mut message = make_test_message(&format!("m_{}", id), id);
+            message.label_ids.extend(category.map(String::from));
+            store.upsert_message(message).unwrap();
+        }
+
+        let promotions = ["CATEGORY_PROMOTIONS"];
+        let threads = store
+            .list_threads_with_labels_after(&["INBOX"], &promotions, Some(1), None, 10)
+            .unwrap();
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].id.as_str(), "t1");
+
+        let threads = store
+            .list_threads_with_labels_after(&["CATEGORY_PROMOTIONS", "INBOX"], &[], None, None, 10)
+            .unwrap();
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].id.as_str(), "t2");
+
+        assert_eq!(store.count_unread_threads_with_labels(&["INBOX"], &[], None).unwrap(), 2);
+        assert_eq!(
+            store
+                .count_unread_threads_with_labels(&["INBOX"], &promotions, Some(2))
+                .unwrap(),
+            0
+        );
+        assert!(store.count_unread_threads_with_labels(&[], &[], None).is_err());
+
+        assert_eq!(store.count_threads_with_labels(&["INBOX"], &[], None).unwrap(), 2);
+        assert_eq!(
+            store
+                .count_threads_with_labels(&["CATEGORY_PROMOTIONS", "INBOX"], &[], Some(1))
+                .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_count_unread_threads_per_label() {
+        let (store, _dir) = create_test_store();
+
+        for (id, unread) in [("t1", true), ("t2", true), ("t3", false)] {
+            let mut thread = make_test_thread(id, "Test Thread");
+            thread.is_unread = unread;
+            store.upsert_thread(thread).unwrap();
+            let mut message = make_test_message(&format!("m_{}", id), id);
+            if id == "t2" {
+                message.label_ids.push("Label_1".to_string());
+            }
+            store.upsert_message(message).unwrap();
+        }
+
+        let counts = store.count_unread_threads_per_label(Some(1)).unwrap();
+        assert_eq!(counts.get("INBOX"), Some(&2));
+        assert_eq!(counts.get("Label_1"), Some(&1));
+        assert!(store.count_unread_threads_per_label(Some(2)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_list_threads_after_cursor() {
+        let (store, _dir) = create_test_store();
+
+        // Two threads share a timestamp to exercise the thread_id tiebreak
+        let now = Utc::now();
+        for (id, age_hours) in [("t1", 0), ("t2", 1), ("t3", 1), ("t4", 2)] {
+            let mut thread = make_test_thread(id, "Thread");
+            thread.last_message_at = now - chrono::Duration::hours(age_hours);
+            store.upsert_thread(thread).unwrap();
+            store.upsert_message(make_test_message(&format!("m{}", id), id)).unwrap();
+        }
+
+        let page1 = store.list_threads_after(None, None, None, 2).unwrap();
+        let ids: Vec<_> = page1.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, vec!["t1", "t2"]);
+
+        let cursor = ThreadCursor::for_thread(page1.last().unwrap());
+        let page2 = store.list_threads_after(None, None, Some(&cursor), 2).unwrap();
+        let ids: Vec<_> = page2.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, vec!["t3", "t4"]);
+
+        // Same traversal through the label index
+        let page2 = store
+            .list_threads_after(Some("INBOX"), Some(1), Some(&cursor), 10)
+            .unwrap();
+        let ids: Vec<_> = page2.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, vec!["t3", "t4"]);
+    }
+
+    #[test]
+    fn test_sync_state() {
+        let (store, _dir) = create_test_store();
+
+        assert!(store.get_sync_state(1).unwrap().is_none());
+
+        let state = SyncState::new(1, "12345");
+        store.save_sync_state(state).unwrap();
+
+        let retrieved = store.get_sync_state(1).unwrap().unwrap();
+        assert_eq!(retrieved.history_id, "12345");
+
+        store.delete_sync_state(1).unwrap();
+        assert!(store.get_sync_state(1).unwrap().is_none());
+    }
+
+    #[test]
     fn test_pending_messages() {
         let (store, _dir) = create_test_store();
-        let account_id = 1;
+        let account_id = 1;
+
+        let id = MessageId::new("p1");
+        let data = b"raw gmail json";
+        let labels = vec!["INBOX".to_string()];
+
+        store
+            .store_pending_message(&id, account_id, data, labels)
+            .unwrap();
+
+        assert!(store.has_pending_message(&id).unwrap());
+        assert_eq!(store.count_pending_messages(account_id, None).unwrap(), 1);
+        assert_eq!(
+            store
+                .count_pending_messages(account_id, Some("INBOX"))
+                .unwrap(),
+            1
+        );
+
+        let pending = store.get_pending_messages(account_id, None, 10).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].data, data);
+
+        store.delete_pending_message(&id).unwrap();
+        assert!(!store.has_pending_message(&id).unwrap());
+    }
+
+    #[test]
+    fn test_pending_messages_are_compressed() {
+        let (store, _dir) = create_test_store();
+        let data = br#"{"id":"p1","payload":"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"}"#.repeat(50);
+        store
+            .store_pending_message(&MessageId::new("p1"), 1, &data, vec!["INBOX".to_string()])
+            .unwrap();
+
+        // Rows queued before compression are read as they are
+        store
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO pending_messages (id, account_id, data) VALUES ('p0', 1, ?)",
+                [b"plain json".as_slice()],
+            )
+            .unwrap();
+
+        let stored: usize = store
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT length(data) FROM pending_messages WHERE id = 'p1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(stored < data.len() / 4);
+
+        let pending = store.get_pending_messages(1, None, 10).unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].id.as_str(), "p1");
+        assert_eq!(pending[0].data, data);
+        assert_eq!(pending[1].data, b"plain json");
+    }
+
+    #[test]
+    fn test_delete_message() {
+        let (store, _dir) = create_test_store();
+
+        let thread = make_test_thread("t1", "Test Thread");
+        store.upsert_thread(thread).unwrap();
+
+        let message = make_test_message("m1", "t1");
+        store.upsert_message(message).unwrap();
+
+        assert!(store.has_message(&MessageId::new("m1")).unwrap());
+
+        store.delete_message(&MessageId::new("m1")).unwrap();
+
+        assert!(!store.has_message(&MessageId::new("m1")).unwrap());
+        // Thread should also be deleted since it was the only message
+        assert!(!store.has_thread(&ThreadId::new("t1")).unwrap());
+    }
+
+    #[test]
+    fn test_move_message() {
+        let (store, _dir) = create_test_store();
+        store.upsert_thread(make_test_thread("t1", "Test Thread")).unwrap();
+        store.upsert_thread(make_test_thread("t2", "Other Thread")).unwrap();
+        store.upsert_message(make_test_message("m1", "t1")).unwrap();
+        store.upsert_message(make_test_message("m2", "t1")).unwrap();
+        let mut archived = make_test_message("m3", "t2");
+        archived.label_ids = Vec::new();
+        store.upsert_message(archived).unwrap();
+        assert_eq!(store.count_threads_by_label("INBOX").unwrap(), 1);
+
+        store.move_message(&MessageId::new("m2"), &ThreadId::new("t2")).unwrap();
+        let moved = store.get_message(&MessageId::new("m2")).unwrap().unwrap();
+        assert_eq!(moved.thread_id.as_str(), "t2");
+        assert_eq!(store.get_thread(&ThreadId::new("t1")).unwrap().unwrap().message_count, 1);
+        // The destination thread now carries the moved message's labels
+        assert_eq!(store.count_threads_by_label("INBOX").unwrap(), 2);
+
+        // Moving a thread's last message deletes it
+        store.move_message(&MessageId::new("m1"), &ThreadId::new("t2")).unwrap();
+        assert!(!store.has_thread(&ThreadId::new("t1")).unwrap());
+        assert_eq!(store.count_threads_by_label("INBOX").unwrap(), 1);
+        assert_eq!(store.get_message_ids_for_thread(&ThreadId::new("t2")).unwrap().len(), 3);
+
+        // Unknown messages and no-op moves are ignored
+        store.move_message(&MessageId::new("m9"), &ThreadId::new("t2")).unwrap();
+        store.move_message(&MessageId::new("m1"), &ThreadId::new("t2")).unwrap();
+    }
+
+    #[test]
+    fn test_update_labels() {
+        let (store, _dir) = create_test_store();
+
+        let thread = make_test_thread("t1", "Test Thread");
+        store.upsert_thread(thread).unwrap();
+
+        let message = make_test_message("m1", "t1");
+        store.upsert_message(message).unwrap();
+
+        // Check initial labels
+        let msg = store.get_message(&MessageId::new("m1")).unwrap().unwrap();
+        assert!(msg.label_ids.contains(&"UNREAD".to_string()));
+
+        // Update labels (mark as read)
+        store
+            .update_message_labels(&MessageId::new("m1"), vec!["INBOX".to_string()])
+            .unwrap();
+
+        // Check updated labels
+        let msg = store.get_message(&MessageId::new("m1")).unwrap().unwrap();
+        assert!(!msg.label_ids.contains(&"UNREAD".to_string()));
+        assert!(msg.label_ids.contains(&"INBOX".to_string()));
+
+        // Check thread is_unread updated
+        let thread = store.get_thread(&ThreadId::new("t1")).unwrap().unwrap();
+        assert!(!thread.is_unread);
+    }
+
+    #[test]
+    fn test_list_messages_for_thread_assembles_batched_rows() {
+        let (store, _dir) = create_test_store();
+        store.upsert_thread(make_test_thread("t1", "Test Thread")).unwrap();
+
+        // Stored newest first, so listing has to reorder
+        for i in (1..=3).rev() {
+            let msg = Message::builder(MessageId::new(format!("m{}", i)), ThreadId::new("t1"))
+                .account_id(1)
+                .from(EmailAddress::new(format!("sender{}@example.com", i)))
+                .to(vec![
+                    EmailAddress::new(format!("to{}a@example.com", i)),
+                    EmailAddress::new(format!("to{}b@example.com", i)),
+                ])
+                .cc(vec![EmailAddress::with_name("Cc", format!("cc{}@example.com", i))])
+                .received_at(Utc::now() + chrono::Duration::seconds(i))
+                .label_ids(vec![format!("Label_{}", i), "INBOX".to_string()])
+                .build();
+            store.upsert_message(msg).unwrap();
+        }
+
+        let messages = store.list_messages_for_thread(&ThreadId::new("t1")).unwrap();
+        let ids: Vec<&str> = messages.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["m1", "m2", "m3"]);
+        for (i, message) in (1..=3).zip(&messages) {
+            let to: Vec<&str> = message.to.iter().map(|a| a.email.as_str()).collect();
+            assert_eq!(to, vec![format!("to{}a@example.com", i), format!("to{}b@example.com", i)]);
+            assert_eq!(message.cc.len(), 1);
+            assert_eq!(message.cc[0].name.as_deref(), Some("Cc"));
+            assert_eq!(message.cc[0].email, format!("cc{}@example.com", i));
+            assert_eq!(message.label_ids, vec!["INBOX".to_string(), format!("Label_{}", i)]);
+        }
+
+        // Ids spanning two batches, with unknown ids skipped
+        let mut ids: Vec<String> = (0..METADATA_BATCH_SIZE).map(|i| format!("missing{}", i)).collect();
+        ids.insert(0, "m3".to_string());
+        ids.push("m1".to_string());
+        let conn = store.reader();
+        let loaded = store.load_messages_metadata(&conn, &ids).unwrap();
+        let loaded: Vec<&str> = loaded.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(loaded, vec!["m3", "m1"]);
+    }
+
+    #[test]
+    fn test_list_messages_for_thread_multiple() {
+        let (store, _dir) = create_test_store();
+
+        let thread = make_test_thread("t1", "Test Thread");
+        store.upsert_thread(thread).unwrap();
+
+        // Add 3 messages to the same thread
+        for i in 1..=3 {
+            let msg = Message::builder(MessageId::new(format!("m{}", i)), ThreadId::new("t1"))
+                .account_id(1)
+                .from(EmailAddress::new(format!("sender{}@example.com", i)))
+                .subject(format!("Message {}", i))
+                .body_preview(format!("Preview {}", i))
+                .body_text(Some(format!("Body text {}", i)))
+                .body_html(Some(format!("<p>Body HTML {}</p>", i)))
+                .received_at(Utc::now() + chrono::Duration::seconds(i as i64))
+                .label_ids(vec!["INBOX".to_string()])
+                .build();
+            store.upsert_message(msg).unwrap();
+        }
+
+        // list_messages_for_thread should return all 3
+        let metadata_list = store
+            .list_messages_for_thread(&ThreadId::new("t1"))
+            .unwrap();
+        assert_eq!(
+            metadata_list.len(),
+            3,
+            "Expected 3 messages, got {}",
+            metadata_list.len()
+        );
+
+        // list_messages_for_thread_with_bodies should also return all 3
+        let messages = store
+            .list_messages_for_thread_with_bodies(&ThreadId::new("t1"))
+            .unwrap();
+        assert_eq!(
+            messages.len(),
+            3,
+            "Expected 3 messages with bodies, got {}",
+            messages.len()
+        );
+
+        // Verify each message has the correct body
+        for (i, msg) in messages.iter().enumerate() {
+            let expected_text = format!("Body text {}", i + 1);
+            assert_eq!(msg.body_text, Some(expected_text.clone()));
+        }
+    }
+
+    #[test]
+    fn test_attachment_metadata_roundtrip() {
+        let (store, _dir) = create_test_store();
+        store
+            .upsert_thread(make_test_thread("t1", "Test Thread"))
+            .unwrap();
+
+        let message = Message::builder(MessageId::new("m1"), ThreadId::new("t1"))
+            .account_id(1)
+            .from(EmailAddress::new("test@example.com"))
+            .has_attachments(true)
+            .attachment_names(vec!["report.pdf".to_string(), "photo.jpg".to_string()])
+            .size_bytes(6_200_000)
+            .build();
+        store.upsert_message(message).unwrap();
+
+        let metadata = store
+            .get_message_metadata(&MessageId::new("m1"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.attachment_names, vec!["report.pdf", "photo.jpg"]);
+        assert_eq!(metadata.size_bytes, 6_200_000);
+    }
+
+    #[test]
+    fn test_analytics_queries() {
+        let (store, _dir) = create_test_store();
+        store
+            .upsert_thread(make_test_thread("t1", "Test Thread"))
+            .unwrap();
+
+        let start = Utc::now() - chrono::Duration::days(1);
+        let add = |id: &str, from: &str, minutes: i64, size: i64, label: &str| {
+            let at = start + chrono::Duration::minutes(minutes);
+            let message = Message::builder(MessageId::new(id), ThreadId::new("t1"))
+                .account_id(1)
+                .from(EmailAddress::parse(from))
+                .received_at(at)
+                .internal_date(at.timestamp_millis())
+                .size_bytes(size)
+                .label_ids(vec![label.to_string()])
+                .build();
+            store.upsert_message(message).unwrap();
+        };
+        add("m1", "Ann <ann@example.com>", 0, 100, "INBOX");
+        add("m2", "ANN@example.com", 5, 100, "INBOX");
+        add("m3", "me@example.com", 20, 50, "SENT");
+        add("m4", "me@example.com", 30, 50, "SENT");
+        add("m5", "bob@example.com", 40, 1000, "INBOX");
+
+        let senders = store.sender_stats(None, SenderRanking::Volume, 10).unwrap();
+        assert_eq!(senders.len(), 2);
+        assert_eq!(senders[0].email, "ann@example.com");
+        assert_eq!(senders[0].message_count, 2);
+        assert_eq!(senders[0].total_bytes, 200);
+        assert_eq!(senders[0].name, None);
+        let senders = store.sender_stats(Some(1), SenderRanking::Size, 1).unwrap();
+        assert_eq!(senders[0].email, "bob@example.com");
+
+        let days = store
+            .daily_message_counts(None, start - chrono::Duration::days(1))
+            .unwrap();
+        assert_eq!(days.iter().map(|d| d.message_count).sum::<usize>(), 3);
+
+        // m3 replies to m2; m4 follows the account's own message
+        let delays = store.reply_delays_ms(None, start).unwrap();
+        assert_eq!(delays, vec![15 * 60 * 1000]);
+        assert!(store.reply_delays_ms(Some(2), start).unwrap().is_empty());
+
+        let storage = store.label_storage(None).unwrap();
+        let summary: Vec<(&str, usize, i64)> = storage
+            .iter()
+            .map(|s| (s.label_id.as_str(), s.message_count, s.total_bytes))
+            .collect();
+        assert_eq!(summary, vec![("INBOX", 3, 1200), ("SENT", 2, 100)]);
+
+        let largest = store.largest_threads(None, 10).unwrap();
+        assert_eq!(largest, vec![(ThreadId::new("t1"), 1300)]);
+        assert!(store.largest_threads(Some(2), 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unsubscribe_roundtrip() {
+        let (store, _dir) = create_test_store();
+        store
+            .upsert_thread(make_test_thread("t1", "Test Thread"))
+            .unwrap();
+
+        let unsubscribe = Unsubscribe {
+            url: Some("https://example.com/unsubscribe".to_string()),
+            mailto: Some("mailto:unsub@example.com".to_string()),
+            one_click: true,
+        };
+        let mut message = make_test_message("m1", "t1");
+        message.unsubscribe = Some(unsubscribe.clone());
+        store.upsert_message(message).unwrap();
+        store.upsert_message(make_test_message("m2", "t1")).unwrap();
+
+        let metadata = store
+            .get_message_metadata(&MessageId::new("m1"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.unsubscribe, Some(unsubscribe));
+
+        let listed = store.list_unsubscribable_messages(None).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id.as_str(), "m1");
+        assert!(store.list_unsubscribable_messages(Some(2)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_auth_results_roundtrip() {
+        let (store, _dir) = create_test_store();
+        store
+            .upsert_thread(make_test_thread("t1", "Test Thread"))
+            .unwrap();
+
+        let auth_results = AuthResults {
+            spf: Some(AuthVerdict::Pass),
+            dkim: None,
+            dmarc: Some(AuthVerdict::Fail),
+        };
+        let mut message = make_test_message("m1", "t1");
+        message.auth_results = Some(auth_results.clone());
+        store.upsert_message(message).unwrap();
+        store.upsert_message(make_test_message("m2", "t1")).unwrap();
+
+        let auth = |id: &str| {
+            store
+                .get_message_metadata(&MessageId::new(id))
+                .unwrap()
+                .unwrap()
+                .auth_results
+        };
+        assert_eq!(auth("m1"), Some(auth_results));
+        assert_eq!(auth("m2"), None);
+    }
+
+    #[test]
+    fn test_thread_summary_cache() {
+        let (store, _dir) = create_test_store();
+        store
+            .upsert_thread(make_test_thread("t1", "Test Thread"))
+            .unwrap();
+        let thread_id = ThreadId::new("t1");
+
+        assert_eq!(store.get_cached_summary(&thread_id, "m1").unwrap(), None);
+        store.cache_summary(&thread_id, "m1", "First").unwrap();
+        assert_eq!(
+            store.get_cached_summary(&thread_id, "m1").unwrap().as_deref(),
+            Some("First")
+        );
+
+        // A different message set misses, and caching replaces the old entry
+        assert_eq!(store.get_cached_summary(&thread_id, "m1,m2").unwrap(), None);
+        store.cache_summary(&thread_id, "m1,m2", "Second").unwrap();
+        assert_eq!(store.get_cached_summary(&thread_id, "m1").unwrap(), None);
+        assert_eq!(
+            store.get_cached_summary(&thread_id, "m1,m2").unwrap().as_deref(),
+            Some("Second")
+        );
+    }
+
+    #[test]
+    fn test_message_opens() {
+        use chrono::TimeZone;
+
+        let (store, _dir) = create_test_store();
+        let id = MessageId::new("m1");
+        let at = |h| chrono::Utc.with_ymd_and_hms(2026, 1, 10, h, 0, 0).unwrap();
+
+        assert!(store.get_open_status(&id).unwrap().is_none());
+        assert!(!store.record_message_open("tok", at(9)).unwrap());
+
+        // Tracked before the sent copy has synced
+        store.save_tracking_token("tok", &id).unwrap();
+        assert_eq!(store.get_open_status(&id).unwrap(), Some(OpenStatus::default()));
+
+        assert!(store.record_message_open("tok", at(11)).unwrap());
+        assert!(store.record_message_open("tok", at(9)).unwrap());
+        assert!(!store.record_message_open("tok", at(9)).unwrap());
+
+        let status = store.get_open_status(&id).unwrap().unwrap();
+        assert_eq!(status.open_count, 2);
+        assert_eq!(status.first_opened_at, Some(at(9)));
+        assert_eq!(status.last_opened_at, Some(at(11)));
+        assert_eq!(store.latest_message_open().unwrap(), Some(at(11)));
+    }
+
+    #[test]
+    fn test_followup_state_roundtrip() {
+        let (store, _dir) = create_test_store();
+        store
+            .upsert_thread(make_test_thread("t1", "Test Thread"))
+            .unwrap();
+        let thread_id = ThreadId::new("t1");
+        assert_eq!(store.get_followup_state(&thread_id).unwrap(), None);
+
+        let mut state = FollowupState::new(thread_id.clone());
+        state.snoozed_until = Some(chrono::Utc::now());
+        store.save_followup_state(&state).unwrap();
+        state.dismissed_at = Some(chrono::Utc::now());
+        state.snoozed_until = None;
+        store.save_followup_state(&state).unwrap();
+
+        let loaded = store.get_followup_state(&thread_id).unwrap().unwrap();
+        assert_eq!(loaded.dismissed_at, state.dismissed_at);
+        assert_eq!(loaded.snoozed_until, None);
+    }
+
+    #[test]
+    fn test_thread_notes() {
+        let (store, _dir) = create_test_store();
+        store
+            .upsert_thread(make_test_thread("t1", "Test Thread"))
+            .unwrap();
+        let thread_id = ThreadId::new("t1");
+        assert_eq!(store.get_note(&thread_id).unwrap(), None);
 
-        let id = MessageId::new("p1");
-        let data = b"raw gmail json";
-        let labels = vec!["INBOX".to_string()];
+        store
+            .set_note(&thread_id, "Waiting on **legal** review")
+            .unwrap();
+        let note = store.get_note(&thread_id).unwrap().unwrap();
+        assert_eq!(note.markdown, "Waiting on **legal** review");
+
+        store.set_note(&thread_id, "  \n").unwrap();
+        assert_eq!(store.get_note(&thread_id).unwrap(), None);
+    }
 
+    #[test]
+    fn test_attachment_text_roundtrip() {
+        let (store, _dir) = create_test_store();
         store
-            .store_pending_message(&id, account_id, data, labels)
+            .upsert_thread(make_test_thread("t1", "Test Thread"))
             .unwrap();
 
-        assert!(store.has_pending_message(&id).unwrap());
-        assert_eq!(store.count_pending_messages(account_id, None).unwrap(), 1);
-        assert_eq!(
-            store
-                .count_pending_messages(account_id, Some("INBOX"))
-                .unwrap(),
-            1
-        );
+        let mut message = make_test_message("m1", "t1");
+        message.body_text = None;
+        message.body_html = None;
+        message.attachment_text = Some("Invoice 4821".to_string());
+        store.upsert_message(message).unwrap();
 
-        let pending = store.get_pending_messages(account_id, None, 10).unwrap();
-        assert_eq!(pending.len(), 1);
-        assert_eq!(pending[0].data, data);
+        let body = store
+            .get_message_body(&MessageId::new("m1"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(body.attachment_text.as_deref(), Some("Invoice 4821"));
+        assert_eq!(body.text, None);
 
-        store.delete_pending_message(&id).unwrap();
-        assert!(!store.has_pending_message(&id).unwrap());
+        let message = store.get_message(&MessageId::new("m1")).unwrap().unwrap();
+        assert_eq!(message.attachment_text.as_deref(), Some("Invoice 4821"));
     }
 
     #[test]
-    fn test_delete_message() {
+    fn test_account_settings() {
         let (store, _dir) = create_test_store();
+        let account = store.get_account(1).unwrap().unwrap();
+        assert!(account.signature.is_none());
+
+        let mut settings = account.settings();
+        settings.display_name = Some("Work".to_string());
+        settings.avatar_color = "hsl(150, 70%, 40%)".to_string();
+        settings.signature = Some(Signature::text("Cheers,\nTest"));
+        store.update_account_settings(1, settings.clone()).unwrap();
+        let account = store.get_account_by_email("test@example.com").unwrap().unwrap();
+        assert_eq!(account.settings(), settings);
+
+        settings.signature = Some(Signature::text("Test").with_html("<b>Test</b>"));
+        store.update_account_settings(1, settings.clone()).unwrap();
+        assert_eq!(store.list_accounts().unwrap()[0].settings(), settings);
+
+        let other = Account::new("other@example.com").with_signature(Signature::text("O"));
+        let registered = store.register_account(other).unwrap();
+        let other = store.get_account(registered.id).unwrap().unwrap();
+        assert_eq!(other.signature, Some(Signature::text("O")));
+    }
 
-        let thread = make_test_thread("t1", "Test Thread");
-        store.upsert_thread(thread).unwrap();
+    #[test]
+    fn test_account_sync_enabled() {
+        let (store, _dir) = create_test_store();
+        assert!(store.get_account(1).unwrap().unwrap().sync_enabled);
 
-        let message = make_test_message("m1", "t1");
-        store.upsert_message(message).unwrap();
+        store.set_account_sync_enabled(1, false).unwrap();
+        assert!(!store.get_account(1).unwrap().unwrap().sync_enabled);
+        assert!(!store.list_accounts().unwrap()[0].sync_enabled);
 
-        assert!(store.has_message(&MessageId::new("m1")).unwrap());
+        store.set_account_sync_enabled(1, true).unwrap();
+        assert!(store.get_account(1).unwrap().unwrap().sync_enabled);
+    }
 
-        store.delete_message(&MessageId::new("m1")).unwrap();
+    #[test]
+    fn test_labels_roundtrip() {
+        let (store, _dir) = create_test_store();
+        let account = store.register_account(Account::new("me@example.com")).unwrap();
 
-        assert!(!store.has_message(&MessageId::new("m1")).unwrap());
-        // Thread should also be deleted since it was the only message
-        assert!(!store.has_thread(&ThreadId::new("t1")).unwrap());
+        store
+            .replace_labels(
+                account.id,
+                vec![
+                    Label::new("Label_2", "work/clients"),
+                    Label::new("Label_1", "Work"),
+                    Label::new("Label_3", "receipts"),
+                ],
+            )
+            .unwrap();
+        let names: Vec<String> = store
+            .list_labels(account.id)
+            .unwrap()
+            .into_iter()
+            .map(|label| label.name)
+            .collect();
+        assert_eq!(names, vec!["receipts", "Work", "work/clients"]);
+
+        store
+            .replace_labels(account.id, vec![Label::new("Label_1", "Work")])
+            .unwrap();
+        assert_eq!(store.list_labels(account.id).unwrap().len(), 1);
+        assert!(store.list_labels(account.id + 1).unwrap().is_empty());
     }
 
     #[test]
-    fn test_update_labels() {
+    fn test_label_overrides() {
         let (store, _dir) = create_test_store();
+        let account = store.register_account(Account::new("me@example.com")).unwrap();
+        let gmail_color = LabelColor::new("#fb4c2f", "#ffffff");
+        let local_color = LabelColor::new("#16a766", "#000000");
 
-        let thread = make_test_thread("t1", "Test Thread");
-        store.upsert_thread(thread).unwrap();
+        store
+            .replace_labels(
+                account.id,
+                vec![
+                    Label::new("Label_1", "work").with_color(Some(gmail_color.clone())),
+                    Label::new("Label_2", "receipts"),
+                ],
+            )
+            .unwrap();
+        store
+            .set_label_override(account.id, "Label_1", Some("💼".to_string()), None)
+            .unwrap();
+        store
+            .set_label_override(account.id, "Label_2", None, Some(local_color.clone()))
+            .unwrap();
 
-        let message = make_test_message("m1", "t1");
-        store.upsert_message(message).unwrap();
+        let labels = store.list_labels(account.id).unwrap();
+        assert_eq!(labels[0].id.as_str(), "Label_2");
+        assert_eq!(labels[0].color, Some(local_color));
+        assert_eq!(labels[0].icon, None);
+        assert_eq!(labels[1].color, Some(gmail_color.clone()));
+        assert_eq!(labels[1].display_icon(), "💼");
 
-        // Check initial labels
-        let msg = store.get_message(&MessageId::new("m1")).unwrap().unwrap();
-        assert!(msg.label_ids.contains(&"UNREAD".to_string()));
+        // Overrides outlive a refresh from Gmail
+        store
+            .replace_labels(account.id, vec![Label::new("Label_1", "work")])
+            .unwrap();
+        assert_eq!(store.list_labels(account.id).unwrap()[0].display_icon(), "💼");
 
-        // Update labels (mark as read)
         store
-            .update_message_labels(&MessageId::new("m1"), vec!["INBOX".to_string()])
+            .set_label_override(account.id, "Label_1", None, None)
+            .unwrap();
+        assert_eq!(store.list_labels(account.id).unwrap()[0].icon, None);
+    }
+
+    #[test]
+    fn test_list_user_labels_for_threads() {
+        let (store, _dir) = create_test_store();
+        let account = store.register_account(Account::new("me@example.com")).unwrap();
+        assert_eq!(account.id, 1);
+        store
+            .replace_labels(1, vec![Label::new("Label_1", "work")])
+            .unwrap();
+        store
+            .set_label_override(1, "Label_1", Some("💼".to_string()), None)
             .unwrap();
 
-        // Check updated labels
-        let msg = store.get_message(&MessageId::new("m1")).unwrap().unwrap();
-        assert!(!msg.label_ids.contains(&"UNREAD".to_string()));
-        assert!(msg.label_ids.contains(&"INBOX".to_string()));
+        for id in ["t1", "t2"] {
+            store.upsert_thread(make_test_thread(id, "Test Thread")).unwrap();
+            let mut message = make_test_message(&format!("m_{}", id), id);
+            if id == "t1" {
+                message.label_ids.push("Label_1".to_string());
+            }
+            store.upsert_message(message).unwrap();
+        }
 
-        // Check thread is_unread updated
-        let thread = store.get_thread(&ThreadId::new("t1")).unwrap().unwrap();
-        assert!(!thread.is_unread);
+        let labels = store
+            .list_user_labels_for_threads(&[ThreadId::new("t1"), ThreadId::new("t2")])
+            .unwrap();
+        assert_eq!(labels.len(), 1);
+        let chips = &labels[&ThreadId::new("t1")];
+        assert_eq!(chips.len(), 1);
+        assert_eq!(chips[0].name, "work");
+        assert_eq!(chips[0].icon.as_deref(), Some("💼"));
     }
 
     #[test]
-    fn test_list_messages_for_thread_multiple() {
+    fn test_send_as_aliases() {
         let (store, _dir) = create_test_store();
 
-        let thread = make_test_thread("t1", "Test Thread");
-        store.upsert_thread(thread).unwrap();
+        let mut primary = SendAsAlias::new(1, "test@example.com");
+        primary.is_primary = true;
+        let mut team = SendAsAlias::new(1, "team@example.com");
+        team.is_default = true;
+        team.display_name = Some("Team".to_string());
+        let mut pending = SendAsAlias::new(1, "pending@example.com");
+        pending.verified = false;
 
-        // Add 3 messages to the same thread
-        for i in 1..=3 {
-            let msg = Message::builder(MessageId::new(format!("m{}", i)), ThreadId::new("t1"))
-                .account_id(1)
-                .from(EmailAddress::new(format!("sender{}@example.com", i)))
-                .subject(format!("Message {}", i))
-                .body_preview(format!("Preview {}", i))
-                .body_text(Some(format!("Body text {}", i)))
-                .body_html(Some(format!("<p>Body HTML {}</p>", i)))
-                .received_at(Utc::now() + chrono::Duration::seconds(i as i64))
-                .label_ids(vec!["INBOX".to_string()])
-                .build();
-            store.upsert_message(msg).unwrap();
+        store
+            .replace_send_as_aliases(1, vec![primary.clone(), team.clone(), pending.clone()])
+            .unwrap();
+        let aliases = store.list_send_as_aliases(1).unwrap();
+        assert_eq!(aliases, vec![team, pending, primary.clone()]);
+
+        // Replacing drops aliases Gmail no longer reports
+        store.replace_send_as_aliases(1, vec![primary.clone()]).unwrap();
+        assert_eq!(store.list_send_as_aliases(1).unwrap(), vec![primary]);
+
+        store.delete_account(1).unwrap();
+        assert!(store.list_send_as_aliases(1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_recent_searches() {
+        let (store, _dir) = create_test_store();
+
+        for i in 0..MAX_RECENT_SEARCHES + 5 {
+            store.record_recent_search(&format!("query {}", i)).unwrap();
         }
+        // Re-running an old query moves it to the front
+        store.record_recent_search("query 10").unwrap();
 
-        // list_messages_for_thread should return all 3
-        let metadata_list = store
-            .list_messages_for_thread(&ThreadId::new("t1"))
+        let recent = store.list_recent_searches(100).unwrap();
+        assert_eq!(recent.len(), MAX_RECENT_SEARCHES);
+        assert_eq!(recent[0], "query 10");
+        assert_eq!(recent[1], format!("query {}", MAX_RECENT_SEARCHES + 4));
+        assert_eq!(store.list_recent_searches(3).unwrap().len(), 3);
+
+        store.clear_recent_searches().unwrap();
+        assert!(store.list_recent_searches(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rules_roundtrip() {
+        let (store, _dir) = create_test_store();
+        let account = store.register_account(Account::new("me@example.com")).unwrap();
+
+        let vip = store.save_rule(Rule::vip("boss@example.com")).unwrap();
+        assert!(vip.id > 0);
+        let billing = store
+            .save_rule(
+                Rule::new("Billing")
+                    .for_account(account.id)
+                    .with_predicate(RulePredicate::Domain("billing.example.com".into()))
+                    .with_action(RuleAction::AddLabel("Label_1".into()))
+                    .with_action(RuleAction::Archive)
+                    .with_gmail_filter("ANe1Bmj"),
+            )
+            .unwrap();
+
+        let rules = store.list_rules().unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].predicates, vip.predicates);
+        assert_eq!(rules[1].actions, billing.actions);
+        assert_eq!(rules[1].account_id, Some(account.id));
+        assert_eq!(rules[0].gmail_filter_id, None);
+        assert_eq!(rules[1].gmail_filter_id.as_deref(), Some("ANe1Bmj"));
+
+        store.save_rule(billing.clone().with_enabled(false)).unwrap();
+        assert!(!store.list_rules().unwrap()[1].enabled);
+
+        store.delete_rule(vip.id).unwrap();
+        assert_eq!(store.list_rules().unwrap().len(), 1);
+
+        // Account rules go with the account
+        store.delete_account(account.id).unwrap();
+        assert!(store.list_rules().unwrap().is_empty());
+
+        assert!(store.save_rule(Rule { id: 999, ..Rule::new("Missing") }).is_err());
+    }
+
+    #[test]
+    fn test_queued_actions_roundtrip() {
+        let (store, _dir) = create_test_store();
+        let account = store.register_account(Account::new("me@example.com")).unwrap();
+
+        let archive = store
+            .queue_action(QueuedAction::new(account.id, ThreadId::new("t1"), &[], &["INBOX"]))
+            .unwrap();
+        assert!(archive.id > 0);
+        store
+            .queue_action(QueuedAction::new(account.id, ThreadId::new("t2"), &["STARRED"], &[]))
             .unwrap();
+
+        let queued = store.list_queued_actions(account.id).unwrap();
+        assert_eq!(queued.len(), 2);
+        assert_eq!(queued[0].id, archive.id);
+        assert_eq!(queued[0].thread_id, ThreadId::new("t1"));
+        assert_eq!(queued[0].remove_labels, vec!["INBOX"]);
+        assert_eq!(queued[1].add_labels, vec!["STARRED"]);
+        assert!(store.list_queued_actions(account.id + 1).unwrap().is_empty());
+
+        store.delete_queued_action(archive.id).unwrap();
+        assert_eq!(store.list_queued_actions(account.id).unwrap().len(), 1);
+
+        // Queued changes go with the account
+        store.delete_account(account.id).unwrap();
+        assert!(store.list_queued_actions(account.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_outbox_roundtrip() {
+        let (store, _dir) = create_test_store();
+        let account = store.register_account(Account::new("me@example.com")).unwrap();
+
+        let mut queued = OutboxMessage::new(
+            account.id,
+            Some(ThreadId::new("t1")),
+            "Hello",
+            vec!["you@example.com".to_string()],
+            "Subject: Hello\r\n\r\nHi".to_string(),
+        );
+        queued.draft_id = Some("r1".to_string());
+        let queued = store.add_outbox_message(queued).unwrap();
+        assert!(queued.id > 0);
+        assert_eq!(store.get_outbox_message(queued.id).unwrap(), Some(queued.clone()));
+
+        let mut failed = queued.clone();
+        failed.state = OutboxState::Failed;
+        failed.attempts = 3;
+        failed.last_error = Some("Rejected".to_string());
+        store.update_outbox_message(&failed).unwrap();
+        let listed = store.list_outbox_messages(None).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].state, OutboxState::Failed);
+        assert_eq!(listed[0].attempts, 3);
+        assert_eq!(listed[0].last_error.as_deref(), Some("Rejected"));
+        assert_eq!(listed[0].draft_id.as_deref(), Some("r1"));
+        assert!(store.list_outbox_messages(Some(account.id + 1)).unwrap().is_empty());
+
+        store.delete_outbox_message(queued.id).unwrap();
+        assert!(store.get_outbox_message(queued.id).unwrap().is_none());
+        assert!(store.update_outbox_message(&failed).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_messages() {
+        let (store, _dir) = create_test_store();
+        store
+            .upsert_thread(make_test_thread("t1", "Test Thread"))
+            .unwrap();
+
+        let add = |id: &str, internal_date: i64, rfc_message_id: Option<&str>| {
+            let mut message = make_test_message(id, "t1");
+            message.internal_date = internal_date;
+            message.rfc_message_id = rfc_message_id.map(String::from);
+            store.upsert_message(message).unwrap();
+        };
+        // The later copy syncs first; the earlier one still becomes canonical
+        add("list", 200, Some("abc@example.com"));
+        add("direct", 100, Some("abc@example.com"));
+        add("other", 150, Some("xyz@example.com"));
+        add("bare", 300, None);
+
+        let metadata = store.get_message_metadata(&MessageId::new("list")).unwrap().unwrap();
+        assert_eq!(metadata.rfc_message_id.as_deref(), Some("abc@example.com"));
+
+        let thread_id = ThreadId::new("t1");
         assert_eq!(
-            metadata_list.len(),
-            3,
-            "Expected 3 messages, got {}",
-            metadata_list.len()
+            store.list_duplicate_messages(&thread_id).unwrap(),
+            vec![(MessageId::new("list"), MessageId::new("direct"))]
         );
 
-        // list_messages_for_thread_with_bodies should also return all 3
-        let messages = store
-            .list_messages_for_thread_with_bodies(&ThreadId::new("t1"))
+        // Re-syncing a copy leaves the mapping as it was
+        add("list", 200, Some("abc@example.com"));
+        assert_eq!(store.list_duplicate_messages(&thread_id).unwrap().len(), 1);
+
+        store.delete_message(&MessageId::new("direct")).unwrap();
+        assert!(store.list_duplicate_messages(&thread_id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_saved_searches_roundtrip() {
+        let (store, _dir) = create_test_store();
+
+        let boss = store
+            .save_search(SavedSearch::new("Boss", "from:boss is:unread").with_pinned(true))
+            .unwrap();
+        assert!(boss.id > 0);
+        let receipts = store
+            .save_search(SavedSearch::new("Receipts", "subject:receipt"))
+            .unwrap();
+
+        // Duplicate names are rejected
+        assert!(store.save_search(SavedSearch::new("Boss", "from:ceo")).is_err());
+
+        let names: Vec<_> = store
+            .list_saved_searches()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, vec!["Boss", "Receipts"]);
+
+        // Pinning reorders, updates keep the id
+        let receipts = store.save_search(receipts.with_pinned(true)).unwrap();
+        let unpinned = SavedSearch {
+            pinned: false,
+            ..boss
+        };
+        store.save_search(unpinned).unwrap();
+        let list = store.list_saved_searches().unwrap();
+        assert_eq!(list[0].id, receipts.id);
+        assert!(list[0].pinned);
+
+        store.delete_saved_search(receipts.id).unwrap();
+        assert_eq!(store.list_saved_searches().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_clear_account_data_leaves_other_accounts() {
+        let (store, dir) = create_test_store();
+        let other = store.register_account(Account::new("other@example.com")).unwrap();
+
+        store.upsert_thread(make_test_thread("t1", "Mine")).unwrap();
+        store.upsert_message(make_test_message("m1", "t1")).unwrap();
+        let mut theirs = make_test_thread("t2", "Theirs");
+        theirs.account_id = other.id;
+        store.upsert_thread(theirs).unwrap();
+        let mut message = make_test_message("m2", "t2");
+        message.account_id = other.id;
+        store.upsert_message(message).unwrap();
+        store
+            .store_pending_message(&MessageId::new("p1"), 1, b"{}", Vec::new())
+            .unwrap();
+        store
+            .store_pending_message(&MessageId::new("p2"), other.id, b"{}", Vec::new())
+            .unwrap();
+        let blobs = FileBlobStore::new(dir.path().join("blobs.test")).unwrap();
+        blobs.put(&BlobKey::translation("m1", "fr"), b"Bonjour").unwrap();
+        blobs.put(&BlobKey::translation("m2", "fr"), b"Salut").unwrap();
+
+        store.clear_account_data(1).unwrap();
+
+        assert!(store.get_account(1).unwrap().is_some());
+        assert!(!store.has_thread(&ThreadId::new("t1")).unwrap());
+        assert!(!store.has_message(&MessageId::new("m1")).unwrap());
+        assert_eq!(store.count_threads_by_label_for_account("INBOX", Some(1)).unwrap(), 0);
+        assert!(!store.has_pending_message(&MessageId::new("p1")).unwrap());
+        assert!(!blobs.exists(&BlobKey::translation("m1", "fr")).unwrap());
+
+        assert!(store.has_thread(&ThreadId::new("t2")).unwrap());
+        assert!(store.has_message(&MessageId::new("m2")).unwrap());
+        assert!(store.has_pending_message(&MessageId::new("p2")).unwrap());
+        assert!(blobs.exists(&BlobKey::translation("m2", "fr")).unwrap());
+
+        // New databases can give freed pages back after a clear
+        let conn = store.conn.lock().unwrap();
+        let auto_vacuum: i64 = conn.query_row("PRAGMA auto_vacuum", [], |row| row.get(0)).unwrap();
+        assert_eq!(auto_vacuum, 2);
+    }
+
+    #[test]
+    fn test_storage_stats_and_compact() {
+        let (store, dir) = create_test_store();
+        let now = Utc::now();
+        store.upsert_thread(make_test_thread("t1", "Test")).unwrap();
+
+        let body = "A newsletter paragraph that repeats. ".repeat(200);
+        let old = Message::builder(MessageId::new("m_old"), ThreadId::new("t1"))
+            .account_id(1)
+            .internal_date((now - chrono::Duration::days(90)).timestamp_millis())
+            .body_text(Some(body.clone()))
+            .build();
+        let mut recent = make_test_message("m_new", "t1");
+        recent.internal_date = now.timestamp_millis();
+        store.upsert_message(old).unwrap();
+        store.upsert_message(recent).unwrap();
+
+        // A blob whose message was never stored
+        let blobs = FileBlobStore::new(dir.path().join("blobs.test")).unwrap();
+        blobs.put(&BlobKey::body_text("gone"), b"orphan").unwrap();
+
+        let stats = store.storage_stats().unwrap();
+        assert!(stats.database_bytes > 0);
+        let text = stats.usage(ContentType::BodyText);
+        assert_eq!(text.count, 3);
+        assert_eq!(text.original_bytes, (body.len() + "Test body text".len()) as u64);
+        assert!(text.compression_ratio().unwrap() > 1.0);
+        assert_eq!(stats.usage(ContentType::BodyHtml).count, 1);
+        assert_eq!(stats.usage(ContentType::Attachment), ContentUsage::default());
+
+        let report = store.compact(now - chrono::Duration::days(30)).unwrap();
+        assert_eq!(report.recompressed, 1);
+        assert_eq!(report.orphaned_blobs_removed, 1);
+        assert!(report.database_bytes_after > 0);
+
+        let after = store.storage_stats().unwrap().usage(ContentType::BodyText);
+        assert_eq!(after.count, 2);
+        assert!(after.stored_bytes <= text.stored_bytes);
+        let retrieved = store.get_message(&MessageId::new("m_old")).unwrap().unwrap();
+        assert_eq!(retrieved.body_text, Some(body));
+
+        // Already archived bodies are left alone
+        let report = store.compact(now - chrono::Duration::days(30)).unwrap();
+        assert_eq!(report.recompressed, 0);
+    }
+
+    #[test]
+    fn test_check_and_repair_integrity() {
+        let (store, dir) = create_test_store();
+        store.upsert_thread(make_test_thread("t1", "Test")).unwrap();
+        store.upsert_message(make_test_message("m1", "t1")).unwrap();
+        assert!(store.check_integrity().unwrap().is_clean());
+
+        // Orphans can only appear with foreign keys off (or from older schemas)
+        {
+            let conn = store.conn.lock().unwrap();
+            conn.execute_batch(
+                "PRAGMA foreign_keys = OFF;
+                 DELETE FROM threads WHERE id = 't1';
+                 INSERT INTO thread_labels (thread_id, account_id, label_id, last_message_at)
+                 VALUES ('ghost', 1, 'INBOX', '2024-01-01T00:00:00Z');
+                 PRAGMA foreign_keys = ON;",
+            )
             .unwrap();
+        }
+        let blobs = FileBlobStore::new(dir.path().join("blobs.test")).unwrap();
+        blobs.put(&BlobKey::body_html("gone"), b"orphan").unwrap();
+
+        let report = store.check_integrity().unwrap();
+        assert_eq!(report.messages_without_threads, vec![MessageId::new("m1")]);
         assert_eq!(
-            messages.len(),
-            3,
-            "Expected 3 messages with bodies, got {}",
-            messages.len()
+            report.thread_labels_without_threads,
+            vec![ThreadId::new("ghost"), ThreadId::new("t1")]
         );
+        assert_eq!(report.blobs_without_messages, vec![BlobKey::body_html("gone")]);
 
-        // Verify each message has the correct body
-        for (i, msg) in messages.iter().enumerate() {
-            let expected_text = format!("Body text {}", i + 1);
-            assert_eq!(msg.body_text, Some(expected_text.clone()));
+        let repaired = store.repair().unwrap();
+        assert_eq!(repaired.thread_labels_without_threads, vec![ThreadId::new("ghost")]);
+        assert!(store.check_integrity().unwrap().is_clean());
+
+        // The rebuilt thread is listed under its message's labels again
+        let thread = store.get_thread(&ThreadId::new("t1")).unwrap().unwrap();
+        assert_eq!(thread.subject, "Test");
+        assert!(thread.is_unread);
+        let inbox = store.list_threads_by_label("INBOX", 10, 0).unwrap();
+        assert_eq!(inbox.len(), 1);
+    }
+
+    #[test]
+    fn test_label_listing_reads_thread_labels_in_index_order() {
+        let (store, _dir) = create_test_store();
+        let conn = store.reader();
+
+        for (for_account, after_cursor) in [(false, false), (true, false), (false, true), (true, true)] {
+            let query = label_threads_query(for_account, after_cursor);
+            let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {}", query)).unwrap();
+            let params = vec![rusqlite::types::Value::Null; stmt.parameter_count()];
+            let plan: Vec<String> = stmt
+                .query_map(rusqlite::params_from_iter(params), |row| row.get(3))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+
+            // One search of thread_labels, already sorted: no join, no sort step
+            assert_eq!(plan.len(), 1, "{:?}", plan);
+            assert!(plan[0].starts_with("SEARCH tl USING INDEX idx_thread_labels_"), "{:?}", plan);
+            assert!(!plan[0].contains("TEMP B-TREE"), "{:?}", plan);
+        }
+    }
+
+    #[test]
+    fn test_first_message_at() {
+        let (store, _dir) = create_test_store();
+        store.upsert_thread(make_test_thread("t1", "Test Thread")).unwrap();
+        assert_eq!(store.first_message_at(&ThreadId::new("t1")).unwrap(), None);
+
+        let start = Utc::now();
+        for (id, offset) in [("m1", 20), ("m2", 5), ("m3", 40)] {
+            let msg = Message::builder(MessageId::new(id), ThreadId::new("t1"))
+                .account_id(1)
+                .from(EmailAddress::new("sender@example.com"))
+                .received_at(start + chrono::Duration::seconds(offset))
+                .build();
+            store.upsert_message(msg).unwrap();
+        }
+
+        let first = store.first_message_at(&ThreadId::new("t1")).unwrap().unwrap();
+        assert_eq!(first.timestamp(), (start + chrono::Duration::seconds(5)).timestamp());
+
+        // Answered from the index, without visiting the thread's rows
+        let plan: String = store
+            .reader()
+            .query_row(
+                "EXPLAIN QUERY PLAN SELECT MIN(received_at) FROM messages WHERE thread_id = ?",
+                ["t1"],
+                |row| row.get(3),
+            )
+            .unwrap();
+        assert!(plan.contains("COVERING INDEX idx_messages_thread_received"), "{}", plan);
+    }
+
+    #[test]
+    fn test_label_listing_follows_thread_updates() {
+        let (store, _dir) = create_test_store();
+        store.upsert_thread(make_test_thread("t1", "Before")).unwrap();
+        store.upsert_message(make_test_message("m1", "t1")).unwrap();
+
+        let mut thread = make_test_thread("t1", "After");
+        thread.is_unread = true;
+        thread.message_count = 2;
+        store.upsert_thread(thread).unwrap();
+
+        let inbox = store.list_threads_by_label_for_account("INBOX", Some(1), 10, 0).unwrap();
+        assert_eq!(inbox.len(), 1);
+        assert_eq!(inbox[0].subject, "After");
+        assert!(inbox[0].is_unread);
+        assert_eq!(inbox[0].message_count, 2);
+        let page = store.list_threads_after(Some("INBOX"), None, None, 10).unwrap();
+        assert_eq!(page, inbox);
+    }
+
+    #[test]
+    fn test_reads_do_not_wait_for_writes() {
+        let (store, _dir) = create_test_store();
+        store.upsert_thread(make_test_thread("t1", "Committed")).unwrap();
+        store.upsert_message(make_test_message("m1", "t1")).unwrap();
+        assert_eq!(store.count_threads_by_label("INBOX").unwrap(), 1);
+
+        // Sync is midway through a write transaction
+        let writer = store.conn.lock().unwrap();
+        writer.execute_batch("BEGIN IMMEDIATE; DELETE FROM thread_labels;").unwrap();
+
+        let threads = store.list_threads(10, 0).unwrap();
+        assert_eq!(threads.len(), 1);
+        assert_eq!(store.count_threads_by_label("INBOX").unwrap(), 1);
+
+        writer.execute_batch("ROLLBACK;").unwrap();
+        drop(writer);
+
+        // Without a pool reads share the writer
+        let dir = tempdir().unwrap();
+        let blob_store = Box::new(FileBlobStore::new(dir.path().join("blobs.test")).unwrap());
+        let config = StoreConfig { read_connections: 0 };
+        let store =
+            SqliteMailStore::with_config(dir.path().join("mail.test.sqlite"), blob_store, config)
+                .unwrap();
+        assert!(store.readers.is_none());
+        assert_eq!(store.count_threads().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_unreadable_blobs_queue_refetch_on_open() {
+        let (store, dir) = create_test_store();
+        store.upsert_thread(make_test_thread("t1", "Test")).unwrap();
+        store.upsert_message(make_test_message("m1", "t1")).unwrap();
+        store.upsert_message(make_test_message("m2", "t1")).unwrap();
+        drop(store);
+
+        // Torn writes from before a crash
+        for (shard, name) in [("m1", "m1.txt.zst"), ("m2", "m2.tr.fra.zst")] {
+            let shard = dir.path().join("blobs.test").join(shard);
+            std::fs::create_dir_all(&shard).unwrap();
+            std::fs::write(shard.join(name), b"torn").unwrap();
         }
+
+        let blob_store = Box::new(FileBlobStore::new(dir.path().join("blobs.test")).unwrap());
+        let store = SqliteMailStore::new(dir.path().join("mail.test.sqlite"), blob_store).unwrap();
+        // Translations are redone on demand rather than re-fetched
+        assert_eq!(store.list_refetch_messages(1).unwrap(), vec![MessageId::new("m1")]);
+        assert!(store.get_translation(&MessageId::new("m2"), "fra").unwrap().is_none());
+
+        store.mark_for_refetch(&[MessageId::new("m2"), MessageId::new("unknown")]).unwrap();
+        store.clear_refetch(&MessageId::new("m1")).unwrap();
+        assert_eq!(store.list_refetch_messages(1).unwrap(), vec![MessageId::new("m2")]);
+        store.delete_message(&MessageId::new("m2")).unwrap();
+        assert!(store.list_refetch_messages(1).unwrap().is_empty());
     }
 }
@@ -1,8 +1,21 @@
 //! Storage trait definitions
 
-use crate::models::{Account, EmailAddress, Message, MessageId, SyncState, Thread, ThreadId};
+use crate::models::{
+    Account, AccountSettings, AuthResults, EmailAddress, EventInvite, FollowupState, Label,
+    LabelColor, LabelId, Message, MessageId, OpenStatus, OutboxMessage, QueuedAction, Rule,
+    SavedSearch, SendAsAlias, SyncState, Thread, ThreadId, ThreadNote, Unsubscribe,
+};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::mpsc::Receiver;
+
+use super::blob::{BlobKey, ContentType};
+use super::events::StoreEvent;
+use crate::analytics::{DailyVolume, LabelStorage, SenderRanking, SenderStats};
+
+/// Maximum number of recent search queries kept by a store
+pub const MAX_RECENT_SEARCHES: usize = 50;
 
 /// A raw message pending processing
 ///
@@ -51,6 +64,22 @@ pub struct MessageMetadata {
     pub has_body_text: bool,
     /// Whether HTML body exists in blob storage
     pub has_body_html: bool,
+    /// Whether the message has at least one file attachment
+    pub has_attachments: bool,
+    /// Filenames of file attachments
+    pub attachment_names: Vec<String>,
+    /// Estimated size of the raw message in bytes
+    pub size_bytes: i64,
+    /// Unsubscribe targets from the List-Unsubscribe headers
+    pub unsubscribe: Option<Unsubscribe>,
+    /// Sender authentication results
+    pub auth_results: Option<AuthResults>,
+    /// Calendar invite carried by the message
+    pub invite: Option<EventInvite>,
+    /// Detected body language (ISO 639-3)
+    pub lang: Option<String>,
+    /// RFC 2822 Message-ID header
+    pub rfc_message_id: Option<String>,
 }
 
 impl MessageMetadata {
@@ -67,9 +96,18 @@ impl MessageMetadata {
             body_preview: self.body_preview,
             body_text: body.text,
             body_html: body.html,
+            attachment_text: body.attachment_text,
             received_at: self.received_at,
             internal_date: self.internal_date,
             label_ids: self.label_ids,
+            has_attachments: self.has_attachments,
+            attachment_names: self.attachment_names,
+            size_bytes: self.size_bytes,
+            unsubscribe: self.unsubscribe,
+            auth_results: self.auth_results,
+            invite: self.invite,
+            lang: self.lang,
+            rfc_message_id: self.rfc_message_id,
         }
     }
 }
@@ -90,8 +128,96 @@ impl From<&Message> for MessageMetadata {
             label_ids: msg.label_ids.clone(),
             has_body_text: msg.body_text.is_some(),
             has_body_html: msg.body_html.is_some(),
+            has_attachments: msg.has_attachments,
+            attachment_names: msg.attachment_names.clone(),
+            size_bytes: msg.size_bytes,
+            unsubscribe: msg.unsubscribe.clone(),
+            auth_results: msg.auth_results.clone(),
+            invite: msg.invite.clone(),
+            lang: msg.lang.clone(),
+            rfc_message_id: msg.rfc_message_id.clone(),
+        }
+    }
+}
+
+/// Compute thread properties from its messages
+///
+/// The subject and sender come from the first message, the snippet from the
+/// latest; participants are the distinct senders in order. Messages received
+/// at the same time are ordered by ID, so the result doesn't depend on the
+/// order of `messages`. Returns None if `messages` is empty.
+pub(crate) fn summarize_thread(
+    thread_id: &ThreadId,
+    account_id: i64,
+    messages: &[&MessageMetadata],
+) -> Option<Thread> {
+    let order = |a: &&&MessageMetadata, b: &&&MessageMetadata| {
+        a.received_at
+            .cmp(&b.received_at)
+            .then_with(|| a.id.as_str().cmp(b.id.as_str()))
+    };
+    let latest = messages.iter().max_by(order)?;
+    let first = messages.iter().min_by(order)?;
+
+    let subject = if first.subject.is_empty() {
+        "(no subject)".to_string()
+    } else {
+        first.subject.clone()
+    };
+
+    let is_unread = messages
+        .iter()
+        .any(|m| m.label_ids.iter().any(|l| l == LabelId::UNREAD));
+
+    let mut thread = Thread::new(
+        thread_id.clone(),
+        account_id,
+        subject,
+        latest.body_preview.clone(),
+        latest.received_at,
+        messages.len(),
+        first.from.name.clone(),
+        first.from.email.clone(),
+        is_unread,
+    );
+
+    let mut ordered: Vec<&&MessageMetadata> = messages.iter().collect();
+    ordered.sort_by(order);
+    for message in ordered {
+        thread.add_participant(&message.from, false);
+    }
+
+    Some(thread)
+}
+
+/// Recreate the missing threads of the given messages from their messages
+///
+/// Returns the IDs of the threads rebuilt.
+pub(crate) fn rebuild_threads(
+    store: &dyn MailStore,
+    message_ids: &[MessageId],
+) -> Result<Vec<ThreadId>> {
+    let mut thread_ids: Vec<ThreadId> = Vec::new();
+    for id in message_ids {
+        if let Some(message) = store.get_message_metadata(id)?
+            && !thread_ids.contains(&message.thread_id)
+        {
+            thread_ids.push(message.thread_id);
         }
     }
+
+    for thread_id in &thread_ids {
+        let messages = store.list_messages_for_thread(thread_id)?;
+        let Some(account_id) = messages.first().map(|m| m.account_id) else {
+            continue;
+        };
+        let refs: Vec<&MessageMetadata> = messages.iter().collect();
+        if let Some(thread) = summarize_thread(thread_id, account_id, &refs) {
+            store.upsert_thread(thread)?;
+        }
+    }
+
+    Ok(thread_ids)
 }
 
 /// Message body content (loaded separately from metadata)
@@ -101,6 +227,8 @@ pub struct MessageBody {
     pub text: Option<String>,
     /// Full HTML body content
     pub html: Option<String>,
+    /// Text extracted from attachments
+    pub attachment_text: Option<String>,
 }
 
 impl MessageBody {
@@ -113,15 +241,15 @@ impl MessageBody {
     pub fn text(text: String) -> Self {
         Self {
             text: Some(text),
-            html: None,
+            ..Self::default()
         }
     }
 
     /// Create a body with just HTML
     pub fn html(html: String) -> Self {
         Self {
-            text: None,
             html: Some(html),
+            ..Self::default()
         }
     }
 
@@ -130,8 +258,153 @@ impl MessageBody {
         Self {
             text: Some(text),
             html: Some(html),
+            ..Self::default()
+        }
+    }
+}
+
+/// Keyset position in a thread list
+///
+/// Thread lists are ordered by `(last_message_at DESC, thread_id ASC)`. A cursor
+/// records the last row of a page so the next page can start strictly after it,
+/// which keeps pages stable while new mail arrives and avoids OFFSET scans.
+///
+/// The cursor is opaque to callers: use `encode`/`decode` to hand it across
+/// process or FFI boundaries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadCursor {
+    pub(crate) last_message_at: DateTime<Utc>,
+    pub(crate) thread_id: ThreadId,
+}
+
+impl ThreadCursor {
+    /// Create a cursor positioned at the given thread
+    pub fn for_thread(thread: &Thread) -> Self {
+        Self {
+            last_message_at: thread.last_message_at,
+            thread_id: thread.id.clone(),
         }
     }
+
+    /// Encode the cursor as an opaque URL-safe token
+    pub fn encode(&self) -> String {
+        use base64::prelude::*;
+
+        let raw = format!("{}|{}", self.last_message_at.to_rfc3339(), self.thread_id.as_str());
+        BASE64_URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    /// Decode a token produced by `encode`
+    pub fn decode(token: &str) -> Result<Self> {
+        use base64::prelude::*;
+
+        let bytes = BASE64_URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|e| anyhow::anyhow!("Invalid thread cursor: {}", e))?;
+        let raw = String::from_utf8(bytes)
+            .map_err(|e| anyhow::anyhow!("Invalid thread cursor: {}", e))?;
+        let (ts, thread_id) = raw
+            .split_once('|')
+            .ok_or_else(|| anyhow::anyhow!("Invalid thread cursor: missing separator"))?;
+        let last_message_at = DateTime::parse_from_rfc3339(ts)
+            .map_err(|e| anyhow::anyhow!("Invalid thread cursor: {}", e))?
+            .with_timezone(&Utc);
+
+        Ok(Self {
+            last_message_at,
+            thread_id: ThreadId::new(thread_id),
+        })
+    }
+}
+
+/// Storage used by one type of content
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContentUsage {
+    /// Number of stored items
+    pub count: usize,
+    /// Bytes used on disk, after compression
+    pub stored_bytes: u64,
+    /// Uncompressed bytes of the items whose original size is known
+    pub original_bytes: u64,
+    /// Stored bytes of those same items
+    pub measured_bytes: u64,
+}
+
+impl ContentUsage {
+    /// Uncompressed size over stored size
+    ///
+    /// Only items with a known original size count. Returns None when there
+    /// are none.
+    pub fn compression_ratio(&self) -> Option<f64> {
+        (self.measured_bytes > 0).then(|| self.original_bytes as f64 / self.measured_bytes as f64)
+    }
+
+    /// Add another usage to this one
+    pub fn add(&mut self, other: ContentUsage) {
+        self.count += other.count;
+        self.stored_bytes += other.stored_bytes;
+        self.original_bytes += other.original_bytes;
+        self.measured_bytes += other.measured_bytes;
+    }
+}
+
+/// Disk usage of a store
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StorageStats {
+    /// Size of the database file (0 for stores without one)
+    pub database_bytes: u64,
+    /// Content usage by type, covering bodies in the database and blob files
+    pub content: HashMap<ContentType, ContentUsage>,
+}
+
+impl StorageStats {
+    /// Usage for one content type (zero when nothing is stored)
+    pub fn usage(&self, content_type: ContentType) -> ContentUsage {
+        self.content.get(&content_type).copied().unwrap_or_default()
+    }
+}
+
+/// Result of `MailStore::compact`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompactionReport {
+    /// Database size before compaction
+    pub database_bytes_before: u64,
+    /// Database size after compaction
+    pub database_bytes_after: u64,
+    /// Bodies recompressed at the archival level
+    pub recompressed: usize,
+    /// Blobs removed because their message no longer exists
+    pub orphaned_blobs_removed: usize,
+}
+
+/// Inconsistencies found by an integrity check
+///
+/// After a repair, lists what was fixed or removed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntegrityReport {
+    /// Messages whose thread row is missing
+    pub messages_without_threads: Vec<MessageId>,
+    /// Threads missing from the store but still in the thread-label index
+    pub thread_labels_without_threads: Vec<ThreadId>,
+    /// Blobs whose message is missing
+    pub blobs_without_messages: Vec<BlobKey>,
+    /// Search documents whose message is missing
+    pub search_documents_without_messages: Vec<MessageId>,
+}
+
+impl IntegrityReport {
+    /// Total number of problems found
+    pub fn issue_count(&self) -> usize {
+        self.messages_without_threads.len()
+            + self.thread_labels_without_threads.len()
+            + self.blobs_without_messages.len()
+            + self.search_documents_without_messages.len()
+    }
+
+    /// Whether no problems were found
+    pub fn is_clean(&self) -> bool {
+        self.issue_count() == 0
+    }
 }
 
 /// Trait for mail storage operations
@@ -168,6 +441,56 @@ pub trait MailStore: Send + Sync {
     /// Use this when you already have metadata and just need the body.
     fn get_message_body(&self, id: &MessageId) -> Result<Option<MessageBody>>;
 
+    /// Get a cached translation of a message's body into `lang`
+    fn get_translation(&self, id: &MessageId, lang: &str) -> Result<Option<String>>;
+
+    /// Cache a translation of a message's body into `lang`
+    ///
+    /// Translations are removed along with their message.
+    fn put_translation(&self, id: &MessageId, lang: &str, text: &str) -> Result<()>;
+
+    /// Get a thread's cached summary, if it was written from `source_key`
+    fn get_cached_summary(&self, thread_id: &ThreadId, source_key: &str) -> Result<Option<String>>;
+
+    /// Cache a thread summary, replacing any previous one
+    ///
+    /// `source_key` identifies the messages the summary was written from.
+    fn cache_summary(&self, thread_id: &ThreadId, source_key: &str, summary: &str) -> Result<()>;
+
+    // === Open Tracking ===
+
+    /// Remember the beacon token embedded in a sent message
+    fn save_tracking_token(&self, token: &str, message_id: &MessageId) -> Result<()>;
+
+    /// Record an open of the message carrying `token`
+    ///
+    /// Returns false if the token is unknown or the open was already recorded.
+    fn record_message_open(&self, token: &str, opened_at: DateTime<Utc>) -> Result<bool>;
+
+    /// Get the opens recorded for a message
+    ///
+    /// Returns None if the message wasn't sent with a beacon.
+    fn get_open_status(&self, message_id: &MessageId) -> Result<Option<OpenStatus>>;
+
+    /// Get the time of the most recent recorded open, across all messages
+    fn latest_message_open(&self) -> Result<Option<DateTime<Utc>>>;
+
+    // === Follow-up Reminders ===
+
+    /// Get the dismiss/snooze state of a thread's follow-up reminder
+    fn get_followup_state(&self, thread_id: &ThreadId) -> Result<Option<FollowupState>>;
+
+    /// Save a thread's follow-up reminder state, replacing any previous one
+    fn save_followup_state(&self, state: &FollowupState) -> Result<()>;
+
+    // === Thread Notes ===
+
+    /// Get the user's note on a thread
+    fn get_note(&self, thread_id: &ThreadId) -> Result<Option<ThreadNote>>;
+
+    /// Set the user's note on a thread; blank Markdown removes the note
+    fn set_note(&self, thread_id: &ThreadId, markdown: &str) -> Result<()>;
+
     /// List threads, ordered by last_message_at descending
     fn list_threads(&self, limit: usize, offset: usize) -> Result<Vec<Thread>>;
 
@@ -195,6 +518,25 @@ pub trait MailStore: Send + Sync {
         thread_id: &ThreadId,
     ) -> Result<Vec<Message>>;
 
+    /// List metadata for messages with unsubscribe targets, newest first
+    ///
+    /// Optionally filtered to one account.
+    fn list_unsubscribable_messages(&self, account_id: Option<i64>)
+    -> Result<Vec<MessageMetadata>>;
+
+    /// List message metadata newest first, optionally filtered by label
+    /// and account
+    ///
+    /// Backs the one-row-per-message list shown when conversation view is
+    /// off. Messages received at the same time are ordered by ID.
+    fn list_messages_for_account(
+        &self,
+        label: Option<&str>,
+        account_id: Option<i64>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<MessageMetadata>>;
+
     /// Check if a message exists
     fn has_message(&self, id: &MessageId) -> Result<bool>;
 
@@ -210,6 +552,19 @@ pub trait MailStore: Send + Sync {
     /// Count messages in a thread
     fn count_messages_in_thread(&self, thread_id: &ThreadId) -> Result<usize>;
 
+    /// When a thread's earliest message was received, or None if it has no
+    /// stored messages
+    ///
+    /// Lets sync fold a new message into a stored thread's aggregates
+    /// without reading the thread's other messages.
+    fn first_message_at(&self, thread_id: &ThreadId) -> Result<Option<DateTime<Utc>>>;
+
+    /// Gmail internal date (milliseconds since the epoch) of an account's
+    /// newest stored message, or `None` if it has none
+    ///
+    /// Where a backfill resumes when the history API can't say what changed.
+    fn latest_internal_date(&self, account_id: i64) -> Result<Option<i64>>;
+
     /// Clear all data (for testing)
     fn clear(&self) -> Result<()>;
 
@@ -237,6 +592,20 @@ pub trait MailStore: Send + Sync {
     /// Used for batch operations like archiving all messages in a thread.
     fn get_message_ids_for_thread(&self, thread_id: &ThreadId) -> Result<Vec<MessageId>>;
 
+    /// Get the repeat deliveries among a thread's messages
+    ///
+    /// Returns `(duplicate, canonical)` pairs, ordered by duplicate ID: each
+    /// message sharing its RFC Message-ID with an earlier-received message
+    /// of the same account, and that earliest copy. The canonical copy may
+    /// be in another thread.
+    fn list_duplicate_messages(&self, thread_id: &ThreadId)
+    -> Result<Vec<(MessageId, MessageId)>>;
+
+    /// Get the IDs of all of an account's stored messages
+    ///
+    /// Used to reconcile the store with the server's message list.
+    fn list_message_ids(&self, account_id: i64) -> Result<Vec<MessageId>>;
+
     /// Update labels on a message
     ///
     /// Replaces the entire label_ids array on the message.
@@ -249,6 +618,15 @@ pub trait MailStore: Send + Sync {
     /// in the thread, the thread is also deleted.
     fn delete_message(&self, message_id: &MessageId) -> Result<()>;
 
+    /// Move a message to another thread, as Gmail does when it rethreads
+    /// mail after a subject or References change
+    ///
+    /// The destination thread must already be stored. The old thread is
+    /// recounted, or deleted if this was its last message; its other
+    /// aggregates are left for the caller to recompute. Does nothing if the
+    /// message isn't stored or is already in `thread_id`.
+    fn move_message(&self, message_id: &MessageId, thread_id: &ThreadId) -> Result<()>;
+
     // === Phase 4: Pending Message Queue (Decoupled Fetch/Process) ===
 
     /// Store a raw message for deferred processing
@@ -288,6 +666,20 @@ pub trait MailStore: Send + Sync {
     /// Clear all pending messages
     fn clear_pending_messages(&self) -> Result<()>;
 
+    // === Re-fetch Queue ===
+
+    /// Queue stored messages to be fetched from Gmail again
+    ///
+    /// Used when their stored content was lost, e.g. a corrupt blob. Ids of
+    /// messages that aren't stored are ignored.
+    fn mark_for_refetch(&self, ids: &[MessageId]) -> Result<()>;
+
+    /// Messages of an account queued for re-fetch
+    fn list_refetch_messages(&self, account_id: i64) -> Result<Vec<MessageId>>;
+
+    /// Remove a message from the re-fetch queue once it has been stored again
+    fn clear_refetch(&self, id: &MessageId) -> Result<()>;
+
     // === Multi-Account Support Methods ===
 
     /// Register a new account
@@ -316,6 +708,51 @@ pub trait MailStore: Send + Sync {
     /// Stores the JSON-serialized token data for the account.
     fn update_account_token(&self, account_id: i64, token_data: Option<String>) -> Result<()>;
 
+    /// Update an account's display name, color and signature
+    fn update_account_settings(&self, account_id: i64, settings: AccountSettings) -> Result<()>;
+
+    /// Pause (`false`) or resume (`true`) syncing an account
+    fn set_account_sync_enabled(&self, account_id: i64, enabled: bool) -> Result<()>;
+
+    /// Replace an account's send-as aliases with those fetched from Gmail
+    fn replace_send_as_aliases(&self, account_id: i64, aliases: Vec<SendAsAlias>) -> Result<()>;
+
+    /// List an account's send-as aliases, default first, then by email
+    fn list_send_as_aliases(&self, account_id: i64) -> Result<Vec<SendAsAlias>>;
+
+    /// Replace an account's user labels with those fetched from Gmail
+    ///
+    /// Local overrides are kept, and apply again if the label comes back.
+    fn replace_labels(&self, account_id: i64, labels: Vec<Label>) -> Result<()>;
+
+    /// List an account's user labels by name
+    ///
+    /// Only IDs, names and Gmail colors are stored; counts are left at zero.
+    /// A local icon or color override replaces the one from Gmail.
+    fn list_labels(&self, account_id: i64) -> Result<Vec<Label>>;
+
+    /// User labels on each of `thread_ids`, by name, for thread row chips
+    ///
+    /// One lookup for a whole page of threads. Only labels in the account's
+    /// stored label list are returned, so system labels are left out; threads
+    /// without user labels have no entry.
+    fn list_user_labels_for_threads(
+        &self,
+        thread_ids: &[ThreadId],
+    ) -> Result<HashMap<ThreadId, Vec<Label>>>;
+
+    /// Set the icon and color a label is shown with on this machine
+    ///
+    /// `None` falls back to the default icon or the Gmail color; passing
+    /// `None` for both removes the override.
+    fn set_label_override(
+        &self,
+        account_id: i64,
+        label_id: &str,
+        icon: Option<String>,
+        color: Option<LabelColor>,
+    ) -> Result<()>;
+
     /// List threads with optional account filter
     ///
     /// If `account_id` is None, returns threads from all accounts (unified view).
@@ -339,6 +776,33 @@ pub trait MailStore: Send + Sync {
         offset: usize,
     ) -> Result<Vec<Thread>>;
 
+    /// List threads after a keyset cursor
+    ///
+    /// Returns up to `limit` threads ordered by `(last_message_at DESC, thread_id ASC)`,
+    /// starting strictly after `after` (or from the top when None). Optionally
+    /// filtered by label and/or account.
+    fn list_threads_after(
+        &self,
+        label: Option<&str>,
+        account_id: Option<i64>,
+        after: Option<&ThreadCursor>,
+        limit: usize,
+    ) -> Result<Vec<Thread>>;
+
+    /// List threads carrying all of `labels` and none of `excluded_labels`
+    ///
+    /// Same ordering and keyset pagination as `list_threads_after`, keyed by
+    /// the first label. Used for inbox category tabs. `labels` must not be
+    /// empty.
+    fn list_threads_with_labels_after(
+        &self,
+        labels: &[&str],
+        excluded_labels: &[&str],
+        account_id: Option<i64>,
+        after: Option<&ThreadCursor>,
+        limit: usize,
+    ) -> Result<Vec<Thread>>;
+
     /// Count threads with optional account filter
     fn count_threads_for_account(&self, account_id: Option<i64>) -> Result<usize>;
 
@@ -356,9 +820,182 @@ pub trait MailStore: Send + Sync {
         account_id: Option<i64>,
     ) -> Result<usize>;
 
+    /// Count unread threads for every label in one pass, by label ID
+    ///
+    /// Labels without unread threads are left out.
+    fn count_unread_threads_per_label(
+        &self,
+        account_id: Option<i64>,
+    ) -> Result<HashMap<String, usize>>;
+
+    /// Count threads carrying all of `labels` and none of `excluded_labels`
+    fn count_threads_with_labels(
+        &self,
+        labels: &[&str],
+        excluded_labels: &[&str],
+        account_id: Option<i64>,
+    ) -> Result<usize>;
+
+    /// Count unread threads carrying all of `labels` and none of `excluded_labels`
+    fn count_unread_threads_with_labels(
+        &self,
+        labels: &[&str],
+        excluded_labels: &[&str],
+        account_id: Option<i64>,
+    ) -> Result<usize>;
+
     /// Clear all data for a specific account
     ///
-    /// Removes threads, messages, pending messages, and sync state for the account,
-    /// but keeps the account record itself.
+    /// Removes the account's threads, messages, labels, blobs, pending
+    /// messages and sync state in one transaction, but keeps the account
+    /// record itself. Other accounts' data is untouched. Search documents
+    /// live outside the store; see [`crate::clear_account`].
     fn clear_account_data(&self, account_id: i64) -> Result<()>;
+
+    // === Saved Searches ===
+
+    /// Insert or update a saved search
+    ///
+    /// A search with `id` 0 is inserted and returned with its assigned ID;
+    /// otherwise the existing search with that ID is updated.
+    fn save_search(&self, search: SavedSearch) -> Result<SavedSearch>;
+
+    /// List saved searches, pinned first, then by name
+    fn list_saved_searches(&self) -> Result<Vec<SavedSearch>>;
+
+    /// Delete a saved search by ID
+    fn delete_saved_search(&self, id: i64) -> Result<()>;
+
+    // === Rules ===
+
+    /// Insert or update a rule
+    ///
+    /// A rule with `id` 0 is inserted and returned with its assigned ID;
+    /// otherwise the existing rule with that ID is updated.
+    fn save_rule(&self, rule: Rule) -> Result<Rule>;
+
+    /// List rules in evaluation order (creation order)
+    fn list_rules(&self) -> Result<Vec<Rule>>;
+
+    /// Delete a rule by ID
+    fn delete_rule(&self, id: i64) -> Result<()>;
+
+    // === Offline Action Queue ===
+
+    /// Queue a label change to send to Gmail later
+    ///
+    /// Returns the action with its assigned ID.
+    fn queue_action(&self, action: QueuedAction) -> Result<QueuedAction>;
+
+    /// List an account's queued actions, oldest first
+    fn list_queued_actions(&self, account_id: i64) -> Result<Vec<QueuedAction>>;
+
+    /// Remove a queued action once Gmail has accepted or rejected it
+    fn delete_queued_action(&self, id: i64) -> Result<()>;
+
+    // === Outbox ===
+
+    /// Add a message to the outbox
+    ///
+    /// Returns the message with its assigned ID.
+    fn add_outbox_message(&self, message: OutboxMessage) -> Result<OutboxMessage>;
+
+    /// Save an outbox message's delivery state
+    ///
+    /// Updates the state, attempts, last error, next attempt and sent
+    /// message ID; the message itself doesn't change once queued.
+    fn update_outbox_message(&self, message: &OutboxMessage) -> Result<()>;
+
+    /// Get an outbox message by ID
+    fn get_outbox_message(&self, id: i64) -> Result<Option<OutboxMessage>>;
+
+    /// List outbox messages, oldest first, for one account or all
+    fn list_outbox_messages(&self, account_id: Option<i64>) -> Result<Vec<OutboxMessage>>;
+
+    /// Remove a message from the outbox
+    fn delete_outbox_message(&self, id: i64) -> Result<()>;
+
+    // === Analytics ===
+
+    /// Per-sender totals for received (non-SENT) messages
+    ///
+    /// Senders are grouped by lowercase address and ordered by `ranking`,
+    /// ties broken by the other measure, then address.
+    fn sender_stats(
+        &self,
+        account_id: Option<i64>,
+        ranking: SenderRanking,
+        limit: usize,
+    ) -> Result<Vec<SenderStats>>;
+
+    /// Received (non-SENT) messages per UTC day from `since`, oldest first
+    ///
+    /// Days without messages are omitted.
+    fn daily_message_counts(
+        &self,
+        account_id: Option<i64>,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<DailyVolume>>;
+
+    /// Reply delays in milliseconds for replies sent from `since`
+    ///
+    /// A reply is a SENT message whose immediately preceding message in the
+    /// thread was received; the delay is the gap between the two.
+    fn reply_delays_ms(&self, account_id: Option<i64>, since: DateTime<Utc>) -> Result<Vec<i64>>;
+
+    /// Message count and total size per label, largest first, ties by label ID
+    fn label_storage(&self, account_id: Option<i64>) -> Result<Vec<LabelStorage>>;
+
+    /// Threads with the largest total message size, largest first, ties by
+    /// thread ID
+    fn largest_threads(&self, account_id: Option<i64>, limit: usize)
+    -> Result<Vec<(ThreadId, i64)>>;
+
+    // === Storage Maintenance ===
+
+    /// Report disk usage by content type, with compression ratios
+    fn storage_stats(&self) -> Result<StorageStats>;
+
+    /// Reclaim disk space
+    ///
+    /// Recompresses bodies of messages received before `older_than` at a
+    /// higher compression level, removes blobs whose message no longer exists,
+    /// then vacuums the database.
+    fn compact(&self, older_than: DateTime<Utc>) -> Result<CompactionReport>;
+
+    /// Find orphaned rows and blobs
+    ///
+    /// Search documents live outside the store, so
+    /// `search_documents_without_messages` is left empty; see
+    /// `integrity::check_integrity` for a check that covers the index too.
+    fn check_integrity(&self) -> Result<IntegrityReport>;
+
+    /// Fix what `check_integrity` finds
+    ///
+    /// Threads missing under existing messages are rebuilt from those
+    /// messages; stale thread-label rows and orphaned blobs are removed.
+    /// Returns the problems that were repaired.
+    fn repair(&self) -> Result<IntegrityReport>;
+
+    // === Recent Searches ===
+
+    /// Record a submitted search query
+    ///
+    /// Re-running a query moves it to the front. Only the newest
+    /// `MAX_RECENT_SEARCHES` queries are kept.
+    fn record_recent_search(&self, query: &str) -> Result<()>;
+
+    /// List recent search queries, newest first
+    fn list_recent_searches(&self, limit: usize) -> Result<Vec<String>>;
+
+    /// Forget all recent search queries
+    fn clear_recent_searches(&self) -> Result<()>;
+
+    // === Change Notifications ===
+
+    /// Subscribe to store change events
+    ///
+    /// Each call returns a new receiver. Events are published after the
+    /// corresponding mutation is committed. Dropping the receiver unsubscribes.
+    fn subscribe(&self) -> Receiver<StoreEvent>;
 }
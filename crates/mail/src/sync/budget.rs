@@ -0,0 +1,186 @@
+//! Network budgets for syncs
+//!
+//! An initial sync downloads the whole mailbox as fast as Gmail allows,
+//! which saturates a tethered or metered connection. A [`NetworkBudget`]
+//! caps how many messages are downloaded at once and how much one sync
+//! downloads; the fetch phase stops at its next checkpoint once the budget
+//! is spent, and the next sync resumes from there.
+
+use log::debug;
+
+const MB: u64 = 1024 * 1024;
+
+/// Megabytes one sync downloads on a metered connection unless configured
+pub const METERED_MAX_MB_PER_SYNC: u64 = 25;
+
+/// Messages downloaded at once on a metered connection unless configured
+pub const METERED_MAX_CONCURRENT_FETCHES: usize = 5;
+
+/// Error returned by a sync that stopped because its network budget ran out
+///
+/// Like a cancelled sync, it can resume later from its last checkpoint.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Sync used its network budget of {} MB; the rest syncs next time",
+    .max_bytes.div_ceil(MB)
+)]
+pub struct NetworkBudgetSpentError {
+    pub max_bytes: u64,
+}
+
+/// Limits on the network use of one sync
+///
+/// The default budget is unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NetworkBudget {
+    /// Most bytes of messages one sync downloads
+    pub max_bytes: Option<u64>,
+    /// Most messages requested at once
+    pub max_concurrent_fetches: Option<usize>,
+}
+
+impl NetworkBudget {
+    /// Budget from the shared config
+    ///
+    /// Limits the settings leave unset fall back to the metered defaults
+    /// when the connection is metered (as configured, or as detected).
+    pub fn from_settings(settings: &config::NetworkSettings) -> Self {
+        let metered = settings
+            .metered
+            .or_else(is_metered_connection)
+            .unwrap_or(false);
+        Self::for_connection(settings, metered)
+    }
+
+    fn for_connection(settings: &config::NetworkSettings, metered: bool) -> Self {
+        let (max_mb, max_concurrent_fetches) = if metered {
+            debug!("Connection is metered, limiting sync downloads");
+            (
+                settings.max_mb_per_sync.or(Some(METERED_MAX_MB_PER_SYNC)),
+                settings
+                    .max_concurrent_fetches
+                    .or(Some(METERED_MAX_CONCURRENT_FETCHES)),
+            )
+        } else {
+            (settings.max_mb_per_sync, settings.max_concurrent_fetches)
+        };
+        Self {
+            max_bytes: max_mb.map(|mb| mb.saturating_mul(MB)),
+            max_concurrent_fetches,
+        }
+    }
+
+    /// Whether a sync that downloaded `bytes` has used up the budget
+    pub fn is_spent(&self, bytes: u64) -> bool {
+        self.max_bytes.is_some_and(|max_bytes| bytes >= max_bytes)
+    }
+
+    /// Fail with [`NetworkBudgetSpentError`] once `bytes` use up the budget
+    pub fn check(&self, bytes: u64) -> anyhow::Result<()> {
+        match self.max_bytes {
+            Some(max_bytes) if bytes >= max_bytes => {
+                Err(NetworkBudgetSpentError { max_bytes }.into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Whether the current connection is metered, if the platform says
+///
+/// Asks NetworkManager on Linux; other platforms don't report it, so
+/// configure `network.metered` there.
+pub fn is_metered_connection() -> Option<bool> {
+    #[cfg(target_os = "linux")]
+    {
+        network_manager_metered()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// NetworkManager's `Metered` property, which covers guesses such as a
+/// phone's hotspot
+#[cfg(target_os = "linux")]
+fn network_manager_metered() -> Option<bool> {
+    let output = std::process::Command::new("busctl")
+        .args([
+            "get-property",
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+            "Metered",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_metered(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse busctl's `u <NMMetered>` output
+#[cfg(target_os = "linux")]
+fn parse_metered(output: &str) -> Option<bool> {
+    match output.trim().strip_prefix("u ")?.parse::<u32>().ok()? {
+        // Yes, guessed yes
+        1 | 3 => Some(true),
+        // No, guessed no
+        2 | 4 => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metered_connections_get_default_limits() {
+        let settings = config::NetworkSettings {
+            max_concurrent_fetches: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            NetworkBudget::for_connection(&settings, false),
+            NetworkBudget {
+                max_bytes: None,
+                max_concurrent_fetches: Some(2),
+            }
+        );
+        assert_eq!(
+            NetworkBudget::for_connection(&settings, true),
+            NetworkBudget {
+                max_bytes: Some(METERED_MAX_MB_PER_SYNC * MB),
+                max_concurrent_fetches: Some(2),
+            }
+        );
+    }
+
+    #[test]
+    fn test_budget_check() {
+        let budget = NetworkBudget {
+            max_bytes: Some(MB),
+            ..Default::default()
+        };
+        assert!(budget.check(MB - 1).is_ok());
+        let err = budget.check(MB).unwrap_err();
+        assert!(err.downcast_ref::<NetworkBudgetSpentError>().is_some());
+        assert_eq!(
+            err.to_string(),
+            "Sync used its network budget of 1 MB; the rest syncs next time"
+        );
+        assert!(NetworkBudget::default().check(u64::MAX).is_ok());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_metered() {
+        assert_eq!(parse_metered("u 1\n"), Some(true));
+        assert_eq!(parse_metered("u 4\n"), Some(false));
+        assert_eq!(parse_metered("u 0\n"), None);
+        assert_eq!(parse_metered(""), None);
+    }
+}
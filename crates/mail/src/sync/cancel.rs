@@ -0,0 +1,58 @@
+//! Cooperative cancellation for long-running syncs
+//!
+//! The sync engine checks the flag between pages and batches, so a
+//! cancelled sync stops at the next checkpoint and can resume later.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Error returned by a sync that stopped because it was cancelled
+#[derive(Debug, thiserror::Error)]
+#[error("Sync cancelled")]
+pub struct SyncCancelledError;
+
+/// Shared flag for cancelling a sync from another thread
+///
+/// Clones share the flag. The default flag is never cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct SyncCancel(Arc<AtomicBool>);
+
+impl SyncCancel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the sync to stop at its next checkpoint
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Fail with [`SyncCancelledError`] if cancellation was requested
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.is_cancelled() {
+            return Err(SyncCancelledError.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_share_the_flag() {
+        let cancel = SyncCancel::new();
+        let other = cancel.clone();
+        assert!(other.check().is_ok());
+
+        cancel.cancel();
+        assert!(other.is_cancelled());
+        let err = other.check().unwrap_err();
+        assert!(err.downcast_ref::<SyncCancelledError>().is_some());
+    }
+}
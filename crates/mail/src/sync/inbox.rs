@@ -6,14 +6,39 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use log::{info, warn};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use std::time::Instant;
-
-use crate::gmail::{api::GmailMessage, normalize_message, GmailClient, HistoryExpiredError};
-use crate::models::{LabelId, Message, MessageId, SyncState, Thread, ThreadId};
+use std::time::{Duration, Instant};
+
+use crate::diagnostics::{DiagnosticKind, record_diagnostic};
+use crate::gmail::{
+    api::{GmailMessage, HistoryResponse, MessageRef}, http_status, is_rate_limited, normalize_message, ApiCall,
+    ChunkSizer, GmailApi, HistoryExpiredError, QuotaMeter,
+};
+use crate::models::{Category, LabelId, Message, MessageId, Rule, SyncState, Thread, ThreadId};
+use crate::rules::{RuleMatch, apply_rules};
 use crate::search::SearchIndex;
-use crate::storage::{MailStore, MessageMetadata};
+use crate::storage::{MailStore, MessageMetadata, summarize_thread};
+use crate::telemetry;
+use super::budget::NetworkBudget;
+use super::cancel::SyncCancel;
+
+/// Pending messages at which fetching waits for processing to catch up
+pub const DEFAULT_MAX_PENDING: usize = 10_000;
+
+/// How long fetching waits on a full queue that isn't shrinking before
+/// handing back to the caller to process it
+const PENDING_STALL_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Interval between pending queue checks while fetching waits
+const PENDING_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How far before the newest stored message a backfill starts listing
+///
+/// Gmail's `after:` search has one-second resolution and internal dates
+/// aren't strictly in delivery order, so the backfill overlaps what's
+/// stored and skips the messages it already has.
+const BACKFILL_OVERLAP_SECS: i64 = 24 * 60 * 60;
 
 /// The action that should be taken when syncing
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -158,7 +183,7 @@ pub fn get_sync_state_info(sync_state: Option<&SyncState>) -> SyncStateInfo {
 }
 
 /// Options for sync operation
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct SyncOptions {
     /// Maximum messages to fetch in initial sync
     pub max_messages: Option<usize>,
@@ -166,6 +191,49 @@ pub struct SyncOptions {
     pub full_resync: bool,
     /// Optional search index for incremental indexing during sync
     pub search_index: Option<Arc<SearchIndex>>,
+    /// Stops the sync at its next page or batch when cancelled
+    pub cancel: SyncCancel,
+    /// Pending messages at which the fetch phase stops listing until
+    /// processing catches up; 0 disables the cap
+    pub max_pending: usize,
+    /// Limits on what the fetch phase downloads
+    pub network: NetworkBudget,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        Self {
+            max_messages: None,
+            full_resync: false,
+            search_index: None,
+            cancel: SyncCancel::default(),
+            max_pending: DEFAULT_MAX_PENDING,
+            network: NetworkBudget::default(),
+        }
+    }
+}
+
+impl SyncOptions {
+    /// Apply an account's sync settings from the shared Cosmos config
+    pub fn with_settings(mut self, settings: &config::SyncSettings) -> Self {
+        if settings.max_messages.is_some() {
+            self.max_messages = settings.max_messages;
+        }
+        if let Some(max_pending) = settings.max_pending {
+            self.max_pending = max_pending;
+        }
+        self
+    }
+
+    /// Apply the shared config's network limits, detecting a metered
+    /// connection if the settings don't say
+    ///
+    /// Detection may run a short system query, so call this off the UI
+    /// thread.
+    pub fn with_network(mut self, settings: &config::NetworkSettings) -> Self {
+        self.network = NetworkBudget::from_settings(settings);
+        self
+    }
 }
 
 /// Statistics from a sync operation
@@ -193,6 +261,10 @@ pub struct SyncStats {
     pub duration_ms: u64,
     /// Timing breakdown for performance analysis
     pub timing: SyncTiming,
+    /// Gmail quota units spent, per call type, and the current burn rate
+    pub quota: QuotaMeter,
+    /// Bytes of messages downloaded by the fetch phase
+    pub bytes_fetched: u64,
 }
 
 /// Detailed timing breakdown for sync operations
@@ -222,6 +294,25 @@ pub struct SyncTiming {
     pub history_ms: u64,
 }
 
+impl SyncTiming {
+    /// Each phase's name and time (ms), for reporting as metrics
+    pub fn phases(&self) -> [(&'static str, u64); 11] {
+        [
+            ("initial_sync", self.initial_sync_ms),
+            ("incremental_sync", self.incremental_sync_ms),
+            ("profile", self.profile_ms),
+            ("list_messages", self.list_messages_ms),
+            ("fetch_messages", self.fetch_messages_ms),
+            ("normalize", self.normalize_ms),
+            ("storage", self.storage_ms),
+            ("compute_thread", self.compute_thread_ms),
+            ("has_message", self.has_message_ms),
+            ("search_index", self.search_index_ms),
+            ("history", self.history_ms),
+        ]
+    }
+}
+
 // Keep backward compatibility with Phase 1 API
 impl SyncStats {
     /// Total messages stored (created + updated) for backward compatibility
@@ -241,7 +332,9 @@ impl SyncStats {
 /// - **Page token checkpointing**: Message listing can resume from where it left off.
 /// - **Failed ID retry**: Messages that failed to fetch are retried on next sync.
 /// - **Stale state detection**: History IDs older than 5 days trigger a proactive full resync.
-/// - **History expired handling**: 404/400 from Gmail History API triggers full resync.
+/// - **History expired handling**: 404/400 from Gmail History API triggers a
+///   reconcile that adds missing messages and removes deleted ones, keeping
+///   local data in place.
 /// - **Catch-up sync retry**: After initial sync, catch-up is retried up to 3 times.
 ///
 /// # Arguments
@@ -250,7 +343,7 @@ impl SyncStats {
 /// * `account_id` - Account ID (FK to accounts table)
 /// * `options` - Sync options
 pub fn sync_gmail(
-    gmail: &GmailClient,
+    gmail: &dyn GmailApi,
     store: &dyn MailStore,
     account_id: i64,
     options: SyncOptions,
@@ -263,7 +356,47 @@ pub fn sync_gmail(
 /// Same as `sync_gmail` but with a progress callback for UI updates.
 /// The callback receives (messages_fetched, phase_description).
 pub fn sync_gmail_with_progress<F>(
-    gmail: &GmailClient,
+    gmail: &dyn GmailApi,
+    store: &dyn MailStore,
+    account_id: i64,
+    options: SyncOptions,
+    on_progress: F,
+) -> Result<SyncStats>
+where
+    F: Fn(usize, &str),
+{
+    record_diagnostic(DiagnosticKind::Sync, Some(account_id), "Sync started");
+
+    let result = run_sync(gmail, store, account_id, options, on_progress);
+    match &result {
+        Ok(stats) => telemetry::record_sync(stats),
+        Err(e) => telemetry::record_sync_failure(e),
+    }
+    match &result {
+        Ok(stats) => record_diagnostic(
+            DiagnosticKind::Sync,
+            Some(account_id),
+            format!(
+                "Sync completed in {}ms (incremental={}, created={}, updated={}, errors={})",
+                stats.duration_ms,
+                stats.was_incremental,
+                stats.messages_created,
+                stats.messages_updated,
+                stats.errors
+            ),
+        ),
+        Err(e) => record_diagnostic(
+            DiagnosticKind::Sync,
+            Some(account_id),
+            format!("Sync failed: {:#}", e),
+        ),
+    }
+    result
+}
+
+/// Run a sync, choosing between full, resumed, and incremental modes
+fn run_sync<F>(
+    gmail: &dyn GmailApi,
     store: &dyn MailStore,
     account_id: i64,
     options: SyncOptions,
@@ -284,8 +417,7 @@ where
         _ if options.full_resync => {
             on_progress(0, "Starting full resync...");
             info!("Full resync requested, clearing existing data...");
-            store.clear_mail_data()?;
-            store.delete_sync_state(account_id)?;
+            store.clear_account_data(account_id)?;
             initial_sync_with_progress(gmail, store, account_id, &options, &on_progress)?
         }
         // Incomplete initial sync - resume it
@@ -311,8 +443,12 @@ where
                     "Sync state is {} days old (history_id may expire), performing full resync",
                     age.num_days()
                 );
-                store.clear_mail_data()?;
-                store.delete_sync_state(account_id)?;
+                record_diagnostic(
+                    DiagnosticKind::Sync,
+                    Some(account_id),
+                    format!("Sync state {} days old, full resync", age.num_days()),
+                );
+                store.clear_account_data(account_id)?;
                 initial_sync_with_progress(gmail, store, account_id, &options, &on_progress)?
             } else {
                 on_progress(0, "Checking for new messages...");
@@ -320,12 +456,16 @@ where
                 match incremental_sync(gmail, store, &state, &options) {
                     Ok(stats) => stats,
                     Err(e) if e.downcast_ref::<HistoryExpiredError>().is_some() => {
-                        // History ID expired, fall back to full resync
-                        on_progress(0, "History expired, resyncing...");
-                        warn!("History ID expired (404/400 from Gmail), performing full resync");
-                        store.clear_mail_data()?;
-                        store.delete_sync_state(account_id)?;
-                        initial_sync_with_progress(gmail, store, account_id, &options, &on_progress)?
+                        // History ID expired, reconcile with the server's
+                        // message list instead of clearing and resyncing
+                        on_progress(0, "History expired, reconciling...");
+                        warn!("History ID expired (404/400 from Gmail), reconciling with server");
+                        record_diagnostic(
+                            DiagnosticKind::Sync,
+                            Some(account_id),
+                            "History ID expired, reconciling with server",
+                        );
+                        reconcile_sync(gmail, store, &state, &options)?
                     }
                     Err(e) => return Err(e),
                 }
@@ -345,7 +485,7 @@ where
 
 /// Perform initial full sync using decoupled fetch/process phases (no progress callback)
 fn initial_sync(
-    gmail: &GmailClient,
+    gmail: &dyn GmailApi,
     store: &dyn MailStore,
     account_id: i64,
     options: &SyncOptions,
@@ -360,7 +500,7 @@ fn initial_sync(
 ///
 /// After completing, runs an incremental catch-up sync for messages that arrived during sync.
 fn initial_sync_with_progress<F>(
-    gmail: &GmailClient,
+    gmail: &dyn GmailApi,
     store: &dyn MailStore,
     account_id: i64,
     options: &SyncOptions,
@@ -392,6 +532,7 @@ where
             let profile_start = Instant::now();
             let history_id = get_current_history_id(gmail)?;
             stats.timing.profile_ms += profile_start.elapsed().as_millis() as u64;
+            stats.quota.record(ApiCall::GetProfile, 1);
             log::debug!("Got history_id: {} in {}ms", history_id, profile_start.elapsed().as_millis());
             info!("Starting initial sync from history_id: {}", history_id);
 
@@ -407,23 +548,31 @@ where
     log::debug!("Phase 1: Fetching messages from Gmail...");
     info!("[SYNC] Phase 1: Fetching messages from Gmail...");
     on_progress(0, "Fetching messages...");
-    let fetch_stats = fetch_phase_with_progress(gmail, store, account_id, options, &mut stats, on_progress)?;
-    log::debug!("Phase 1 complete: {} fetched, {} pending", fetch_stats.fetched, fetch_stats.pending);
-    info!("[SYNC] Fetch phase complete: {} fetched, {} pending, {} skipped, {} failed",
-        fetch_stats.fetched, fetch_stats.pending, fetch_stats.skipped, fetch_stats.failed_ids.len());
-
-    // === PHASE 2: PROCESS ===
-    // Process pending messages: INBOX first, then the rest
-    let pending_count = store.count_pending_messages(account_id, None)?;
-    info!("[SYNC] Phase 2: Processing {} pending messages (INBOX first)...", pending_count);
-    on_progress(stats.messages_fetched, &format!("Processing {} messages...", pending_count));
-
-    if pending_count == 0 {
-        info!("[SYNC] No pending messages to process, skipping process phase");
-    } else {
-        process_phase_with_progress(store, account_id, options, &mut stats, on_progress)?;
-        info!("[SYNC] Process phase complete: {} messages created, {} threads",
-            stats.messages_created, stats.threads_created + stats.threads_updated);
+    // A fetch that fills the pending queue stops early; process what it
+    // queued, then fetch the rest
+    loop {
+        let fetch_stats = fetch_phase_with_progress(gmail, store, account_id, options, &mut stats, on_progress)?;
+        log::debug!("Phase 1 complete: {} fetched, {} pending", fetch_stats.fetched, fetch_stats.pending);
+        info!("[SYNC] Fetch phase complete: {} fetched, {} pending, {} skipped, {} failed",
+            fetch_stats.fetched, fetch_stats.pending, fetch_stats.skipped, fetch_stats.failed_ids.len());
+
+        // === PHASE 2: PROCESS ===
+        // Process pending messages: INBOX first, then the rest
+        let pending_count = store.count_pending_messages(account_id, None)?;
+        info!("[SYNC] Phase 2: Processing {} pending messages (INBOX first)...", pending_count);
+        on_progress(stats.messages_fetched, &format!("Processing {} messages...", pending_count));
+
+        if pending_count == 0 {
+            info!("[SYNC] No pending messages to process, skipping process phase");
+        } else {
+            process_phase_with_progress(store, account_id, options, &mut stats, on_progress)?;
+            info!("[SYNC] Process phase complete: {} messages created, {} threads",
+                stats.messages_created, stats.threads_created + stats.threads_updated);
+        }
+
+        if !fetch_stats.backlogged {
+            break;
+        }
     }
 
     // Mark initial sync as complete with the history_id we captured at the start
@@ -461,6 +610,10 @@ where
 
     while catchup_attempt < max_catchup_retries && !catchup_success {
         catchup_attempt += 1;
+        // The initial sync is already saved; skipping catch-up is safe
+        if options.cancel.is_cancelled() {
+            break;
+        }
 
         match incremental_sync(gmail, store, &complete_state, options) {
             Ok(catchup_stats) => {
@@ -485,6 +638,7 @@ where
                 stats.timing.storage_ms += catchup_stats.timing.storage_ms;
                 stats.timing.compute_thread_ms += catchup_stats.timing.compute_thread_ms;
                 stats.timing.search_index_ms += catchup_stats.timing.search_index_ms;
+                stats.quota.merge(&catchup_stats.quota);
                 catchup_success = true;
             }
             Err(e) => {
@@ -503,6 +657,11 @@ where
                         "Catch-up sync failed after {} attempts (non-fatal): {}",
                         max_catchup_retries, e
                     );
+                    record_diagnostic(
+                        DiagnosticKind::Sync,
+                        Some(account_id),
+                        format!("Catch-up sync failed: {}", e),
+                    );
                 }
             }
         }
@@ -522,11 +681,14 @@ pub struct FetchPhaseStats {
     pub skipped: usize,
     /// Message IDs that failed to fetch (will be retried next sync)
     pub failed_ids: Vec<String>,
+    /// Listing stopped early because the pending queue is full and nothing
+    /// is processing it; process the queue, then fetch again to resume
+    pub backlogged: bool,
 }
 
 /// Phase 1: Fetch messages from Gmail as fast as possible (no progress callback)
 pub fn fetch_phase(
-    gmail: &GmailClient,
+    gmail: &dyn GmailApi,
     store: &dyn MailStore,
     account_id: i64,
     options: &SyncOptions,
@@ -546,11 +708,17 @@ pub fn fetch_phase(
 ///   If sync is interrupted, it will resume from the last saved page token.
 /// - **Failed ID tracking**: Messages that fail to fetch (non-retriable errors) are
 ///   recorded and will be retried on the next sync attempt.
+/// - **Backpressure**: Once `options.max_pending` messages are pending, listing
+///   waits for processing to shrink the queue. If nothing does, it stops with
+///   `backlogged` set; process the queue and call again to resume.
+/// - **Network budget**: Downloads are capped at `options.network`'s
+///   concurrency, and the phase fails with `NetworkBudgetSpentError` at the
+///   next checkpoint once the sync has downloaded its budget.
 ///
 /// Call this from a background thread, then call `process_pending_batch` repeatedly
 /// to process messages with UI updates between batches.
 pub fn fetch_phase_with_progress<F>(
-    gmail: &GmailClient,
+    gmail: &dyn GmailApi,
     store: &dyn MailStore,
     account_id: i64,
     options: &SyncOptions,
@@ -566,6 +734,7 @@ where
         pending: 0,
         skipped: 0,
         failed_ids: Vec::new(),
+        backlogged: false,
     };
 
     // Load existing sync state to get resume position and failed IDs
@@ -594,6 +763,10 @@ where
     };
 
     let batch_size = 500; // Gmail API max is 500 per page
+    let mut chunk_sizer = match options.network.max_concurrent_fetches {
+        Some(max) => ChunkSizer::capped(max),
+        None => ChunkSizer::default(),
+    };
 
     // First, retry any previously failed message IDs
     if !previous_failed_ids.is_empty() {
@@ -608,6 +781,8 @@ where
             store,
             account_id,
             &failed_ids_to_retry,
+            options,
+            &mut chunk_sizer,
             stats,
         );
         fetch_stats.fetched += retry_failed.fetched;
@@ -617,6 +792,10 @@ where
     }
 
     loop {
+        // Each page is checkpointed, so a cancelled fetch resumes from here
+        options.cancel.check()?;
+        options.network.check(stats.bytes_fetched)?;
+
         // Check if we've hit the limit
         if let Some(max) = options.max_messages {
             if total_listed >= max {
@@ -624,6 +803,13 @@ where
             }
         }
 
+        // Let processing catch up before queueing more
+        if !wait_for_pending_room(store, account_id, options)? {
+            info!("Pending queue full, pausing fetch at {} listed", total_listed);
+            fetch_stats.backlogged = true;
+            break;
+        }
+
         // Limit batch size if we have a max_messages constraint
         let effective_batch_size = if let Some(max) = options.max_messages {
             batch_size.min(max - total_listed)
@@ -640,6 +826,7 @@ where
             None,
         )?;
         stats.timing.list_messages_ms += list_start.elapsed().as_millis() as u64;
+        stats.quota.record(ApiCall::ListMessages, 1);
 
         let message_refs = list_response.messages.unwrap_or_default();
         log::debug!("Listed {} messages in {}ms", message_refs.len(), list_start.elapsed().as_millis());
@@ -674,12 +861,27 @@ where
         stats.messages_fetched += message_refs.len();
 
         if !to_fetch.is_empty() {
-            let batch_result = fetch_message_batch(gmail, store, account_id, &to_fetch, stats);
+            let batch_result = fetch_message_batch(
+                gmail,
+                store,
+                account_id,
+                &to_fetch,
+                options,
+                &mut chunk_sizer,
+                stats,
+            );
             fetch_stats.fetched += batch_result.fetched;
             fetch_stats.pending += batch_result.pending;
             fetch_stats.failed_ids.extend(batch_result.failed_ids);
         }
 
+        // A page cut short isn't checkpointed; resuming lists it again and
+        // skips the messages already pending
+        options.cancel.check()?;
+        options.network.check(stats.bytes_fetched)?;
+
+        telemetry::record_pending(store.count_pending_messages(account_id, None)?);
+
         // Report progress after each page
         on_progress(
             fetch_stats.fetched,
@@ -706,9 +908,11 @@ where
         }
     }
 
-    // Clear page token in final state (listing complete)
+    // Clear page token in final state (listing complete), or keep it for
+    // the next call if the queue cut listing short
     if let Some(ref state) = existing_state {
-        let final_state = state.clone().with_fetch_progress(None, total_listed)
+        let resume_token = page_token.filter(|_| fetch_stats.backlogged);
+        let final_state = state.clone().with_fetch_progress(resume_token, total_listed)
             .with_failed_ids(fetch_stats.failed_ids.clone());
         store.save_sync_state(final_state)?;
     }
@@ -723,6 +927,45 @@ where
     Ok(fetch_stats)
 }
 
+/// Wait until the pending queue is below `options.max_pending`
+///
+/// Returns false if the queue stayed full without shrinking for
+/// [`PENDING_STALL_TIMEOUT`], i.e. nothing is processing it alongside the
+/// fetch.
+fn wait_for_pending_room(
+    store: &dyn MailStore,
+    account_id: i64,
+    options: &SyncOptions,
+) -> Result<bool> {
+    if options.max_pending == 0 {
+        return Ok(true);
+    }
+
+    let mut pending = store.count_pending_messages(account_id, None)?;
+    if pending < options.max_pending {
+        return Ok(true);
+    }
+    telemetry::record_backpressure();
+
+    let mut last_progress = Instant::now();
+    loop {
+        options.cancel.check()?;
+        std::thread::sleep(PENDING_POLL_INTERVAL);
+
+        let now_pending = store.count_pending_messages(account_id, None)?;
+        telemetry::record_pending(now_pending);
+        if now_pending < options.max_pending {
+            return Ok(true);
+        }
+        if now_pending < pending {
+            last_progress = Instant::now();
+        } else if last_progress.elapsed() >= PENDING_STALL_TIMEOUT {
+            return Ok(false);
+        }
+        pending = now_pending;
+    }
+}
+
 /// Helper struct for batch fetch results
 struct BatchFetchResult {
     fetched: usize,
@@ -730,12 +973,29 @@ struct BatchFetchResult {
     failed_ids: Vec<String>,
 }
 
+/// Download supported attachments and attach their text to the message
+#[cfg(feature = "attachment-text")]
+fn extract_attachment_text(gmail: &dyn GmailApi, gmail_msg: &mut GmailMessage) {
+    crate::attachment_text::fill_attachment_text(gmail, gmail_msg);
+}
+
+/// Attachment text extraction is disabled without the `attachment-text` feature
+#[cfg(not(feature = "attachment-text"))]
+fn extract_attachment_text(_gmail: &dyn GmailApi, _gmail_msg: &mut GmailMessage) {}
+
 /// Fetch a batch of messages and store them as pending
+///
+/// Messages are requested in chunks sized by `chunk_sizer`, which shrinks
+/// them when Gmail rate limits a chunk. Chunks that would push the quota
+/// burn rate over budget wait until it drops, and none start once the
+/// sync's network budget is spent.
 fn fetch_message_batch(
-    gmail: &GmailClient,
+    gmail: &dyn GmailApi,
     store: &dyn MailStore,
     account_id: i64,
     to_fetch: &[MessageId],
+    options: &SyncOptions,
+    chunk_sizer: &mut ChunkSizer,
     stats: &mut SyncStats,
 ) -> BatchFetchResult {
     let mut result = BatchFetchResult {
@@ -744,25 +1004,54 @@ fn fetch_message_batch(
         failed_ids: Vec::new(),
     };
 
-    // Gmail batch API has aggressive rate limiting independent of quota
-    // 25 messages per batch with no delay works reliably
-    let chunk_size = 25;
-    for chunk in to_fetch.chunks(chunk_size) {
+    // Gmail batch API has aggressive rate limiting independent of quota, so
+    // chunks start small and only grow while nothing is rate limited
+    let mut remaining = to_fetch;
+    while !remaining.is_empty() {
+        // Unfetched messages are picked up again when the sync resumes
+        if options.cancel.is_cancelled() || options.network.is_spent(stats.bytes_fetched) {
+            break;
+        }
+        let (chunk, rest) = remaining.split_at(chunk_sizer.size().min(remaining.len()));
+        remaining = rest;
+
+        let delay = if gmail.has_quota() {
+            stats.quota.delay_for(ApiCall::GetMessage, chunk.len())
+        } else {
+            Duration::ZERO
+        };
+        if !delay.is_zero() {
+            log::debug!("Quota burn rate near limit, waiting {}ms", delay.as_millis());
+            std::thread::sleep(delay);
+        }
+
         let fetch_start = Instant::now();
         let results = gmail.get_messages_batch(chunk);
         stats.timing.fetch_messages_ms += fetch_start.elapsed().as_millis() as u64;
+        stats.quota.record(ApiCall::GetMessage, chunk.len());
+        let rate_limited = results
+            .iter()
+            .any(|result| result.as_ref().is_err_and(is_rate_limited));
+        chunk_sizer.adjust(rate_limited, stats.quota.burn_rate());
 
         // Store immediately after each chunk
         let store_start = Instant::now();
         for (msg_id, fetch_result) in chunk.iter().zip(results) {
             match fetch_result {
-                Ok(gmail_msg) => {
+                Ok(mut gmail_msg) => {
+                    extract_attachment_text(gmail, &mut gmail_msg);
                     let label_ids = gmail_msg.label_ids.clone().unwrap_or_default();
 
                     match serde_json::to_vec(&gmail_msg) {
                         Ok(data) => {
+                            stats.bytes_fetched += data.len() as u64;
                             if let Err(e) = store.store_pending_message(msg_id, account_id, &data, label_ids) {
                                 warn!("Failed to store pending message {}: {}", msg_id.as_str(), e);
+                                record_diagnostic(
+                                    DiagnosticKind::Storage,
+                                    Some(account_id),
+                                    format!("Failed to store pending message: {}", e),
+                                );
                                 stats.errors += 1;
                                 result.failed_ids.push(msg_id.as_str().to_string());
                             } else {
@@ -778,11 +1067,10 @@ fn fetch_message_batch(
                     }
                 }
                 Err(e) => {
-                    let error_msg = e.to_string();
                     // Only track as failed if it's potentially recoverable
                     // 404 might be a permanently deleted message, but we'll retry once
                     // to be sure (could be a transient issue)
-                    if error_msg.contains("404") {
+                    if http_status(&e) == Some(404) {
                         warn!("Message {} not found (404), will retry once: {}", msg_id.as_str(), e);
                         result.failed_ids.push(msg_id.as_str().to_string());
                     } else {
@@ -810,12 +1098,22 @@ pub struct ProcessBatchResult {
     pub errors: usize,
     /// Whether there are more messages to process
     pub has_more: bool,
+    /// New messages that matched rules, for pushing label changes to Gmail
+    /// (see [`push_rule_changes`](crate::rules::push_rule_changes)) and
+    /// raising notifications
+    pub rule_matches: Vec<RuleMatch>,
 }
 
 /// Process a single batch of pending messages (INBOX first)
 ///
 /// Returns after processing up to `batch_size` messages, allowing the caller
 /// to update the UI between batches. Call repeatedly until `has_more` is false.
+///
+/// Enabled rules for the account are applied to messages not yet in the
+/// store before they are saved; their matches are returned in the result.
+///
+/// Indexed mail is committed as the search config's commit policy allows,
+/// and always once the queue is empty.
 pub fn process_pending_batch(
     store: &dyn MailStore,
     account_id: i64,
@@ -823,6 +1121,7 @@ pub fn process_pending_batch(
     stats: &mut SyncStats,
     batch_size: usize,
 ) -> Result<ProcessBatchResult> {
+    options.cancel.check()?;
     let mut result = ProcessBatchResult::default();
 
     // Get next batch of pending messages (INBOX prioritized automatically)
@@ -836,6 +1135,18 @@ pub fn process_pending_batch(
 
     let mut threads_seen: HashSet<ThreadId> = HashSet::new();
 
+    // A broken rule table shouldn't stall sync, so fall back to no rules
+    let rules: Vec<Rule> = match store.list_rules() {
+        Ok(rules) => rules
+            .into_iter()
+            .filter(|r| r.enabled && r.account_id.is_none_or(|id| id == account_id))
+            .collect(),
+        Err(e) => {
+            warn!("Failed to load rules: {}", e);
+            Vec::new()
+        }
+    };
+
     for pending_msg in pending {
         // Deserialize the raw Gmail message
         let gmail_msg: GmailMessage = match serde_json::from_slice(&pending_msg.data) {
@@ -850,7 +1161,7 @@ pub fn process_pending_batch(
         };
 
         // Normalize
-        let message = match normalize_message(gmail_msg, account_id) {
+        let mut message = match normalize_message(gmail_msg, account_id) {
             Ok(msg) => msg,
             Err(e) => {
                 warn!("Failed to normalize message: {}", e);
@@ -860,6 +1171,15 @@ pub fn process_pending_batch(
                 continue;
             }
         };
+        Category::assign(&mut message);
+
+        // Rules only act on mail seen for the first time, so re-syncing
+        // doesn't undo a user moving a message back
+        if !store.has_message(&message.id)?
+            && let Some(rule_match) = apply_rules(&rules, &mut message)
+        {
+            result.rule_matches.push(rule_match);
+        }
 
         let thread_id = message.thread_id.clone();
         let is_new_thread = !store.has_thread(&thread_id)?;
@@ -894,15 +1214,14 @@ pub fn process_pending_batch(
         store.delete_pending_message(&pending_msg.id)?;
     }
 
-    // Commit search index after batch
-    if let Some(ref index) = options.search_index {
-        if let Err(e) = index.commit() {
-            warn!("Failed to commit search index: {}", e);
-        }
-    }
-
     result.remaining = store.count_pending_messages(account_id, None)?;
     result.has_more = result.remaining > 0;
+    telemetry::record_pending(result.remaining);
+
+    // Commit when the policy says so, and always after the last batch
+    if let Some(ref index) = options.search_index {
+        commit_search_index(index, account_id, !result.has_more);
+    }
 
     Ok(result)
 }
@@ -942,6 +1261,8 @@ where
     let mut search_index_us: u64 = 0;
 
     loop {
+        options.cancel.check()?;
+
         // Get next batch of pending messages (INBOX prioritized automatically)
         let pending = store.get_pending_messages(account_id, None, process_batch_size)?;
 
@@ -963,7 +1284,7 @@ where
 
             // Normalize
             let normalize_start = Instant::now();
-            let message = match normalize_message(gmail_msg, account_id) {
+            let mut message = match normalize_message(gmail_msg, account_id) {
                 Ok(msg) => msg,
                 Err(e) => {
                     warn!("Failed to normalize message: {}", e);
@@ -972,6 +1293,7 @@ where
                     continue;
                 }
             };
+            Category::assign(&mut message);
             normalize_us += normalize_start.elapsed().as_micros() as u64;
 
             let thread_id = message.thread_id.clone();
@@ -1013,23 +1335,29 @@ where
             store.delete_pending_message(&pending_msg.id)?;
         }
 
-        // Commit search index after each batch
+        // Commit search index when enough has built up
         if let Some(ref index) = options.search_index {
             let commit_start = Instant::now();
-            if let Err(e) = index.commit() {
-                warn!("Failed to commit search index: {}", e);
-            }
+            commit_search_index(index, account_id, false);
             search_index_us += commit_start.elapsed().as_millis() as u64 * 1000;
         }
 
         // Report progress after each batch
         let remaining = store.count_pending_messages(account_id, None)?;
+        telemetry::record_pending(remaining);
         on_progress(
             stats.messages_created,
             &format!("Processed {} messages ({} remaining)...", stats.messages_created, remaining)
         );
     }
 
+    // Make the rest of the indexed mail searchable
+    if let Some(ref index) = options.search_index {
+        let commit_start = Instant::now();
+        commit_search_index(index, account_id, true);
+        search_index_us += commit_start.elapsed().as_millis() as u64 * 1000;
+    }
+
     // Convert microseconds to milliseconds
     stats.timing.normalize_ms += normalize_us / 1000;
     stats.timing.storage_ms += storage_us / 1000;
@@ -1039,12 +1367,326 @@ where
     Ok(())
 }
 
+/// Commit the search index, or with `force` unset only when its commit
+/// policy says enough has been indexed
+///
+/// Failures are logged and recorded rather than failing the sync.
+fn commit_search_index(index: &SearchIndex, account_id: i64, force: bool) {
+    let result = if force {
+        index.commit()
+    } else {
+        index.commit_if_due().map(|_| ())
+    };
+    if let Err(e) = result {
+        warn!("Failed to commit search index: {}", e);
+        record_diagnostic(
+            DiagnosticKind::Storage,
+            Some(account_id),
+            format!("Search index commit failed: {}", e),
+        );
+    }
+}
+
 /// Get the current history ID from Gmail
-fn get_current_history_id(gmail: &GmailClient) -> Result<String> {
+pub(super) fn get_current_history_id(gmail: &dyn GmailApi) -> Result<String> {
     let profile = gmail.get_profile()?;
     Ok(profile.history_id)
 }
 
+/// Whether a history listing looks like it's missing records
+///
+/// Gmail's history IDs increase with every change but skip values, so a
+/// jump between IDs alone is normal. Records at or before
+/// `start_history_id`, out of order, or past the listing's own history ID
+/// mean some of what happened in between can't be trusted to be there.
+pub fn history_has_gap(start_history_id: &str, history: &HistoryResponse) -> bool {
+    let parse = |id: &str| id.parse::<u64>().ok();
+    let Some(mut previous) = parse(start_history_id) else {
+        return true;
+    };
+    for record in history.history.iter().flatten() {
+        match parse(&record.id) {
+            Some(id) if id > previous => previous = id,
+            _ => return true,
+        }
+    }
+    history
+        .history_id
+        .as_deref()
+        .is_some_and(|id| parse(id).is_none_or(|id| id < previous))
+}
+
+/// Fetch an account's messages received since about its newest stored one
+///
+/// Messages already stored or pending are skipped, and each page is
+/// processed before the next is listed. Returns how many messages failed
+/// to fetch.
+fn backfill_messages(
+    gmail: &dyn GmailApi,
+    store: &dyn MailStore,
+    account_id: i64,
+    options: &SyncOptions,
+    stats: &mut SyncStats,
+) -> Result<usize> {
+    let after_secs = store
+        .latest_internal_date(account_id)?
+        .map_or(0, |millis| millis / 1000 - BACKFILL_OVERLAP_SECS);
+    info!("Backfilling messages received after {}", after_secs);
+
+    let mut chunk_sizer = match options.network.max_concurrent_fetches {
+        Some(max) => ChunkSizer::capped(max),
+        None => ChunkSizer::default(),
+    };
+    let mut failed = 0;
+    let mut page_token: Option<String> = None;
+
+    loop {
+        options.cancel.check()?;
+        options.network.check(stats.bytes_fetched)?;
+
+        let list_start = Instant::now();
+        let list_response = gmail.list_messages_after(after_secs, 500, page_token.as_deref())?;
+        stats.timing.list_messages_ms += list_start.elapsed().as_millis() as u64;
+        stats.quota.record(ApiCall::ListMessages, 1);
+
+        let message_refs = list_response.messages.unwrap_or_default();
+        failed += fetch_missing(
+            gmail,
+            store,
+            account_id,
+            &message_refs,
+            options,
+            &mut chunk_sizer,
+            stats,
+        )?;
+
+        match list_response.next_page_token {
+            Some(token) => page_token = Some(token),
+            None => break,
+        }
+    }
+
+    if failed > 0 {
+        warn!("Backfill could not fetch {} messages", failed);
+    }
+    Ok(failed)
+}
+
+/// Fetch and process the listed messages that aren't stored or pending
+///
+/// Returns how many failed to fetch.
+fn fetch_missing(
+    gmail: &dyn GmailApi,
+    store: &dyn MailStore,
+    account_id: i64,
+    message_refs: &[MessageRef],
+    options: &SyncOptions,
+    chunk_sizer: &mut ChunkSizer,
+    stats: &mut SyncStats,
+) -> Result<usize> {
+    let has_msg_start = Instant::now();
+    let mut to_fetch: Vec<MessageId> = Vec::new();
+    for msg_ref in message_refs {
+        let msg_id = MessageId::new(&msg_ref.id);
+        if store.has_message(&msg_id)? || store.has_pending_message(&msg_id)? {
+            stats.messages_skipped += 1;
+        } else {
+            to_fetch.push(msg_id);
+        }
+    }
+    stats.timing.has_message_ms += has_msg_start.elapsed().as_millis() as u64;
+    stats.messages_fetched += to_fetch.len();
+
+    if to_fetch.is_empty() {
+        return Ok(0);
+    }
+    let batch_result = fetch_message_batch(
+        gmail,
+        store,
+        account_id,
+        &to_fetch,
+        options,
+        chunk_sizer,
+        stats,
+    );
+    while process_pending_batch(store, account_id, options, stats, 100)?.has_more {}
+    Ok(batch_result.failed_ids.len())
+}
+
+/// Catch up an account whose history Gmail no longer has, without resyncing
+///
+/// Instead of clearing the account and downloading the whole mailbox again,
+/// fetches only the messages received since about the newest stored one,
+/// then continues incremental syncs from Gmail's current history ID. Label
+/// changes and deletions from the lost history aren't recovered; a full
+/// resync picks those up.
+///
+/// If any message fails to fetch, the sync state is left as it was so the
+/// next sync backfills again.
+pub fn backfill_sync(
+    gmail: &dyn GmailApi,
+    store: &dyn MailStore,
+    state: &SyncState,
+    options: &SyncOptions,
+) -> Result<SyncStats> {
+    let sync_start = Instant::now();
+    let mut stats = SyncStats {
+        was_incremental: true,
+        ..Default::default()
+    };
+
+    // Taken before listing, so anything that changes during the backfill
+    // is in the next incremental sync's history
+    let profile_start = Instant::now();
+    let history_id = get_current_history_id(gmail)?;
+    stats.timing.profile_ms = profile_start.elapsed().as_millis() as u64;
+    stats.quota.record(ApiCall::GetProfile, 1);
+
+    let failed = backfill_messages(gmail, store, state.account_id, options, &mut stats)?;
+    if failed == 0 {
+        store.save_sync_state(state.clone().updated(history_id))?;
+    }
+
+    stats.timing.incremental_sync_ms = sync_start.elapsed().as_millis() as u64;
+    info!(
+        "Backfill sync: {} messages in {}ms",
+        stats.messages_created, stats.timing.incremental_sync_ms
+    );
+
+    Ok(stats)
+}
+
+/// Bring an account in line with Gmail's message list without clearing it
+///
+/// Used when Gmail no longer has the account's history. Lists every
+/// message on the server, fetches the ones missing locally and removes
+/// stored messages the server no longer has. Everything else (bodies,
+/// threads, search documents) is kept, so the mailbox stays readable while
+/// this runs. Label changes to kept messages aren't recovered.
+///
+/// Nothing is removed unless the whole list was read, and the sync state
+/// keeps its old history ID if any message failed to fetch, so the next
+/// sync reconciles again.
+pub fn reconcile_sync(
+    gmail: &dyn GmailApi,
+    store: &dyn MailStore,
+    state: &SyncState,
+    options: &SyncOptions,
+) -> Result<SyncStats> {
+    let sync_start = Instant::now();
+    let account_id = state.account_id;
+    let mut stats = SyncStats {
+        was_incremental: true,
+        ..Default::default()
+    };
+
+    // Taken before listing, so anything that changes while listing is in
+    // the next incremental sync's history
+    let profile_start = Instant::now();
+    let history_id = get_current_history_id(gmail)?;
+    stats.timing.profile_ms = profile_start.elapsed().as_millis() as u64;
+    stats.quota.record(ApiCall::GetProfile, 1);
+
+    let mut chunk_sizer = match options.network.max_concurrent_fetches {
+        Some(max) => ChunkSizer::capped(max),
+        None => ChunkSizer::default(),
+    };
+    let mut server_ids: HashSet<String> = HashSet::new();
+    let mut failed = 0;
+    let mut listed_all = true;
+    let mut page_token: Option<String> = None;
+
+    loop {
+        options.cancel.check()?;
+        options.network.check(stats.bytes_fetched)?;
+
+        if options.max_messages.is_some_and(|max| server_ids.len() >= max) {
+            listed_all = false;
+            break;
+        }
+
+        let list_start = Instant::now();
+        let list_response = gmail.list_messages(500, page_token.as_deref(), None)?;
+        stats.timing.list_messages_ms += list_start.elapsed().as_millis() as u64;
+        stats.quota.record(ApiCall::ListMessages, 1);
+
+        let message_refs = list_response.messages.unwrap_or_default();
+        failed += fetch_missing(
+            gmail,
+            store,
+            account_id,
+            &message_refs,
+            options,
+            &mut chunk_sizer,
+            &mut stats,
+        )?;
+        server_ids.extend(message_refs.into_iter().map(|msg_ref| msg_ref.id));
+
+        match list_response.next_page_token {
+            Some(token) => page_token = Some(token),
+            None => break,
+        }
+    }
+
+    // Remove what was deleted on the server, then refresh the threads
+    // that lost messages
+    if listed_all {
+        let mut threads_to_update: HashSet<ThreadId> = HashSet::new();
+        for msg_id in store.list_message_ids(account_id)? {
+            if server_ids.contains(msg_id.as_str()) {
+                continue;
+            }
+            if let Some(msg) = store.get_message(&msg_id)? {
+                threads_to_update.insert(msg.thread_id);
+            }
+            store.delete_message(&msg_id)?;
+            if let Some(ref index) = options.search_index
+                && let Err(e) = index.delete_message(&msg_id)
+            {
+                warn!("Failed to remove message {} from index: {}", msg_id.as_str(), e);
+            }
+            stats.messages_updated += 1; // Count deletions as updates
+        }
+
+        for thread_id in threads_to_update {
+            if store.get_message_ids_for_thread(&thread_id)?.is_empty() {
+                if let Some(ref index) = options.search_index
+                    && let Err(e) = index.delete_thread(&thread_id)
+                {
+                    warn!("Failed to remove thread {} from index: {}", thread_id.as_str(), e);
+                }
+                continue;
+            }
+            if store.has_thread(&thread_id)? {
+                let thread = compute_thread(&thread_id, account_id, &[], store)?;
+                store.upsert_thread(thread)?;
+                stats.threads_updated += 1;
+            }
+        }
+    }
+
+    if let Some(ref index) = options.search_index {
+        commit_search_index(index, account_id, true);
+    }
+
+    if failed == 0 {
+        store.save_sync_state(state.clone().updated(history_id))?;
+    } else {
+        warn!("Reconcile could not fetch {} messages, will retry", failed);
+    }
+
+    stats.timing.incremental_sync_ms = sync_start.elapsed().as_millis() as u64;
+    info!(
+        "Reconcile sync: {} added, {} removed of {} on the server in {}ms",
+        stats.messages_created,
+        stats.messages_updated,
+        server_ids.len(),
+        stats.timing.incremental_sync_ms
+    );
+
+    Ok(stats)
+}
+
 /// Perform incremental sync using Gmail History API
 ///
 /// Fetches changes since the last sync using the history_id from the sync state.
@@ -1059,7 +1701,7 @@ fn get_current_history_id(gmail: &GmailClient) -> Result<String> {
 /// # Returns
 /// Sync statistics or error (including HistoryExpiredError if history_id is too old)
 pub fn incremental_sync(
-    gmail: &GmailClient,
+    gmail: &dyn GmailApi,
     store: &dyn MailStore,
     state: &SyncState,
     options: &SyncOptions,
@@ -1070,17 +1712,35 @@ pub fn incremental_sync(
         ..Default::default()
     };
 
+    options.cancel.check()?;
+
     // Fetch history since last sync
     let history_start = Instant::now();
     let history = gmail
         .list_history_all(&state.history_id)
         .context("Failed to fetch history")?;
     stats.timing.history_ms = history_start.elapsed().as_millis() as u64;
+    // Pages aren't reported; a listing costs at least one
+    stats.quota.record(ApiCall::ListHistory, 1);
+
+    let history_gap = history_has_gap(&state.history_id, &history);
+    if history_gap {
+        warn!("History since {} looks incomplete, backfilling recent messages", state.history_id);
+        record_diagnostic(
+            DiagnosticKind::Sync,
+            Some(state.account_id),
+            "History incomplete, backfilling recent messages",
+        );
+    }
 
     // Collect message IDs to fetch (new messages)
     let mut message_ids_to_fetch: Vec<MessageId> = Vec::new();
     // Track threads that need updating due to label changes
     let mut threads_to_update: HashSet<ThreadId> = HashSet::new();
+    // Latest labels of already-indexed messages, grouped by thread for the search index
+    let mut label_updates: HashMap<ThreadId, HashMap<MessageId, Vec<String>>> = HashMap::new();
+    // Messages removed from the store that must also leave the search index
+    let mut deleted_message_ids: Vec<MessageId> = Vec::new();
 
     // Process history records
     // Track storage time in microseconds for per-message operations
@@ -1095,6 +1755,19 @@ pub fn incremental_sync(
                     // Only fetch if we don't already have it
                     if !store.has_message(&msg_id)? {
                         message_ids_to_fetch.push(msg_id);
+                    } else if let Some(mut msg) = store.get_message(&msg_id)?
+                        && let Some(old_thread_id) = rethread_message(
+                            store,
+                            &mut msg,
+                            &msg_added.message,
+                            state.account_id,
+                            options.search_index.as_deref(),
+                        )?
+                    {
+                        if let Some(updates) = label_updates.get_mut(&old_thread_id) {
+                            updates.remove(&msg_id);
+                        }
+                        threads_to_update.insert(old_thread_id);
                     }
                 }
             }
@@ -1106,8 +1779,12 @@ pub fn incremental_sync(
                     // Get thread ID before deletion for potential thread update
                     if let Some(msg) = store.get_message(&msg_id)? {
                         threads_to_update.insert(msg.thread_id.clone());
+                        if let Some(updates) = label_updates.get_mut(&msg.thread_id) {
+                            updates.remove(&msg_id);
+                        }
                     }
                     store.delete_message(&msg_id)?;
+                    deleted_message_ids.push(msg_id);
                     stats.messages_updated += 1; // Count deletions as updates
                 }
             }
@@ -1117,14 +1794,30 @@ pub fn incremental_sync(
                 for change in labels_added {
                     let msg_id = MessageId::new(&change.message.id);
                     if let Some(mut msg) = store.get_message(&msg_id)? {
+                        if let Some(old_thread_id) = rethread_message(
+                            store,
+                            &mut msg,
+                            &change.message,
+                            state.account_id,
+                            options.search_index.as_deref(),
+                        )? {
+                            if let Some(updates) = label_updates.get_mut(&old_thread_id) {
+                                updates.remove(&msg_id);
+                            }
+                            threads_to_update.insert(old_thread_id);
+                        }
                         // Add labels that aren't already present
                         for label in &change.label_ids {
                             if !msg.label_ids.contains(label) {
                                 msg.label_ids.push(label.clone());
                             }
                         }
-                        store.update_message_labels(&msg_id, msg.label_ids)?;
+                        store.update_message_labels(&msg_id, msg.label_ids.clone())?;
                         stats.labels_updated += 1;
+                        label_updates
+                            .entry(msg.thread_id.clone())
+                            .or_default()
+                            .insert(msg_id, msg.label_ids);
                         threads_to_update.insert(msg.thread_id);
                     }
                 }
@@ -1135,10 +1828,26 @@ pub fn incremental_sync(
                 for change in labels_removed {
                     let msg_id = MessageId::new(&change.message.id);
                     if let Some(mut msg) = store.get_message(&msg_id)? {
+                        if let Some(old_thread_id) = rethread_message(
+                            store,
+                            &mut msg,
+                            &change.message,
+                            state.account_id,
+                            options.search_index.as_deref(),
+                        )? {
+                            if let Some(updates) = label_updates.get_mut(&old_thread_id) {
+                                updates.remove(&msg_id);
+                            }
+                            threads_to_update.insert(old_thread_id);
+                        }
                         // Remove the specified labels
                         msg.label_ids.retain(|l| !change.label_ids.contains(l));
-                        store.update_message_labels(&msg_id, msg.label_ids)?;
+                        store.update_message_labels(&msg_id, msg.label_ids.clone())?;
                         stats.labels_updated += 1;
+                        label_updates
+                            .entry(msg.thread_id.clone())
+                            .or_default()
+                            .insert(msg_id, msg.label_ids);
                         threads_to_update.insert(msg.thread_id);
                     }
                 }
@@ -1146,8 +1855,35 @@ pub fn incremental_sync(
         }
     }
 
+    // Stored messages whose content was lost, e.g. to a corrupt blob
+    let mut refetch: HashSet<MessageId> = store
+        .list_refetch_messages(state.account_id)?
+        .into_iter()
+        .collect();
+    for msg_id in &refetch {
+        if !message_ids_to_fetch.contains(msg_id) {
+            message_ids_to_fetch.push(msg_id.clone());
+        }
+    }
+
     stats.messages_fetched = message_ids_to_fetch.len();
 
+    // Mirror deletions and label changes into the search index
+    if let Some(ref index) = options.search_index {
+        let index_start = Instant::now();
+        for msg_id in &deleted_message_ids {
+            if let Err(e) = index.delete_message(msg_id) {
+                warn!("Failed to remove message {} from index: {}", msg_id.as_str(), e);
+            }
+        }
+        for (thread_id, updates) in &label_updates {
+            if let Err(e) = index.update_labels(thread_id, updates) {
+                warn!("Failed to update index labels for thread {}: {}", thread_id.as_str(), e);
+            }
+        }
+        stats.timing.search_index_ms += index_start.elapsed().as_micros() as u64;
+    }
+
     // Track which threads we've seen for stats
     let mut threads_seen: HashSet<ThreadId> = HashSet::new();
 
@@ -1156,16 +1892,19 @@ pub fn incremental_sync(
         let fetch_start = Instant::now();
         let results = gmail.get_messages_batch(&message_ids_to_fetch);
         stats.timing.fetch_messages_ms += fetch_start.elapsed().as_millis() as u64;
+        stats.quota.record(ApiCall::GetMessage, message_ids_to_fetch.len());
 
         for result in results {
             match result {
-                Ok(gmail_msg) => {
+                Ok(mut gmail_msg) => {
+                    extract_attachment_text(gmail, &mut gmail_msg);
                     let normalize_start = Instant::now();
                     let normalize_result = normalize_message(gmail_msg, state.account_id);
                     stats.timing.normalize_ms += normalize_start.elapsed().as_micros() as u64;
 
                     match normalize_result {
-                        Ok(message) => {
+                        Ok(mut message) => {
+                            Category::assign(&mut message);
                             let thread_id = message.thread_id.clone();
                             let is_new_thread = !store.has_thread(&thread_id)?;
 
@@ -1180,8 +1919,13 @@ pub fn incremental_sync(
 
                             // Now store message (thread exists, FK constraint satisfied)
                             store.upsert_message(message.clone())?;
+                            if refetch.remove(&message.id) {
+                                store.clear_refetch(&message.id)?;
+                                stats.messages_updated += 1;
+                            } else {
+                                stats.messages_created += 1;
+                            }
                             storage_us += storage_start.elapsed().as_micros() as u64;
-                            stats.messages_created += 1;
 
                             // Index for search if index is provided
                             if let Some(ref index) = options.search_index {
@@ -1220,6 +1964,16 @@ pub fn incremental_sync(
 
     // Update threads affected by label changes (that weren't already updated)
     for thread_id in threads_to_update {
+        // A thread whose last message was deleted no longer has anything to search
+        if store.get_message_ids_for_thread(&thread_id)?.is_empty() {
+            if let Some(ref index) = options.search_index
+                && let Err(e) = index.delete_thread(&thread_id)
+            {
+                warn!("Failed to remove thread {} from index: {}", thread_id.as_str(), e);
+            }
+            continue;
+        }
+
         if store.has_thread(&thread_id)? {
             let compute_start = Instant::now();
             let thread = compute_thread(&thread_id, state.account_id, &[], store)?;
@@ -1235,23 +1989,29 @@ pub fn incremental_sync(
         }
     }
 
+    // Pick up new messages the history may have missed; keep the old
+    // history ID if some couldn't be fetched, so the next sync tries again
+    let backfill_failed = if history_gap {
+        backfill_messages(gmail, store, state.account_id, options, &mut stats)?
+    } else {
+        0
+    };
+
     // Commit search index
     if let Some(ref index) = options.search_index {
         let commit_start = Instant::now();
-        if let Err(e) = index.commit() {
-            warn!("Failed to commit search index: {}", e);
-        }
+        commit_search_index(index, state.account_id, true);
         stats.timing.search_index_ms += commit_start.elapsed().as_millis() as u64;
     }
 
     // Update sync state with new history ID
-    if let Some(new_history_id) = history.history_id {
+    if let Some(new_history_id) = history.history_id.filter(|_| backfill_failed == 0) {
         let updated_state = state.clone().updated(new_history_id);
         store.save_sync_state(updated_state)?;
     }
 
     // Convert microseconds to milliseconds for sub-ms operations
-    stats.timing.storage_ms = storage_us / 1000;
+    stats.timing.storage_ms += storage_us / 1000;
     stats.timing.normalize_ms /= 1000;
     stats.timing.compute_thread_ms /= 1000;
     stats.timing.search_index_ms /= 1000;
@@ -1267,6 +2027,46 @@ pub fn incremental_sync(
     Ok(stats)
 }
 
+/// Move a stored message to the thread a history record files it under
+///
+/// Gmail rethreads messages when a subject or References header changes.
+/// The new thread is stored or updated first, then the message moves into
+/// it and is reindexed under it. Returns the thread the message
+/// left, for the caller to recompute, or None if it didn't move.
+fn rethread_message(
+    store: &dyn MailStore,
+    message: &mut Message,
+    message_ref: &MessageRef,
+    account_id: i64,
+    search_index: Option<&SearchIndex>,
+) -> Result<Option<ThreadId>> {
+    if message_ref.thread_id.is_empty() || message.thread_id.as_str() == message_ref.thread_id {
+        return Ok(None);
+    }
+    let thread_id = ThreadId::new(&message_ref.thread_id);
+    let old_thread_id = std::mem::replace(&mut message.thread_id, thread_id.clone());
+    info!(
+        "Message {} moved from thread {} to {}",
+        message.id.as_str(),
+        old_thread_id.as_str(),
+        thread_id.as_str()
+    );
+
+    // Must upsert thread BEFORE moving the message due to FK constraint
+    let thread = compute_thread(&thread_id, account_id, std::slice::from_ref(message), store)?;
+    store.upsert_thread(thread.clone())?;
+    store.move_message(&message.id, &thread_id)?;
+
+    // Indexing replaces the message's entry under its old thread
+    if let Some(index) = search_index
+        && let Err(e) = index.index_message(message, &thread)
+    {
+        warn!("Failed to reindex moved message {}: {}", message.id.as_str(), e);
+    }
+
+    Ok(Some(old_thread_id))
+}
+
 /// Compute thread properties from its messages
 fn compute_thread(
     thread_id: &ThreadId,
@@ -1274,6 +2074,14 @@ fn compute_thread(
     new_messages: &[Message],
     store: &dyn MailStore,
 ) -> Result<Thread> {
+    // One message new to a stored thread is folded into its aggregates,
+    // keeping sync linear in the length of long threads
+    if let [message] = new_messages
+        && let Some(thread) = fold_into_thread(thread_id, account_id, message, store)?
+    {
+        return Ok(thread);
+    }
+
     // Get existing messages for this thread (as metadata)
     let existing_messages = store.list_messages_for_thread(thread_id)?;
 
@@ -1294,67 +2102,93 @@ fn compute_thread(
             label_ids: m.label_ids.clone(),
             has_body_text: m.body_text.is_some(),
             has_body_html: m.body_html.is_some(),
+            has_attachments: m.has_attachments,
+            attachment_names: m.attachment_names.clone(),
+            size_bytes: m.size_bytes,
+            unsubscribe: m.unsubscribe.clone(),
+            auth_results: m.auth_results.clone(),
+            invite: m.invite.clone(),
+            lang: m.lang.clone(),
+            rfc_message_id: m.rfc_message_id.clone(),
         })
         .collect();
 
-    // Combine existing and new messages
+    // Combine existing and new messages; a message being processed again
+    // replaces its stored copy rather than being counted twice
     let all_messages: Vec<&MessageMetadata> = existing_messages
         .iter()
+        .filter(|existing| !new_metadata.iter().any(|m| m.id == existing.id))
         .chain(new_metadata.iter())
         .collect();
 
-    // Find the latest message for subject and snippet
-    let latest = all_messages
-        .iter()
-        .max_by_key(|m| m.received_at)
-        .expect("Thread must have at least one message");
-
-    // Find the overall latest timestamp
-    let last_message_at = all_messages
-        .iter()
-        .map(|m| m.received_at)
-        .max()
-        .unwrap_or_else(Utc::now);
-
-    // Use subject from first message (thread subject)
-    let first = all_messages
-        .iter()
-        .min_by_key(|m| m.received_at)
-        .expect("Thread must have at least one message");
+    summarize_thread(thread_id, account_id, &all_messages)
+        .context("Thread must have at least one message")
+}
 
-    let subject = if first.subject.is_empty() {
-        "(no subject)".to_string()
-    } else {
-        first.subject.clone()
+/// Update a stored thread's aggregates with a message not yet in the store
+///
+/// Returns None when the stored aggregates aren't enough: the thread isn't
+/// stored yet, the message is (its old copy is already counted), it was
+/// received at the same time as the thread's first or latest message, where
+/// the order depends on the other message's ID, or it falls between them
+/// from someone other than the first sender, which can reorder participants.
+fn fold_into_thread(
+    thread_id: &ThreadId,
+    account_id: i64,
+    message: &Message,
+    store: &dyn MailStore,
+) -> Result<Option<Thread>> {
+    let Some(mut thread) = store.get_thread(thread_id)? else {
+        return Ok(None);
     };
+    if store.has_message(&message.id)? {
+        return Ok(None);
+    }
+    let Some(first_message_at) = store.first_message_at(thread_id)? else {
+        return Ok(None);
+    };
+    if message.received_at == first_message_at || message.received_at == thread.last_message_at {
+        return Ok(None);
+    }
+    let is_first = message.received_at < first_message_at;
+    let is_latest = message.received_at > thread.last_message_at;
+    let from_first_sender = thread
+        .participants
+        .first()
+        .is_some_and(|p| p.email.eq_ignore_ascii_case(&message.from.email));
+    if !is_first && !is_latest && !from_first_sender {
+        return Ok(None);
+    }
+    if is_first || is_latest {
+        thread.add_participant(&message.from, is_first);
+    }
 
-    // Extract sender from first message
-    let sender_name = first.from.name.clone();
-    let sender_email = first.from.email.clone();
-
-    // Check if any message is unread
-    let is_unread = all_messages
-        .iter()
-        .any(|m| m.label_ids.iter().any(|l| l == LabelId::UNREAD));
+    if is_first {
+        thread.subject = if message.subject.is_empty() {
+            "(no subject)".to_string()
+        } else {
+            message.subject.clone()
+        };
+        thread.sender_name = message.from.name.clone();
+        thread.sender_email = message.from.email.clone();
+    }
+    if is_latest {
+        thread.snippet = message.body_preview.clone();
+        thread.last_message_at = message.received_at;
+    }
+    thread.account_id = account_id;
+    thread.message_count += 1;
+    thread.is_unread |= message.label_ids.iter().any(|l| l == LabelId::UNREAD);
 
-    Ok(Thread::new(
-        thread_id.clone(),
-        account_id,
-        subject,
-        latest.body_preview.clone(),
-        last_message_at,
-        all_messages.len(),
-        sender_name,
-        sender_email,
-        is_unread,
-    ))
+    Ok(Some(thread))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::EmailAddress;
+    use crate::models::{EmailAddress, MAX_THREAD_PARTICIPANTS};
     use crate::storage::InMemoryMailStore;
+    use proptest::prelude::*;
 
     fn make_test_message(id: &str, thread_id: &str, subject: &str, age_hours: i64) -> Message {
         let received_at = Utc::now() - chrono::Duration::hours(age_hours);
@@ -1385,6 +2219,87 @@ mod tests {
         assert_eq!(thread.account_id, 1);
     }
 
+    #[test]
+    fn test_compute_thread_participants() {
+        let store = InMemoryMailStore::new();
+        let thread_id = ThreadId::new("t1");
+        let senders = [
+            "ana@example.com",
+            "bo@example.com",
+            "ANA@example.com",
+            "cy@example.com",
+            "di@example.com",
+            "ed@example.com",
+        ];
+        let messages: Vec<Message> = senders
+            .iter()
+            .enumerate()
+            .map(|(i, sender)| {
+                let mut message =
+                    make_test_message(&format!("m{}", i), "t1", "Plans", 10 - i as i64);
+                message.from = EmailAddress::new(*sender);
+                message
+            })
+            .collect();
+
+        let thread = compute_thread(&thread_id, 1, &messages, &store).unwrap();
+
+        // Ana writes twice under different cases but is listed once; Ed
+        // falls past the cap
+        let participants: Vec<&str> =
+            thread.participants.iter().map(|p| p.email.as_str()).collect();
+        assert_eq!(
+            participants,
+            vec!["ana@example.com", "bo@example.com", "cy@example.com", "di@example.com"]
+        );
+    }
+
+    #[test]
+    fn test_process_pending_batch_applies_rules() {
+        use crate::models::{RuleAction, RulePredicate};
+
+        let store = InMemoryMailStore::new();
+        store
+            .save_rule(
+                Rule::new("Newsletters")
+                    .with_predicate(RulePredicate::Domain("news.example.com".into()))
+                    .with_action(RuleAction::Archive),
+            )
+            .unwrap();
+        store.save_rule(Rule::vip("boss@example.com").for_account(2)).unwrap();
+
+        for (id, from) in [("m1", "digest@news.example.com"), ("m2", "boss@example.com")] {
+            let gmail_msg = serde_json::json!({
+                "id": id,
+                "threadId": format!("t_{}", id),
+                "labelIds": ["INBOX", "UNREAD"],
+                "snippet": "Hello",
+                "internalDate": "1700000000000",
+                "payload": {"headers": [{"name": "From", "value": from}]},
+            });
+            store
+                .store_pending_message(
+                    &MessageId::new(id),
+                    1,
+                    &serde_json::to_vec(&gmail_msg).unwrap(),
+                    vec!["INBOX".to_string()],
+                )
+                .unwrap();
+        }
+
+        let mut stats = SyncStats::default();
+        let result =
+            process_pending_batch(&store, 1, &SyncOptions::default(), &mut stats, 10).unwrap();
+        assert_eq!(result.processed, 2);
+
+        // The VIP rule belongs to another account
+        assert_eq!(result.rule_matches.len(), 1);
+        assert_eq!(result.rule_matches[0].message_id.as_str(), "m1");
+        assert_eq!(result.rule_matches[0].removed_labels, vec!["INBOX"]);
+        let stored = store.get_message(&MessageId::new("m1")).unwrap().unwrap();
+        assert_eq!(stored.label_ids, vec!["UNREAD"]);
+    }
+
     #[test]
     fn test_compute_thread_with_existing() {
         let store = InMemoryMailStore::new();
@@ -1423,6 +2338,153 @@ mod tests {
         assert_eq!(stats.messages_stored(), 8);
     }
 
+    #[test]
+    fn test_history_has_gap() {
+        let history = |ids: &[&str], history_id: &str| -> HistoryResponse {
+            let records: Vec<_> = ids.iter().map(|id| serde_json::json!({ "id": id })).collect();
+            serde_json::from_value(serde_json::json!({
+                "history": records,
+                "historyId": history_id,
+            }))
+            .unwrap()
+        };
+
+        // Gmail skips IDs between records, which isn't a gap
+        assert!(!history_has_gap("100", &history(&["105", "230"], "240")));
+        assert!(!history_has_gap("100", &history(&[], "100")));
+
+        // A record from before the start, out of order, or past the
+        // listing's history ID means records went missing
+        assert!(history_has_gap("100", &history(&["90", "105"], "240")));
+        assert!(history_has_gap("100", &history(&["230", "105"], "240")));
+        assert!(history_has_gap("100", &history(&["105", "230"], "200")));
+        assert!(history_has_gap("bogus", &history(&["105"], "240")));
+    }
+
+    // === Thread Computation Properties ===
+
+    /// Messages of thread t1 with unique IDs; timestamps, subjects and
+    /// senders come from small pools so that they collide often
+    fn arb_thread_messages() -> impl Strategy<Value = Vec<Message>> {
+        prop::collection::vec((0..4i64, 0..3usize, 0..6usize, any::<bool>()), 1..8).prop_map(
+            |fields| {
+                fields
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (minute, subject, sender, unread))| {
+                        let mut labels = vec!["INBOX".to_string()];
+                        if unread {
+                            labels.push("UNREAD".to_string());
+                        }
+                        let received_at =
+                            DateTime::from_timestamp(1_700_000_000 + minute * 60, 0).unwrap();
+                        Message::builder(MessageId::new(format!("m{}", i)), ThreadId::new("t1"))
+                            .account_id(1)
+                            .from(EmailAddress::new(format!("sender{}@example.com", sender)))
+                            .subject(["", "Plans", "Re: Plans"][subject])
+                            .body_preview(format!("Body {}", i))
+                            .received_at(received_at)
+                            .label_ids(labels)
+                            .build()
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    /// Compute t1 with `stored` already in the store and `new` being processed
+    fn thread_of(stored: &[Message], new: &[Message]) -> Thread {
+        let store = InMemoryMailStore::new();
+        for message in stored {
+            store.upsert_message(message.clone()).unwrap();
+        }
+        compute_thread(&ThreadId::new("t1"), 1, new, &store).unwrap()
+    }
+
+    proptest! {
+        #[test]
+        fn prop_compute_thread_ignores_message_order(
+            (messages, shuffled) in arb_thread_messages()
+                .prop_flat_map(|m| (Just(m.clone()), Just(m).prop_shuffle())),
+            split in 0..8usize,
+        ) {
+            let expected = thread_of(&[], &messages);
+            prop_assert_eq!(&thread_of(&[], &shuffled), &expected);
+
+            // Which messages were already stored doesn't matter either
+            let split = split.min(shuffled.len());
+            prop_assert_eq!(&thread_of(&shuffled[..split], &shuffled[split..]), &expected);
+        }
+
+        #[test]
+        fn prop_compute_thread_is_idempotent(messages in arb_thread_messages()) {
+            let expected = thread_of(&messages, &[]);
+            // Processing stored messages again changes nothing
+            prop_assert_eq!(&thread_of(&messages, &messages[..1]), &expected);
+            prop_assert_eq!(&thread_of(&messages, &messages), &expected);
+        }
+
+        #[test]
+        fn prop_compute_thread_counts_each_message_once(messages in arb_thread_messages()) {
+            for n in 1..=messages.len() {
+                let (stored, rest) = messages.split_at(n - 1);
+                let thread = thread_of(stored, &rest[..1]);
+                prop_assert_eq!(thread.message_count, n);
+            }
+        }
+
+        #[test]
+        fn prop_compute_thread_folds_messages_one_at_a_time(
+            messages in arb_thread_messages().prop_shuffle(),
+        ) {
+            // As sync stores them: thread first, then the message
+            let store = InMemoryMailStore::new();
+            let thread_id = ThreadId::new("t1");
+            for message in &messages {
+                let thread = compute_thread(&thread_id, 1, &[message.clone()], &store).unwrap();
+                store.upsert_thread(thread).unwrap();
+                store.upsert_message(message.clone()).unwrap();
+            }
+
+            let folded = store.get_thread(&thread_id).unwrap().unwrap();
+            prop_assert_eq!(&folded, &thread_of(&[], &messages));
+        }
+
+        #[test]
+        fn prop_compute_thread_derives_fields(messages in arb_thread_messages()) {
+            let thread = thread_of(&[], &messages);
+            let order = |m: &&Message| (m.received_at, m.id.as_str().to_string());
+            let first = messages.iter().min_by_key(order).unwrap();
+            let latest = messages.iter().max_by_key(order).unwrap();
+
+            let subject: &str = if first.subject.is_empty() {
+                "(no subject)"
+            } else {
+                &first.subject
+            };
+            prop_assert_eq!(&thread.subject, subject);
+            prop_assert_eq!(&thread.sender_email, &first.from.email);
+            let mut senders: Vec<&str> = Vec::new();
+            let mut ordered: Vec<&Message> = messages.iter().collect();
+            ordered.sort_by_key(order);
+            for message in ordered {
+                if !senders.contains(&message.from.email.as_str()) {
+                    senders.push(&message.from.email);
+                }
+            }
+            senders.truncate(MAX_THREAD_PARTICIPANTS);
+            let participants: Vec<&str> =
+                thread.participants.iter().map(|p| p.email.as_str()).collect();
+            prop_assert_eq!(participants, senders);
+            prop_assert_eq!(&thread.snippet, &latest.body_preview);
+            prop_assert_eq!(thread.last_message_at, latest.received_at);
+            prop_assert_eq!(
+                thread.is_unread,
+                messages.iter().any(|m| m.label_ids.iter().any(|l| l == "UNREAD"))
+            );
+        }
+    }
+
     // === Sync Decision Tests ===
 
     #[test]
@@ -3,15 +3,30 @@
 //! Provides idempotent sync operations that can be safely retried.
 //! Supports both initial full sync and incremental sync via Gmail History API.
 
+mod budget;
+mod cancel;
 mod inbox;
+mod orchestrator;
+mod step;
 mod timing;
 
+pub use budget::{
+    METERED_MAX_CONCURRENT_FETCHES, METERED_MAX_MB_PER_SYNC, NetworkBudget,
+    NetworkBudgetSpentError, is_metered_connection,
+};
+pub use cancel::{SyncCancel, SyncCancelledError};
 pub use inbox::{
     // Sync execution
-    FetchPhaseStats, ProcessBatchResult, SyncOptions, SyncStats, SyncTiming,
+    DEFAULT_MAX_PENDING, FetchPhaseStats, ProcessBatchResult, SyncOptions, SyncStats, SyncTiming,
     fetch_phase, fetch_phase_with_progress, process_pending_batch, sync_gmail, sync_gmail_with_progress, incremental_sync,
+    backfill_sync, history_has_gap, reconcile_sync,
     // Sync decision (testable)
     SyncAction, SyncStateInfo, ResumeProgress,
     determine_sync_action, should_auto_sync_on_startup, get_sync_state_info,
 };
-pub use timing::cooldown_elapsed;
+pub use orchestrator::{SyncOrchestrator, SyncTicket};
+pub use step::{SyncCheckpoint, SyncStep, SyncStepPhase, sync_step};
+pub use timing::{
+    SchedulerState, SyncSchedule, SyncSkipReason, check_sync_allowed, cooldown_elapsed,
+    next_allowed_sync_at, seconds_until, watch_sync_schedule,
+};
@@ -0,0 +1,154 @@
+//! Pausing and resuming account syncs
+//!
+//! Users on metered connections can pause an account without removing it.
+//! The pause is the account's stored `sync_enabled` flag, so it holds across
+//! restarts and for every app sharing the store. Syncs started through
+//! [`SyncOrchestrator::begin`] are also stopped when their account is
+//! paused, at the next page or batch.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use log::info;
+
+use super::cancel::SyncCancel;
+use crate::error::MailError;
+use crate::storage::MailStore;
+
+/// Cancel flags of running syncs, by account ID
+type RunningSyncs = Arc<Mutex<HashMap<i64, SyncCancel>>>;
+
+/// Tracks which accounts may sync and stops the ones that are paused
+///
+/// Clones share the running syncs.
+#[derive(Clone)]
+pub struct SyncOrchestrator {
+    store: Arc<dyn MailStore>,
+    running: RunningSyncs,
+}
+
+/// A running sync registered with [`SyncOrchestrator::begin`]
+///
+/// Dropping it marks the sync finished.
+pub struct SyncTicket {
+    account_id: i64,
+    cancel: SyncCancel,
+    running: RunningSyncs,
+}
+
+impl SyncOrchestrator {
+    pub fn new(store: Arc<dyn MailStore>) -> Self {
+        Self {
+            store,
+            running: Arc::default(),
+        }
+    }
+
+    /// Stop syncing an account until it's resumed
+    ///
+    /// A sync of the account running in this process stops at its next
+    /// checkpoint. Synced mail is kept.
+    pub fn pause(&self, account_id: i64) -> Result<()> {
+        self.store.set_account_sync_enabled(account_id, false)?;
+        if let Some(cancel) = self.running.lock().unwrap().get(&account_id) {
+            cancel.cancel();
+        }
+        info!("Paused syncing account {}", account_id);
+        Ok(())
+    }
+
+    /// Let a paused account sync again
+    pub fn resume(&self, account_id: i64) -> Result<()> {
+        self.store.set_account_sync_enabled(account_id, true)?;
+        info!("Resumed syncing account {}", account_id);
+        Ok(())
+    }
+
+    /// Whether an account is paused
+    pub fn is_paused(&self, account_id: i64) -> Result<bool> {
+        let account = self
+            .store
+            .get_account(account_id)?
+            .ok_or_else(|| MailError::NotFound(format!("Account {}", account_id)))?;
+        Ok(!account.sync_enabled)
+    }
+
+    /// Register a sync of an account about to start
+    ///
+    /// Returns `None` if the account is paused. Otherwise pass the ticket's
+    /// [`cancel`](SyncTicket::cancel) flag in `SyncOptions::cancel` and keep
+    /// the ticket until the sync ends.
+    pub fn begin(&self, account_id: i64) -> Result<Option<SyncTicket>> {
+        if self.is_paused(account_id)? {
+            return Ok(None);
+        }
+        let cancel = SyncCancel::new();
+        self.running
+            .lock()
+            .unwrap()
+            .insert(account_id, cancel.clone());
+        Ok(Some(SyncTicket {
+            account_id,
+            cancel,
+            running: self.running.clone(),
+        }))
+    }
+}
+
+impl SyncTicket {
+    /// Flag that stops the sync when the account is paused
+    pub fn cancel(&self) -> SyncCancel {
+        self.cancel.clone()
+    }
+}
+
+impl Drop for SyncTicket {
+    fn drop(&mut self) {
+        self.running.lock().unwrap().remove(&self.account_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Account;
+    use crate::storage::InMemoryMailStore;
+
+    fn orchestrator() -> (SyncOrchestrator, i64) {
+        let store = Arc::new(InMemoryMailStore::new());
+        let account = store
+            .register_account(Account::new("me@example.com"))
+            .unwrap();
+        (SyncOrchestrator::new(store), account.id)
+    }
+
+    #[test]
+    fn test_paused_accounts_dont_begin() {
+        let (orchestrator, account_id) = orchestrator();
+        assert!(!orchestrator.is_paused(account_id).unwrap());
+
+        orchestrator.pause(account_id).unwrap();
+        assert!(orchestrator.is_paused(account_id).unwrap());
+        assert!(orchestrator.begin(account_id).unwrap().is_none());
+
+        orchestrator.resume(account_id).unwrap();
+        assert!(orchestrator.begin(account_id).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_pause_cancels_running_sync() {
+        let (orchestrator, account_id) = orchestrator();
+        let ticket = orchestrator.begin(account_id).unwrap().unwrap();
+        let cancel = ticket.cancel();
+
+        orchestrator.pause(account_id).unwrap();
+        assert!(cancel.is_cancelled());
+
+        // A finished sync is no longer tracked
+        drop(ticket);
+        orchestrator.resume(account_id).unwrap();
+        let ticket = orchestrator.begin(account_id).unwrap().unwrap();
+        assert!(!ticket.cancel().is_cancelled());
+    }
+}
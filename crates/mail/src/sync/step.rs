@@ -0,0 +1,387 @@
+//! Time-boxed sync slices
+//!
+//! Background windows on mobile (iOS BackgroundTasks grant roughly 30
+//! seconds) are too short for an initial sync of a large mailbox.
+//! [`sync_step`] does as much fetch and process work as fits in a budget,
+//! stops at the next checkpoint, and leaves the sync state in the store so
+//! the following slice - possibly in another launch - carries on from there.
+
+use anyhow::Result;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use super::cancel::{SyncCancel, SyncCancelledError};
+use super::inbox::{
+    SyncAction, SyncOptions, SyncStats, determine_sync_action, fetch_phase, get_current_history_id,
+    incremental_sync, process_pending_batch, reconcile_sync,
+};
+use crate::diagnostics::{DiagnosticKind, record_diagnostic};
+use crate::gmail::{ApiCall, GmailApi, HistoryExpiredError};
+use crate::models::SyncState;
+use crate::storage::MailStore;
+use crate::telemetry;
+
+/// Pending messages processed between budget checks
+const STEP_BATCH_SIZE: usize = 50;
+
+/// How often the budget timer checks for caller cancellation
+const BUDGET_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Work left for an account after a sync slice
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncStepPhase {
+    /// Message listing hasn't finished
+    Fetching,
+    /// Everything is listed; pending messages still need processing
+    Processing,
+    /// Initial sync is done; later slices run incremental syncs
+    UpToDate,
+}
+
+/// Where a sliced sync stands, as saved in the store
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncCheckpoint {
+    pub phase: SyncStepPhase,
+    /// History ID the sync is anchored to, once one was captured
+    pub history_id: Option<String>,
+    /// Message IDs listed so far by an unfinished initial sync
+    pub messages_listed: usize,
+    /// Messages fetched but not yet processed
+    pub pending: usize,
+}
+
+impl SyncCheckpoint {
+    /// Read the checkpoint for an account from the store
+    pub fn load(store: &dyn MailStore, account_id: i64) -> Result<Self> {
+        let state = store.get_sync_state(account_id)?;
+        let pending = store.count_pending_messages(account_id, None)?;
+        let phase = match &state {
+            Some(state) if state.initial_sync_complete => SyncStepPhase::UpToDate,
+            Some(state) if listing_done(state) => SyncStepPhase::Processing,
+            _ => SyncStepPhase::Fetching,
+        };
+        Ok(Self {
+            phase,
+            history_id: state.as_ref().map(|s| s.history_id.clone()),
+            messages_listed: state.map(|s| s.messages_listed).unwrap_or(0),
+            pending,
+        })
+    }
+
+    /// Opaque string identifying this checkpoint
+    ///
+    /// Equal tokens from consecutive slices mean no progress was made.
+    pub fn token(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Parse a token produced by [`token`](Self::token)
+    pub fn from_token(token: &str) -> Option<Self> {
+        serde_json::from_str(token).ok()
+    }
+}
+
+/// Outcome of one sync slice
+#[derive(Debug, Clone)]
+pub struct SyncStep {
+    /// Where the sync stands now
+    pub checkpoint: SyncCheckpoint,
+    /// Work done during this slice
+    pub stats: SyncStats,
+    /// Whether the budget ran out before the slice finished its work
+    pub out_of_budget: bool,
+}
+
+impl SyncStep {
+    /// Whether the account is fully synced, so no further slice is needed
+    pub fn is_complete(&self) -> bool {
+        self.checkpoint.phase == SyncStepPhase::UpToDate && !self.out_of_budget
+    }
+}
+
+/// Run one slice of sync for an account, stopping once `budget` is spent
+///
+/// Picks the same action a full sync would (initial, resumed, incremental or
+/// stale resync) but stops at the first checkpoint after the budget runs
+/// out: a listing page, a chunk of message downloads or a processing batch.
+/// Overshoot is bounded by one of those units, so leave some headroom under
+/// the platform's deadline. Call repeatedly until the returned step
+/// [`is_complete`](SyncStep::is_complete).
+///
+/// Cancelling `options.cancel` fails with [`SyncCancelledError`] as usual;
+/// running out of budget does not. `full_resync` clears mail before the
+/// slice starts, so pass it to the first slice only.
+pub fn sync_step(
+    gmail: &dyn GmailApi,
+    store: &dyn MailStore,
+    account_id: i64,
+    options: &SyncOptions,
+    budget: Duration,
+) -> Result<SyncStep> {
+    let start = Instant::now();
+    let (budget_cancel, _timer) = cancel_after(&options.cancel, start + budget);
+    let step_options = SyncOptions {
+        cancel: budget_cancel,
+        ..options.clone()
+    };
+
+    let mut stats = SyncStats::default();
+    let out_of_budget = match run_step(gmail, store, account_id, &step_options, &mut stats) {
+        Ok(()) => false,
+        Err(e)
+            if e.downcast_ref::<SyncCancelledError>().is_some()
+                && !options.cancel.is_cancelled() =>
+        {
+            true
+        }
+        Err(e) => {
+            telemetry::record_sync_failure(&e);
+            record_diagnostic(
+                DiagnosticKind::Sync,
+                Some(account_id),
+                format!("Sync step failed: {:#}", e),
+            );
+            return Err(e);
+        }
+    };
+    stats.duration_ms = start.elapsed().as_millis() as u64;
+    telemetry::record_sync(&stats);
+
+    let checkpoint = SyncCheckpoint::load(store, account_id)?;
+    info!(
+        "Sync step for account {} took {}ms: {:?}, {} pending{}",
+        account_id,
+        stats.duration_ms,
+        checkpoint.phase,
+        checkpoint.pending,
+        if out_of_budget {
+            " (out of budget)"
+        } else {
+            ""
+        }
+    );
+    Ok(SyncStep {
+        checkpoint,
+        stats,
+        out_of_budget,
+    })
+}
+
+/// Do the slice's work, failing with `SyncCancelledError` at the budget
+fn run_step(
+    gmail: &dyn GmailApi,
+    store: &dyn MailStore,
+    account_id: i64,
+    options: &SyncOptions,
+    stats: &mut SyncStats,
+) -> Result<()> {
+    let existing = store.get_sync_state(account_id)?;
+    let state = match (
+        determine_sync_action(existing.as_ref(), options.full_resync),
+        existing,
+    ) {
+        (SyncAction::IncrementalSync { .. }, Some(state)) => {
+            match incremental_sync(gmail, store, &state, options) {
+                Ok(incremental) => {
+                    add_stats(stats, &incremental);
+                    return Ok(());
+                }
+                Err(e) if e.downcast_ref::<HistoryExpiredError>().is_some() => {
+                    warn!("History ID expired, reconciling with server");
+                    let reconcile = reconcile_sync(gmail, store, &state, options)?;
+                    add_stats(stats, &reconcile);
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        (SyncAction::ResumeInitialSync { .. }, Some(state)) => state,
+        (SyncAction::StaleResync { days_since_sync }, _) => {
+            warn!(
+                "Sync state is {} days old, restarting initial sync in steps",
+                days_since_sync
+            );
+            restart_initial_sync(gmail, store, account_id, stats)?
+        }
+        (_, existing) if options.full_resync || existing.is_some() => {
+            restart_initial_sync(gmail, store, account_id, stats)?
+        }
+        _ => start_initial_sync(gmail, store, account_id, stats)?,
+    };
+
+    // A fetch stopped by a full pending queue resumes once it's processed
+    if !listing_done(&state) {
+        while fetch_phase(gmail, store, account_id, options, stats)?.backlogged {
+            while process_pending_batch(store, account_id, options, stats, STEP_BATCH_SIZE)?
+                .has_more
+            {}
+        }
+    }
+
+    while process_pending_batch(store, account_id, options, stats, STEP_BATCH_SIZE)?.has_more {}
+
+    // Keep failed IDs from the fetch phase for retry, as a full sync does
+    let failed_ids = store
+        .get_sync_state(account_id)?
+        .map(|state| state.failed_message_ids)
+        .unwrap_or_default();
+    let mut complete = SyncState::partial(account_id, &state.history_id).mark_complete();
+    complete.failed_message_ids = failed_ids;
+    store.save_sync_state(complete.clone())?;
+    info!("Initial sync for account {} completed in steps", account_id);
+
+    // Catch up on mail that arrived while the slices ran. The initial sync
+    // is already saved, so a failure here only delays that mail.
+    match incremental_sync(gmail, store, &complete, options) {
+        Ok(catchup) => add_stats(stats, &catchup),
+        Err(e) if e.downcast_ref::<SyncCancelledError>().is_some() => return Err(e),
+        Err(e) => warn!("Catch-up sync failed (non-fatal): {}", e),
+    }
+    Ok(())
+}
+
+/// Whether an initial sync has finished listing messages
+///
+/// A fresh partial state has no page token either, but hasn't listed
+/// anything yet. An empty mailbox is simply listed again, which is cheap.
+fn listing_done(state: &SyncState) -> bool {
+    state.fetch_page_token.is_none() && state.messages_listed > 0
+}
+
+/// Drop existing mail and sync state, then start a new initial sync
+fn restart_initial_sync(
+    gmail: &dyn GmailApi,
+    store: &dyn MailStore,
+    account_id: i64,
+    stats: &mut SyncStats,
+) -> Result<SyncState> {
+    record_diagnostic(
+        DiagnosticKind::Sync,
+        Some(account_id),
+        "Restarting initial sync in steps",
+    );
+    store.clear_account_data(account_id)?;
+    start_initial_sync(gmail, store, account_id, stats)
+}
+
+/// Capture the current history ID and save a partial sync state
+fn start_initial_sync(
+    gmail: &dyn GmailApi,
+    store: &dyn MailStore,
+    account_id: i64,
+    stats: &mut SyncStats,
+) -> Result<SyncState> {
+    let profile_start = Instant::now();
+    let history_id = get_current_history_id(gmail)?;
+    stats.timing.profile_ms += profile_start.elapsed().as_millis() as u64;
+    stats.quota.record(ApiCall::GetProfile, 1);
+    info!(
+        "Starting initial sync in steps from history_id: {}",
+        history_id
+    );
+
+    let state = SyncState::partial(account_id, &history_id);
+    store.save_sync_state(state.clone())?;
+    Ok(state)
+}
+
+/// Merge an incremental sync's stats into the slice's
+fn add_stats(stats: &mut SyncStats, other: &SyncStats) {
+    stats.was_incremental |= other.was_incremental;
+    stats.messages_fetched += other.messages_fetched;
+    stats.messages_created += other.messages_created;
+    stats.messages_updated += other.messages_updated;
+    stats.labels_updated += other.labels_updated;
+    stats.threads_created += other.threads_created;
+    stats.threads_updated += other.threads_updated;
+    stats.errors += other.errors;
+    stats.timing.incremental_sync_ms += other.timing.incremental_sync_ms;
+    stats.timing.history_ms += other.timing.history_ms;
+    stats.timing.fetch_messages_ms += other.timing.fetch_messages_ms;
+    stats.timing.normalize_ms += other.timing.normalize_ms;
+    stats.timing.storage_ms += other.timing.storage_ms;
+    stats.timing.compute_thread_ms += other.timing.compute_thread_ms;
+    stats.timing.search_index_ms += other.timing.search_index_ms;
+    stats.quota.merge(&other.quota);
+}
+
+/// A flag that trips at `deadline` or when `parent` is cancelled
+///
+/// The timer thread exits as soon as the returned sender is dropped.
+fn cancel_after(parent: &SyncCancel, deadline: Instant) -> (SyncCancel, mpsc::Sender<()>) {
+    let cancel = SyncCancel::new();
+    let (done, finished) = mpsc::channel::<()>();
+    let parent = parent.clone();
+    let flag = cancel.clone();
+    std::thread::spawn(move || {
+        loop {
+            let wait = deadline
+                .saturating_duration_since(Instant::now())
+                .min(BUDGET_POLL_INTERVAL);
+            match finished.recv_timeout(wait) {
+                Err(RecvTimeoutError::Timeout) => {
+                    if parent.is_cancelled() || Instant::now() >= deadline {
+                        flag.cancel();
+                        break;
+                    }
+                }
+                _ => break,
+            }
+        }
+    });
+    (cancel, done)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listing_done() {
+        let state = SyncState::partial(1, "100");
+        assert!(!listing_done(&state));
+
+        let listing = state
+            .clone()
+            .with_fetch_progress(Some("page-2".to_string()), 500);
+        assert!(!listing_done(&listing));
+
+        let listed = state.with_fetch_progress(None, 1200);
+        assert!(listing_done(&listed));
+    }
+
+    #[test]
+    fn test_checkpoint_token_roundtrip() {
+        let checkpoint = SyncCheckpoint {
+            phase: SyncStepPhase::Processing,
+            history_id: Some("12345".to_string()),
+            messages_listed: 1200,
+            pending: 300,
+        };
+        let token = checkpoint.token();
+        assert_eq!(SyncCheckpoint::from_token(&token), Some(checkpoint));
+        assert_eq!(SyncCheckpoint::from_token("not a token"), None);
+    }
+
+    #[test]
+    fn test_cancel_after_deadline() {
+        let parent = SyncCancel::new();
+        let (cancel, _timer) = cancel_after(&parent, Instant::now() + Duration::from_millis(20));
+        assert!(!cancel.is_cancelled());
+        std::thread::sleep(Duration::from_millis(250));
+        assert!(cancel.is_cancelled());
+        assert!(!parent.is_cancelled());
+    }
+
+    #[test]
+    fn test_cancel_after_follows_parent() {
+        let parent = SyncCancel::new();
+        let (cancel, _timer) = cancel_after(&parent, Instant::now() + Duration::from_secs(60));
+        parent.cancel();
+        std::thread::sleep(Duration::from_millis(250));
+        assert!(cancel.is_cancelled());
+    }
+}
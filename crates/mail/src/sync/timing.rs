@@ -1,8 +1,12 @@
 //! Sync timing utilities for cooldown management
 //!
-//! Pure functions that can be tested without UI dependencies.
+//! Pure functions that can be tested without UI dependencies, plus
+//! [`watch_sync_schedule`] to pick up edits to the sync settings.
 
-use chrono::{DateTime, Utc};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use config::{ConfigWatcher, CosmosConfig, SyncSettings};
+use log::warn;
 
 /// Check if enough time has elapsed since the last sync to allow a new sync.
 ///
@@ -22,6 +26,236 @@ pub fn cooldown_elapsed(last_sync_at: Option<DateTime<Utc>>, cooldown_secs: u64)
     }
 }
 
+/// Compute the earliest time a new sync is allowed.
+///
+/// # Arguments
+/// * `last_sync_at` - When the last successful sync completed (None if never synced)
+/// * `cooldown_secs` - Minimum seconds that must elapse between syncs
+///
+/// # Returns
+/// `None` if a sync is allowed immediately because no sync has happened yet,
+/// otherwise `last_sync_at + cooldown_secs` (which may already be in the past)
+pub fn next_allowed_sync_at(
+    last_sync_at: Option<DateTime<Utc>>,
+    cooldown_secs: u64,
+) -> Option<DateTime<Utc>> {
+    last_sync_at.map(|last| last + Duration::seconds(cooldown_secs as i64))
+}
+
+/// Whole seconds from `now` until `at`, clamped to zero.
+///
+/// Rounds up so a countdown never shows "0s" while still waiting.
+pub fn seconds_until(at: DateTime<Utc>, now: DateTime<Utc>) -> u64 {
+    let millis = (at - now).num_milliseconds();
+    if millis <= 0 {
+        0
+    } else {
+        (millis as u64).div_ceil(1000)
+    }
+}
+
+/// How often the app syncs on its own
+///
+/// Comes from the `[sync]` table of the shared Cosmos config, so edits
+/// apply while the app runs (see [`watch_sync_schedule`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncSchedule {
+    /// Minimum seconds between automatic syncs
+    pub cooldown_secs: u64,
+    /// Seconds between background polls
+    pub poll_interval_secs: u64,
+}
+
+impl Default for SyncSchedule {
+    fn default() -> Self {
+        Self::from_settings(&SyncSettings::default())
+    }
+}
+
+impl SyncSchedule {
+    pub fn from_settings(settings: &SyncSettings) -> Self {
+        Self {
+            cooldown_secs: settings.cooldown_secs,
+            poll_interval_secs: settings.poll_interval_secs.max(1),
+        }
+    }
+
+    /// Time between background polls
+    pub fn poll_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.poll_interval_secs)
+    }
+}
+
+/// Call `callback` whenever an edit to the shared Cosmos config changes the
+/// sync schedule
+///
+/// Starts from `current`. Edits that leave the settings invalid are logged
+/// and ignored, keeping the last good schedule. Watching stops when the
+/// returned watcher is dropped.
+pub fn watch_sync_schedule<F>(mut current: SyncSchedule, mut callback: F) -> Result<ConfigWatcher>
+where
+    F: FnMut(SyncSchedule) + Send + 'static,
+{
+    CosmosConfig::watch(move |config| match config {
+        Ok(config) => {
+            let schedule = SyncSchedule::from_settings(&config.sync);
+            if schedule != current {
+                current = schedule;
+                callback(schedule);
+            }
+        }
+        Err(e) => warn!("Keeping the current sync schedule: {:#}", e),
+    })
+}
+
+/// Why the scheduler declined to start a sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncSkipReason {
+    /// A sync is already running
+    AlreadySyncing,
+    /// No Gmail account is configured
+    NotConfigured,
+    /// The cooldown since the last sync has not elapsed
+    Cooldown {
+        /// Seconds left until a sync is allowed
+        remaining_secs: u64,
+    },
+    /// The `cosmosd` daemon is syncing on the app's behalf
+    DaemonActive,
+}
+
+impl SyncSkipReason {
+    /// Short human-readable description for status text
+    pub fn description(&self) -> String {
+        match self {
+            SyncSkipReason::AlreadySyncing => "Sync already in progress".to_string(),
+            SyncSkipReason::NotConfigured => "No account configured".to_string(),
+            SyncSkipReason::Cooldown { remaining_secs } => {
+                format!("Cooling down ({}s left)", remaining_secs)
+            }
+            SyncSkipReason::DaemonActive => "Synced by background daemon".to_string(),
+        }
+    }
+}
+
+/// Decide whether a sync may start now.
+///
+/// `force` bypasses the cooldown (e.g. an explicit click on the sync button)
+/// but never starts a second sync on top of a running one.
+///
+/// # Arguments
+/// * `last_sync_at` - When the last successful sync completed (None if never synced)
+/// * `cooldown_secs` - Minimum seconds that must elapse between syncs
+/// * `is_syncing` - Whether a sync is currently running
+/// * `is_configured` - Whether an account is available to sync
+/// * `force` - Skip the cooldown check
+/// * `now` - Current time
+pub fn check_sync_allowed(
+    last_sync_at: Option<DateTime<Utc>>,
+    cooldown_secs: u64,
+    is_syncing: bool,
+    is_configured: bool,
+    force: bool,
+    now: DateTime<Utc>,
+) -> Result<(), SyncSkipReason> {
+    if !is_configured {
+        return Err(SyncSkipReason::NotConfigured);
+    }
+    if is_syncing {
+        return Err(SyncSkipReason::AlreadySyncing);
+    }
+    if !force
+        && let Some(next) = next_allowed_sync_at(last_sync_at, cooldown_secs)
+        && next > now
+    {
+        return Err(SyncSkipReason::Cooldown {
+            remaining_secs: seconds_until(next, now),
+        });
+    }
+    Ok(())
+}
+
+/// Snapshot of the background sync scheduler for UI display
+///
+/// The app updates this as polls are scheduled, syncs complete, and syncs
+/// are skipped, so the sync button can render "next check in 42s" and
+/// explain why the last attempt didn't run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchedulerState {
+    /// When the last successful sync completed
+    pub last_sync_at: Option<DateTime<Utc>>,
+    /// Earliest time the cooldown allows another sync
+    pub next_allowed_sync_at: Option<DateTime<Utc>>,
+    /// When the background poller will next wake up
+    pub next_poll_at: Option<DateTime<Utc>>,
+    /// Why the most recent sync attempt was skipped (cleared when a sync starts)
+    pub last_skip_reason: Option<SyncSkipReason>,
+    /// When the most recent skip happened
+    pub last_skip_at: Option<DateTime<Utc>>,
+}
+
+impl SchedulerState {
+    /// Create scheduler state from the last known sync time
+    pub fn new(last_sync_at: Option<DateTime<Utc>>, cooldown_secs: u64) -> Self {
+        Self {
+            last_sync_at,
+            next_allowed_sync_at: next_allowed_sync_at(last_sync_at, cooldown_secs),
+            ..Default::default()
+        }
+    }
+
+    /// Record a completed sync
+    pub fn record_sync(&mut self, at: DateTime<Utc>, cooldown_secs: u64) {
+        self.last_sync_at = Some(at);
+        self.next_allowed_sync_at = next_allowed_sync_at(Some(at), cooldown_secs);
+    }
+
+    /// Apply a new cooldown to the last sync
+    pub fn set_cooldown(&mut self, cooldown_secs: u64) {
+        self.next_allowed_sync_at = next_allowed_sync_at(self.last_sync_at, cooldown_secs);
+    }
+
+    /// Record that a sync started, clearing any previous skip reason
+    pub fn record_started(&mut self) {
+        self.last_skip_reason = None;
+        self.last_skip_at = None;
+    }
+
+    /// Record that a sync attempt was skipped
+    pub fn record_skip(&mut self, reason: SyncSkipReason, at: DateTime<Utc>) {
+        self.last_skip_reason = Some(reason);
+        self.last_skip_at = Some(at);
+    }
+
+    /// Record when the poller will next wake up
+    pub fn record_poll_scheduled(&mut self, at: DateTime<Utc>) {
+        self.next_poll_at = Some(at);
+    }
+
+    /// Record that the poller stopped
+    pub fn record_poll_stopped(&mut self) {
+        self.next_poll_at = None;
+    }
+
+    /// When the next automatic sync can actually happen
+    ///
+    /// This is the later of the next poll and the end of the cooldown, since a
+    /// poll that fires during cooldown is skipped. Returns None when no poller
+    /// is running.
+    pub fn next_check_at(&self) -> Option<DateTime<Utc>> {
+        let poll = self.next_poll_at?;
+        Some(match self.next_allowed_sync_at {
+            Some(allowed) if allowed > poll => allowed,
+            _ => poll,
+        })
+    }
+
+    /// Seconds until the next automatic check, for countdown display
+    pub fn seconds_until_next_check(&self, now: DateTime<Utc>) -> Option<u64> {
+        self.next_check_at().map(|at| seconds_until(at, now))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -70,4 +304,101 @@ mod tests {
         let last_sync = Utc::now() - Duration::hours(24);
         assert!(cooldown_elapsed(Some(last_sync), 60));
     }
+
+    #[test]
+    fn test_next_allowed_sync_at() {
+        assert_eq!(next_allowed_sync_at(None, 30), None);
+
+        let last_sync = Utc::now();
+        assert_eq!(
+            next_allowed_sync_at(Some(last_sync), 30),
+            Some(last_sync + Duration::seconds(30))
+        );
+    }
+
+    #[test]
+    fn test_seconds_until_rounds_up_and_clamps() {
+        let now = Utc::now();
+        assert_eq!(seconds_until(now + Duration::milliseconds(41_200), now), 42);
+        assert_eq!(seconds_until(now + Duration::seconds(5), now), 5);
+        assert_eq!(seconds_until(now - Duration::seconds(5), now), 0);
+    }
+
+    #[test]
+    fn test_check_sync_allowed() {
+        let now = Utc::now();
+        let recent = Some(now - Duration::seconds(10));
+
+        assert_eq!(
+            check_sync_allowed(recent, 30, false, true, false, now),
+            Err(SyncSkipReason::Cooldown { remaining_secs: 20 })
+        );
+        // Force overrides the cooldown
+        assert_eq!(
+            check_sync_allowed(recent, 30, false, true, true, now),
+            Ok(())
+        );
+        // ...but never a running sync
+        assert_eq!(
+            check_sync_allowed(recent, 30, true, true, true, now),
+            Err(SyncSkipReason::AlreadySyncing)
+        );
+        assert_eq!(
+            check_sync_allowed(None, 30, false, false, false, now),
+            Err(SyncSkipReason::NotConfigured)
+        );
+        assert_eq!(
+            check_sync_allowed(None, 30, false, true, false, now),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_scheduler_next_check() {
+        let now = Utc::now();
+        let mut state = SchedulerState::new(None, 30);
+        assert_eq!(state.seconds_until_next_check(now), None);
+
+        state.record_poll_scheduled(now + Duration::seconds(60));
+        assert_eq!(state.seconds_until_next_check(now), Some(60));
+
+        // A cooldown ending after the next poll pushes the check out
+        state.record_sync(now + Duration::seconds(45), 30);
+        assert_eq!(state.seconds_until_next_check(now), Some(75));
+
+        state.record_skip(SyncSkipReason::AlreadySyncing, now);
+        assert_eq!(state.last_skip_reason, Some(SyncSkipReason::AlreadySyncing));
+        state.record_started();
+        assert_eq!(state.last_skip_reason, None);
+
+        state.record_poll_stopped();
+        assert_eq!(state.seconds_until_next_check(now), None);
+    }
+
+    #[test]
+    fn test_scheduler_set_cooldown() {
+        let now = Utc::now();
+        let mut state = SchedulerState::new(Some(now), 30);
+        assert_eq!(state.next_allowed_sync_at, Some(now + Duration::seconds(30)));
+
+        state.set_cooldown(300);
+        assert_eq!(state.next_allowed_sync_at, Some(now + Duration::seconds(300)));
+
+        let mut never = SchedulerState::new(None, 30);
+        never.set_cooldown(300);
+        assert_eq!(never.next_allowed_sync_at, None);
+    }
+
+    #[test]
+    fn test_sync_schedule_from_settings() {
+        let settings = SyncSettings {
+            cooldown_secs: 0,
+            poll_interval_secs: 0,
+            ..SyncSettings::default()
+        };
+        let schedule = SyncSchedule::from_settings(&settings);
+        assert_eq!(schedule.cooldown_secs, 0);
+        assert_eq!(schedule.poll_interval(), std::time::Duration::from_secs(1));
+        assert_eq!(SyncSchedule::default().poll_interval_secs, 60);
+    }
 }
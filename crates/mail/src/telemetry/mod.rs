@@ -0,0 +1,261 @@
+//! Sync and Gmail API metrics
+//!
+//! Sync and the Gmail client report counters and histograms through the
+//! [`metrics`] facade, which costs next to nothing until an app installs a
+//! recorder. Any `metrics` exporter works; with the `otlp` feature,
+//! [`install_otlp_exporter`] pushes them to an OpenTelemetry collector.
+//!
+//! | Metric | Kind | Labels |
+//! |---|---|---|
+//! | `mail.sync.runs` | counter | `outcome`: ok, error, cancelled, over_budget |
+//! | `mail.sync.duration` | histogram (s) | `mode`: initial, incremental |
+//! | `mail.sync.phase.duration` | histogram (s) | `phase`: see [`SyncTiming::phases`] |
+//! | `mail.sync.messages` | counter | `action`: created, updated, skipped |
+//! | `mail.sync.errors` | counter | |
+//! | `mail.sync.throughput` | histogram (messages/s) | `mode` |
+//! | `mail.sync.pending` | gauge (messages) | |
+//! | `mail.sync.backpressure` | counter | |
+//! | `mail.gmail.requests` | counter | `outcome`: ok, an HTTP status, network |
+//! | `mail.gmail.retries` | counter | |
+//! | `mail.gmail.batch.duration` | histogram (s) | |
+//! | `mail.gmail.batch.size` | histogram (messages) | |
+//! | `mail.gmail.quota.units` | counter | `call`: see [`ApiCall::name`] |
+//! | `mail.gmail.quota.burn_rate` | gauge (units/s) | |
+
+#[cfg(feature = "otlp")]
+mod otlp;
+
+#[cfg(feature = "otlp")]
+pub use otlp::{DEFAULT_EXPORT_INTERVAL, OtlpExporter, install_otlp_exporter};
+
+use std::time::Duration;
+
+use metrics::{
+    Unit, counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram,
+};
+
+use crate::gmail::ApiCall;
+use crate::sync::{NetworkBudgetSpentError, SyncCancelledError, SyncStats, SyncTiming};
+
+pub const SYNC_RUNS: &str = "mail.sync.runs";
+pub const SYNC_DURATION: &str = "mail.sync.duration";
+pub const SYNC_PHASE_DURATION: &str = "mail.sync.phase.duration";
+pub const SYNC_MESSAGES: &str = "mail.sync.messages";
+pub const SYNC_ERRORS: &str = "mail.sync.errors";
+pub const SYNC_THROUGHPUT: &str = "mail.sync.throughput";
+pub const SYNC_PENDING: &str = "mail.sync.pending";
+pub const SYNC_BACKPRESSURE: &str = "mail.sync.backpressure";
+pub const GMAIL_REQUESTS: &str = "mail.gmail.requests";
+pub const GMAIL_RETRIES: &str = "mail.gmail.retries";
+pub const GMAIL_BATCH_DURATION: &str = "mail.gmail.batch.duration";
+pub const GMAIL_BATCH_SIZE: &str = "mail.gmail.batch.size";
+pub const GMAIL_QUOTA_UNITS: &str = "mail.gmail.quota.units";
+pub const GMAIL_QUOTA_BURN_RATE: &str = "mail.gmail.quota.burn_rate";
+
+/// Register units and descriptions with the installed recorder
+///
+/// Call once, right after installing a recorder, so exporters that fix an
+/// instrument's description when it is created see them.
+pub fn describe_metrics() {
+    describe_counter!(SYNC_RUNS, "Sync runs, by outcome");
+    describe_histogram!(
+        SYNC_DURATION,
+        Unit::Seconds,
+        "Wall-clock time of a sync run"
+    );
+    describe_histogram!(
+        SYNC_PHASE_DURATION,
+        Unit::Seconds,
+        "Time a sync run spent in each phase"
+    );
+    describe_counter!(
+        SYNC_MESSAGES,
+        "Messages created, updated or skipped by sync"
+    );
+    describe_counter!(SYNC_ERRORS, "Messages sync failed to fetch or store");
+    describe_histogram!(SYNC_THROUGHPUT, "Messages stored per second of a sync run");
+    describe_gauge!(SYNC_PENDING, "Fetched messages waiting to be processed");
+    describe_counter!(
+        SYNC_BACKPRESSURE,
+        "Times fetching waited for a full pending queue"
+    );
+    describe_counter!(GMAIL_REQUESTS, "Gmail API requests, by outcome");
+    describe_counter!(
+        GMAIL_RETRIES,
+        "Gmail API requests retried after a transient failure"
+    );
+    describe_histogram!(
+        GMAIL_BATCH_DURATION,
+        Unit::Seconds,
+        "Time of one Gmail batch request"
+    );
+    describe_histogram!(
+        GMAIL_BATCH_SIZE,
+        "Messages requested per Gmail batch request"
+    );
+    describe_counter!(GMAIL_QUOTA_UNITS, "Gmail quota units spent by sync, by call");
+    describe_gauge!(
+        GMAIL_QUOTA_BURN_RATE,
+        "Gmail quota units per second spent by sync"
+    );
+}
+
+/// Report a finished sync run
+pub(crate) fn record_sync(stats: &SyncStats) {
+    let mode = if stats.was_incremental {
+        "incremental"
+    } else {
+        "initial"
+    };
+    counter!(SYNC_RUNS, "outcome" => "ok").increment(1);
+    histogram!(SYNC_DURATION, "mode" => mode).record(seconds(stats.duration_ms));
+    record_phases(&stats.timing);
+
+    for (action, count) in [
+        ("created", stats.messages_created),
+        ("updated", stats.messages_updated),
+        ("skipped", stats.messages_skipped),
+    ] {
+        counter!(SYNC_MESSAGES, "action" => action).increment(count as u64);
+    }
+    counter!(SYNC_ERRORS).increment(stats.errors as u64);
+
+    let stored = stats.messages_stored();
+    if stored > 0 && stats.duration_ms > 0 {
+        let per_second = stored as f64 / seconds(stats.duration_ms);
+        histogram!(SYNC_THROUGHPUT, "mode" => mode).record(per_second);
+    }
+}
+
+/// Report a sync run that failed, was cancelled or ran out of network budget
+pub(crate) fn record_sync_failure(error: &anyhow::Error) {
+    let outcome = if error.downcast_ref::<SyncCancelledError>().is_some() {
+        "cancelled"
+    } else if error.downcast_ref::<NetworkBudgetSpentError>().is_some() {
+        "over_budget"
+    } else {
+        "error"
+    };
+    counter!(SYNC_RUNS, "outcome" => outcome).increment(1);
+}
+
+/// Report the time spent in each phase, skipping phases that didn't run
+fn record_phases(timing: &SyncTiming) {
+    for (phase, ms) in timing.phases() {
+        if ms > 0 {
+            histogram!(SYNC_PHASE_DURATION, "phase" => phase).record(seconds(ms));
+        }
+    }
+}
+
+/// Report the size of the pending message queue
+pub(crate) fn record_pending(count: usize) {
+    gauge!(SYNC_PENDING).set(count as f64);
+}
+
+/// Report fetching waiting for the pending queue to drain
+pub(crate) fn record_backpressure() {
+    counter!(SYNC_BACKPRESSURE).increment(1);
+}
+
+/// Report one attempt at a Gmail API request
+pub(crate) fn record_request(error: Option<&ureq::Error>) {
+    let outcome = match error {
+        None => "ok".to_string(),
+        Some(ureq::Error::StatusCode(status)) => status.to_string(),
+        Some(_) => "network".to_string(),
+    };
+    counter!(GMAIL_REQUESTS, "outcome" => outcome).increment(1);
+}
+
+/// Report a Gmail API request about to be retried
+pub(crate) fn record_retry() {
+    counter!(GMAIL_RETRIES).increment(1);
+}
+
+/// Report one Gmail batch request
+pub(crate) fn record_batch(size: usize, elapsed: Duration) {
+    histogram!(GMAIL_BATCH_SIZE).record(size as f64);
+    histogram!(GMAIL_BATCH_DURATION).record(elapsed.as_secs_f64());
+}
+
+/// Report Gmail quota units spent on a call and the resulting burn rate
+pub(crate) fn record_quota(call: ApiCall, units: u64, burn_rate: f64) {
+    counter!(GMAIL_QUOTA_UNITS, "call" => call.name()).increment(units);
+    gauge!(GMAIL_QUOTA_BURN_RATE).set(burn_rate);
+}
+
+fn seconds(ms: u64) -> f64 {
+    ms as f64 / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_record_sync() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let stats = SyncStats {
+            messages_created: 8,
+            messages_updated: 2,
+            was_incremental: true,
+            duration_ms: 2000,
+            timing: SyncTiming {
+                history_ms: 300,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        metrics::with_local_recorder(&recorder, || {
+            record_sync(&stats);
+            record_request(None);
+            record_request(Some(&ureq::Error::StatusCode(429)));
+            record_quota(ApiCall::GetMessage, 125, 25.0);
+        });
+
+        // Keyed as name{label=value,...}
+        let values: HashMap<String, DebugValue> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| {
+                let key = key.key();
+                let labels: Vec<String> = key
+                    .labels()
+                    .map(|label| format!("{}={}", label.key(), label.value()))
+                    .collect();
+                (format!("{}{{{}}}", key.name(), labels.join(",")), value)
+            })
+            .collect();
+
+        assert_eq!(values["mail.sync.runs{outcome=ok}"], DebugValue::Counter(1));
+        assert_eq!(
+            values["mail.sync.messages{action=created}"],
+            DebugValue::Counter(8)
+        );
+        assert_eq!(
+            values["mail.sync.throughput{mode=incremental}"],
+            DebugValue::Histogram(vec![5.0.into()])
+        );
+        assert_eq!(
+            values["mail.sync.phase.duration{phase=history}"],
+            DebugValue::Histogram(vec![0.3.into()])
+        );
+        // Phases that didn't run aren't reported
+        assert!(!values.contains_key("mail.sync.phase.duration{phase=profile}"));
+        assert_eq!(values["mail.gmail.requests{outcome=ok}"], DebugValue::Counter(1));
+        assert_eq!(values["mail.gmail.requests{outcome=429}"], DebugValue::Counter(1));
+        assert_eq!(
+            values["mail.gmail.quota.units{call=messages.get}"],
+            DebugValue::Counter(125)
+        );
+        assert_eq!(
+            values["mail.gmail.quota.burn_rate{}"],
+            DebugValue::Gauge(25.0.into())
+        );
+    }
+}
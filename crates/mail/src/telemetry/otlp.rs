@@ -0,0 +1,248 @@
+//! Export to an OpenTelemetry collector
+//!
+//! Bridges the `metrics` facade to OpenTelemetry instruments and pushes them
+//! over OTLP/HTTP from a background thread, so no async runtime is needed.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use log::warn;
+use metrics::{
+    Counter, CounterFn, Gauge, GaugeFn, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder,
+    SharedString, Unit,
+};
+use opentelemetry::KeyValue;
+use opentelemetry::metrics::{Meter, MeterProvider};
+use opentelemetry_otlp::{MetricExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+
+/// Interval between pushes used when the app has no preference
+pub const DEFAULT_EXPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Keeps metrics flowing to the collector
+///
+/// Dropping it pushes what has been recorded since the last export and
+/// stops the exporter.
+pub struct OtlpExporter {
+    provider: SdkMeterProvider,
+}
+
+impl Drop for OtlpExporter {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            warn!("Failed to flush metrics: {}", e);
+        }
+    }
+}
+
+/// Push mail metrics to an OTLP/HTTP collector every `interval`
+///
+/// `endpoint` is the collector's metrics URL, e.g.
+/// `http://localhost:4318/v1/metrics`. Installs the process-wide `metrics`
+/// recorder, so it fails if one is already installed.
+pub fn install_otlp_exporter(
+    endpoint: &str,
+    service_name: &str,
+    interval: Duration,
+) -> Result<OtlpExporter> {
+    let exporter = MetricExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()
+        .context("Failed to create OTLP metric exporter")?;
+    let reader = PeriodicReader::builder(exporter)
+        .with_interval(interval)
+        .build();
+    let provider = SdkMeterProvider::builder()
+        .with_reader(reader)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(service_name.to_string())
+                .build(),
+        )
+        .build();
+
+    let recorder = OtelRecorder {
+        meter: provider.meter("mail"),
+        descriptions: Mutex::default(),
+        instruments: Mutex::default(),
+    };
+    metrics::set_global_recorder(recorder)
+        .map_err(|_| anyhow!("A metrics recorder is already installed"))?;
+    super::describe_metrics();
+
+    Ok(OtlpExporter { provider })
+}
+
+/// Unit and description registered for a metric name
+type Description = (Option<Unit>, SharedString);
+
+/// Instruments created so far, by metric key
+#[derive(Default)]
+struct Instruments {
+    counters: HashMap<Key, Arc<OtelCounter>>,
+    gauges: HashMap<Key, Arc<OtelGauge>>,
+    histograms: HashMap<Key, Arc<OtelHistogram>>,
+}
+
+/// `metrics` recorder creating an OpenTelemetry instrument per metric
+struct OtelRecorder {
+    meter: Meter,
+    descriptions: Mutex<HashMap<String, Description>>,
+    instruments: Mutex<Instruments>,
+}
+
+impl OtelRecorder {
+    fn describe(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.descriptions
+            .lock()
+            .unwrap()
+            .insert(key.as_str().to_string(), (unit, description));
+    }
+
+    /// Description and unit label for a metric, if it was described
+    fn description_of(&self, key: &Key) -> (String, String) {
+        match self.descriptions.lock().unwrap().get(key.name()) {
+            Some((unit, description)) => (
+                description.to_string(),
+                unit.map(|unit| unit.as_canonical_label().to_string())
+                    .unwrap_or_default(),
+            ),
+            None => (String::new(), String::new()),
+        }
+    }
+}
+
+impl Recorder for OtelRecorder {
+    fn describe_counter(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.describe(key, unit, description);
+    }
+
+    fn describe_gauge(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.describe(key, unit, description);
+    }
+
+    fn describe_histogram(&self, key: KeyName, unit: Option<Unit>, description: SharedString) {
+        self.describe(key, unit, description);
+    }
+
+    fn register_counter(&self, key: &Key, _metadata: &Metadata<'_>) -> Counter {
+        let mut instruments = self.instruments.lock().unwrap();
+        let counter = instruments.counters.entry(key.clone()).or_insert_with(|| {
+            let (description, unit) = self.description_of(key);
+            Arc::new(OtelCounter {
+                counter: self
+                    .meter
+                    .u64_counter(key.name().to_string())
+                    .with_description(description)
+                    .with_unit(unit)
+                    .build(),
+                attributes: attributes(key),
+            })
+        });
+        Counter::from_arc(counter.clone())
+    }
+
+    fn register_gauge(&self, key: &Key, _metadata: &Metadata<'_>) -> Gauge {
+        let mut instruments = self.instruments.lock().unwrap();
+        let gauge = instruments.gauges.entry(key.clone()).or_insert_with(|| {
+            let (description, unit) = self.description_of(key);
+            Arc::new(OtelGauge {
+                gauge: self
+                    .meter
+                    .f64_gauge(key.name().to_string())
+                    .with_description(description)
+                    .with_unit(unit)
+                    .build(),
+                attributes: attributes(key),
+                value: Mutex::new(0.0),
+            })
+        });
+        Gauge::from_arc(gauge.clone())
+    }
+
+    fn register_histogram(&self, key: &Key, _metadata: &Metadata<'_>) -> Histogram {
+        let mut instruments = self.instruments.lock().unwrap();
+        let histogram = instruments
+            .histograms
+            .entry(key.clone())
+            .or_insert_with(|| {
+                let (description, unit) = self.description_of(key);
+                Arc::new(OtelHistogram {
+                    histogram: self
+                        .meter
+                        .f64_histogram(key.name().to_string())
+                        .with_description(description)
+                        .with_unit(unit)
+                        .build(),
+                    attributes: attributes(key),
+                })
+            });
+        Histogram::from_arc(histogram.clone())
+    }
+}
+
+/// OpenTelemetry attributes for a metric's labels
+fn attributes(key: &Key) -> Vec<KeyValue> {
+    key.labels()
+        .map(|label| KeyValue::new(label.key().to_string(), label.value().to_string()))
+        .collect()
+}
+
+struct OtelCounter {
+    counter: opentelemetry::metrics::Counter<u64>,
+    attributes: Vec<KeyValue>,
+}
+
+impl CounterFn for OtelCounter {
+    fn increment(&self, value: u64) {
+        self.counter.add(value, &self.attributes);
+    }
+
+    fn absolute(&self, _value: u64) {
+        // OpenTelemetry counters only add; no mail metric sets an absolute value
+    }
+}
+
+struct OtelGauge {
+    gauge: opentelemetry::metrics::Gauge<f64>,
+    attributes: Vec<KeyValue>,
+    /// Current value, for increments and decrements
+    value: Mutex<f64>,
+}
+
+impl OtelGauge {
+    fn update(&self, f: impl FnOnce(f64) -> f64) {
+        let mut value = self.value.lock().unwrap();
+        *value = f(*value);
+        self.gauge.record(*value, &self.attributes);
+    }
+}
+
+impl GaugeFn for OtelGauge {
+    fn increment(&self, value: f64) {
+        self.update(|current| current + value);
+    }
+
+    fn decrement(&self, value: f64) {
+        self.update(|current| current - value);
+    }
+
+    fn set(&self, value: f64) {
+        self.update(|_| value);
+    }
+}
+
+struct OtelHistogram {
+    histogram: opentelemetry::metrics::Histogram<f64>,
+    attributes: Vec<KeyValue>,
+}
+
+impl HistogramFn for OtelHistogram {
+    fn record(&self, value: f64) {
+        self.histogram.record(value, &self.attributes);
+    }
+}
@@ -0,0 +1,259 @@
+//! Opt-in open tracking for sent mail
+//!
+//! Off unless `mail.tracking.json` in the Cosmos config directory enables it
+//! and names an endpoint the user runs themselves; nothing is ever sent to a
+//! third party. When enabled, messages composed with
+//! `OutgoingMessage::track_opens(true)` carry a 1x1 image whose URL holds a
+//! random token, and the endpoint logs requests for it.
+//!
+//! The endpoint is expected to serve:
+//! - `GET {endpoint}?t={token}`: the beacon image, recording an open
+//! - `GET {endpoint}/opens?since={rfc3339}`: opens recorded after `since`, as
+//!   `[{"token": "...", "opened_at": "2026-01-10T09:30:00Z"}]`
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::MailStore;
+
+/// Config file holding open tracking settings
+pub const TRACKING_CONFIG_FILE: &str = "mail.tracking.json";
+
+/// Open tracking settings
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrackingConfig {
+    /// Whether tracking may be used at all (off by default)
+    pub enabled: bool,
+    /// Base URL of the user's beacon endpoint, e.g. "https://t.example.com/open"
+    pub endpoint: Option<String>,
+}
+
+impl TrackingConfig {
+    /// Load settings from the config directory, falling back to disabled
+    ///
+    /// An unreadable or malformed file is logged and ignored.
+    pub fn load() -> Self {
+        if !config::config_exists(TRACKING_CONFIG_FILE) {
+            return Self::default();
+        }
+        match config::load_json(TRACKING_CONFIG_FILE) {
+            Ok(tracking_config) => tracking_config,
+            Err(e) => {
+                warn!("Ignoring invalid tracking config: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// The endpoint, if tracking is enabled and the endpoint is an HTTP(S) URL
+    pub fn active_endpoint(&self) -> Option<&str> {
+        if !self.enabled {
+            return None;
+        }
+        let endpoint = self.endpoint.as_deref()?.trim_end_matches('/');
+        let url = url::Url::parse(endpoint).ok()?;
+        matches!(url.scheme(), "https" | "http").then_some(endpoint)
+    }
+
+    /// Whether beacons can be embedded
+    pub fn is_active(&self) -> bool {
+        self.active_endpoint().is_some()
+    }
+
+    /// The beacon URL for a token
+    pub fn beacon_url(&self, token: &str) -> Option<String> {
+        let endpoint = self.active_endpoint()?;
+        let separator = if endpoint.contains('?') { '&' } else { '?' };
+        Some(format!("{}{}t={}", endpoint, separator, token))
+    }
+}
+
+/// Generate an unguessable beacon token (128 bits, hex)
+pub fn new_token() -> String {
+    // Each RandomState gets fresh random keys, so two of them yield two
+    // independent random words
+    let word = || {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(Utc::now().timestamp_nanos_opt().unwrap_or_default() as u128);
+        hasher.finish()
+    };
+    format!("{:016x}{:016x}", word(), word())
+}
+
+/// An image tag loading the beacon
+pub fn beacon_html(url: &str) -> String {
+    format!(
+        "<img src=\"{}\" width=\"1\" height=\"1\" alt=\"\" style=\"display:none\">",
+        url.replace('&', "&amp;").replace('"', "&quot;")
+    )
+}
+
+/// An open reported by the endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BeaconOpen {
+    pub token: String,
+    pub opened_at: DateTime<Utc>,
+}
+
+/// Fetch opens the endpoint recorded after `since`
+pub fn fetch_opens(
+    config: &TrackingConfig,
+    since: Option<DateTime<Utc>>,
+) -> Result<Vec<BeaconOpen>> {
+    let endpoint = config
+        .active_endpoint()
+        .context("Open tracking is not enabled")?;
+    let mut url = format!("{}/opens", endpoint);
+    if let Some(since) = since {
+        url.push_str(&format!(
+            "?since={}",
+            urlencoding::encode(&since.to_rfc3339())
+        ));
+    }
+
+    let mut response = ureq::get(&url)
+        .call()
+        .context("Failed to fetch opens from tracking endpoint")?;
+    response
+        .body_mut()
+        .read_json()
+        .context("Failed to parse opens from tracking endpoint")
+}
+
+/// Fetch new opens from the endpoint and record them
+///
+/// Returns the number of opens recorded. Opens for tokens this store didn't
+/// issue are ignored.
+pub fn sync_opens(store: &dyn MailStore, config: &TrackingConfig) -> Result<usize> {
+    let since = store.latest_message_open()?;
+    let opens = fetch_opens(config, since)?;
+    let recorded = record_opens(store, &opens)?;
+    if recorded > 0 {
+        info!("Recorded {} message opens", recorded);
+    }
+    Ok(recorded)
+}
+
+/// Record opens, skipping unknown tokens and duplicates
+fn record_opens(store: &dyn MailStore, opens: &[BeaconOpen]) -> Result<usize> {
+    let mut recorded = 0;
+    for open in opens {
+        if store.record_message_open(&open.token, open.opened_at)? {
+            recorded += 1;
+        }
+    }
+    Ok(recorded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MessageId;
+    use crate::storage::InMemoryMailStore;
+    use chrono::TimeZone;
+
+    fn enabled(endpoint: &str) -> TrackingConfig {
+        TrackingConfig {
+            enabled: true,
+            endpoint: Some(endpoint.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_config_is_opt_in() {
+        assert!(!TrackingConfig::default().is_active());
+        let disabled = TrackingConfig {
+            enabled: false,
+            endpoint: Some("https://t.example.com/open".to_string()),
+        };
+        assert!(!disabled.is_active());
+        assert!(!enabled("not a url").is_active());
+        assert!(!enabled("ftp://t.example.com").is_active());
+
+        let config: TrackingConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, TrackingConfig::default());
+    }
+
+    #[test]
+    fn test_beacon_url() {
+        let config = enabled("https://t.example.com/open/");
+        assert_eq!(
+            config.beacon_url("abc").as_deref(),
+            Some("https://t.example.com/open?t=abc")
+        );
+        let config = enabled("https://t.example.com/open?user=me");
+        assert_eq!(
+            config.beacon_url("abc").as_deref(),
+            Some("https://t.example.com/open?user=me&t=abc")
+        );
+        assert!(beacon_html("https://x/?a=1&t=abc").contains("src=\"https://x/?a=1&amp;t=abc\""));
+    }
+
+    #[test]
+    fn test_new_token() {
+        let a = new_token();
+        assert_eq!(a.len(), 32);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(a, new_token());
+    }
+
+    #[test]
+    fn test_record_opens() {
+        let store = InMemoryMailStore::new();
+        store
+            .save_tracking_token("tok1", &MessageId::new("m1"))
+            .unwrap();
+        let at = |h| Utc.with_ymd_and_hms(2026, 1, 10, h, 0, 0).unwrap();
+        let opens = vec![
+            BeaconOpen {
+                token: "tok1".to_string(),
+                opened_at: at(9),
+            },
+            BeaconOpen {
+                token: "tok1".to_string(),
+                opened_at: at(9),
+            },
+            BeaconOpen {
+                token: "unknown".to_string(),
+                opened_at: at(10),
+            },
+            BeaconOpen {
+                token: "tok1".to_string(),
+                opened_at: at(11),
+            },
+        ];
+
+        assert_eq!(record_opens(&store, &opens).unwrap(), 2);
+        let status = store
+            .get_open_status(&MessageId::new("m1"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(status.open_count, 2);
+        assert_eq!(status.first_opened_at, Some(at(9)));
+        assert_eq!(status.last_opened_at, Some(at(11)));
+        assert_eq!(store.latest_message_open().unwrap(), Some(at(11)));
+
+        // Untracked messages have no status; tracked but unopened ones do
+        assert!(
+            store
+                .get_open_status(&MessageId::new("m2"))
+                .unwrap()
+                .is_none()
+        );
+        store
+            .save_tracking_token("tok2", &MessageId::new("m2"))
+            .unwrap();
+        let unopened = store
+            .get_open_status(&MessageId::new("m2"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(unopened.open_count, 0);
+        assert_eq!(unopened.last_opened_at, None);
+    }
+}
@@ -0,0 +1,237 @@
+//! Language detection and message translation
+//!
+//! Each message's body language is detected during normalization and stored
+//! as `Message::lang`. Translation itself is left to an integration (a local
+//! model or a translation API) behind the [`Translator`] trait; results are
+//! cached in the store so a body is only translated once per language.
+//!
+//! Language codes are ISO 639-3 throughout (e.g. "eng", "fra", "deu").
+
+use anyhow::Result;
+
+use crate::models::{Message, MessageId};
+use crate::storage::MailStore;
+
+/// Characters of body text sampled for detection
+const DETECTION_SAMPLE_CHARS: usize = 2000;
+
+/// Detect the language of a piece of text
+///
+/// Returns None for text too short or too mixed to classify reliably.
+pub fn detect_language(text: &str) -> Option<String> {
+    let sample: String = text.chars().take(DETECTION_SAMPLE_CHARS).collect();
+    let info = whatlang::detect(&sample)?;
+    info.is_reliable().then(|| info.lang().code().to_string())
+}
+
+/// Format of a body handed to a [`Translator`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyFormat {
+    /// Plain text
+    Text,
+    /// HTML; markup should be preserved
+    Html,
+}
+
+/// Provides translations of message bodies
+pub trait Translator: Send + Sync {
+    /// Translate `text` into `target_lang`
+    ///
+    /// `source_lang` is the detected language, if known.
+    fn translate(
+        &self,
+        text: &str,
+        format: BodyFormat,
+        source_lang: Option<&str>,
+        target_lang: &str,
+    ) -> Result<String>;
+}
+
+/// A message body in the requested language
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranslatedBody {
+    /// Body content
+    pub body: String,
+    /// Whether `body` is plain text or HTML
+    pub format: BodyFormat,
+    /// Whether the body was translated (false if already in the target language)
+    pub translated: bool,
+}
+
+/// Get a message's body translated into `target_lang`
+///
+/// Plain text bodies are preferred over HTML. Bodies already in the target
+/// language are returned as-is, and translations are cached in the store.
+/// Returns None if the message doesn't exist or has no body.
+pub fn get_translated_body(
+    store: &dyn MailStore,
+    translator: &dyn Translator,
+    message_id: &MessageId,
+    target_lang: &str,
+) -> Result<Option<TranslatedBody>> {
+    let Some(message) = store.get_message(message_id)? else {
+        return Ok(None);
+    };
+    let Some((body, format)) = source_body(&message) else {
+        return Ok(None);
+    };
+
+    if message.lang.as_deref() == Some(target_lang) {
+        return Ok(Some(TranslatedBody {
+            body: body.to_string(),
+            format,
+            translated: false,
+        }));
+    }
+
+    if let Some(cached) = store.get_translation(message_id, target_lang)? {
+        return Ok(Some(TranslatedBody {
+            body: cached,
+            format,
+            translated: true,
+        }));
+    }
+
+    let translated = translator.translate(body, format, message.lang.as_deref(), target_lang)?;
+    store.put_translation(message_id, target_lang, &translated)?;
+
+    Ok(Some(TranslatedBody {
+        body: translated,
+        format,
+        translated: true,
+    }))
+}
+
+/// The body to translate: plain text if present, otherwise HTML
+fn source_body(message: &Message) -> Option<(&str, BodyFormat)> {
+    if let Some(text) = message
+        .body_text
+        .as_deref()
+        .filter(|t| !t.trim().is_empty())
+    {
+        return Some((text, BodyFormat::Text));
+    }
+    message
+        .body_html
+        .as_deref()
+        .filter(|h| !h.trim().is_empty())
+        .map(|html| (html, BodyFormat::Html))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Thread, ThreadId};
+    use crate::storage::InMemoryMailStore;
+    use chrono::Utc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Upper-cases text and counts calls
+    struct ShoutTranslator {
+        calls: AtomicUsize,
+    }
+
+    impl Translator for ShoutTranslator {
+        fn translate(
+            &self,
+            text: &str,
+            _format: BodyFormat,
+            _source_lang: Option<&str>,
+            target_lang: &str,
+        ) -> Result<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(format!("[{}] {}", target_lang, text.to_uppercase()))
+        }
+    }
+
+    fn store_with(message: Message) -> InMemoryMailStore {
+        let store = InMemoryMailStore::new();
+        store
+            .upsert_thread(Thread {
+                id: message.thread_id.clone(),
+                account_id: 1,
+                subject: String::new(),
+                snippet: String::new(),
+                last_message_at: Utc::now(),
+                message_count: 1,
+                sender_name: None,
+                sender_email: String::new(),
+                is_unread: false,
+                participants: Vec::new(),
+            })
+            .unwrap();
+        store.upsert_message(message).unwrap();
+        store
+    }
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(
+            detect_language(
+                "Bonjour à tous, la réunion de demain est reportée à jeudi matin. \
+                 Merci de confirmer votre présence avant ce soir."
+            )
+            .as_deref(),
+            Some("fra")
+        );
+        assert_eq!(detect_language("ok"), None);
+    }
+
+    #[test]
+    fn test_get_translated_body_caches() {
+        let message = Message::builder(MessageId::new("m1"), ThreadId::new("t1"))
+            .body_text(Some("bonjour".to_string()))
+            .lang(Some("fra".to_string()))
+            .build();
+        let store = store_with(message);
+        let translator = ShoutTranslator {
+            calls: AtomicUsize::new(0),
+        };
+        let id = MessageId::new("m1");
+
+        let first = get_translated_body(&store, &translator, &id, "eng")
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.body, "[eng] BONJOUR");
+        assert_eq!(first.format, BodyFormat::Text);
+        assert!(first.translated);
+
+        let second = get_translated_body(&store, &translator, &id, "eng")
+            .unwrap()
+            .unwrap();
+        assert_eq!(second, first);
+        assert_eq!(translator.calls.load(Ordering::SeqCst), 1);
+
+        // Already in the target language
+        let same = get_translated_body(&store, &translator, &id, "fra")
+            .unwrap()
+            .unwrap();
+        assert_eq!(same.body, "bonjour");
+        assert!(!same.translated);
+        assert_eq!(translator.calls.load(Ordering::SeqCst), 1);
+
+        let missing = MessageId::new("nope");
+        assert!(
+            get_translated_body(&store, &translator, &missing, "eng")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_get_translated_body_html_fallback() {
+        let message = Message::builder(MessageId::new("m1"), ThreadId::new("t1"))
+            .body_html(Some("<p>hola</p>".to_string()))
+            .build();
+        let store = store_with(message);
+        let translator = ShoutTranslator {
+            calls: AtomicUsize::new(0),
+        };
+
+        let body = get_translated_body(&store, &translator, &MessageId::new("m1"), "eng")
+            .unwrap()
+            .unwrap();
+        assert_eq!(body.format, BodyFormat::Html);
+        assert_eq!(body.body, "[eng] <P>HOLA</P>");
+    }
+}
@@ -6,7 +6,8 @@ use chrono::Utc;
 use mail::models::{Account, EmailAddress, Message, MessageId, SyncState, Thread, ThreadId};
 use mail::query::{get_thread_detail, list_threads};
 use mail::storage::{FileBlobStore, InMemoryMailStore, MailStore, SqliteMailStore};
-use mail::{SyncAction, cooldown_elapsed, determine_sync_action, get_sync_state_info, should_auto_sync_on_startup};
+use mail::gmail::mock::{MockEndpoint, MockFailure, MockGmail, MockMessage};
+use mail::{ApiCall, NetworkBudget, NetworkBudgetSpentError, SyncAction, SyncOptions, cooldown_elapsed, determine_sync_action, get_sync_state_info, should_auto_sync_on_startup};
 use tempfile::TempDir;
 
 /// Helper to create test messages
@@ -63,7 +64,7 @@ fn test_full_sync_simulation() {
         .unwrap();
 
     // Verify threads are stored
-    let threads = list_threads(&store, 10, 0).unwrap();
+    let threads = list_threads(&store, None, 10).unwrap().threads;
     assert_eq!(threads.len(), 2);
 
     // Verify t2 comes first (more recent)
@@ -190,7 +191,7 @@ fn test_multiple_threads() {
     }
 
     // List all threads
-    let threads = list_threads(&store, 100, 0).unwrap();
+    let threads = list_threads(&store, None, 100).unwrap().threads;
     assert_eq!(threads.len(), 10);
 
     // Most recent should be first (t9 is newest)
@@ -198,19 +199,20 @@ fn test_multiple_threads() {
     assert_eq!(threads[9].id.as_str(), "t0");
 
     // Test pagination
-    let page1 = list_threads(&store, 3, 0).unwrap();
-    let page2 = list_threads(&store, 3, 3).unwrap();
-    assert_eq!(page1.len(), 3);
-    assert_eq!(page2.len(), 3);
-    assert_eq!(page1[0].id.as_str(), "t9");
-    assert_eq!(page2[0].id.as_str(), "t6");
+    let page1 = list_threads(&store, None, 3).unwrap();
+    let page2 = list_threads(&store, page1.next_cursor.as_ref(), 3).unwrap();
+    assert_eq!(page1.threads.len(), 3);
+    assert_eq!(page2.threads.len(), 3);
+    assert!(page1.has_more);
+    assert_eq!(page1.threads[0].id.as_str(), "t9");
+    assert_eq!(page2.threads[0].id.as_str(), "t6");
 }
 
 #[test]
 fn test_empty_store() {
     let store = InMemoryMailStore::new();
 
-    let threads = list_threads(&store, 10, 0).unwrap();
+    let threads = list_threads(&store, None, 10).unwrap().threads;
     assert!(threads.is_empty());
 
     let detail = get_thread_detail(&store, &ThreadId::new("nonexistent")).unwrap();
@@ -257,6 +259,8 @@ fn create_sqlite_store() -> (SqliteMailStore, TempDir) {
         is_primary: true,
         added_at: Utc::now(),
         token_data: None,
+        signature: None,
+        sync_enabled: true,
     };
     store.register_account(test_account).unwrap();
 
@@ -322,6 +326,8 @@ fn test_sqlite_sync_state_across_reopens() {
             is_primary: true,
             added_at: Utc::now(),
             token_data: None,
+            signature: None,
+            sync_enabled: true,
         };
         store.register_account(test_account).unwrap();
 
@@ -628,6 +634,8 @@ fn test_scenario_launch_resume_incomplete_sync() {
             is_primary: true,
             added_at: Utc::now(),
             token_data: None,
+            signature: None,
+            sync_enabled: true,
         };
         store.register_account(test_account).unwrap();
 
@@ -924,7 +932,7 @@ fn test_label_filtering_gmail_folder_parity() {
     store.upsert_message(archived_msg).unwrap();
 
     // Verify all threads are stored correctly
-    let all_threads = list_threads(&store, 100, 0).unwrap();
+    let all_threads = list_threads(&store, None, 100).unwrap().threads;
     assert_eq!(all_threads.len(), 5);
 
     // Verify message labels are preserved (this is what matters for Gmail parity)
@@ -1021,6 +1029,8 @@ fn test_account_management_methods() {
         is_primary: false,
         added_at: Utc::now(),
         token_data: Some("{\"access_token\":\"test\"}".to_string()),
+        signature: None,
+        sync_enabled: true,
     };
     let registered = store.register_account(second_account).unwrap();
     assert!(registered.id > 0); // ID should be assigned by database
@@ -1058,6 +1068,8 @@ fn test_unified_vs_filtered_thread_listing() {
         is_primary: true,
         added_at: Utc::now(),
         token_data: None,
+        signature: None,
+        sync_enabled: true,
     };
     let account2 = Account {
         id: 0,
@@ -1067,6 +1079,8 @@ fn test_unified_vs_filtered_thread_listing() {
         is_primary: false,
         added_at: Utc::now(),
         token_data: None,
+        signature: None,
+        sync_enabled: true,
     };
     let alice = store.register_account(account1).unwrap();
     let bob = store.register_account(account2).unwrap();
@@ -1187,6 +1201,8 @@ fn test_clear_account_data() {
         is_primary: true,
         added_at: Utc::now(),
         token_data: None,
+        signature: None,
+        sync_enabled: true,
     };
     let account2 = Account {
         id: 0,
@@ -1196,6 +1212,8 @@ fn test_clear_account_data() {
         is_primary: false,
         added_at: Utc::now(),
         token_data: None,
+        signature: None,
+        sync_enabled: true,
     };
     let alice = store.register_account(account1).unwrap();
     let bob = store.register_account(account2).unwrap();
@@ -1301,6 +1319,8 @@ fn test_delete_account_cascade() {
         is_primary: true,
         added_at: Utc::now(),
         token_data: None,
+        signature: None,
+        sync_enabled: true,
     };
     let registered = store.register_account(account).unwrap();
     let account_id = registered.id;
@@ -1347,3 +1367,312 @@ fn test_delete_account_cascade() {
     assert!(store.get_sync_state(account_id).unwrap().is_none(), "Sync state should be deleted");
     assert_eq!(store.list_accounts().unwrap().len(), 0, "No accounts should remain");
 }
+
+// === Mock Gmail Sync Tests ===
+
+/// A mailbox of five messages in three threads, listed two per page
+fn mock_mailbox() -> MockGmail {
+    MockGmail::new()
+        .with_page_size(2)
+        .with_message(MockMessage::new("m1", "t1").subject("Plans").body("Lunch on Friday?"))
+        .with_message(MockMessage::new("m2", "t1").subject("Re: Plans").body("Sure"))
+        .with_message(MockMessage::new("m3", "t2").subject("Invoice").labels(&["INBOX", "UNREAD"]))
+        .with_message(MockMessage::new("m4", "t3").subject("Notes").labels(&["SENT"]))
+        .with_message(MockMessage::new("m5", "t2").subject("Re: Invoice"))
+}
+
+/// Assert that every stored thread agrees with its messages
+fn assert_threads_consistent(store: &dyn MailStore) {
+    let mismatches = mail::check_thread_invariants(store).unwrap();
+    assert!(mismatches.is_empty(), "inconsistent threads: {:?}", mismatches);
+}
+
+#[test]
+fn test_mock_initial_sync() {
+    let gmail = mock_mailbox();
+    let store = InMemoryMailStore::new();
+
+    let stats = mail::sync_gmail(&gmail, &store, 1, SyncOptions::default()).unwrap();
+    assert!(!stats.was_incremental);
+    assert_eq!(stats.messages_created, 5);
+    assert_eq!(stats.errors, 0);
+
+    assert_eq!(store.count_threads().unwrap(), 3);
+    assert_eq!(store.count_pending_messages(1, None).unwrap(), 0);
+    let thread = store.get_thread(&ThreadId::new("t2")).unwrap().unwrap();
+    assert_eq!(thread.message_count, 2);
+    assert!(thread.is_unread);
+
+    let state = store.get_sync_state(1).unwrap().unwrap();
+    assert!(state.initial_sync_complete);
+    assert_eq!(state.history_id, gmail.history_id());
+    // Three pages of two
+    assert_eq!(gmail.request_count(MockEndpoint::ListMessages), 3);
+    assert_threads_consistent(&store);
+}
+
+#[test]
+fn test_mock_full_pending_queue_pauses_fetch() {
+    let gmail = mock_mailbox();
+    let store = InMemoryMailStore::new();
+
+    // Every page fills the queue, so each fetch stops and resumes after
+    // processing
+    let options = SyncOptions {
+        max_pending: 2,
+        ..SyncOptions::default()
+    };
+    let stats = mail::sync_gmail(&gmail, &store, 1, options).unwrap();
+    assert_eq!(stats.messages_created, 5);
+    assert_eq!(store.count_threads().unwrap(), 3);
+    assert_eq!(store.count_pending_messages(1, None).unwrap(), 0);
+    assert!(store.get_sync_state(1).unwrap().unwrap().initial_sync_complete);
+
+    // Resuming continued from the saved page token
+    assert_eq!(gmail.request_count(MockEndpoint::ListMessages), 3);
+    assert_threads_consistent(&store);
+}
+
+#[test]
+fn test_mock_network_budget_stops_fetch() {
+    let gmail = mock_mailbox();
+    let store = InMemoryMailStore::new();
+
+    // The first message downloaded spends the budget
+    let options = SyncOptions {
+        network: NetworkBudget {
+            max_bytes: Some(1),
+            max_concurrent_fetches: Some(1),
+        },
+        ..SyncOptions::default()
+    };
+    let err = mail::sync_gmail(&gmail, &store, 1, options).unwrap_err();
+    assert!(err.downcast_ref::<NetworkBudgetSpentError>().is_some());
+    assert_eq!(gmail.request_count(MockEndpoint::GetMessage), 1);
+    assert!(!store.get_sync_state(1).unwrap().unwrap().initial_sync_complete);
+
+    // The next sync picks up the rest
+    mail::sync_gmail(&gmail, &store, 1, SyncOptions::default()).unwrap();
+    assert!(store.get_sync_state(1).unwrap().unwrap().initial_sync_complete);
+    assert_eq!(store.count_threads().unwrap(), 3);
+    assert_eq!(store.count_pending_messages(1, None).unwrap(), 0);
+    assert_threads_consistent(&store);
+}
+
+#[test]
+fn test_mock_sync_accounts_quota() {
+    let gmail = mock_mailbox();
+    let store = InMemoryMailStore::new();
+
+    let stats = mail::sync_gmail(&gmail, &store, 1, SyncOptions::default()).unwrap();
+    let quota = &stats.quota;
+    assert_eq!(quota.units_for(ApiCall::GetProfile), 1);
+    // Three pages and five messages at 5 units each
+    assert_eq!(quota.units_for(ApiCall::ListMessages), 15);
+    assert_eq!(quota.units_for(ApiCall::GetMessage), 25);
+    // The catch-up sync's history listing
+    assert_eq!(quota.units_for(ApiCall::ListHistory), 2);
+    assert_eq!(quota.units(), 43);
+    assert!(quota.burn_rate() > 0.0);
+}
+
+#[test]
+fn test_mock_interrupted_sync_resumes() {
+    let gmail = mock_mailbox();
+    let store = InMemoryMailStore::new();
+
+    // The second page fails, after the first was fetched and checkpointed
+    gmail.fail_request(MockEndpoint::ListMessages, 2, MockFailure::Status(503));
+    assert!(mail::sync_gmail(&gmail, &store, 1, SyncOptions::default()).is_err());
+
+    let state = store.get_sync_state(1).unwrap().unwrap();
+    assert!(!state.initial_sync_complete);
+    assert_eq!(state.messages_listed, 2);
+    assert!(state.fetch_page_token.is_some());
+    assert_eq!(store.count_pending_messages(1, None).unwrap(), 2);
+
+    let stats = mail::sync_gmail(&gmail, &store, 1, SyncOptions::default()).unwrap();
+    assert_eq!(stats.messages_created, 5);
+    assert!(store.get_sync_state(1).unwrap().unwrap().initial_sync_complete);
+    assert_eq!(store.count_threads().unwrap(), 3);
+
+    // The first page was neither listed nor downloaded again
+    assert_eq!(gmail.request_count(MockEndpoint::ListMessages), 4);
+    assert_eq!(gmail.request_count(MockEndpoint::GetMessage), 5);
+    assert_threads_consistent(&store);
+}
+
+#[test]
+fn test_mock_incremental_sync() {
+    let gmail = mock_mailbox();
+    let store = InMemoryMailStore::new();
+    mail::sync_gmail(&gmail, &store, 1, SyncOptions::default()).unwrap();
+
+    gmail.deliver(MockMessage::new("m6", "t1").subject("Re: Plans").body("See you there"));
+    gmail.add_labels("m1", &["STARRED"]);
+    gmail.remove_labels("m3", &["INBOX"]);
+    gmail.delete_message("m4");
+
+    let stats = mail::sync_gmail(&gmail, &store, 1, SyncOptions::default()).unwrap();
+    assert!(stats.was_incremental);
+    assert_eq!(stats.messages_created, 1);
+
+    assert!(store.has_message(&MessageId::new("m6")).unwrap());
+    assert!(!store.has_message(&MessageId::new("m4")).unwrap());
+    let m1 = store.get_message(&MessageId::new("m1")).unwrap().unwrap();
+    assert!(m1.label_ids.contains(&"STARRED".to_string()));
+    let m3 = store.get_message(&MessageId::new("m3")).unwrap().unwrap();
+    assert!(!m3.label_ids.contains(&"INBOX".to_string()));
+    let t1 = store.get_thread(&ThreadId::new("t1")).unwrap().unwrap();
+    assert_eq!(t1.message_count, 3);
+    assert_eq!(store.get_sync_state(1).unwrap().unwrap().history_id, gmail.history_id());
+    assert_threads_consistent(&store);
+}
+
+#[test]
+fn test_mock_incremental_sync_follows_rethreaded_messages() {
+    let gmail = mock_mailbox();
+    let store = InMemoryMailStore::new();
+    mail::sync_gmail(&gmail, &store, 1, SyncOptions::default()).unwrap();
+
+    // A reply split off into its own thread, and a thread merged into another
+    gmail.move_message("m2", "t4");
+    gmail.move_message("m4", "t1");
+
+    let stats = mail::sync_gmail(&gmail, &store, 1, SyncOptions::default()).unwrap();
+    assert!(stats.was_incremental);
+    assert_eq!(stats.messages_fetched, 0);
+
+    let m2 = store.get_message(&MessageId::new("m2")).unwrap().unwrap();
+    assert_eq!(m2.thread_id.as_str(), "t4");
+    let t4 = store.get_thread(&ThreadId::new("t4")).unwrap().unwrap();
+    assert_eq!(t4.subject, "Re: Plans");
+    assert_eq!(t4.message_count, 1);
+    let mut t1_ids = store.get_message_ids_for_thread(&ThreadId::new("t1")).unwrap();
+    t1_ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    assert_eq!(t1_ids, vec![MessageId::new("m1"), MessageId::new("m4")]);
+    assert!(store.get_thread(&ThreadId::new("t3")).unwrap().is_none());
+    assert_eq!(store.count_threads().unwrap(), 3);
+    assert_threads_consistent(&store);
+}
+
+#[test]
+fn test_mock_incremental_sync_refetches_lost_messages() {
+    let gmail = mock_mailbox();
+    let store = InMemoryMailStore::new();
+    mail::sync_gmail(&gmail, &store, 1, SyncOptions::default()).unwrap();
+
+    store.mark_for_refetch(&[MessageId::new("m2")]).unwrap();
+    let stats = mail::sync_gmail(&gmail, &store, 1, SyncOptions::default()).unwrap();
+    assert!(stats.was_incremental);
+    assert_eq!(stats.messages_created, 0);
+    assert_eq!(stats.messages_updated, 1);
+
+    assert!(store.list_refetch_messages(1).unwrap().is_empty());
+    let m2 = store.get_message(&MessageId::new("m2")).unwrap().unwrap();
+    assert_eq!(m2.body_text.as_deref(), Some("Sure"));
+    assert_eq!(store.get_thread(&ThreadId::new("t1")).unwrap().unwrap().message_count, 2);
+    assert_threads_consistent(&store);
+}
+
+#[test]
+fn test_mock_expired_history_reconciles() {
+    let gmail = mock_mailbox();
+    let store = InMemoryMailStore::new();
+    mail::sync_gmail(&gmail, &store, 1, SyncOptions::default()).unwrap();
+
+    gmail.deliver(MockMessage::new("m6", "t4").subject("Missed while offline"));
+    gmail.delete_message("m2");
+    gmail.expire_history();
+    gmail.deliver(MockMessage::new("m7", "t4").subject("Re: Missed while offline"));
+    gmail.delete_message("m4");
+
+    let fetched_before = gmail.request_count(MockEndpoint::GetMessage);
+    let stats = mail::sync_gmail(&gmail, &store, 1, SyncOptions::default()).unwrap();
+    assert!(stats.was_incremental, "expired history is reconciled, not resynced");
+    assert_eq!(stats.messages_created, 2);
+    assert_eq!(stats.messages_updated, 2);
+    // Only the missed messages are downloaded
+    assert_eq!(gmail.request_count(MockEndpoint::GetMessage) - fetched_before, 2);
+
+    assert!(store.has_message(&MessageId::new("m6")).unwrap());
+    assert!(store.has_message(&MessageId::new("m7")).unwrap());
+    assert!(!store.has_message(&MessageId::new("m2")).unwrap());
+    assert!(!store.has_message(&MessageId::new("m4")).unwrap());
+    // The rest of the local mail was kept in place
+    let m1 = store.get_message(&MessageId::new("m1")).unwrap().unwrap();
+    assert_eq!(m1.body_text.as_deref(), Some("Lunch on Friday?"));
+    assert_eq!(store.get_thread(&ThreadId::new("t1")).unwrap().unwrap().message_count, 1);
+    assert!(store.get_thread(&ThreadId::new("t3")).unwrap().is_none());
+    assert_eq!(store.count_threads().unwrap(), 3);
+    assert_threads_consistent(&store);
+
+    let state = store.get_sync_state(1).unwrap().unwrap();
+    assert!(state.initial_sync_complete);
+    assert_eq!(state.history_id, gmail.history_id());
+}
+
+#[test]
+fn test_mock_backfill_fetches_only_recent_messages() {
+    let gmail = mock_mailbox();
+    let store = InMemoryMailStore::new();
+    mail::sync_gmail(&gmail, &store, 1, SyncOptions::default()).unwrap();
+    gmail.deliver(MockMessage::new("m6", "t4").subject("Missed while offline"));
+
+    let state = store.get_sync_state(1).unwrap().unwrap();
+    let fetched_before = gmail.request_count(MockEndpoint::GetMessage);
+    let stats = mail::backfill_sync(&gmail, &store, &state, &SyncOptions::default()).unwrap();
+    assert_eq!(stats.messages_created, 1);
+    assert_eq!(gmail.request_count(MockEndpoint::GetMessage) - fetched_before, 1);
+    assert!(store.has_message(&MessageId::new("m6")).unwrap());
+    assert_eq!(
+        store.get_sync_state(1).unwrap().unwrap().history_id,
+        gmail.history_id()
+    );
+}
+
+#[test]
+fn test_mock_failed_message_is_kept_for_retry() {
+    let gmail = mock_mailbox();
+    let store = InMemoryMailStore::new();
+
+    // Newest first, so the first message fetched is m5
+    gmail.fail_next(MockEndpoint::GetMessage, MockFailure::Status(429));
+    let stats = mail::sync_gmail(&gmail, &store, 1, SyncOptions::default()).unwrap();
+    assert_eq!(stats.errors, 1);
+    assert_eq!(stats.messages_created, 4);
+    assert!(!store.has_message(&MessageId::new("m5")).unwrap());
+
+    let state = store.get_sync_state(1).unwrap().unwrap();
+    assert_eq!(state.failed_message_ids, vec!["m5".to_string()]);
+}
+
+#[test]
+fn test_mock_sync_step_completes_small_mailbox() {
+    let gmail = mock_mailbox();
+    let store = InMemoryMailStore::new();
+
+    let step = mail::sync_step(
+        &gmail,
+        &store,
+        1,
+        &SyncOptions::default(),
+        std::time::Duration::from_secs(30),
+    )
+    .unwrap();
+    assert!(step.is_complete());
+    assert_eq!(step.checkpoint.pending, 0);
+    assert_eq!(store.count_threads().unwrap(), 3);
+
+    // With nothing new, the next slice is a quick incremental sync
+    let step = mail::sync_step(
+        &gmail,
+        &store,
+        1,
+        &SyncOptions::default(),
+        std::time::Duration::from_secs(30),
+    )
+    .unwrap();
+    assert!(step.is_complete());
+    assert!(step.stats.was_incremental);
+}